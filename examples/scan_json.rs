@@ -0,0 +1,36 @@
+//! Scan a range and print one JSON object per host:
+//!
+//! ```text
+//! cargo run --example scan_json -- 192.168.1.0/24 22,80,443
+//! ```
+
+use ipscannr::scanner::{parse_ports, IpRange};
+use ipscannr::{scan, ScanOptions};
+use serde_json::json;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let range = IpRange::parse(&args.next().unwrap_or_else(|| "192.168.1.0/24".to_string()))?;
+    let options = ScanOptions {
+        ports: args.next().map(|spec| parse_ports(&spec)),
+        ..ScanOptions::default()
+    };
+
+    let mut reports = scan(&range, options);
+    while let Some(host) = reports.next().await {
+        let line = json!({
+            "ip": host.ip.to_string(),
+            "is_alive": host.is_alive,
+            "status": format!("{:?}", host.status),
+            "method": host.method.to_string(),
+            "rtt_ms": host.rtt.map(|d| d.as_millis() as u64),
+            "hostname": host.hostname,
+            "mac_address": host.mac.as_ref().map(|m| m.address.clone()),
+            "mac_vendor": host.mac.as_ref().and_then(|m| m.vendor.clone()),
+            "open_ports": host.open_ports,
+        });
+        println!("{}", line);
+    }
+    Ok(())
+}
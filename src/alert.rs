@@ -0,0 +1,103 @@
+use std::io::{self, Write};
+
+/// How to get the user's attention when something finishes in the background
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum AlertMode {
+    /// No alerts
+    #[default]
+    Off,
+    /// Ring the terminal bell
+    Bell,
+    /// OSC 9 desktop notification (Windows Terminal, iTerm2, ...) plus bell
+    Osc9,
+    /// OSC 777 desktop notification (rxvt, foot, WezTerm, ...) plus bell
+    Osc777,
+}
+
+/// Receives alert-worthy events (scan complete, host state changes).
+/// A trait so tests can record alerts instead of writing escape sequences.
+pub trait Alerter: Send {
+    fn alert(&mut self, title: &str, body: &str);
+}
+
+/// Alerter that writes BEL / OSC sequences to a terminal
+pub struct TerminalAlerter<W: Write + Send> {
+    mode: AlertMode,
+    out: W,
+}
+
+impl<W: Write + Send> TerminalAlerter<W> {
+    pub fn new(mode: AlertMode, out: W) -> Self {
+        Self { mode, out }
+    }
+}
+
+impl<W: Write + Send> Alerter for TerminalAlerter<W> {
+    fn alert(&mut self, title: &str, body: &str) {
+        let seq = alert_sequence(self.mode, title, body);
+        if !seq.is_empty() {
+            // Alerts are best-effort; a closed terminal is not worth an error
+            let _ = self.out.write_all(seq.as_bytes());
+            let _ = self.out.flush();
+        }
+    }
+}
+
+/// Alerter for the configured mode, writing to stdout
+pub fn for_mode(mode: AlertMode) -> Box<dyn Alerter> {
+    Box::new(TerminalAlerter::new(mode, io::stdout()))
+}
+
+/// Escape sequence emitted for an alert in `mode`
+fn alert_sequence(mode: AlertMode, title: &str, body: &str) -> String {
+    const BEL: char = '\x07';
+    let title = sanitize(title);
+    let body = sanitize(body);
+    match mode {
+        AlertMode::Off => String::new(),
+        AlertMode::Bell => BEL.to_string(),
+        AlertMode::Osc9 => format!("\x1b]9;{}: {}{BEL}{BEL}", title, body),
+        AlertMode::Osc777 => format!("\x1b]777;notify;{};{}{BEL}{BEL}", title, body),
+    }
+}
+
+/// Drop control characters (and `;`, the OSC 777 field separator) so text
+/// can't terminate the escape sequence early
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if c == ';' { ',' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_per_mode() {
+        assert_eq!(alert_sequence(AlertMode::Off, "t", "b"), "");
+        assert_eq!(alert_sequence(AlertMode::Bell, "t", "b"), "\x07");
+        assert_eq!(
+            alert_sequence(AlertMode::Osc9, "Scan complete", "3 hosts"),
+            "\x1b]9;Scan complete: 3 hosts\x07\x07"
+        );
+        assert_eq!(
+            alert_sequence(AlertMode::Osc777, "Scan complete", "3 hosts"),
+            "\x1b]777;notify;Scan complete;3 hosts\x07\x07"
+        );
+    }
+
+    #[test]
+    fn text_cannot_break_out_of_sequence() {
+        let seq = alert_sequence(AlertMode::Osc777, "a;b", "c\x07\x1b]0;pwned");
+        assert_eq!(seq, "\x1b]777;notify;a,b;c]0,pwned\x07\x07");
+    }
+
+    #[test]
+    fn terminal_alerter_writes_to_output() {
+        let mut alerter = TerminalAlerter::new(AlertMode::Bell, Vec::new());
+        alerter.alert("Scan complete", "");
+        assert_eq!(alerter.out, b"\x07");
+    }
+}
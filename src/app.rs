@@ -1,17 +1,36 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::Ipv4Addr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ratatui::widgets::TableState;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Semaphore};
 
-use crate::config::Config;
-use crate::input::{Action, InputMode};
+use crate::alert::{self, Alerter};
+use crate::cache::Checkpoint;
+use crate::config::{Config, HostAction, RangeSpec};
+use crate::conflict::{self, Conflict, DiffKind, MacGroup};
+use crate::connectivity::{Connectivity, GatewayCheck, Link};
+use crate::input::{self, Action, InputMode, KeySequence, Motion, Sequence};
+use crate::eventlog::EventLog;
+use crate::export::{self, ExportHost};
+use crate::highlight::Highlighter;
+use crate::serve::{self, Snapshot};
+use crate::portdiff::PortDiff;
+use crate::names::{NameSource, Names};
+use crate::notify::{Notifications, Severity};
+use crate::settings::{save_settings, Settings};
+use crate::summary::{self, LineKind};
+use crate::tuning::{Field, Tuning};
+use crate::ui::text::rtt_text;
+use crate::ui::Column;
+use crate::wake::WakeJob;
+use crate::webhook::{self, Delivery};
 use crate::scanner::{
-    get_active_adapters, get_mac_address, scan_hosts, AdapterInfo, DnsResolver, HostStatus,
-    IpRange, MacInfo, PingMethod, PingResult, PortScanner, COMMON_PORTS,
+    adapter_for_range, check_source_ip, discover, get_active_adapters, is_local, lookup_vendor, probe_hosts, wol, AdapterInfo,
+    Announcement, ArpTable, DnsResolver, HostProbe, HostStatus, IcmpUnavailable, IpRange, KnockPlan, MacInfo, PingMethod, PingResult, Pinger, PortProgress, PortScanner, Probe, ProbeError,
+    MacLookup, NameResolver, NetbiosLookup, NetbiosResolver, Resolution, COMMON_PORTS, COMMON_PORTS_SPEC,
 };
 
 /// Information about a scanned host
@@ -20,31 +39,221 @@ pub struct HostInfo {
     pub ip: Ipv4Addr,
     pub is_alive: bool,
     pub rtt: Option<Duration>,
+    /// Name shown for the host: the first of `names` in `--name-order`
     pub hostname: Option<String>,
+    /// Every name found for the host, by source
+    pub names: Names,
     pub mac: Option<MacInfo>,
     pub open_ports: Vec<u16>,
     /// True once a port scan has been run for this host (distinguishes "none found" from "not yet scanned")
     pub ports_scanned: bool,
+    /// Unix timestamp (seconds) the last port scan finished
+    pub ports_scanned_at: Option<u64>,
+    /// Port set the last port scan covered, e.g. "common"
+    pub ports_spec: Option<String>,
+    /// Open ports of the port scan before the last one; None until a
+    /// host has been port scanned twice
+    pub previous_ports: Option<Vec<u16>>,
     /// Unix timestamp (seconds) when this entry was loaded from cache; None = live scan data
     pub cached_at: Option<u64>,
+    /// Unix timestamp (seconds) this host was last observed online, in this
+    /// or any earlier scan of the range
+    pub last_seen: Option<u64>,
     /// Detection method and status
     pub method: PingMethod,
     pub status: HostStatus,
+    /// Probe that found the host online
+    pub probe: Option<Probe>,
+    /// Why the last scan's probes got no answer; None for online hosts and
+    /// cached entries
+    pub probe_error: Option<ProbeError>,
+    /// Unix timestamp (seconds) the host went up or down, whichever it is
+    /// now; carried across rescans for the uptime column
+    pub state_since: Option<u64>,
+    /// Services advertised over SSDP / mDNS discovery
+    pub services: Vec<String>,
+    /// Outcome of the last reverse DNS lookup; None until one has run
+    pub dns: Option<Resolution>,
+    /// Online in the cached scan of the range, but offline in this one
+    pub stale: bool,
 }
 
 impl From<PingResult> for HostInfo {
     fn from(result: PingResult) -> Self {
         Self {
-            ip: result.ip,
             is_alive: result.is_alive,
             rtt: result.rtt,
+            method: result.method,
+            status: result.status,
+            probe: result.probe,
+            probe_error: result.error,
+            ..Self::new(result.ip)
+        }
+    }
+}
+
+/// A device only known from SSDP / mDNS: it answered, but no probe did
+impl From<&Announcement> for HostInfo {
+    fn from(announcement: &Announcement) -> Self {
+        Self {
+            is_alive: true,
+            hostname: announcement.name.clone(),
+            names: Names {
+                mdns: announcement.name.clone(),
+                ..Names::default()
+            },
+            last_seen: Some(crate::cache::now_secs()),
+            status: HostStatus::Online,
+            services: announcement.services.clone(),
+            ..Self::new(announcement.ip)
+        }
+    }
+}
+
+impl HostInfo {
+    /// A host nothing is known about yet: offline, never port scanned
+    pub fn new(ip: Ipv4Addr) -> Self {
+        Self {
+            ip,
+            is_alive: false,
+            rtt: None,
             hostname: None,
+            names: Names::default(),
             mac: None,
             open_ports: Vec::new(),
             ports_scanned: false,
+            ports_scanned_at: None,
+            ports_spec: None,
+            previous_ports: None,
             cached_at: None,
-            method: result.method,
-            status: result.status,
+            last_seen: None,
+            method: PingMethod::Icmp,
+            status: HostStatus::Offline,
+            probe: None,
+            probe_error: None,
+            state_since: None,
+            services: Vec::new(),
+            dns: None,
+            stale: false,
+        }
+    }
+
+    /// Record a reverse lookup. A failed retry keeps the PTR name already
+    /// known.
+    fn set_resolution(&mut self, dns: Resolution, order: &[NameSource]) {
+        if let Some(name) = dns.name() {
+            self.names.set(NameSource::Ptr, name);
+        }
+        self.dns = Some(dns);
+        self.refresh_hostname(order);
+    }
+
+    /// Take the alias set for the address in `aliases`, or drop one that
+    /// was removed there
+    fn apply_alias(&mut self, aliases: &HashMap<Ipv4Addr, String>, order: &[NameSource]) {
+        let alias = aliases.get(&self.ip).cloned();
+        if alias == self.names.alias {
+            return;
+        }
+        if let Some(old) = self.names.alias.take() {
+            if self.hostname.as_ref() == Some(&old) {
+                self.hostname = None;
+            }
+        }
+        self.names.alias = alias;
+        self.refresh_hostname(order);
+    }
+
+    /// Show the first of `names` in `order`. A host with no names keeps its
+    /// hostname, e.g. one from a cache written before names were kept apart.
+    fn refresh_hostname(&mut self, order: &[NameSource]) {
+        if let Some(name) = self.names.display(order) {
+            self.hostname = Some(name.to_string());
+        }
+    }
+
+    /// When the liveness shown was observed: the cached scan, or the last
+    /// time the host was seen up or went down
+    fn liveness_at(&self) -> Option<u64> {
+        self.cached_at.or(self.last_seen.max(self.state_since))
+    }
+
+    /// What the last port scan found changed since the one before; None
+    /// after a single scan or when nothing changed
+    pub fn port_diff(&self) -> Option<PortDiff> {
+        let diff = PortDiff::between(self.previous_ports.as_deref()?, &self.open_ports);
+        (!diff.is_empty()).then_some(diff)
+    }
+
+    /// Whether the open ports predate the liveness data, so they may no
+    /// longer match the host
+    pub fn ports_outdated(&self) -> bool {
+        match (self.ports_scanned_at, self.liveness_at()) {
+            (Some(ports), Some(liveness)) => ports < liveness,
+            _ => false,
+        }
+    }
+
+    /// Online, but only TCP probes got an answer (ICMP blocked or unavailable)
+    pub fn tcp_only(&self) -> bool {
+        self.is_alive && self.status == HostStatus::OnlineNoIcmp
+    }
+
+    /// Whether the last reverse lookup timed out, so a rescan should retry it
+    pub fn dns_timed_out(&self) -> bool {
+        self.dns == Some(Resolution::Timeout)
+    }
+
+    /// Keep what an earlier result for the same address found that a ping
+    /// can't: its name, MAC, port scan and advertised services. Lookups the
+    /// rescan runs still update them when they finish.
+    fn carry_over(&mut self, before: HostInfo, order: &[NameSource]) {
+        if self.hostname.is_none() {
+            self.hostname = before.hostname;
+        }
+        self.names.fill_from(before.names);
+        self.refresh_hostname(order);
+        if self.mac.is_none() {
+            self.mac = before.mac;
+        }
+        if !self.ports_scanned {
+            self.open_ports = before.open_ports;
+            self.ports_scanned = before.ports_scanned;
+            self.ports_scanned_at = before.ports_scanned_at;
+            self.ports_spec = before.ports_spec;
+            self.previous_ports = before.previous_ports;
+        }
+        for service in before.services {
+            if !self.services.contains(&service) {
+                self.services.push(service);
+            }
+        }
+    }
+
+    /// Take what a device advertised: its name when it hasn't announced one
+    /// yet, and any services not listed yet
+    fn add_announcement(&mut self, announcement: &Announcement, order: &[NameSource]) {
+        if let Some(name) = &announcement.name {
+            self.names.set_if_missing(NameSource::Mdns, name);
+        }
+        self.refresh_hostname(order);
+        for service in &announcement.services {
+            if !self.services.contains(service) {
+                self.services.push(service.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl HostInfo {
+    /// Test fixture: a host that answered ICMP, or nothing at all. Tests set
+    /// whatever else they need with struct update.
+    pub fn pinged(ip: Ipv4Addr, is_alive: bool) -> Self {
+        Self {
+            is_alive,
+            status: if is_alive { HostStatus::Online } else { HostStatus::Offline },
+            ..Self::new(ip)
         }
     }
 }
@@ -54,21 +263,37 @@ impl From<PingResult> for HostInfo {
 pub enum FilterMode {
     All,
     OnlineOnly,
+    /// Online and answering ICMP echo, leaving out TCP-only hosts
+    Pingable,
+    /// Online in the cached scan, offline now
+    Stale,
 }
 
 impl FilterMode {
     pub fn toggle(&self) -> Self {
         match self {
             FilterMode::All => FilterMode::OnlineOnly,
-            FilterMode::OnlineOnly => FilterMode::All,
+            FilterMode::OnlineOnly => FilterMode::Pingable,
+            FilterMode::Pingable => FilterMode::Stale,
+            FilterMode::Stale => FilterMode::All,
         }
     }
 
-    #[allow(dead_code)]
     pub fn label(&self) -> &'static str {
         match self {
             FilterMode::All => "All",
             FilterMode::OnlineOnly => "Online",
+            FilterMode::Pingable => "Pingable",
+            FilterMode::Stale => "Stale",
+        }
+    }
+
+    pub fn matches(&self, host: &HostInfo) -> bool {
+        match self {
+            FilterMode::All => true,
+            FilterMode::OnlineOnly => host.is_alive,
+            FilterMode::Pingable => host.is_alive && !host.tcp_only(),
+            FilterMode::Stale => host.stale,
         }
     }
 }
@@ -90,9 +315,101 @@ pub enum Focus {
     DetailsPane,
 }
 
+/// Per-host actions offered by the right-click context menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuItem {
+    Ping,
+    Tracert,
+    PortScan,
+    WakeOnLan,
+    Forget,
+    CopyIp,
+    CopyCommand,
+    Knock,
+    Columns,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 9] = [
+        MenuItem::Ping,
+        MenuItem::Tracert,
+        MenuItem::PortScan,
+        MenuItem::WakeOnLan,
+        MenuItem::Forget,
+        MenuItem::CopyIp,
+        MenuItem::CopyCommand,
+        MenuItem::Knock,
+        MenuItem::Columns,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuItem::Ping => "Ping",
+            MenuItem::Tracert => "Tracert",
+            MenuItem::PortScan => "Port scan",
+            MenuItem::WakeOnLan => "Wake-on-LAN",
+            MenuItem::Forget => "Forget details...",
+            MenuItem::CopyIp => "Copy IP",
+            MenuItem::CopyCommand => "Copy command...",
+            MenuItem::Knock => "Knock...",
+            MenuItem::Columns => "Columns...",
+        }
+    }
+}
+
+/// Scan event channel capacity: deep enough that a burst of instant replies
+/// doesn't stall the probe workers while the UI catches up
+pub const SCAN_EVENT_CAPACITY: usize = 4096;
+
+/// Hostname / MAC lookups in flight at once; MAC lookups may each spawn an
+/// `arp` process
+const ENRICH_CONCURRENCY: usize = 16;
+
+/// How often partial results of a running scan are checkpointed to the
+/// cache, so an unexpected exit doesn't lose them
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Results after which a checkpoint is due regardless of the interval
+const CHECKPOINT_HOSTS: usize = 1024;
+
+/// Shortest gap between two snapshots published for `--listen`
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Up/down changes remembered for the status bar ticker, newest first
+const RECENT_CHANGES: usize = 20;
+
+/// Changes the ticker shows at once
+const TICKER_SHOWN: usize = 2;
+
+/// How long each change leads the ticker before the next one takes over
+const TICKER_STEP: Duration = Duration::from_secs(4);
+
+/// Changes older than this (seconds) drop out of the ticker
+const TICKER_MAX_AGE: u64 = 3600;
+
+/// A host that answered differently from the scan before: up after being
+/// down, or down after being up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateChange {
+    pub ip: Ipv4Addr,
+    pub up: bool,
+    /// Unix seconds
+    pub at: u64,
+}
+
+/// A left-button drag across table rows in progress
+#[derive(Debug, Default)]
+pub struct DragSelect {
+    /// Row the pointer was last over
+    pub last_row: usize,
+    /// Hosts this gesture added; crossing them again keeps them selected
+    pub added: HashSet<Ipv4Addr>,
+}
+
 /// Application state
 pub struct App {
     pub config: Config,
+    pub settings: Settings,
     pub input_mode: InputMode,
     pub scan_state: ScanState,
     pub focus: Focus,
@@ -120,38 +437,234 @@ pub struct App {
     // Progress
     pub scan_total: usize,
     pub scan_completed: usize,
+    /// Offline hosts counted but not kept (ranges above `offline_retain_limit`)
+    pub offline_dropped: usize,
+    /// Why those dropped hosts' probes failed, for the summary's breakdown
+    pub dropped_causes: HashMap<ProbeError, usize>,
+    /// Hostname / MAC lookups queued for discovered hosts and not yet back
+    pub enrichment_pending: usize,
+    /// Range of the current (or paused) scan; the input may have been
+    /// edited since
+    scan_range: String,
+    /// Addresses with a result in the current scan, so a resume only
+    /// probes the rest
+    scanned_ips: HashSet<Ipv4Addr>,
+    /// Ranges of the current scan, in scan order: one, or several queued as
+    /// a batch (`;`-separated)
+    pub batch: Vec<BatchRange>,
+    /// Index into `batch` of each address of the current scan
+    batch_of: HashMap<Ipv4Addr, usize>,
+    /// Hosts listed before the scan at addresses it probes again, until
+    /// their new result arrives and takes over their enrichment
+    rescanned_before: HashMap<Ipv4Addr, HostInfo>,
+    /// Listed hosts outside the current scan's ranges, kept from earlier
+    /// scans. They stay on screen but aren't saved with this scan's results.
+    kept_from_earlier: HashSet<Ipv4Addr>,
+    /// Batch range the table is narrowed to; None shows every range
+    pub range_view: Option<usize>,
+    /// Start of the current scan (Unix seconds); checkpoints are only
+    /// written while this is set
+    scan_started_at: Option<u64>,
+    /// When the last scan completed (Unix seconds), for `/status`
+    scan_finished_at: Option<u64>,
+    last_checkpoint: Instant,
+    checkpoint_completed: usize,
+    /// Checkpoints and per-range results go through it; final results and
+    /// anything that reads the cache back flush it first
+    pub cache_writer: crate::cache::Writer,
+    /// Where `--listen` serves results from; None without the flag
+    pub status_feed: Option<serve::Shared>,
+    /// Results changed since the last publish
+    publish_pending: bool,
+    published_at: Option<Instant>,
+    /// `/metrics` as of the last finished scan
+    metrics: Arc<str>,
+    /// Where a failed `--metrics-file` write is reported; `run_app` owns
+    /// the receiver
+    pub metrics_failures: Option<mpsc::Sender<String>>,
+    /// The settings file's highlight rules, compiled by
+    /// `apply_highlight_rules`
+    pub highlighter: Highlighter,
+    /// Posts host changes to the settings file's webhook; None without one
+    webhook: Option<webhook::Client>,
+    /// Changes seen during the scan, posted once its lookups are done so
+    /// they carry names and MACs: (event, address, Unix seconds)
+    webhook_queue: Vec<(&'static str, Ipv4Addr, u64)>,
+    /// When each address was last seen online before the current scan
+    /// started, so hosts that are offline now keep their history
+    seen_before: HashMap<Ipv4Addr, u64>,
+    /// Whether each address was up before the current scan, and since when
+    state_before: HashMap<Ipv4Addr, (bool, u64)>,
+    /// Cached results for the range when the scan started, for spotting
+    /// hosts whose MAC changed
+    previous_scan: Vec<HostInfo>,
+    /// Addresses online in `previous_scan`: any that don't answer now are
+    /// marked stale
+    cached_online: HashSet<Ipv4Addr>,
+    /// Latest up/down changes across rescans, newest first, one per host
+    pub recent_changes: VecDeque<StateChange>,
+    /// Rotation of the status bar ticker, advanced by the animation clock
+    ticker_step: usize,
+    /// Changes already visited by [H], from the newest
+    change_jumps: usize,
+
+    // Duplicate or changed MACs in the results, and every IP they involve
+    pub conflicts: Vec<Conflict>,
+    pub conflicted_ips: HashSet<Ipv4Addr>,
+    /// Hosts sharing a MAC, conflicting or not, for the details pane and [=]
+    pub mac_groups: Vec<MacGroup>,
+    pub conflicts_scroll: usize,
+    /// Hosts whose port changes were on screen in the details and have
+    /// been moved on from; their +/- markers are not shown again
+    port_changes_seen: HashSet<Ipv4Addr>,
+
+    // Summary overlay text, computed when it opens
+    pub summary_lines: Vec<(LineKind, String)>,
+    pub summary_scroll: usize,
 
     // Communication
     scan_cancel_tx: Option<mpsc::Sender<()>>,
-    scan_resume_tx: Option<mpsc::Sender<()>>,
 
-    // DNS resolver
-    dns_resolver: Arc<DnsResolver>,
+    // Network lookups made by scans and rescans
+    pub probes: Probes,
 
     // Show details pane (can be toggled in full mode)
     pub show_details: bool,
 
-    // Export / message state
-    pub export_message: Option<String>,
+    // True while the terminal is in the Compact layout (no side details pane);
+    // focusing the Details pane then shows it as a popup instead
+    pub compact_layout: bool,
+
+    // Show the table symbol legend along the hosts table border
+    pub show_legend: bool,
+
+    // Limit exports to the hosts the table filter shows (export overlay [F])
+    pub export_respect_filter: bool,
 
-    // Animation state for activity indicator
-    pub animation_tick: u8,
+    // Toast notifications (export results, errors, ...)
+    pub notifications: Notifications,
+
+    // Session event log and its overlay state; the page size is refreshed after each draw
+    pub event_log: EventLog,
+    pub event_log_scroll: usize,
+    pub event_log_filter: Option<Severity>,
+    pub event_log_page: usize,
+
+    // Animation state for activity indicator: the spinner frame follows the
+    // clock from `animation_epoch`, not the number of loop iterations
+    animation_epoch: Instant,
+    spinner_frame: usize,
 
     // Output overlay (continuous ping / tracert)
     pub overlay_title: String,
-    pub overlay_lines: Vec<String>,
+    pub overlay_lines: VecDeque<String>,
     pub overlay_scroll: usize,
+    /// Output rows the overlay can show; refreshed after each draw
+    pub overlay_page: usize,
     pub overlay_cancel_tx: Option<mpsc::Sender<()>>,
 
-    // Background port scan for the currently selected host
+    // Help overlay scroll position; the maximum is refreshed after each draw
+    pub help_scroll: usize,
+    pub help_max_scroll: usize,
+
+    // Background port scan of the selected host(s), with each host's
+    // (ports scanned, total) while it runs
     port_scan_cancel_tx: Option<mpsc::Sender<()>>,
     pub port_scanning: bool,
+    pub port_progress: HashMap<Ipv4Addr, (usize, usize)>,
+
+    // SSDP / mDNS discovery ([B] / --discover). What each address advertised
+    // is kept so a later scan result for it keeps the name and services.
+    discovery_cancel_tx: Option<mpsc::Sender<()>>,
+    pub discovering: bool,
+    announced: HashMap<Ipv4Addr, Announcement>,
+
+    // Wake-on-LAN run over the selected hosts; kept after it ends for its report
+    pub wake_job: Option<WakeJob>,
+
+    // Half-typed vim-style table sequence (`gg`, `25j`)
+    pub key_sequence: KeySequence,
+
+    // Mouse: last left click on a table row (for double-click detection) and
+    // the right-click context menu's screen anchor and highlighted item
+    pub last_row_click: Option<(Instant, usize)>,
+    pub menu_anchor: (u16, u16),
+    pub menu_index: usize,
+
+    // Highlighted line of the copy-command chooser
+    pub command_index: usize,
+    // Highlighted line of the knock chooser
+    pub knock_index: usize,
+
+    // Column picker: every column with whether it is ticked, in the order
+    // being edited, and the highlighted line
+    pub column_picker: Vec<(Column, bool)>,
+    pub column_index: usize,
+
+    // Settings overlay: the highlighted tunable, the text typed for it while
+    // editing, and why the last value typed was refused
+    pub tuning_index: usize,
+    pub tuning_edit: Option<String>,
+    pub tuning_error: Option<String>,
+
+    // Last octet or address typed after [:] / [#]
+    pub goto_input: String,
+
+    // Header status box shows the summary during a scan and the progress bar
+    // otherwise (toggled by clicking it)
+    pub status_swapped: bool,
+
+    // Why the last scan failed to start; shown on the Range box until the
+    // range is edited
+    pub input_error: Option<String>,
+    // Shown the same way, in warning colors, when the scanned range is not on
+    // any local subnet
+    pub range_warning: Option<String>,
+    // Addresses the range field parses to, shown beside it; None while it
+    // doesn't parse
+    pub range_size: Option<usize>,
+    // One-line banner above the table (ICMP unavailable and how to fix it);
+    // Esc on the table or a click dismisses it
+    pub banner: Option<String>,
+    // Adapters when the scan started; decides which hosts get a MAC lookup
+    scan_adapters: Vec<AdapterInfo>,
+
+    // Table viewport offset set by the mouse wheel; None keeps the selected
+    // row in view (keyboard navigation resets it)
+    pub table_scroll: Option<usize>,
+
+    // Mouse drag-select gesture, from button down to button up
+    pub drag: Option<DragSelect>,
 
     // True while the user holds Left Ctrl — shows contextual keybindings popup
     pub show_keybindings: bool,
 
+    // Mode the popup was pinned open in with F1; it hides again once the
+    // mode changes
+    pub keybindings_pinned: Option<InputMode>,
+
+    // Whether this terminal reports Left Ctrl on its own (Kitty keyboard
+    // protocol, or Win32 polling); without it the status bar offers F1
+    pub ctrl_detected: bool,
+
+    // False between the terminal's focus-lost and focus-gained events; the
+    // spinners stop redrawing meanwhile
+    pub terminal_focused: bool,
+
+    // Set whenever state changes; the main loop only redraws when it's true
+    pub dirty: bool,
+
     // ASCII-only compat mode (set from --compat CLI flag)
     pub compat: bool,
+
+    // Bell / desktop notification sink (set from --notify)
+    pub alerter: Box<dyn Alerter>,
+
+    // Latest gateway / internet check for the header indicator
+    pub connectivity: Connectivity,
+    /// Gateway check per adapter (by its address), run the first time the
+    /// adapter is picked in the Range pane and kept for the session
+    pub gateway_checks: HashMap<Ipv4Addr, GatewayCheck>,
 }
 
 impl App {
@@ -160,10 +673,13 @@ impl App {
         // Start with default range - adapters will be loaded in background
         let range_input = config.default_range.clone();
         let range_cursor = range_input.len();
+        let range_size = range_size(&range_input);
         let compat = config.compat;
+        let alerter = alert::for_mode(config.alert_mode);
 
         Self {
             config,
+            settings: Settings::default(),
             input_mode: InputMode::Normal,
             scan_state: ScanState::Idle,
             focus: Focus::RangeInput, // Default to Range pane
@@ -185,57 +701,344 @@ impl App {
 
             scan_total: 0,
             scan_completed: 0,
+            offline_dropped: 0,
+            dropped_causes: HashMap::new(),
+            enrichment_pending: 0,
+            scan_range: String::new(),
+            scanned_ips: HashSet::new(),
+            batch: Vec::new(),
+            batch_of: HashMap::new(),
+            rescanned_before: HashMap::new(),
+            kept_from_earlier: HashSet::new(),
+            range_view: None,
+            scan_started_at: None,
+            scan_finished_at: None,
+            last_checkpoint: Instant::now(),
+            checkpoint_completed: 0,
+            cache_writer: crate::cache::Writer::new(crate::cache::WRITE_INTERVAL),
+            status_feed: None,
+            publish_pending: false,
+            published_at: None,
+            metrics: Arc::from(""),
+            metrics_failures: None,
+            highlighter: Highlighter::default(),
+            webhook: None,
+            webhook_queue: Vec::new(),
+            seen_before: HashMap::new(),
+            state_before: HashMap::new(),
+            previous_scan: Vec::new(),
+            cached_online: HashSet::new(),
+            recent_changes: VecDeque::new(),
+            ticker_step: 0,
+            change_jumps: 0,
+            conflicts: Vec::new(),
+            conflicted_ips: HashSet::new(),
+            mac_groups: Vec::new(),
+            conflicts_scroll: 0,
+            port_changes_seen: HashSet::new(),
+            summary_lines: Vec::new(),
+            summary_scroll: 0,
 
             scan_cancel_tx: None,
-            scan_resume_tx: None,
-            dns_resolver: Arc::new(DnsResolver::default()),
+            probes: Probes::default(),
             show_details: true,
-            export_message: None,
-            animation_tick: 0,
+            compact_layout: false,
+            show_legend: false,
+            export_respect_filter: false,
+            notifications: Notifications::default(),
+            event_log: EventLog::default(),
+            event_log_scroll: 0,
+            event_log_filter: None,
+            event_log_page: 0,
+            animation_epoch: Instant::now(),
+            spinner_frame: 0,
 
             overlay_title: String::new(),
-            overlay_lines: Vec::new(),
+            overlay_lines: VecDeque::new(),
             overlay_scroll: 0,
+            overlay_page: 1,
+            help_scroll: 0,
+            help_max_scroll: 0,
             overlay_cancel_tx: None,
 
             port_scan_cancel_tx: None,
             port_scanning: false,
+            port_progress: HashMap::new(),
+
+            discovery_cancel_tx: None,
+            discovering: false,
+            announced: HashMap::new(),
 
+            wake_job: None,
+
+            key_sequence: KeySequence::default(),
+            last_row_click: None,
+            menu_anchor: (0, 0),
+            menu_index: 0,
+            command_index: 0,
+            knock_index: 0,
+            column_picker: Vec::new(),
+            column_index: 0,
+            tuning_index: 0,
+            tuning_edit: None,
+            tuning_error: None,
+            goto_input: String::new(),
+            status_swapped: false,
+            input_error: None,
+            banner: None,
+            range_warning: None,
+            range_size,
+            scan_adapters: Vec::new(),
+            table_scroll: None,
+            drag: None,
             show_keybindings: false,
+            keybindings_pinned: None,
+            ctrl_detected: false,
+            terminal_focused: true,
+            dirty: true,
 
             compat,
+            alerter,
+            connectivity: Connectivity::default(),
+            gateway_checks: HashMap::new(),
         }
     }
 
     /// Load adapters (call from async context)
     #[allow(dead_code)]
-    pub fn load_adapters(&mut self) {
-        self.adapters = get_active_adapters();
-        self.adapters_loading = false;
+    pub fn load_adapters(&mut self) -> Result<()> {
+        self.set_adapters(get_active_adapters())
+    }
 
-        // Set default range from first adapter (preferring ethernet)
-        if !self.adapters.is_empty() && self.adapter_index.is_none() {
-            self.adapter_index = Some(0);
-            self.range_input = self.adapters[0].subnet.clone();
+    /// Take the loaded adapters and switch to the range `--range` asked for
+    /// (`Config::range_spec`). A literal range is kept as given. Err when it
+    /// named an adapter type that isn't up; the range then falls back to the
+    /// best adapter.
+    pub fn set_adapters(&mut self, adapters: Vec<AdapterInfo>) -> Result<()> {
+        self.adapters = adapters;
+        self.adapters_loading = false;
+        if self.adapter_index.is_some() {
+            return Ok(());
+        }
+        let (index, missing) = match &self.config.range_spec {
+            RangeSpec::Literal(range) => {
+                self.adapter_index = adapter_for_range(&self.adapters, range);
+                return Ok(());
+            }
+            RangeSpec::Auto => (Some(0), None),
+            RangeSpec::Adapter(kind) => {
+                match self.adapters.iter().position(|a| a.adapter_type == *kind) {
+                    Some(i) => (Some(i), None),
+                    None => (Some(0), Some(*kind)),
+                }
+            }
+        };
+        // Adapters are sorted best first (default route, then Ethernet, then WiFi)
+        if let Some(adapter) = index.and_then(|i| self.adapters.get(i)) {
+            self.adapter_index = index;
+            self.range_input = adapter.subnet.clone();
             self.range_cursor = self.range_input.len();
+            self.range_size = range_size(&self.range_input);
+        }
+        match missing {
+            Some(kind) => Err(anyhow::anyhow!(
+                "--range {}: no {} adapter is up",
+                kind.to_string().to_lowercase(),
+                kind
+            )),
+            None => Ok(()),
         }
     }
 
     /// Load cached scan results for the current range (shows data before first scan)
     pub fn load_cache(&mut self) {
+        self.cache_writer.flush();
         let cached = crate::cache::load_cache(&self.range_input);
         if !cached.is_empty() {
+            self.log(
+                Severity::Info,
+                format!("Loaded {} cached hosts for {}", cached.len(), self.range_input),
+            );
             self.hosts = cached;
+            for host in &mut self.hosts {
+                host.apply_alias(&self.config.aliases, &self.config.name_precedence);
+            }
             self.update_filtered_hosts();
+            self.update_conflicts();
             if !self.filtered_hosts.is_empty() {
                 self.table_state.select(Some(0));
             }
+            if let Some(progress) = crate::cache::load_checkpoint(&self.range_input) {
+                self.restore_checkpoint(progress);
+            }
+        }
+    }
+
+    /// Treat the loaded hosts as a scan that was interrupted at `progress`:
+    /// it shows as paused, and resuming probes only the addresses without a
+    /// result. Offline hosts a large scan didn't keep are probed again.
+    pub fn restore_checkpoint(&mut self, progress: Checkpoint) {
+        self.scan_range = self.range_input.clone();
+        self.scanned_ips = self.hosts.iter().map(|h| h.ip).collect();
+        self.scan_total = progress.total;
+        self.scan_completed = progress.scanned;
+        self.scan_state = ScanState::Paused;
+        self.notify(
+            Severity::Info,
+            format!(
+                "Interrupted scan restored ({}/{}): Space resumes, S rescans",
+                progress.scanned, progress.total
+            ),
+        );
+    }
+
+    /// Re-run duplicate / changed MAC detection over the current results
+    fn update_conflicts(&mut self) {
+        self.conflicts = conflict::detect(&self.hosts, &self.previous_scan);
+        self.mac_groups = conflict::mac_groups(&self.hosts);
+        self.conflicted_ips = self
+            .conflicts
+            .iter()
+            .flat_map(|c| c.ips().iter().copied())
+            .collect();
+        for c in &self.conflicts {
+            let heading = &c.diff_lines()[0].1;
+            self.event_log.push(Severity::Error, format!("Conflict: {}", heading));
+        }
+        for group in self.mac_groups.iter().filter(|g| g.same_host) {
+            let ips: Vec<String> = group.ips.iter().map(|ip| ip.to_string()).collect();
+            self.event_log.push(
+                Severity::Info,
+                format!("One device on several addresses: {} ({})", ips.join(", "), group.mac),
+            );
+        }
+    }
+
+    /// The group of hosts sharing `ip`'s MAC, if any
+    pub fn mac_group(&self, ip: Ipv4Addr) -> Option<&MacGroup> {
+        self.mac_groups.iter().find(|g| g.ips.contains(&ip))
+    }
+
+    /// [=]: select the next address with the selected host's MAC, wrapping
+    fn jump_to_same_mac(&mut self) {
+        let Some(ip) = self.selected_host().map(|h| h.ip) else {
+            return;
+        };
+        let Some(group) = self.mac_group(ip) else {
+            self.notify(Severity::Info, "No other address shares this host's MAC");
+            return;
+        };
+        let at = group.ips.iter().position(|&other| other == ip).unwrap_or(0);
+        let next = group.ips[(at + 1) % group.ips.len()];
+        self.select_ip(next);
+    }
+
+    /// Conflicts overlay content, a blank line between entries
+    pub fn conflict_lines(&self) -> Vec<(DiffKind, String)> {
+        let mut lines = Vec::new();
+        let conflicts = self.conflicts.iter().map(Conflict::diff_lines);
+        let port_changes = self.port_changes().map(|(ip, diff)| diff.diff_lines(ip));
+        for (i, entry) in conflicts.chain(port_changes).enumerate() {
+            if i > 0 {
+                lines.push((DiffKind::Heading, String::new()));
+            }
+            lines.extend(entry);
         }
+        lines
+    }
+
+    /// Hosts whose last port scan found ports opened or closed
+    pub fn port_changes(&self) -> impl Iterator<Item = (Ipv4Addr, PortDiff)> + '_ {
+        self.hosts.iter().filter_map(|h| Some((h.ip, h.port_diff()?)))
+    }
+
+    /// Record an event in the session log
+    pub fn log(&mut self, severity: Severity, message: impl Into<String>) {
+        self.event_log.push(severity, message);
+    }
+
+    /// `start_scan` failed: keep the reason on the Range box instead of a
+    /// toast that the next keystroke would dismiss. Focus is left alone so an
+    /// open editor stays open.
+    pub fn scan_start_failed(&mut self, error: anyhow::Error) {
+        let message = format!("{:#}", error);
+        self.log(Severity::Error, format!("Scan not started: {}", message));
+        self.input_error = Some(message);
+    }
+
+    /// ICMP echo can't be used: scans fall back to TCP probes, which miss
+    /// hosts with no open or refusing ports. Log why and offer the remedy.
+    pub fn icmp_unavailable(&mut self, unavailable: &IcmpUnavailable) {
+        self.log(Severity::Error, unavailable.to_string());
+        self.banner = Some(match &unavailable.remedy {
+            Some(remedy) => format!("ICMP needs privileges, using TCP probes only: {}", remedy),
+            None => format!("ICMP unavailable ({}), using TCP probes only", unavailable.reason),
+        });
     }
 
-    /// Tick the animation (call every frame)
+    /// Show a toast and record it in the event log
+    pub fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        let message = message.into();
+        self.event_log.push(severity, message.clone());
+        self.notifications.push(severity, message);
+    }
+
+    /// True while the screen changes without input: spinners, progress,
+    /// streaming ping / tracert output, a toast counting down, or the Ctrl
+    /// popup waiting for the key to be released
+    pub fn is_animating(&self) -> bool {
+        self.scan_state == ScanState::Scanning
+            || self.adapters_loading
+            || self.port_scanning
+            || self.discovering
+            || self.overlay_cancel_tx.is_some()
+            || self.notifications.current().is_some()
+            || self.show_keybindings
+    }
+
+    /// Tick the animation and expire notifications (call every loop); only
+    /// marks the frame dirty while something is animating
     pub fn tick_animation(&mut self) {
-        self.animation_tick = (self.animation_tick + 1) % 12; // Cycle through 0-11
+        self.tick_animation_at(Instant::now());
+    }
+
+    fn tick_animation_at(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.animation_epoch);
+        // Nobody is watching an unfocused window: toasts still expire, but
+        // nothing is redrawn just to move along
+        if !self.terminal_focused {
+            self.notifications.tick(now);
+            return;
+        }
+        // The change ticker rotates even when nothing else is animating
+        if !self.recent_changes.is_empty() {
+            let step = (elapsed.as_millis() / TICKER_STEP.as_millis()) as usize;
+            if step != self.ticker_step {
+                self.ticker_step = step;
+                self.dirty = true;
+            }
+        }
+        if !self.is_animating() {
+            return;
+        }
+        self.spinner_frame = spinner_frame(elapsed);
+        self.notifications.tick(now);
+        self.dirty = true;
+    }
+
+    /// Whether the contextual shortcuts popup is on screen: Left Ctrl held,
+    /// or pinned with F1 in the current mode
+    pub fn keybindings_visible(&self) -> bool {
+        self.show_keybindings || self.keybindings_pinned == Some(self.input_mode)
+    }
+
+    /// The terminal window gained or lost focus
+    pub fn set_terminal_focused(&mut self, focused: bool) {
+        self.terminal_focused = focused;
+        if !focused {
+            // The Ctrl release would go to the other window
+            self.show_keybindings = false;
+        }
     }
 
     /// Get the current adapter info if one is selected
@@ -298,11 +1101,38 @@ impl App {
         self.range_cursor = self.range_input.len();
     }
 
+    /// Check the gateway of the adapter just picked, unless it has been
+    /// (or `--no-connectivity` turned background pings off)
+    fn check_adapter_gateway(&mut self) -> Option<AppCommand> {
+        if !self.config.connectivity_check {
+            return None;
+        }
+        let adapter = self.current_adapter()?.clone();
+        if self.gateway_checks.contains_key(&adapter.ip) {
+            return None;
+        }
+        self.gateway_checks.insert(adapter.ip, GatewayCheck::Checking);
+        Some(AppCommand::CheckGateway(adapter))
+    }
+
     pub fn handle_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
-        // Global escape handler for pausing scan (not in overlay mode)
+        // Global escape handler for pausing scan (not in overlay mode, and
+        // not while Esc is needed to close the compact details popup)
         if action == Action::Cancel
             && self.scan_state == ScanState::Scanning
-            && self.input_mode != InputMode::OutputOverlay
+            && !matches!(
+                self.input_mode,
+                InputMode::OutputOverlay
+                    | InputMode::ConfirmQuit
+                    | InputMode::ConfirmForget
+                    | InputMode::ConfirmNewScan
+                    | InputMode::ContextMenu
+                    | InputMode::CopyCommand
+                    | InputMode::Knock
+                    | InputMode::Settings
+                    | InputMode::Goto
+            )
+            && !self.details_popup_open()
         {
             self.pause_scan();
             return Ok(None);
@@ -316,6 +1146,87 @@ impl App {
             return Ok(Some(AppCommand::ResumeScan));
         }
 
+        // Quit (q, or Ctrl+C from any mode) asks first if work would be lost.
+        // Ctrl+C in a live ping/tracert overlay stops the task; a second press quits.
+        if action == Action::Quit {
+            return match self.input_mode {
+                InputMode::ConfirmQuit => Ok(Some(AppCommand::Quit)),
+                InputMode::OutputOverlay if self.overlay_cancel_tx.is_some() => {
+                    self.close_overlay();
+                    Ok(None)
+                }
+                _ => Ok(self.request_quit()),
+            };
+        }
+
+        // F1 pins the shortcuts popup open, for terminals that can't report
+        // Left Ctrl on its own. A pin left behind by a mode change (a
+        // finished job opening its report) is already gone.
+        self.unpin_stale_keybindings();
+        if action == Action::ToggleKeybindings {
+            self.keybindings_pinned = match self.keybindings_pinned {
+                Some(_) => None,
+                None => Some(self.input_mode),
+            };
+            return Ok(None);
+        }
+
+        // Any change to the range clears the error or warning reported for it
+        let flagged = self.input_error.is_some() || self.range_warning.is_some();
+        let flagged_range = flagged.then(|| self.range_input.clone());
+        let was_editing_range = self.input_mode == InputMode::EditingRange;
+        let range_before = self.range_input.clone();
+        let shown_changes = self.selected_port_diff().and(self.selected_host().map(|h| h.ip));
+        let result = self.dispatch_action(action);
+        if let Some(ip) = shown_changes {
+            if self.selected_host().map(|h| h.ip) != Some(ip) {
+                self.port_changes_seen.insert(ip);
+            }
+        }
+        if range_before != self.range_input {
+            self.range_size = range_size(&self.range_input);
+        }
+        if flagged_range.is_some_and(|range| range != self.range_input) {
+            self.input_error = None;
+            self.range_warning = None;
+        }
+        // A typed range that is exactly an adapter's subnet counts as that
+        // adapter again (title, broadcast WOL, local-range checks)
+        if was_editing_range && self.input_mode != InputMode::EditingRange {
+            self.adapter_index = adapter_for_range(&self.adapters, &self.range_input);
+        }
+        self.unpin_stale_keybindings();
+        result
+    }
+
+    /// The pinned shortcuts popup hides for good once the mode changes
+    fn unpin_stale_keybindings(&mut self) {
+        if self.keybindings_pinned.is_some_and(|mode| mode != self.input_mode) {
+            self.keybindings_pinned = None;
+        }
+    }
+
+    /// Bracketed paste: type `text` into the range or ports field being
+    /// edited, entering range editing first when the range pane is focused.
+    /// Ranges and port lists are ASCII, so line breaks, other control
+    /// characters and anything non-ASCII (an en dash, say) are dropped;
+    /// the cursors count bytes. Anywhere else
+    /// the paste is ignored rather than replayed as shortcuts.
+    pub fn handle_paste(&mut self, text: &str) -> Result<Option<AppCommand>> {
+        let editable = match self.input_mode {
+            InputMode::EditingRange | InputMode::EditingPorts => true,
+            InputMode::Normal => self.focus == Focus::RangeInput,
+            _ => false,
+        };
+        if editable {
+            for c in text.trim().chars().filter(|c| c.is_ascii_graphic() || *c == ' ') {
+                self.handle_action(Action::Character(c))?;
+            }
+        }
+        Ok(None)
+    }
+
+    fn dispatch_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
         match self.input_mode {
             InputMode::Normal => self.handle_normal_action(action),
             InputMode::EditingRange => self.handle_editing_range_action(action),
@@ -323,17 +1234,57 @@ impl App {
             InputMode::Help => self.handle_help_action(action),
             InputMode::Exporting => self.handle_export_action(action),
             InputMode::OutputOverlay => self.handle_overlay_action(action),
+            InputMode::EventLog => self.handle_event_log_action(action),
+            InputMode::Conflicts => self.handle_conflicts_action(action),
+            InputMode::Summary | InputMode::WakeReport => self.handle_summary_action(action),
+            InputMode::ConfirmQuit => self.handle_confirm_quit_action(action),
+            InputMode::ConfirmForget => self.handle_confirm_forget_action(action),
+            InputMode::ConfirmNewScan => self.handle_confirm_new_scan_action(action),
+            InputMode::ConfirmLargeScan => self.handle_confirm_large_scan_action(action),
+            InputMode::ContextMenu => self.handle_context_menu_action(action),
+            InputMode::CopyCommand => self.handle_copy_command_action(action),
+            InputMode::Knock => self.handle_knock_action(action),
+            InputMode::ColumnPicker => self.handle_column_picker_action(action),
+            InputMode::Settings => self.handle_settings_action(action),
+            InputMode::Goto => self.handle_goto_action(action),
         }
     }
 
     fn handle_normal_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        if self.focus == Focus::HostsTable {
+            match self.key_sequence.feed(action, Instant::now()) {
+                Sequence::Pending => return Ok(None),
+                Sequence::Motion(motion) => {
+                    self.apply_motion(motion);
+                    return Ok(None);
+                }
+                Sequence::PassThrough => {}
+            }
+        } else {
+            self.key_sequence.reset();
+        }
+
+        if matches!(
+            action,
+            Action::NavigateUp
+                | Action::NavigateDown
+                | Action::NavigatePageUp
+                | Action::NavigatePageDown
+                | Action::NavigateHome
+                | Action::NavigateEnd
+        ) {
+            self.table_scroll = None;
+        }
+
         match action {
-            Action::Quit => Ok(Some(AppCommand::Quit)),
             Action::Cancel => {
-                // Escape in normal mode - if in range pane, go to hosts table
-                if self.focus == Focus::RangeInput {
-                    self.focus = Focus::HostsTable;
+                // Escape in normal mode steps back to the hosts table from the
+                // range pane or the details pane (closing the compact popup);
+                // on the table it dismisses the banner
+                if self.focus == Focus::HostsTable {
+                    self.banner = None;
                 }
+                self.focus = Focus::HostsTable;
                 Ok(None)
             }
             Action::Backspace => {
@@ -365,7 +1316,7 @@ impl App {
             }
             Action::StartScan => {
                 if self.scan_state != ScanState::Scanning {
-                    return Ok(Some(AppCommand::StartScan));
+                    return Ok(self.request_new_scan());
                 }
                 Ok(None)
             }
@@ -373,6 +1324,13 @@ impl App {
                 if self.scan_state == ScanState::Scanning {
                     self.pause_scan();
                 }
+                self.stop_discovery();
+                Ok(None)
+            }
+            Action::Discover => {
+                if !self.discovering {
+                    return Ok(Some(AppCommand::Discover));
+                }
                 Ok(None)
             }
             Action::EditRange => {
@@ -395,28 +1353,130 @@ impl App {
             Action::ToggleFilter => {
                 self.filter_mode = self.filter_mode.toggle();
                 self.update_filtered_hosts();
+                // Old offsets point into the previous list
+                self.table_scroll = None;
+                *self.table_state.offset_mut() = 0;
                 Ok(None)
             }
             Action::Export => {
-                self.input_mode = InputMode::Exporting;
+                if self.ensure_exportable() {
+                    self.input_mode = InputMode::Exporting;
+                }
                 Ok(None)
             }
             Action::ToggleDetails => {
                 self.show_details = !self.show_details;
                 Ok(None)
             }
+            Action::ToggleLegend => {
+                self.show_legend = !self.show_legend;
+                Ok(None)
+            }
+            Action::ToggleLastSeen => {
+                self.toggle_column(Column::LastSeen);
+                Ok(None)
+            }
+            Action::ToggleUptime => {
+                self.toggle_column(Column::Uptime);
+                Ok(None)
+            }
+            Action::TogglePorts => {
+                self.toggle_column(Column::Ports);
+                Ok(None)
+            }
+            Action::PickColumns => {
+                self.open_column_picker();
+                Ok(None)
+            }
+            Action::OpenSettings => {
+                self.tuning_edit = None;
+                self.tuning_error = None;
+                self.input_mode = InputMode::Settings;
+                Ok(None)
+            }
+            Action::JumpToChange => {
+                self.jump_to_next_change();
+                Ok(None)
+            }
+            Action::JumpToSameMac => {
+                self.jump_to_same_mac();
+                Ok(None)
+            }
+            Action::PreviousRange | Action::NextRange => {
+                self.cycle_range_view(action == Action::NextRange);
+                Ok(None)
+            }
+            Action::SplitLeft | Action::SplitRight => {
+                let steps = if action == Action::SplitLeft { -1 } else { 1 };
+                if self.details_pane_visible() && self.settings.adjust_split(steps) {
+                    save_settings(&self.settings);
+                }
+                Ok(None)
+            }
+            Action::ToggleStacked => {
+                self.settings.force_stacked = !self.settings.force_stacked;
+                save_settings(&self.settings);
+                Ok(None)
+            }
+            Action::ShowEventLog => {
+                self.input_mode = InputMode::EventLog;
+                self.event_log_scroll = 0;
+                Ok(None)
+            }
+            Action::ReopenOverlay => {
+                if self.overlay_title.is_empty() {
+                    self.notify(Severity::Info, "No ping or tracert output yet");
+                } else {
+                    self.input_mode = InputMode::OutputOverlay;
+                    self.clamp_scroll();
+                }
+                Ok(None)
+            }
+            Action::ShowConflicts => {
+                if self.conflicts.is_empty() && self.port_changes().next().is_none() {
+                    self.notify(Severity::Info, "No IP conflicts or port changes found");
+                } else {
+                    self.input_mode = InputMode::Conflicts;
+                    self.conflicts_scroll = 0;
+                }
+                Ok(None)
+            }
+            Action::ShowSummary => {
+                if self.hosts.is_empty() {
+                    self.notify(Severity::Info, "Nothing to summarize: run a scan first");
+                } else {
+                    let summary = summary::summarize(&self.hosts, self.offline_dropped)
+                        .count_offline_causes(&self.dropped_causes);
+                    self.summary_lines = summary.lines(self.compat);
+                    self.summary_scroll = 0;
+                    self.input_mode = InputMode::Summary;
+                }
+                Ok(None)
+            }
             Action::Help => {
                 self.input_mode = InputMode::Help;
+                self.help_scroll = 0;
                 Ok(None)
             }
             Action::WakeOnLan => {
+                if let Some(job) = self.wake_job.as_mut().filter(|job| job.is_running()) {
+                    job.stop();
+                    self.log(Severity::Info, "Wake-on-LAN stopped");
+                    return Ok(None);
+                }
+                if self.refused_in_safe_mode("Wake-on-LAN") {
+                    return Ok(None);
+                }
+                if !self.selected_hosts.is_empty() {
+                    return Ok(Some(AppCommand::WakeSelected));
+                }
                 match self.send_wol() {
-                    Ok(Some(msg)) => self.export_message = Some(msg),
-                    Ok(None) => {
-                        self.export_message =
-                            Some("Select a host with a known MAC address for WOL".to_string())
-                    }
-                    Err(e) => self.export_message = Some(format!("WOL error: {}", e)),
+                    Ok(Some(msg)) => self.notify(Severity::Success, msg),
+                    Ok(None) => self.notify(
+                        Severity::Info,
+                        "Select a host with a known MAC address for WOL",
+                    ),
+                    Err(e) => self.notify(Severity::Error, format!("WOL error: {}", e)),
                 }
                 Ok(None)
             }
@@ -435,13 +1495,60 @@ impl App {
                 Ok(None)
             }
             Action::SaveHost => {
-                self.save_selected_host()?;
+                self.save_selected_host(false)?;
                 Ok(None)
             }
-            Action::NavigateUp => {
-                if self.focus == Focus::RangeInput {
-                    self.prev_adapter();
-                } else {
+            Action::SaveHostJson => {
+                self.save_selected_host(true)?;
+                Ok(None)
+            }
+            Action::ForgetHost => {
+                if self.selected_host().is_some() {
+                    self.input_mode = InputMode::ConfirmForget;
+                } else {
+                    self.notify(Severity::Info, "No host selected");
+                }
+                Ok(None)
+            }
+            Action::CopyIp => {
+                self.copy_selected_ip();
+                Ok(None)
+            }
+            Action::RefreshMac => Ok(self.request_mac_refresh()),
+            Action::Goto => {
+                if !self.filtered_hosts.is_empty() {
+                    self.goto_input.clear();
+                    self.input_mode = InputMode::Goto;
+                }
+                Ok(None)
+            }
+            Action::CopyCommand => {
+                if self.selected_host().is_none() {
+                    self.notify(Severity::Info, "No host selected");
+                } else if self.command_choices().is_empty() {
+                    self.notify(Severity::Info, "No command templates apply to this host");
+                } else {
+                    self.command_index = 0;
+                    self.input_mode = InputMode::CopyCommand;
+                }
+                Ok(None)
+            }
+            Action::Knock => {
+                if self.selected_host().is_none() {
+                    self.notify(Severity::Info, "No host selected");
+                } else if self.settings.knock_actions.is_empty() {
+                    self.notify(Severity::Info, "No knock sequences: add knock_actions to the settings file");
+                } else if !self.refused_in_safe_mode("port knocking") {
+                    self.knock_index = 0;
+                    self.input_mode = InputMode::Knock;
+                }
+                Ok(None)
+            }
+            Action::NavigateUp => {
+                if self.focus == Focus::RangeInput {
+                    self.prev_adapter();
+                    return Ok(self.check_adapter_gateway());
+                } else {
                     self.select_previous();
                 }
                 Ok(None)
@@ -449,6 +1556,7 @@ impl App {
             Action::NavigateDown => {
                 if self.focus == Focus::RangeInput {
                     self.next_adapter();
+                    return Ok(self.check_adapter_gateway());
                 } else {
                     self.select_next();
                 }
@@ -487,16 +1595,21 @@ impl App {
                 match self.focus {
                     Focus::RangeInput => {
                         if self.scan_state != ScanState::Scanning {
-                            return Ok(Some(AppCommand::StartScan));
+                            return Ok(self.request_new_scan());
                         }
                     }
                     Focus::HostsTable => {
-                        // Jump to Details pane when a host row is selected
-                        if self.show_details && self.selected_host().is_some() {
-                            self.focus = Focus::DetailsPane;
+                        // Jump to Details pane (or open the compact popup) when a host row is
+                        // selected; with no details to show, go straight to the host action
+                        if self.selected_host().is_some() {
+                            if self.show_details || self.compact_layout {
+                                self.focus = Focus::DetailsPane;
+                            } else {
+                                return self.run_enter_action();
+                            }
                         }
                     }
-                    Focus::DetailsPane => {}
+                    Focus::DetailsPane => return self.run_enter_action(),
                 }
                 Ok(None)
             }
@@ -504,7 +1617,7 @@ impl App {
                 self.focus = match self.focus {
                     Focus::RangeInput => Focus::HostsTable,
                     Focus::HostsTable => {
-                        if self.show_details {
+                        if self.details_pane_visible() {
                             Focus::DetailsPane
                         } else {
                             Focus::RangeInput
@@ -517,7 +1630,7 @@ impl App {
             Action::SwitchPaneReverse => {
                 self.focus = match self.focus {
                     Focus::RangeInput => {
-                        if self.show_details {
+                        if self.details_pane_visible() {
                             Focus::DetailsPane
                         } else {
                             Focus::HostsTable
@@ -552,7 +1665,7 @@ impl App {
                 // Enter - exit editing and start scan
                 self.input_mode = InputMode::Normal;
                 if self.scan_state != ScanState::Scanning && !self.range_input.is_empty() {
-                    return Ok(Some(AppCommand::StartScan));
+                    return Ok(self.request_new_scan());
                 }
             }
             Action::Backspace => {
@@ -587,6 +1700,25 @@ impl App {
             Action::NavigateEnd => {
                 self.range_cursor = self.range_input.len();
             }
+            Action::WordLeft => {
+                self.range_cursor = input::word_left(&self.range_input, self.range_cursor);
+            }
+            Action::WordRight => {
+                self.range_cursor = input::word_right(&self.range_input, self.range_cursor);
+            }
+            Action::DeleteWord => {
+                let start = input::word_left(&self.range_input, self.range_cursor);
+                if start < self.range_cursor {
+                    self.range_input.replace_range(start..self.range_cursor, "");
+                    self.range_cursor = start;
+                    self.adapter_index = None;
+                }
+            }
+            Action::ClearField => {
+                self.range_input.clear();
+                self.range_cursor = 0;
+                self.adapter_index = None;
+            }
             Action::Character(c) => {
                 self.range_input.insert(self.range_cursor, c);
                 self.range_cursor += 1;
@@ -633,6 +1765,21 @@ impl App {
             Action::NavigateEnd => {
                 self.ports_cursor = self.ports_input.len();
             }
+            Action::WordLeft => {
+                self.ports_cursor = input::word_left(&self.ports_input, self.ports_cursor);
+            }
+            Action::WordRight => {
+                self.ports_cursor = input::word_right(&self.ports_input, self.ports_cursor);
+            }
+            Action::DeleteWord => {
+                let start = input::word_left(&self.ports_input, self.ports_cursor);
+                self.ports_input.replace_range(start..self.ports_cursor, "");
+                self.ports_cursor = start;
+            }
+            Action::ClearField => {
+                self.ports_input.clear();
+                self.ports_cursor = 0;
+            }
             Action::Character(c) => {
                 self.ports_input.insert(self.ports_cursor, c);
                 self.ports_cursor += 1;
@@ -643,500 +1790,4788 @@ impl App {
     }
 
     fn handle_help_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
-        if action == Action::Cancel {
-            self.input_mode = InputMode::Normal;
+        let scroll = self.help_scroll;
+        self.help_scroll = match action {
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                0
+            }
+            Action::NavigateUp => scroll.saturating_sub(1),
+            Action::NavigateDown => scroll + 1,
+            Action::NavigatePageUp => scroll.saturating_sub(10),
+            Action::NavigatePageDown => scroll + 10,
+            Action::NavigateHome => 0,
+            Action::NavigateEnd => self.help_max_scroll,
+            _ => scroll,
         }
+        .min(self.help_max_scroll);
         Ok(None)
     }
 
-    fn handle_export_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
-        match action {
+    fn handle_event_log_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let max_scroll = self
+            .event_log
+            .count(self.event_log_filter)
+            .saturating_sub(self.event_log_page);
+        let scroll = self.event_log_scroll;
+        self.event_log_scroll = match action {
             Action::Cancel => {
                 self.input_mode = InputMode::Normal;
+                0
             }
-            Action::Character('c') => {
-                self.export_csv()?;
+            Action::ToggleFilter => {
+                // All → Info → Success → Error → All
+                self.event_log_filter = match self.event_log_filter {
+                    None => Some(Severity::Info),
+                    Some(Severity::Info) => Some(Severity::Success),
+                    Some(Severity::Success) => Some(Severity::Error),
+                    Some(Severity::Error) => None,
+                };
+                0
+            }
+            Action::NavigateUp => scroll.saturating_sub(1),
+            Action::NavigateDown => (scroll + 1).min(max_scroll),
+            Action::NavigatePageUp => scroll.saturating_sub(10),
+            Action::NavigatePageDown => (scroll + 10).min(max_scroll),
+            Action::NavigateHome => 0,
+            Action::NavigateEnd => max_scroll,
+            _ => scroll,
+        };
+        Ok(None)
+    }
+
+    fn handle_conflicts_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        // The overlay shares the event log's size
+        let max_scroll = self.conflict_lines().len().saturating_sub(self.event_log_page);
+        let scroll = self.conflicts_scroll;
+        self.conflicts_scroll = match action {
+            Action::Cancel => {
                 self.input_mode = InputMode::Normal;
+                0
             }
-            Action::Character('j') => {
-                self.export_json()?;
+            Action::NavigateUp => scroll.saturating_sub(1),
+            Action::NavigateDown => (scroll + 1).min(max_scroll),
+            Action::NavigatePageUp => scroll.saturating_sub(10),
+            Action::NavigatePageDown => (scroll + 10).min(max_scroll),
+            Action::NavigateHome => 0,
+            Action::NavigateEnd => max_scroll,
+            _ => scroll,
+        };
+        Ok(None)
+    }
+
+    fn handle_summary_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        // The overlay shares the event log's size
+        let max_scroll = self.summary_lines.len().saturating_sub(self.event_log_page);
+        let scroll = self.summary_scroll;
+        self.summary_scroll = match action {
+            Action::Cancel => {
                 self.input_mode = InputMode::Normal;
+                0
             }
+            Action::NavigateUp => scroll.saturating_sub(1),
+            Action::NavigateDown => (scroll + 1).min(max_scroll),
+            Action::NavigatePageUp => scroll.saturating_sub(10),
+            Action::NavigatePageDown => (scroll + 10).min(max_scroll),
+            Action::NavigateHome => 0,
+            Action::NavigateEnd => max_scroll,
+            _ => scroll,
+        };
+        Ok(None)
+    }
+
+    fn handle_context_menu_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let count = MenuItem::ALL.len();
+        match action {
+            Action::NavigateUp => self.menu_index = (self.menu_index + count - 1) % count,
+            Action::NavigateDown => self.menu_index = (self.menu_index + 1) % count,
+            Action::Select => return self.run_menu_item(MenuItem::ALL[self.menu_index]),
+            Action::Cancel => self.input_mode = InputMode::Normal,
             _ => {}
         }
         Ok(None)
     }
 
-    fn handle_overlay_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
-        match action {
-            Action::StopOverlay => {
-                if let Some(tx) = &self.overlay_cancel_tx {
-                    let _ = tx.try_send(());
-                }
-                self.overlay_cancel_tx = None;
-                self.input_mode = InputMode::Normal;
-                self.overlay_lines.clear();
-                self.overlay_scroll = 0;
-            }
+    fn handle_copy_command_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let choices = self.command_choices();
+        let count = choices.len().max(1);
+        let pick = match action {
             Action::NavigateUp => {
-                self.overlay_scroll = self.overlay_scroll.saturating_sub(1);
+                self.command_index = (self.command_index + count - 1) % count;
+                None
             }
             Action::NavigateDown => {
-                self.overlay_scroll += 1; // clamped during render
+                self.command_index = (self.command_index + 1) % count;
+                None
             }
-            Action::NavigateHome => {
-                self.overlay_scroll = 0;
+            Action::Select => Some(self.command_index),
+            Action::Character(c) => c.to_digit(10).map(|n| n as usize - 1),
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                None
             }
-            Action::NavigateEnd => {
-                self.overlay_scroll = self.overlay_lines.len().saturating_sub(1);
+            _ => None,
+        };
+        if let Some((_, command)) = pick.and_then(|i| choices.into_iter().nth(i)) {
+            self.input_mode = InputMode::Normal;
+            match crate::clipboard::copy(&command) {
+                Ok(()) => self.notify(Severity::Success, format!("Copied {}", command)),
+                Err(e) => self.notify(Severity::Error, format!("Copy failed: {}", e)),
             }
-            _ => {}
         }
         Ok(None)
     }
 
-    fn pause_scan(&mut self) {
-        if self.scan_state == ScanState::Scanning {
-            if let Some(tx) = &self.scan_cancel_tx {
-                let _ = tx.try_send(());
+    fn handle_knock_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let count = self.settings.knock_actions.len().max(1);
+        let pick = match action {
+            Action::NavigateUp => {
+                self.knock_index = (self.knock_index + count - 1) % count;
+                None
+            }
+            Action::NavigateDown => {
+                self.knock_index = (self.knock_index + 1) % count;
+                None
+            }
+            Action::Select => Some(self.knock_index),
+            Action::Character(c) => c.to_digit(10).map(|n| n as usize - 1),
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                None
+            }
+            _ => None,
+        };
+        let (Some(host), Some(knock)) = (
+            self.selected_host(),
+            pick.and_then(|i| self.settings.knock_actions.get(i)),
+        ) else {
+            return Ok(None);
+        };
+        let (ip, label, plan) = (host.ip, knock.label.clone(), knock.plan());
+        self.input_mode = InputMode::Normal;
+        match plan {
+            Ok(plan) => Ok(Some(AppCommand::Knock(ip, label, plan))),
+            Err(e) => {
+                self.notify(Severity::Error, format!("Knock: {}", e));
+                Ok(None)
             }
-            self.scan_state = ScanState::Paused;
         }
     }
 
-    pub fn resume_scan(&mut self) {
-        if self.scan_state == ScanState::Paused {
-            self.scan_state = ScanState::Scanning;
-            if let Some(tx) = &self.scan_resume_tx {
-                let _ = tx.try_send(());
+    fn handle_column_picker_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let count = self.column_picker.len().max(1);
+        let index = self.column_index.min(count - 1);
+        match action {
+            Action::NavigateUp => self.column_index = (index + count - 1) % count,
+            Action::NavigateDown => self.column_index = (index + 1) % count,
+            Action::ToggleSelect => match self.column_picker.get_mut(index) {
+                Some((Column::Ip, _)) => {
+                    self.notify(Severity::Info, "The IP column is always shown")
+                }
+                Some((_, shown)) => *shown = !*shown,
+                None => {}
+            },
+            // Move the highlighted column left (-) or right (+) in the table
+            Action::Character('-') if index > 0 => {
+                self.column_picker.swap(index, index - 1);
+                self.column_index = index - 1;
+            }
+            Action::Character('+') if index + 1 < self.column_picker.len() => {
+                self.column_picker.swap(index, index + 1);
+                self.column_index = index + 1;
             }
+            Action::Select => {
+                self.settings.columns = self
+                    .column_picker
+                    .iter()
+                    .filter(|c| c.1)
+                    .map(|c| c.0)
+                    .collect();
+                save_settings(&self.settings);
+                self.input_mode = InputMode::Normal;
+            }
+            Action::Cancel => self.input_mode = InputMode::Normal,
+            _ => {}
         }
+        Ok(None)
     }
 
-    pub fn select_next(&mut self) {
-        if self.filtered_hosts.is_empty() {
-            return;
+    fn handle_settings_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let count = Field::ALL.len();
+        let field = Field::ALL[self.tuning_index.min(count - 1)];
+        if let Some(text) = &mut self.tuning_edit {
+            match action {
+                Action::Character(c) if text.len() < 6 => text.push(c),
+                Action::Backspace => {
+                    text.pop();
+                }
+                Action::Select => {
+                    let text = text.clone();
+                    self.change_tuning(|tuning| field.set(tuning, &text));
+                }
+                Action::Cancel => {
+                    self.tuning_edit = None;
+                    self.tuning_error = None;
+                }
+                _ => {}
+            }
+            return Ok(None);
         }
-        let i = match self.table_state.selected() {
-            Some(i) => (i + 1).min(self.filtered_hosts.len() - 1),
-            None => 0,
-        };
-        self.table_state.select(Some(i));
+        match action {
+            Action::NavigateUp => self.tuning_index = (self.tuning_index + count - 1) % count,
+            Action::NavigateDown => self.tuning_index = (self.tuning_index + 1) % count,
+            Action::Select | Action::ToggleSelect if field.is_toggle() => {
+                self.change_tuning(|tuning| {
+                    field.toggle(tuning);
+                    Ok(())
+                });
+            }
+            Action::Select => {
+                self.tuning_edit = Some(field.raw(&Tuning::capture(&self.config)));
+            }
+            // Typing a digit replaces the value
+            Action::Character(c) if !field.is_toggle() => self.tuning_edit = Some(c.to_string()),
+            Action::Cancel => self.input_mode = InputMode::Normal,
+            _ => {}
+        }
+        Ok(None)
     }
 
-    pub fn select_previous(&mut self) {
-        if self.filtered_hosts.is_empty() {
-            return;
+    /// Apply an edit from the settings overlay to the config and save it;
+    /// a refused edit stays open with the reason
+    fn change_tuning(&mut self, edit: impl FnOnce(&mut Tuning) -> Result<(), String>) {
+        let mut tuning = Tuning::capture(&self.config);
+        match edit(&mut tuning) {
+            Ok(()) => {
+                tuning.apply(&mut self.config);
+                self.settings.tuning = Some(tuning);
+                save_settings(&self.settings);
+                self.tuning_edit = None;
+                self.tuning_error = None;
+            }
+            Err(error) => self.tuning_error = Some(error),
         }
-        let i = match self.table_state.selected() {
-            Some(i) => i.saturating_sub(1),
-            None => 0,
-        };
-        self.table_state.select(Some(i));
     }
 
-    pub fn update_filtered_hosts(&mut self) {
-        self.filtered_hosts = self
-            .hosts
+    /// List every column for the picker: the shown ones in table order,
+    /// then the rest
+    fn open_column_picker(&mut self) {
+        let shown = &self.settings.columns;
+        self.column_picker = shown
             .iter()
-            .enumerate()
-            .filter(|(_, h)| match self.filter_mode {
-                FilterMode::All => true,
-                FilterMode::OnlineOnly => h.is_alive,
-            })
-            .map(|(i, _)| i)
+            .map(|&c| (c, true))
+            .chain(Column::ALL.into_iter().filter(|c| !shown.contains(c)).map(|c| (c, false)))
             .collect();
+        self.column_index = 0;
+        self.input_mode = InputMode::ColumnPicker;
+    }
 
-        // Adjust selection if needed
-        if let Some(selected) = self.table_state.selected() {
-            if selected >= self.filtered_hosts.len() {
-                if self.filtered_hosts.is_empty() {
-                    self.table_state.select(None);
-                } else {
-                    self.table_state.select(Some(self.filtered_hosts.len() - 1));
+    /// Show `column` at the right of the table, or hide it, and save the
+    /// column set
+    fn toggle_column(&mut self, column: Column) {
+        let columns = &mut self.settings.columns;
+        if columns.contains(&column) {
+            columns.retain(|&c| c != column);
+        } else {
+            columns.push(column);
+        }
+        save_settings(&self.settings);
+    }
+
+    fn handle_goto_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        match action {
+            Action::Character(c) => self.goto_input.push(c),
+            Action::Backspace => {
+                self.goto_input.pop();
+            }
+            Action::Select => {
+                self.input_mode = InputMode::Normal;
+                let ips: Vec<Ipv4Addr> = self.filtered_hosts.iter().map(|&i| self.hosts[i].ip).collect();
+                // No match leaves the selection where it was
+                if let Some(row) = goto_match(&ips, &self.goto_input) {
+                    self.table_state.select(Some(row));
+                    self.table_scroll = None;
+                    self.focus = Focus::HostsTable;
                 }
             }
+            Action::Cancel => self.input_mode = InputMode::Normal,
+            _ => {}
         }
+        Ok(None)
     }
 
-    pub fn get_filtered_hosts(&self) -> Vec<&HostInfo> {
-        self.filtered_hosts
+    /// `(label, command line)` for each command template that applies to the
+    /// selected host (by its open ports)
+    pub fn command_choices(&self) -> Vec<(String, String)> {
+        let Some(host) = self.selected_host() else {
+            return Vec::new();
+        };
+        self.settings
+            .command_templates
             .iter()
-            .map(|&i| &self.hosts[i])
+            .filter(|t| t.applies_to(host))
+            .map(|t| (t.label.clone(), t.expand(host)))
             .collect()
     }
 
-    pub fn selected_host(&self) -> Option<&HostInfo> {
-        self.table_state
-            .selected()
-            .and_then(|i| self.filtered_hosts.get(i))
-            .map(|&i| &self.hosts[i])
+    /// `(label, knocks)` for each entry of the knock chooser
+    pub fn knock_choices(&self) -> Vec<(String, String)> {
+        self.settings
+            .knock_actions
+            .iter()
+            .map(|k| (k.label.clone(), k.describe()))
+            .collect()
     }
 
-    #[allow(dead_code)]
-    pub fn selected_host_mut(&mut self) -> Option<&mut HostInfo> {
-        let idx = self
-            .table_state
-            .selected()
-            .and_then(|i| self.filtered_hosts.get(i).copied())?;
-        self.hosts.get_mut(idx)
+    /// Open the host context menu with its top-left corner near `(col, row)`
+    pub fn open_context_menu(&mut self, col: u16, row: u16) {
+        if self.selected_host().is_some() {
+            self.menu_anchor = (col, row);
+            self.menu_index = 0;
+            self.input_mode = InputMode::ContextMenu;
+        }
     }
 
-    pub fn progress(&self) -> f64 {
-        if self.scan_total == 0 {
-            0.0
-        } else {
-            self.scan_completed as f64 / self.scan_total as f64
-        }
+    fn run_menu_item(&mut self, item: MenuItem) -> Result<Option<AppCommand>> {
+        self.input_mode = InputMode::Normal;
+        let action = match item {
+            // `p` means "edit ports" outside the details pane, so go direct
+            MenuItem::PortScan => return Ok(Some(AppCommand::ScanPortsForSelected)),
+            MenuItem::Ping => Action::ContinuousPing,
+            MenuItem::Tracert => Action::RunTracert,
+            MenuItem::WakeOnLan => Action::WakeOnLan,
+            MenuItem::Forget => Action::ForgetHost,
+            MenuItem::CopyIp => Action::CopyIp,
+            MenuItem::CopyCommand => Action::CopyCommand,
+            MenuItem::Knock => Action::Knock,
+            MenuItem::Columns => Action::PickColumns,
+        };
+        self.handle_normal_action(action)
     }
 
-    /// Dots12-style CLI spinner frames (braille characters)
-    const SPINNER_FRAMES: &'static [&'static str] = &[
-        "⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"
-    ];
+    /// Run the configured Enter action (`--enter-action`) on the selected host
+    fn run_enter_action(&mut self) -> Result<Option<AppCommand>> {
+        let Some(host) = self.selected_host() else {
+            return Ok(None);
+        };
+        let item = match self.config.enter_action {
+            HostAction::Auto if host.is_alive && !host.ports_scanned => MenuItem::PortScan,
+            HostAction::Auto | HostAction::Ping => MenuItem::Ping,
+            HostAction::Ports => MenuItem::PortScan,
+            HostAction::Tracert => MenuItem::Tracert,
+            HostAction::Wake => MenuItem::WakeOnLan,
+            HostAction::None => return Ok(None),
+        };
+        self.run_menu_item(item)
+    }
 
-    /// Get current spinner frame based on animation tick
-    fn spinner(&self) -> &'static str {
-        Self::SPINNER_FRAMES[(self.animation_tick as usize) % Self::SPINNER_FRAMES.len()]
+    fn handle_confirm_forget_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let rescan = match action {
+            Action::Confirm => false,
+            Action::Character('p') => true,
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+        self.input_mode = InputMode::Normal;
+        let alive = self.forget_selected_host();
+        Ok((rescan && alive).then_some(AppCommand::ScanPortsForSelected))
     }
 
-    /// Short state string for the bottom status bar (must stay compact)
-    pub fn status_text(&self) -> String {
-        if self.adapters_loading {
-            return format!("{} Loading", self.spinner());
-        }
+    fn handle_confirm_new_scan_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        let command = match action {
+            Action::Confirm => {
+                self.input_mode = InputMode::Normal;
+                return Ok(self.request_start());
+            }
+            Action::Character('r') => AppCommand::ResumeScan,
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+        self.input_mode = InputMode::Normal;
+        Ok(Some(command))
+    }
 
-        match self.scan_state {
-            ScanState::Idle => {
-                if self.hosts.iter().any(|h| h.cached_at.is_some()) {
-                    "Cached".to_string()
-                } else {
-                    "Ready".to_string()
-                }
+    fn handle_confirm_large_scan_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        match action {
+            Action::Confirm => {
+                self.input_mode = InputMode::Normal;
+                Ok(Some(AppCommand::StartScan))
             }
-            ScanState::Scanning => {
-                format!("{} {}/{}", self.spinner(), self.scan_completed, self.scan_total)
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                Ok(None)
             }
-            ScanState::Paused => "Paused".to_string(),
-            ScanState::Completed => "Done".to_string(),
+            _ => Ok(None),
         }
     }
 
-    /// Full summary shown in the header Status box after a scan completes
-    pub fn completion_summary(&self) -> String {
-        let online = self.hosts.iter().filter(|h| h.is_alive).count();
-        format!("{} hosts ({} online)", self.hosts.len(), online)
+    fn handle_confirm_quit_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        match action {
+            Action::Confirm => Ok(Some(AppCommand::Quit)),
+            Action::Character('s') if self.has_partial_results() => {
+                self.cache_writer.checkpoint(
+                    &self.scan_range,
+                    self.hosts.clone(),
+                    self.checkpoint_progress(),
+                    self.scan_started_at.unwrap_or(0),
+                );
+                self.cache_writer.flush();
+                Ok(Some(AppCommand::Quit))
+            }
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
     }
 
-    pub async fn start_scan(&mut self) -> Result<mpsc::Receiver<ScanEvent>> {
-        let range = IpRange::parse(&self.range_input)?;
-        let addresses: Vec<Ipv4Addr> = range.addresses().to_vec();
-
-        self.hosts.clear();
-        self.filtered_hosts.clear();
-        self.selected_hosts.clear();
-        self.table_state.select(None);
-        self.scan_total = addresses.len();
-        self.scan_completed = 0;
-        self.scan_state = ScanState::Scanning;
-        // Move focus to hosts table when scan starts
-        self.focus = Focus::HostsTable;
-
-        let (event_tx, event_rx) = mpsc::channel(256);
-        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
-        self.scan_cancel_tx = Some(cancel_tx);
-
-        let config = self.config.clone();
-        let dns_resolver = Arc::clone(&self.dns_resolver);
-
-        tokio::spawn(async move {
-            let (ping_tx, mut ping_rx) = mpsc::channel(256);
-
-            // Start ping scan
-            let addresses_clone = addresses.clone();
-            let ping_config = config.ping.clone();
-            tokio::spawn(async move {
-                let _ = scan_hosts(addresses_clone, ping_config, ping_tx).await;
-            });
-
-            // Process results
-            loop {
-                tokio::select! {
-                    _ = cancel_rx.recv() => {
-                        break;
-                    }
-                    result = ping_rx.recv() => {
-                        match result {
-                            Some(ping_result) => {
-                                let mut host: HostInfo = ping_result.into();
-
-                                // Resolve hostname for alive hosts
-                                if host.is_alive && config.resolve_hostnames {
-                                    if let Some(hostname) = dns_resolver.resolve(host.ip).await {
-                                        host.hostname = Some(hostname);
-                                    }
-                                }
-
-                                // Get MAC address for alive hosts on local network
-                                if host.is_alive && config.detect_mac {
-                                    if let Some(mac) = get_mac_address(host.ip) {
-                                        host.mac = Some(mac);
-                                    }
-                                }
-
-                                let _ = event_tx.send(ScanEvent::HostDiscovered(host)).await;
-                            }
-                            None => {
-                                let _ = event_tx.send(ScanEvent::ScanComplete).await;
+    fn handle_export_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        match action {
+            // The dialog stays open so the options can be changed
+            Action::Character('c' | 'j') if self.exported_hosts().next().is_none() => {
+                self.notify(Severity::Info, "No host matches the export options");
+            }
+            Action::Cancel => {
+                self.input_mode = InputMode::Normal;
+            }
+            Action::Character('c') => {
+                self.export_csv()?;
+                self.input_mode = InputMode::Normal;
+            }
+            Action::Character('j') => {
+                self.export_json()?;
+                self.input_mode = InputMode::Normal;
+            }
+            Action::Character('t') => {
+                self.export_text()?;
+                self.input_mode = InputMode::Normal;
+            }
+            Action::ToggleFilter => {
+                self.export_respect_filter = !self.export_respect_filter;
+            }
+            Action::Character('o') => {
+                self.config.export_offline = !self.config.export_offline;
+                self.settings.export_offline = Some(self.config.export_offline);
+                save_settings(&self.settings);
+            }
+            Action::Character('p') => {
+                self.config.export_open_ports_only = !self.config.export_open_ports_only;
+                self.settings.export_open_ports_only = Some(self.config.export_open_ports_only);
+                save_settings(&self.settings);
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_overlay_action(&mut self, action: Action) -> Result<Option<AppCommand>> {
+        match action {
+            Action::StopOverlay => self.stop_overlay_task(),
+            Action::CloseOverlay => self.close_overlay(),
+            Action::NavigateUp => {
+                self.overlay_scroll = self.overlay_scroll.saturating_sub(1);
+            }
+            Action::NavigateDown => {
+                self.overlay_scroll = (self.overlay_scroll + 1).min(self.overlay_max_scroll());
+            }
+            Action::NavigateHome => {
+                self.overlay_scroll = 0;
+            }
+            Action::NavigateEnd => {
+                self.overlay_scroll = self.overlay_max_scroll();
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn pause_scan(&mut self) {
+        if self.scan_state == ScanState::Scanning {
+            if let Some(tx) = &self.scan_cancel_tx {
+                let _ = tx.try_send(());
+            }
+            self.scan_state = ScanState::Paused;
+            self.log(
+                Severity::Info,
+                format!("Scan paused at {}/{}", self.scan_completed, self.scan_total),
+            );
+            self.checkpoint();
+        }
+    }
+
+    /// Continue a paused (or restored) scan with the addresses that have no
+    /// result yet, keeping the hosts found so far
+    pub async fn resume_scan(&mut self) -> Result<mpsc::Receiver<ScanEvent>> {
+        let (total, remaining) = self.remaining_addresses()?;
+        self.check_source_ip()?;
+        let remaining_count: usize = remaining.iter().map(|(_, group)| group.len()).sum();
+        self.scan_total = total;
+        self.scan_completed = total - remaining_count;
+        // Lookups still queued on the paused scan's channel are dropped with it
+        self.enrichment_pending = 0;
+        self.scan_started_at.get_or_insert_with(crate::cache::now_secs);
+        self.checkpoint_completed = self.scan_completed;
+        self.last_checkpoint = Instant::now();
+        self.scan_state = ScanState::Scanning;
+        self.log(
+            Severity::Info,
+            format!("Scan resumed: {} addresses left", remaining_count),
+        );
+        self.focus = Focus::HostsTable;
+        Ok(self.spawn_scan(remaining))
+    }
+
+    /// Size of the scan's ranges and, per batch range, its addresses without
+    /// a result yet. Batch progress is recounted from the results kept.
+    fn remaining_addresses(&mut self) -> Result<(usize, BatchGroups)> {
+        let groups = self.plan_batch(&self.scan_range.clone())?;
+        let total = groups.iter().map(Vec::len).sum();
+        let mut remaining = Vec::new();
+        for (index, group) in groups.into_iter().enumerate() {
+            let left: Vec<Ipv4Addr> = group
+                .iter()
+                .copied()
+                .filter(|ip| !self.scanned_ips.contains(ip))
+                .collect();
+            let range = &mut self.batch[index];
+            range.completed = group.len() - left.len();
+            range.done = left.is_empty();
+            if !left.is_empty() {
+                remaining.push((index, left));
+            }
+        }
+        for host in self.hosts.iter().filter(|h| h.is_alive) {
+            if let Some(&index) = self.batch_of.get(&host.ip) {
+                self.batch[index].online += 1;
+            }
+        }
+        Ok((total, remaining))
+    }
+
+    /// Set up `batch` for the ranges in `input`, returning each range's
+    /// addresses. An address listed by several ranges belongs to the first.
+    fn plan_batch(&mut self, input: &str) -> Result<Vec<Vec<Ipv4Addr>>> {
+        let ranges = IpRange::parse_batch(input)?;
+        self.batch.clear();
+        self.batch_of.clear();
+        self.range_view = None;
+        let mut groups = Vec::with_capacity(ranges.len());
+        for (index, (text, range)) in ranges.into_iter().enumerate() {
+            let group: Vec<Ipv4Addr> = range
+                .addresses()
+                .iter()
+                .copied()
+                .filter(|&ip| *self.batch_of.entry(ip).or_insert(index) == index)
+                .collect();
+            self.batch.push(BatchRange {
+                range: text,
+                total: group.len(),
+                completed: 0,
+                online: 0,
+                done: false,
+            });
+            groups.push(group);
+        }
+        Ok(groups)
+    }
+
+    /// Count a scan result towards its batch range
+    fn count_in_batch(&mut self, ip: Ipv4Addr, alive: bool) {
+        if let Some(range) = self.batch_of.get(&ip).and_then(|&i| self.batch.get_mut(i)) {
+            range.completed += 1;
+            range.online += usize::from(alive);
+        }
+    }
+
+    /// Hosts of the current results that belong to batch range `index`
+    fn batch_hosts(&self, index: usize) -> Vec<HostInfo> {
+        self.hosts
+            .iter()
+            .filter(|h| self.batch_of.get(&h.ip) == Some(&index))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the current scan is a batch of several ranges
+    pub fn is_batch(&self) -> bool {
+        self.batch.len() > 1
+    }
+
+    /// Narrow the table to the next (`forward`) or previous batch range,
+    /// passing through "every range" between the last and the first
+    fn cycle_range_view(&mut self, forward: bool) {
+        if !self.is_batch() {
+            self.notify(Severity::Info, "Not a batch scan: separate ranges with ;");
+            return;
+        }
+        let count = self.batch.len();
+        self.range_view = match (self.range_view, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(count - 1),
+            (Some(i), true) => (i + 1 < count).then_some(i + 1),
+            (Some(i), false) => i.checked_sub(1),
+        };
+        self.update_filtered_hosts();
+        self.table_state.select((!self.filtered_hosts.is_empty()).then_some(0));
+    }
+
+    /// Table title for the range view, e.g. "10.0.2.0/24 (2/5)"
+    pub fn range_view_label(&self) -> Option<String> {
+        let index = self.range_view?;
+        let range = self.batch.get(index)?;
+        Some(format!("{} ({}/{})", range.range, index + 1, self.batch.len()))
+    }
+
+    /// Status bar progress of a batch scan: the range being scanned and how
+    /// far it got, e.g. "range 2/5 45/256"
+    pub fn batch_text(&self) -> Option<String> {
+        if !self.is_batch() || self.scan_state != ScanState::Scanning {
+            return None;
+        }
+        let index = self.batch.iter().position(|r| !r.done)?;
+        let range = &self.batch[index];
+        Some(format!(
+            "range {}/{} {}/{}",
+            index + 1,
+            self.batch.len(),
+            range.completed,
+            range.total
+        ))
+    }
+
+    /// Whether `host` is listed under the table filter and range view
+    fn shows(&self, host: &HostInfo) -> bool {
+        self.filter_mode.matches(host)
+            && self
+                .range_view
+                .is_none_or(|index| self.batch_of.get(&host.ip) == Some(&index))
+    }
+
+    fn checkpoint_progress(&self) -> Checkpoint {
+        Checkpoint {
+            scanned: self.scan_completed,
+            total: self.scan_total,
+        }
+    }
+
+    /// Checkpoint a running scan every [`CHECKPOINT_INTERVAL`] or
+    /// [`CHECKPOINT_HOSTS`] results, whichever comes first
+    fn checkpoint_if_due(&mut self) {
+        let new_results = self.scan_completed.saturating_sub(self.checkpoint_completed);
+        let due = new_results >= CHECKPOINT_HOSTS
+            || (new_results > 0 && self.last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL);
+        if self.scan_state == ScanState::Scanning && due {
+            self.checkpoint();
+        }
+    }
+
+    /// Queue the partial results for the cache writer
+    fn checkpoint(&mut self) {
+        let Some(started) = self.scan_started_at else {
+            return;
+        };
+        self.last_checkpoint = Instant::now();
+        self.checkpoint_completed = self.scan_completed;
+        let hosts = self.scan_results();
+        let progress = self.checkpoint_progress();
+        self.cache_writer.checkpoint(&self.scan_range, hosts, progress, started);
+    }
+
+    /// Append a line of ping / tracert output, dropping the oldest beyond the
+    /// configured cap. Follows new output when scrolled to the bottom;
+    /// otherwise the view stays on the same line as older ones are dropped.
+    pub fn push_overlay_line(&mut self, text: String) {
+        let at_bottom = self.overlay_scroll >= self.overlay_max_scroll();
+        if self.overlay_lines.len() >= self.config.overlay_max_lines.max(1) {
+            self.overlay_lines.pop_front();
+            self.overlay_scroll = self.overlay_scroll.saturating_sub(1);
+        }
+        self.overlay_lines.push_back(text);
+        if at_bottom {
+            self.overlay_scroll = self.overlay_max_scroll();
+        }
+    }
+
+    /// Scroll offset that shows the last page of overlay output
+    pub fn overlay_max_scroll(&self) -> usize {
+        self.overlay_lines.len().saturating_sub(self.overlay_page.max(1))
+    }
+
+    /// Pull scroll positions back into range after the frame size changed
+    pub fn clamp_scroll(&mut self) {
+        self.overlay_scroll = self.overlay_scroll.min(self.overlay_max_scroll());
+        self.help_scroll = self.help_scroll.min(self.help_max_scroll);
+    }
+
+    /// Cancel the running ping / tracert, leaving its output on screen
+    fn stop_overlay_task(&mut self) {
+        if let Some(tx) = self.overlay_cancel_tx.take() {
+            let _ = tx.try_send(());
+        }
+    }
+
+    /// Close the output view, stopping its task if it is still running. The
+    /// output is kept until the next ping / tracert so it can be reopened.
+    fn close_overlay(&mut self) {
+        self.stop_overlay_task();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Quit now, or open the confirmation prompt if work would be lost
+    fn request_quit(&mut self) -> Option<AppCommand> {
+        if self.background_work_running() {
+            self.input_mode = InputMode::ConfirmQuit;
+            None
+        } else {
+            Some(AppCommand::Quit)
+        }
+    }
+
+    /// Start a new scan, asking first if that would throw away a paused one
+    fn request_new_scan(&mut self) -> Option<AppCommand> {
+        if self.scan_state == ScanState::Paused {
+            self.input_mode = InputMode::ConfirmNewScan;
+            None
+        } else {
+            self.request_start()
+        }
+    }
+
+    /// Start scanning the range, asking first if it is larger than
+    /// `--confirm-above`
+    pub fn request_start(&mut self) -> Option<AppCommand> {
+        if self.scan_is_large() {
+            self.input_mode = InputMode::ConfirmLargeScan;
+            None
+        } else {
+            Some(AppCommand::StartScan)
+        }
+    }
+
+    fn scan_is_large(&self) -> bool {
+        match (self.range_size, self.config.confirm_scan_above) {
+            (Some(size), Some(limit)) => size > limit,
+            _ => false,
+        }
+    }
+
+    /// Worst case for scanning `addresses`, when next to none answer: every
+    /// probe waits out its timeout and retries, `concurrent_limit` at a time,
+    /// no faster than `--rate` allows
+    pub fn scan_estimate(&self, addresses: usize) -> Duration {
+        let ping = &self.config.ping;
+        let rounds = addresses.div_ceil(ping.concurrent_limit.max(1)) as u32;
+        let probing = ping.timeout * (ping.retries + 1) * rounds;
+        let throttled = self.config.rate_limit().map_or(Duration::ZERO, |limit| {
+            Duration::from_secs_f64(addresses as f64 / f64::from(limit.per_sec()))
+        });
+        probing.max(throttled)
+    }
+
+    /// Whether quitting now would throw away work in progress
+    pub fn background_work_running(&self) -> bool {
+        matches!(self.scan_state, ScanState::Scanning | ScanState::Paused)
+            || self.port_scanning
+            || self.overlay_cancel_tx.is_some()
+    }
+
+    /// An unfinished scan has results that are not cached yet
+    pub fn has_partial_results(&self) -> bool {
+        matches!(self.scan_state, ScanState::Scanning | ScanState::Paused) && !self.hosts.is_empty()
+    }
+
+    /// The selected host's port changes while its details are on screen
+    /// and they haven't been looked at and moved on from yet
+    pub fn selected_port_diff(&self) -> Option<PortDiff> {
+        let host = self.selected_host()?;
+        let shown = self.details_pane_visible() || self.details_popup_open();
+        if !shown || self.port_changes_seen.contains(&host.ip) {
+            return None;
+        }
+        host.port_diff()
+    }
+
+    /// Whether the side Details pane is on screen (Tab cycling skips it otherwise)
+    pub fn details_pane_visible(&self) -> bool {
+        self.show_details && !self.compact_layout
+    }
+
+    /// Whether the compact-layout details popup is open
+    pub fn details_popup_open(&self) -> bool {
+        self.compact_layout && self.focus == Focus::DetailsPane
+    }
+
+    /// Move the table selection for a completed key sequence
+    fn apply_motion(&mut self, motion: Motion) {
+        let Some(last) = self.filtered_hosts.len().checked_sub(1) else {
+            return;
+        };
+        self.table_scroll = None;
+        let current = self.table_state.selected().unwrap_or(0);
+        let row = match motion {
+            Motion::First => 0,
+            Motion::Last => last,
+            Motion::Row(row) => row,
+            Motion::Down(n) => current.saturating_add(n),
+            Motion::Up(n) => current.saturating_sub(n),
+        };
+        self.table_state.select(Some(row.min(last)));
+    }
+
+    /// Scroll the table viewport `delta` rows from offset `from`, leaving the
+    /// selection alone. Stops at either end; `visible` is the rows on screen.
+    pub fn scroll_table(&mut self, from: usize, delta: isize, visible: usize) {
+        let max = self.filtered_hosts.len().saturating_sub(visible);
+        self.table_scroll = Some(from.saturating_add_signed(delta).min(max));
+    }
+
+    /// Extend the drag gesture to `row`, selecting every host between the
+    /// previous pointer row and this one (fast drags skip rows)
+    pub fn drag_to(&mut self, row: usize) {
+        let Some(drag) = &mut self.drag else {
+            return;
+        };
+        let (from, to) = (drag.last_row.min(row), drag.last_row.max(row));
+        for &index in self.filtered_hosts.iter().skip(from).take(to - from + 1) {
+            let ip = self.hosts[index].ip;
+            self.selected_hosts.insert(ip);
+            drag.added.insert(ip);
+        }
+        drag.last_row = row;
+    }
+
+    pub fn select_next(&mut self) {
+        if self.filtered_hosts.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) => (i + 1).min(self.filtered_hosts.len() - 1),
+            None => 0,
+        };
+        self.table_state.select(Some(i));
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.filtered_hosts.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) => i.saturating_sub(1),
+            None => 0,
+        };
+        self.table_state.select(Some(i));
+    }
+
+    pub fn update_filtered_hosts(&mut self) {
+        self.filtered_hosts = self
+            .hosts
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| self.shows(h))
+            .map(|(i, _)| i)
+            .collect();
+
+        // Adjust selection if needed
+        if let Some(selected) = self.table_state.selected() {
+            if selected >= self.filtered_hosts.len() {
+                if self.filtered_hosts.is_empty() {
+                    self.table_state.select(None);
+                } else {
+                    self.table_state.select(Some(self.filtered_hosts.len() - 1));
+                }
+            }
+        }
+    }
+
+    pub fn selected_host(&self) -> Option<&HostInfo> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.filtered_hosts.get(i))
+            .map(|&i| &self.hosts[i])
+    }
+
+    #[allow(dead_code)]
+    pub fn selected_host_mut(&mut self) -> Option<&mut HostInfo> {
+        let idx = self
+            .table_state
+            .selected()
+            .and_then(|i| self.filtered_hosts.get(i).copied())?;
+        self.hosts.get_mut(idx)
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.scan_total == 0 {
+            0.0
+        } else {
+            self.scan_completed as f64 / self.scan_total as f64
+        }
+    }
+
+    /// Current spinner frame, as of the last animation tick
+    fn spinner(&self) -> &'static str {
+        SPINNER_FRAMES[self.spinner_frame]
+    }
+
+    /// Short state string for the bottom status bar (must stay compact)
+    pub fn status_text(&self) -> String {
+        if self.adapters_loading {
+            return format!("{} Loading", self.spinner());
+        }
+
+        match self.scan_state {
+            ScanState::Idle => {
+                if self.hosts.iter().any(|h| h.cached_at.is_some()) {
+                    "Cached".to_string()
+                } else {
+                    "Ready".to_string()
+                }
+            }
+            ScanState::Scanning => {
+                format!("{} {}/{}", self.spinner(), self.scan_completed, self.scan_total)
+            }
+            ScanState::Paused => "Paused".to_string(),
+            ScanState::Completed => "Done".to_string(),
+        }
+    }
+
+    /// Take a connectivity check result. Only a target going down or coming
+    /// back is logged, not every failed round.
+    pub fn apply_connectivity(&mut self, next: Connectivity) {
+        let previous = std::mem::replace(&mut self.connectivity, next);
+        let gateway = next
+            .gateway_ip
+            .map_or_else(|| "Gateway".to_string(), |ip| format!("Gateway {}", ip));
+        let internet = format!("Internet ({})", self.config.internet_anchor);
+        for (name, was, now) in [
+            (gateway, previous.gateway, next.gateway),
+            (internet, previous.internet, next.internet),
+        ] {
+            match (was, now) {
+                (Link::Down, Link::Down) => {}
+                (_, Link::Down) => self.log(Severity::Error, format!("{} not answering", name)),
+                (Link::Down, Link::Up(_)) => {
+                    self.log(Severity::Success, format!("{} answering again", name))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Ring the bell / raise a desktop notification for a finished scan
+    fn alert_scan_complete(&mut self) {
+        let body = format!("{}: {}", self.range_input, self.completion_summary());
+        self.alerter.alert("ipscannr scan complete", &body);
+    }
+
+    /// Full summary shown in the header Status box after a scan completes
+    pub fn completion_summary(&self) -> String {
+        let results = self.scan_results();
+        let total = results.len() + self.offline_dropped;
+        match online_counts(&results) {
+            (online, 0) => format!("{} hosts ({} online)", total, online),
+            (online, tcp_only) => format!("{} hosts ({} online, {} TCP-only)", total, online, tcp_only),
+        }
+    }
+
+    /// "Offline: 240 timeout, 14 network unreachable" when some hosts failed
+    /// for more than a timeout: errors that point at this end, not the hosts
+    fn offline_causes_note(&self) -> Option<String> {
+        let causes = summary::summarize(&self.scan_results(), self.offline_dropped)
+            .count_offline_causes(&self.dropped_causes)
+            .offline_causes;
+        causes
+            .iter()
+            .any(|c| c.0 != ProbeError::Timeout)
+            .then(|| format!("Offline: {}", summary::causes_text(&causes)))
+    }
+
+    /// Remember that `ip` went up or down, for the status bar ticker
+    fn record_change(&mut self, ip: Ipv4Addr, up: bool, at: u64) {
+        self.queue_webhook(if up { "up" } else { "down" }, ip, at);
+        self.recent_changes.retain(|c| c.ip != ip);
+        self.recent_changes.push_front(StateChange { ip, up, at });
+        self.recent_changes.truncate(RECENT_CHANGES);
+        self.change_jumps = 0;
+    }
+
+    /// Compile the settings file's highlight rules; each that doesn't
+    /// compile is reported and left out
+    pub fn apply_highlight_rules(&mut self) {
+        let (highlighter, errors) = Highlighter::new(&self.settings.highlight_rules);
+        self.highlighter = highlighter;
+        for error in errors {
+            self.notify(Severity::Error, format!("Settings: {}", error));
+        }
+    }
+
+    /// Post the settings file's webhook, if any, with the deliveries
+    /// reported on `results`; a bad URL is logged and leaves it off
+    pub fn connect_webhook(&mut self, results: mpsc::Sender<Delivery>) {
+        let Some(config) = self.settings.webhook.as_ref().filter(|c| !c.url.trim().is_empty()) else {
+            return;
+        };
+        match webhook::Client::new(config, results) {
+            Ok(client) => {
+                let message = format!("Webhook: posting host changes to {}", client.target());
+                self.log(Severity::Info, message);
+                self.webhook = Some(client);
+            }
+            Err(e) => self.log(Severity::Error, format!("Webhook off: {}", e)),
+        }
+    }
+
+    fn queue_webhook(&mut self, event: &'static str, ip: Ipv4Addr, at: u64) {
+        if self.webhook.is_some() {
+            self.webhook_queue.push((event, ip, at));
+        }
+    }
+
+    /// Post the queued changes, named after what the lookups found (or the
+    /// cached scan, for offline hosts a large scan didn't keep)
+    fn send_webhooks(&mut self) {
+        let Some(client) = &self.webhook else {
+            return;
+        };
+        let mut dropped = Vec::new();
+        for (event, ip, at) in self.webhook_queue.drain(..) {
+            let host = self
+                .hosts
+                .iter()
+                .find(|h| h.ip == ip)
+                .or_else(|| self.previous_scan.iter().find(|h| h.ip == ip));
+            let sent = client.send(webhook::Event {
+                event,
+                ip: ip.to_string(),
+                hostname: host.and_then(|h| h.hostname.clone()),
+                mac: host.and_then(|h| Some(h.mac.as_ref()?.address.clone())),
+                timestamp: at,
+            });
+            dropped.extend(sent.err());
+        }
+        for event in dropped {
+            let message = format!("Webhook: {} {} dropped, too many waiting", event.ip, event.event);
+            self.log(Severity::Error, message);
+        }
+    }
+
+    /// Log how a webhook post went
+    pub fn webhook_delivered(&mut self, delivery: Delivery) {
+        let what = format!("{} {}", delivery.event.ip, delivery.event.event);
+        match delivery.result {
+            Ok(status) => self.log(
+                Severity::Success,
+                format!("Webhook: {} delivered (HTTP {})", what, status),
+            ),
+            Err(e) => self.log(
+                Severity::Error,
+                format!("Webhook: {} failed after {} attempt(s): {}", what, delivery.attempts, e),
+            ),
+        }
+    }
+
+    /// Changes for the status bar ticker as of `now` (Unix seconds): those
+    /// from the last hour, rotated so each leads in turn. Empty when there
+    /// are none, and the status bar shows its usual hint.
+    pub fn ticker(&self, now: u64) -> Vec<StateChange> {
+        let recent = self.recent_change_count(now);
+        if recent == 0 {
+            return Vec::new();
+        }
+        self.recent_changes
+            .iter()
+            .take(recent)
+            .cycle()
+            .skip(self.ticker_step % recent)
+            .take(recent.min(TICKER_SHOWN))
+            .copied()
+            .collect()
+    }
+
+    /// How many of the newest changes are recent enough for the ticker
+    fn recent_change_count(&self, now: u64) -> usize {
+        self.recent_changes
+            .iter()
+            .take_while(|c| now.saturating_sub(c.at) <= TICKER_MAX_AGE)
+            .count()
+    }
+
+    /// Select `ip` in the hosts table, showing every host again if the
+    /// filter or range view hides it
+    pub fn select_ip(&mut self, ip: Ipv4Addr) {
+        let Some(index) = self.hosts.iter().position(|h| h.ip == ip) else {
+            self.notify(Severity::Info, format!("{} isn't listed", ip));
+            return;
+        };
+        if !self.shows(&self.hosts[index]) {
+            self.filter_mode = FilterMode::All;
+            self.range_view = None;
+            self.update_filtered_hosts();
+        }
+        let row = self.filtered_hosts.iter().position(|&i| i == index);
+        self.table_state.select(row);
+        self.table_scroll = None;
+        self.focus = Focus::HostsTable;
+    }
+
+    /// [H]: select the host of the newest change, then older ones on each
+    /// press, wrapping around
+    fn jump_to_next_change(&mut self) {
+        let recent = self.recent_change_count(crate::cache::now_secs());
+        if recent == 0 {
+            self.notify(Severity::Info, "No hosts went up or down recently");
+            return;
+        }
+        let change = self.recent_changes[self.change_jumps % recent];
+        self.change_jumps += 1;
+        self.select_ip(change.ip);
+    }
+
+    /// Listed hosts that belong to the current scan, without those kept
+    /// from earlier scans of other addresses
+    fn scan_results(&self) -> Vec<HostInfo> {
+        self.hosts
+            .iter()
+            .filter(|h| !self.kept_from_earlier.contains(&h.ip))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn start_scan(&mut self) -> Result<mpsc::Receiver<ScanEvent>> {
+        let groups = self.plan_batch(&self.range_input.clone())?;
+        self.check_source_ip()?;
+        let addresses: Vec<Ipv4Addr> = groups.iter().flatten().copied().collect();
+        self.input_error = None;
+        self.range_warning = self.non_local_warning(&addresses);
+        if let Some(warning) = &self.range_warning {
+            self.event_log.push(Severity::Info, warning.clone());
+        }
+        self.scan_adapters = self.adapters.clone();
+
+        // Carry last_seen over from the results on screen and the range's
+        // cache; the cache is also the snapshot MAC changes are measured against
+        self.cache_writer.flush();
+        let cached = crate::cache::load_cache(&self.range_input);
+        self.seen_before.clear();
+        crate::cache::merge_last_seen(&mut self.seen_before, &self.hosts);
+        crate::cache::merge_last_seen(&mut self.seen_before, &cached);
+        // Up/down history comes from the range's cache, and from the results
+        // on screen only when they are of the same range
+        self.state_before = state_history(&cached).collect();
+        if self.scan_range == self.range_input {
+            self.state_before.extend(state_history(&self.hosts));
+        }
+        self.cached_online = cached.iter().filter(|h| h.is_alive).map(|h| h.ip).collect();
+        self.previous_scan = cached;
+        self.conflicts.clear();
+        self.conflicted_ips.clear();
+        self.mac_groups.clear();
+
+        self.clear_rescanned_hosts();
+        self.scan_total = addresses.len();
+        self.scan_completed = 0;
+        self.offline_dropped = 0;
+        self.dropped_causes.clear();
+        self.enrichment_pending = 0;
+        self.scan_range = self.range_input.clone();
+        self.scanned_ips.clear();
+        self.scan_started_at = Some(crate::cache::now_secs());
+        self.last_checkpoint = Instant::now();
+        self.checkpoint_completed = 0;
+        self.scan_state = ScanState::Scanning;
+        self.log(
+            Severity::Info,
+            format!("Scan started: {} ({} addresses)", self.range_input, addresses.len()),
+        );
+        tracing::info!(
+            range = %self.range_input,
+            addresses = addresses.len(),
+            ranges = self.batch.len(),
+            refused_means_alive = self.config.ping.refused_means_alive,
+            timeout_ms = self.config.ping.timeout.as_millis() as u64,
+            "scan started"
+        );
+        if self.is_batch() {
+            self.log(
+                Severity::Info,
+                format!(
+                    "Batch of {} ranges, scanned one after another; [ and ] switch the table between them",
+                    self.batch.len()
+                ),
+            );
+        }
+        if self.drops_offline_hosts() {
+            self.log(
+                Severity::Info,
+                format!(
+                    "More than {} addresses: offline hosts are counted but not listed",
+                    self.config.offline_retain_limit
+                ),
+            );
+        }
+        // Move focus to hosts table when scan starts
+        self.focus = Focus::HostsTable;
+
+        Ok(self.spawn_scan(groups.into_iter().enumerate().collect()))
+    }
+
+    /// Unlist the hosts at addresses the planned scan probes again, keeping
+    /// them aside so their enrichment carries over to the new results. Hosts
+    /// of other ranges stay listed, and selected.
+    fn clear_rescanned_hosts(&mut self) {
+        let (rescanned, kept): (Vec<HostInfo>, Vec<HostInfo>) = std::mem::take(&mut self.hosts)
+            .into_iter()
+            .partition(|h| self.batch_of.contains_key(&h.ip));
+        self.hosts = kept;
+        self.kept_from_earlier = self.hosts.iter().map(|h| h.ip).collect();
+        self.rescanned_before = rescanned.into_iter().map(|h| (h.ip, h)).collect();
+        self.selected_hosts.retain(|ip| !self.batch_of.contains_key(ip));
+        self.table_state.select(None);
+        self.update_filtered_hosts();
+    }
+
+    /// Probe each batch range's addresses in the background, one range after
+    /// another, streaming results as scan events
+    fn spawn_scan(&mut self, groups: BatchGroups) -> mpsc::Receiver<ScanEvent> {
+        let (event_tx, event_rx) = mpsc::channel(SCAN_EVENT_CAPACITY);
+        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        self.scan_cancel_tx = Some(cancel_tx);
+
+        let mut config = self.config.clone();
+        config.ping.source_ip = self.probe_source(groups.iter().flat_map(|(_, g)| g.iter().copied()));
+        let adapters = self.scan_adapters.clone();
+        let probes = self.probes.clone();
+
+        let enrich_limit = Arc::new(Semaphore::new(ENRICH_CONCURRENCY));
+
+        tokio::spawn(async move {
+            for (index, addresses) in groups {
+                let (ping_tx, mut ping_rx) = mpsc::channel(SCAN_EVENT_CAPACITY);
+
+                // Start ping scan
+                let pinger = probes
+                    .ping
+                    .clone()
+                    .unwrap_or_else(|| Arc::new(Pinger::new(config.ping.clone())));
+                let workers = config.ping.concurrent_limit;
+                tokio::spawn(async move {
+                    let _ = probe_hosts(addresses, pinger, workers, ping_tx).await;
+                });
+
+                // Process results
+                loop {
+                    tokio::select! {
+                        _ = cancel_rx.recv() => {
+                            return;
+                        }
+                        result = ping_rx.recv() => {
+                            let Some(ping_result) = result else {
                                 break;
+                            };
+                            let host: HostInfo = ping_result.into();
+                            let ip = host.ip;
+                            // ARP can't see past a router: no MAC lookup
+                            // for remote addresses
+                            let local = is_local(&adapters, ip);
+                            let enrich = config.enriches(&host, local);
+                            let _ = event_tx.send(ScanEvent::HostDiscovered(Box::new(host))).await;
+
+                            // Hostname and MAC lookups run in the background so
+                            // a slow reverse DNS doesn't hold up the next reply
+                            if enrich {
+                                let event_tx = event_tx.clone();
+                                let probes = probes.clone();
+                                let enrich_limit = Arc::clone(&enrich_limit);
+                                let (resolve, netbios, detect_mac, prewarm) = (
+                                    config.resolve_hostnames,
+                                    config.queries_netbios(),
+                                    config.detect_mac && local,
+                                    config.prewarms(),
+                                );
+                                tokio::spawn(async move {
+                                    let _permit = enrich_limit.acquire().await;
+                                    // Both name lookups wait on the network:
+                                    // side by side, the slower one sets the pace
+                                    let (dns, netbios) = tokio::join!(
+                                        async {
+                                            if resolve {
+                                                probes.names.resolve(ip).await
+                                            } else {
+                                                Resolution::Disabled
+                                            }
+                                        },
+                                        async {
+                                            if netbios {
+                                                probes.netbios.name(ip).await
+                                            } else {
+                                                None
+                                            }
+                                        }
+                                    );
+                                    let mac = if detect_mac {
+                                        probes.mac.lookup(ip, prewarm).await
+                                    } else {
+                                        None
+                                    };
+                                    let _ = event_tx
+                                        .send(ScanEvent::HostUpdated { ip, dns, netbios, mac })
+                                        .await;
+                                });
                             }
                         }
                     }
                 }
+                let _ = event_tx.send(ScanEvent::RangeComplete(index)).await;
+            }
+            let _ = event_tx.send(ScanEvent::ScanComplete).await;
+        });
+
+        event_rx
+    }
+
+    /// Warning for a range with no address on a local subnet: a typo'd range
+    /// otherwise just comes back all offline
+    fn non_local_warning(&self, addresses: &[Ipv4Addr]) -> Option<String> {
+        if addresses.iter().any(|&ip| is_local(&self.adapters, ip)) {
+            return None;
+        }
+        Some(format!(
+            "Range {} is not on any local subnet: results may be limited (no MAC/ARP)",
+            self.range_input.trim()
+        ))
+    }
+
+    /// Fail the scan start when `--source-ip` isn't an address of this
+    /// machine, instead of every probe failing
+    fn check_source_ip(&self) -> Result<()> {
+        match self.config.ping.source_ip {
+            Some(source) => check_source_ip(source).context("--source-ip"),
+            None => Ok(()),
+        }
+    }
+
+    /// Address probes to `addresses` are sent from: `--source-ip`, else the
+    /// chosen adapter's when they are all on its subnet, so a multi-homed
+    /// machine probes the range from the interface it was picked on
+    pub fn probe_source(&self, mut addresses: impl Iterator<Item = Ipv4Addr>) -> Option<Ipv4Addr> {
+        if let Some(source) = self.config.ping.source_ip {
+            return Some(source);
+        }
+        let adapter = self.current_adapter()?;
+        addresses.all(|ip| adapter.contains(ip)).then_some(adapter.ip)
+    }
+
+    /// Whether this scan is large enough that offline hosts aren't kept
+    fn drops_offline_hosts(&self) -> bool {
+        self.scan_total > self.config.offline_retain_limit
+    }
+
+    /// Handle `first` and every scan event already queued behind it, so a
+    /// burst of replies costs one redraw rather than one per host. Returns
+    /// false once the scan task has closed the channel.
+    pub fn handle_scan_events(
+        &mut self,
+        first: ScanEvent,
+        rx: &mut mpsc::Receiver<ScanEvent>,
+    ) -> bool {
+        self.handle_scan_event(first);
+        let mut open = true;
+        // Bounded so a never-ending stream still lets input through
+        for _ in 0..SCAN_EVENT_CAPACITY {
+            match rx.try_recv() {
+                Ok(event) => self.handle_scan_event(event),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+        self.checkpoint_if_due();
+        open
+    }
+
+    pub fn handle_scan_event(&mut self, event: ScanEvent) {
+        match event {
+            // Stale hosts are kept even then: they are the offline ones that matter
+            ScanEvent::HostDiscovered(host)
+                if !host.is_alive
+                    && self.drops_offline_hosts()
+                    && !self.cached_online.contains(&host.ip) =>
+            {
+                if self.state_before.get(&host.ip).is_some_and(|&(was_up, _)| was_up) {
+                    self.record_change(host.ip, false, crate::cache::now_secs());
+                }
+                self.count_in_batch(host.ip, false);
+                self.scanned_ips.insert(host.ip);
+                self.offline_dropped += 1;
+                if let Some(error) = host.probe_error {
+                    *self.dropped_causes.entry(error).or_default() += 1;
+                }
+                self.scan_completed += 1;
+            }
+            ScanEvent::HostDiscovered(host) => {
+                let mut host = *host;
+                if let Some(before) = self.rescanned_before.remove(&host.ip) {
+                    host.carry_over(before, &self.config.name_precedence);
+                }
+                if let Some(announcement) = self.announced.get(&host.ip) {
+                    host.add_announcement(announcement, &self.config.name_precedence);
+                }
+                host.apply_alias(&self.config.aliases, &self.config.name_precedence);
+                host.stale = !host.is_alive && self.cached_online.contains(&host.ip);
+                let now = crate::cache::now_secs();
+                host.last_seen = if host.is_alive {
+                    Some(now)
+                } else {
+                    self.seen_before.get(&host.ip).copied()
+                };
+                // A host seen before in the same state keeps its start time;
+                // one that was never up has been down since it was last seen
+                host.state_since = Some(match self.state_before.get(&host.ip) {
+                    Some(&(was_up, since)) if was_up == host.is_alive => since,
+                    Some(_) => {
+                        self.record_change(host.ip, host.is_alive, now);
+                        now
+                    }
+                    // New to a range scanned before
+                    None if host.is_alive => {
+                        if !self.state_before.is_empty() {
+                            self.queue_webhook("new", host.ip, now);
+                        }
+                        now
+                    }
+                    None => host.last_seen.unwrap_or(now),
+                });
+                if self.config.enriches(&host, is_local(&self.scan_adapters, host.ip)) {
+                    self.enrichment_pending += 1;
+                }
+                self.count_in_batch(host.ip, host.is_alive);
+                self.scanned_ips.insert(host.ip);
+                self.scan_completed += 1;
+                // A device that discovery listed first is replaced in place
+                let listed = self
+                    .announced
+                    .contains_key(&host.ip)
+                    .then(|| self.hosts.iter().position(|h| h.ip == host.ip))
+                    .flatten();
+                if let Some(index) = listed {
+                    self.hosts[index] = host;
+                    self.update_filtered_hosts();
+                } else {
+                    // Append to the filtered list instead of rebuilding it: a
+                    // /16 delivers 65k of these
+                    if self.shows(&host) {
+                        self.filtered_hosts.push(self.hosts.len());
+                    }
+                    self.hosts.push(host);
+                }
+
+                // Auto-select first host
+                if self.table_state.selected().is_none() && !self.filtered_hosts.is_empty() {
+                    self.table_state.select(Some(0));
+                }
+            }
+            ScanEvent::HostUpdated { ip, dns, netbios, mac } => {
+                self.enrichment_pending = self.enrichment_pending.saturating_sub(1);
+                self.log_resolution(ip, &dns);
+                // Recent discoveries are at the end until the scan sorts them
+                if let Some(host) = self.hosts.iter_mut().rev().find(|h| h.ip == ip) {
+                    if let Some(name) = netbios {
+                        host.names.set(NameSource::Netbios, &name);
+                    }
+                    host.set_resolution(dns, &self.config.name_precedence);
+                    if mac.is_some() {
+                        host.mac = mac;
+                    }
+                }
+                if self.enrichment_pending == 0 && self.scan_state == ScanState::Completed {
+                    self.log(Severity::Success, "Hostname and MAC lookups finished");
+                    self.finish_results();
+                }
+            }
+            ScanEvent::RangeComplete(index) => self.finish_batch_range(index),
+            ScanEvent::ScanComplete => {
+                if self.scan_state != ScanState::Paused {
+                    self.scan_state = ScanState::Completed;
+                    self.scan_finished_at = Some(crate::cache::now_secs());
+                    let summary = self.completion_summary();
+                    tracing::info!(%summary, offline_dropped = self.offline_dropped, "scan complete");
+                    if self.enrichment_pending > 0 {
+                        self.log(
+                            Severity::Info,
+                            format!(
+                                "Scan complete: {}; still resolving {} host(s)",
+                                summary, self.enrichment_pending
+                            ),
+                        );
+                    } else {
+                        self.log(Severity::Success, format!("Scan complete: {}", summary));
+                    }
+                    if let Some(note) = self.offline_causes_note() {
+                        self.log(Severity::Info, note);
+                    }
+                    let stale = self.hosts.iter().filter(|h| h.stale).count();
+                    if stale > 0 {
+                        self.log(
+                            Severity::Info,
+                            format!(
+                                "{} host(s) online in the cached scan didn't answer; F filters to them",
+                                stale
+                            ),
+                        );
+                    }
+                    self.alert_scan_complete();
+                }
+                self.scan_cancel_tx = None;
+
+                self.hosts.sort_by(table_order);
+                self.update_filtered_hosts();
+
+                // Otherwise the last lookup finishes the results
+                if self.enrichment_pending == 0 {
+                    self.finish_results();
+                }
+            }
+        }
+    }
+
+    /// One range of a batch has every result: report it and cache its hosts
+    /// under its own range (again once the batch's lookups are done)
+    fn finish_batch_range(&mut self, index: usize) {
+        if !self.is_batch() {
+            return;
+        }
+        let Some(range) = self.batch.get_mut(index) else {
+            return;
+        };
+        range.done = true;
+        let (text, total, online) = (range.range.clone(), range.total, range.online);
+        self.log(
+            Severity::Success,
+            format!(
+                "Range {}/{} done: {} ({} addresses, {} online)",
+                index + 1,
+                self.batch.len(),
+                text,
+                total,
+                online
+            ),
+        );
+        let hosts = self.batch_hosts(index);
+        self.cache_writer.save(&text, hosts);
+    }
+
+    /// Render the metrics of the results once: for `/metrics`, and into the
+    /// `--metrics-file` textfile, written off the UI thread. A failed write
+    /// is reported on `metrics_failures`.
+    fn export_metrics(&mut self) {
+        if self.status_feed.is_none() && self.config.metrics_file.is_none() {
+            return;
+        }
+        self.metrics = crate::metrics::render(&self.hosts).into();
+        if let Some(path) = self.config.metrics_file.clone() {
+            let text = Arc::clone(&self.metrics);
+            let failures = self.metrics_failures.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = crate::metrics::write_textfile(&path, &text) {
+                    let message = format!("Metrics not written to {}: {}", path.display(), e);
+                    if let Some(failures) = failures {
+                        let _ = failures.blocking_send(message);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Once a scan and its lookups are done: cache the results and check them
+    /// for conflicts, which need every MAC to be known
+    fn finish_results(&mut self) {
+        if self.scan_state == ScanState::Completed {
+            // Persist results so they're available at next startup
+            let results = self.scan_results();
+            self.cache_writer.save(&self.scan_range, results);
+            if self.is_batch() {
+                for index in 0..self.batch.len() {
+                    let range = self.batch[index].range.clone();
+                    let hosts = self.batch_hosts(index);
+                    self.cache_writer.save(&range, hosts);
+                }
+            }
+            self.cache_writer.flush();
+            self.export_metrics();
+            if self.hosts.iter().any(|h| h.is_alive) {
+                self.notify(Severity::Info, "Press U for a scan summary");
+            }
+        }
+        self.send_webhooks();
+        self.update_conflicts();
+        if !self.conflicts.is_empty() {
+            self.notify(
+                Severity::Error,
+                format!(
+                    "{} possible IP conflict(s): press I to review",
+                    self.conflicts.len()
+                ),
+            );
+        }
+    }
+
+    /// Browse for SSDP / mDNS devices on every adapter for the configured
+    /// window; answers arrive on the returned channel, which closes when the
+    /// window ends or [B] discovery is stopped. None in safe mode, which
+    /// sends no UDP.
+    pub fn start_discovery(&mut self) -> Option<mpsc::Receiver<Announcement>> {
+        if self.refused_in_safe_mode("SSDP / mDNS discovery") {
+            return None;
+        }
+        self.stop_discovery();
+        let (tx, rx) = mpsc::channel(64);
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        self.discovery_cancel_tx = Some(cancel_tx);
+        self.discovering = true;
+        self.announced.clear();
+        let interfaces: Vec<Ipv4Addr> = self.adapters.iter().map(|a| a.ip).collect();
+        self.log(
+            Severity::Info,
+            format!(
+                "Discovery started: SSDP and mDNS on {} adapter(s) for {}s",
+                interfaces.len().max(1),
+                self.config.discovery_window.as_secs()
+            ),
+        );
+        tokio::spawn(discover(interfaces, self.config.discovery_window, tx, cancel_rx));
+        Some(rx)
+    }
+
+    /// In safe mode, say that `what` is off there and return true
+    fn refused_in_safe_mode(&mut self, what: &str) -> bool {
+        if self.config.safe_mode.is_none() {
+            return false;
+        }
+        self.notify(Severity::Error, format!("Safe mode: {} is off", what));
+        true
+    }
+
+    /// The ports a port scan covers and how the results label them: the
+    /// common ports, or only the allowed ones in safe mode
+    fn port_scan_ports(&self) -> (Vec<u16>, &'static str) {
+        match &self.config.safe_mode {
+            Some(allowed) => (allowed.clone(), "allowed"),
+            None => (COMMON_PORTS.to_vec(), COMMON_PORTS_SPEC),
+        }
+    }
+
+    fn stop_discovery(&mut self) {
+        if let Some(tx) = self.discovery_cancel_tx.take() {
+            let _ = tx.try_send(());
+        }
+    }
+
+    /// Merge a discovery answer into the host with its address, or list the
+    /// device as a new online host
+    pub fn apply_announcement(&mut self, announcement: Announcement) {
+        let ip = announcement.ip;
+        let known = self.announced.entry(ip).or_insert_with(|| Announcement {
+            ip,
+            name: None,
+            services: Vec::new(),
+        });
+        known.merge(&announcement);
+        let known = known.clone();
+
+        if let Some(host) = self.hosts.iter_mut().find(|h| h.ip == ip) {
+            host.add_announcement(&announcement, &self.config.name_precedence);
+            return;
+        }
+        let selected_ip = self.selected_host().map(|h| h.ip);
+        let mut host = HostInfo::from(&known);
+        host.apply_alias(&self.config.aliases, &self.config.name_precedence);
+        self.hosts.push(host);
+        // A running scan sorts everything once it completes
+        if self.scan_state != ScanState::Scanning {
+            self.hosts.sort_by(table_order);
+        }
+        self.update_filtered_hosts();
+        let selected = selected_ip
+            .and_then(|ip| self.filtered_hosts.iter().position(|&i| self.hosts[i].ip == ip));
+        match selected {
+            Some(row) => self.table_state.select(Some(row)),
+            None if !self.filtered_hosts.is_empty() => self.table_state.select(Some(0)),
+            None => {}
+        }
+    }
+
+    /// The discovery window ended or was cancelled
+    pub fn discovery_finished(&mut self) {
+        self.discovery_cancel_tx = None;
+        self.discovering = false;
+        self.notify(
+            Severity::Success,
+            format!("Discovery finished: {} device(s) announced", self.announced.len()),
+        );
+    }
+
+    /// "discovering…" while SSDP / mDNS discovery is listening
+    pub fn discovery_text(&self) -> Option<String> {
+        let ellipsis = if self.compat { "..." } else { "…" };
+        self.discovering.then(|| format!("discovering{}", ellipsis))
+    }
+
+    /// "resolving N…" while hostname / MAC lookups are still outstanding
+    pub fn enrichment_text(&self) -> Option<String> {
+        let ellipsis = if self.compat { "..." } else { "…" };
+        (self.enrichment_pending > 0)
+            .then(|| format!("resolving {}{}", self.enrichment_pending, ellipsis))
+    }
+
+    /// "throttled 50/s" while `--rate` is holding probes back
+    pub fn rate_text(&self) -> Option<String> {
+        let limit = self.config.rate_limit()?;
+        limit.is_throttled().then(|| format!("throttled {}/s", limit.per_sec()))
+    }
+
+    /// Start a background port scan of the multi-selection, or of the
+    /// selected host without one; offline hosts are skipped. Cancels any
+    /// in-progress port scan first. The receiver yields each host's progress
+    /// and result, and closes once every host is done.
+    pub fn start_port_scan_for_selected(&mut self) -> Option<mpsc::Receiver<PortScanEvent>> {
+        let (ports, _) = self.port_scan_ports();
+        if ports.is_empty() {
+            self.notify(Severity::Error, "Safe mode: port scans are off (no TCP ports allowed)");
+            return None;
+        }
+
+        // Cancel any in-progress scan
+        if let Some(tx) = self.port_scan_cancel_tx.take() {
+            let _ = tx.try_send(());
+        }
+        self.port_progress.clear();
+
+        let targets: Vec<&HostInfo> = if self.selected_hosts.is_empty() {
+            self.selected_host().into_iter().collect()
+        } else {
+            self.hosts
+                .iter()
+                .filter(|h| self.selected_hosts.contains(&h.ip))
+                .collect()
+        };
+        let prewarm = self.config.prewarms();
+        let jobs: Vec<(Ipv4Addr, bool, bool)> = targets
+            .into_iter()
+            .filter(|h| h.is_alive)
+            .map(|host| {
+                // Only a lookup that timed out, or one never made (a forgotten
+                // or cached host), is worth repeating
+                let retry_dns = self.config.resolve_hostnames
+                    && (host.dns_timed_out() || (host.dns.is_none() && host.hostname.is_none()));
+                let find_mac = self.config.detect_mac
+                    && host.mac.is_none()
+                    && is_local(&self.adapters, host.ip);
+                (host.ip, retry_dns, find_mac)
+            })
+            .collect();
+        if jobs.is_empty() {
+            self.port_scanning = false;
+            return None;
+        }
+        for (ip, ..) in &jobs {
+            self.port_progress.insert(*ip, (0, ports.len()));
+        }
+        if self.config.safe_mode.is_some() {
+            // The common-port scan reaches past the allow-list
+            let list: Vec<String> = ports.iter().map(|p| p.to_string()).collect();
+            self.notify(
+                Severity::Info,
+                format!("Safe mode: common-port scan refused; checking only {}", list.join(", ")),
+            );
+        }
+
+        // One scanner for every host, so its concurrency limit is shared
+        let mut port_scan = self.config.port_scan.clone();
+        port_scan.source_ip = self.probe_source(jobs.iter().map(|job| job.0));
+        let scanner = Arc::new(PortScanner::new(port_scan));
+        let probes = self.probes.clone();
+        let ports: Arc<[u16]> = ports.into();
+
+        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        let (event_tx, event_rx) = mpsc::channel::<PortScanEvent>(SCAN_EVENT_CAPACITY);
+
+        self.port_scan_cancel_tx = Some(cancel_tx);
+        self.port_scanning = true;
+
+        tokio::spawn(async move {
+            let mut hosts = tokio::task::JoinSet::new();
+            for (ip, retry_dns, find_mac) in jobs {
+                let (scanner, probes, event_tx, ports) =
+                    (Arc::clone(&scanner), probes.clone(), event_tx.clone(), Arc::clone(&ports));
+                hosts.spawn(async move {
+                    let dns = async {
+                        if retry_dns {
+                            Some(probes.names.refresh(ip).await)
+                        } else {
+                            None
+                        }
+                    };
+                    let mac = async {
+                        if find_mac {
+                            probes.mac.lookup(ip, prewarm).await
+                        } else {
+                            None
+                        }
+                    };
+                    // Progress is cumulative: one dropped on a full channel
+                    // is made up by the next
+                    let progress_tx = event_tx.clone();
+                    let ports = scanner.scan_ports_reporting(ip, &ports, move |progress| {
+                        let _ = progress_tx.try_send(PortScanEvent::Progress(progress));
+                    });
+                    let (results, dns, mac) = tokio::join!(ports, dns, mac);
+                    let open_ports: Vec<u16> = results
+                        .into_iter()
+                        .filter(|r| r.is_open())
+                        .map(|r| r.port)
+                        .collect();
+                    let rescan = HostRescan { ip, open_ports, dns, mac };
+                    let _ = event_tx.send(PortScanEvent::Finished(rescan)).await;
+                });
+            }
+            drop(event_tx);
+            loop {
+                tokio::select! {
+                    _ = cancel_rx.recv() => {
+                        hosts.abort_all();
+                        return;
+                    }
+                    joined = hosts.join_next() => {
+                        if joined.is_none() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Some(event_rx)
+    }
+
+    /// Take a progress report or result of a background port scan
+    pub fn apply_port_scan_event(&mut self, event: PortScanEvent) {
+        match event {
+            PortScanEvent::Progress(progress) => {
+                if let Some(entry) = self.port_progress.get_mut(&progress.ip) {
+                    *entry = (progress.scanned, progress.total);
+                }
+            }
+            PortScanEvent::Finished(rescan) => {
+                self.port_progress.remove(&rescan.ip);
+                self.apply_host_rescan(rescan);
+            }
+        }
+    }
+
+    /// The background port scan's channel closed: every host is done
+    pub fn port_scan_finished(&mut self) {
+        self.port_scanning = false;
+        self.port_scan_cancel_tx = None;
+        self.port_progress.clear();
+    }
+
+    /// Whether the selected host's ports are being scanned right now
+    pub fn selected_host_port_scanning(&self) -> bool {
+        self.selected_host()
+            .is_some_and(|h| self.port_progress.contains_key(&h.ip))
+    }
+
+    /// Drop the selected host's hostname, MAC, open ports and services, on
+    /// screen and in the cache, keeping its address and liveness. Returns
+    /// whether the host is online, i.e. worth rescanning.
+    fn forget_selected_host(&mut self) -> bool {
+        let Some(host) = self
+            .selected_host()
+            .map(|h| h.ip)
+            .and_then(|ip| self.hosts.iter_mut().find(|h| h.ip == ip))
+        else {
+            return false;
+        };
+        host.hostname = None;
+        host.names = Names::default();
+        host.mac = None;
+        host.dns = None;
+        host.open_ports.clear();
+        host.ports_scanned = false;
+        host.ports_scanned_at = None;
+        host.ports_spec = None;
+        host.previous_ports = None;
+        host.services.clear();
+        let (ip, alive) = (host.ip, host.is_alive);
+        // Saves still queued hold the details being forgotten
+        self.cache_writer.flush();
+        crate::cache::forget_host(ip);
+        if self.conflicted_ips.contains(&ip) || self.mac_group(ip).is_some() {
+            self.update_conflicts();
+        }
+        self.update_filtered_hosts();
+        self.notify(Severity::Success, format!("Forgot cached details of {}", ip));
+        alive
+    }
+
+    /// Take the result of a single-host rescan
+    pub fn apply_host_rescan(&mut self, rescan: HostRescan) {
+        let HostRescan {
+            ip,
+            open_ports,
+            dns,
+            mac,
+        } = rescan;
+        self.log(
+            Severity::Info,
+            format!("Port scan of {} finished: {} open", ip, open_ports.len()),
+        );
+        if let Some(dns) = &dns {
+            self.log_resolution(ip, dns);
+        }
+        let (_, spec) = self.port_scan_ports();
+        if let Some(host) = self.hosts.iter_mut().find(|h| h.ip == ip) {
+            let previous = std::mem::replace(&mut host.open_ports, open_ports);
+            host.previous_ports = host.ports_scanned.then_some(previous);
+            if let Some(diff) = host.port_diff() {
+                self.event_log
+                    .push(Severity::Info, format!("Ports on {} changed: {}", ip, diff.summary()));
+                self.port_changes_seen.remove(&ip);
+            }
+            host.ports_scanned = true;
+            host.ports_scanned_at = Some(crate::cache::now_secs());
+            host.ports_spec = Some(spec.to_string());
+            if let Some(dns) = dns {
+                host.set_resolution(dns, &self.config.name_precedence);
+            }
+            if mac.is_some() {
+                host.mac = mac;
+            }
+        }
+    }
+
+    /// Note a reverse lookup that found no name in the event log
+    fn log_resolution(&mut self, ip: Ipv4Addr, dns: &Resolution) {
+        match dns {
+            Resolution::NoRecord => self.log(Severity::Info, format!("No reverse DNS for {}", ip)),
+            Resolution::Timeout => self.log(Severity::Info, format!("Reverse DNS for {} timed out", ip)),
+            Resolution::Resolved(_) | Resolution::Disabled => {}
+        }
+    }
+
+    /// Send a Wake-on-LAN magic packet to the selected host's MAC address
+    pub fn send_wol(&self) -> Result<Option<String>> {
+        let Some(host) = self.selected_host() else {
+            return Ok(None);
+        };
+        let Some(mac) = &host.mac else {
+            return Ok(Some(format!(
+                "No MAC address for {} — WOL unavailable",
+                host.ip
+            )));
+        };
+
+        let Some(bytes) = wol::parse_mac(&mac.address) else {
+            return Ok(Some(format!("Invalid MAC address: {}", mac.address)));
+        };
+        wol::wake(bytes)?;
+
+        Ok(Some(format!("WOL packet sent to {} ({})", host.ip, mac.address)))
+    }
+
+    /// Wake every selected host with a known MAC, `wol_interval` apart, and
+    /// watch for them to answer. Progress arrives on the returned channel,
+    /// which closes when the run ends or [W] stops it.
+    pub fn start_wake(&mut self) -> Option<mpsc::Receiver<wol::WakeEvent>> {
+        let mut targets = Vec::new();
+        let mut no_mac = Vec::new();
+        for host in self.hosts.iter().filter(|h| self.selected_hosts.contains(&h.ip)) {
+            match host.mac.as_ref().and_then(|mac| wol::parse_mac(&mac.address)) {
+                Some(mac) => targets.push((host.ip, mac)),
+                None => no_mac.push(host.ip),
+            }
+        }
+        if targets.is_empty() {
+            self.notify(
+                Severity::Info,
+                format!("None of the {} selected hosts has a MAC address for WOL", no_mac.len()),
+            );
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel(64);
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        let ips: Vec<Ipv4Addr> = targets.iter().map(|t| t.0).collect();
+        let watch = self.config.wol_watch.map(|time| (self.config.ping.clone(), time));
+        self.wake_job = Some(WakeJob::new(&ips, no_mac, watch.is_some(), cancel_tx));
+        self.log(
+            Severity::Info,
+            format!(
+                "Waking {} hosts, {}ms apart",
+                ips.len(),
+                self.config.wol_interval.as_millis()
+            ),
+        );
+        tokio::spawn(wol::wake_all(targets, self.config.wol_interval, watch, tx, cancel_rx));
+        Some(rx)
+    }
+
+    pub fn apply_wake_event(&mut self, event: wol::WakeEvent) {
+        match &event {
+            wol::WakeEvent::Failed(ip, error) => {
+                self.log(Severity::Error, format!("WOL packet to {} failed: {}", ip, error))
+            }
+            wol::WakeEvent::Woke(ip) => self.log(Severity::Success, format!("{} woke up", ip)),
+            wol::WakeEvent::Sent(_) | wol::WakeEvent::TimedOut(_) => {}
+        }
+        if let Some(job) = &mut self.wake_job {
+            job.apply(&event);
+        }
+    }
+
+    /// The wake run ended: log its outcome and show the report, unless
+    /// another overlay or a text field has the keyboard
+    pub fn wake_finished(&mut self) {
+        let Some(job) = &mut self.wake_job else {
+            return;
+        };
+        job.finish();
+        let headline = job.headline();
+        let lines = job.report_lines();
+        self.notify(Severity::Success, format!("Wake-on-LAN: {}", headline));
+        if self.input_mode == InputMode::Normal {
+            self.summary_lines = lines;
+            self.summary_scroll = 0;
+            self.input_mode = InputMode::WakeReport;
+        }
+    }
+
+    /// "WOL 12/30" while a wake run is sending, "WOL 18/27 woke" while it
+    /// watches
+    pub fn wake_text(&self) -> Option<String> {
+        self.wake_job.as_ref()?.progress_text()
+    }
+
+    /// Start editing the range with the cursor on the `column`th character
+    /// (the end of the text if the click was past it)
+    pub fn edit_range_at(&mut self, column: usize) -> Result<Option<AppCommand>> {
+        let command = self.handle_action(Action::EditRange)?;
+        self.range_cursor = self
+            .range_input
+            .char_indices()
+            .nth(column)
+            .map_or(self.range_input.len(), |(i, _)| i);
+        Ok(command)
+    }
+
+    /// Check the selected host can be seen over ARP before looking its MAC
+    /// up again
+    fn request_mac_refresh(&mut self) -> Option<AppCommand> {
+        let Some(ip) = self.selected_host().map(|h| h.ip) else {
+            self.notify(Severity::Info, "No host selected");
+            return None;
+        };
+        if !is_local(&self.adapters, ip) {
+            self.notify(Severity::Info, format!("{} is behind a router: ARP can't see its MAC", ip));
+            return None;
+        }
+        self.notify(Severity::Info, format!("Looking up the MAC of {}", ip));
+        Some(AppCommand::RefreshMac(ip))
+    }
+
+    /// Read the ARP table for `ip` in the background; the answer comes back
+    /// on `tx` for [`App::apply_mac_refresh`]
+    pub fn spawn_mac_refresh(&self, ip: Ipv4Addr, tx: mpsc::Sender<(Ipv4Addr, Option<MacInfo>)>) {
+        let probes = self.probes.clone();
+        let prewarm = self.config.prewarms();
+        tokio::spawn(async move {
+            let mac = probes.mac.lookup(ip, prewarm).await;
+            let _ = tx.send((ip, mac)).await;
+        });
+    }
+
+    /// Take a refreshed MAC lookup. A miss keeps the MAC already known, with
+    /// its vendor looked up again.
+    pub fn apply_mac_refresh(&mut self, ip: Ipv4Addr, mac: Option<MacInfo>) {
+        let Some(host) = self.hosts.iter_mut().find(|h| h.ip == ip) else {
+            return;
+        };
+        let previous = host.mac.as_ref().map(|m| m.address.clone());
+        let changed = mac.as_ref().is_some_and(|m| previous.as_ref() != Some(&m.address));
+        let (severity, message) = match mac {
+            Some(mac) => {
+                let text = match &mac.vendor {
+                    Some(vendor) => format!("{} ({})", mac.address, vendor),
+                    None => mac.address.clone(),
+                };
+                let message = match previous.filter(|_| changed) {
+                    Some(old) => format!("MAC of {}: {}, was {}", ip, text, old),
+                    None => format!("MAC of {}: {}", ip, text),
+                };
+                host.mac = Some(mac);
+                (Severity::Success, message)
+            }
+            None => {
+                if let Some(known) = &mut host.mac {
+                    known.vendor = lookup_vendor(&known.address);
+                }
+                (Severity::Info, format!("{} is not in the ARP table", ip))
+            }
+        };
+        self.notify(severity, message);
+        // A new MAC may start or end a conflict
+        if changed {
+            self.update_conflicts();
+        }
+    }
+
+    /// Put the selected host's IP on the clipboard
+    fn copy_selected_ip(&mut self) {
+        let Some(ip) = self.selected_host().map(|h| h.ip) else {
+            self.notify(Severity::Info, "No host selected");
+            return;
+        };
+        match crate::clipboard::copy(&ip.to_string()) {
+            Ok(()) => self.notify(Severity::Success, format!("Copied {}", ip)),
+            Err(e) => self.notify(Severity::Error, format!("Copy failed: {}", e)),
+        }
+    }
+
+    /// Save the selected host's details to a text file, or as JSON in the
+    /// export's host schema
+    pub fn save_selected_host(&mut self, json: bool) -> Result<()> {
+        let Some(host) = self.selected_host() else {
+            self.notify(Severity::Info, "No host selected");
+            return Ok(());
+        };
+
+        if json {
+            let filename = format!("ipscannr_host_{}.json", host.ip);
+            std::fs::write(&filename, serde_json::to_string_pretty(&ExportHost::from(host))?)?;
+            tracing::info!(ip = %host.ip, file = %filename, "host saved");
+            self.notify(Severity::Success, format!("Saved to {}", filename));
+            return Ok(());
+        }
+
+        let filename = format!("ipscannr_host_{}.txt", host.ip);
+        let mut content = String::new();
+        content.push_str(&format!("IP:     {}\n", host.ip));
+        content.push_str(&format!(
+            "Status: {}\n",
+            if host.is_alive { "Online" } else { "Offline" }
+        ));
+        if let Some(rtt) = host.rtt {
+            content.push_str(&format!("RTT:    {}\n", rtt_text(rtt)));
+        }
+        if let Some(hostname) = &host.hostname {
+            content.push_str(&format!("Host:   {}\n", hostname));
+        }
+        if let Some(mac) = &host.mac {
+            content.push_str(&format!("MAC:    {}\n", mac.address));
+            if let Some(vendor) = &mac.vendor {
+                content.push_str(&format!("Vendor: {}\n", vendor));
+            }
+        }
+        if !host.open_ports.is_empty() {
+            content.push_str("\nOpen Ports:\n");
+            for port in &host.open_ports {
+                content.push_str(&format!("  {}\n", port));
+            }
+        }
+
+        std::fs::write(&filename, content)?;
+        tracing::info!(ip = %host.ip, file = %filename, "host saved");
+        self.notify(Severity::Success, format!("Saved to {}", filename));
+        Ok(())
+    }
+
+    /// Drop selections that no longer correspond to any host, returning how
+    /// many were removed
+    fn prune_selection(&mut self) -> usize {
+        let before = self.selected_hosts.len();
+        // A set, not a scan of the hosts per selected address: selecting all
+        // of a /16 would otherwise cost 65k × 65k comparisons
+        let listed: HashSet<Ipv4Addr> = self.hosts.iter().map(|h| h.ip).collect();
+        self.selected_hosts.retain(|ip| listed.contains(ip));
+        before - self.selected_hosts.len()
+    }
+
+    /// Prune stale selections and check there is something to export,
+    /// telling the user why not otherwise
+    fn ensure_exportable(&mut self) -> bool {
+        let pruned = self.prune_selection();
+        if pruned > 0 {
+            self.event_log.push(
+                Severity::Info,
+                format!("Cleared {} selected host(s) no longer in the results", pruned),
+            );
+        }
+        if self.hosts.is_empty() {
+            self.notify(Severity::Info, "Nothing to export: run a scan first");
+            return false;
+        }
+        true
+    }
+
+    /// The results and scan progress as `--listen` serves them
+    pub fn snapshot(&self) -> Snapshot {
+        let state = match self.scan_state {
+            ScanState::Idle => "idle",
+            ScanState::Scanning => "scanning",
+            ScanState::Paused => "paused",
+            ScanState::Completed => "completed",
+        };
+        let range = if self.scan_range.is_empty() { &self.range_input } else { &self.scan_range };
+        let mut hosts: Vec<&HostInfo> = self.hosts.iter().collect();
+        hosts.sort_by(|a, b| table_order(a, b));
+        Snapshot {
+            status: serve::Status {
+                state,
+                range: range.clone(),
+                scanned: self.scan_completed,
+                total: self.scan_total,
+                hosts: self.hosts.len(),
+                online: self.hosts.iter().filter(|h| h.is_alive).count(),
+                started_at: self.scan_started_at,
+                last_scan: self
+                    .scan_finished_at
+                    .or_else(|| self.hosts.iter().filter_map(|h| h.cached_at).max()),
+            },
+            hosts: hosts.into_iter().map(ExportHost::from).collect(),
+            metrics: Arc::clone(&self.metrics),
+        }
+    }
+
+    /// Replace what `--listen` serves with the current results
+    pub fn publish(&self) {
+        if let Some(feed) = &self.status_feed {
+            let snapshot = self.snapshot();
+            *feed.write().unwrap_or_else(|e| e.into_inner()) = snapshot;
+        }
+    }
+
+    /// Publish once something `changed`, at most every [`PUBLISH_INTERVAL`]:
+    /// a scan changes the results many times a second, and each snapshot
+    /// sorts and copies every host
+    pub fn publish_if_due(&mut self, changed: bool) {
+        if self.status_feed.is_none() {
+            return;
+        }
+        self.publish_pending |= changed;
+        let due = self.published_at.is_none_or(|at| at.elapsed() >= PUBLISH_INTERVAL);
+        if self.publish_pending && due {
+            self.publish();
+            self.publish_pending = false;
+            self.published_at = Some(Instant::now());
+        }
+    }
+
+    /// Hosts to include in export: the selected subset (or all if nothing is
+    /// selected), optionally narrowed to the table filter, online hosts and
+    /// hosts with open ports (the dialog's toggles). Sorted like the
+    /// completed table, so an export taken mid-scan isn't in reply order.
+    fn hosts_for_export(&self) -> Vec<&HostInfo> {
+        let mut hosts: Vec<&HostInfo> = self.exported_hosts().collect();
+        hosts.sort_by(|a, b| table_order(a, b));
+        hosts
+    }
+
+    /// The hosts [`App::hosts_for_export`] returns, unsorted: one pass over
+    /// the hosts, each looked up in the selection set
+    fn exported_hosts(&self) -> impl Iterator<Item = &HostInfo> {
+        let filtered = self.export_filtered();
+        let offline = self.config.export_offline;
+        let ports_only = self.config.export_open_ports_only;
+        self.hosts
+            .iter()
+            .filter(|h| self.selected_hosts.is_empty() || self.selected_hosts.contains(&h.ip))
+            .filter(move |h| !filtered || self.filter_mode.matches(h))
+            .filter(move |h| offline || h.is_alive)
+            .filter(move |h| !ports_only || !h.open_ports.is_empty())
+    }
+
+    /// Whether the table filter narrows the export
+    fn export_filtered(&self) -> bool {
+        self.export_respect_filter && self.filter_mode != FilterMode::All
+    }
+
+    /// What the export will contain, for the export overlay
+    pub fn export_scope(&self) -> String {
+        // Drawn every frame the dialog is open: count without sorting
+        let count = self.exported_hosts().count();
+        let filter = self.filter_mode.label().to_lowercase();
+        let narrowed = !self.config.export_offline || self.config.export_open_ports_only;
+        let mut scope = match (self.selected_hosts.is_empty(), self.export_filtered()) {
+            (true, false) if !narrowed => format!("All {} hosts", count),
+            (true, false) => format!("{} host(s)", count),
+            (true, true) => format!("{} {} hosts", count, filter),
+            (false, false) => format!("{} selected host(s)", count),
+            (false, true) => format!("{} selected {} host(s)", count, filter),
+        };
+        if self.config.export_open_ports_only {
+            scope.push_str(" with open ports");
+        }
+        if !self.config.export_offline {
+            scope.push_str(", offline left out");
+        }
+        scope
+    }
+
+    fn export_csv(&mut self) -> Result<()> {
+        if !self.ensure_exportable() {
+            return Ok(());
+        }
+        let stem = format!("ipscannr_export_{}", chrono_timestamp());
+        let filename = format!("{}.csv", stem);
+        let mut wtr = csv::Writer::from_path(&filename)?;
+
+        wtr.write_record([
+            "IP",
+            "Status",
+            "RTT (ms)",
+            "Hostname",
+            "Alias",
+            "mDNS",
+            "NetBIOS",
+            "PTR",
+            "MAC",
+            "Vendor",
+            "Ports",
+            "Last Seen",
+        ])?;
+
+        for host in self.hosts_for_export() {
+            wtr.write_record([
+                host.ip.to_string(),
+                if host.is_alive { "Online" } else { "Offline" }.to_string(),
+                host.rtt.map(|d| export::rtt_ms(d).to_string()).unwrap_or_default(),
+                host.hostname.clone().unwrap_or_default(),
+                host.names.alias.clone().unwrap_or_default(),
+                host.names.mdns.clone().unwrap_or_default(),
+                host.names.netbios.clone().unwrap_or_default(),
+                host.names.ptr.clone().unwrap_or_default(),
+                host.mac.as_ref().map(|m| m.address.clone()).unwrap_or_default(),
+                host.mac.as_ref().and_then(|m| m.vendor.clone()).unwrap_or_default(),
+                host.open_ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(";"),
+                host.last_seen.map(|t| t.to_string()).unwrap_or_default(),
+            ])?;
+        }
+
+        wtr.flush()?;
+        tracing::info!(file = %filename, hosts = self.hosts_for_export().len(), "CSV export written");
+        let summary = self.export_summary(&stem)?;
+        self.notify(
+            Severity::Success,
+            format!("Exported to {} (summary in {})", filename, summary),
+        );
+        Ok(())
+    }
+
+    fn export_json(&mut self) -> Result<()> {
+        if !self.ensure_exportable() {
+            return Ok(());
+        }
+        let stem = format!("ipscannr_export_{}", chrono_timestamp());
+        let filename = format!("{}.json", stem);
+
+        let export_data: Vec<ExportHost> = self
+            .hosts_for_export()
+            .into_iter()
+            .map(ExportHost::from)
+            .collect();
+
+        let json = serde_json::to_string_pretty(&export_data)?;
+        std::fs::write(&filename, json)?;
+        tracing::info!(file = %filename, hosts = export_data.len(), "JSON export written");
+
+        let summary = self.export_summary(&stem)?;
+        self.notify(
+            Severity::Success,
+            format!("Exported to {} (summary in {})", filename, summary),
+        );
+        Ok(())
+    }
+
+    /// The table as shown (filtered rows, picked columns) as plain text
+    pub fn table_text(&self) -> String {
+        let hosts: Vec<&HostInfo> = self.filtered_hosts.iter().map(|&i| &self.hosts[i]).collect();
+        export::text_table(&hosts, &self.settings.columns, crate::cache::now_secs())
+    }
+
+    /// Save [`App::table_text`] to a file. It follows the screen rather than
+    /// the export scope, so what a screen reader gets matches the table.
+    fn export_text(&mut self) -> Result<()> {
+        if self.filtered_hosts.is_empty() {
+            self.notify(Severity::Info, "Nothing to save: the table is empty");
+            return Ok(());
+        }
+        let filename = format!("ipscannr_view_{}.txt", chrono_timestamp());
+        std::fs::write(&filename, self.table_text())?;
+        tracing::info!(file = %filename, hosts = self.filtered_hosts.len(), "text view written");
+        self.notify(Severity::Success, format!("Table saved to {}", filename));
+        Ok(())
+    }
+
+    /// Write the summary of the exported hosts next to the export; returns
+    /// the file name
+    fn export_summary(&self, stem: &str) -> Result<String> {
+        let filename = format!("{}_summary.txt", stem);
+        // Dropped offline hosts only belong to a whole-scan export
+        let whole_scan = self.selected_hosts.is_empty()
+            && !self.export_filtered()
+            && self.config.export_offline
+            && !self.config.export_open_ports_only;
+        let (dropped, dropped_causes) = if whole_scan {
+            (self.offline_dropped, self.dropped_causes.clone())
+        } else {
+            (0, HashMap::new())
+        };
+        let summary = summary::summarize(self.hosts_for_export(), dropped)
+            .count_offline_causes(&dropped_causes);
+        std::fs::write(&filename, summary.to_text())?;
+        Ok(filename)
+    }
+}
+
+/// Dots12-style CLI spinner frames (braille characters)
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long each spinner frame shows, however often the loop runs
+const SPINNER_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Spinner frame to show `elapsed` after the animation started
+/// Row of the address `query` names among `ips`, for [:] / [#]. Digits
+/// alone (or after a leading dot) name a last octet: an exact match wins,
+/// then the first octet starting with them. With dots inside, a whole
+/// address wins, then trailing octets (`1.57`), then a leading part.
+pub fn goto_match(ips: &[Ipv4Addr], query: &str) -> Option<usize> {
+    let query = query.strip_prefix('.').unwrap_or(query);
+    if query.is_empty() {
+        return None;
+    }
+    if !query.contains('.') {
+        let last = |ip: &Ipv4Addr| ip.octets()[3].to_string();
+        return ips
+            .iter()
+            .position(|ip| last(ip) == query)
+            .or_else(|| ips.iter().position(|ip| last(ip).starts_with(query)));
+    }
+    let texts: Vec<String> = ips.iter().map(|ip| ip.to_string()).collect();
+    let suffix = format!(".{}", query);
+    texts
+        .iter()
+        .position(|ip| ip == query)
+        .or_else(|| texts.iter().position(|ip| ip.ends_with(&suffix)))
+        .or_else(|| texts.iter().position(|ip| ip.starts_with(query)))
+}
+
+/// Online hosts in `hosts`, and how many of those only answered TCP probes
+pub fn online_counts<'a>(hosts: impl IntoIterator<Item = &'a HostInfo>) -> (usize, usize) {
+    hosts.into_iter().fold((0, 0), |(online, tcp_only), h| {
+        (online + usize::from(h.is_alive), tcp_only + usize::from(h.tcp_only()))
+    })
+}
+
+/// "18 online (3 TCP-only)", or just "18 online" when every host answered ICMP
+pub fn online_text<'a>(hosts: impl IntoIterator<Item = &'a HostInfo>) -> String {
+    match online_counts(hosts) {
+        (online, 0) => format!("{} online", online),
+        (online, tcp_only) => format!("{} online ({} TCP-only)", online, tcp_only),
+    }
+}
+
+/// Addresses `input` scans; None when it doesn't parse
+fn range_size(input: &str) -> Option<usize> {
+    IpRange::count_batch(input).ok()
+}
+
+/// "about 40 s", "about 13 min", "about 2 h 5 min" for scan estimates
+pub fn estimate_text(estimate: Duration) -> String {
+    let secs = estimate.as_secs().max(1);
+    match (secs / 3600, secs / 60 % 60) {
+        (0, 0) => format!("about {} s", secs),
+        (0, m) => format!("about {} min", m),
+        (h, 0) => format!("about {} h", h),
+        (h, m) => format!("about {} h {} min", h, m),
+    }
+}
+
+fn spinner_frame(elapsed: Duration) -> usize {
+    (elapsed.as_millis() / SPINNER_FRAME_TIME.as_millis()) as usize % SPINNER_FRAMES.len()
+}
+
+/// Each host's up/down state and when it began, where known
+fn state_history(hosts: &[HostInfo]) -> impl Iterator<Item = (Ipv4Addr, (bool, u64))> + '_ {
+    hosts
+        .iter()
+        .filter_map(|h| Some((h.ip, (h.is_alive, h.state_since?))))
+}
+
+/// Order of the hosts table once a scan completes: online hosts first, then
+/// by IP within each group
+fn table_order(a: &HostInfo, b: &HostInfo) -> std::cmp::Ordering {
+    b.is_alive.cmp(&a.is_alive).then_with(|| a.ip.cmp(&b.ip))
+}
+
+/// Commands returned by the app
+#[derive(Debug)]
+pub enum AppCommand {
+    Quit,
+    StartScan,
+    ResumeScan,
+    ScanPortsForSelected,
+    StartContinuousPing(Ipv4Addr),
+    StartTracert(Ipv4Addr),
+    /// Send a knock action's sequence, labelled for the overlay
+    Knock(Ipv4Addr, String, KnockPlan),
+    Discover,
+    CheckGateway(AdapterInfo),
+    WakeSelected,
+    RefreshMac(Ipv4Addr),
+}
+
+/// Addresses to probe, each list tagged with its index into `App::batch`
+type BatchGroups = Vec<(usize, Vec<Ipv4Addr>)>;
+
+/// One range of a scan and how far it has got
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRange {
+    /// The range as typed, also its cache key
+    pub range: String,
+    pub total: usize,
+    pub completed: usize,
+    pub online: usize,
+    pub done: bool,
+}
+
+/// What a background port scan reports
+#[derive(Debug)]
+pub enum PortScanEvent {
+    Progress(PortProgress),
+    Finished(HostRescan),
+}
+
+/// Result of rescanning a host: its open ports, a retried
+/// reverse lookup when the last one timed out or never ran, and its MAC when
+/// it had none
+#[derive(Debug)]
+pub struct HostRescan {
+    pub ip: Ipv4Addr,
+    pub open_ports: Vec<u16>,
+    pub dns: Option<Resolution>,
+    pub mac: Option<MacInfo>,
+}
+
+/// The network lookups behind a scan, swapped for in-memory fakes in tests
+#[derive(Clone)]
+pub struct Probes {
+    /// Liveness check; `None` pings with a [`Pinger`] built from
+    /// `Config::ping` for each range
+    pub ping: Option<Arc<dyn HostProbe>>,
+    pub names: Arc<dyn NameResolver>,
+    pub netbios: Arc<dyn NetbiosLookup>,
+    pub mac: Arc<dyn MacLookup>,
+}
+
+impl Default for Probes {
+    fn default() -> Self {
+        Self {
+            ping: None,
+            names: Arc::new(DnsResolver::default()),
+            netbios: Arc::new(NetbiosResolver::default()),
+            mac: Arc::new(ArpTable),
+        }
+    }
+}
+
+/// Events from the scan process
+#[derive(Debug)]
+pub enum ScanEvent {
+    HostDiscovered(Box<HostInfo>),
+    /// Background hostname / MAC lookup for an already discovered host
+    HostUpdated {
+        ip: Ipv4Addr,
+        dns: Resolution,
+        netbios: Option<String>,
+        mac: Option<MacInfo>,
+    },
+    /// Every address of this batch range (index into `App::batch`) has a
+    /// result; the next range starts
+    RangeComplete(usize),
+    ScanComplete,
+}
+
+fn chrono_timestamp() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{}", duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{CommandTemplate, KnockAction};
+    use crate::scanner::{HostStatus, MacInfo, PingMethod};
+
+    fn host(last_octet: u8, is_alive: bool) -> HostInfo {
+        HostInfo::pinged(Ipv4Addr::new(192, 168, 1, last_octet), is_alive)
+    }
+
+    fn app_with_hosts(count: u8) -> App {
+        let mut app = App::new(Config::default());
+        app.adapters_loading = false;
+        app.hosts = (1..=count).map(|i| host(i, i % 2 == 1)).collect();
+        app.update_filtered_hosts();
+        app.table_state.select(Some(0));
+        app.focus = Focus::HostsTable;
+        app
+    }
+
+    #[test]
+    fn enter_opens_and_esc_closes_compact_details_popup() {
+        let mut app = app_with_hosts(3);
+        app.compact_layout = true;
+
+        app.handle_action(Action::Select).unwrap();
+        assert!(app.details_popup_open());
+
+        // Esc closes the popup even while a scan is running
+        app.scan_state = ScanState::Scanning;
+        app.handle_action(Action::Cancel).unwrap();
+        assert!(!app.details_popup_open());
+        assert_eq!(app.focus, Focus::HostsTable);
+        assert_eq!(app.scan_state, ScanState::Scanning);
+    }
+
+    #[test]
+    fn second_enter_runs_default_host_action() {
+        let mut app = app_with_hosts(3);
+        app.show_details = true;
+
+        // First Enter only moves focus to the details pane
+        assert!(app.handle_action(Action::Select).unwrap().is_none());
+        assert_eq!(app.focus, Focus::DetailsPane);
+
+        // Online and unscanned: port scan; once scanned: continuous ping
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::ScanPortsForSelected)));
+        app.hosts[0].ports_scanned = true;
+        app.update_filtered_hosts();
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::StartContinuousPing(_))));
+
+        // Offline hosts get pinged
+        app.table_state.select(Some(1));
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::StartContinuousPing(_))));
+
+        app.config.enter_action = HostAction::Tracert;
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::StartTracert(_))));
+        app.config.enter_action = HostAction::None;
+        assert!(app.handle_action(Action::Select).unwrap().is_none());
+        assert_eq!(app.focus, Focus::DetailsPane);
+    }
+
+    #[test]
+    fn enter_runs_host_action_directly_when_details_are_hidden() {
+        let mut app = app_with_hosts(3);
+        app.show_details = false;
+        app.config.enter_action = HostAction::Ping;
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::StartContinuousPing(_))));
+        assert_eq!(app.focus, Focus::HostsTable);
+    }
+
+    #[test]
+    fn details_hotkeys_work_inside_compact_popup() {
+        let mut app = app_with_hosts(3);
+        app.compact_layout = true;
+        app.handle_action(Action::Select).unwrap();
+
+        let cmd = app.handle_action(Action::ConfigurePorts).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::ScanPortsForSelected)));
+        let cmd = app.handle_action(Action::ContinuousPing).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::StartContinuousPing(_))));
+    }
+
+    #[test]
+    fn tab_skips_hidden_details_pane_in_compact_layout() {
+        let mut app = app_with_hosts(3);
+        app.compact_layout = true;
+        app.handle_action(Action::SwitchPane).unwrap();
+        assert_eq!(app.focus, Focus::RangeInput);
+
+        app.compact_layout = false;
+        app.focus = Focus::HostsTable;
+        app.handle_action(Action::SwitchPane).unwrap();
+        assert_eq!(app.focus, Focus::DetailsPane);
+    }
+
+    #[test]
+    fn help_scroll_is_clamped_and_reset() {
+        let mut app = app_with_hosts(1);
+        app.handle_action(Action::Help).unwrap();
+        app.help_max_scroll = 12;
+
+        app.handle_action(Action::NavigateUp).unwrap();
+        assert_eq!(app.help_scroll, 0);
+        app.handle_action(Action::NavigatePageDown).unwrap();
+        assert_eq!(app.help_scroll, 10);
+        app.handle_action(Action::NavigatePageDown).unwrap();
+        assert_eq!(app.help_scroll, 12);
+        app.handle_action(Action::NavigateHome).unwrap();
+        assert_eq!(app.help_scroll, 0);
+        app.handle_action(Action::NavigateEnd).unwrap();
+        assert_eq!(app.help_scroll, 12);
+
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.help_scroll, 0);
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingAlerter(Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+    impl Alerter for RecordingAlerter {
+        fn alert(&mut self, title: &str, body: &str) {
+            self.0.lock().unwrap().push((title.to_string(), body.to_string()));
+        }
+    }
+
+    #[test]
+    fn scan_completion_raises_alert() {
+        let recorder = RecordingAlerter::default();
+        let mut app = app_with_hosts(3);
+        app.range_input = "192.168.1.0/30".to_string();
+        app.alerter = Box::new(recorder.clone());
+
+        app.alert_scan_complete();
+
+        let alerts = recorder.0.lock().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].0, "ipscannr scan complete");
+        assert_eq!(alerts[0].1, "192.168.1.0/30: 3 hosts (2 online)");
+    }
+
+    #[test]
+    fn quit_is_immediate_when_idle() {
+        let mut app = app_with_hosts(2);
+        assert!(matches!(
+            app.handle_action(Action::Quit).unwrap(),
+            Some(AppCommand::Quit)
+        ));
+    }
+
+    #[test]
+    fn quit_during_scan_asks_for_confirmation() {
+        let mut app = app_with_hosts(2);
+        app.scan_state = ScanState::Scanning;
+
+        assert!(app.handle_action(Action::Quit).unwrap().is_none());
+        assert_eq!(app.input_mode, InputMode::ConfirmQuit);
+        assert!(app.has_partial_results());
+
+        // Esc closes the prompt instead of pausing the scan
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.scan_state, ScanState::Scanning);
+
+        app.handle_action(Action::Quit).unwrap();
+        assert!(app.handle_action(Action::None).unwrap().is_none());
+        assert!(matches!(
+            app.handle_action(Action::Confirm).unwrap(),
+            Some(AppCommand::Quit)
+        ));
+    }
+
+    #[test]
+    fn second_ctrl_c_forces_quit() {
+        let mut app = app_with_hosts(2);
+        app.port_scanning = true;
+        assert!(app.handle_action(Action::Quit).unwrap().is_none());
+        assert!(matches!(
+            app.handle_action(Action::Quit).unwrap(),
+            Some(AppCommand::Quit)
+        ));
+    }
+
+    #[test]
+    fn quit_works_from_every_mode() {
+        for mode in [
+            InputMode::EditingRange,
+            InputMode::EditingPorts,
+            InputMode::Help,
+            InputMode::Exporting,
+            InputMode::OutputOverlay,
+            InputMode::EventLog,
+        ] {
+            let mut app = app_with_hosts(1);
+            app.input_mode = mode;
+            assert!(
+                matches!(app.handle_action(Action::Quit).unwrap(), Some(AppCommand::Quit)),
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn ctrl_c_in_live_overlay_stops_task_first() {
+        let mut app = app_with_hosts(1);
+        let (tx, mut rx) = mpsc::channel(1);
+        app.overlay_cancel_tx = Some(tx);
+        app.input_mode = InputMode::OutputOverlay;
+
+        assert!(app.handle_action(Action::Quit).unwrap().is_none());
+        assert!(rx.try_recv().is_ok(), "task cancelled");
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        assert!(matches!(
+            app.handle_action(Action::Quit).unwrap(),
+            Some(AppCommand::Quit)
+        ));
+    }
+
+    #[test]
+    fn shift_tab_cycles_panes_backwards() {
+        let mut app = app_with_hosts(2);
+        app.focus = Focus::DetailsPane;
+        app.handle_action(Action::SwitchPaneReverse).unwrap();
+        assert_eq!(app.focus, Focus::HostsTable);
+        app.handle_action(Action::SwitchPaneReverse).unwrap();
+        assert_eq!(app.focus, Focus::RangeInput);
+        app.handle_action(Action::SwitchPaneReverse).unwrap();
+        assert_eq!(app.focus, Focus::DetailsPane);
+
+        app.show_details = false;
+        app.focus = Focus::RangeInput;
+        app.handle_action(Action::SwitchPaneReverse).unwrap();
+        assert_eq!(app.focus, Focus::HostsTable);
+    }
+
+    #[test]
+    fn esc_steps_back_to_hosts_table() {
+        for from in [Focus::RangeInput, Focus::DetailsPane, Focus::HostsTable] {
+            let mut app = app_with_hosts(2);
+            app.focus = from;
+            app.handle_action(Action::Cancel).unwrap();
+            assert_eq!(app.focus, Focus::HostsTable, "from {:?}", from);
+        }
+    }
+
+    #[test]
+    fn vim_motions_move_table_selection() {
+        let mut app = app_with_hosts(40);
+        let keys = |app: &mut App, actions: &[Action]| {
+            for a in actions {
+                app.handle_action(*a).unwrap();
+            }
+            app.table_state.selected()
+        };
+        use Action::{Character as C, NavigateDown, NavigateUp};
+
+        assert_eq!(keys(&mut app, &[C('G')]), Some(39));
+        assert_eq!(keys(&mut app, &[C('g'), C('g')]), Some(0));
+        assert_eq!(keys(&mut app, &[C('2'), C('5'), NavigateDown]), Some(25));
+        assert_eq!(keys(&mut app, &[C('9'), C('9'), NavigateDown]), Some(39));
+        assert_eq!(keys(&mut app, &[C('1'), C('0'), NavigateUp]), Some(29));
+        assert_eq!(keys(&mut app, &[C('5'), C('G')]), Some(4));
+    }
+
+    #[test]
+    fn vim_motions_only_in_hosts_table() {
+        let mut app = app_with_hosts(10);
+        app.focus = Focus::RangeInput;
+        app.handle_action(Action::Character('G')).unwrap();
+        assert_eq!(app.table_state.selected(), Some(0));
+        assert_eq!(app.input_mode, InputMode::EditingRange, "typed into the range");
+    }
+
+    #[test]
+    fn column_picker_toggles_and_reorders() {
+        let mut app = app_with_hosts(3);
+        app.settings.columns = vec![Column::Ip, Column::Hostname, Column::Status];
+        app.handle_action(Action::PickColumns).unwrap();
+        assert_eq!(app.input_mode, InputMode::ColumnPicker);
+        assert_eq!(app.column_picker.len(), Column::ALL.len());
+        assert_eq!(app.column_picker[3], (Column::Rtt, false));
+
+        // The IP column can move but not be hidden
+        app.handle_action(Action::ToggleSelect).unwrap();
+        assert_eq!(app.column_picker[0], (Column::Ip, true));
+        app.handle_action(Action::Character('-')).unwrap();
+        assert_eq!(app.column_index, 0, "already first");
+
+        app.handle_action(Action::NavigateDown).unwrap();
+        app.handle_action(Action::Character('+')).unwrap();
+        assert_eq!(app.column_picker[2], (Column::Hostname, true));
+        assert_eq!(app.column_index, 2);
+        app.handle_action(Action::NavigateDown).unwrap();
+        app.handle_action(Action::ToggleSelect).unwrap();
+        assert_eq!(app.column_picker[3], (Column::Rtt, true));
+
+        // Esc leaves the table as it was
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.settings.columns, [Column::Ip, Column::Hostname, Column::Status]);
+    }
+
+    #[test]
+    fn settings_overlay_validates_and_applies_tunables() {
+        let path = std::env::temp_dir().join("ipscannr_settings_overlay_test.json");
+        let _guard = crate::settings::use_settings_file(&path);
+        let mut app = app_with_hosts(3);
+        app.handle_action(Action::OpenSettings).unwrap();
+        assert_eq!(app.input_mode, InputMode::Settings);
+
+        // Typing a digit starts over; out of range keeps the editor open
+        app.handle_action(Action::Character('9')).unwrap();
+        app.handle_action(Action::Select).unwrap();
+        assert_eq!(app.tuning_edit.as_deref(), Some("9"));
+        assert_eq!(
+            app.tuning_error.as_deref(),
+            Some("Ping timeout must be 50-10000 ms")
+        );
+        assert_eq!(app.config.ping.timeout, Duration::from_millis(300));
+
+        app.handle_action(Action::Character('0')).unwrap();
+        app.handle_action(Action::Character('0')).unwrap();
+        app.handle_action(Action::Select).unwrap();
+        assert_eq!(app.tuning_edit, None);
+        assert_eq!(app.tuning_error, None);
+        assert_eq!(app.config.ping.timeout, Duration::from_millis(900));
+
+        // Enter on a toggle flips it; Esc while editing only drops the edit
+        app.tuning_index = Field::ALL.iter().position(|&f| f == Field::DetectMac).unwrap();
+        app.handle_action(Action::Select).unwrap();
+        assert!(!app.config.detect_mac);
+        app.handle_action(Action::NavigateUp).unwrap();
+        app.handle_action(Action::NavigateUp).unwrap();
+        app.handle_action(Action::Select).unwrap();
+        assert_eq!(app.tuning_edit.as_deref(), Some("50"), "port concurrency");
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Settings);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        let saved = crate::settings::load_settings().tuning.expect("tuning saved");
+        assert_eq!(saved.ping_timeout_ms, 900);
+        assert!(!saved.detect_mac);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn context_menu_runs_host_actions() {
+        let mut app = app_with_hosts(3);
+        app.open_context_menu(10, 5);
+        assert_eq!(app.input_mode, InputMode::ContextMenu);
+
+        app.handle_action(Action::NavigateUp).unwrap();
+        assert_eq!(MenuItem::ALL[app.menu_index], MenuItem::Columns);
+        app.handle_action(Action::NavigateDown).unwrap();
+        app.handle_action(Action::NavigateDown).unwrap();
+        assert_eq!(MenuItem::ALL[app.menu_index], MenuItem::Tracert);
+
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::StartTracert(_))));
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.open_context_menu(10, 5);
+        app.menu_index = 2;
+        let cmd = app.handle_action(Action::Select).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::ScanPortsForSelected)));
+    }
+
+    #[test]
+    fn copy_command_chooser_lists_templates_for_open_ports() {
+        let mut app = app_with_hosts(1);
+        app.settings.command_templates = vec![
+            CommandTemplate {
+                label: "Ping".to_string(),
+                template: "ping {ip}".to_string(),
+                port: None,
+            },
+            CommandTemplate {
+                label: "SSH".to_string(),
+                template: "ssh admin@{ip}".to_string(),
+                port: Some(22),
+            },
+            CommandTemplate {
+                label: "RDP".to_string(),
+                template: "mstsc /v:{ip}".to_string(),
+                port: Some(3389),
+            },
+        ];
+        app.hosts[0].open_ports = vec![22];
+        app.hosts[0].ports_scanned = true;
+
+        app.handle_action(Action::CopyCommand).unwrap();
+        assert_eq!(app.input_mode, InputMode::CopyCommand);
+        assert_eq!(
+            app.command_choices(),
+            [
+                ("Ping".to_string(), "ping 192.168.1.1".to_string()),
+                ("SSH".to_string(), "ssh admin@192.168.1.1".to_string()),
+            ]
+        );
+        app.handle_action(Action::NavigateUp).unwrap();
+        assert_eq!(app.command_index, 1);
+        // A number past the list does nothing; Esc closes without copying
+        app.handle_action(Action::Character('3')).unwrap();
+        assert_eq!(app.input_mode, InputMode::CopyCommand);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn knock_chooser_sends_a_sequence_without_copying() {
+        let mut app = app_with_hosts(1);
+        app.handle_action(Action::Knock).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal, "no knock sequences set");
+
+        let mut knock = KnockAction {
+            label: "Open SSH".to_string(),
+            ports: vec![7000, 8000],
+            protocol: "udp".to_string(),
+            delay_ms: 200,
+            check_port: Some(22),
+        };
+        app.settings.knock_actions = vec![knock.clone()];
+        app.handle_action(Action::Knock).unwrap();
+        assert_eq!(app.input_mode, InputMode::Knock);
+        assert_eq!(app.knock_choices(), [("Open SSH".to_string(), "udp 7000 8000, then check 22".to_string())]);
+        let cmd = app.handle_action(Action::Select).unwrap();
+        let Some(AppCommand::Knock(ip, label, plan)) = cmd else {
+            panic!("expected a knock, got {:?}", cmd);
+        };
+        assert_eq!((ip, label.as_str()), (Ipv4Addr::new(192, 168, 1, 1), "Open SSH"));
+        assert_eq!((plan.knocks.len(), plan.check_port), (2, Some(22)));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let newest = app.event_log.newest_first(None).next().unwrap().message.clone();
+        assert!(newest.starts_with("No knock sequences"), "nothing copied: {}", newest);
+
+        // A malformed sequence is reported instead of sent
+        knock.protocol = "icmp".to_string();
+        app.settings.knock_actions = vec![knock];
+        app.handle_action(Action::Knock).unwrap();
+        assert!(app.handle_action(Action::Character('1')).unwrap().is_none());
+        let newest = &app.event_log.newest_first(None).next().unwrap().message;
+        assert!(newest.contains("protocol must be tcp or udp"), "{}", newest);
+    }
+
+    #[test]
+    fn esc_closes_context_menu_without_pausing_scan() {
+        let mut app = app_with_hosts(3);
+        app.scan_state = ScanState::Scanning;
+        app.open_context_menu(0, 0);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.scan_state, ScanState::Scanning);
+    }
+
+    #[test]
+    fn overlay_output_is_capped_and_keeps_scroll_position() {
+        let mut app = app_with_hosts(1);
+        app.config.overlay_max_lines = 5;
+        for i in 0..5 {
+            app.push_overlay_line(format!("line {}", i));
+        }
+        assert_eq!(app.overlay_scroll, 4, "follows output at the bottom");
+
+        // Scrolled up to read "line 2": dropping "line 0" must not move the view
+        app.overlay_scroll = 2;
+        app.push_overlay_line("line 5".to_string());
+        assert_eq!(app.overlay_lines.len(), 5);
+        assert_eq!(app.overlay_lines[app.overlay_scroll], "line 2");
+
+        app.overlay_scroll = app.overlay_lines.len() - 1;
+        app.push_overlay_line("line 6".to_string());
+        assert_eq!(app.overlay_lines.front().unwrap(), "line 2");
+        assert_eq!(app.overlay_lines[app.overlay_scroll], "line 6");
+    }
+
+    #[test]
+    fn huge_ranges_count_offline_hosts_without_keeping_them() {
+        let mut app = app_with_hosts(0);
+        app.config.offline_retain_limit = 10;
+        app.scan_total = 20;
+        for i in 1..=20 {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(i, i % 4 == 0))));
+        }
+        assert_eq!(app.hosts.len(), 5);
+        assert_eq!(app.offline_dropped, 15);
+        assert_eq!(app.scan_completed, 20);
+        assert_eq!(app.completion_summary(), "20 hosts (5 online)");
+
+        // Below the limit everything is kept, as before
+        let mut app = app_with_hosts(0);
+        app.scan_total = 20;
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(1, false))));
+        assert_eq!(app.hosts.len(), 1);
+    }
+
+    #[test]
+    fn hosts_online_in_the_cache_but_not_now_are_stale() {
+        let mut app = app_with_hosts(0);
+        app.config.offline_retain_limit = 2;
+        app.scan_total = 4;
+        app.cached_online = [2, 3].into_iter().map(|i| Ipv4Addr::new(192, 168, 1, i)).collect();
+        for (i, alive) in [(1, false), (2, false), (3, true), (4, false)] {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(i, alive))));
+        }
+        // The large scan drops 1 and 4 but keeps 2, which was up last time
+        let ips: Vec<u8> = app.hosts.iter().map(|h| h.ip.octets()[3]).collect();
+        assert_eq!(ips, [2, 3]);
+        assert_eq!(app.offline_dropped, 2);
+        let stale: Vec<bool> = app.hosts.iter().map(|h| h.stale).collect();
+        assert_eq!(stale, [true, false]);
+
+        app.filter_mode = FilterMode::Stale;
+        app.update_filtered_hosts();
+        let shown: Vec<u8> = app.filtered_hosts.iter().map(|&i| app.hosts[i].ip.octets()[3]).collect();
+        assert_eq!(shown, [2]);
+    }
+
+    #[test]
+    fn stale_selections_are_pruned_before_export() {
+        let mut app = app_with_hosts(3);
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 2));
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 200));
+        assert_eq!(app.hosts_for_export().len(), 1);
+
+        // Only stale entries left: export falls back to every host
+        app.selected_hosts.remove(&Ipv4Addr::new(192, 168, 1, 2));
+        app.handle_action(Action::Export).unwrap();
+        assert!(app.selected_hosts.is_empty());
+        assert_eq!(app.hosts_for_export().len(), 3);
+        assert_eq!(app.input_mode, InputMode::Exporting);
+    }
+
+    #[test]
+    fn export_is_in_table_order_and_can_follow_the_filter() {
+        let mut app = app_with_hosts(0);
+        // Mid-scan the hosts are in reply order
+        for i in [4, 1, 3, 2, 5] {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(i, i % 2 == 1))));
+        }
+        let octets = |app: &App| -> Vec<u8> {
+            app.hosts_for_export().iter().map(|h| h.ip.octets()[3]).collect()
+        };
+        assert_eq!(octets(&app), [1, 3, 5, 2, 4]);
+        assert_eq!(app.export_scope(), "All 5 hosts");
+
+        app.handle_action(Action::ToggleFilter).unwrap();
+        assert_eq!(app.filter_mode, FilterMode::OnlineOnly);
+        app.handle_action(Action::Export).unwrap();
+        // The filter only applies once the box is ticked
+        assert_eq!(octets(&app), [1, 3, 5, 2, 4]);
+        app.handle_action(Action::ToggleFilter).unwrap();
+        assert!(app.export_respect_filter);
+        assert_eq!(octets(&app), [1, 3, 5]);
+        assert_eq!(app.export_scope(), "3 online hosts");
+
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 5));
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 4));
+        assert_eq!(octets(&app), [5]);
+        assert_eq!(app.export_scope(), "1 selected online host(s)");
+        app.handle_action(Action::ToggleFilter).unwrap();
+        assert_eq!(octets(&app), [5, 4]);
+        assert_eq!(app.export_scope(), "2 selected host(s)");
+    }
+
+    #[test]
+    fn export_can_leave_out_offline_hosts_and_hosts_without_ports() {
+        let path = std::env::temp_dir().join("ipscannr_settings_export_test.json");
+        let _guard = crate::settings::use_settings_file(&path);
+        let mut app = app_with_hosts(0);
+        for i in 1..=5 {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(i, i % 2 == 1))));
+        }
+        // An offline host keeps the ports its last scan found
+        for (i, ports) in [(0, vec![22]), (3, vec![80])] {
+            app.hosts[i].open_ports = ports;
+        }
+        let octets = |app: &App| -> Vec<u8> {
+            app.hosts_for_export().iter().map(|h| h.ip.octets()[3]).collect()
+        };
+        app.handle_action(Action::Export).unwrap();
+        assert_eq!(octets(&app), [1, 3, 5, 2, 4]);
+
+        app.handle_action(Action::Character('o')).unwrap();
+        assert_eq!(octets(&app), [1, 3, 5]);
+        assert_eq!(app.export_scope(), "3 host(s), offline left out");
+
+        app.handle_action(Action::Character('p')).unwrap();
+        assert_eq!(octets(&app), [1]);
+        assert_eq!(app.export_scope(), "1 host(s) with open ports, offline left out");
+
+        app.handle_action(Action::Character('o')).unwrap();
+        assert_eq!(octets(&app), [1, 4]);
+        assert_eq!(app.export_scope(), "2 host(s) with open ports");
+
+        // Nothing left to export keeps the dialog open
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 3));
+        app.handle_action(Action::Character('c')).unwrap();
+        assert_eq!(app.input_mode, InputMode::Exporting);
+
+        let saved = crate::settings::load_settings();
+        assert_eq!(saved.export_offline, Some(true));
+        assert_eq!(saved.export_open_ports_only, Some(true));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn export_with_no_hosts_shows_message_instead_of_overlay() {
+        let mut app = app_with_hosts(0);
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 9));
+        app.handle_action(Action::Export).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.selected_hosts.is_empty());
+        let note = app.notifications.current().unwrap();
+        assert_eq!(note.severity, Severity::Info);
+        assert!(note.text.starts_with("Nothing to export"));
+    }
+
+    #[tokio::test]
+    async fn new_scan_over_a_paused_one_asks_first() {
+        let mut app = app_with_hosts(4);
+        app.range_input = "127.0.0.1".to_string();
+        app.scan_state = ScanState::Paused;
+        app.scan_total = 254;
+        app.scan_completed = 4;
+        app.offline_dropped = 2;
+
+        // Backing out keeps the paused scan as it was
+        assert!(app.handle_action(Action::StartScan).unwrap().is_none());
+        assert_eq!(app.input_mode, InputMode::ConfirmNewScan);
+        assert!(app.handle_action(Action::Cancel).unwrap().is_none());
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.scan_state, ScanState::Paused);
+        assert_eq!((app.scan_completed, app.scan_total, app.offline_dropped), (4, 254, 2));
+        assert_eq!(app.hosts.len(), 4);
+
+        // Enter resumes instead
+        app.handle_action(Action::StartScan).unwrap();
+        let command = app.handle_action(Action::Character('r')).unwrap();
+        assert!(matches!(command, Some(AppCommand::ResumeScan)));
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // Confirming starts over from zero
+        app.handle_action(Action::StartScan).unwrap();
+        let command = app.handle_action(Action::Confirm).unwrap();
+        assert!(matches!(command, Some(AppCommand::StartScan)));
+        app.start_scan().await.unwrap();
+        assert_eq!((app.scan_completed, app.scan_total, app.offline_dropped), (0, 1, 0));
+        assert_eq!(app.scan_state, ScanState::Scanning);
+    }
+
+    #[tokio::test]
+    async fn status_shows_the_rate_while_probes_wait() {
+        let mut config = Config::default();
+        config.limit_rate(1000);
+        let app = App::new(config);
+        assert_eq!(app.rate_text(), None);
+
+        // One over the tenth of a second's burst has to wait
+        let limit = app.config.ping.rate_limit.clone().unwrap();
+        for _ in 0..101 {
+            limit.acquire().await;
+        }
+        assert_eq!(app.rate_text().as_deref(), Some("throttled 1000/s"));
+        assert!(Arc::ptr_eq(&limit, app.config.port_scan.rate_limit.as_ref().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn scan_start_error_stays_until_range_is_edited() {
+        let mut app = app_with_hosts(0);
+        app.focus = Focus::RangeInput;
+        app.range_input = "10.0.0.300".to_string();
+        let err = app.start_scan().await.unwrap_err();
+        app.scan_start_failed(err);
+        assert!(app.input_error.as_deref().unwrap().contains("10.0.0.300"));
+        assert!(app.notifications.current().is_none());
+        assert_eq!(app.focus, Focus::RangeInput);
+
+        // Keys that leave the range alone keep the error visible
+        app.handle_action(Action::EditRange).unwrap();
+        app.handle_action(Action::NavigateHome).unwrap();
+        assert!(app.input_error.is_some());
+
+        app.handle_action(Action::Delete).unwrap();
+        assert_eq!(app.input_mode, InputMode::EditingRange);
+        assert!(app.input_error.is_none());
+    }
+
+    /// Canned answers standing in for ping, reverse DNS, NetBIOS and the
+    /// ARP table
+    #[derive(Default)]
+    struct FakeNetwork {
+        /// Hosts that answer, with their reply time in milliseconds
+        up: HashMap<Ipv4Addr, u64>,
+        names: HashMap<Ipv4Addr, &'static str>,
+        netbios: HashMap<Ipv4Addr, &'static str>,
+        macs: HashMap<Ipv4Addr, &'static str>,
+        /// Offline hosts whose probes fail with "network unreachable"
+        /// rather than time out
+        unreachable: HashSet<Ipv4Addr>,
+    }
+
+    impl HostProbe for FakeNetwork {
+        fn ping(&self, ip: Ipv4Addr) -> crate::scanner::BoxFuture<'_, PingResult> {
+            let reply = self.up.get(&ip).map(|&ms| Duration::from_millis(ms));
+            let error = match reply {
+                Some(_) => None,
+                None if self.unreachable.contains(&ip) => Some(ProbeError::NetworkUnreachable),
+                None => Some(ProbeError::Timeout),
+            };
+            Box::pin(async move {
+                if let Some(rtt) = reply {
+                    tokio::time::sleep(rtt).await;
+                }
+                PingResult {
+                    ip,
+                    is_alive: reply.is_some(),
+                    rtt: reply,
+                    method: PingMethod::Icmp,
+                    status: if reply.is_some() {
+                        HostStatus::Online
+                    } else {
+                        HostStatus::Offline
+                    },
+                    probe: reply.map(|_| Probe {
+                        port: None,
+                        attempt: 1,
+                        suspect: false,
+                    }),
+                    error,
+                }
+            })
+        }
+    }
+
+    impl NameResolver for FakeNetwork {
+        fn resolve(&self, ip: Ipv4Addr) -> crate::scanner::BoxFuture<'_, Resolution> {
+            let resolution = match self.names.get(&ip) {
+                Some(name) => Resolution::Resolved(name.to_string()),
+                None => Resolution::NoRecord,
+            };
+            Box::pin(async move { resolution })
+        }
+
+        fn refresh(&self, ip: Ipv4Addr) -> crate::scanner::BoxFuture<'_, Resolution> {
+            self.resolve(ip)
+        }
+    }
+
+    impl NetbiosLookup for FakeNetwork {
+        fn name(&self, ip: Ipv4Addr) -> crate::scanner::BoxFuture<'_, Option<String>> {
+            let name = self.netbios.get(&ip).map(|name| name.to_string());
+            Box::pin(async move { name })
+        }
+    }
+
+    impl MacLookup for FakeNetwork {
+        fn lookup(
+            &self,
+            ip: Ipv4Addr,
+            _prewarm: bool,
+        ) -> crate::scanner::BoxFuture<'_, Option<MacInfo>> {
+            let mac = self.macs.get(&ip).map(|address| MacInfo {
+                address: address.to_string(),
+                vendor: None,
+            });
+            Box::pin(async move { mac })
+        }
+    }
+
+    #[tokio::test]
+    async fn mac_refresh_reads_the_arp_table_again() {
+        let mut app = app_with_hosts(3);
+        let network = Arc::new(FakeNetwork {
+            macs: HashMap::from([(Ipv4Addr::new(192, 168, 1, 1), "F0:27:2D:00:00:01")]),
+            ..FakeNetwork::default()
+        });
+        app.probes.mac = network;
+        let command = app.handle_action(Action::RefreshMac).unwrap();
+        assert!(matches!(command, Some(AppCommand::RefreshMac(ip)) if ip == app.hosts[0].ip));
+
+        let (tx, mut rx) = mpsc::channel(1);
+        app.spawn_mac_refresh(app.hosts[0].ip, tx);
+        let (ip, mac) = rx.recv().await.expect("lookup answered");
+        app.apply_mac_refresh(ip, mac);
+        let mac = app.hosts[0].mac.as_ref().expect("MAC found");
+        assert_eq!(mac.address, "F0:27:2D:00:00:01");
+
+        // A miss keeps the known MAC and fills in its vendor
+        app.hosts[1].mac = Some(MacInfo {
+            address: "f0-27-2d-00-00-02".to_string(),
+            vendor: None,
+        });
+        app.apply_mac_refresh(app.hosts[1].ip, None);
+        let mac = app.hosts[1].mac.as_ref().expect("MAC kept");
+        assert_eq!(mac.vendor.as_deref(), Some("eero"));
+
+        // ARP can't see past a router
+        app.adapters = vec![AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: Ipv4Addr::new(10, 0, 0, 10),
+            prefix_length: 24,
+            subnet: "10.0.0.0/24".to_string(),
+        }];
+        assert!(app.handle_action(Action::RefreshMac).unwrap().is_none());
+    }
+
+    #[test]
+    fn fake_scan_runs_through_the_whole_pipeline() {
+        // The env lock is held outside the runtime: the scan awaits under it
+        let _guard = crate::cache::tests::env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_app_fake_scan_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var("IPSCANNR_CACHE_FILE", &temp_path);
+        }
+        let runtime = tokio::runtime::Runtime::new().expect("runtime");
+        let (mut app, answered) = runtime.block_on(fake_scan());
+
+        // Listed as they answer, then sorted online first once complete
+        assert_eq!(answered, [3, 5, 1]);
+        assert_eq!(app.scan_state, ScanState::Completed);
+        assert_eq!((app.scan_completed, app.scan_total, app.enrichment_pending), (6, 6, 0));
+        let octets: Vec<u8> = app.hosts.iter().map(|h| h.ip.octets()[3]).collect();
+        assert_eq!(octets, [1, 3, 5, 2, 4, 6]);
+        assert_eq!(app.hosts[0].hostname.as_deref(), Some("router.lan"));
+        assert_eq!(app.hosts[1].dns, Some(Resolution::NoRecord));
+        // NetBIOS names a host without a PTR record, and outranks one by default
+        assert_eq!(app.hosts[1].hostname.as_deref(), Some("DESKTOP-03"));
+        assert_eq!(app.hosts[2].names.ptr.as_deref(), Some("nas.lan"));
+        assert_eq!(app.hosts[2].hostname.as_deref(), Some("NAS"));
+        let mac = app.hosts[1].mac.as_ref().map(|m| m.address.as_str());
+        assert_eq!(mac, Some("AA:BB:CC:DD:EE:03"));
+        assert!(app.hosts[2].mac.is_none());
+        assert_eq!(app.hosts[5].probe_error, Some(ProbeError::NetworkUnreachable));
+        assert_eq!(app.hosts[4].probe_error, Some(ProbeError::Timeout));
+        let note = "Offline: 2 timeout, 1 network unreachable";
+        assert!(app.event_log.newest_first(None).any(|e| e.message == note));
+
+        app.handle_action(Action::ToggleFilter).unwrap();
+        assert_eq!(app.filtered_hosts.len(), 3);
+
+        let mut cached = crate::cache::load_cache("10.0.0.1-6");
+        cached.retain(|h| h.is_alive);
+        cached.sort_by_key(|h| h.ip);
+        let saved: Vec<(u8, Option<&str>, Option<&str>)> = cached
+            .iter()
+            .map(|h| {
+                let mac = h.mac.as_ref().map(|m| m.address.as_str());
+                (h.ip.octets()[3], h.hostname.as_deref(), mac)
+            })
+            .collect();
+        assert_eq!(
+            saved,
+            [
+                (1, Some("router.lan"), Some("AA:BB:CC:DD:EE:01")),
+                (3, Some("DESKTOP-03"), Some("AA:BB:CC:DD:EE:03")),
+                (5, Some("NAS"), None),
+            ]
+        );
+
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::remove_var("IPSCANNR_CACHE_FILE");
+        }
+    }
+
+    /// Scan 10.0.0.1-6 against a [`FakeNetwork`] where .1, .3 and .5 answer;
+    /// also returns the last octets of the online hosts in discovery order
+    async fn fake_scan() -> (App, Vec<u8>) {
+        let ip = |last_octet| Ipv4Addr::new(10, 0, 0, last_octet);
+        let network = Arc::new(FakeNetwork {
+            up: HashMap::from([(ip(1), 80), (ip(3), 1), (ip(5), 40)]),
+            names: HashMap::from([(ip(1), "router.lan"), (ip(5), "nas.lan")]),
+            netbios: HashMap::from([(ip(3), "DESKTOP-03"), (ip(5), "NAS")]),
+            macs: HashMap::from([(ip(1), "AA:BB:CC:DD:EE:01"), (ip(3), "AA:BB:CC:DD:EE:03")]),
+            unreachable: HashSet::from([ip(6)]),
+        });
+        let mut app = app_with_hosts(0);
+        app.probes = Probes {
+            ping: Some(network.clone()),
+            names: network.clone(),
+            netbios: network.clone(),
+            mac: network,
+        };
+        app.adapters = vec![AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: ip(10),
+            prefix_length: 24,
+            subnet: "10.0.0.0/24".to_string(),
+        }];
+        app.range_input = "10.0.0.1-6".to_string();
+
+        let mut rx = app.start_scan().await.unwrap();
+        assert_eq!((app.scan_completed, app.scan_total), (0, 6));
+        let mut answered = Vec::new();
+        while let Some(event) = rx.recv().await {
+            if let ScanEvent::HostDiscovered(host) = &event {
+                if host.is_alive {
+                    answered.push(host.ip.octets()[3]);
+                }
+            }
+            app.handle_scan_event(event);
+        }
+        (app, answered)
+    }
+
+    #[test]
+    fn non_local_range_warns_until_edited() {
+        let mut app = app_with_hosts(0);
+        let remote = [Ipv4Addr::new(172, 16, 0, 1), Ipv4Addr::new(172, 16, 0, 2)];
+        // Adapters unknown: no basis for a warning
+        assert!(app.non_local_warning(&remote).is_none());
+
+        app.adapters = vec![AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: Ipv4Addr::new(192, 168, 1, 10),
+            prefix_length: 24,
+            subnet: "192.168.1.0/24".to_string(),
+        }];
+        app.range_input = "172.16.0.1-2".to_string();
+        let warning = app.non_local_warning(&remote).unwrap();
+        assert!(warning.starts_with("Range 172.16.0.1-2 is not on any local subnet"));
+        let mixed = [remote[0], Ipv4Addr::new(192, 168, 1, 20)];
+        assert!(app.non_local_warning(&mixed).is_none());
+
+        app.range_warning = Some(warning);
+        app.focus = Focus::RangeInput;
+        app.handle_action(Action::EditRange).unwrap();
+        assert!(app.range_warning.is_some());
+        app.handle_action(Action::Backspace).unwrap();
+        assert!(app.range_warning.is_none());
+    }
+
+    #[test]
+    fn large_ranges_ask_before_scanning() {
+        let mut app = app_with_hosts(0);
+        app.focus = Focus::RangeInput;
+        app.handle_action(Action::EditRange).unwrap();
+        app.handle_action(Action::ClearField).unwrap();
+        assert_eq!(app.range_size, None);
+        for c in "10.0.0.0/14".chars() {
+            app.handle_action(Action::Character(c)).unwrap();
+        }
+        assert_eq!(app.range_size, Some(262_144));
+
+        let command = app.handle_action(Action::Select).unwrap();
+        assert!(command.is_none());
+        assert_eq!(app.input_mode, InputMode::ConfirmLargeScan);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        app.handle_action(Action::StartScan).unwrap();
+        let command = app.handle_action(Action::Confirm).unwrap();
+        assert!(matches!(command, Some(AppCommand::StartScan)));
+
+        // 262144 / 100 at a time, 300 ms each
+        assert_eq!(app.scan_estimate(262_144), Duration::from_millis(300 * 2622));
+        assert_eq!(estimate_text(app.scan_estimate(262_144)), "about 13 min");
+        app.config.limit_rate(100);
+        assert_eq!(estimate_text(app.scan_estimate(262_144)), "about 43 min");
+
+        // Batch ranges count shared addresses once; --yes never asks
+        app.range_input = "10.0.0.0/24; 10.0.0.128/25".to_string();
+        app.handle_action(Action::EditRange).unwrap();
+        app.handle_action(Action::Character('7')).unwrap();
+        app.handle_action(Action::Backspace).unwrap();
+        assert_eq!(app.range_size, Some(256));
+        app.range_size = Some(1 << 20);
+        app.config.confirm_scan_above = None;
+        assert!(matches!(app.request_start(), Some(AppCommand::StartScan)));
+    }
+
+    #[test]
+    fn probes_go_out_from_the_chosen_adapter_or_source_ip() {
+        let mut app = app_with_hosts(0);
+        app.adapters = vec![AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: Ipv4Addr::new(192, 168, 1, 10),
+            prefix_length: 24,
+            subnet: "192.168.1.0/24".to_string(),
+        }];
+        let local = [Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 2)];
+        let remote = Ipv4Addr::new(172, 16, 0, 1);
+        // Custom range: the OS picks
+        assert_eq!(app.probe_source(local.into_iter()), None);
+        app.adapter_index = Some(0);
+        assert_eq!(app.probe_source(local.into_iter()), Some(Ipv4Addr::new(192, 168, 1, 10)));
+        assert_eq!(app.probe_source(local.into_iter().chain([remote])), None);
+
+        app.config.send_from(Ipv4Addr::LOCALHOST);
+        assert_eq!(app.probe_source([remote].into_iter()), Some(Ipv4Addr::LOCALHOST));
+        assert!(app.check_source_ip().is_ok());
+        // TEST-NET-1 is never assigned to a local interface
+        app.config.send_from(Ipv4Addr::new(192, 0, 2, 1));
+        let error = format!("{:#}", app.check_source_ip().unwrap_err());
+        assert!(error.starts_with("--source-ip: can't send from 192.0.2.1"), "{}", error);
+    }
+
+    #[test]
+    fn rescanning_an_overlapping_range_keeps_other_hosts_and_enrichment() {
+        let mut app = app_with_hosts(6);
+        app.hosts[1].hostname = Some("nas.lan".to_string());
+        app.hosts[1].open_ports = vec![22, 445];
+        app.hosts[1].ports_scanned = true;
+        app.hosts[1].mac = Some(MacInfo {
+            address: "AA:BB:CC:DD:EE:02".to_string(),
+            vendor: None,
+        });
+        app.hosts[5].hostname = Some("printer.lan".to_string());
+        app.selected_hosts = [1, 2, 5].into_iter().map(|i| Ipv4Addr::new(192, 168, 1, i)).collect();
+
+        let groups = app.plan_batch("192.168.1.2-4").unwrap();
+        app.clear_rescanned_hosts();
+        let octets = |app: &App| -> Vec<u8> { app.hosts.iter().map(|h| h.ip.octets()[3]).collect() };
+        assert_eq!(octets(&app), [1, 5, 6]);
+        assert_eq!(app.filtered_hosts, [0, 1, 2]);
+        let mut selected: Vec<u8> = app.selected_hosts.iter().map(|ip| ip.octets()[3]).collect();
+        selected.sort();
+        assert_eq!(selected, [1, 5]);
+
+        app.scan_total = groups[0].len();
+        for ip in &groups[0] {
+            let mut result = host(ip.octets()[3], true);
+            if ip.octets()[3] == 3 {
+                result.hostname = Some("fresh.lan".to_string());
+            }
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(result)));
+        }
+        let nas = app.hosts.iter().find(|h| h.ip.octets()[3] == 2).unwrap();
+        assert_eq!(nas.hostname.as_deref(), Some("nas.lan"));
+        assert_eq!(nas.open_ports, [22, 445]);
+        assert!(nas.ports_scanned && nas.mac.is_some());
+        let fresh = app.hosts.iter().find(|h| h.ip.octets()[3] == 3).unwrap();
+        assert_eq!(fresh.hostname.as_deref(), Some("fresh.lan"));
+        let printer = app.hosts.iter().find(|h| h.ip.octets()[3] == 6).unwrap();
+        assert_eq!(printer.hostname.as_deref(), Some("printer.lan"));
+
+        // Only the rescanned range counts as this scan's results
+        assert_eq!(app.completion_summary(), "3 hosts (3 online)");
+        let mut results: Vec<u8> = app.scan_results().iter().map(|h| h.ip.octets()[3]).collect();
+        results.sort();
+        assert_eq!(results, [2, 3, 4]);
+    }
+
+    #[test]
+    fn rescans_feed_the_change_ticker() {
+        let mut app = app_with_hosts(0);
+        let now = crate::cache::now_secs();
+        let ip = |i| Ipv4Addr::new(192, 168, 1, i);
+        app.state_before = [(ip(1), (true, now - 600)), (ip(2), (false, now - 600)), (ip(3), (true, now - 600))]
+            .into_iter()
+            .collect();
+        for (i, alive) in [(1, false), (2, true), (3, true)] {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(i, alive))));
+        }
+        let changes: Vec<(u8, bool)> =
+            app.recent_changes.iter().map(|c| (c.ip.octets()[3], c.up)).collect();
+        assert_eq!(changes, [(2, true), (1, false)]);
+
+        let ticker = |app: &App, now| -> Vec<u8> {
+            app.ticker(now).iter().map(|c| c.ip.octets()[3]).collect()
+        };
+        assert_eq!(ticker(&app, now), [2, 1]);
+        app.dirty = false;
+        app.tick_animation_at(app.animation_epoch + TICKER_STEP);
+        assert!(app.dirty, "rotating redraws even when idle");
+        assert_eq!(ticker(&app, now), [1, 2]);
+        assert!(ticker(&app, now + TICKER_MAX_AGE + 60).is_empty());
+
+        // [H] walks the changes from the newest, showing hidden hosts
+        app.filter_mode = FilterMode::OnlineOnly;
+        app.update_filtered_hosts();
+        let selected = |app: &App| app.selected_host().map(|h| h.ip.octets()[3]);
+        app.handle_action(Action::JumpToChange).unwrap();
+        assert_eq!(selected(&app), Some(2));
+        app.handle_action(Action::JumpToChange).unwrap();
+        assert_eq!(selected(&app), Some(1));
+        assert_eq!(app.filter_mode, FilterMode::All);
+        app.handle_action(Action::JumpToChange).unwrap();
+        assert_eq!(selected(&app), Some(2));
+    }
+
+    #[test]
+    fn ctrl_backspace_deletes_one_octet_at_a_time() {
+        let mut app = app_with_hosts(0);
+        app.focus = Focus::RangeInput;
+        app.range_input = "10.123.240.0/22".to_string();
+        app.handle_action(Action::EditRange).unwrap();
+        assert_eq!(app.input_mode, InputMode::EditingRange);
+        app.range_cursor = app.range_input.len();
+
+        app.handle_action(Action::DeleteWord).unwrap();
+        assert_eq!(app.range_input, "10.123.240.0/");
+        for _ in 0..2 {
+            app.handle_action(Action::WordLeft).unwrap();
+        }
+        assert_eq!(app.range_cursor, 7);
+        app.handle_action(Action::WordRight).unwrap();
+        assert_eq!(app.range_cursor, 11);
+        // Back over the dot, then delete "240"
+        app.handle_action(Action::NavigateUp).unwrap();
+        app.handle_action(Action::DeleteWord).unwrap();
+        assert_eq!(app.range_input, "10.123..0/");
+        assert_eq!(app.range_cursor, 7);
+        app.handle_action(Action::Character('8')).unwrap();
+        assert_eq!(app.range_input, "10.123.8.0/");
+
+        app.handle_action(Action::ClearField).unwrap();
+        assert_eq!((app.range_input.as_str(), app.range_cursor), ("", 0));
+    }
+
+    #[test]
+    fn pastes_go_into_the_field_being_edited() {
+        let mut app = app_with_hosts(3);
+        app.focus = Focus::HostsTable;
+        app.table_state.select(Some(2));
+        let range = app.range_input.clone();
+        // Not a text field: neither typed into the range nor run as "gg"
+        app.handle_paste("gg").unwrap();
+        assert_eq!(app.range_input, range);
+        assert_eq!(app.table_state.selected(), Some(2));
+
+        app.focus = Focus::RangeInput;
+        app.range_input.clear();
+        app.handle_paste("10.0.0.0/24\r\n").unwrap();
+        assert_eq!(app.input_mode, InputMode::EditingRange);
+        assert_eq!(app.range_input, "10.0.0.0/24");
+        assert_eq!(app.range_cursor, app.range_input.len());
+
+        app.range_cursor = 3;
+        app.handle_paste("1.2\n.").unwrap();
+        assert_eq!(app.range_input, "10.1.2.0.0.0/24");
+        assert_eq!(app.range_cursor, 7);
+
+        app.input_mode = InputMode::EditingPorts;
+        app.ports_input = "22,443".to_string();
+        app.ports_cursor = 3;
+        app.handle_paste("80,").unwrap();
+        assert_eq!(app.ports_input, "22,80,443");
+
+        // Non-ASCII would leave the cursor off a char boundary
+        app.input_mode = InputMode::EditingRange;
+        app.range_input.clear();
+        app.range_cursor = 0;
+        app.handle_paste("10.0.0.1–20 ✓").unwrap();
+        app.handle_action(Action::Character('0')).unwrap();
+        assert_eq!(app.range_input, "10.0.0.120 0");
+        assert_eq!(app.range_cursor, app.range_input.len());
+
+        app.input_mode = InputMode::Help;
+        app.handle_paste("8080").unwrap();
+        assert_eq!(app.ports_input, "22,80,443");
+    }
+
+    #[test]
+    fn range_keywords_pick_an_adapter_once_loaded() {
+        use crate::scanner::adapters::AdapterType;
+        let adapter = |name: &str, adapter_type, third: u8| AdapterInfo {
+            name: name.to_string(),
+            adapter_type,
+            ip: Ipv4Addr::new(10, 0, third, 10),
+            prefix_length: 24,
+            subnet: format!("10.0.{}.0/24", third),
+        };
+        let adapters = vec![
+            adapter("Ethernet", AdapterType::Ethernet, 1),
+            adapter("Wi-Fi", AdapterType::Wifi, 2),
+        ];
+        let load = |range: &str| {
+            let range_spec = RangeSpec::parse(range);
+            let default_range = match &range_spec {
+                RangeSpec::Literal(range) => range.clone(),
+                _ => Config::default().default_range,
+            };
+            let mut app = App::new(Config {
+                range_spec,
+                default_range,
+                ..Config::default()
+            });
+            let result = app.set_adapters(adapters.clone());
+            (app, result)
+        };
+
+        let (app, result) = load("auto");
+        assert!(result.is_ok());
+        assert_eq!((app.adapter_index, app.range_input.as_str()), (Some(0), "10.0.1.0/24"));
+        let (app, _) = load("WiFi");
+        assert_eq!((app.adapter_index, app.range_input.as_str()), (Some(1), "10.0.2.0/24"));
+        // A literal range is kept, and still recognised as an adapter's subnet
+        let (app, _) = load("10.0.2.0/24");
+        assert_eq!((app.adapter_index, app.range_input.as_str()), (Some(1), "10.0.2.0/24"));
+        let (app, _) = load("172.16.0.0/24");
+        assert_eq!((app.adapter_index, app.range_input.as_str()), (None, "172.16.0.0/24"));
+
+        let (app, result) = load("vpn");
+        assert_eq!(result.unwrap_err().to_string(), "--range vpn: no VPN adapter is up");
+        assert_eq!(app.range_input, "10.0.1.0/24", "falls back to the best adapter");
+    }
+
+    #[test]
+    fn typed_adapter_subnet_reselects_the_adapter() {
+        let mut app = app_with_hosts(0);
+        app.adapters = vec![AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: Ipv4Addr::new(192, 168, 1, 10),
+            prefix_length: 24,
+            subnet: "192.168.1.0/24".to_string(),
+        }];
+        app.focus = Focus::RangeInput;
+        let type_range = |app: &mut App, range: &str| {
+            app.handle_action(Action::EditRange).unwrap();
+            assert_eq!(app.adapter_index, None);
+            app.range_input.clear();
+            app.range_cursor = 0;
+            for c in range.chars() {
+                app.handle_action(Action::Character(c)).unwrap();
             }
-        });
+            app.handle_action(Action::Cancel).unwrap();
+        };
 
-        Ok(event_rx)
+        type_range(&mut app, "192.168.1.77/24");
+        assert_eq!(app.adapter_index, Some(0));
+        assert_eq!(app.range_input, "192.168.1.77/24");
+
+        type_range(&mut app, "192.168.1.0/25");
+        assert_eq!(app.adapter_index, None);
     }
 
-    pub fn handle_scan_event(&mut self, event: ScanEvent) {
-        match event {
-            ScanEvent::HostDiscovered(host) => {
-                self.hosts.push(host);
-                self.scan_completed += 1;
-                self.update_filtered_hosts();
+    #[test]
+    fn waking_a_selection_runs_as_one_job() {
+        let mut app = app_with_hosts(3);
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 1));
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 2));
+        let command = app.handle_action(Action::WakeOnLan).unwrap();
+        assert!(matches!(command, Some(AppCommand::WakeSelected)));
+        // Neither has a MAC: nothing to send
+        assert!(app.start_wake().is_none());
+        assert!(app.wake_job.is_none());
 
-                // Auto-select first host
-                if self.table_state.selected().is_none() && !self.filtered_hosts.is_empty() {
-                    self.table_state.select(Some(0));
-                }
-            }
-            ScanEvent::ScanComplete => {
-                if self.scan_state != ScanState::Paused {
-                    self.scan_state = ScanState::Completed;
-                    // Persist results so they're available at next startup
-                    crate::cache::save_cache(&self.range_input, &self.hosts);
-                }
-                self.scan_cancel_tx = None;
+        let (cancel_tx, mut cancel_rx) = mpsc::channel(1);
+        let ips = [Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 2)];
+        app.wake_job = Some(WakeJob::new(&ips, vec![Ipv4Addr::new(192, 168, 1, 3)], true, cancel_tx));
+        app.apply_wake_event(wol::WakeEvent::Sent(ips[0]));
+        assert_eq!(app.wake_text().as_deref(), Some("WOL 1/2"));
 
-                // Sort: online hosts first, then by IP within each group
-                self.hosts.sort_by(|a, b| {
-                    b.is_alive.cmp(&a.is_alive).then_with(|| a.ip.cmp(&b.ip))
-                });
-                self.update_filtered_hosts();
-            }
-        }
+        // [W] again stops the run instead of starting another
+        assert!(app.handle_action(Action::WakeOnLan).unwrap().is_none());
+        assert!(cancel_rx.try_recv().is_ok());
+        app.wake_finished();
+        assert_eq!(app.input_mode, InputMode::WakeReport);
+        assert_eq!(app.summary_lines[0].1, "1 not answering, 1 not sent, 1 no MAC");
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
     }
 
-    /// Start a background port scan for the currently selected host.
-    /// Cancels any in-progress port scan first. Returns a receiver that
-    /// yields `(ip, open_ports)` when the scan completes.
-    pub fn start_port_scan_for_selected(&mut self) -> Option<mpsc::Receiver<(Ipv4Addr, Vec<u16>)>> {
-        // Cancel any in-progress scan
-        if let Some(tx) = self.port_scan_cancel_tx.take() {
-            let _ = tx.try_send(());
-        }
+    #[test]
+    fn picking_an_adapter_checks_its_gateway_once() {
+        let mut app = app_with_hosts(0);
+        let adapter = |last: u8| AdapterInfo {
+            name: format!("Ethernet {}", last),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: Ipv4Addr::new(10, 0, last, 10),
+            prefix_length: 24,
+            subnet: format!("10.0.{}.0/24", last),
+        };
+        app.adapters = vec![adapter(1), adapter(2)];
+        app.focus = Focus::RangeInput;
 
-        let host = self.selected_host()?;
-        if !host.is_alive {
-            self.port_scanning = false;
-            return None;
-        }
+        let command = app.handle_action(Action::NavigateDown).unwrap();
+        assert!(matches!(command, Some(AppCommand::CheckGateway(ref a)) if a.ip == Ipv4Addr::new(10, 0, 1, 10)));
+        assert_eq!(app.gateway_checks[&Ipv4Addr::new(10, 0, 1, 10)], GatewayCheck::Checking);
+        assert!(app.handle_action(Action::NavigateDown).unwrap().is_some());
+        // Custom range, then back to adapters already checked
+        assert!(app.handle_action(Action::NavigateDown).unwrap().is_none());
+        assert!(app.handle_action(Action::NavigateDown).unwrap().is_none());
+        assert!(app.handle_action(Action::NavigateUp).unwrap().is_none());
+        assert_eq!(app.gateway_checks.len(), 2);
 
-        let ip = host.ip;
-        let config = self.config.port_scan.clone();
+        let mut quiet = app_with_hosts(0);
+        quiet.config.connectivity_check = false;
+        quiet.adapters = vec![adapter(1)];
+        quiet.focus = Focus::RangeInput;
+        assert!(quiet.handle_action(Action::NavigateDown).unwrap().is_none());
+        assert!(quiet.gateway_checks.is_empty());
+    }
 
-        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
-        let (result_tx, result_rx) = mpsc::channel::<(Ipv4Addr, Vec<u16>)>(1);
+    #[test]
+    fn names_from_each_source_are_kept_apart() {
+        let mut app = app_with_hosts(1);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        app.apply_announcement(Announcement {
+            ip,
+            name: Some("Living-Room-TV.local".to_string()),
+            services: Vec::new(),
+        });
+        app.handle_scan_event(ScanEvent::HostUpdated {
+            ip,
+            dns: Resolution::Resolved("dhcp-42.lan".to_string()),
+            netbios: None,
+            mac: None,
+        });
+        let host = &app.hosts[0];
+        assert_eq!(host.names.mdns.as_deref(), Some("Living-Room-TV.local"));
+        assert_eq!(host.names.ptr.as_deref(), Some("dhcp-42.lan"));
+        // mDNS outranks PTR by default
+        assert_eq!(host.hostname.as_deref(), Some("Living-Room-TV.local"));
 
-        self.port_scan_cancel_tx = Some(cancel_tx);
-        self.port_scanning = true;
+        app.config.name_precedence = vec![NameSource::Ptr, NameSource::Mdns];
+        app.handle_scan_event(ScanEvent::HostUpdated {
+            ip,
+            dns: Resolution::Timeout,
+            netbios: None,
+            mac: None,
+        });
+        assert_eq!(app.hosts[0].hostname.as_deref(), Some("dhcp-42.lan"));
+    }
 
-        tokio::spawn(async move {
-            let scanner = PortScanner::new(config);
-            tokio::select! {
-                _ = cancel_rx.recv() => {}
-                results = scanner.scan_ports(ip, COMMON_PORTS) => {
-                    let open_ports: Vec<u16> = results
-                        .into_iter()
-                        .filter(|r| r.is_open)
-                        .map(|r| r.port)
-                        .collect();
-                    let _ = result_tx.send((ip, open_ports)).await;
-                }
-            }
+    #[test]
+    fn aliases_outrank_found_names_until_removed() {
+        let mut app = app_with_hosts(0);
+        let ip = Ipv4Addr::new(192, 168, 1, 9);
+        app.config.aliases = HashMap::from([(ip, "Lounge TV".to_string())]);
+        app.apply_announcement(Announcement {
+            ip,
+            name: Some("Living-Room-TV.local".to_string()),
+            services: Vec::new(),
         });
+        app.handle_scan_event(ScanEvent::HostUpdated {
+            ip,
+            dns: Resolution::NoRecord,
+            netbios: Some("LGTV".to_string()),
+            mac: None,
+        });
+        let host = &app.hosts[0];
+        assert_eq!(host.hostname.as_deref(), Some("Lounge TV"));
+        assert_eq!(host.names.netbios.as_deref(), Some("LGTV"));
 
-        Some(result_rx)
+        // Taken out of the settings: the next best name shows
+        let mut host = host.clone();
+        host.apply_alias(&HashMap::new(), &NameSource::DEFAULT_ORDER);
+        assert_eq!(host.names.alias, None);
+        assert_eq!(host.hostname.as_deref(), Some("Living-Room-TV.local"));
+        let mut unnamed = HostInfo {
+            hostname: Some("Lounge TV".to_string()),
+            names: Names {
+                alias: Some("Lounge TV".to_string()),
+                ..Names::default()
+            },
+            ..HostInfo::pinged(ip, true)
+        };
+        unnamed.apply_alias(&HashMap::new(), &NameSource::DEFAULT_ORDER);
+        assert_eq!(unnamed.hostname, None);
     }
 
-    /// Send a Wake-on-LAN magic packet to the selected host's MAC address
-    pub fn send_wol(&self) -> Result<Option<String>> {
-        let Some(host) = self.selected_host() else {
-            return Ok(None);
+    #[test]
+    fn offline_host_keeps_last_seen_from_earlier_scan() {
+        let mut app = app_with_hosts(0);
+        app.scan_total = 2;
+        app.seen_before.insert(Ipv4Addr::new(192, 168, 1, 2), 1_700_000_000);
+
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(1, true))));
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(2, false))));
+        assert!(app.hosts[0].last_seen.unwrap() > 1_700_000_000);
+        assert_eq!(app.hosts[1].last_seen, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn announced_devices_merge_with_scan_results() {
+        let announce = |octet: u8, name: Option<&str>, service: &str| Announcement {
+            ip: Ipv4Addr::new(192, 168, 1, octet),
+            name: name.map(str::to_string),
+            services: vec![service.to_string()],
         };
-        let Some(mac) = &host.mac else {
-            return Ok(Some(format!(
-                "No MAC address for {} — WOL unavailable",
-                host.ip
-            )));
+        let mut app = app_with_hosts(2);
+        app.apply_announcement(announce(1, Some("Office Printer"), "ipp"));
+        assert_eq!(app.hosts[0].hostname.as_deref(), Some("Office Printer"));
+        assert_eq!(app.hosts[0].services, ["ipp"]);
+
+        // An unknown responder is listed as online, in table order
+        app.apply_announcement(announce(50, Some("Living Room TV"), "MediaRenderer"));
+        app.apply_announcement(announce(50, None, "airplay"));
+        assert_eq!(app.hosts.len(), 3);
+        assert_eq!(app.hosts[1].ip, Ipv4Addr::new(192, 168, 1, 50));
+        assert!(app.hosts[1].is_alive);
+        assert_eq!(app.hosts[1].services, ["MediaRenderer", "airplay"]);
+        assert_eq!(app.selected_host().unwrap().ip, Ipv4Addr::new(192, 168, 1, 1));
+
+        // A later scan replaces the entry instead of listing it twice
+        app.scan_state = ScanState::Scanning;
+        app.scan_total = 1;
+        let mut scanned = host(50, true);
+        scanned.rtt = Some(Duration::from_millis(4));
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(scanned)));
+        assert_eq!(app.hosts.len(), 3);
+        let device = &app.hosts[1];
+        assert_eq!(device.rtt, Some(Duration::from_millis(4)));
+        assert_eq!(device.hostname.as_deref(), Some("Living Room TV"));
+        assert_eq!(device.services, ["MediaRenderer", "airplay"]);
+    }
+
+    #[test]
+    fn uptime_start_survives_rescans_until_the_state_changes() {
+        let mut app = app_with_hosts(0);
+        app.scan_total = 4;
+        let ip = |octet| Ipv4Addr::new(192, 168, 1, octet);
+        app.state_before.insert(ip(1), (true, 1_700_000_000));
+        app.state_before.insert(ip(2), (true, 1_700_000_000));
+        app.seen_before.insert(ip(4), 1_600_000_000);
+
+        for (octet, up) in [(1, true), (2, false), (3, true), (4, false)] {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(octet, up))));
+        }
+        let since: Vec<Option<u64>> = app.hosts.iter().map(|h| h.state_since).collect();
+        assert_eq!(since[0], Some(1_700_000_000));
+        assert!(since[1].unwrap() > 1_700_000_000, "went down just now");
+        assert!(since[2].unwrap() > 1_700_000_000, "first seen up just now");
+        assert_eq!(since[3], Some(1_600_000_000), "down since last seen");
+
+        // The next scan starts from each host's state and its start time
+        let history: HashMap<_, _> = state_history(&app.hosts).collect();
+        assert_eq!(history[&ip(1)], (true, 1_700_000_000));
+        assert!(!history[&ip(2)].0);
+    }
+
+    #[test]
+    fn connectivity_changes_are_logged_once() {
+        let mut app = app_with_hosts(0);
+        let report = |gateway, internet| Connectivity {
+            gateway_ip: Some(Ipv4Addr::new(192, 168, 1, 1)),
+            gateway,
+            internet,
         };
+        let up = Link::Up(Duration::from_millis(3));
+        let messages = |app: &App| -> Vec<String> {
+            app.event_log.newest_first(None).map(|e| e.message.clone()).collect()
+        };
+
+        app.apply_connectivity(report(up, up));
+        assert!(messages(&app).is_empty());
+        app.apply_connectivity(report(up, Link::Down));
+        app.apply_connectivity(report(up, Link::Down));
+        assert_eq!(messages(&app), ["Internet (1.1.1.1) not answering"]);
+        app.apply_connectivity(report(Link::Down, up));
+        assert_eq!(
+            messages(&app),
+            [
+                "Internet (1.1.1.1) answering again",
+                "Gateway 192.168.1.1 not answering",
+                "Internet (1.1.1.1) not answering",
+            ]
+        );
+        assert_eq!(app.connectivity.internet, up);
+    }
+
+    #[test]
+    fn restored_checkpoint_resumes_only_unscanned_addresses() {
+        let mut app = app_with_hosts(3);
+        app.range_input = "192.168.1.1-8".to_string();
+        app.restore_checkpoint(Checkpoint {
+            scanned: 3,
+            total: 8,
+        });
+        assert_eq!(app.scan_state, ScanState::Paused);
+        assert_eq!((app.scan_completed, app.scan_total), (3, 8));
+        assert!(app.has_partial_results());
 
-        // Parse MAC bytes (supports XX:XX:XX:XX:XX:XX or XX-XX-XX-XX-XX-XX)
-        let parts: Vec<u8> = mac
-            .address
-            .split([':', '-'])
-            .filter_map(|s| u8::from_str_radix(s, 16).ok())
+        // A result arriving before the pause also counts as scanned
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(5, false))));
+        let (total, remaining) = app.remaining_addresses().unwrap();
+        assert_eq!(total, 8);
+        let last_octets: Vec<u8> = remaining
+            .iter()
+            .flat_map(|(_, group)| group)
+            .map(|ip| ip.octets()[3])
             .collect();
+        assert_eq!(last_octets, [4, 6, 7, 8]);
 
-        if parts.len() != 6 {
-            return Ok(Some(format!("Invalid MAC address: {}", mac.address)));
-        }
+        // Editing the range doesn't change what the paused scan covers
+        app.range_input = "10.0.0.0/24".to_string();
+        assert_eq!(app.remaining_addresses().unwrap().1, remaining);
+    }
 
-        // Build magic packet: 6×0xFF then MAC repeated 16 times
-        let mut packet = vec![0xFF_u8; 6];
-        for _ in 0..16 {
-            packet.extend_from_slice(&parts);
+    #[test]
+    fn batch_ranges_are_counted_and_viewed_separately() {
+        let mut app = app_with_hosts(0);
+        app.range_input = "192.168.1.1-2; 192.168.1.2-4".to_string();
+        app.scan_range = app.range_input.clone();
+        let groups = app.plan_batch(&app.scan_range.clone()).unwrap();
+        // The address both ranges list belongs to the first
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2]);
+        app.scan_state = ScanState::Scanning;
+        for octet in [1, 2, 3] {
+            app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(octet, true))));
         }
+        assert_eq!((app.batch[0].completed, app.batch[0].online), (2, 2));
+        assert_eq!(app.batch_text().as_deref(), Some("range 1/2 2/2"));
+        app.batch[0].done = true;
+        assert_eq!(app.batch_text().as_deref(), Some("range 2/2 1/2"));
 
-        let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
-        socket.set_broadcast(true)?;
-        socket.send_to(&packet, "255.255.255.255:9")?;
+        app.handle_action(Action::NextRange).unwrap();
+        assert_eq!(app.filtered_hosts.len(), 2);
+        assert_eq!(app.range_view_label().as_deref(), Some("192.168.1.1-2 (1/2)"));
+        app.handle_action(Action::NextRange).unwrap();
+        assert_eq!(app.range_view_label().as_deref(), Some("192.168.1.2-4 (2/2)"));
+        assert_eq!(app.selected_host().unwrap().ip, Ipv4Addr::new(192, 168, 1, 3));
+        // New results of another range stay out of the view
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(1, false))));
+        assert_eq!(app.filtered_hosts.len(), 1);
+        app.handle_action(Action::NextRange).unwrap();
+        assert_eq!(app.range_view, None);
+        assert_eq!(app.filtered_hosts.len(), 4);
 
-        Ok(Some(format!("WOL packet sent to {} ({})", host.ip, mac.address)))
+        // A resume picks up where each range left off
+        app.scan_state = ScanState::Paused;
+        let (total, remaining) = app.remaining_addresses().unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(remaining, [(1, vec![Ipv4Addr::new(192, 168, 1, 4)])]);
+        assert!(app.batch[0].done && !app.batch[1].done);
+
+        let mut single = app_with_hosts(0);
+        single.handle_action(Action::NextRange).unwrap();
+        assert_eq!(single.range_view, None);
+        assert!(single.notifications.current().unwrap().text.starts_with("Not a batch scan"));
     }
 
-    /// Save the selected host's details to a text file
-    pub fn save_selected_host(&mut self) -> Result<()> {
-        let Some(host) = self.selected_host() else {
-            self.export_message = Some("No host selected".to_string());
-            return Ok(());
-        };
+    #[test]
+    fn enrichment_backlog_tracks_queued_lookups() {
+        let mut app = app_with_hosts(0);
+        app.scan_state = ScanState::Scanning;
+        app.scan_total = 3;
 
-        let filename = format!("ipscannr_host_{}.txt", host.ip);
-        let mut content = String::new();
-        content.push_str(&format!("IP:     {}\n", host.ip));
-        content.push_str(&format!(
-            "Status: {}\n",
-            if host.is_alive { "Online" } else { "Offline" }
-        ));
-        if let Some(rtt) = host.rtt {
-            content.push_str(&format!("RTT:    {}ms\n", rtt.as_millis()));
+        // Only online hosts are looked up
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(1, true))));
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(2, false))));
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(3, true))));
+        assert_eq!(app.enrichment_pending, 2);
+        assert_eq!(app.enrichment_text().as_deref(), Some("resolving 2…"));
+
+        app.handle_scan_event(ScanEvent::HostUpdated {
+            ip: Ipv4Addr::new(192, 168, 1, 3),
+            dns: Resolution::Resolved("nas.lan".to_string()),
+            netbios: None,
+            mac: Some(MacInfo {
+                address: "AA:BB:CC:DD:EE:03".to_string(),
+                vendor: None,
+            }),
+        });
+        assert_eq!(app.enrichment_pending, 1);
+        assert_eq!(app.hosts[2].hostname.as_deref(), Some("nas.lan"));
+        assert!(app.hosts[2].mac.is_some());
+
+        // The scan finishing first is reported as still resolving
+        app.handle_scan_event(ScanEvent::ScanComplete);
+        assert_eq!(app.scan_state, ScanState::Completed);
+        let last = app.event_log.newest_first(None).next().unwrap();
+        assert_eq!(last.severity, Severity::Info);
+        assert!(last.message.ends_with("still resolving 1 host(s)"), "{}", last.message);
+
+        // Lookups from an abandoned scan can't drive the count below zero
+        app.enrichment_pending = 0;
+        app.scan_state = ScanState::Scanning;
+        app.handle_scan_event(ScanEvent::HostUpdated {
+            ip: Ipv4Addr::new(192, 168, 1, 1),
+            dns: Resolution::NoRecord,
+            netbios: None,
+            mac: None,
+        });
+        assert_eq!(app.enrichment_pending, 0);
+        assert!(app.enrichment_text().is_none());
+
+        app.config.resolve_hostnames = false;
+        app.config.detect_mac = false;
+        app.handle_scan_event(ScanEvent::HostDiscovered(Box::new(host(4, true))));
+        assert_eq!(app.enrichment_pending, 0);
+    }
+
+    #[test]
+    fn spinner_follows_the_clock_not_the_tick_count() {
+        assert_eq!(spinner_frame(Duration::ZERO), 0);
+        assert_eq!(spinner_frame(Duration::from_millis(99)), 0);
+        assert_eq!(spinner_frame(Duration::from_millis(100)), 1);
+        // Wraps after the last of the 10 frames, with no extra frames
+        assert_eq!(spinner_frame(Duration::from_millis(999)), 9);
+        assert_eq!(spinner_frame(Duration::from_millis(1_000)), 0);
+
+        // A burst of ticks within one frame time doesn't advance the spinner
+        let mut app = app_with_hosts(0);
+        app.scan_state = ScanState::Scanning;
+        let start = app.animation_epoch;
+        for ms in 0..50 {
+            app.tick_animation_at(start + Duration::from_micros(ms * 1_000));
         }
-        if let Some(hostname) = &host.hostname {
-            content.push_str(&format!("Host:   {}\n", hostname));
+        assert_eq!(app.spinner(), SPINNER_FRAMES[0]);
+        app.tick_animation_at(start + Duration::from_millis(350));
+        assert_eq!(app.spinner(), SPINNER_FRAMES[3]);
+    }
+
+    #[test]
+    fn f1_pins_shortcuts_until_the_mode_changes() {
+        let mut app = app_with_hosts(3);
+        app.handle_action(Action::ToggleKeybindings).unwrap();
+        assert!(app.keybindings_visible());
+        app.handle_action(Action::NavigateDown).unwrap();
+        assert!(app.keybindings_visible());
+        app.handle_action(Action::ToggleKeybindings).unwrap();
+        assert!(!app.keybindings_visible());
+
+        app.handle_action(Action::ToggleKeybindings).unwrap();
+        app.handle_action(Action::Help).unwrap();
+        assert_eq!(app.input_mode, InputMode::Help);
+        assert!(!app.keybindings_visible());
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(!app.keybindings_visible());
+    }
+
+    #[test]
+    fn unfocused_terminal_stops_redrawing_the_spinner() {
+        let mut app = app_with_hosts(0);
+        app.scan_state = ScanState::Scanning;
+        app.show_keybindings = true;
+        app.set_terminal_focused(false);
+        assert!(!app.show_keybindings);
+
+        app.dirty = false;
+        let start = app.animation_epoch;
+        app.tick_animation_at(start + Duration::from_millis(350));
+        assert!(!app.dirty);
+        assert_eq!(app.spinner(), SPINNER_FRAMES[0]);
+
+        app.set_terminal_focused(true);
+        app.tick_animation_at(start + Duration::from_millis(450));
+        assert!(app.dirty);
+        assert_eq!(app.spinner(), SPINNER_FRAMES[4]);
+    }
+
+    #[test]
+    fn forgetting_a_host_keeps_only_its_liveness() {
+        let _guard = crate::cache::tests::env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_app_forget_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var("IPSCANNR_CACHE_FILE", &temp_path);
         }
-        if let Some(mac) = &host.mac {
-            content.push_str(&format!("MAC:    {}\n", mac.address));
-            if let Some(vendor) = &mac.vendor {
-                content.push_str(&format!("Vendor: {}\n", vendor));
-            }
+
+        let mut app = app_with_hosts(2);
+        app.hosts[0].hostname = Some("old-nas.lan".to_string());
+        app.hosts[0].mac = Some(MacInfo {
+            address: "AA:BB:CC:DD:EE:01".to_string(),
+            vendor: None,
+        });
+        app.hosts[0].open_ports = vec![22, 445];
+        app.hosts[0].ports_scanned = true;
+        app.hosts[0].previous_ports = Some(vec![22]);
+        crate::cache::save_cache("192.168.1.0/24", &app.hosts);
+
+        app.handle_action(Action::ForgetHost).unwrap();
+        assert_eq!(app.input_mode, InputMode::ConfirmForget);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.hosts[0].hostname.as_deref(), Some("old-nas.lan"));
+
+        app.handle_action(Action::ForgetHost).unwrap();
+        let cmd = app.handle_action(Action::Character('p')).unwrap();
+        assert!(matches!(cmd, Some(AppCommand::ScanPortsForSelected)));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let host = &app.hosts[0];
+        assert!(host.is_alive);
+        assert!(host.hostname.is_none() && host.mac.is_none());
+        assert!(host.open_ports.is_empty() && !host.ports_scanned);
+        assert!(host.previous_ports.is_none() && host.port_diff().is_none());
+        let cached = crate::cache::load_cache("192.168.1.0/24");
+        assert!(cached[0].hostname.is_none());
+        assert!(cached[0].is_alive);
+
+        unsafe {
+            std::env::remove_var("IPSCANNR_CACHE_FILE");
         }
-        if !host.open_ports.is_empty() {
-            content.push_str("\nOpen Ports:\n");
-            for port in &host.open_ports {
-                content.push_str(&format!("  {}\n", port));
+        let _ = std::fs::remove_file(temp_path.with_extension("json.lock"));
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn timed_out_lookups_are_kept_for_a_retry() {
+        let mut app = app_with_hosts(3);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        app.handle_scan_event(ScanEvent::HostUpdated {
+            ip,
+            dns: Resolution::Timeout,
+            netbios: None,
+            mac: None,
+        });
+        assert!(app.hosts[0].dns_timed_out());
+        assert_eq!(app.hosts[0].hostname, None);
+        let last = app.event_log.newest_first(None).next().unwrap();
+        assert_eq!(last.message, "Reverse DNS for 192.168.1.1 timed out");
+
+        // The rescan's retry fills the name in; ports alone leave it alone
+        app.apply_host_rescan(HostRescan {
+            ip,
+            open_ports: vec![22],
+            dns: Some(Resolution::Resolved("nas.lan".to_string())),
+            mac: None,
+        });
+        assert_eq!(app.hosts[0].hostname.as_deref(), Some("nas.lan"));
+        assert!(!app.hosts[0].dns_timed_out());
+        assert_eq!(app.hosts[0].open_ports, [22]);
+        app.apply_host_rescan(HostRescan {
+            ip,
+            open_ports: Vec::new(),
+            dns: None,
+            mac: None,
+        });
+        assert_eq!(app.hosts[0].hostname.as_deref(), Some("nas.lan"));
+        assert!(app.hosts[0].ports_scanned);
+        assert_eq!(app.hosts[0].ports_spec.as_deref(), Some("common"));
+        assert!(app.hosts[0].ports_scanned_at.is_some());
+    }
+
+    #[test]
+    fn port_scan_covers_the_live_selection_and_reports_progress() {
+        let mut app = app_with_hosts(4);
+        app.config.resolve_hostnames = false;
+        app.config.detect_mac = false;
+        app.hosts[1].is_alive = false;
+        let ips: Vec<Ipv4Addr> = app.hosts.iter().map(|h| h.ip).collect();
+        app.selected_hosts = HashSet::from([ips[1]]);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            // Nothing alive to scan
+            assert!(app.start_port_scan_for_selected().is_none());
+            assert!(!app.port_scanning);
+
+            app.selected_hosts.extend([ips[0], ips[2]]);
+            assert!(app.start_port_scan_for_selected().is_some());
+            let mut scanning: Vec<Ipv4Addr> = app.port_progress.keys().copied().collect();
+            scanning.sort();
+            assert_eq!(scanning, [ips[0], ips[2]]);
+            assert_eq!(app.port_progress[&ips[0]], (0, COMMON_PORTS.len()));
+        });
+
+        app.table_state.select(Some(2));
+        assert!(app.selected_host_port_scanning());
+        app.apply_port_scan_event(PortScanEvent::Progress(PortProgress {
+            ip: ips[2],
+            scanned: 7,
+            total: COMMON_PORTS.len(),
+        }));
+        assert_eq!(app.port_progress[&ips[2]], (7, COMMON_PORTS.len()));
+        app.apply_port_scan_event(PortScanEvent::Finished(HostRescan {
+            ip: ips[2],
+            open_ports: vec![80],
+            dns: None,
+            mac: None,
+        }));
+        assert!(!app.selected_host_port_scanning());
+        assert_eq!(app.hosts[2].open_ports, [80]);
+        assert!(app.port_progress.contains_key(&ips[0]));
+
+        app.port_scan_finished();
+        assert!(!app.port_scanning);
+        assert!(app.port_progress.is_empty());
+    }
+
+    #[test]
+    fn safe_mode_limits_port_scans_and_refuses_udp_tools() {
+        let mut app = app_with_hosts(3);
+        app.config.resolve_hostnames = false;
+        app.config.detect_mac = false;
+        app.config.arp_prewarm = true;
+        app.config.enter_safe_mode(vec![443]);
+        assert_eq!(app.config.ping.tcp_ports, [443]);
+        assert!(!app.config.prewarms());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            assert!(app.start_port_scan_for_selected().is_some());
+            assert_eq!(app.port_progress[&app.hosts[0].ip], (0, 1));
+            let note = app.notifications.current().unwrap();
+            assert!(note.text.contains("common-port scan refused; checking only 443"), "{}", note.text);
+
+            assert!(app.start_discovery().is_none());
+            assert!(!app.discovering);
+        });
+        app.port_scan_finished();
+        app.apply_host_rescan(HostRescan {
+            ip: app.hosts[0].ip,
+            open_ports: vec![443],
+            dns: None,
+            mac: None,
+        });
+        assert_eq!(app.hosts[0].ports_spec.as_deref(), Some("allowed"));
+
+        app.handle_action(Action::WakeOnLan).unwrap();
+        app.settings.knock_actions = vec![KnockAction {
+            label: "Open".to_string(),
+            ports: vec![7000],
+            protocol: "tcp".to_string(),
+            delay_ms: 500,
+            check_port: None,
+        }];
+        app.handle_action(Action::Knock).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let refused: Vec<String> = app.event_log.newest_first(None).map(|e| e.message.clone()).collect();
+        assert!(refused[0].contains("Safe mode: port knocking is off"), "{:?}", refused);
+        assert!(refused[1].contains("Safe mode: Wake-on-LAN is off"), "{:?}", refused);
+        assert!(refused.contains(&"Safe mode: SSDP / mDNS discovery is off".to_string()), "{:?}", refused);
+
+        // ICMP only: nothing left to port scan
+        app.config.enter_safe_mode(Vec::new());
+        assert!(app.start_port_scan_for_selected().is_none());
+        assert!(app.event_log.newest_first(None).next().unwrap().message.contains("port scans are off"));
+    }
+
+    #[test]
+    fn rescanned_ports_show_what_changed_until_viewed() {
+        let mut app = app_with_hosts(3);
+        let ip = app.hosts[0].ip;
+        let rescan = |ports: &[u16]| HostRescan {
+            ip,
+            open_ports: ports.to_vec(),
+            dns: None,
+            mac: None,
+        };
+        // The first scan has nothing to compare with
+        app.apply_host_rescan(rescan(&[22, 23]));
+        assert_eq!(app.hosts[0].previous_ports, None);
+        assert_eq!(app.selected_port_diff(), None);
+
+        app.apply_host_rescan(rescan(&[22, 8080]));
+        assert_eq!(app.hosts[0].previous_ports.as_deref(), Some(&[22, 23][..]));
+        let last = app.event_log.newest_first(None).next().unwrap();
+        assert_eq!(last.message, "Ports on 192.168.1.1 changed: +8080 -23");
+        let lines: Vec<String> = app.conflict_lines().into_iter().map(|l| l.1).collect();
+        assert_eq!(lines[0], "192.168.1.1 ports changed");
+        app.handle_action(Action::ShowConflicts).unwrap();
+        assert_eq!(app.input_mode, InputMode::Conflicts);
+        app.handle_action(Action::Cancel).unwrap();
+
+        // Shown in the details until the selection moves on
+        app.show_details = true;
+        assert_eq!(app.selected_port_diff().unwrap().added, [8080]);
+        app.handle_action(Action::NavigateDown).unwrap();
+        app.handle_action(Action::NavigateUp).unwrap();
+        assert_eq!(app.selected_port_diff(), None);
+        // The overlay keeps listing it; a new change shows again
+        assert!(app.port_changes().next().is_some());
+        app.apply_host_rescan(rescan(&[22]));
+        assert_eq!(app.selected_port_diff().unwrap().removed, [8080]);
+        app.apply_host_rescan(rescan(&[22]));
+        assert!(app.port_changes().next().is_none());
+    }
+
+    #[test]
+    fn goto_prefers_an_exact_last_octet() {
+        let ips: Vec<Ipv4Addr> = [(1, 5), (1, 57), (1, 50), (2, 57), (2, 5)]
+            .iter()
+            .map(|&(c, d)| Ipv4Addr::new(10, 0, c, d))
+            .collect();
+        assert_eq!(goto_match(&ips, "5"), Some(0));
+        assert_eq!(goto_match(&ips, ".57"), Some(1));
+        assert_eq!(goto_match(&ips, "50"), Some(2));
+        // Nothing is .4; "4" is no octet's start either
+        assert_eq!(goto_match(&ips, "4"), None);
+        assert_eq!(goto_match(&ips, "2.57"), Some(3));
+        assert_eq!(goto_match(&ips, "10.0.2.5"), Some(4));
+        assert_eq!(goto_match(&ips, "10.0.2"), Some(3));
+        assert_eq!(goto_match(&ips, ""), None);
+        assert_eq!(goto_match(&ips, "."), None);
+    }
+
+    #[test]
+    fn goto_selects_among_the_shown_hosts() {
+        let mut app = app_with_hosts(60);
+        app.filter_mode = FilterMode::OnlineOnly;
+        app.update_filtered_hosts();
+        let goto = |app: &mut App, keys: &str| {
+            app.handle_action(Action::Goto).unwrap();
+            assert_eq!(app.input_mode, InputMode::Goto);
+            for c in keys.chars() {
+                app.handle_action(Action::Character(c)).unwrap();
             }
-        }
+            app.handle_action(Action::Select).unwrap();
+            assert_eq!(app.input_mode, InputMode::Normal);
+            app.selected_host().map(|h| h.ip.octets()[3])
+        };
+        assert_eq!(goto(&mut app, "57"), Some(57));
+        // .58 is offline and filtered out: no match, the selection stays
+        assert_eq!(goto(&mut app, "58"), Some(57));
+        assert_eq!(goto(&mut app, "1.3"), Some(3));
 
-        std::fs::write(&filename, content)?;
-        self.export_message = Some(format!("Saved to {}", filename));
-        Ok(())
+        app.handle_action(Action::Goto).unwrap();
+        app.handle_action(Action::Character('9')).unwrap();
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.selected_host().map(|h| h.ip.octets()[3]), Some(3));
     }
 
-    /// Get hosts to include in export (selected subset, or all if nothing selected)
-    fn hosts_for_export(&self) -> Vec<&HostInfo> {
-        if self.selected_hosts.is_empty() {
-            self.hosts.iter().collect()
-        } else {
-            self.hosts
-                .iter()
-                .filter(|h| self.selected_hosts.contains(&h.ip))
-                .collect()
+    #[test]
+    fn tcp_only_hosts_are_counted_and_filtered_apart() {
+        let mut app = app_with_hosts(4);
+        // .1 and .3 are online; a stale status on offline .2 doesn't count
+        app.hosts[0].status = HostStatus::OnlineNoIcmp;
+        app.hosts[1].status = HostStatus::OnlineNoIcmp;
+        assert_eq!(online_counts(&app.hosts), (2, 1));
+        assert_eq!(online_text(&app.hosts), "2 online (1 TCP-only)");
+        assert_eq!(app.completion_summary(), "4 hosts (2 online, 1 TCP-only)");
+
+        app.filter_mode = FilterMode::Pingable;
+        app.update_filtered_hosts();
+        let shown: Vec<u8> = app.filtered_hosts.iter().map(|&i| app.hosts[i].ip.octets()[3]).collect();
+        assert_eq!(shown, [3]);
+        app.filter_mode = FilterMode::OnlineOnly;
+        app.update_filtered_hosts();
+        assert_eq!(app.filtered_hosts.len(), 2);
+
+        app.hosts[0].status = HostStatus::Online;
+        assert_eq!(online_text(&app.hosts), "2 online");
+    }
+
+    #[test]
+    fn ports_scanned_before_the_liveness_data_are_outdated() {
+        let mut host = host(1, true);
+        host.last_seen = Some(1_000);
+        assert!(!host.ports_outdated());
+        host.ports_scanned_at = Some(2_000);
+        assert!(!host.ports_outdated());
+        // A newer cached scan, or a newer sighting, outdates the ports
+        host.cached_at = Some(3_000);
+        assert!(host.ports_outdated());
+        host.cached_at = None;
+        host.last_seen = Some(2_500);
+        assert!(host.ports_outdated());
+    }
+
+    #[test]
+    fn summary_overlay_opens_only_with_results() {
+        let mut app = app_with_hosts(0);
+        app.handle_action(Action::ShowSummary).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.notifications.current().unwrap().text.starts_with("Nothing to summarize"));
+
+        let mut app = app_with_hosts(4);
+        app.event_log_page = 5;
+        app.handle_action(Action::ShowSummary).unwrap();
+        assert_eq!(app.input_mode, InputMode::Summary);
+        assert_eq!(app.summary_lines[0].1, "4 hosts scanned, 2 online");
+
+        app.handle_action(Action::NavigateEnd).unwrap();
+        assert_eq!(app.summary_scroll, app.summary_lines.len() - 5);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.summary_scroll, 0);
+    }
+
+    #[test]
+    fn shared_macs_are_flagged_and_listed() {
+        let mut app = app_with_hosts(3);
+        app.handle_action(Action::ShowConflicts).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.notifications.current().unwrap().text, "No IP conflicts or port changes found");
+
+        for h in &mut app.hosts[..2] {
+            h.mac = Some(MacInfo {
+                address: "AA:BB:CC:DD:EE:01".to_string(),
+                vendor: None,
+            });
         }
+        app.update_conflicts();
+        let flagged: HashSet<Ipv4Addr> =
+            [Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 2)].into();
+        assert_eq!(app.conflicted_ips, flagged);
+
+        app.handle_action(Action::ShowConflicts).unwrap();
+        assert_eq!(app.input_mode, InputMode::Conflicts);
+        assert_eq!(app.conflict_lines().len(), 3);
+        app.handle_action(Action::Cancel).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
     }
 
-    fn export_csv(&mut self) -> Result<()> {
-        let filename = format!("ipscannr_export_{}.csv", chrono_timestamp());
-        let mut wtr = csv::Writer::from_path(&filename)?;
+    #[tokio::test]
+    async fn host_changes_are_posted_once_the_results_are_final() {
+        let (addr, mut requests) = webhook::tests::capture(&[200]).await;
+        let url = format!("http://{}/hook?key=s3cret", addr);
 
-        wtr.write_record(["IP", "Status", "RTT (ms)", "Hostname", "MAC", "Vendor", "Ports"])?;
+        let mut app = app_with_hosts(2);
+        let (tx, mut rx) = mpsc::channel(4);
+        app.connect_webhook(tx.clone());
+        assert!(app.webhook.is_none(), "no webhook configured");
 
-        for host in self.hosts_for_export() {
-            wtr.write_record([
-                host.ip.to_string(),
-                if host.is_alive { "Online" } else { "Offline" }.to_string(),
-                host.rtt.map(|d| d.as_millis().to_string()).unwrap_or_default(),
-                host.hostname.clone().unwrap_or_default(),
-                host.mac.as_ref().map(|m| m.address.clone()).unwrap_or_default(),
-                host.mac.as_ref().and_then(|m| m.vendor.clone()).unwrap_or_default(),
-                host.open_ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(";"),
-            ])?;
+        app.settings.webhook = Some(webhook::WebhookConfig {
+            url: "http://hooks.example/x".to_string(),
+            bearer_token: Some("s3cret".to_string()),
+            ..Default::default()
+        });
+        app.connect_webhook(tx.clone());
+        assert!(app.webhook.is_none());
+        let refused = app.event_log.newest_first(None).next().unwrap();
+        assert!(refused.message.starts_with("Webhook off: "), "{}", refused.message);
+
+        app.settings.webhook = Some(webhook::WebhookConfig { url, ..Default::default() });
+        app.connect_webhook(tx);
+        let connected = app.event_log.newest_first(None).next().unwrap();
+        assert_eq!(connected.message, format!("Webhook: posting host changes to http://{}/hook", addr));
+        app.hosts[1].hostname = Some("nas.lan".to_string());
+        app.record_change(Ipv4Addr::new(192, 168, 1, 2), false, 1_700_000_000);
+        app.send_webhooks();
+        assert!(app.webhook_queue.is_empty());
+
+        let request = requests.recv().await.unwrap();
+        let body = String::from_utf8_lossy(request.body());
+        assert!(body.contains(r#""event":"down","ip":"192.168.1.2","hostname":"nas.lan""#), "{}", body);
+        app.webhook_delivered(rx.recv().await.unwrap());
+        let logged = app.event_log.newest_first(None).next().unwrap();
+        assert_eq!(logged.message, "Webhook: 192.168.1.2 down delivered (HTTP 200)");
+    }
+
+    #[test]
+    fn publish_feeds_the_listen_snapshot() {
+        let mut app = app_with_hosts(3);
+        app.publish();
+        assert!(app.status_feed.is_none());
+
+        let feed = serve::Shared::default();
+        app.status_feed = Some(feed.clone());
+        app.hosts[2].cached_at = Some(1_700_000_000);
+        app.scan_state = ScanState::Scanning;
+        app.scan_total = 254;
+        app.scan_completed = 3;
+        app.publish();
+        {
+            let snapshot = feed.read().unwrap();
+            assert_eq!(snapshot.status.state, "scanning");
+            assert_eq!(snapshot.status.range, app.range_input);
+            assert_eq!((snapshot.status.scanned, snapshot.status.total), (3, 254));
+            assert_eq!((snapshot.status.hosts, snapshot.status.online), (3, 2));
+            assert_eq!(snapshot.status.last_scan, Some(1_700_000_000));
+            let ips: Vec<&str> = snapshot.hosts.iter().map(|h| h.ip.as_str()).collect();
+            assert_eq!(ips, ["192.168.1.1", "192.168.1.3", "192.168.1.2"], "online first");
         }
 
-        wtr.flush()?;
-        self.export_message = Some(format!("Exported to {}", filename));
-        Ok(())
+        // Lookups still running keep the results out of the cache
+        app.enrichment_pending = 1;
+        app.handle_scan_event(ScanEvent::ScanComplete);
+        app.publish();
+        let snapshot = feed.read().unwrap();
+        assert_eq!(snapshot.status.state, "completed");
+        assert!(snapshot.status.last_scan.unwrap() > 1_700_000_000);
     }
 
-    fn export_json(&mut self) -> Result<()> {
-        let filename = format!("ipscannr_export_{}.json", chrono_timestamp());
+    #[test]
+    fn publishing_waits_for_a_change_and_the_interval() {
+        let mut app = app_with_hosts(2);
+        let feed = serve::Shared::default();
+        app.status_feed = Some(feed.clone());
+        let published = |feed: &serve::Shared| feed.read().unwrap().status.hosts;
 
-        #[derive(serde::Serialize)]
-        struct ExportHost {
-            ip: String,
-            is_alive: bool,
-            rtt_ms: Option<u128>,
-            hostname: Option<String>,
-            mac_address: Option<String>,
-            mac_vendor: Option<String>,
-            open_ports: Vec<u16>,
+        app.publish_if_due(false);
+        assert_eq!(published(&feed), 0, "nothing changed");
+        app.publish_if_due(true);
+        assert_eq!(published(&feed), 2);
+
+        // Changes within the interval wait for it, then go out together
+        app.hosts.push(host(3, true));
+        app.publish_if_due(true);
+        app.publish_if_due(false);
+        assert_eq!(published(&feed), 2);
+        app.published_at = Some(Instant::now() - PUBLISH_INTERVAL);
+        app.publish_if_due(false);
+        assert_eq!(published(&feed), 3);
+    }
+
+    #[test]
+    fn metrics_are_rendered_once_per_finished_scan() {
+        let mut app = app_with_hosts(2);
+        let feed = serve::Shared::default();
+        app.status_feed = Some(feed.clone());
+        app.publish();
+        assert!(feed.read().unwrap().metrics.is_empty());
+
+        app.export_metrics();
+        app.hosts.push(host(3, true));
+        app.publish();
+        let metrics = feed.read().unwrap().metrics.clone();
+        assert!(metrics.contains("ipscannr_hosts_total 2\n"), "{}", metrics);
+    }
+
+    #[test]
+    fn same_named_device_on_several_ips_is_linked_not_flagged() {
+        let mut app = app_with_hosts(4);
+        for i in [0, 2] {
+            app.hosts[i].hostname = Some("nas.lan".to_string());
+            app.hosts[i].mac = Some(MacInfo {
+                address: "AA:BB:CC:DD:EE:01".to_string(),
+                vendor: None,
+            });
         }
+        app.handle_action(Action::JumpToSameMac).unwrap();
+        assert_eq!(app.notifications.current().unwrap().text, "No other address shares this host's MAC");
 
-        let export_data: Vec<ExportHost> = self
-            .hosts_for_export()
-            .into_iter()
-            .map(|h| ExportHost {
-                ip: h.ip.to_string(),
-                is_alive: h.is_alive,
-                rtt_ms: h.rtt.map(|d| d.as_millis()),
-                hostname: h.hostname.clone(),
-                mac_address: h.mac.as_ref().map(|m| m.address.clone()),
-                mac_vendor: h.mac.as_ref().and_then(|m| m.vendor.clone()),
-                open_ports: h.open_ports.clone(),
-            })
-            .collect();
+        app.update_conflicts();
+        assert!(app.conflicts.is_empty());
+        assert!(app.conflicted_ips.is_empty());
+        assert!(app.mac_group(Ipv4Addr::new(192, 168, 1, 3)).is_some_and(|g| g.same_host));
 
-        let json = serde_json::to_string_pretty(&export_data)?;
-        std::fs::write(&filename, json)?;
+        app.handle_action(Action::JumpToSameMac).unwrap();
+        assert_eq!(app.selected_host().unwrap().ip, Ipv4Addr::new(192, 168, 1, 3));
+        app.handle_action(Action::JumpToSameMac).unwrap();
+        assert_eq!(app.selected_host().unwrap().ip, Ipv4Addr::new(192, 168, 1, 1));
 
-        self.export_message = Some(format!("Exported to {}", filename));
-        Ok(())
+        // A different name on the same MAC is a conflict again
+        app.hosts[2].hostname = Some("printer.lan".to_string());
+        app.update_conflicts();
+        assert_eq!(app.conflicts.len(), 1);
+        assert!(app.mac_group(Ipv4Addr::new(192, 168, 1, 1)).is_some_and(|g| !g.same_host));
     }
-}
 
-/// Commands returned by the app
-#[derive(Debug)]
-pub enum AppCommand {
-    Quit,
-    StartScan,
-    ResumeScan,
-    ScanPortsForSelected,
-    StartContinuousPing(Ipv4Addr),
-    StartTracert(Ipv4Addr),
-}
+    #[test]
+    fn overlay_stop_and_close_keep_output_for_reopening() {
+        let mut app = app_with_hosts(1);
+        app.handle_action(Action::ReopenOverlay).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
 
-/// Events from the scan process
-#[derive(Debug)]
-pub enum ScanEvent {
-    HostDiscovered(HostInfo),
-    ScanComplete,
-}
+        let (tx, mut rx) = mpsc::channel(1);
+        app.overlay_cancel_tx = Some(tx);
+        app.overlay_title = "Tracert — 192.168.1.1".to_string();
+        app.input_mode = InputMode::OutputOverlay;
+        app.push_overlay_line("1  1ms  192.168.1.254".to_string());
 
-fn chrono_timestamp() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{}", duration.as_secs())
+        // Stop ends the task but keeps the view open
+        app.handle_action(Action::StopOverlay).unwrap();
+        assert!(rx.try_recv().is_ok());
+        assert!(app.overlay_cancel_tx.is_none());
+        assert_eq!(app.input_mode, InputMode::OutputOverlay);
+
+        // Stopping again has no dead channel to poke; closing keeps the lines
+        app.handle_action(Action::StopOverlay).unwrap();
+        app.handle_action(Action::CloseOverlay).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.overlay_lines.len(), 1);
+
+        app.handle_action(Action::ReopenOverlay).unwrap();
+        assert_eq!(app.input_mode, InputMode::OutputOverlay);
+    }
 }
@@ -1,11 +1,15 @@
 use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::net::Ipv4Addr;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
 use crate::app::HostInfo;
-use crate::scanner::{HostStatus, MacInfo, PingMethod};
+use crate::names::Names;
+use crate::scanner::{HostStatus, MacInfo, PingMethod, Probe};
 
 const CACHE_FILE: &str = "ipscannr_cache.json";
 const CACHE_FILE_ENV: &str = "IPSCANNR_CACHE_FILE";
@@ -14,15 +18,55 @@ const CACHE_FILE_ENV: &str = "IPSCANNR_CACHE_FILE";
 struct CachedHost {
     ip: String,
     is_alive: bool,
+    /// Whole milliseconds, for older builds reading the file; `rtt_us`
+    /// is what's loaded
     rtt_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rtt_us: Option<u64>,
     hostname: Option<String>,
+    /// The names behind `hostname`, by source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mdns_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    netbios_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ptr_name: Option<String>,
     mac_address: Option<String>,
     mac_vendor: Option<String>,
     open_ports: Vec<u16>,
+    /// Unix time the port scan behind `open_ports` finished, and the port
+    /// set it covered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ports_scanned_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ports_spec: Option<String>,
+    /// Open ports of the port scan before that one, so what changed is
+    /// still known after a restart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous_ports: Option<Vec<u16>>,
     #[serde(default)]
     method: Option<String>,
     #[serde(default)]
     status: Option<String>,
+    /// Unix time the host was last observed online
+    #[serde(default)]
+    last_seen: Option<u64>,
+    /// Discovery probe: TCP port (none for ICMP) and the attempt that answered
+    #[serde(default)]
+    probe_port: Option<u16>,
+    #[serde(default)]
+    probe_attempt: Option<u32>,
+    /// Only identical instant refusals answered: possibly a reset proxy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    probe_suspect: bool,
+    /// Unix time the host entered its current up/down state
+    #[serde(default)]
+    state_since: Option<u64>,
+    /// Services the host advertised over SSDP / mDNS
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    services: Vec<String>,
 }
 
 fn cache_file_path() -> std::path::PathBuf {
@@ -35,11 +79,71 @@ fn cache_file_path() -> std::path::PathBuf {
 struct CacheEntry {
     scanned_at: u64,
     hosts: Vec<CachedHost>,
+    /// Set while the entry is a checkpoint of an unfinished scan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    partial: Option<Checkpoint>,
 }
 
 type CacheFile = HashMap<String, CacheEntry>;
 
-fn now_secs() -> u64 {
+/// How far an interrupted scan got before its results were checkpointed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Addresses probed so far
+    pub scanned: usize,
+    /// Addresses in the range
+    pub total: usize,
+}
+
+/// Serializes read-merge-write cycles: the writer thread may be saving
+/// checkpoints while the UI thread forgets a host
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Take the advisory lock that other ipscannr instances honour while they
+/// read, merge and rewrite the cache. It lives on a sidecar file, since the
+/// cache itself is replaced on every save, and is released on drop. None when
+/// the lock file can't be opened; the save then goes ahead unlocked.
+fn lock_cache_file() -> Option<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(cache_file_path().with_extension("json.lock"))
+        .inspect_err(|e| tracing::warn!(error = %e, "cannot open cache lock file"))
+        .ok()?;
+    file.lock()
+        .inspect_err(|e| tracing::warn!(error = %e, "cannot lock cache file"))
+        .ok()?;
+    Some(file)
+}
+
+/// Replace the cache with `cache_file` through a temporary file, so readers
+/// see either the old or the new contents
+fn write_cache_file(cache_file: &CacheFile) {
+    let cache_path = cache_file_path();
+    if let Ok(json) = serde_json::to_string_pretty(cache_file) {
+        let tmp_path = cache_path.with_extension("json.tmp");
+        if let Err(e) = std::fs::write(&tmp_path, json) {
+            tracing::warn!(path = %tmp_path.display(), error = %e, "cache write failed");
+        } else if std::fs::rename(&tmp_path, &cache_path).is_err() {
+            let _ = std::fs::copy(&tmp_path, &cache_path);
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        tracing::debug!(path = %cache_path.display(), ranges = cache_file.len(), "cache written");
+    }
+}
+
+fn read_cache_file() -> Option<CacheFile> {
+    let path = cache_file_path();
+    let content = std::fs::read_to_string(&path)
+        .inspect_err(|e| tracing::debug!(path = %path.display(), error = %e, "no cache read"))
+        .ok()?;
+    serde_json::from_str(&content)
+        .inspect_err(|e| tracing::warn!(path = %path.display(), error = %e, "cache is not valid JSON"))
+        .ok()
+}
+
+pub fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -48,11 +152,7 @@ fn now_secs() -> u64 {
 
 /// Load cached hosts for a given IP range. Returns empty Vec if no cache exists.
 pub fn load_cache(range: &str) -> Vec<HostInfo> {
-    let cache_path = cache_file_path();
-    let Ok(content) = std::fs::read_to_string(cache_path) else {
-        return Vec::new();
-    };
-    let Ok(cache_file): Result<CacheFile, _> = serde_json::from_str(&content) else {
+    let Some(cache_file) = read_cache_file() else {
         return Vec::new();
     };
     let Some(entry) = cache_file.get(range) else {
@@ -98,66 +198,392 @@ pub fn load_cache(range: &str) -> Vec<HostInfo> {
             Some(HostInfo {
                 ip,
                 is_alive: h.is_alive,
-                rtt: h.rtt_ms.map(Duration::from_millis),
+                // Entries written before microseconds were kept
+                rtt: h
+                    .rtt_us
+                    .map(Duration::from_micros)
+                    .or(h.rtt_ms.map(Duration::from_millis)),
                 hostname: h.hostname.clone(),
+                names: Names {
+                    alias: h.alias.clone(),
+                    mdns: h.mdns_name.clone(),
+                    netbios: h.netbios_name.clone(),
+                    ptr: h.ptr_name.clone(),
+                },
                 mac,
                 open_ports: h.open_ports.clone(),
-                ports_scanned: !h.open_ports.is_empty(),
+                // Entries written before scans were timestamped only know
+                // a scan ran if it found something
+                ports_scanned: !h.open_ports.is_empty() || h.ports_scanned_at.is_some(),
+                ports_scanned_at: h.ports_scanned_at,
+                ports_spec: h.ports_spec.clone(),
+                previous_ports: h.previous_ports.clone(),
                 cached_at: Some(scanned_at),
+                // Entries written before last_seen existed: an online host was
+                // seen when the range was scanned
+                last_seen: h.last_seen.max(h.is_alive.then_some(scanned_at)),
                 method,
                 status,
+                // Entries written before probes were recorded have none
+                probe: h.probe_attempt.map(|attempt| Probe {
+                    port: h.probe_port,
+                    attempt,
+                    suspect: h.probe_suspect,
+                }),
+                probe_error: None,
+                state_since: h.state_since,
+                services: h.services.clone(),
+                dns: None,
+                stale: false,
             })
         })
         .collect()
 }
 
-/// Persist current scan results for the given IP range.
+/// Progress of the unfinished scan checkpointed for `range`, if that is
+/// what the cache holds for it
+pub fn load_checkpoint(range: &str) -> Option<Checkpoint> {
+    read_cache_file()?.get(range)?.partial
+}
+
+/// Persist current scan results for the given IP range right away; the app
+/// queues them on a [`Writer`] instead
+#[cfg(test)]
 pub fn save_cache(range: &str, hosts: &[HostInfo]) {
-    if hosts.is_empty() {
+    let pending = Pending {
+        hosts: hosts.to_vec(),
+        partial: None,
+        scan_started: 0,
+    };
+    store_all(vec![(range.to_string(), pending)]);
+}
+
+/// Persist the results of a scan still in progress, marked as partial. A
+/// checkpoint landing after the final results of the same scan (started at
+/// `scan_started`) is dropped.
+#[cfg(test)]
+pub fn save_checkpoint(range: &str, hosts: &[HostInfo], progress: Checkpoint, scan_started: u64) {
+    let pending = Pending {
+        hosts: hosts.to_vec(),
+        partial: Some(progress),
+        scan_started,
+    };
+    store_all(vec![(range.to_string(), pending)]);
+}
+
+/// Store several ranges with one read and one rewrite of the file
+fn store_all(batch: Vec<(String, Pending)>) {
+    let entries: Vec<(String, CacheEntry, u64)> = batch
+        .into_iter()
+        .filter(|(_, pending)| !pending.hosts.is_empty())
+        .map(|(range, pending)| {
+            let entry = cache_entry(&pending.hosts, pending.partial);
+            (range, entry, pending.scan_started)
+        })
+        .collect();
+    if entries.is_empty() {
         return;
     }
 
+    let _lock = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _file_lock = lock_cache_file();
+    let mut cache_file: CacheFile = read_cache_file().unwrap_or_default();
+    let mut changed = false;
+    for (range, entry, scan_started) in entries {
+        changed |= merge(&mut cache_file, &range, entry, scan_started);
+    }
+    if changed {
+        write_cache_file(&cache_file);
+    }
+}
+
+fn cache_entry(hosts: &[HostInfo], partial: Option<Checkpoint>) -> CacheEntry {
     let cached_hosts: Vec<CachedHost> = hosts
         .iter()
         .map(|h| CachedHost {
             ip: h.ip.to_string(),
             is_alive: h.is_alive,
             rtt_ms: h.rtt.map(|d| d.as_millis() as u64),
+            rtt_us: h.rtt.map(|d| d.as_micros() as u64),
             hostname: h.hostname.clone(),
+            alias: h.names.alias.clone(),
+            mdns_name: h.names.mdns.clone(),
+            netbios_name: h.names.netbios.clone(),
+            ptr_name: h.names.ptr.clone(),
             mac_address: h.mac.as_ref().map(|m| m.address.clone()),
             mac_vendor: h.mac.as_ref().and_then(|m| m.vendor.clone()),
             open_ports: h.open_ports.clone(),
+            ports_scanned_at: h.ports_scanned_at,
+            ports_spec: h.ports_spec.clone(),
+            previous_ports: h.previous_ports.clone(),
             method: Some(h.method.to_string()),
             status: Some(match h.status {
                 HostStatus::Online => "Online".to_string(),
                 HostStatus::OnlineNoIcmp => "OnlineNoIcmp".to_string(),
                 HostStatus::Offline => "Offline".to_string(),
             }),
+            last_seen: h.last_seen,
+            probe_port: h.probe.and_then(|p| p.port),
+            probe_attempt: h.probe.map(|p| p.attempt),
+            probe_suspect: h.probe.is_some_and(|p| p.suspect),
+            state_since: h.state_since,
+            services: h.services.clone(),
         })
         .collect();
 
-    let entry = CacheEntry {
+    CacheEntry {
         scanned_at: now_secs(),
         hosts: cached_hosts,
-    };
+        partial,
+    }
+}
 
-    // Load existing file and merge, preserving entries for other ranges
-    let cache_path = cache_file_path();
-    let mut cache_file: CacheFile = std::fs::read_to_string(&cache_path)
-        .ok()
-        .and_then(|content| serde_json::from_str(&content).ok())
-        .unwrap_or_default();
+/// Merge `entry` for `range` into the file read under the lock (another
+/// instance may have saved since this one loaded it), preserving entries for
+/// other ranges. Returns false for a checkpoint landing after the final
+/// results of its scan, which is dropped.
+fn merge(cache_file: &mut CacheFile, range: &str, mut entry: CacheEntry, scan_started: u64) -> bool {
+    let partial = entry.partial;
+    if let Some(previous) = cache_file.get(range) {
+        let finished = previous.partial.is_none() && previous.scanned_at >= scan_started;
+        if partial.is_some() && finished {
+            return false;
+        }
+    }
 
+    // Never move a host's last_seen backwards
+    if let Some(previous) = cache_file.get(range) {
+        let seen: HashMap<&str, u64> = previous
+            .hosts
+            .iter()
+            .filter_map(|h| Some((h.ip.as_str(), h.last_seen?)))
+            .collect();
+        for host in &mut entry.hosts {
+            host.last_seen = host.last_seen.max(seen.get(host.ip.as_str()).copied());
+        }
+    }
+
+    tracing::debug!(range, hosts = entry.hosts.len(), partial = partial.is_some(), "saving cache");
     cache_file.insert(range.to_string(), entry);
+    true
+}
 
-    if let Ok(json) = serde_json::to_string_pretty(&cache_file) {
-        let tmp_path = cache_path.with_extension("json.tmp");
-        if std::fs::write(&tmp_path, json).is_ok() {
-            let _ = std::fs::remove_file(&cache_path);
-            if std::fs::rename(&tmp_path, &cache_path).is_err() {
-                let _ = std::fs::copy(&tmp_path, &cache_path);
-                let _ = std::fs::remove_file(&tmp_path);
+/// How long [`Writer`] collects saves before rewriting the cache
+pub const WRITE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One range's results waiting for the [`Writer`]
+struct Pending {
+    hosts: Vec<HostInfo>,
+    partial: Option<Checkpoint>,
+    scan_started: u64,
+}
+
+type Sink = dyn Fn(Vec<(String, Pending)>) + Send + Sync;
+
+/// Debounced cache saves. Every save rewrites the whole file, so ranges
+/// queued within `interval` go out together in one rewrite from a background
+/// thread, and a range queued twice keeps only its latest results.
+/// [`Writer::flush`] writes whatever is queued right away, as does dropping
+/// the writer.
+pub struct Writer {
+    shared: Arc<Shared>,
+    /// Started by the first save
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    interval: Duration,
+    queue: Mutex<Queue>,
+    wake: Condvar,
+    /// Held from taking the queue until it is written, so an older batch
+    /// never lands after a newer one
+    writing: Mutex<()>,
+    sink: Box<Sink>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: HashMap<String, Pending>,
+    /// When the oldest unwritten save was queued
+    dirty_since: Option<Instant>,
+    closed: bool,
+}
+
+impl Writer {
+    pub fn new(interval: Duration) -> Self {
+        Self::with_sink(interval, Box::new(store_all))
+    }
+
+    fn with_sink(interval: Duration, sink: Box<Sink>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                interval,
+                queue: Mutex::new(Queue::default()),
+                wake: Condvar::new(),
+                writing: Mutex::new(()),
+                sink,
+            }),
+            worker: None,
+        }
+    }
+
+    /// Queue the final results of a scan of `range`
+    pub fn save(&mut self, range: &str, hosts: Vec<HostInfo>) {
+        self.queue(
+            range,
+            Pending {
+                hosts,
+                partial: None,
+                scan_started: 0,
+            },
+        );
+    }
+
+    /// Queue the results of a scan still in progress, marked as partial.
+    /// Final results of the range still queued are kept instead, and one
+    /// landing in the file after the final results of the same scan (started
+    /// at `scan_started`) is dropped.
+    pub fn checkpoint(
+        &mut self,
+        range: &str,
+        hosts: Vec<HostInfo>,
+        progress: Checkpoint,
+        scan_started: u64,
+    ) {
+        self.queue(
+            range,
+            Pending {
+                hosts,
+                partial: Some(progress),
+                scan_started,
+            },
+        );
+    }
+
+    /// Write everything queued now, on the calling thread
+    pub fn flush(&self) {
+        self.shared.write_queued();
+    }
+
+    fn queue(&mut self, range: &str, pending: Pending) {
+        if pending.hosts.is_empty() {
+            return;
+        }
+        {
+            let mut queue = self.shared.lock_queue();
+            let finished = queue.pending.get(range).is_some_and(|p| p.partial.is_none());
+            if pending.partial.is_some() && finished {
+                return;
             }
+            queue.pending.insert(range.to_string(), pending);
+            queue.dirty_since.get_or_insert_with(Instant::now);
+        }
+        self.shared.wake.notify_one();
+        if self.worker.is_none() {
+            let shared = Arc::clone(&self.shared);
+            self.worker = std::thread::Builder::new()
+                .name("cache-writer".to_string())
+                .spawn(move || shared.run())
+                .inspect_err(|e| tracing::warn!(error = %e, "cannot start cache writer"))
+                .ok();
+        }
+    }
+}
+
+impl Shared {
+    fn lock_queue(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Worker loop: write the queue `interval` after it became dirty
+    fn run(&self) {
+        let mut queue = self.lock_queue();
+        while !queue.closed {
+            let Some(since) = queue.dirty_since else {
+                queue = self.wake.wait(queue).unwrap_or_else(|e| e.into_inner());
+                continue;
+            };
+            let wait = (since + self.interval).saturating_duration_since(Instant::now());
+            if !wait.is_zero() {
+                queue = self.wake.wait_timeout(queue, wait).unwrap_or_else(|e| e.into_inner()).0;
+                continue;
+            }
+            drop(queue);
+            self.write_queued();
+            queue = self.lock_queue();
+        }
+    }
+
+    fn write_queued(&self) {
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        let batch: Vec<(String, Pending)> = {
+            let mut queue = self.lock_queue();
+            queue.dirty_since = None;
+            queue.pending.drain().collect()
+        };
+        if !batch.is_empty() {
+            tracing::debug!(ranges = batch.len(), "writing queued cache saves");
+            (self.sink)(batch);
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        self.shared.lock_queue().closed = true;
+        self.shared.wake.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        self.flush();
+    }
+}
+
+/// Drop the hostname, MAC, open ports and services cached for `ip`, in every
+/// range it was scanned in, keeping its liveness and history. Only that
+/// host's entries change; the rest of the file is written back as read.
+/// Returns how many ranges held the host.
+pub fn forget_host(ip: Ipv4Addr) -> usize {
+    let _lock = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _file_lock = lock_cache_file();
+    let Some(mut cache_file) = read_cache_file() else {
+        return 0;
+    };
+    let ip = ip.to_string();
+    let mut forgotten = 0;
+    for entry in cache_file.values_mut() {
+        for host in entry.hosts.iter_mut().filter(|h| h.ip == ip) {
+            host.hostname = None;
+            host.alias = None;
+            host.mdns_name = None;
+            host.netbios_name = None;
+            host.ptr_name = None;
+            host.mac_address = None;
+            host.mac_vendor = None;
+            host.open_ports.clear();
+            host.ports_scanned_at = None;
+            host.ports_spec = None;
+            host.previous_ports = None;
+            host.services.clear();
+            forgotten += 1;
+        }
+    }
+    if forgotten > 0 {
+        tracing::debug!(%ip, ranges = forgotten, "forgetting cached host details");
+        write_cache_file(&cache_file);
+    }
+    forgotten
+}
+
+/// Record each host's last_seen in `seen`, keeping the newest per address
+pub fn merge_last_seen<'a>(
+    seen: &mut HashMap<Ipv4Addr, u64>,
+    hosts: impl IntoIterator<Item = &'a HostInfo>,
+) {
+    for host in hosts {
+        if let Some(at) = host.last_seen {
+            let entry = seen.entry(host.ip).or_insert(at);
+            *entry = (*entry).max(at);
         }
     }
 }
@@ -178,19 +604,18 @@ pub fn format_cache_age(scanned_at: u64) -> String {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use std::sync::{Mutex, OnceLock};
 
-    fn env_lock() -> &'static Mutex<()> {
+    /// Held by any test that points `IPSCANNR_CACHE_FILE` somewhere
+    pub(crate) fn env_lock() -> &'static Mutex<()> {
         static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
         LOCK.get_or_init(|| Mutex::new(()))
     }
 
     fn sample_host(ip: Ipv4Addr, is_alive: bool) -> HostInfo {
         HostInfo {
-            ip,
-            is_alive,
             rtt: Some(Duration::from_millis(10)),
             hostname: Some("host.local".to_string()),
             mac: Some(MacInfo {
@@ -199,13 +624,7 @@ mod tests {
             }),
             open_ports: vec![80, 443],
             ports_scanned: true,
-            cached_at: None,
-            method: PingMethod::Icmp,
-            status: if is_alive {
-                HostStatus::Online
-            } else {
-                HostStatus::Offline
-            },
+            ..HostInfo::pinged(ip, is_alive)
         }
     }
 
@@ -228,6 +647,38 @@ mod tests {
         let _ = std::fs::remove_file(temp_path);
     }
 
+    #[test]
+    fn port_scan_time_and_set_survive_the_cache() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_ports_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        // A scan that found nothing is still a scan once it has a time
+        let mut host = sample_host(Ipv4Addr::new(10, 0, 0, 10), true);
+        host.open_ports.clear();
+        host.ports_scanned_at = Some(1_700_000_000);
+        host.ports_spec = Some("common".to_string());
+        // So does what the scan before it found, for the port changes
+        host.previous_ports = Some(vec![23]);
+        save_cache("10.0.0.0/24", &[host, sample_host(Ipv4Addr::new(10, 0, 0, 11), true)]);
+
+        let loaded = load_cache("10.0.0.0/24");
+        assert!(loaded[0].ports_scanned);
+        assert_eq!(loaded[0].ports_scanned_at, Some(1_700_000_000));
+        assert_eq!(loaded[0].ports_spec.as_deref(), Some("common"));
+        assert_eq!(loaded[0].port_diff().unwrap().removed, [23]);
+        assert_eq!((loaded[1].ports_scanned_at, loaded[1].ports_spec.as_deref()), (None, None));
+        assert_eq!(loaded[1].previous_ports, None);
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path);
+    }
+
     #[test]
     fn save_cache_preserves_other_ranges() {
         let _guard = env_lock().lock().expect("test env lock");
@@ -257,4 +708,299 @@ mod tests {
         }
         let _ = std::fs::remove_file(temp_path);
     }
+
+    #[test]
+    fn interleaved_saves_from_two_instances_keep_both_ranges() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_interleave_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        // Another instance is mid-save of its range, holding the lock; ours
+        // starts saving a different range meanwhile
+        let other = lock_cache_file().expect("lock cache");
+        let saver = std::thread::spawn(|| {
+            save_cache("192.168.1.0/24", &[sample_host(Ipv4Addr::new(192, 168, 1, 20), true)]);
+        });
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!saver.is_finished(), "save ignored the lock");
+        let other_entry = r#"{"10.0.0.0/24": {"scanned_at": 1, "hosts": [{"ip": "10.0.0.10",
+            "is_alive": true, "rtt_ms": null, "hostname": null, "mac_address": null,
+            "mac_vendor": null, "open_ports": []}]}}"#;
+        std::fs::write(&temp_path, other_entry).expect("write other instance's save");
+        drop(other);
+        saver.join().expect("save thread");
+
+        assert_eq!(load_cache("10.0.0.0/24").len(), 1);
+        assert_eq!(load_cache("192.168.1.0/24").len(), 1);
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path.with_extension("json.lock"));
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn forget_host_clears_enrichment_in_every_range() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_forget_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        let ip = Ipv4Addr::new(10, 0, 2, 5);
+        let mut host = sample_host(ip, true);
+        host.services = vec!["airplay".to_string()];
+        host.names.ptr = Some("host.lan".to_string());
+        host.previous_ports = Some(vec![22, 80]);
+        host.ports_scanned_at = Some(1_700_000_000);
+        host.ports_spec = Some("common".to_string());
+        let mut neighbour = sample_host(Ipv4Addr::new(10, 0, 2, 6), true);
+        neighbour.names.mdns = Some("host.local".to_string());
+        neighbour.names.netbios = Some("HOST".to_string());
+        save_cache("10.0.2.0/24", &[host.clone(), neighbour.clone()]);
+        save_cache("10.0.2.0/28", &[host]);
+
+        assert_eq!(forget_host(ip), 2);
+        for range in ["10.0.2.0/24", "10.0.2.0/28"] {
+            let forgotten = load_cache(range).into_iter().find(|h| h.ip == ip).unwrap();
+            assert!(forgotten.is_alive);
+            assert!(forgotten.hostname.is_none());
+            assert_eq!(forgotten.names, Names::default());
+            assert!(forgotten.mac.is_none());
+            assert!(forgotten.open_ports.is_empty() && !forgotten.ports_scanned);
+            assert!(forgotten.ports_scanned_at.is_none() && forgotten.ports_spec.is_none());
+            assert!(forgotten.previous_ports.is_none());
+            assert!(forgotten.services.is_empty());
+            assert_eq!(forgotten.rtt, Some(Duration::from_millis(10)));
+        }
+        let kept = load_cache("10.0.2.0/24").into_iter().find(|h| h.ip == neighbour.ip).unwrap();
+        assert_eq!(kept.hostname, neighbour.hostname);
+        assert_eq!(kept.names, neighbour.names);
+        assert_eq!(kept.open_ports, neighbour.open_ports);
+        assert_eq!(forget_host(Ipv4Addr::new(10, 9, 9, 9)), 0);
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path.with_extension("json.lock"));
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn last_seen_never_moves_backwards() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_last_seen_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        let range = "10.0.1.0/24";
+        let ip = Ipv4Addr::new(10, 0, 1, 5);
+        let mut seen = sample_host(ip, true);
+        seen.last_seen = Some(1_700_000_000);
+        save_cache(range, &[seen]);
+
+        // Offline in the next scan, without history of its own
+        save_cache(range, &[sample_host(ip, false)]);
+        let loaded = load_cache(range);
+        assert_eq!(loaded[0].last_seen, Some(1_700_000_000));
+
+        let mut map = HashMap::from([(ip, 1_800_000_000)]);
+        merge_last_seen(&mut map, &loaded);
+        assert_eq!(map[&ip], 1_800_000_000);
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn sub_millisecond_rtts_survive_the_cache() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_rtt_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        let range = "10.0.4.0/24";
+        let mut fast = sample_host(Ipv4Addr::new(10, 0, 4, 1), true);
+        fast.rtt = Some(Duration::from_micros(400));
+        let mut slower = sample_host(Ipv4Addr::new(10, 0, 4, 2), true);
+        slower.rtt = Some(Duration::from_micros(9_900));
+        save_cache(range, &[fast, slower]);
+        let loaded = load_cache(range);
+        assert_eq!(loaded[0].rtt, Some(Duration::from_micros(400)));
+        assert_eq!(loaded[1].rtt, Some(Duration::from_micros(9_900)));
+
+        // Files from before rtt_us still load their milliseconds
+        let old = r#"{"10.0.5.0/24": {"scanned_at": 1, "hosts": [{"ip": "10.0.5.1",
+            "is_alive": true, "rtt_ms": 9, "hostname": null, "mac_address": null,
+            "mac_vendor": null, "open_ports": []}]}}"#;
+        std::fs::write(&temp_path, old).expect("write old cache");
+        assert_eq!(load_cache("10.0.5.0/24")[0].rtt, Some(Duration::from_millis(9)));
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path.with_extension("json.lock"));
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn discovery_probe_round_trips() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_probe_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        let range = "10.0.3.0/24";
+        let probe = Probe {
+            port: Some(445),
+            attempt: 2,
+            suspect: true,
+        };
+        let mut tcp = sample_host(Ipv4Addr::new(10, 0, 3, 1), true);
+        tcp.probe = Some(probe);
+        let mut icmp = sample_host(Ipv4Addr::new(10, 0, 3, 2), true);
+        icmp.probe = Some(Probe {
+            port: None,
+            attempt: 1,
+            suspect: false,
+        });
+        save_cache(range, &[tcp, icmp]);
+
+        let loaded = load_cache(range);
+        assert_eq!(loaded[0].probe, Some(probe));
+        assert_eq!(loaded[1].probe.unwrap().port, None);
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn checkpoints_are_partial_until_final_save() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_checkpoint_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        let range = "10.0.4.0/24";
+        let started = now_secs();
+        let progress = Checkpoint {
+            scanned: 100,
+            total: 256,
+        };
+        let first = [sample_host(Ipv4Addr::new(10, 0, 4, 1), true)];
+        save_checkpoint(range, &first, progress, started);
+        assert_eq!(load_checkpoint(range), Some(progress));
+        assert_eq!(load_cache(range).len(), 1);
+
+        // The scan finished: a checkpoint written late must not win
+        let all = [first[0].clone(), sample_host(Ipv4Addr::new(10, 0, 4, 2), false)];
+        save_cache(range, &all);
+        save_checkpoint(range, &first, progress, started);
+        assert_eq!(load_checkpoint(range), None);
+        assert_eq!(load_cache(range).len(), 2);
+
+        // A later scan checkpoints over the old results
+        save_checkpoint(range, &first, progress, now_secs() + 1);
+        assert_eq!(load_checkpoint(range), Some(progress));
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn legacy_entries_take_last_seen_from_scan_time() {
+        let _guard = env_lock().lock().expect("test env lock");
+        let temp_path = std::env::temp_dir().join("ipscannr_cache_legacy_seen_test.json");
+        std::fs::write(
+            &temp_path,
+            r#"{"10.0.2.0/24": {"scanned_at": 1600000000, "hosts": [
+                {"ip": "10.0.2.1", "is_alive": true, "rtt_ms": null, "hostname": null,
+                 "mac_address": null, "mac_vendor": null, "open_ports": []},
+                {"ip": "10.0.2.2", "is_alive": false, "rtt_ms": null, "hostname": null,
+                 "mac_address": null, "mac_vendor": null, "open_ports": []}]}}"#,
+        )
+        .expect("write legacy cache");
+        unsafe {
+            std::env::set_var(CACHE_FILE_ENV, &temp_path);
+        }
+
+        let loaded = load_cache("10.0.2.0/24");
+        assert_eq!(loaded[0].last_seen, Some(1_600_000_000));
+        assert_eq!(loaded[1].last_seen, None);
+        assert!(loaded[0].probe.is_none());
+
+        unsafe {
+            std::env::remove_var(CACHE_FILE_ENV);
+        }
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    /// Each write of a batch, as (range, host count, partial)
+    type Writes = Arc<Mutex<Vec<Vec<(String, usize, bool)>>>>;
+
+    fn recording_writer(interval: Duration) -> (Writer, Writes) {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let record = Arc::clone(&writes);
+        let writer = Writer::with_sink(
+            interval,
+            Box::new(move |batch: Vec<(String, Pending)>| {
+                let mut batch: Vec<(String, usize, bool)> = batch
+                    .into_iter()
+                    .map(|(range, p)| (range, p.hosts.len(), p.partial.is_some()))
+                    .collect();
+                batch.sort();
+                record.lock().unwrap().push(batch);
+            }),
+        );
+        (writer, writes)
+    }
+
+    #[test]
+    fn writer_batches_ranges_and_keeps_the_latest_save() {
+        let (mut writer, writes) = recording_writer(Duration::from_millis(200));
+        let host = |i| sample_host(Ipv4Addr::new(10, 0, 5, i), true);
+        let progress = Checkpoint { scanned: 1, total: 4 };
+        writer.checkpoint("a", vec![host(1)], progress, 0);
+        writer.checkpoint("a", vec![host(1), host(2)], progress, 0);
+        writer.save("b", vec![host(3)]);
+        writer.save("empty", Vec::new());
+        assert!(writes.lock().unwrap().is_empty(), "nothing before the interval");
+
+        std::thread::sleep(Duration::from_millis(600));
+        assert_eq!(
+            *writes.lock().unwrap(),
+            [vec![("a".to_string(), 2, true), ("b".to_string(), 1, false)]]
+        );
+
+        // Final results win over a checkpoint queued after them
+        writer.save("a", vec![host(1), host(2), host(4)]);
+        writer.checkpoint("a", vec![host(1)], progress, 0);
+        writer.flush();
+        assert_eq!(writes.lock().unwrap()[1], [("a".to_string(), 3, false)]);
+
+        // Dropping the writer flushes what is still queued
+        writer.save("c", vec![host(5)]);
+        drop(writer);
+        assert_eq!(writes.lock().unwrap().len(), 3);
+    }
+
 }
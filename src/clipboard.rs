@@ -0,0 +1,75 @@
+use std::io::{self, Write};
+
+/// Copy `text` to the system clipboard. Windows pipes it to `clip.exe`;
+/// elsewhere an OSC 52 sequence asks the terminal to set the clipboard, which
+/// also works over SSH.
+pub fn copy(text: &str) -> io::Result<()> {
+    #[cfg(windows)]
+    {
+        use std::process::{Command, Stdio};
+        let mut child = Command::new("clip").stdin(Stdio::piped()).spawn()?;
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        child.wait()?;
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        let mut stdout = io::stdout();
+        stdout.write_all(osc52(text).as_bytes())?;
+        stdout.flush()
+    }
+}
+
+/// OSC 52 "set clipboard" sequence for `text`
+#[cfg_attr(windows, allow(dead_code))]
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Standard base64 with padding (RFC 4648)
+#[cfg_attr(windows, allow(dead_code))]
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(base64(input.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn osc52_wraps_encoded_text() {
+        assert_eq!(osc52("10.0.0.1"), "\x1b]52;c;MTAuMC4wLjE=\x07");
+    }
+}
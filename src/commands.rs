@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::HostInfo;
+use crate::scanner::{Knock, KnockPlan, KnockProtocol};
+
+/// A command line offered by the copy-command chooser (`Y`). `{ip}`,
+/// `{host}` (hostname, else the IP) and `{port}` are filled in per host.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandTemplate {
+    pub label: String,
+    pub template: String,
+    /// Only offered when this port is open, or before the host's ports are
+    /// scanned; None offers it for every host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+/// A port knocking sequence offered by the knock chooser (`Ctrl+K`). Picking
+/// it sends the knocks to the selected host, streaming how it went to the
+/// output overlay; nothing is copied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnockAction {
+    pub label: String,
+    /// Knocked in order
+    pub ports: Vec<u16>,
+    /// `tcp` (a connect attempt) or `udp` (an empty datagram)
+    #[serde(default = "default_knock_protocol")]
+    pub protocol: String,
+    /// Between one knock and the next
+    #[serde(default = "default_knock_delay")]
+    pub delay_ms: u64,
+    /// TCP port probed after the last knock to tell whether it opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check_port: Option<u16>,
+}
+
+fn default_knock_protocol() -> String {
+    "tcp".to_string()
+}
+
+fn default_knock_delay() -> u64 {
+    500
+}
+
+fn template(label: &str, template: &str, port: Option<u16>) -> CommandTemplate {
+    CommandTemplate {
+        label: label.to_string(),
+        template: template.to_string(),
+        port,
+    }
+}
+
+/// Templates for the platform's own tools
+pub fn default_templates() -> Vec<CommandTemplate> {
+    if cfg!(windows) {
+        vec![
+            template("Ping", "ping -t {ip}", None),
+            template("Tracert", "tracert -d {ip}", None),
+            template("SSH", "ssh admin@{ip}", Some(22)),
+            template("RDP", "mstsc /v:{ip}", Some(3389)),
+            template("SMB share", "explorer \\\\{ip}", Some(445)),
+            template("HTTP", "start http://{ip}/", Some(80)),
+            template("HTTPS", "start https://{ip}/", Some(443)),
+        ]
+    } else {
+        vec![
+            template("Ping", "ping {ip}", None),
+            template("Traceroute", "traceroute -n {ip}", None),
+            template("SSH", "ssh admin@{ip}", Some(22)),
+            template("RDP", "xfreerdp /v:{ip}", Some(3389)),
+            template("SMB share", "smbclient -L //{ip} -N", Some(445)),
+            template("HTTP", "curl -I http://{ip}/", Some(80)),
+            template("HTTPS", "curl -kI https://{ip}/", Some(443)),
+        ]
+    }
+}
+
+impl CommandTemplate {
+    /// Whether the chooser offers this template for `host`
+    pub fn applies_to(&self, host: &HostInfo) -> bool {
+        match self.port {
+            Some(port) => !host.ports_scanned || host.open_ports.contains(&port),
+            None => true,
+        }
+    }
+
+    /// The command line for `host`
+    pub fn expand(&self, host: &HostInfo) -> String {
+        let ip = host.ip.to_string();
+        let port = self.port.map(|p| p.to_string()).unwrap_or_default();
+        self.template
+            .replace("{ip}", &ip)
+            .replace("{host}", host.hostname.as_deref().unwrap_or(&ip))
+            .replace("{port}", &port)
+    }
+}
+
+impl KnockAction {
+    /// The sequence to send; an error for a malformed entry
+    pub fn plan(&self) -> Result<KnockPlan, String> {
+        let protocol = match self.protocol.trim().to_ascii_lowercase().as_str() {
+            "tcp" => KnockProtocol::Tcp,
+            "udp" => KnockProtocol::Udp,
+            _ => return Err(format!("'{}': protocol must be tcp or udp", self.label)),
+        };
+        if self.ports.is_empty() || self.ports.contains(&0) {
+            return Err(format!("'{}' needs knock ports from 1 to 65535", self.label));
+        }
+        Ok(KnockPlan {
+            knocks: self.ports.iter().map(|&port| Knock { port, protocol }).collect(),
+            delay: Duration::from_millis(self.delay_ms),
+            check_port: self.check_port,
+        })
+    }
+
+    /// What the chooser lists next to the label
+    pub fn describe(&self) -> String {
+        let ports: Vec<String> = self.ports.iter().map(|p| p.to_string()).collect();
+        let mut text = format!("{} {}", self.protocol.trim().to_ascii_lowercase(), ports.join(" "));
+        if let Some(port) = self.check_port {
+            text.push_str(&format!(", then check {}", port));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn host(open_ports: Option<Vec<u16>>) -> HostInfo {
+        HostInfo {
+            hostname: Some("nas.lan".to_string()),
+            ports_scanned: open_ports.is_some(),
+            open_ports: open_ports.unwrap_or_default(),
+            ..HostInfo::pinged(Ipv4Addr::new(192, 168, 1, 5), true)
+        }
+    }
+
+    #[test]
+    fn templates_expand_host_fields() {
+        let ssh = template("SSH", "ssh -p {port} admin@{host} # {ip}", Some(2222));
+        assert_eq!(ssh.expand(&host(None)), "ssh -p 2222 admin@nas.lan # 192.168.1.5");
+        let mut unnamed = host(None);
+        unnamed.hostname = None;
+        assert_eq!(template("Ping", "ping {host}", None).expand(&unnamed), "ping 192.168.1.5");
+    }
+
+    #[test]
+    fn port_templates_follow_the_port_scan() {
+        let labels = |host: &HostInfo| -> Vec<String> {
+            default_templates()
+                .into_iter()
+                .filter(|t| t.applies_to(host))
+                .map(|t| t.label)
+                .collect()
+        };
+        // Unknown ports: everything is offered
+        assert_eq!(labels(&host(None)).len(), default_templates().len());
+        let scanned = labels(&host(Some(vec![22, 8080])));
+        assert!(scanned.contains(&"SSH".to_string()));
+        assert!(!scanned.contains(&"RDP".to_string()));
+        assert!(scanned.contains(&"Ping".to_string()));
+    }
+
+    fn knock(json: &str) -> KnockAction {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn knock_actions_build_a_plan() {
+        let ssh = knock(r#"{"label": "SSH", "ports": [7000, 8000], "protocol": "UDP", "delay_ms": 250, "check_port": 22}"#);
+        assert_eq!(ssh.describe(), "udp 7000 8000, then check 22");
+        let plan = ssh.plan().unwrap();
+        assert_eq!(plan.knocks.iter().map(|k| k.to_string()).collect::<Vec<_>>(), ["7000/udp", "8000/udp"]);
+        assert_eq!((plan.delay, plan.check_port), (Duration::from_millis(250), Some(22)));
+
+        // Defaults: TCP, 500 ms apart, nothing probed
+        let bare = knock(r#"{"label": "Open", "ports": [7000]}"#);
+        assert_eq!(bare.describe(), "tcp 7000");
+        let plan = bare.plan().unwrap();
+        assert_eq!((plan.knocks[0].protocol, plan.delay, plan.check_port), (KnockProtocol::Tcp, Duration::from_millis(500), None));
+
+        assert!(knock(r#"{"label": "X", "ports": [7000], "protocol": "icmp"}"#).plan().is_err());
+        assert!(knock(r#"{"label": "X", "ports": []}"#).plan().is_err());
+        assert!(knock(r#"{"label": "X", "ports": [0]}"#).plan().is_err());
+    }
+}
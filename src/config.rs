@@ -1,34 +1,268 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::scanner::{PingerConfig, PortScannerConfig};
+use crate::alert::AlertMode;
+use crate::app::HostInfo;
+use crate::names::NameSource;
+use crate::scanner::adapters::AdapterType;
+use crate::scanner::{
+    PingerConfig, PortScannerConfig, RateLimiter, DEFAULT_ICMP_PAYLOAD, TCP_PROBE_PORTS,
+};
+
+/// What `--range` names: a range to use as typed, or an adapter whose subnet
+/// is picked once the adapters have loaded
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum RangeSpec {
+    /// The best adapter (the default route's, then Ethernet, then WiFi, then
+    /// the rest): `auto`, or no `--range` at all
+    #[default]
+    Auto,
+    /// The first adapter of this type: `ethernet`, `wifi` or `vpn`
+    Adapter(AdapterType),
+    Literal(String),
+}
+
+impl RangeSpec {
+    pub fn parse(input: &str) -> Self {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "auto" => RangeSpec::Auto,
+            "ethernet" | "eth" => RangeSpec::Adapter(AdapterType::Ethernet),
+            "wifi" | "wi-fi" | "wlan" => RangeSpec::Adapter(AdapterType::Wifi),
+            "vpn" => RangeSpec::Adapter(AdapterType::Vpn),
+            _ => RangeSpec::Literal(input.to_string()),
+        }
+    }
+}
+
+/// What a second Enter on a host runs (`--enter-action`)
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum HostAction {
+    /// Port scan an online host whose ports aren't scanned yet, otherwise
+    /// continuous ping
+    #[default]
+    Auto,
+    /// Port scan
+    Ports,
+    /// Continuous ping
+    Ping,
+    /// Tracert
+    Tracert,
+    /// Wake-on-LAN
+    Wake,
+    /// Nothing: Enter only opens the details
+    None,
+}
+
+impl HostAction {
+    /// Short description for the Ctrl popup; `None` for no action
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            HostAction::Auto => Some("port scan or ping"),
+            HostAction::Ports => Some("port scan"),
+            HostAction::Ping => Some("ping"),
+            HostAction::Tracert => Some("tracert"),
+            HostAction::Wake => Some("Wake-on-LAN"),
+            HostAction::None => None,
+        }
+    }
+}
+
+/// Addresses a scan may cover before it asks first: a /20 takes seconds on
+/// the defaults, while a mistyped /14 takes minutes
+pub const LARGE_SCAN: usize = 4096;
+
+/// TCP ports safe mode allows unless told otherwise
+pub const SAFE_MODE_PORTS: [u16; 1] = [443];
+
+/// The TCP ports safe mode allows, sorted; empty for ICMP only
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowedPorts(pub Vec<u16>);
+
+/// `--safe-ports` value: ports and `start-end` ranges, comma-separated, or
+/// `none` for ICMP only. Unlike `scanner::parse_ports`, anything it can't
+/// read is an error, so a typo can't quietly leave ICMP only.
+pub fn parse_allowed_ports(text: &str) -> Result<AllowedPorts, String> {
+    if text.trim().eq_ignore_ascii_case("none") {
+        return Ok(AllowedPorts(Vec::new()));
+    }
+    let mut ports = Vec::new();
+    for part in text.split(',').map(str::trim) {
+        let port = |p: &str| p.trim().parse::<u16>().ok().filter(|&p| p > 0);
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (port(start), port(end)),
+            None => (port(part), port(part)),
+        };
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => ports.extend(start..=end),
+            _ if part.is_empty() => {
+                return Err("expected ports like 443,8443 or 'none' for ICMP only".to_string())
+            }
+            _ => return Err(format!("'{}' is not a port (1-65535) or port range", part)),
+        }
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    Ok(AllowedPorts(ports))
+}
 
 /// Application configuration
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Range shown until the adapters load, and kept after if `range_spec`
+    /// is a literal range
     pub default_range: String,
+    /// What `--range` asked for
+    pub range_spec: RangeSpec,
     pub ping: PingerConfig,
     pub port_scan: PortScannerConfig,
     pub resolve_hostnames: bool,
     pub detect_mac: bool,
+    /// Send a MAC lookup that misses the ARP table a UDP datagram first, so
+    /// the OS resolves the host and the retry finds it (`--arp-prewarm`)
+    pub arp_prewarm: bool,
     pub compat: bool,
+    /// Strip all colors from rendering (`--no-color` / `NO_COLOR`)
+    pub monochrome: bool,
+    /// How to signal a finished scan (`--notify`)
+    pub alert_mode: AlertMode,
+    /// Ping / tracert output lines kept for the overlay; the oldest are
+    /// dropped beyond this (`--overlay-lines`)
+    pub overlay_max_lines: usize,
+    /// Ranges with more addresses than this keep only online hosts in memory
+    /// and just count the offline ones (`--offline-limit`)
+    pub offline_retain_limit: usize,
+    /// Scans of more addresses than this wait for a confirmation
+    /// (`--confirm-above`); None starts them right away (`--yes`)
+    pub confirm_scan_above: Option<usize>,
+    /// Host action run by Enter once the details are open (`--enter-action`)
+    pub enter_action: HostAction,
+    /// Ping the default gateway and `internet_anchor` in the background for
+    /// the header indicator (off with `--no-connectivity`)
+    pub connectivity_check: bool,
+    /// Hold background re-probing (the connectivity check) while the terminal
+    /// window is unfocused (`--pause-unfocused`)
+    pub pause_unfocused: bool,
+    /// Address that stands for "the internet" (`--anchor`)
+    pub internet_anchor: Ipv4Addr,
+    /// How long SSDP / mDNS discovery listens for answers (`--discover-window`)
+    pub discovery_window: Duration,
+    /// Which of a host's names the table shows, first found wins (`--name-order`)
+    pub name_precedence: Vec<NameSource>,
+    /// Gap between magic packets when waking several hosts (`--wol-interval`)
+    pub wol_interval: Duration,
+    /// How long to ping woken hosts for an answer; None skips the watch
+    /// (`--wol-watch 0`)
+    pub wol_watch: Option<Duration>,
+    /// Exports include offline hosts (export dialog [O], saved in settings)
+    pub export_offline: bool,
+    /// Exports leave out hosts without open ports (export dialog [P])
+    pub export_open_ports_only: bool,
+    /// Prometheus textfile rewritten after every completed scan
+    /// (`--metrics-file`)
+    pub metrics_file: Option<PathBuf>,
+    /// The only TCP ports probed, in discovery and port scans, when in safe
+    /// mode (`--safe-mode`); None when off. Safe mode also turns off
+    /// everything sending UDP. Set with [`Config::enter_safe_mode`].
+    pub safe_mode: Option<Vec<u16>>,
+    /// Names set by hand per address (the settings file's `aliases`)
+    pub aliases: HashMap<Ipv4Addr, String>,
+}
+
+impl Config {
+    /// Whether the scan looks up a hostname or MAC for `host` after it
+    /// answers. MACs are only looked up on a `local` subnet, where ARP sees them.
+    pub fn enriches(&self, host: &HostInfo, local: bool) -> bool {
+        host.is_alive && (self.resolve_hostnames || (self.detect_mac && local))
+    }
+
+    /// Cap ping and port scan probes together at `max_probes_per_sec`
+    /// (`--rate`); 0 lifts the cap
+    pub fn limit_rate(&mut self, max_probes_per_sec: u32) {
+        let limit = (max_probes_per_sec > 0)
+            .then(|| Arc::new(RateLimiter::new(max_probes_per_sec)));
+        self.ping.rate_limit = limit.clone();
+        self.port_scan.rate_limit = limit;
+    }
+
+    /// Send ping and port scan probes from `source` (`--source-ip`)
+    pub fn send_from(&mut self, source: Ipv4Addr) {
+        self.ping.source_ip = Some(source);
+        self.port_scan.source_ip = Some(source);
+    }
+
+    /// Probe with ICMP echo and `allowed_ports` only (`--safe-mode`)
+    pub fn enter_safe_mode(&mut self, allowed_ports: Vec<u16>) {
+        self.ping.tcp_ports = allowed_ports.clone();
+        self.safe_mode = Some(allowed_ports);
+    }
+
+    /// Whether MAC lookups may send the UDP datagram of `arp_prewarm`
+    pub fn prewarms(&self) -> bool {
+        self.arp_prewarm && self.safe_mode.is_none()
+    }
+
+    /// Whether hostname lookups also ask the host for its NetBIOS name, a
+    /// UDP query safe mode rules out
+    pub fn queries_netbios(&self) -> bool {
+        self.resolve_hostnames && self.safe_mode.is_none()
+    }
+
+    /// The probe rate cap set by [`Config::limit_rate`]
+    pub fn rate_limit(&self) -> Option<&RateLimiter> {
+        self.ping.rate_limit.as_deref()
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             default_range: "192.168.1.0/24".to_string(),
+            range_spec: RangeSpec::Auto,
             ping: PingerConfig {
                 timeout: Duration::from_millis(300),
                 retries: 0,
                 concurrent_limit: 100,
+                refused_means_alive: true,
+                rate_limit: None,
+                source_ip: None,
+                payload_size: DEFAULT_ICMP_PAYLOAD,
+                tcp_ports: TCP_PROBE_PORTS.to_vec(),
             },
             port_scan: PortScannerConfig {
                 timeout: Duration::from_millis(500),
                 concurrent_limit: 50,
+                retries: 1,
+                rate_limit: None,
+                source_ip: None,
             },
             resolve_hostnames: true,
             detect_mac: true,
+            arp_prewarm: false,
             compat: false,
+            monochrome: false,
+            alert_mode: AlertMode::Off,
+            // About 3 hours of one-per-second ping output
+            overlay_max_lines: 10_000,
+            // A whole /16 is still kept in full
+            offline_retain_limit: 65_536,
+            confirm_scan_above: Some(LARGE_SCAN),
+            enter_action: HostAction::Auto,
+            connectivity_check: true,
+            pause_unfocused: false,
+            internet_anchor: Ipv4Addr::new(1, 1, 1, 1),
+            discovery_window: Duration::from_secs(3),
+            name_precedence: NameSource::DEFAULT_ORDER.to_vec(),
+            // Slow enough for switches that rate-limit broadcasts
+            wol_interval: Duration::from_millis(100),
+            wol_watch: Some(Duration::from_secs(120)),
+            export_offline: true,
+            export_open_ports_only: false,
+            metrics_file: None,
+            safe_mode: None,
+            aliases: HashMap::new(),
         }
     }
 }
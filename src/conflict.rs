@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+
+use crate::app::HostInfo;
+
+/// Something suspicious about how addresses map to MAC addresses
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// One MAC answering on several IPs: an IP conflict, or a bridge or
+    /// proxy-ARP device answering for others
+    SharedMac { mac: String, ips: Vec<Ipv4Addr> },
+    /// The MAC behind an IP differs from the cached snapshot
+    MacChanged {
+        ip: Ipv4Addr,
+        previous: String,
+        current: String,
+    },
+}
+
+/// How a line of the conflicts overlay is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffKind {
+    Heading,
+    Removed,
+    Added,
+}
+
+impl Conflict {
+    /// Addresses affected by this conflict
+    pub fn ips(&self) -> &[Ipv4Addr] {
+        match self {
+            Conflict::SharedMac { ips, .. } => ips,
+            Conflict::MacChanged { ip, .. } => std::slice::from_ref(ip),
+        }
+    }
+
+    pub fn involves(&self, ip: Ipv4Addr) -> bool {
+        self.ips().contains(&ip)
+    }
+
+    /// One-line explanation for the details pane of `ip`
+    pub fn describe_for(&self, ip: Ipv4Addr) -> String {
+        match self {
+            Conflict::SharedMac { ips, .. } => {
+                let others: Vec<String> = ips
+                    .iter()
+                    .filter(|&&other| other != ip)
+                    .map(|other| other.to_string())
+                    .collect();
+                format!("Same MAC as {}", others.join(", "))
+            }
+            Conflict::MacChanged { previous, .. } => {
+                format!("MAC changed since last scan (was {})", previous)
+            }
+        }
+    }
+
+    /// Diff-style lines for the conflicts overlay
+    pub fn diff_lines(&self) -> Vec<(DiffKind, String)> {
+        match self {
+            Conflict::SharedMac { mac, ips } => {
+                let mut lines = vec![(
+                    DiffKind::Heading,
+                    format!("MAC {} answers on {} addresses", mac, ips.len()),
+                )];
+                lines.extend(ips.iter().map(|ip| (DiffKind::Added, format!("+ {}", ip))));
+                lines
+            }
+            Conflict::MacChanged {
+                ip,
+                previous,
+                current,
+            } => vec![
+                (DiffKind::Heading, format!("{} changed MAC", ip)),
+                (DiffKind::Removed, format!("- {}  (cached)", previous)),
+                (DiffKind::Added, format!("+ {}  (now)", current)),
+            ],
+        }
+    }
+}
+
+/// Addresses answering with the same MAC
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacGroup {
+    pub mac: String,
+    /// Ascending, at least two
+    pub ips: Vec<Ipv4Addr>,
+    /// Every address resolved to the same name: one device with several
+    /// addresses (virtual IPs, aliases) rather than a conflict
+    pub same_host: bool,
+}
+
+impl MacGroup {
+    /// The group's addresses other than `ip`
+    pub fn others(&self, ip: Ipv4Addr) -> impl Iterator<Item = Ipv4Addr> + '_ {
+        self.ips.iter().copied().filter(move |&other| other != ip)
+    }
+}
+
+/// Group the hosts of `hosts` that share a MAC. MACs and hostnames are
+/// compared case-insensitively; hosts without a MAC are ignored.
+pub fn mac_groups(hosts: &[HostInfo]) -> Vec<MacGroup> {
+    let mut by_mac: BTreeMap<String, Vec<&HostInfo>> = BTreeMap::new();
+    for host in hosts {
+        if let Some(mac) = &host.mac {
+            by_mac.entry(normalize(&mac.address)).or_default().push(host);
+        }
+    }
+    by_mac
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(mac, group)| {
+            let name = |h: &HostInfo| h.hostname.as_deref().map(str::to_ascii_lowercase);
+            let first = name(group[0]);
+            let same_host = first.is_some() && group.iter().all(|&h| name(h) == first);
+            let mut ips: Vec<Ipv4Addr> = group.iter().map(|h| h.ip).collect();
+            ips.sort();
+            MacGroup { mac, ips, same_host }
+        })
+        .collect()
+}
+
+/// Find MACs shared by several hosts in `hosts` that aren't one named
+/// device, and hosts whose MAC differs from the one recorded for the same
+/// IP in `previous`. MACs are compared case-insensitively; hosts without a
+/// MAC are ignored.
+pub fn detect(hosts: &[HostInfo], previous: &[HostInfo]) -> Vec<Conflict> {
+    let mut conflicts: Vec<Conflict> = mac_groups(hosts)
+        .into_iter()
+        .filter(|group| !group.same_host)
+        .map(|MacGroup { mac, ips, .. }| Conflict::SharedMac { mac, ips })
+        .collect();
+
+    let before: HashMap<Ipv4Addr, String> = previous
+        .iter()
+        .filter_map(|h| Some((h.ip, normalize(&h.mac.as_ref()?.address))))
+        .collect();
+    conflicts.extend(hosts.iter().filter_map(|h| {
+        let current = normalize(&h.mac.as_ref()?.address);
+        let previous = before.get(&h.ip)?;
+        (*previous != current).then(|| Conflict::MacChanged {
+            ip: h.ip,
+            previous: previous.clone(),
+            current,
+        })
+    }));
+    conflicts
+}
+
+fn normalize(mac: &str) -> String {
+    mac.trim().to_ascii_uppercase().replace('-', ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::MacInfo;
+
+    fn named(last_octet: u8, mac: &str, hostname: Option<&str>) -> HostInfo {
+        HostInfo {
+            hostname: hostname.map(str::to_string),
+            ..host(last_octet, Some(mac))
+        }
+    }
+
+    fn host(last_octet: u8, mac: Option<&str>) -> HostInfo {
+        HostInfo {
+            mac: mac.map(|address| MacInfo {
+                address: address.to_string(),
+                vendor: None,
+            }),
+            ..HostInfo::pinged(Ipv4Addr::new(10, 0, 0, last_octet), true)
+        }
+    }
+
+    #[test]
+    fn shared_mac_is_reported_once_with_every_ip() {
+        let hosts = [
+            host(7, Some("aa:bb:cc:dd:ee:01")),
+            host(2, Some("AA-BB-CC-DD-EE-01")),
+            host(3, Some("AA:BB:CC:DD:EE:02")),
+            host(4, None),
+            host(5, None),
+        ];
+        let conflicts = detect(&hosts, &[]);
+        assert_eq!(
+            conflicts,
+            vec![Conflict::SharedMac {
+                mac: "AA:BB:CC:DD:EE:01".to_string(),
+                ips: vec![Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 7)],
+            }]
+        );
+        assert!(conflicts[0].involves(Ipv4Addr::new(10, 0, 0, 7)));
+        assert!(!conflicts[0].involves(Ipv4Addr::new(10, 0, 0, 3)));
+        assert_eq!(
+            conflicts[0].describe_for(Ipv4Addr::new(10, 0, 0, 7)),
+            "Same MAC as 10.0.0.2"
+        );
+    }
+
+    #[test]
+    fn changed_mac_is_compared_with_previous_snapshot() {
+        let previous = [
+            host(1, Some("AA:BB:CC:DD:EE:01")),
+            host(2, Some("AA:BB:CC:DD:EE:02")),
+            host(3, None),
+        ];
+        let hosts = [
+            host(1, Some("aa:bb:cc:dd:ee:01")),
+            host(2, Some("AA:BB:CC:DD:EE:99")),
+            host(3, Some("AA:BB:CC:DD:EE:03")),
+            host(4, Some("AA:BB:CC:DD:EE:04")),
+        ];
+        let conflicts = detect(&hosts, &previous);
+        assert_eq!(conflicts.len(), 1);
+        let Conflict::MacChanged { ip, previous, current } = &conflicts[0] else {
+            panic!("expected a MAC change: {:?}", conflicts);
+        };
+        assert_eq!(*ip, Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(previous, "AA:BB:CC:DD:EE:02");
+        assert_eq!(current, "AA:BB:CC:DD:EE:99");
+
+        let kinds: Vec<DiffKind> = conflicts[0].diff_lines().iter().map(|l| l.0).collect();
+        assert_eq!(kinds, [DiffKind::Heading, DiffKind::Removed, DiffKind::Added]);
+    }
+
+    #[test]
+    fn no_conflicts_in_clean_results() {
+        let hosts = [host(1, Some("AA:BB:CC:DD:EE:01")), host(2, None)];
+        assert!(detect(&hosts, &hosts).is_empty());
+        assert!(detect(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn one_named_device_on_several_ips_is_not_a_conflict() {
+        let hosts = [
+            named(9, "AA:BB:CC:DD:EE:01", Some("nas.lan")),
+            named(3, "aa:bb:cc:dd:ee:01", Some("NAS.lan")),
+            named(4, "AA:BB:CC:DD:EE:02", Some("printer.lan")),
+            named(5, "AA:BB:CC:DD:EE:02", Some("camera.lan")),
+            named(6, "AA:BB:CC:DD:EE:03", None),
+            named(7, "AA:BB:CC:DD:EE:03", None),
+            named(8, "AA:BB:CC:DD:EE:04", Some("solo.lan")),
+        ];
+        let groups = mac_groups(&hosts);
+        let summary: Vec<(u8, bool)> = groups
+            .iter()
+            .map(|g| (g.ips[0].octets()[3], g.same_host))
+            .collect();
+        assert_eq!(summary, [(3, true), (4, false), (6, false)]);
+        assert_eq!(groups[0].ips, [Ipv4Addr::new(10, 0, 0, 3), Ipv4Addr::new(10, 0, 0, 9)]);
+        assert_eq!(
+            groups[0].others(Ipv4Addr::new(10, 0, 0, 9)).collect::<Vec<_>>(),
+            [Ipv4Addr::new(10, 0, 0, 3)]
+        );
+
+        let conflicts = detect(&hosts, &[]);
+        let shared: Vec<&str> = conflicts
+            .iter()
+            .map(|c| match c {
+                Conflict::SharedMac { mac, .. } => mac.as_str(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(shared, ["AA:BB:CC:DD:EE:02", "AA:BB:CC:DD:EE:03"]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+use tokio::time::MissedTickBehavior;
+
+use crate::scanner::{get_default_gateway, get_default_gateways, AdapterInfo, Pinger, PingerConfig};
+
+/// How often the gateway and the internet anchor are pinged
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Latest check of one target
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Link {
+    /// Not checked yet, or no default gateway to check
+    #[default]
+    Unknown,
+    Up(Duration),
+    Down,
+}
+
+impl Link {
+    /// "2ms", "down" or "-"
+    pub fn text(self) -> String {
+        match self {
+            Link::Unknown => "-".to_string(),
+            Link::Up(rtt) => format!("{}ms", rtt.as_millis()),
+            Link::Down => "down".to_string(),
+        }
+    }
+}
+
+/// Whether the LAN and the internet answer, for the header indicator
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Connectivity {
+    /// Default gateway, once one is found
+    pub gateway_ip: Option<Ipv4Addr>,
+    pub gateway: Link,
+    pub internet: Link,
+}
+
+/// Ping the default gateway and `anchor` every [`CHECK_INTERVAL`], sending the
+/// result of each round. The gateway is looked up again while it isn't
+/// answering, in case the route changed. Rounds wait while `active` is false
+/// (an unfocused terminal with `--pause-unfocused`).
+pub fn spawn_checker(
+    anchor: Ipv4Addr,
+    ping: PingerConfig,
+    mut active: watch::Receiver<bool>,
+) -> mpsc::Receiver<Connectivity> {
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let pinger = Pinger::new(ping);
+        let mut gateway_ip = None;
+        let mut gateway = Link::Unknown;
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        // One round on resuming, not one per interval missed while paused
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            // A dropped sender can't pause us again
+            while !*active.borrow_and_update() {
+                if active.changed().await.is_err() {
+                    break;
+                }
+            }
+            if !matches!(gateway, Link::Up(_)) {
+                gateway_ip = tokio::task::spawn_blocking(get_default_gateway)
+                    .await
+                    .ok()
+                    .flatten();
+            }
+            gateway = match gateway_ip {
+                Some(ip) => link(&pinger, ip).await,
+                None => Link::Unknown,
+            };
+            let internet = link(&pinger, anchor).await;
+            let report = Connectivity {
+                gateway_ip,
+                gateway,
+                internet,
+            };
+            if tx.send(report).await.is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Whether an adapter's gateway answers, shown in the Range title while
+/// cycling adapters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GatewayCheck {
+    Checking,
+    /// No default route leaves through this adapter
+    NoGateway,
+    Checked(Ipv4Addr, Link),
+}
+
+impl GatewayCheck {
+    /// "gw 192.168.1.1 2ms", "gw 192.168.1.1 down", "no gateway", "gw …"
+    pub fn text(self, compat: bool) -> String {
+        match self {
+            GatewayCheck::Checking => format!("gw {}", if compat { "..." } else { "…" }),
+            GatewayCheck::NoGateway => "no gateway".to_string(),
+            GatewayCheck::Checked(ip, link) => format!("gw {} {}", ip, link.text()),
+        }
+    }
+}
+
+/// Find `adapter`'s gateway and ping it once, sending the result tagged
+/// with the adapter's address
+pub fn spawn_gateway_check(
+    adapter: AdapterInfo,
+    ping: PingerConfig,
+    tx: mpsc::Sender<(Ipv4Addr, GatewayCheck)>,
+) {
+    tokio::spawn(async move {
+        let gateways = tokio::task::spawn_blocking(get_default_gateways)
+            .await
+            .unwrap_or_default();
+        let check = match adapter.gateway(&gateways) {
+            Some(gateway) => GatewayCheck::Checked(gateway, link(&Pinger::new(ping), gateway).await),
+            None => GatewayCheck::NoGateway,
+        };
+        let _ = tx.send((adapter.ip, check)).await;
+    });
+}
+
+async fn link(pinger: &Pinger, ip: Ipv4Addr) -> Link {
+    let result = pinger.ping(ip).await;
+    if result.is_alive {
+        Link::Up(result.rtt.unwrap_or_default())
+    } else {
+        Link::Down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_text() {
+        assert_eq!(Link::Unknown.text(), "-");
+        assert_eq!(Link::Up(Duration::from_micros(2_400)).text(), "2ms");
+        assert_eq!(Link::Down.text(), "down");
+
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        let up = GatewayCheck::Checked(gateway, Link::Up(Duration::from_millis(3)));
+        assert_eq!(up.text(false), "gw 192.168.1.1 3ms");
+        assert_eq!(GatewayCheck::Checked(gateway, Link::Down).text(false), "gw 192.168.1.1 down");
+        assert_eq!(GatewayCheck::NoGateway.text(false), "no gateway");
+        assert_eq!(GatewayCheck::Checking.text(true), "gw ...");
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// Where `--debug` without a path writes
+pub fn default_path() -> PathBuf {
+    std::env::temp_dir().join("ipscannr-debug.log")
+}
+
+/// Send `tracing` events to `path` (appended) for the rest of the session.
+/// Never writes to stdout or stderr, which belong to the TUI.
+#[cfg(feature = "debug-log")]
+pub fn init(path: &Path) -> Result<()> {
+    use anyhow::Context as _;
+
+    let file = crate::eventlog::EventLog::open_file(path)
+        .with_context(|| format!("cannot open debug log {}", path.display()))?;
+    tracing::subscriber::set_global_default(subscriber(file))
+        .context("a debug logger is already installed")
+}
+
+#[cfg(not(feature = "debug-log"))]
+pub fn init(_path: &Path) -> Result<()> {
+    anyhow::bail!("--debug needs a build with the debug-log feature")
+}
+
+#[cfg(feature = "debug-log")]
+fn subscriber(file: std::fs::File) -> impl tracing::Subscriber + Send + Sync {
+    tracing_subscriber::fmt()
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false)
+        .with_target(true)
+        .with_max_level(tracing::Level::DEBUG)
+        .finish()
+}
+
+#[cfg(all(test, feature = "debug-log"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscriber_writes_events_to_the_file() {
+        let path = std::env::temp_dir().join(format!("ipscannr-debug-test-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let file = crate::eventlog::EventLog::open_file(&path).unwrap();
+        tracing::subscriber::with_default(subscriber(file), || {
+            tracing::info!(range = "192.168.1.0/24", "scan started");
+            tracing::trace!("below the debug level");
+        });
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", text);
+        assert!(lines[0].contains("INFO"), "{}", lines[0]);
+        assert!(lines[0].contains("scan started range=\"192.168.1.0/24\""), "{}", lines[0]);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::notify::Severity;
+
+/// Oldest entries are dropped once the log holds this many
+pub const EVENT_LOG_CAPACITY: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub at: SystemTime,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// `HH:MM:SS` (UTC) for the overlay
+    pub fn time(&self) -> String {
+        let secs = unix_secs(self.at);
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600 % 24,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+
+    /// Single line as written to `--log-file`
+    pub fn to_line(&self) -> String {
+        let secs = unix_secs(self.at);
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        format!(
+            "{:04}-{:02}-{:02}T{}Z {:<5} {}",
+            year,
+            month,
+            day,
+            self.time(),
+            self.severity.label().to_uppercase(),
+            self.message
+        )
+    }
+}
+
+/// Bounded, timestamped record of what happened during the session, optionally
+/// mirrored to a file.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+    file: Option<File>,
+}
+
+impl EventLog {
+    /// Open `path` for appending; every later entry is also written there
+    pub fn open_file(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    pub fn set_file(&mut self, file: File) {
+        self.file = Some(file);
+    }
+
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        let entry = LogEntry {
+            at: SystemTime::now(),
+            severity,
+            message: message.into(),
+        };
+        if let Some(file) = &mut self.file {
+            // A failing log file must never take the UI down with it
+            if writeln!(file, "{}", entry.to_line()).is_err() {
+                self.file = None;
+            }
+        }
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Entries newest first, optionally limited to one severity
+    pub fn newest_first(&self, filter: Option<Severity>) -> impl Iterator<Item = &LogEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |e| filter.is_none_or(|f| e.severity == f))
+    }
+
+    pub fn count(&self, filter: Option<Severity>) -> usize {
+        self.newest_first(filter).count()
+    }
+}
+
+fn unix_secs(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Days since 1970-01-01 to (year, month, day) in the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn log_is_bounded_and_newest_first() {
+        let mut log = EventLog::default();
+        for i in 0..EVENT_LOG_CAPACITY + 5 {
+            log.push(Severity::Info, format!("event {}", i));
+        }
+        assert_eq!(log.count(None), EVENT_LOG_CAPACITY);
+        let newest = log.newest_first(None).next().unwrap();
+        assert_eq!(newest.message, format!("event {}", EVENT_LOG_CAPACITY + 4));
+        assert_eq!(log.newest_first(None).last().unwrap().message, "event 5");
+    }
+
+    #[test]
+    fn filter_by_severity() {
+        let mut log = EventLog::default();
+        log.push(Severity::Info, "scan started");
+        log.push(Severity::Error, "dns failed");
+        log.push(Severity::Success, "exported");
+        assert_eq!(log.count(Some(Severity::Error)), 1);
+        assert_eq!(
+            log.newest_first(Some(Severity::Error)).next().unwrap().message,
+            "dns failed"
+        );
+    }
+
+    #[test]
+    fn file_lines_carry_utc_timestamp() {
+        let entry = LogEntry {
+            at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            severity: Severity::Error,
+            message: "boom".to_string(),
+        };
+        assert_eq!(entry.to_line(), "2023-11-14T22:13:20Z ERROR boom");
+    }
+
+    #[test]
+    fn entries_are_mirrored_to_file() {
+        let path = std::env::temp_dir().join(format!("ipscannr_log_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut log = EventLog::default();
+        log.set_file(EventLog::open_file(&path).unwrap());
+        log.push(Severity::Info, "scan started");
+        log.push(Severity::Success, "scan complete");
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.lines().next().unwrap().ends_with("INFO  scan started"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::HostInfo;
+use crate::scanner::{get_service_name, HostStatus};
+use crate::ui::widgets::Column;
+
+/// A host as written to JSON, by both the results export and the single-host
+/// save, so the two files share one schema
+#[derive(Debug, Serialize)]
+pub struct ExportHost {
+    pub ip: String,
+    pub is_alive: bool,
+    /// "Online", "OnlineNoIcmp" or "Offline"
+    pub status: &'static str,
+    /// Fractional milliseconds, to the microsecond
+    pub rtt_ms: Option<f64>,
+    /// The name shown, picked from the ones below by `--name-order`
+    pub hostname: Option<String>,
+    pub alias: Option<String>,
+    /// Name advertised over mDNS / SSDP
+    pub mdns_name: Option<String>,
+    pub netbios_name: Option<String>,
+    /// Reverse DNS name
+    pub ptr_name: Option<String>,
+    pub mac_address: Option<String>,
+    pub mac_vendor: Option<String>,
+    pub open_ports: Vec<u16>,
+    /// `open_ports` with their well-known service names
+    pub ports: Vec<ExportPort>,
+    /// Whether a port scan has run; an empty `open_ports` otherwise means
+    /// "not scanned"
+    pub ports_scanned: bool,
+    /// Unix time the host was last observed online
+    pub last_seen: Option<u64>,
+    /// Unix time the host went up or down, whichever it is now
+    pub state_since: Option<u64>,
+    /// Unix time of the scan the entry was loaded from; null for live results
+    pub cached_at: Option<u64>,
+    /// Online in the cached scan of the range, but offline in this one
+    pub stale: bool,
+    /// "ICMP" or "TCP"
+    pub method: String,
+    /// TCP port that answered discovery; null for ICMP
+    pub probe_port: Option<u16>,
+    /// Discovery attempt that answered, from 1
+    pub probe_attempt: Option<u32>,
+    /// Only identical instant TCP refusals answered: possibly a firewall
+    /// resetting connections for a dead address
+    pub probe_suspect: bool,
+    /// Services advertised over SSDP / mDNS
+    pub services: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportPort {
+    pub port: u16,
+    /// Null when the port has no well-known service
+    pub service: Option<&'static str>,
+}
+
+impl From<&HostInfo> for ExportHost {
+    fn from(h: &HostInfo) -> Self {
+        Self {
+            ip: h.ip.to_string(),
+            is_alive: h.is_alive,
+            status: match h.status {
+                HostStatus::Online => "Online",
+                HostStatus::OnlineNoIcmp => "OnlineNoIcmp",
+                HostStatus::Offline => "Offline",
+            },
+            rtt_ms: h.rtt.map(rtt_ms),
+            hostname: h.hostname.clone(),
+            alias: h.names.alias.clone(),
+            mdns_name: h.names.mdns.clone(),
+            netbios_name: h.names.netbios.clone(),
+            ptr_name: h.names.ptr.clone(),
+            mac_address: h.mac.as_ref().map(|m| m.address.clone()),
+            mac_vendor: h.mac.as_ref().and_then(|m| m.vendor.clone()),
+            open_ports: h.open_ports.clone(),
+            ports: h
+                .open_ports
+                .iter()
+                .map(|&port| ExportPort {
+                    port,
+                    service: Some(get_service_name(port)).filter(|s| *s != "unknown"),
+                })
+                .collect(),
+            ports_scanned: h.ports_scanned,
+            last_seen: h.last_seen,
+            state_since: h.state_since,
+            cached_at: h.cached_at,
+            stale: h.stale,
+            method: h.method.to_string(),
+            probe_port: h.probe.and_then(|p| p.port),
+            probe_attempt: h.probe.map(|p| p.attempt),
+            probe_suspect: h.probe.is_some_and(|p| p.suspect),
+            services: h.services.clone(),
+        }
+    }
+}
+
+/// `rtt` in milliseconds, to the microsecond (0.412), for the RTT column of
+/// CSV and JSON exports
+pub fn rtt_ms(rtt: Duration) -> f64 {
+    rtt.as_micros() as f64 / 1000.0
+}
+
+/// `hosts` as an aligned plain-text table of `columns`, one line per host
+/// under a heading line, for screen readers and the scrollback
+/// (`--print-on-exit`)
+pub fn text_table(hosts: &[&HostInfo], columns: &[Column], now: u64) -> String {
+    let mut rows: Vec<Vec<String>> = vec![columns.iter().map(|c| c.header().to_string()).collect()];
+    rows.extend(
+        hosts
+            .iter()
+            .map(|host| columns.iter().map(|c| c.text(host, now)).collect()),
+    );
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| rows.iter().map(|row| row[i].width()).max().unwrap_or(0))
+        .collect();
+    let mut text = String::new();
+    for row in rows {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(&widths) {
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - cell.width() + 2));
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::names::Names;
+    use crate::scanner::{MacInfo, PingMethod, Probe};
+
+    #[test]
+    fn host_schema_snapshot() {
+        let host = HostInfo {
+            is_alive: true,
+            rtt: Some(Duration::from_micros(3_412)),
+            hostname: Some("NAS.local".to_string()),
+            names: Names {
+                mdns: Some("NAS.local".to_string()),
+                ptr: Some("nas.lan".to_string()),
+                ..Names::default()
+            },
+            mac: Some(MacInfo {
+                address: "AA:BB:CC:DD:EE:05".to_string(),
+                vendor: Some("Synology".to_string()),
+            }),
+            open_ports: vec![22, 5000],
+            ports_scanned: true,
+            last_seen: Some(1_700_000_000),
+            method: PingMethod::Tcp,
+            status: HostStatus::OnlineNoIcmp,
+            probe: Some(Probe {
+                port: Some(445),
+                attempt: 1,
+                suspect: false,
+            }),
+            state_since: Some(1_699_990_000),
+            services: vec!["smb".to_string()],
+            ..HostInfo::new(Ipv4Addr::new(192, 168, 1, 5))
+        };
+        let json = serde_json::to_string_pretty(&ExportHost::from(&host)).unwrap();
+        assert_eq!(
+            json,
+            r#"{
+  "ip": "192.168.1.5",
+  "is_alive": true,
+  "status": "OnlineNoIcmp",
+  "rtt_ms": 3.412,
+  "hostname": "NAS.local",
+  "alias": null,
+  "mdns_name": "NAS.local",
+  "netbios_name": null,
+  "ptr_name": "nas.lan",
+  "mac_address": "AA:BB:CC:DD:EE:05",
+  "mac_vendor": "Synology",
+  "open_ports": [
+    22,
+    5000
+  ],
+  "ports": [
+    {
+      "port": 22,
+      "service": "ssh"
+    },
+    {
+      "port": 5000,
+      "service": null
+    }
+  ],
+  "ports_scanned": true,
+  "last_seen": 1700000000,
+  "state_since": 1699990000,
+  "cached_at": null,
+  "stale": false,
+  "method": "TCP",
+  "probe_port": 445,
+  "probe_attempt": 1,
+  "probe_suspect": false,
+  "services": [
+    "smb"
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn text_table_aligns_every_column() {
+        let host = |last: u8, is_alive: bool| HostInfo {
+            rtt: is_alive.then(|| Duration::from_millis(12)),
+            ..HostInfo::pinged(Ipv4Addr::new(192, 168, 1, last), is_alive)
+        };
+        let mut nas = host(5, true);
+        nas.hostname = Some("nas.lan".to_string());
+        nas.open_ports = vec![22, 443];
+        nas.ports_scanned = true;
+        let mut printer = host(120, true);
+        printer.mac = Some(MacInfo {
+            address: "AA:BB:CC:DD:EE:78".to_string(),
+            vendor: Some("Brother".to_string()),
+        });
+        let offline = host(7, false);
+        let columns = [Column::Ip, Column::Status, Column::Hostname, Column::Rtt, Column::Ports];
+        assert_eq!(
+            text_table(&[&nas, &printer, &offline], &columns, 0),
+            "\
+IP             STATUS   HOSTNAME   RTT   PORTS
+192.168.1.5    online   nas.lan    12ms  22,443
+192.168.1.120  online   [Brother]  12ms  -
+192.168.1.7    offline  -          -     -
+"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use ratatui::style::{Modifier, Style};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::app::HostInfo;
+use crate::ui::theme::{Compat, Theme};
+
+/// A `highlight_rules` entry of the settings file: hosts whose hostname or
+/// vendor matches `pattern` get a badge, a row color, or both
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighlightRule {
+    /// A regular expression, matched case-insensitively anywhere in the
+    /// field, so plain text works as a substring
+    pub pattern: String,
+    #[serde(default)]
+    pub field: RuleField,
+    /// Badge shown after the hostname, e.g. "HYPERVISOR"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<RuleColor>,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// What a rule's pattern is matched against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    #[default]
+    Hostname,
+    /// The MAC's OUI vendor
+    Vendor,
+}
+
+/// Row colors a rule can pick, from the theme's palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleColor {
+    Accent,
+    Success,
+    Warning,
+    Error,
+}
+
+impl RuleColor {
+    pub fn style(self, compat: bool) -> Style {
+        match (self, compat) {
+            (RuleColor::Accent, false) => Theme::accent(),
+            (RuleColor::Success, false) => Theme::success(),
+            (RuleColor::Warning, false) => Theme::warning(),
+            (RuleColor::Error, false) => Theme::error(),
+            (RuleColor::Accent, true) => Compat::accent(),
+            (RuleColor::Success, true) => Compat::status_online(),
+            (RuleColor::Warning, true) => Compat::warning(),
+            (RuleColor::Error, true) => Compat::error(),
+        }
+    }
+}
+
+fn rule(pattern: &str, field: RuleField, tag: &str, color: RuleColor) -> HighlightRule {
+    HighlightRule {
+        pattern: pattern.to_string(),
+        field,
+        tag: Some(tag.to_string()),
+        color: Some(color),
+        enabled: false,
+    }
+}
+
+/// Examples written to a new settings file, all disabled
+pub fn example_rules() -> Vec<HighlightRule> {
+    vec![
+        rule("esxi|pve|proxmox|hyper-?v", RuleField::Hostname, "HYPERVISOR", RuleColor::Accent),
+        rule("espressif|tuya|shelly", RuleField::Vendor, "IOT", RuleColor::Warning),
+        rule("^(printer|npi|brw)", RuleField::Hostname, "PRINTER", RuleColor::Success),
+    ]
+}
+
+/// How the rules matching a host mark it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlight<'a> {
+    /// The first matching rule's color that sets one
+    pub color: Option<RuleColor>,
+    /// Every matching rule's tag, in rule order, without repeats
+    pub tags: Vec<&'a str>,
+}
+
+impl Highlight<'_> {
+    /// The badges as shown: `[HYPERVISOR] [IOT]`
+    pub fn badges(&self) -> String {
+        self.tags
+            .iter()
+            .map(|tag| format!("[{}]", tag))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Style for the badges: the row's color, else the accent, in bold
+    pub fn badge_style(&self, compat: bool) -> Style {
+        self.color
+            .unwrap_or(RuleColor::Accent)
+            .style(compat)
+            .add_modifier(Modifier::BOLD)
+    }
+}
+
+/// The enabled rules, compiled
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    rules: Vec<(Regex, HighlightRule)>,
+}
+
+impl Highlighter {
+    /// Compile the enabled `rules`, leaving out the ones that don't compile;
+    /// why each was left out comes back alongside
+    pub fn new(rules: &[HighlightRule]) -> (Self, Vec<String>) {
+        let mut compiled = Vec::new();
+        let mut errors = Vec::new();
+        for rule in rules.iter().filter(|r| r.enabled) {
+            match RegexBuilder::new(&rule.pattern).case_insensitive(true).build() {
+                Ok(regex) => compiled.push((regex, rule.clone())),
+                Err(e) => {
+                    // A syntax error spans lines, pointing at the pattern;
+                    // its last one says what's wrong
+                    let message = e.to_string();
+                    let reason = message.lines().last().unwrap_or_default();
+                    errors.push(format!(
+                        "highlight rule '{}': {}",
+                        rule.pattern,
+                        reason.trim_start_matches("error: ")
+                    ));
+                }
+            }
+        }
+        (Self { rules: compiled }, errors)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// How `host` is marked; rules are tried in file order
+    pub fn highlight(&self, host: &HostInfo) -> Highlight<'_> {
+        let mut highlight = Highlight::default();
+        for (regex, rule) in &self.rules {
+            let value = match rule.field {
+                RuleField::Hostname => host.hostname.as_deref(),
+                RuleField::Vendor => host.mac.as_ref().and_then(|m| m.vendor.as_deref()),
+            };
+            if !value.is_some_and(|v| regex.is_match(v)) {
+                continue;
+            }
+            if highlight.color.is_none() {
+                highlight.color = rule.color;
+            }
+            if let Some(tag) = rule.tag.as_deref().filter(|t| !highlight.tags.contains(t)) {
+                highlight.tags.push(tag);
+            }
+        }
+        highlight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::scanner::MacInfo;
+
+    fn host(hostname: Option<&str>, vendor: Option<&str>) -> HostInfo {
+        HostInfo {
+            hostname: hostname.map(str::to_string),
+            mac: vendor.map(|vendor| MacInfo {
+                address: "AA:BB:CC:DD:EE:05".to_string(),
+                vendor: Some(vendor.to_string()),
+            }),
+            ..HostInfo::pinged(Ipv4Addr::new(192, 168, 1, 5), true)
+        }
+    }
+
+    fn enabled(rules: Vec<HighlightRule>) -> Highlighter {
+        let rules: Vec<HighlightRule> = rules
+            .into_iter()
+            .map(|r| HighlightRule { enabled: true, ..r })
+            .collect();
+        let (highlighter, errors) = Highlighter::new(&rules);
+        assert!(errors.is_empty(), "{:?}", errors);
+        highlighter
+    }
+
+    #[test]
+    fn examples_ship_disabled_and_match_when_enabled() {
+        let (highlighter, errors) = Highlighter::new(&example_rules());
+        assert!(highlighter.is_empty() && errors.is_empty());
+
+        let highlighter = enabled(example_rules());
+        let esxi = highlighter.highlight(&host(Some("ESXi-01.lan"), None));
+        assert_eq!((esxi.color, esxi.tags), (Some(RuleColor::Accent), vec!["HYPERVISOR"]));
+        let plug = highlighter.highlight(&host(None, Some("Espressif Inc.")));
+        assert_eq!(plug.tags, ["IOT"]);
+        assert_eq!(highlighter.highlight(&host(Some("nas.lan"), Some("Synology"))), Highlight::default());
+    }
+
+    #[test]
+    fn first_color_wins_and_every_tag_shows() {
+        let tag_only = HighlightRule {
+            color: None,
+            ..rule("lab", RuleField::Hostname, "LAB", RuleColor::Accent)
+        };
+        let highlighter = enabled(vec![
+            tag_only,
+            rule("pve", RuleField::Hostname, "HYPERVISOR", RuleColor::Warning),
+            rule("espressif", RuleField::Vendor, "IOT", RuleColor::Error),
+            // Same tag again: shown once
+            rule("^pve", RuleField::Hostname, "HYPERVISOR", RuleColor::Success),
+        ]);
+
+        let both = highlighter.highlight(&host(Some("pve-lab"), Some("Espressif")));
+        assert_eq!(both.color, Some(RuleColor::Warning));
+        assert_eq!(both.tags, ["LAB", "HYPERVISOR", "IOT"]);
+        assert_eq!(both.badges(), "[LAB] [HYPERVISOR] [IOT]");
+
+        // Only the vendor rule: its color applies
+        let iot = highlighter.highlight(&host(Some("plug"), Some("espressif systems")));
+        assert_eq!((iot.color, iot.tags), (Some(RuleColor::Error), vec!["IOT"]));
+        // A tag-only rule leaves the row uncolored
+        let lab = highlighter.highlight(&host(Some("LAB-switch"), None));
+        assert_eq!((lab.color, lab.tags), (None, vec!["LAB"]));
+    }
+
+    #[test]
+    fn bad_patterns_are_reported_and_skipped() {
+        let mut broken = rule("esxi(", RuleField::Hostname, "HYPERVISOR", RuleColor::Accent);
+        broken.enabled = true;
+        let mut good = rule("nas", RuleField::Hostname, "NAS", RuleColor::Accent);
+        good.enabled = true;
+        let (highlighter, errors) = Highlighter::new(&[broken, good]);
+        assert_eq!(errors, ["highlight rule 'esxi(': unclosed group"]);
+        assert_eq!(highlighter.highlight(&host(Some("nas.lan"), None)).tags, ["NAS"]);
+    }
+
+    #[test]
+    fn rules_load_with_defaults() {
+        let rule: HighlightRule = serde_json::from_str(r#"{"pattern": "esxi", "tag": "HV"}"#).unwrap();
+        assert_eq!((rule.field, rule.color, rule.enabled), (RuleField::Hostname, None, true));
+        let rule: HighlightRule =
+            serde_json::from_str(r#"{"pattern": "tuya", "field": "vendor", "color": "warning"}"#).unwrap();
+        assert_eq!((rule.field, rule.color, rule.tag), (RuleField::Vendor, Some(RuleColor::Warning), None));
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 /// Actions that can be performed in the application
@@ -11,7 +13,23 @@ pub enum Action {
     ToggleFilter,
     Export,
     ToggleDetails,
+    ToggleLegend,
+    ToggleLastSeen,
+    ToggleUptime,
+    TogglePorts,
+    PreviousRange, // Batch scan: narrow the table to the previous range
+    NextRange,
+    SplitLeft,  // Move table/details divider left
+    SplitRight, // Move table/details divider right
+    ToggleStacked,
     Help,
+    ShowEventLog,
+    JumpToChange, // Select the host of the latest up / down change
+    JumpToSameMac, // Select the next host with the selected one's MAC
+    Goto,         // Type a last octet or address to select that host
+    ShowConflicts,
+    ShowSummary,
+    Discover, // SSDP / mDNS browse
     NavigateUp,
     NavigateDown,
     NavigatePageUp,
@@ -21,17 +39,33 @@ pub enum Action {
     Select,
     ToggleSelect, // Spacebar: multi-select hosts (or resume paused scan)
     Cancel,
+    Confirm,
     SwitchPane,
     SwitchPaneReverse,
     Delete,
     Backspace,
+    WordLeft,   // Ctrl+Left in a text field
+    WordRight,  // Ctrl+Right in a text field
+    DeleteWord, // Ctrl+Backspace / Ctrl+W: delete back to the previous delimiter
+    ClearField, // Ctrl+U
     Character(char),
     // Host Details actions
     WakeOnLan,
     ContinuousPing,
     RunTracert,
     SaveHost,
-    StopOverlay, // Close output overlay (ping/tracert)
+    SaveHostJson,
+    RefreshMac, // Read the ARP table for the host again
+    ForgetHost, // Clear cached hostname / MAC / ports, after a confirm
+    CopyIp,
+    CopyCommand, // Chooser of ready-made ping / ssh / rdp command lines
+    Knock,       // Ctrl+K: chooser of the settings file's knock sequences
+    PickColumns, // Column picker overlay
+    OpenSettings, // F2: scan tunables overlay
+    ToggleKeybindings, // F1: pin the shortcuts popup open
+    StopOverlay,   // Stop the ping / tracert task, keeping its output on screen
+    CloseOverlay,  // Close the output view (stopping a task that is still running)
+    ReopenOverlay, // Show the last ping / tracert output again
     None,
 }
 
@@ -44,16 +78,85 @@ pub enum InputMode {
     Help,
     Exporting,
     OutputOverlay, // Streaming output for continuous ping / tracert
+    EventLog,
+    Conflicts,     // Duplicate / changed MAC list
+    Summary,       // Per-subnet / vendor / service breakdown
+    WakeReport,    // Outcome of waking several hosts
+    ConfirmQuit,   // "Scan in progress — quit anyway?" prompt
+    ConfirmForget, // "Forget the host's cached details?" prompt
+    ConfirmNewScan, // "A paused scan exists — discard and start new?" prompt
+    ConfirmLargeScan, // "Scan 262144 addresses?" prompt for a big range
+    ContextMenu,   // Right-click host actions menu
+    CopyCommand,   // Command line chooser for the selected host
+    Knock,         // Knock sequence chooser for the selected host
+    ColumnPicker,  // Which hosts table columns to show, and their order
+    Settings,      // Scan tunables, edited in place
+    Goto,          // Typing a last octet / address to jump to
+}
+
+/// Capital letters bound to their own actions, distinct from the lowercase
+/// key. Every other shortcut works with Shift or Caps Lock held; a new
+/// Shift+letter binding must be listed here, or it will never be seen because
+/// `normalize_case` folds it to lowercase first.
+/// - `G`: jump to the last row (vim), while `g` starts `gg`
+/// - `Y`: copy a command line for the host, while `y` copies its IP
+/// - `A`: save the host as JSON, while `a` saves it as text
+/// - `P`: toggle the ports column, while `p` configures the ports to scan
+/// - `H`: jump to the latest up / down change, while `h` opens the event log
+/// - `M`: look the host's MAC up again, while `m` toggles the uptime column
+const RESERVED_CAPITALS: &[char] = &['A', 'G', 'H', 'M', 'P', 'Y'];
+
+/// Fold A-Z to lowercase so shortcuts work with Caps Lock / Shift held
+fn normalize_case(mut key: KeyEvent) -> KeyEvent {
+    if let KeyCode::Char(c) = key.code {
+        if c.is_ascii_uppercase() && !RESERVED_CAPITALS.contains(&c) {
+            key.code = KeyCode::Char(c.to_ascii_lowercase());
+        }
+    }
+    key
+}
+
+fn is_ctrl_c(key: KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('c') | KeyCode::Char('C'))
+        && key.modifiers.contains(KeyModifiers::CONTROL)
 }
 
 /// Map key events to actions based on current mode
 pub fn handle_key(key: KeyEvent, mode: InputMode) -> Action {
+    // Editing modes take characters literally; everywhere else letters are
+    // shortcuts and case-insensitive
+    let key = match mode {
+        InputMode::EditingRange | InputMode::EditingPorts => key,
+        _ => normalize_case(key),
+    };
+    // Ctrl+C means Quit everywhere; App decides whether to confirm first
+    if is_ctrl_c(key) {
+        return Action::Quit;
+    }
+    // F1 toggles the shortcuts popup everywhere, for terminals where Left
+    // Ctrl alone can't be detected
+    if key.code == KeyCode::F(1) {
+        return Action::ToggleKeybindings;
+    }
     match mode {
         InputMode::Normal => handle_normal_mode(key),
         InputMode::EditingRange | InputMode::EditingPorts => handle_editing_mode(key),
         InputMode::Help => handle_help_mode(key),
         InputMode::Exporting => handle_export_mode(key),
         InputMode::OutputOverlay => handle_overlay_mode(key),
+        InputMode::EventLog => handle_event_log_mode(key),
+        InputMode::Conflicts => handle_conflicts_mode(key),
+        InputMode::Summary => handle_summary_mode(key),
+        InputMode::WakeReport => handle_wake_report_mode(key),
+        InputMode::ConfirmQuit => handle_confirm_quit_mode(key),
+        InputMode::ConfirmForget => handle_confirm_forget_mode(key),
+        InputMode::ConfirmNewScan => handle_confirm_new_scan_mode(key),
+        InputMode::ConfirmLargeScan => handle_confirm_large_scan_mode(key),
+        InputMode::ContextMenu => handle_context_menu_mode(key),
+        InputMode::CopyCommand | InputMode::Knock => handle_chooser_mode(key),
+        InputMode::ColumnPicker => handle_column_picker_mode(key),
+        InputMode::Settings => handle_settings_mode(key),
+        InputMode::Goto => handle_goto_mode(key),
     }
 }
 
@@ -61,7 +164,6 @@ fn handle_normal_mode(key: KeyEvent) -> Action {
     match key.code {
         KeyCode::Char('q') => Action::Quit,
         KeyCode::Esc => Action::Cancel, // Pause scan or switch panes
-        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
         KeyCode::Char('s') => Action::StartScan,
         KeyCode::Char('x') => Action::StopScan,
         KeyCode::Char('r') => Action::EditRange,
@@ -69,11 +171,36 @@ fn handle_normal_mode(key: KeyEvent) -> Action {
         KeyCode::Char('f') => Action::ToggleFilter,
         KeyCode::Char('e') => Action::Export,
         KeyCode::Char('d') => Action::ToggleDetails,
+        KeyCode::Char('l') => Action::ToggleLegend,
+        KeyCode::Char('n') => Action::ToggleLastSeen,
+        KeyCode::Char('m') => Action::ToggleUptime,
+        KeyCode::Char('P') => Action::TogglePorts,
+        KeyCode::Char('[') => Action::PreviousRange,
+        KeyCode::Char(']') => Action::NextRange,
+        KeyCode::Char('<') => Action::SplitLeft,
+        KeyCode::Char('>') => Action::SplitRight,
+        KeyCode::Char('v') => Action::ToggleStacked,
         KeyCode::Char('?') => Action::Help,
+        KeyCode::Char('h') => Action::ShowEventLog,
+        KeyCode::Char('H') => Action::JumpToChange,
+        KeyCode::Char('=') => Action::JumpToSameMac,
+        KeyCode::Char(':' | '#') => Action::Goto,
+        KeyCode::Char('i') => Action::ShowConflicts,
+        KeyCode::Char('u') => Action::ShowSummary,
+        KeyCode::Char('b') => Action::Discover, // browse
+        KeyCode::Char('o') => Action::ReopenOverlay,
         KeyCode::Char('w') => Action::WakeOnLan,
         KeyCode::Char('c') => Action::ContinuousPing, // non-Ctrl c
         KeyCode::Char('t') => Action::RunTracert,
         KeyCode::Char('a') => Action::SaveHost,
+        KeyCode::Char('A') => Action::SaveHostJson,
+        KeyCode::Char('M') => Action::RefreshMac,
+        KeyCode::Delete => Action::ForgetHost,
+        KeyCode::Char('y') => Action::CopyIp, // yank
+        KeyCode::Char('Y') => Action::CopyCommand,
+        KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Knock,
+        KeyCode::Char('z') => Action::PickColumns,
+        KeyCode::F(2) => Action::OpenSettings,
         KeyCode::Char(' ') => Action::ToggleSelect, // Space: multi-select or resume
         KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
         KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
@@ -91,7 +218,19 @@ fn handle_normal_mode(key: KeyEvent) -> Action {
 }
 
 fn handle_editing_mode(key: KeyEvent) -> Action {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
     match key.code {
+        KeyCode::Left if ctrl => Action::WordLeft,
+        KeyCode::Right if ctrl => Action::WordRight,
+        // Terminals send Ctrl+Backspace as Ctrl+H, or Backspace with a
+        // modifier; Ctrl+W is the shell's spelling
+        KeyCode::Backspace if ctrl || key.modifiers.contains(KeyModifiers::ALT) => {
+            Action::DeleteWord
+        }
+        KeyCode::Char('h' | 'w') if ctrl => Action::DeleteWord,
+        KeyCode::Char('u') if ctrl => Action::ClearField,
+        // Other Ctrl chords aren't text
+        KeyCode::Char(_) if ctrl => Action::None,
         KeyCode::Esc => Action::Cancel,
         KeyCode::Enter => Action::Select,
         KeyCode::Backspace => Action::Backspace,
@@ -105,9 +244,188 @@ fn handle_editing_mode(key: KeyEvent) -> Action {
     }
 }
 
+/// Characters that split a range or port list into words for the Ctrl
+/// cursor keys: octets, the prefix length, list items, range ends
+const WORD_DELIMITERS: &[char] = &['.', '/', ',', '-', ':', ';', ' '];
+
+fn is_delimiter(c: char) -> bool {
+    WORD_DELIMITERS.contains(&c)
+}
+
+/// Where Ctrl+Left moves the cursor: the start of the word before `cursor`,
+/// skipping any delimiters right before it. Ctrl+Backspace deletes up to here.
+pub fn word_left(text: &str, cursor: usize) -> usize {
+    text[..cursor]
+        .trim_end_matches(is_delimiter)
+        .trim_end_matches(|c| !is_delimiter(c))
+        .len()
+}
+
+/// Where Ctrl+Right moves the cursor: past the rest of the current word and
+/// the delimiters after it, to the start of the next word
+pub fn word_right(text: &str, cursor: usize) -> usize {
+    let rest = text[cursor..]
+        .trim_start_matches(|c| !is_delimiter(c))
+        .trim_start_matches(is_delimiter);
+    text.len() - rest.len()
+}
+
 fn handle_help_mode(key: KeyEvent) -> Action {
     match key.code {
         KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') | KeyCode::Enter => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::PageUp => Action::NavigatePageUp,
+        KeyCode::PageDown => Action::NavigatePageDown,
+        KeyCode::Home => Action::NavigateHome,
+        KeyCode::End => Action::NavigateEnd,
+        _ => Action::None,
+    }
+}
+
+fn handle_event_log_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('h' | 'H') => Action::Cancel,
+        KeyCode::Char('f') | KeyCode::Tab => Action::ToggleFilter,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::PageUp => Action::NavigatePageUp,
+        KeyCode::PageDown => Action::NavigatePageDown,
+        KeyCode::Home => Action::NavigateHome,
+        KeyCode::End => Action::NavigateEnd,
+        _ => Action::None,
+    }
+}
+
+fn handle_conflicts_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('i') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::PageUp => Action::NavigatePageUp,
+        KeyCode::PageDown => Action::NavigatePageDown,
+        KeyCode::Home => Action::NavigateHome,
+        KeyCode::End => Action::NavigateEnd,
+        _ => Action::None,
+    }
+}
+
+fn handle_summary_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('u') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::PageUp => Action::NavigatePageUp,
+        KeyCode::PageDown => Action::NavigatePageDown,
+        KeyCode::Home => Action::NavigateHome,
+        KeyCode::End => Action::NavigateEnd,
+        _ => Action::None,
+    }
+}
+
+fn handle_wake_report_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::PageUp => Action::NavigatePageUp,
+        KeyCode::PageDown => Action::NavigatePageDown,
+        KeyCode::Home => Action::NavigateHome,
+        KeyCode::End => Action::NavigateEnd,
+        _ => Action::None,
+    }
+}
+
+fn handle_context_menu_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::Enter | KeyCode::Char(' ') => Action::Select,
+        _ => Action::None,
+    }
+}
+
+fn handle_chooser_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::Enter | KeyCode::Char(' ') => Action::Select,
+        KeyCode::Char(c @ '1'..='9') => Action::Character(c), // pick by number
+        _ => Action::None,
+    }
+}
+
+fn handle_column_picker_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::Char(' ') => Action::ToggleSelect,
+        KeyCode::Char('+' | '=') => Action::Character('+'), // '=' is + without Shift
+        KeyCode::Char('-') => Action::Character('-'),
+        KeyCode::Enter => Action::Select,
+        _ => Action::None,
+    }
+}
+
+fn handle_settings_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Action::Cancel,
+        KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
+        KeyCode::Char(' ') => Action::ToggleSelect,
+        KeyCode::Enter => Action::Select,
+        KeyCode::Backspace => Action::Backspace,
+        KeyCode::Char(c @ '0'..='9') => Action::Character(c),
+        _ => Action::None,
+    }
+}
+
+fn handle_goto_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc => Action::Cancel,
+        KeyCode::Enter => Action::Select,
+        KeyCode::Backspace => Action::Backspace,
+        KeyCode::Char(c @ ('0'..='9' | '.')) => Action::Character(c),
+        _ => Action::None,
+    }
+}
+
+fn handle_confirm_quit_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('y' | 'Y') => Action::Confirm,
+        KeyCode::Char('s') => Action::Character('s'),
+        KeyCode::Char(_) | KeyCode::Esc | KeyCode::Enter => Action::Cancel,
+        _ => Action::None,
+    }
+}
+
+fn handle_confirm_forget_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('y' | 'Y') => Action::Confirm,
+        KeyCode::Char('p' | 'P') => Action::Character('p'), // forget and rescan
+        KeyCode::Char(_) | KeyCode::Esc | KeyCode::Enter => Action::Cancel,
+        _ => Action::None,
+    }
+}
+
+fn handle_confirm_new_scan_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('y' | 'Y') => Action::Confirm,
+        // Resuming is the default
+        KeyCode::Char('r' | 'R') | KeyCode::Enter => Action::Character('r'),
+        KeyCode::Char(_) | KeyCode::Esc => Action::Cancel,
+        _ => Action::None,
+    }
+}
+
+fn handle_confirm_large_scan_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('y' | 'Y') => Action::Confirm,
+        // Only an explicit yes starts it
+        KeyCode::Char(_) | KeyCode::Esc | KeyCode::Enter => Action::Cancel,
         _ => Action::None,
     }
 }
@@ -117,13 +435,18 @@ fn handle_export_mode(key: KeyEvent) -> Action {
         KeyCode::Esc => Action::Cancel,
         KeyCode::Char('c') => Action::Character('c'), // CSV
         KeyCode::Char('j') => Action::Character('j'), // JSON
+        KeyCode::Char('t') => Action::Character('t'), // Table as text
+        KeyCode::Char('f') => Action::ToggleFilter,   // Respect current filter
+        KeyCode::Char('o') => Action::Character('o'), // Include offline hosts
+        KeyCode::Char('p' | 'P') => Action::Character('p'), // Only hosts with open ports
         _ => Action::None,
     }
 }
 
 fn handle_overlay_mode(key: KeyEvent) -> Action {
     match key.code {
-        KeyCode::Esc | KeyCode::Char('q') => Action::StopOverlay,
+        KeyCode::Esc | KeyCode::Char('q') => Action::CloseOverlay,
+        KeyCode::Char('x') => Action::StopOverlay,
         KeyCode::Up | KeyCode::Char('k') => Action::NavigateUp,
         KeyCode::Down | KeyCode::Char('j') => Action::NavigateDown,
         KeyCode::Home => Action::NavigateHome,
@@ -131,3 +454,336 @@ fn handle_overlay_mode(key: KeyEvent) -> Action {
         _ => Action::None,
     }
 }
+
+/// How long a half-typed sequence (`g`, `25`) waits for its next key
+pub const KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Table jump produced by a completed key sequence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Motion {
+    First,
+    Last,
+    /// Zero-based row (`25G`)
+    Row(usize),
+    Down(usize),
+    Up(usize),
+}
+
+/// Result of feeding one action to a `KeySequence`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sequence {
+    /// Key consumed; waiting for more
+    Pending,
+    Motion(Motion),
+    /// Not part of a sequence; handle the action normally
+    PassThrough,
+}
+
+/// Vim-style hosts table sequences: `gg`, `G`, `25G`, and count prefixes for
+/// `j`/`k`. Only fed in Normal mode with the hosts table focused.
+#[derive(Debug, Default)]
+pub struct KeySequence {
+    count: Option<usize>,
+    g: bool,
+    last_key: Option<Instant>,
+}
+
+impl KeySequence {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn feed(&mut self, action: Action, now: Instant) -> Sequence {
+        if self
+            .last_key
+            .is_some_and(|at| now.duration_since(at) > KEY_SEQUENCE_TIMEOUT)
+        {
+            self.reset();
+        }
+        let count = self.count;
+        let g = self.g;
+        self.reset();
+
+        let result = match action {
+            // `0` only extends a count; it doesn't start one
+            Action::Character(c @ '0'..='9') if !g && (c != '0' || count.is_some()) => {
+                let digit = c.to_digit(10).unwrap_or(0) as usize;
+                self.count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                Sequence::Pending
+            }
+            Action::Character('g') if g => Sequence::Motion(Motion::First),
+            Action::Character('g') => {
+                self.count = count;
+                self.g = true;
+                Sequence::Pending
+            }
+            Action::Character('G') => Sequence::Motion(match count {
+                Some(n) => Motion::Row(n.saturating_sub(1)),
+                None => Motion::Last,
+            }),
+            Action::NavigateDown if count.is_some() => {
+                Sequence::Motion(Motion::Down(count.unwrap_or(1)))
+            }
+            Action::NavigateUp if count.is_some() => Sequence::Motion(Motion::Up(count.unwrap_or(1))),
+            _ => Sequence::PassThrough,
+        };
+        if result == Sequence::Pending {
+            self.last_key = Some(now);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(c: char) -> KeyEvent {
+        let modifiers = if c.is_ascii_uppercase() {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        KeyEvent::new(KeyCode::Char(c), modifiers)
+    }
+
+    /// Every letter bound in each non-editing mode
+    const BOUND: &[(InputMode, &str)] = &[
+        (InputMode::Normal, "qsxrpfedlvhwctayjknioumbz"),
+        (InputMode::Help, "qjk"),
+        (InputMode::Exporting, "cjftop"),
+        (InputMode::OutputOverlay, "qxjk"),
+        (InputMode::EventLog, "qhfjk"),
+        (InputMode::Conflicts, "qijk"),
+        (InputMode::Summary, "qujk"),
+        (InputMode::WakeReport, "qwjk"),
+        (InputMode::ConfirmQuit, "ys"),
+        (InputMode::ConfirmForget, "yp"),
+        (InputMode::ConfirmNewScan, "yr"),
+        (InputMode::ConfirmLargeScan, "y"),
+        (InputMode::ContextMenu, "qjk"),
+        (InputMode::CopyCommand, "qjk"),
+        (InputMode::Knock, "qjk"),
+        (InputMode::ColumnPicker, "qjk"),
+        (InputMode::Settings, "qjk"),
+    ];
+
+    #[test]
+    fn uppercase_shortcuts_match_lowercase() {
+        for (mode, letters) in BOUND {
+            for c in letters.chars() {
+                if *mode == InputMode::Normal && RESERVED_CAPITALS.contains(&c.to_ascii_uppercase()) {
+                    continue;
+                }
+                let lower = handle_key(press(c), *mode);
+                assert_ne!(lower, Action::None, "{:?} '{}' unbound", mode, c);
+                let upper = handle_key(press(c.to_ascii_uppercase()), *mode);
+                assert_eq!(upper, lower, "{:?} '{}'", mode, c.to_ascii_uppercase());
+            }
+        }
+    }
+
+    #[test]
+    fn ctrl_c_quits_with_caps_lock() {
+        let key = KeyEvent::new(KeyCode::Char('C'), KeyModifiers::CONTROL);
+        assert_eq!(handle_key(key, InputMode::Normal), Action::Quit);
+    }
+
+    #[test]
+    fn ctrl_c_quits_in_every_mode() {
+        let modes = [
+            InputMode::Normal,
+            InputMode::EditingRange,
+            InputMode::EditingPorts,
+            InputMode::Help,
+            InputMode::Exporting,
+            InputMode::OutputOverlay,
+            InputMode::EventLog,
+            InputMode::Conflicts,
+            InputMode::Summary,
+            InputMode::WakeReport,
+            InputMode::ConfirmQuit,
+            InputMode::ConfirmForget,
+            InputMode::ConfirmNewScan,
+            InputMode::ConfirmLargeScan,
+            InputMode::ContextMenu,
+            InputMode::CopyCommand,
+            InputMode::Knock,
+            InputMode::ColumnPicker,
+            InputMode::Settings,
+            InputMode::Goto,
+        ];
+        for mode in modes {
+            for c in ['c', 'C'] {
+                let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+                assert_eq!(handle_key(key, mode), Action::Quit, "{:?}", mode);
+            }
+        }
+    }
+
+    #[test]
+    fn f1_toggles_shortcuts_while_editing() {
+        let f1 = KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE);
+        for mode in [InputMode::Normal, InputMode::EditingRange, InputMode::Help] {
+            assert_eq!(handle_key(f1, mode), Action::ToggleKeybindings);
+        }
+    }
+
+    #[test]
+    fn editing_modes_keep_literal_case() {
+        for mode in [InputMode::EditingRange, InputMode::EditingPorts] {
+            assert_eq!(handle_key(press('S'), mode), Action::Character('S'));
+            assert_eq!(handle_key(press('s'), mode), Action::Character('s'));
+        }
+    }
+
+    #[test]
+    fn ctrl_chords_edit_by_word() {
+        let ctrl = |code| KeyEvent::new(code, KeyModifiers::CONTROL);
+        for mode in [InputMode::EditingRange, InputMode::EditingPorts] {
+            assert_eq!(handle_key(ctrl(KeyCode::Left), mode), Action::WordLeft);
+            assert_eq!(handle_key(ctrl(KeyCode::Right), mode), Action::WordRight);
+            assert_eq!(handle_key(ctrl(KeyCode::Backspace), mode), Action::DeleteWord);
+            assert_eq!(handle_key(ctrl(KeyCode::Char('h')), mode), Action::DeleteWord);
+            assert_eq!(handle_key(ctrl(KeyCode::Char('w')), mode), Action::DeleteWord);
+            assert_eq!(handle_key(ctrl(KeyCode::Char('u')), mode), Action::ClearField);
+            assert_eq!(handle_key(ctrl(KeyCode::Char('z')), mode), Action::None);
+            let left = KeyEvent::new(KeyCode::Left, KeyModifiers::NONE);
+            assert_eq!(handle_key(left, mode), Action::NavigateUp);
+        }
+    }
+
+    #[test]
+    fn word_motion_stops_at_range_delimiters() {
+        let range = "10.123.240.0/22";
+        assert_eq!(word_left(range, range.len()), 13);
+        assert_eq!(word_left(range, 13), 11);
+        assert_eq!(word_left(range, 10), 7);
+        // Mid-word goes to the start of that word
+        assert_eq!(word_left(range, 5), 3);
+        assert_eq!(word_left(range, 2), 0);
+        assert_eq!(word_left(range, 0), 0);
+
+        assert_eq!(word_right(range, 0), 3);
+        assert_eq!(word_right(range, 4), 7);
+        assert_eq!(word_right(range, 11), 13);
+        assert_eq!(word_right(range, 13), range.len());
+        assert_eq!(word_right(range, range.len()), range.len());
+
+        let ports = "22, 80,443-445";
+        assert_eq!(word_left(ports, 4), 0);
+        assert_eq!(word_right(ports, 0), 4);
+        assert_eq!(word_left(ports, ports.len()), 11);
+        assert_eq!(word_right(ports, 4), 7);
+        assert_eq!(word_right(ports, 7), 11);
+    }
+
+    #[test]
+    fn capital_y_copies_a_command() {
+        assert_eq!(handle_key(press('Y'), InputMode::Normal), Action::CopyCommand);
+        assert_eq!(handle_key(press('y'), InputMode::Normal), Action::CopyIp);
+        assert_eq!(handle_key(press('Y'), InputMode::ConfirmQuit), Action::Confirm);
+    }
+
+    #[test]
+    fn ctrl_k_knocks_while_k_moves_up() {
+        let ctrl_k = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(handle_key(ctrl_k, InputMode::Normal), Action::Knock);
+        assert_eq!(handle_key(press('k'), InputMode::Normal), Action::NavigateUp);
+        assert_eq!(handle_key(ctrl_k, InputMode::Knock), Action::NavigateUp);
+    }
+
+    #[test]
+    fn capital_a_saves_json() {
+        assert_eq!(handle_key(press('A'), InputMode::Normal), Action::SaveHostJson);
+        assert_eq!(handle_key(press('a'), InputMode::Normal), Action::SaveHost);
+    }
+
+    #[test]
+    fn capital_p_toggles_the_ports_column() {
+        assert_eq!(handle_key(press('P'), InputMode::Normal), Action::TogglePorts);
+        assert_eq!(handle_key(press('p'), InputMode::Normal), Action::ConfigurePorts);
+    }
+
+    #[test]
+    fn capital_h_jumps_to_a_change() {
+        assert_eq!(handle_key(press('H'), InputMode::Normal), Action::JumpToChange);
+        assert_eq!(handle_key(press('='), InputMode::Normal), Action::JumpToSameMac);
+        assert_eq!(handle_key(press('h'), InputMode::Normal), Action::ShowEventLog);
+    }
+
+    #[test]
+    fn capital_g_is_reserved() {
+        assert_eq!(handle_key(press('G'), InputMode::Normal), Action::Character('G'));
+        assert_eq!(handle_key(press('g'), InputMode::Normal), Action::Character('g'));
+    }
+
+    #[test]
+    fn gg_and_g_sequences() {
+        let now = Instant::now();
+        let mut seq = KeySequence::default();
+        assert_eq!(seq.feed(Action::Character('g'), now), Sequence::Pending);
+        assert_eq!(
+            seq.feed(Action::Character('g'), now),
+            Sequence::Motion(Motion::First)
+        );
+        assert_eq!(
+            seq.feed(Action::Character('G'), now),
+            Sequence::Motion(Motion::Last)
+        );
+    }
+
+    #[test]
+    fn count_prefixes() {
+        let now = Instant::now();
+        let mut seq = KeySequence::default();
+        for c in ['2', '5'] {
+            assert_eq!(seq.feed(Action::Character(c), now), Sequence::Pending);
+        }
+        assert_eq!(
+            seq.feed(Action::NavigateDown, now),
+            Sequence::Motion(Motion::Down(25))
+        );
+        seq.feed(Action::Character('3'), now);
+        assert_eq!(seq.feed(Action::NavigateUp, now), Sequence::Motion(Motion::Up(3)));
+        seq.feed(Action::Character('1'), now);
+        seq.feed(Action::Character('0'), now);
+        assert_eq!(
+            seq.feed(Action::Character('G'), now),
+            Sequence::Motion(Motion::Row(9))
+        );
+        // Plain keys without a count are left alone
+        assert_eq!(seq.feed(Action::NavigateDown, now), Sequence::PassThrough);
+        assert_eq!(seq.feed(Action::Character('0'), now), Sequence::PassThrough);
+    }
+
+    #[test]
+    fn other_keys_cancel_a_sequence() {
+        let now = Instant::now();
+        let mut seq = KeySequence::default();
+        seq.feed(Action::Character('5'), now);
+        assert_eq!(seq.feed(Action::Export, now), Sequence::PassThrough);
+        assert_eq!(seq.feed(Action::NavigateDown, now), Sequence::PassThrough);
+    }
+
+    #[test]
+    fn sequences_time_out() {
+        let start = Instant::now();
+        let late = start + KEY_SEQUENCE_TIMEOUT + Duration::from_millis(1);
+        let mut seq = KeySequence::default();
+
+        seq.feed(Action::Character('g'), start);
+        assert_eq!(seq.feed(Action::Character('g'), late), Sequence::Pending);
+
+        let mut seq = KeySequence::default();
+        seq.feed(Action::Character('4'), start);
+        assert_eq!(seq.feed(Action::NavigateDown, late), Sequence::PassThrough);
+
+        // Within the timeout the sequence still completes
+        seq.feed(Action::Character('4'), start);
+        assert_eq!(
+            seq.feed(Action::NavigateDown, start + KEY_SEQUENCE_TIMEOUT),
+            Sequence::Motion(Motion::Down(4))
+        );
+    }
+}
@@ -0,0 +1,26 @@
+//! The discovery and port-scan engine behind the `ipscannr` terminal UI,
+//! usable without it (`default-features = false` leaves out the UI's
+//! dependencies).
+//!
+//! [`scan`] pings a range and streams a [`HostReport`] per host, with its
+//! name, MAC and open ports; the building blocks it uses ([`scanner::Pinger`],
+//! [`scanner::PortScanner`], [`scanner::DnsResolver`], ...) are public too.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use ipscannr::scanner::IpRange;
+//! use ipscannr::{scan, ScanOptions};
+//!
+//! let range = IpRange::parse("192.168.1.0/24")?;
+//! let mut reports = scan(&range, ScanOptions::default());
+//! while let Some(host) = reports.next().await {
+//!     println!("{} {:?}", host.ip, host.hostname);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod scan;
+pub mod scanner;
+
+pub use scan::{scan, HostReport, HostReports, ScanOptions};
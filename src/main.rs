@@ -1,21 +1,44 @@
+mod alert;
 mod app;
 mod cache;
+mod clipboard;
+mod commands;
 mod config;
+mod conflict;
+mod connectivity;
+mod debuglog;
+mod eventlog;
+mod export;
+mod highlight;
 mod input;
-mod scanner;
+mod metrics;
+mod names;
+mod notify;
+mod portdiff;
+mod serve;
+mod settings;
+mod summary;
+mod tuning;
 mod ui;
-
-use std::io;
-use std::net::Ipv4Addr;
-use std::time::Duration;
-
-use anyhow::Result;
-use clap::Parser;
+mod wake;
+mod webhook;
+
+use std::borrow::Cow;
+use std::io::{self, IsTerminal};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
-        KeyboardEnhancementFlags, ModifierKeyCode, MouseButton, MouseEventKind,
-        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture,
+        EnableBracketedPaste, EnableFocusChange, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags,
+        ModifierKeyCode, MouseButton, MouseEventKind, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
     },
     execute,
     terminal::{
@@ -25,24 +48,45 @@ use crossterm::{
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Layout, Rect},
+    layout::Rect,
+    style::Modifier,
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame, Terminal,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+use unicode_width::UnicodeWidthStr;
 
+use ipscannr::scanner;
+
+use alert::AlertMode;
 use app::{App, AppCommand, Focus, ScanEvent};
-use config::Config;
+use config::{Config, HostAction, RangeSpec};
+use connectivity::Link;
 use input::{handle_key, InputMode};
-use ui::{AppLayout, Compat, DetailsPane, InputBar, ProgressBar, ScanTable, StatusBar, Theme};
+use eventlog::EventLog;
+use names::NameSource;
+use notify::{Notification, Severity};
+use scanner::wol::WakeEvent;
+use scanner::{
+    Announcement, KnockEvent, KnockPlan, PingMethod, PingResult, Pinger, PortScanner, PortState,
+};
+use ui::layout::centered_rect;
+use ui::shortcuts::{self, Context, Shortcut};
+use ui::{
+    AppLayout, Compat, DetailsPane, InputBar, ProgressBar, ScanTable, StatusBar, Symbol, Theme,
+};
 
 #[derive(Parser)]
 #[command(name = "ipscannr")]
 #[command(about = "A terminal-based IP scanner - hack the planet!")]
 #[command(version)]
 struct Cli {
-    /// IP range to scan (e.g., 192.168.1.0/24)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// IP range to scan (e.g., 192.168.1.0/24), or `auto`, `ethernet`,
+    /// `wifi` or `vpn` for that adapter's subnet once adapters load
     #[arg(short, long)]
     range: Option<String>,
 
@@ -50,18 +94,243 @@ struct Cli {
     #[arg(short, long)]
     scan: bool,
 
+    /// Start scans of any size without asking (see --confirm-above)
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Ask before scanning a range of more than N addresses
+    #[arg(long, value_name = "N", default_value_t = config::LARGE_SCAN)]
+    confirm_above: usize,
+
     /// ASCII-only compatibility mode for limited console environments
     /// (e.g. RMM consoles that cannot render Unicode box-drawing characters)
     #[arg(long)]
     compat: bool,
+
+    /// Disable all color styling (also enabled by the NO_COLOR environment
+    /// variable or when stdout is not a terminal)
+    #[arg(long)]
+    no_color: bool,
+
+    /// On quit, print the table as shown (filtered rows, picked columns) as
+    /// plain text, so the results stay in the scrollback
+    #[arg(long)]
+    print_on_exit: bool,
+
+    /// Alert when a scan completes: bell, or a desktop notification via
+    /// OSC 9 / OSC 777 (`--notify` alone rings the bell)
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        default_value_t = AlertMode::Off,
+        default_missing_value = "bell"
+    )]
+    notify: AlertMode,
+
+    /// Also append the event log to this file
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Write diagnostics (probe outcomes, lookups, cache and export IO) to
+    /// this file, or to ipscannr-debug.log in the temp directory
+    #[arg(long, value_name = "PATH", num_args = 0..=1)]
+    debug: Option<Option<PathBuf>>,
+
+    /// Lines of ping / tracert output to keep; older lines are dropped
+    #[arg(long, value_name = "N", default_value_t = Config::default().overlay_max_lines)]
+    overlay_lines: usize,
+
+    /// For ranges larger than N addresses, keep only online hosts in memory
+    /// and count the offline ones
+    #[arg(long, value_name = "N", default_value_t = Config::default().offline_retain_limit)]
+    offline_limit: usize,
+
+    /// What Enter runs on a host whose details are already open: auto port
+    /// scans an online host not yet scanned and pings anything else
+    #[arg(long, value_name = "ACTION", value_enum, default_value_t = HostAction::Auto)]
+    enter_action: HostAction,
+
+    /// Don't ping the default gateway and the internet anchor in the
+    /// background (the header connectivity indicator)
+    #[arg(long)]
+    no_connectivity: bool,
+
+    /// Pause the background gateway / internet pings while the terminal
+    /// window is unfocused
+    #[arg(long)]
+    pause_unfocused: bool,
+
+    /// Address pinged to tell whether the internet is reachable
+    #[arg(long, value_name = "IP", default_value_t = Config::default().internet_anchor)]
+    anchor: Ipv4Addr,
+
+    /// When a live local host is missing from the ARP table, send it a UDP
+    /// datagram so the OS resolves its MAC, then look again
+    #[arg(long)]
+    arp_prewarm: bool,
+
+    /// Don't count a refused TCP connection as a live host, for networks
+    /// where a firewall or switch sends resets for dead addresses
+    #[arg(long)]
+    no_refused_alive: bool,
+
+    /// Send at most N probes a second, ping and port scan together (ICMP
+    /// echoes and TCP connects), for networks whose NAC flags bursts
+    #[arg(long, value_name = "N")]
+    rate: Option<u32>,
+
+    /// Send probes from this local address instead of the one the OS picks
+    /// (by default the chosen adapter's, for ranges on its subnet)
+    #[arg(long, value_name = "IP")]
+    source_ip: Option<Ipv4Addr>,
+
+    /// ICMP echo payload in bytes, for the scan and the continuous ping
+    /// (MTU troubleshooting); at most 65507
+    #[arg(long, value_name = "BYTES", default_value_t = Config::default().ping.payload_size)]
+    payload: usize,
+
+    /// Connect attempts after a port times out in a port scan (a refused
+    /// port is never retried)
+    #[arg(long, value_name = "N", default_value_t = Config::default().port_scan.retries)]
+    port_retries: u32,
+
+    /// Browse for SSDP / mDNS devices once the adapters are loaded (also [B])
+    #[arg(long)]
+    discover: bool,
+
+    /// Seconds SSDP / mDNS discovery listens for answers
+    #[arg(long, value_name = "SECS", default_value_t = Config::default().discovery_window.as_secs())]
+    discover_window: u64,
+
+    /// Which name the table shows when a host has several, first found wins
+    /// (all of them are listed in the details pane)
+    #[arg(
+        long,
+        value_name = "SOURCES",
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = NameSource::DEFAULT_ORDER
+    )]
+    name_order: Vec<NameSource>,
+
+    /// Milliseconds between magic packets when waking several hosts
+    #[arg(long, value_name = "MS", default_value_t = Config::default().wol_interval.as_millis() as u64)]
+    wol_interval: u64,
+
+    /// Seconds to ping woken hosts for an answer before counting them as
+    /// timed out; 0 just sends the packets
+    #[arg(long, value_name = "SECS", default_value_t = 120)]
+    wol_watch: u64,
+
+    /// Serve the results read-only on this address (`/hosts` and `/status`
+    /// as JSON, `/metrics` for Prometheus), for dashboards; a bare port
+    /// listens on localhost
+    #[arg(long, value_name = "ADDR:PORT", value_parser = serve::parse_listen)]
+    listen: Option<SocketAddr>,
+
+    /// Rewrite this Prometheus textfile (node exporter textfile collector)
+    /// after every completed scan
+    #[arg(long, value_name = "PATH")]
+    metrics_file: Option<PathBuf>,
+
+    /// Probe with ICMP echo and the allowed TCP ports only (443 unless
+    /// `--safe-ports` says otherwise), for networks where that's all that's
+    /// permitted: no port scans past them, no UDP (discovery, ARP prewarm,
+    /// NetBIOS names, knocking, Wake-on-LAN)
+    #[arg(long)]
+    safe_mode: bool,
+
+    /// TCP ports safe mode allows, e.g. 443,8443, or `none` for ICMP only;
+    /// implies --safe-mode
+    #[arg(long, value_name = "PORTS", value_parser = config::parse_allowed_ports)]
+    safe_ports: Option<config::AllowedPorts>,
+}
+
+/// The ports safe mode allows, or None when it's off: `--safe-ports`, else
+/// the settings file's, else [`config::SAFE_MODE_PORTS`]
+fn safe_mode_ports(cli: &Cli, settings: &settings::Settings) -> Result<Option<Vec<u16>>, String> {
+    let file = settings.safe_mode.as_ref().filter(|safe| safe.enabled);
+    match (&cli.safe_ports, file) {
+        (Some(ports), _) => Ok(Some(ports.0.clone())),
+        (None, Some(safe)) => safe.allowed().map(Some),
+        (None, None) if cli.safe_mode => Ok(Some(config::SAFE_MODE_PORTS.to_vec())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Tools run instead of the TUI
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Print the vendor of a MAC address (aa:bb:cc:dd:ee:ff, aa-bb-cc-dd-ee-ff
+    /// or aabb.ccdd.eeff)
+    Vendor { mac: String },
+}
+
+/// `ipscannr vendor <MAC>`
+fn print_vendor(mac: &str) -> Result<()> {
+    let normalized =
+        scanner::normalize_mac(mac).with_context(|| format!("not a MAC address: {}", mac))?;
+    match scanner::lookup_vendor(&normalized) {
+        Some(vendor) => println!("{}  {}", normalized, vendor),
+        None => println!("{}  unknown vendor", normalized),
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Flags typed on the command line win over tunables saved from the
+    // settings overlay; their defaults don't
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if let Some(Command::Vendor { mac }) = &cli.command {
+        return print_vendor(mac);
+    }
+
+    // Decide on monochrome before touching the terminal (https://no-color.org)
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let monochrome = cli.no_color || no_color_env || !io::stdout().is_terminal();
+
+    // Open the log file up front so a bad path fails before the TUI starts
+    let log_file = cli
+        .log_file
+        .as_deref()
+        .map(|path| {
+            EventLog::open_file(path)
+                .with_context(|| format!("cannot open log file {}", path.display()))
+        })
+        .transpose()?;
+    // Bind up front too: a taken port is an error, not a silent no-op
+    let listener = match cli.listen {
+        Some(addr) => Some(
+            tokio::net::TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("cannot listen on {}", addr))?,
+        ),
+        None => None,
+    };
+    // And check the settings file's safe-mode allow list: an empty one is
+    // an error, not a quiet ICMP-only mode
+    let settings = settings::load_settings();
+    let safe_ports = safe_mode_ports(&cli, &settings).map_err(anyhow::Error::msg)?;
+    let debug_log = cli.debug.map(|path| path.unwrap_or_else(debuglog::default_path));
+    if let Some(path) = &debug_log {
+        debuglog::init(path)?;
+        tracing::info!(version = env!("CARGO_PKG_VERSION"), "ipscannr started");
+    }
 
-    // Setup terminal
+    // Setup terminal. The guard undoes every step on drop, including early
+    // returns via `?` below.
     enable_raw_mode()?;
+    let mut guard = TerminalGuard {
+        mouse_capture: !cli.compat,
+        bracketed_paste: false,
+        keyboard_enhanced: false,
+        focus_change: false,
+    };
     let mut stdout = io::stdout();
     if cli.compat {
         execute!(stdout, EnterAlternateScreen)?;
@@ -71,6 +340,12 @@ async fn main() -> Result<()> {
         // ENABLE_MOUSE_INPUT on the *input* handle — the ANSI ?1000h sequence alone
         // is not sufficient in all terminal configurations.
         enable_mouse_input_win32();
+        // Pastes arrive as one Event::Paste instead of a burst of keys that
+        // would run as shortcuts. The legacy Windows console can't do this.
+        guard.bracketed_paste = execute!(stdout, EnableBracketedPaste).is_ok();
+        // Focus in / out events, so an unfocused window stops redrawing its
+        // spinners and reading the Ctrl key
+        guard.focus_change = execute!(stdout, EnableFocusChange).is_ok();
     }
     // Enable keyboard enhancement so Left Ctrl alone fires press/release events.
     // Falls back silently on terminals that don't support the Kitty protocol.
@@ -90,38 +365,127 @@ async fn main() -> Result<()> {
             )
         );
     }
+    guard.keyboard_enhanced = keyboard_enhanced;
+    // Release builds abort on panic, so Drop never runs there; the hook does
+    let (mouse_capture, bracketed_paste, keyboard_enhanced, focus_change) = (
+        guard.mouse_capture,
+        guard.bracketed_paste,
+        guard.keyboard_enhanced,
+        guard.focus_change,
+    );
+    install_panic_hook(move || {
+        restore_terminal(mouse_capture, bracketed_paste, keyboard_enhanced, focus_change)
+    });
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
     let mut config = Config::default();
+    if let Some(tuning) = &settings.tuning {
+        tuning.apply(&mut config);
+    }
+    if let Some(offline) = settings.export_offline {
+        config.export_offline = offline;
+    }
+    if let Some(ports_only) = settings.export_open_ports_only {
+        config.export_open_ports_only = ports_only;
+    }
+    config.aliases = settings
+        .aliases
+        .iter()
+        .filter(|(_, name)| !name.trim().is_empty())
+        .map(|(&ip, name)| (ip, name.trim().to_string()))
+        .collect();
     if let Some(range) = cli.range {
-        config.default_range = range;
+        config.range_spec = RangeSpec::parse(&range);
+        if let RangeSpec::Literal(range) = &config.range_spec {
+            config.default_range = range.clone();
+        }
     }
     config.compat = cli.compat;
+    config.monochrome = monochrome;
+    config.alert_mode = cli.notify;
+    config.overlay_max_lines = cli.overlay_lines;
+    config.offline_retain_limit = cli.offline_limit;
+    config.enter_action = cli.enter_action;
+    config.connectivity_check = !cli.no_connectivity;
+    config.internet_anchor = cli.anchor;
+    config.pause_unfocused = cli.pause_unfocused;
+    if cli.no_refused_alive {
+        config.ping.refused_means_alive = false;
+    }
+    if given("port_retries") {
+        config.port_scan.retries = cli.port_retries;
+    }
+    config.confirm_scan_above = (!cli.yes).then_some(cli.confirm_above);
+    config.ping.payload_size = cli.payload.min(scanner::MAX_ICMP_PAYLOAD);
+    if let Some(rate) = cli.rate {
+        config.limit_rate(rate);
+    }
+    if let Some(source) = cli.source_ip {
+        config.send_from(source);
+    }
+    config.arp_prewarm |= cli.arp_prewarm;
+    if given("discover_window") {
+        config.discovery_window = Duration::from_secs(cli.discover_window.max(1));
+    }
+    config.name_precedence = cli.name_order;
+    config.wol_interval = Duration::from_millis(cli.wol_interval);
+    config.wol_watch = (cli.wol_watch > 0).then(|| Duration::from_secs(cli.wol_watch));
+    config.metrics_file = cli.metrics_file;
+    if let Some(allowed) = safe_ports {
+        config.enter_safe_mode(allowed);
+    }
+    ui::theme::set_monochrome(config.monochrome);
     let mut app = App::new(config);
+    app.settings = settings;
+    app.apply_highlight_rules();
+    // Windows reads Left Ctrl through Win32 polling, except in compat mode
+    app.ctrl_detected = keyboard_enhanced || (cfg!(windows) && !cli.compat);
+    if let Some(file) = log_file {
+        app.event_log.set_file(file);
+    }
+    if let Some(allowed) = &app.config.safe_mode {
+        let tcp = match allowed.as_slice() {
+            [] => "no TCP".to_string(),
+            ports => format!("TCP {}", ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        app.log(
+            Severity::Info,
+            format!("Safe mode: ICMP echo and {} only; no UDP, discovery or Wake-on-LAN", tcp),
+        );
+    }
+    if let Some(listener) = listener {
+        let feed = serve::Shared::default();
+        if let Ok(addr) = listener.local_addr() {
+            app.log(Severity::Info, format!("Serving /hosts, /status and /metrics on http://{}", addr));
+        }
+        serve::spawn(listener, feed.clone());
+        app.status_feed = Some(feed);
+    }
+    if let Err(unavailable) = scanner::probe_icmp() {
+        tracing::warn!(icmp_unavailable_reason = %unavailable.reason, "ICMP echo unavailable");
+        app.icmp_unavailable(&unavailable);
+    }
 
     // Run app
-    let result = run_app(&mut terminal, &mut app, cli.scan).await;
+    let result = run_app(&mut terminal, &mut app, cli.scan, cli.discover).await;
+    app.cache_writer.flush();
 
     // Restore terminal
-    if keyboard_enhanced {
-        let _ = execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags);
-    }
-    disable_raw_mode()?;
-    if cli.compat {
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    } else {
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
-    }
+    drop(guard);
     terminal.show_cursor()?;
 
+    if cli.print_on_exit {
+        print!("{}", app.table_text());
+    }
+    if let Some(path) = debug_log {
+        println!("Debug log written to {}", path.display());
+    }
     if let Err(e) = result {
+        tracing::error!("{:#}", e);
         eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 
     Ok(())
@@ -131,10 +495,13 @@ async fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
     auto_scan: bool,
+    auto_discover: bool,
 ) -> Result<()> {
     let mut scan_rx: Option<mpsc::Receiver<ScanEvent>> = None;
+    let mut discovery_rx: Option<mpsc::Receiver<Announcement>> = None;
     let mut overlay_rx: Option<mpsc::Receiver<String>> = None;
-    let mut port_scan_rx: Option<mpsc::Receiver<(std::net::Ipv4Addr, Vec<u16>)>> = None;
+    let mut port_scan_rx: Option<mpsc::Receiver<app::PortScanEvent>> = None;
+    let mut wake_rx: Option<mpsc::Receiver<WakeEvent>> = None;
 
     // Track last rendered frame area so mouse events can hit-test panes
     let mut last_area = ratatui::layout::Rect::default();
@@ -149,41 +516,62 @@ async fn run_app<B: ratatui::backend::Backend>(
         let _ = adapter_tx.send(adapters).await;
     });
 
-    // Auto-start scan if requested (will wait for adapters)
+    // Gateway checks of adapters picked in the Range pane
+    let (gateway_tx, mut gateway_rx) = mpsc::channel(4);
+    let (mac_tx, mut mac_rx) = mpsc::channel(4);
+    // Webhook deliveries, for the event log
+    let (delivery_tx, mut delivery_rx) = mpsc::channel(16);
+    app.connect_webhook(delivery_tx);
+    // Failed --metrics-file writes, for the event log
+    let (metrics_tx, mut metrics_rx) = mpsc::channel(4);
+    app.metrics_failures = Some(metrics_tx);
+
+    // Whether background re-probing should run: false while the terminal is
+    // unfocused and `--pause-unfocused` is set
+    let (focus_tx, focus_rx) = watch::channel(true);
+    let mut connectivity_rx = app.config.connectivity_check.then(|| {
+        connectivity::spawn_checker(app.config.internet_anchor, app.config.ping.clone(), focus_rx)
+    });
+
+    // Auto-start scan / discovery if requested (will wait for adapters)
     let mut pending_auto_scan = auto_scan;
+    let mut pending_auto_discover = auto_discover;
 
     loop {
         // Tick animation for activity indicator
         app.tick_animation();
-
-        terminal.draw(|f| {
-            last_area = f.area();
-            draw_ui(f, app, &mut last_table_offset);
-        })?;
-
-        // Handle events with timeout for scan updates
-        let timeout = Duration::from_millis(50);
+        app.publish_if_due(app.dirty);
+        render_if_dirty(terminal, app, &mut last_area, &mut last_table_offset)?;
+        let animating = app.is_animating();
 
         tokio::select! {
             // Check for adapter loading completion
             adapters = adapter_rx.recv(), if app.adapters_loading => {
+                app.dirty = true;
                 if let Some(adapters) = adapters {
-                    app.adapters = adapters;
-                    app.adapters_loading = false;
-                    // Set default range from first adapter
-                    if !app.adapters.is_empty() && app.adapter_index.is_none() {
-                        app.adapter_index = Some(0);
-                        app.range_input = app.adapters[0].subnet.clone();
-                        app.range_cursor = app.range_input.len();
+                    // Pick the range `--range` named; a scripted --scan
+                    // must not quietly scan some other subnet
+                    if let Err(e) = app.set_adapters(adapters) {
+                        if pending_auto_scan {
+                            return Err(e);
+                        }
+                        app.notify(Severity::Error, e.to_string());
                     }
                     // Show cached results while the user decides whether to scan
                     app.load_cache();
+                    // Discovery binds to each adapter, so it waits for them too
+                    if pending_auto_discover {
+                        pending_auto_discover = false;
+                        discovery_rx = app.start_discovery();
+                    }
                     // Start auto-scan if requested
                     if pending_auto_scan {
                         pending_auto_scan = false;
-                        match app.start_scan().await {
-                            Ok(rx) => scan_rx = Some(rx),
-                            Err(e) => app.export_message = Some(format!("Error: {}", e)),
+                        if let Some(AppCommand::StartScan) = app.request_start() {
+                            match app.start_scan().await {
+                                Ok(rx) => scan_rx = Some(rx),
+                                Err(e) => app.scan_start_failed(e),
+                            }
                         }
                     }
                 }
@@ -197,13 +585,90 @@ async fn run_app<B: ratatui::backend::Backend>(
                     std::future::pending().await
                 }
             } => {
-                if let Some(scan_event) = event {
-                    app.handle_scan_event(scan_event);
-                } else {
+                app.dirty = true;
+                let open = match (event, scan_rx.as_mut()) {
+                    (Some(first), Some(rx)) => app.handle_scan_events(first, rx),
+                    _ => false,
+                };
+                if !open {
                     scan_rx = None;
                 }
             }
 
+            // SSDP / mDNS discovery answers
+            announcement = async {
+                if let Some(rx) = &mut discovery_rx {
+                    rx.recv().await
+                } else {
+                    std::future::pending().await
+                }
+            } => {
+                app.dirty = true;
+                match announcement {
+                    Some(announcement) => app.apply_announcement(announcement),
+                    None => {
+                        discovery_rx = None;
+                        app.discovery_finished();
+                    }
+                }
+            }
+
+            // Wake-on-LAN progress for several hosts
+            event = async {
+                if let Some(rx) = &mut wake_rx {
+                    rx.recv().await
+                } else {
+                    std::future::pending().await
+                }
+            } => {
+                app.dirty = true;
+                match event {
+                    Some(event) => app.apply_wake_event(event),
+                    None => {
+                        wake_rx = None;
+                        app.wake_finished();
+                    }
+                }
+            }
+
+            // Gateway / internet check results
+            report = async {
+                if let Some(rx) = &mut connectivity_rx {
+                    rx.recv().await
+                } else {
+                    std::future::pending().await
+                }
+            } => {
+                match report {
+                    Some(report) => {
+                        app.dirty |= report != app.connectivity;
+                        app.apply_connectivity(report);
+                    }
+                    None => connectivity_rx = None,
+                }
+            }
+
+            Some((adapter_ip, check)) = gateway_rx.recv() => {
+                app.dirty = true;
+                app.gateway_checks.insert(adapter_ip, check);
+            }
+
+            // MAC looked up again from the details
+            Some((ip, mac)) = mac_rx.recv() => {
+                app.dirty = true;
+                app.apply_mac_refresh(ip, mac);
+            }
+
+            Some(delivery) = delivery_rx.recv() => {
+                app.dirty = true;
+                app.webhook_delivered(delivery);
+            }
+
+            Some(message) = metrics_rx.recv() => {
+                app.dirty = true;
+                app.event_log.push(Severity::Error, message);
+            }
+
             // Receive background port scan results
             port_result = async {
                 if let Some(rx) = &mut port_scan_rx {
@@ -212,14 +677,14 @@ async fn run_app<B: ratatui::backend::Backend>(
                     std::future::pending().await
                 }
             } => {
-                if let Some((ip, open_ports)) = port_result {
-                    if let Some(host) = app.hosts.iter_mut().find(|h| h.ip == ip) {
-                        host.open_ports = open_ports;
-                        host.ports_scanned = true;
+                app.dirty = true;
+                match port_result {
+                    Some(event) => app.apply_port_scan_event(event),
+                    None => {
+                        app.port_scan_finished();
+                        port_scan_rx = None;
                     }
                 }
-                app.port_scanning = false;
-                port_scan_rx = None;
             }
 
             // Check for overlay output (continuous ping / tracert)
@@ -230,44 +695,41 @@ async fn run_app<B: ratatui::backend::Backend>(
                     std::future::pending().await
                 }
             } => {
+                app.dirty = true;
                 match line {
-                    Some(text) => {
-                        // Auto-scroll when near bottom
-                        let at_bottom = app.overlay_lines.is_empty()
-                            || app.overlay_scroll + 1 >= app.overlay_lines.len();
-                        app.overlay_lines.push(text);
-                        if at_bottom {
-                            app.overlay_scroll = app.overlay_lines.len().saturating_sub(1);
-                        }
-                    }
+                    Some(text) => app.push_overlay_line(text),
                     None => {
-                        // Task finished — keep overlay open for reading, title updated
+                        // Task finished — keep its output for reading (and reopening)
                         overlay_rx = None;
                         app.overlay_cancel_tx = None;
-                        if app.input_mode == InputMode::OutputOverlay {
-                            let done_title = format!("{} [Done — Esc to close]", app.overlay_title);
-                            app.overlay_title = done_title;
-                        }
+                        let done_title = format!("{} [Done — Esc to close]", app.overlay_title);
+                        app.overlay_title = done_title;
                     }
                 }
             }
 
             // Check for user input — drain all queued events so held keys don't
             // continue firing after release (one-event-per-tick caused overshoot).
-            _ = tokio::time::sleep(timeout) => {
+            waited = wait_for_input(animating) => {
+                waited?;
                 // On Windows, poll physical Left Ctrl state via Win32.
                 // GetAsyncKeyState reads the hardware key state directly and works
                 // in both legacy console and Windows Terminal (ConPTY) regardless of
                 // which window the OS considers "foreground".
                 // Skipped in compat mode: Ctrl detection doesn't work in RMM consoles.
+                // Skipped while the terminal is unfocused: the key state is global,
+                // so Ctrl held in another window would pop the bar here.
                 #[cfg(windows)]
-                if !app.compat {
-                    app.show_keybindings = is_left_ctrl_held();
+                if !app.compat && app.terminal_focused {
+                    let held = is_left_ctrl_held();
+                    app.dirty |= held != app.show_keybindings;
+                    app.show_keybindings = held;
                 }
 
                 while event::poll(Duration::from_millis(0))? {
                     let evt = event::read()?;
-                    match evt {
+                    app.dirty = true;
+                    let command = match evt {
                         // Left Ctrl alone: show/hide keybindings popup while held
                         Event::Key(key)
                             if key.code
@@ -277,6 +739,7 @@ async fn run_app<B: ratatui::backend::Backend>(
                                 KeyEventKind::Press | KeyEventKind::Repeat => true,
                                 KeyEventKind::Release => false,
                             };
+                            None
                         }
                         Event::Key(key) if key.kind == KeyEventKind::Press => {
                             // Skip modifier-only keys (Ctrl, Alt, Shift alone don't dismiss popups)
@@ -286,26 +749,52 @@ async fn run_app<B: ratatui::backend::Backend>(
                             );
                             
                             if !is_modifier_only {
-                                // Any non-modifier keypress dismisses notification message and keybindings popup
-                                app.export_message = None;
+                                // Any non-modifier keypress dismisses the keybindings popup
                                 app.show_keybindings = false;
                             }
 
+                            // Debug builds: Ctrl+F12 panics on purpose to check that the
+                            // panic hook hands back a usable terminal
+                            #[cfg(debug_assertions)]
+                            if key.code == KeyCode::F(12)
+                                && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL)
+                            {
+                                panic!("deliberate panic (Ctrl+F12, debug builds only)");
+                            }
+
                             let action = handle_key(key, app.input_mode);
-                            match app.handle_action(action)? {
+                            app.handle_action(action)?
+                        }
+                        Event::Mouse(mouse) => {
+                            handle_mouse_event(mouse, app, last_area, last_table_offset)?
+                        }
+                        Event::Paste(text) => app.handle_paste(&text)?,
+                        Event::FocusGained | Event::FocusLost => {
+                            let focused = matches!(evt, Event::FocusGained);
+                            app.set_terminal_focused(focused);
+                            let _ = focus_tx.send(focused || !app.config.pause_unfocused);
+                            None
+                        }
+                        // Redraw right away so events later in this batch
+                        // (clicks especially) hit-test against the new layout
+                        Event::Resize(..) => {
+                            render_if_dirty(terminal, app, &mut last_area, &mut last_table_offset)?;
+                            None
+                        }
+                        _ => None,
+                    };
+                    match command {
                                 Some(AppCommand::Quit) => return Ok(()),
                                 Some(AppCommand::StartScan) => {
                                     match app.start_scan().await {
                                         Ok(rx) => scan_rx = Some(rx),
-                                        Err(e) => app.export_message = Some(format!("Error: {}", e)),
+                                        Err(e) => app.scan_start_failed(e),
                                     }
                                 }
                                 Some(AppCommand::ResumeScan) => {
-                                    // Resume just restarts the scan from the beginning
-                                    app.resume_scan();
-                                    match app.start_scan().await {
+                                    match app.resume_scan().await {
                                         Ok(rx) => scan_rx = Some(rx),
-                                        Err(e) => app.export_message = Some(format!("Error: {}", e)),
+                                        Err(e) => app.scan_start_failed(e),
                                     }
                                 }
                                 Some(AppCommand::ScanPortsForSelected) => {
@@ -319,13 +808,28 @@ async fn run_app<B: ratatui::backend::Backend>(
                                 Some(AppCommand::StartTracert(ip)) => {
                                     overlay_rx = Some(start_tracert(ip, app));
                                 }
+                                Some(AppCommand::Knock(ip, label, plan)) => {
+                                    overlay_rx = Some(start_knock(ip, &label, plan, app));
+                                }
+                                Some(AppCommand::Discover) => {
+                                    discovery_rx = app.start_discovery();
+                                }
+                                Some(AppCommand::WakeSelected) => {
+                                    if let Some(rx) = app.start_wake() {
+                                        wake_rx = Some(rx);
+                                    }
+                                }
+                                Some(AppCommand::CheckGateway(adapter)) => {
+                                    connectivity::spawn_gateway_check(
+                                        adapter,
+                                        app.config.ping.clone(),
+                                        gateway_tx.clone(),
+                                    );
+                                }
+                                Some(AppCommand::RefreshMac(ip)) => {
+                                    app.spawn_mac_refresh(ip, mac_tx.clone());
+                                }
                                 None => {}
-                            }
-                        }
-                        Event::Mouse(mouse) => {
-                            handle_mouse_event(mouse, app, last_area, last_table_offset);
-                        }
-                        _ => {}
                     }
                 }
             }
@@ -334,10 +838,55 @@ async fn run_app<B: ratatui::backend::Backend>(
     }
 }
 
-/// Spawn a continuous ping task and return the output channel receiver
+/// Loop period while something is animating
+const TICK: Duration = Duration::from_millis(50);
+/// Longest wait for input while idle; input still wakes the loop immediately
+const IDLE_POLL: Duration = Duration::from_millis(250);
+
+/// Draw a frame only if state changed since the last one, then record the
+/// frame geometry that input handling needs
+fn render_if_dirty<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    last_area: &mut Rect,
+    last_table_offset: &mut usize,
+) -> io::Result<()> {
+    if !app.dirty {
+        return Ok(());
+    }
+    app.dirty = false;
+    terminal.draw(|f| {
+        *last_area = f.area();
+        draw_ui(f, app, last_table_offset);
+    })?;
+    let (split, stacked) = (app.settings.split_percent, app.settings.force_stacked);
+    app.compact_layout = AppLayout::new(*last_area, split, stacked).is_compact();
+    app.help_max_scroll = help_max_scroll(*last_area, app.compat);
+    app.event_log_page = event_log_page(*last_area);
+    app.overlay_page = output_page(*last_area);
+    app.clamp_scroll();
+    Ok(())
+}
+
+/// Resolve when it's time to check for input. While animating that is every
+/// tick; when idle no channel can fire, so block on the terminal instead of
+/// waking (and redrawing) 20 times a second.
+async fn wait_for_input(animating: bool) -> io::Result<()> {
+    if animating {
+        tokio::time::sleep(TICK).await;
+    } else {
+        tokio::task::block_in_place(|| event::poll(IDLE_POLL))?;
+    }
+    Ok(())
+}
+
+/// Spawn a continuous ping task and return the output channel receiver.
+/// Probes go out the way the scan sends them: ICMP echoes of the configured
+/// payload size where allowed, the TCP fallback otherwise.
 fn start_continuous_ping(ip: Ipv4Addr, app: &mut App) -> mpsc::Receiver<String> {
     cancel_existing_overlay_task(app);
-    app.overlay_title = format!("Continuous Ping — {}", ip);
+    let payload = app.config.ping.payload_size;
+    app.overlay_title = format!("Continuous Ping — {} ({} bytes)", ip, payload);
     app.overlay_lines.clear();
     app.overlay_scroll = 0;
     app.input_mode = InputMode::OutputOverlay;
@@ -346,6 +895,7 @@ fn start_continuous_ping(ip: Ipv4Addr, app: &mut App) -> mpsc::Receiver<String>
     app.overlay_cancel_tx = Some(cancel_tx);
 
     let (line_tx, line_rx) = mpsc::channel::<String>(256);
+    let pinger = Pinger::new(app.config.ping.clone());
 
     tokio::spawn(async move {
         let mut seq = 0u32;
@@ -360,39 +910,8 @@ fn start_continuous_ping(ip: Ipv4Addr, app: &mut App) -> mpsc::Receiver<String>
                 break;
             }
 
-            let start = std::time::Instant::now();
-            let mut alive = false;
-
-            // TCP-based ping across common ports (mirrors scanner behaviour)
-            for &port in &[80u16, 443, 22, 445, 139] {
-                let addr =
-                    std::net::SocketAddr::new(std::net::IpAddr::V4(ip), port);
-                let result = tokio::time::timeout(
-                    tokio::time::Duration::from_millis(300),
-                    tokio::net::TcpStream::connect(addr),
-                )
-                .await;
-                match result {
-                    Ok(Ok(_)) => {
-                        alive = true;
-                        break;
-                    }
-                    Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
-                        alive = true;
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-
-            let rtt = start.elapsed();
-            let line = if alive {
-                format!("[{}] Reply from {}: time={}ms", seq, ip, rtt.as_millis())
-            } else {
-                format!("[{}] Request timed out for {}", seq, ip)
-            };
-
-            if line_tx.send(line).await.is_err() {
+            let result = pinger.ping(ip).await;
+            if line_tx.send(ping_reply_line(seq, &result, payload)).await.is_err() {
                 break;
             }
         }
@@ -401,6 +920,21 @@ fn start_continuous_ping(ip: Ipv4Addr, app: &mut App) -> mpsc::Receiver<String>
     line_rx
 }
 
+/// One line of the continuous-ping overlay
+fn ping_reply_line(seq: u32, result: &PingResult, payload: usize) -> String {
+    if !result.is_alive {
+        return format!("[{}] Request timed out for {}", seq, result.ip);
+    }
+    let time = result.rtt.map_or(0, |rtt| rtt.as_millis());
+    match result.method {
+        PingMethod::Icmp => format!(
+            "[{}] Reply from {}: bytes={} time={}ms",
+            seq, result.ip, payload, time
+        ),
+        PingMethod::Tcp => format!("[{}] Reply from {}: time={}ms (TCP)", seq, result.ip, time),
+    }
+}
+
 fn cancel_existing_overlay_task(app: &mut App) {
     if let Some(tx) = app.overlay_cancel_tx.take() {
         let _ = tx.try_send(());
@@ -470,40 +1004,121 @@ fn start_tracert(ip: Ipv4Addr, app: &mut App) -> mpsc::Receiver<String> {
     line_rx
 }
 
+/// Send a knock sequence from the background and return the output channel
+/// receiver, one line per knock and the check port's answer
+fn start_knock(ip: Ipv4Addr, label: &str, plan: KnockPlan, app: &mut App) -> mpsc::Receiver<String> {
+    cancel_existing_overlay_task(app);
+    app.overlay_title = format!("Knock — {} {}", label, ip);
+    app.overlay_lines.clear();
+    app.overlay_scroll = 0;
+    app.input_mode = InputMode::OutputOverlay;
+
+    let (cancel_tx, cancel_rx) = mpsc::channel::<()>(1);
+    app.overlay_cancel_tx = Some(cancel_tx);
+
+    let (line_tx, line_rx) = mpsc::channel::<String>(256);
+    let mut port_scan = app.config.port_scan.clone();
+    port_scan.source_ip = app.probe_source(std::iter::once(ip));
+    let scanner = PortScanner::new(port_scan);
+    let knocks = plan.knocks.len();
+    let _ = line_tx.try_send(format!(
+        "Knocking {} on {} port(s), {} ms apart",
+        ip,
+        knocks,
+        plan.delay.as_millis()
+    ));
+
+    tokio::spawn(async move {
+        let (event_tx, mut event_rx) = mpsc::channel::<KnockEvent>(16);
+        let run = scanner::knock(ip, plan, &scanner, event_tx, cancel_rx);
+        let report = async {
+            let mut sent = 0;
+            while let Some(event) = event_rx.recv().await {
+                sent += usize::from(!matches!(event, KnockEvent::Checked(..)));
+                if line_tx.send(knock_line(&event, sent, knocks)).await.is_err() {
+                    break;
+                }
+            }
+        };
+        tokio::join!(run, report);
+    });
+
+    line_rx
+}
+
+/// An overlay line for one step of a knock run; `sent` counts the knocks so
+/// far out of `total`
+fn knock_line(event: &KnockEvent, sent: usize, total: usize) -> String {
+    match event {
+        KnockEvent::Sent(knock) => format!("[{}/{}] {} sent", sent, total, knock),
+        KnockEvent::Failed(knock, e) => format!("[{}/{}] {} failed: {}", sent, total, knock, e),
+        KnockEvent::Checked(port, PortState::Open) => format!("Port {} is open", port),
+        KnockEvent::Checked(port, PortState::Closed) => format!("Port {} is still closed", port),
+        KnockEvent::Checked(port, PortState::Filtered) => format!("Port {} didn't answer", port),
+    }
+}
+
 fn draw_ui(f: &mut Frame, app: &App, table_offset_out: &mut usize) {
     let size = f.area();
-    let layout = AppLayout::new(size);
 
     // Clear with background color
     let bg_style = if app.compat { Compat::default() } else { Theme::default() };
     let bg_block = Block::default().style(bg_style);
     f.render_widget(bg_block, size);
 
-    // Draw header (input bar)
-    draw_header(f, app, layout.header);
+    if !AppLayout::fits(size) {
+        draw_too_small(f, app, size);
+        return;
+    }
+    let layout = app_layout(app, size);
 
-    // Build selected IPs set for the table
-    let selected_ips = app.selected_hosts.clone();
+    // Draw header (input bar)
+    draw_header(f, app, &layout);
 
     // Draw hosts table
-    let filtered_hosts: Vec<_> = app.get_filtered_hosts().iter().map(|h| (*h).clone()).collect();
     let mut table_state = app.table_state.clone();
-    let table = ScanTable::new(&filtered_hosts)
+    if let Some(offset) = app.table_scroll {
+        let visible = ScanTable::visible_rows(layout.hosts_table);
+        let offset = offset.min(app.filtered_hosts.len().saturating_sub(visible));
+        // ratatui scrolls back to the selected row, so leave the selection out
+        // of the rendered state while the wheel has scrolled it off screen
+        if table_state.selected().is_some_and(|s| s < offset || s >= offset + visible) {
+            table_state.select(None);
+        }
+        *table_state.offset_mut() = offset;
+    }
+    let table = ScanTable::new(&app.hosts, &app.filtered_hosts)
         .show_rtt(!layout.is_compact())
+        .columns(&app.settings.columns)
         .focused(app.focus == Focus::HostsTable)
-        .selected_ips(&selected_ips)
-        .compat(app.compat);
+        .selected_ips(&app.selected_hosts)
+        .conflicted_ips(&app.conflicted_ips)
+        .range_label(app.range_view_label())
+        .filter_label((app.filter_mode != app::FilterMode::All).then(|| app.filter_mode.label()))
+        .port_progress(&app.port_progress)
+        .highlighter(&app.highlighter)
+        .compat(app.compat)
+        .show_legend(app.show_legend);
 
     f.render_stateful_widget(table, layout.hosts_table, &mut table_state);
-    // Capture the scroll offset ratatui computed so mouse clicks map to the right row
+    // Capture the scroll offset the table settled on so mouse clicks map to the right row
     *table_offset_out = table_state.offset();
 
+    if let Some(banner_area) = layout.banner {
+        draw_banner(f, app, banner_area);
+    }
+
     // Draw details pane (full mode only)
     if let Some(details_area) = layout.details_pane {
         if app.show_details {
             let details = DetailsPane::new(app.selected_host())
                 .focused(app.focus == Focus::DetailsPane)
-                .port_scanning(app.port_scanning)
+                .port_scanning(app.selected_host_port_scanning())
+                .port_diff(app.selected_port_diff())
+                .conflicts(&app.conflicts)
+                .mac_groups(&app.mac_groups)
+                .highlighter(&app.highlighter)
+                .name_order(&app.config.name_precedence)
                 .compat(app.compat);
             f.render_widget(details, details_area);
         }
@@ -512,52 +1127,90 @@ fn draw_ui(f: &mut Frame, app: &App, table_offset_out: &mut usize) {
     // Draw status bar
     draw_status_bar(f, app, layout.status_bar, layout.is_compact());
 
+    // Compact layout has no side pane: show details as a popup while focused
+    if let Some(popup_area) = layout.details_popup {
+        if app.details_popup_open() {
+            f.render_widget(Clear, popup_area);
+            let details = DetailsPane::new(app.selected_host())
+                .focused(true)
+                .port_scanning(app.selected_host_port_scanning())
+                .port_diff(app.selected_port_diff())
+                .conflicts(&app.conflicts)
+                .mac_groups(&app.mac_groups)
+                .highlighter(&app.highlighter)
+                .name_order(&app.config.name_precedence)
+                .compat(app.compat);
+            f.render_widget(details, popup_area);
+        }
+    }
+
     // Draw overlays
     match app.input_mode {
         InputMode::Help => draw_help_overlay(f, app, size),
         InputMode::Exporting => draw_export_overlay(f, app, size),
+        InputMode::EventLog => draw_event_log_overlay(f, app, size),
+        InputMode::Conflicts => draw_conflicts_overlay(f, app, size),
+        InputMode::Summary => draw_summary_overlay(f, app, size, " Scan Summary "),
+        InputMode::WakeReport => draw_summary_overlay(f, app, size, " Wake-on-LAN "),
+        InputMode::ConfirmQuit => draw_confirm_quit(f, app, size),
+        InputMode::ConfirmForget => draw_confirm_forget(f, app, size),
+        InputMode::ConfirmNewScan => draw_confirm_new_scan(f, app, size),
+        InputMode::ConfirmLargeScan => draw_confirm_large_scan(f, app, size),
+        InputMode::ContextMenu => draw_context_menu(f, app, size),
+        InputMode::CopyCommand => {
+            draw_chooser(f, app, size, "Copy command", &app.command_choices(), app.command_index, "Copy")
+        }
+        InputMode::Knock => draw_chooser(f, app, size, "Knock", &app.knock_choices(), app.knock_index, "Knock"),
+        InputMode::ColumnPicker => draw_column_picker(f, app, size),
+        InputMode::Settings => draw_settings(f, app, size),
         InputMode::OutputOverlay => draw_output_overlay(f, app, size),
         _ => {}
     }
 
-    // Contextual keybindings popup (shown while Left Ctrl is held).
-    // Skipped in compat mode: Ctrl detection doesn't work in RMM consoles.
-    if app.show_keybindings && !app.compat {
+    // Contextual keybindings popup, shown while Left Ctrl is held or pinned
+    // with F1. Ctrl detection doesn't work in RMM consoles, so compat mode
+    // only ever gets the F1 one.
+    if app.keybindings_visible() {
         draw_keybindings_popup(f, app, size);
     }
 
     // Draw export/notification message if present
-    if let Some(msg) = &app.export_message {
-        draw_message(f, app, size, msg);
+    if let Some(note) = app.notifications.current() {
+        draw_notification(f, app, size, note);
     }
 }
 
-fn draw_header(f: &mut Frame, app: &App, area: Rect) {
-    let chunks = Layout::horizontal([
-        Constraint::Min(30),
-        Constraint::Length(35), // Increased for longer status text
-    ])
-    .split(area);
-
+fn draw_header(f: &mut Frame, app: &App, layout: &AppLayout) {
     // Build range title with adapter info
     let range_title = if let Some(adapter) = app.current_adapter() {
-        format!(" Range [{}] ", adapter.adapter_type)
+        match app.gateway_checks.get(&adapter.ip) {
+            Some(check) => format!(" Range [{}, {}] ", adapter.adapter_type, check.text(app.compat)),
+            None => format!(" Range [{}] ", adapter.adapter_type),
+        }
     } else if app.adapter_index.is_none() && !app.adapters.is_empty() {
         " Range [Custom] ".to_string()
     } else {
         " Range ".to_string()
     };
 
+    let range_size = app.range_size.map(|size| match size {
+        1 => "1 address".to_string(),
+        size => format!("{} addresses", size),
+    });
+
     // Range input - focused if in RangeInput focus or editing
     let range_focused = app.focus == Focus::RangeInput || app.input_mode == InputMode::EditingRange;
     let range_bar = InputBar::new(&range_title, &app.range_input)
         .cursor_position(app.range_cursor)
         .focused(range_focused)
+        .error(app.input_error.as_deref())
+        .warning(app.range_warning.as_deref())
+        .hint(range_size.as_deref())
         .compat(app.compat);
-    f.render_widget(range_bar, chunks[0]);
+    f.render_widget(range_bar, layout.range_box);
 
     // Progress / Status
-    let progress_area = chunks[1];
+    let progress_area = layout.status_box;
     let (pb_border_style, pb_title_style) = if app.compat {
         (Compat::border(), Compat::title())
     } else {
@@ -568,195 +1221,1028 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
         .border_style(pb_border_style)
         .title(" Status ")
         .title_style(pb_title_style);
+    if app.config.safe_mode.is_some() {
+        // Kept in monochrome too, by reverse video: screenshots must show it
+        let style = if app.compat { Compat::warning() } else { Theme::warning() };
+        let badge = Span::styled(" SAFE MODE ", style.add_modifier(Modifier::BOLD | Modifier::REVERSED));
+        progress_block = progress_block.title(badge);
+    }
+    if let Some(indicator) = connectivity_line(app) {
+        progress_block = progress_block.title(indicator.right_aligned());
+    }
     if app.compat {
         progress_block = progress_block.border_set(Compat::BORDERS);
     }
 
+    // Progress bar while a scan runs, summary otherwise; clicking the box swaps the two
+    let view = status_view(app);
+    if let StatusView::Paused(_) = view {
+        let (hotkey_style, desc_style) = if app.compat {
+            (Compat::hotkey(), Compat::dimmed())
+        } else {
+            (Theme::hotkey(), Theme::hotkey_desc())
+        };
+        let hint = Line::from(vec![
+            Span::styled(" [Space]", hotkey_style),
+            Span::styled(" resume ", desc_style),
+        ]);
+        progress_block = progress_block.title_bottom(hint.right_aligned());
+    }
+
     let inner = progress_block.inner(progress_area);
     f.render_widget(progress_block, progress_area);
 
-    if app.scan_state == app::ScanState::Scanning || app.scan_state == app::ScanState::Paused {
-        let progress = ProgressBar::new(app.progress())
-            .show_percentage(true)
-            .compat(app.compat);
-        f.render_widget(progress, inner);
-    } else {
-        // Show full host summary after scan completes or while showing cached results
-        let text = match app.scan_state {
-            app::ScanState::Completed => app.completion_summary(),
-            app::ScanState::Idle if app.hosts.iter().any(|h| h.cached_at.is_some()) => {
-                let online = app.hosts.iter().filter(|h| h.is_alive).count();
-                format!("{} cached ({} online)", app.hosts.len(), online)
-            }
-            _ => app.status_text(),
-        };
-        let st_style = if app.compat { Compat::default() } else { Theme::default() };
-        let status = Paragraph::new(text).style(st_style);
-        f.render_widget(status, inner);
+    match view {
+        StatusView::Progress => {
+            let progress = ProgressBar::new(app.progress())
+                .show_percentage(true)
+                .compat(app.compat);
+            f.render_widget(progress, inner);
+        }
+        StatusView::Paused(label) => {
+            let progress = ProgressBar::new(app.progress())
+                .label(label)
+                .show_percentage(false)
+                .compat(app.compat);
+            f.render_widget(progress, inner);
+        }
+        StatusView::Summary(text) => {
+            let st_style = if app.compat { Compat::default() } else { Theme::default() };
+            let status = Paragraph::new(text).style(st_style);
+            f.render_widget(status, inner);
+        }
+    }
+}
+
+/// "● gw 2ms ● net 14ms" for the Status box border, when the check is on
+fn connectivity_line(app: &App) -> Option<Line<'static>> {
+    if !app.config.connectivity_check {
+        return None;
     }
+    let dot = |link: Link| match link {
+        Link::Up(_) => Symbol::Online.span(app.compat),
+        Link::Down => {
+            let style = if app.compat { Compat::error() } else { Theme::error() };
+            Span::styled(Symbol::Offline.glyph(app.compat), style)
+        }
+        Link::Unknown => {
+            let style = if app.compat { Compat::dimmed() } else { Theme::dimmed() };
+            Span::styled(Symbol::Offline.glyph(app.compat), style)
+        }
+    };
+    let conn = app.connectivity;
+    Some(Line::from(vec![
+        Span::raw(" "),
+        dot(conn.gateway),
+        Span::raw(format!(" gw {} ", conn.gateway.text())),
+        dot(conn.internet),
+        Span::raw(format!(" net {} ", conn.internet.text())),
+    ]))
+}
+
+/// What the header Status box shows
+enum StatusView {
+    /// Live progress bar with percentage
+    Progress,
+    /// Frozen progress bar labelled "Paused at N%"
+    Paused(String),
+    /// Host summary or idle status text
+    Summary(String),
+}
+
+fn status_view(app: &App) -> StatusView {
+    let scanning = matches!(app.scan_state, app::ScanState::Scanning | app::ScanState::Paused);
+    if scanning != app.status_swapped {
+        if app.scan_state == app::ScanState::Paused {
+            let pause = if app.compat { Compat::SYM_PAUSED } else { "⏸" };
+            return StatusView::Paused(format!(
+                "{} Paused at {:.0}%",
+                pause,
+                app.progress() * 100.0
+            ));
+        }
+        return StatusView::Progress;
+    }
+    // Show full host summary after scan completes or while showing cached results
+    let text = match app.scan_state {
+        app::ScanState::Completed | app::ScanState::Scanning | app::ScanState::Paused => {
+            app.completion_summary()
+        }
+        app::ScanState::Idle if app.hosts.iter().any(|h| h.cached_at.is_some()) => {
+            format!("{} cached ({})", app.hosts.len(), app::online_text(&app.hosts))
+        }
+        _ => app.status_text(),
+    };
+    StatusView::Summary(text)
+}
+
+/// Width the header Status box wants for its current content, borders included
+fn status_box_width(app: &App) -> u16 {
+    /// Progress bar cells, not counting its brackets
+    const BAR_WIDTH: u16 = 26;
+    let content = match status_view(app) {
+        StatusView::Progress => BAR_WIDTH + 2 + " 100%".len() as u16,
+        StatusView::Paused(label) => label.width() as u16 + 1 + BAR_WIDTH + 2,
+        StatusView::Summary(text) => text.width() as u16,
+    };
+    // Room for the indicator beside the " Status " title
+    let titles = connectivity_line(app).map_or(0, |line| line.width() as u16 + 10);
+    content.max(titles) + 2
+}
+
+/// Screen layout for `app`, with the Status box sized to its content
+fn app_layout(app: &App, size: Rect) -> AppLayout {
+    AppLayout::new(size, app.settings.split_percent, app.settings.force_stacked)
+        .status_width(status_box_width(app))
+        .banner(app.banner.is_some())
+}
+
+fn draw_banner(f: &mut Frame, app: &App, area: Rect) {
+    let Some(message) = &app.banner else {
+        return;
+    };
+    let text = format!(
+        "{} {}  (Esc dismisses)",
+        Symbol::Conflict.glyph(app.compat),
+        message
+    );
+    let style = if app.compat { Compat::warning() } else { Theme::warning() };
+    let text = ui::text::truncate(&text, area.width as usize, app.compat);
+    f.render_widget(Paragraph::new(text.into_owned()).style(style), area);
 }
 
 fn draw_status_bar(f: &mut Frame, app: &App, area: Rect, _compact: bool) {
+    f.render_widget(status_bar(app), area);
+}
+
+/// Status bar contents. The right side carries the selection count, online
+/// count and progress, with a minimal "12↑ 87/254" form for narrow terminals.
+fn status_bar(app: &App) -> StatusBar<'static> {
     // Show multi-select count when any hosts are selected
     let sel_sym = if app.compat { "x" } else { "✓" };
-    let selection_prefix = if !app.selected_hosts.is_empty() {
+    let dragged = app.drag.as_ref().map_or(0, |d| d.added.len());
+    let selection_prefix = if dragged > 0 {
+        // Live count while a drag-select is in progress
+        format!("[{}{} +{}] ", app.selected_hosts.len(), sel_sym, dragged)
+    } else if !app.selected_hosts.is_empty() {
         format!("[{}{}] ", app.selected_hosts.len(), sel_sym)
     } else {
         String::new()
     };
 
-    let online_count = app.hosts.iter().filter(|h| h.is_alive).count();
+    let (online_count, _) = app::online_counts(&app.hosts);
+    let enrichment: String = [
+        app.batch_text(),
+        app.discovery_text(),
+        app.wake_text(),
+        app.rate_text(),
+        app.enrichment_text(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|text| format!("{} | ", text))
+    .collect();
     let status_right = format!(
-        "{}{} online | {}",
+        "{}{} | {}{}",
         selection_prefix,
-        online_count,
+        app::online_text(&app.hosts),
+        enrichment,
         app.status_text()
     );
-
-    // Left side: dim affordance hint so users know shortcuts exist.
-    // In compat mode, skip the Ctrl hint (Ctrl popup is disabled in compat).
-    let left_hint = if app.compat {
-        "? Help"
+    let progress = if app.scan_state == app::ScanState::Scanning {
+        format!("{}/{}", app.scan_completed, app.scan_total)
     } else {
-        "^ Ctrl  shortcuts  |  ? Help"
+        app.status_text()
+    };
+    let up = if app.compat { "^" } else { "↑" };
+    let status_minimal = format!("{}{}{} {}", selection_prefix, online_count, up, progress);
+
+    // Recent up/down changes replace the Ctrl hint while there are any
+    let entries = ticker_entries(app);
+    let hint = if app.input_mode == InputMode::Goto {
+        format!("Go to: {}_  (Enter jumps, Esc cancels)", app.goto_input)
+    } else if entries.is_empty() {
+        status_hint(app.ctrl_detected).to_string()
+    } else {
+        let ticker: Vec<&str> = entries.iter().map(|(_, text)| text.as_str()).collect();
+        format!("{}  |  {}", ticker.join(ticker_separator(app.compat)), HELP_HINT)
     };
-    let status_bar = StatusBar::new()
-        .compat(app.compat)
-        .status_left(left_hint)
-        .status_right(status_right);
 
-    f.render_widget(status_bar, area);
+    StatusBar::new()
+        .compat(app.compat)
+        .status_left(hint)
+        .status_left_short(HELP_HINT)
+        .status_right(status_right)
+        .status_right_minimal(status_minimal)
 }
 
-fn draw_help_overlay(f: &mut Frame, app: &App, size: Rect) {
-    let area = centered_rect(62, 85, size);
+const HELP_HINT: &str = "? Help";
+
+/// Left side of the status bar: dim affordance hint so users know shortcuts
+/// exist. Terminals that can't report Left Ctrl alone (compat mode, no Kitty
+/// protocol) are pointed at F1 instead.
+fn status_hint(ctrl_detected: bool) -> &'static str {
+    if ctrl_detected {
+        "^ Ctrl  shortcuts  |  ? Help"
+    } else {
+        "F1 shortcuts  |  ? Help"
+    }
+}
+
+/// Status bar ticker of recent changes: each host with its text, "1.42 ↑ 2m ago"
+fn ticker_entries(app: &App) -> Vec<(Ipv4Addr, String)> {
+    let (up, down) = if app.compat { ("^", "v") } else { ("↑", "↓") };
+    app.ticker(cache::now_secs())
+        .into_iter()
+        .map(|change| {
+            let [_, _, c, d] = change.ip.octets();
+            let arrow = if change.up { up } else { down };
+            let text = format!("{}.{} {} {}", c, d, arrow, cache::format_cache_age(change.at));
+            (change.ip, text)
+        })
+        .collect()
+}
+
+fn ticker_separator(compat: bool) -> &'static str {
+    if compat {
+        " - "
+    } else {
+        " · "
+    }
+}
+
+/// Host of the ticker entry at `column` of the status bar, while the ticker
+/// is showing
+fn ticker_entry_at(area: Rect, app: &App, column: u16) -> Option<Ipv4Addr> {
+    let entries = ticker_entries(app);
+    if entries.is_empty() || status_bar(app).left_text(area.width)? == HELP_HINT {
+        return None;
+    }
+    let gap = ticker_separator(app.compat).width() as u16;
+    let mut x = area.x;
+    for (ip, text) in entries {
+        let width = text.width() as u16;
+        if (x..x + width).contains(&column) {
+            return Some(ip);
+        }
+        x += width + gap;
+    }
+    None
+}
+
+/// Clickable "? Help" text at the end of the status bar hint; empty when the
+/// hint has been squeezed out by the right-hand status
+fn help_hint_area(area: Rect, app: &App) -> Rect {
+    let bar = status_bar(app);
+    let Some(hint) = bar.left_text(area.width) else {
+        return Rect { width: 0, height: 0, ..area };
+    };
+    let offset = (hint.chars().count() - HELP_HINT.len()) as u16;
+    Rect {
+        x: area.x + offset,
+        width: HELP_HINT.len() as u16,
+        height: 1,
+        ..area
+    }
+    .intersection(area)
+}
+
+/// Help overlay area: 62×85% of the screen, growing to the full screen on
+/// small terminals so as many shortcuts as possible stay visible
+fn help_area(size: Rect) -> Rect {
+    let percent_x = if size.width < 80 { 100 } else { 62 };
+    let percent_y = if size.height < 30 { 100 } else { 85 };
+    centered_rect(percent_x, percent_y, size)
+}
+
+/// Largest useful help scroll offset for a frame of `size`
+fn help_max_scroll(size: Rect, compat: bool) -> usize {
+    let visible = help_area(size).height.saturating_sub(2) as usize;
+    shortcuts::help_lines(compat).len().saturating_sub(visible)
+}
+
+fn draw_help_overlay(f: &mut Frame, app: &App, size: Rect) {
+    let area = help_area(size);
 
     f.render_widget(Clear, area);
 
-    let (title_style, hotkey_style, dimmed_style, default_style, border_style) = if app.compat {
-        (Compat::title(), Compat::hotkey(), Compat::dimmed(), Compat::default(), Compat::border_focused())
+    let (title_style, dimmed_style, default_style, border_style) = if app.compat {
+        (Compat::title(), Compat::dimmed(), Compat::default(), Compat::border_focused())
     } else {
-        (Theme::title(), Theme::hotkey(), Theme::dimmed(), Theme::default(), Theme::border_focused())
+        (Theme::title(), Theme::dimmed(), Theme::default(), Theme::border_focused())
+    };
+
+    let lines = shortcuts::help_lines(app.compat);
+    let visible = area.height.saturating_sub(2) as usize;
+    let scroll = app.help_scroll.min(lines.len().saturating_sub(visible));
+    let last = (scroll + visible).min(lines.len());
+
+    let hint = if app.compat {
+        " [^/v] Scroll  [Esc] Close "
+    } else {
+        " [↑↓] Scroll  [Esc] Close "
     };
+    let position = format!(" {}-{} of {} ", scroll + 1, last, lines.len());
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(" Help ")
+        .title_style(title_style)
+        .title_bottom(Line::from(Span::styled(hint, dimmed_style)))
+        .title_bottom(Line::from(Span::styled(position, dimmed_style)).right_aligned());
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let help = Paragraph::new(lines)
+        .block(block)
+        .style(default_style)
+        .scroll((scroll as u16, 0));
 
-    let (sec_scan, sec_nav, sec_sel, sec_det, title_sep, nav_arrow, export_dash) = if app.compat {
+    f.render_widget(help, area);
+}
+
+fn event_log_area(size: Rect) -> Rect {
+    centered_rect(80, 80, size)
+}
+
+/// Entries visible at once in the event log overlay (borders and hint excluded)
+fn event_log_page(size: Rect) -> usize {
+    event_log_area(size).height.saturating_sub(3) as usize
+}
+
+fn draw_event_log_overlay(f: &mut Frame, app: &App, size: Rect) {
+    let area = event_log_area(size);
+    f.render_widget(Clear, area);
+
+    let (border_style, title_style, content_style, dimmed_style, error_style, success_style) =
+        if app.compat {
+            (
+                Compat::border_focused(),
+                Compat::title(),
+                Compat::default(),
+                Compat::dimmed(),
+                Compat::error(),
+                Compat::status_online(),
+            )
+        } else {
+            (
+                Theme::border_focused(),
+                Theme::title(),
+                Theme::default(),
+                Theme::dimmed(),
+                Theme::error(),
+                Theme::success(),
+            )
+        };
+
+    let filter = app
+        .event_log_filter
+        .map(|s| s.label())
+        .unwrap_or("All");
+    let total = app.event_log.count(app.event_log_filter);
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(format!(" Event Log [{}] ({}) ", filter, total))
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.height < 2 {
+        return;
+    }
+
+    let page = inner.height as usize - 1;
+    let scroll = app.event_log_scroll.min(total.saturating_sub(page));
+    let lines: Vec<Line> = if total == 0 {
+        vec![Line::from(Span::styled("No events yet", dimmed_style))]
+    } else {
+        app.event_log
+            .newest_first(app.event_log_filter)
+            .skip(scroll)
+            .take(page)
+            .map(|entry| {
+                let severity_style = match entry.severity {
+                    Severity::Error => error_style,
+                    Severity::Success => success_style,
+                    Severity::Info => dimmed_style,
+                };
+                Line::from(vec![
+                    Span::styled(entry.time(), dimmed_style),
+                    Span::raw(" "),
+                    Span::styled(format!("{:<5}", entry.severity.label()), severity_style),
+                    Span::raw(" "),
+                    Span::styled(entry.message.as_str(), content_style),
+                ])
+            })
+            .collect()
+    };
+
+    let content_area = Rect {
+        height: inner.height - 1,
+        ..inner
+    };
+    let hint_area = Rect {
+        y: inner.y + inner.height - 1,
+        height: 1,
+        ..inner
+    };
+    f.render_widget(Paragraph::new(lines).style(content_style), content_area);
+
+    let hint = if app.compat {
+        "[Esc] Close   [F] Filter   [^/v/j/k] Scroll   newest first"
+    } else {
+        "[Esc] Close   [F] Filter   [↑↓/j/k] Scroll   newest first"
+    };
+    f.render_widget(
+        Paragraph::new(Line::from(Span::styled(hint, dimmed_style))),
+        hint_area,
+    );
+}
+
+/// Duplicate / changed MACs as a diff: `+` for addresses answering now, `-`
+/// for the MAC the cache remembered. Same size as the event log overlay.
+fn draw_conflicts_overlay(f: &mut Frame, app: &App, size: Rect) {
+    let area = event_log_area(size);
+    f.render_widget(Clear, area);
+
+    let (border_style, title_style, content_style, dimmed_style, removed_style, added_style) =
+        if app.compat {
+            (
+                Compat::border_focused(),
+                Compat::title(),
+                Compat::default(),
+                Compat::dimmed(),
+                Compat::error(),
+                Compat::status_online(),
+            )
+        } else {
+            (
+                Theme::border_focused(),
+                Theme::title(),
+                Theme::default(),
+                Theme::dimmed(),
+                Theme::error(),
+                Theme::success(),
+            )
+        };
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(match app.port_changes().count() {
+            0 => format!(" IP Conflicts ({}) ", app.conflicts.len()),
+            changed => format!(
+                " IP Conflicts ({}) / Port Changes ({}) ",
+                app.conflicts.len(),
+                changed
+            ),
+        })
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.height < 2 {
+        return;
+    }
+
+    let page = inner.height as usize - 1;
+    let all = app.conflict_lines();
+    let scroll = app.conflicts_scroll.min(all.len().saturating_sub(page));
+    let lines: Vec<Line> = all
+        .into_iter()
+        .skip(scroll)
+        .take(page)
+        .map(|(kind, text)| {
+            let style = match kind {
+                conflict::DiffKind::Heading => content_style,
+                conflict::DiffKind::Removed => removed_style,
+                conflict::DiffKind::Added => added_style,
+            };
+            Line::from(Span::styled(text, style))
+        })
+        .collect();
+
+    let content_area = Rect {
+        height: inner.height - 1,
+        ..inner
+    };
+    let hint_area = Rect {
+        y: inner.y + inner.height - 1,
+        height: 1,
+        ..inner
+    };
+    f.render_widget(Paragraph::new(lines), content_area);
+
+    let hint = if app.compat {
+        "[Esc] Close   [^/v/j/k] Scroll"
+    } else {
+        "[Esc] Close   [↑↓/j/k] Scroll"
+    };
+    f.render_widget(
+        Paragraph::new(Line::from(Span::styled(hint, dimmed_style))),
+        hint_area,
+    );
+}
+
+/// Per-subnet, vendor, service and RTT breakdown of the results, with a bar
+/// per count. Same size as the event log overlay.
+fn draw_summary_overlay(f: &mut Frame, app: &App, size: Rect, title: &str) {
+    let area = event_log_area(size);
+    f.render_widget(Clear, area);
+
+    let (border_style, title_style, content_style, dimmed_style, heading_style) = if app.compat {
         (
-            "-- Scanning ---------------------------",
-            "-- Navigation -------------------------",
-            "-- Selection & Export -----------------",
-            "-- Host Details (Details pane) --------",
-            "IPSCANNR - Keyboard Shortcuts",
-            "[^/v] or [j/k]",
-            "Export - all hosts, or selected subset",
+            Compat::border_focused(),
+            Compat::title(),
+            Compat::default(),
+            Compat::dimmed(),
+            Compat::hotkey(),
         )
     } else {
         (
-            "── Scanning ──────────────────────",
-            "── Navigation ────────────────────",
-            "── Selection & Export ────────────",
-            "── Host Details (Details pane) ───",
-            "IPSCANNR — Keyboard Shortcuts",
-            "[↑/↓] or [j/k]",
-            "Export — all hosts, or selected subset",
+            Theme::border_focused(),
+            Theme::title(),
+            Theme::default(),
+            Theme::dimmed(),
+            Theme::hotkey(),
         )
     };
 
-    let help_text = vec![
-        Line::from(Span::styled(title_sep, title_style)),
-        Line::from(""),
-        Line::from(Span::styled(sec_scan, dimmed_style)),
-        Line::from(vec![
-            Span::styled("[S]", hotkey_style),
-            Span::raw(" Start scan  "),
-            Span::styled("[X]", hotkey_style),
-            Span::raw(" Stop/pause  "),
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title)
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.height < 2 {
+        return;
+    }
+
+    let page = inner.height as usize - 1;
+    let scroll = app.summary_scroll.min(app.summary_lines.len().saturating_sub(page));
+    let lines: Vec<Line> = app
+        .summary_lines
+        .iter()
+        .skip(scroll)
+        .take(page)
+        .map(|(kind, text)| {
+            let style = match kind {
+                summary::LineKind::Heading => heading_style,
+                summary::LineKind::Row => content_style,
+            };
+            Line::from(Span::styled(text.as_str(), style))
+        })
+        .collect();
+
+    let content_area = Rect {
+        height: inner.height - 1,
+        ..inner
+    };
+    let hint_area = Rect {
+        y: inner.y + inner.height - 1,
+        height: 1,
+        ..inner
+    };
+    f.render_widget(Paragraph::new(lines), content_area);
+
+    let hint = if app.compat {
+        "[Esc] Close   [^/v/j/k] Scroll   saved with every export"
+    } else {
+        "[Esc] Close   [↑↓/j/k] Scroll   saved with every export"
+    };
+    f.render_widget(
+        Paragraph::new(Line::from(Span::styled(hint, dimmed_style))),
+        hint_area,
+    );
+}
+
+fn draw_context_menu(f: &mut Frame, app: &App, size: Rect) {
+    let (title_style, default_style, selected_style, border_style) = if app.compat {
+        (Compat::title(), Compat::default(), Compat::selected(), Compat::border_focused())
+    } else {
+        (Theme::title(), Theme::default(), Theme::selected(), Theme::border_focused())
+    };
+
+    let area = context_menu_area(app.menu_anchor, size);
+    f.render_widget(Clear, area);
+
+    let title = app
+        .selected_host()
+        .map(|h| format!(" {} ", h.ip))
+        .unwrap_or_default();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title)
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let items: Vec<Line> = app::MenuItem::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let style = if i == app.menu_index { selected_style } else { default_style };
+            let text = format!(" {:<width$}", item.label(), width = area.width as usize);
+            Line::from(Span::styled(text, style))
+        })
+        .collect();
+    f.render_widget(Paragraph::new(items).block(block), area);
+}
+
+/// Chooser of `(label, what it does)` entries for the selected host, such
+/// as command lines or knock sequences, numbered for quick picks
+fn draw_chooser(
+    f: &mut Frame,
+    app: &App,
+    size: Rect,
+    title: &str,
+    choices: &[(String, String)],
+    index: usize,
+    verb: &str,
+) {
+    let (title_style, hotkey_style, default_style, selected_style, border_style) = if app.compat {
+        (Compat::title(), Compat::hotkey(), Compat::default(), Compat::selected(), Compat::border_focused())
+    } else {
+        (Theme::title(), Theme::hotkey(), Theme::default(), Theme::selected(), Theme::border_focused())
+    };
+
+    let label_width = choices.iter().map(|(label, _)| label.width()).max().unwrap_or(0);
+    let rows: Vec<String> = choices
+        .iter()
+        .enumerate()
+        .map(|(i, (label, detail))| format!(" {} {:<label_width$}  {} ", i + 1, label, detail))
+        .collect();
+    let content_width = rows.iter().map(|r| r.width()).max().unwrap_or(0).max(30);
+    let width = (content_width as u16 + 2).min(size.width);
+    let height = (rows.len() as u16 + 4).min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, area);
+
+    let title = app
+        .selected_host()
+        .map(|h| format!(" {}: {} ", title, h.ip))
+        .unwrap_or_default();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title)
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let mut lines: Vec<Line> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let style = if i == index { selected_style } else { default_style };
+            let row = ui::text::truncate(&row, inner_width, app.compat);
+            Line::from(Span::styled(format!("{:<inner_width$}", row), style))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" [Enter]", hotkey_style),
+        Span::raw(format!(" {}  ", verb)),
+        Span::styled("[1-9]", hotkey_style),
+        Span::raw(" Pick  "),
+        Span::styled("[Esc]", hotkey_style),
+        Span::raw(" Close"),
+    ]));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_column_picker(f: &mut Frame, app: &App, size: Rect) {
+    let (title_style, hotkey_style, default_style, selected_style, border_style) = if app.compat {
+        (Compat::title(), Compat::hotkey(), Compat::default(), Compat::selected(), Compat::border_focused())
+    } else {
+        (Theme::title(), Theme::hotkey(), Theme::default(), Theme::selected(), Theme::border_focused())
+    };
+
+    let width = 40.min(size.width);
+    let height = (app.column_picker.len() as u16 + 5).min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, area);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(" Columns ")
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let mut lines: Vec<Line> = app
+        .column_picker
+        .iter()
+        .enumerate()
+        .map(|(i, (column, shown))| {
+            let style = if i == app.column_index { selected_style } else { default_style };
+            let check = if *shown { "[x]" } else { "[ ]" };
+            let row = format!(" {} {}", check, column.name());
+            Line::from(Span::styled(format!("{:<inner_width$}", row), style))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" [Space]", hotkey_style),
+        Span::raw(" Show  "),
+        Span::styled("[+/-]", hotkey_style),
+        Span::raw(" Move"),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(" [Enter]", hotkey_style),
+        Span::raw(" Apply  "),
+        Span::styled("[Esc]", hotkey_style),
+        Span::raw(" Cancel"),
+    ]));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_settings(f: &mut Frame, app: &App, size: Rect) {
+    let (title_style, hotkey_style, default_style, selected_style, border_style) = if app.compat {
+        (Compat::title(), Compat::hotkey(), Compat::default(), Compat::selected(), Compat::border_focused())
+    } else {
+        (Theme::title(), Theme::hotkey(), Theme::default(), Theme::selected(), Theme::border_focused())
+    };
+
+    // The row being edited gets an input box below the list
+    let editor_height = if app.tuning_edit.is_some() { 3 } else { 0 };
+    let width = 44.min(size.width);
+    let height = (tuning::Field::ALL.len() as u16 + 5 + editor_height).min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, area);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(" Settings ")
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let current = tuning::Tuning::capture(&app.config);
+    let inner_width = inner.width as usize;
+    let mut lines: Vec<Line> = tuning::Field::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let style = if i == app.tuning_index { selected_style } else { default_style };
+            let row = format!(" {:<20}{}", field.label(), field.value_text(&current));
+            Line::from(Span::styled(format!("{:<inner_width$}", row), style))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    if app.tuning_edit.is_some() {
+        lines.push(Line::from(vec![
+            Span::styled(" [Enter]", hotkey_style),
+            Span::raw(" Apply  "),
+            Span::styled("[Esc]", hotkey_style),
+            Span::raw(" Cancel"),
+        ]));
+    } else {
+        lines.push(Line::from(vec![
+            Span::styled(" [Enter]", hotkey_style),
+            Span::raw(" Edit  "),
             Span::styled("[Space]", hotkey_style),
-            Span::raw(" Resume"),
-        ]),
-        Line::from(vec![
-            Span::styled("[R]", hotkey_style),
-            Span::raw(" Edit IP range  "),
-            Span::styled("[P]", hotkey_style),
-            Span::raw(" Configure ports"),
-        ]),
-        Line::from(vec![
-            Span::styled("[F]", hotkey_style),
-            Span::raw(" Toggle filter (All / Online)"),
-        ]),
+            Span::raw(" Toggle"),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled(" [0-9]", hotkey_style),
+            Span::raw(" Type a value  "),
+            Span::styled("[Esc]", hotkey_style),
+            Span::raw(" Close"),
+        ]));
+    }
+    f.render_widget(Paragraph::new(lines), inner);
+
+    if let Some(text) = &app.tuning_edit {
+        let field = tuning::Field::ALL[app.tuning_index.min(tuning::Field::ALL.len() - 1)];
+        let editor = Rect {
+            y: inner.bottom().saturating_sub(editor_height),
+            height: editor_height.min(inner.height),
+            ..inner
+        };
+        let bar = InputBar::new(field.label(), text)
+            .focused(true)
+            .error(app.tuning_error.as_deref())
+            .compat(app.compat);
+        f.render_widget(bar, editor);
+    }
+}
+
+fn draw_confirm_quit(f: &mut Frame, app: &App, size: Rect) {
+    let (title_style, hotkey_style) = if app.compat {
+        (Compat::title(), Compat::hotkey())
+    } else {
+        (Theme::title(), Theme::hotkey())
+    };
+
+    let what = if matches!(app.scan_state, app::ScanState::Scanning | app::ScanState::Paused) {
+        "Scan in progress"
+    } else if app.port_scanning {
+        "Port scan in progress"
+    } else {
+        "Ping / tracert running"
+    };
+    let dash = if app.compat { "-" } else { "—" };
+
+    let mut text = vec![
+        Line::from(Span::styled(format!("{} {} quit anyway?", what, dash), title_style)),
         Line::from(""),
-        Line::from(Span::styled(sec_nav, dimmed_style)),
-        Line::from(vec![
-            Span::styled(nav_arrow, hotkey_style),
-            Span::raw(" Navigate rows"),
-        ]),
-        Line::from(vec![
-            Span::styled("[PgUp/PgDn]", hotkey_style),
-            Span::raw(" Jump 10 rows  "),
-            Span::styled("[Home/End]", hotkey_style),
-            Span::raw(" First/last"),
-        ]),
         Line::from(vec![
-            Span::styled("[Tab]", hotkey_style),
-            Span::raw(" Switch panes"),
+            Span::styled("[Y]", hotkey_style),
+            Span::raw(" Quit   "),
+            Span::styled("[N]", hotkey_style),
+            Span::raw(" Keep running"),
         ]),
+    ];
+    if app.has_partial_results() {
+        text.push(Line::from(vec![
+            Span::styled("[S]", hotkey_style),
+            Span::raw(format!(" Save {} partial results and quit", app.hosts.len())),
+        ]));
+    }
+    text.push(Line::from(vec![
+        Span::styled("[Ctrl+C]", hotkey_style),
+        Span::raw(" again to force quit"),
+    ]));
+    draw_confirm(f, app, size, " Quit? ", text);
+}
+
+fn draw_confirm_forget(f: &mut Frame, app: &App, size: Rect) {
+    let Some(host) = app.selected_host() else {
+        return;
+    };
+    let (title_style, hotkey_style) = if app.compat {
+        (Compat::title(), Compat::hotkey())
+    } else {
+        (Theme::title(), Theme::hotkey())
+    };
+
+    let mut text = vec![
+        Line::from(Span::styled(format!("Forget cached details of {}?", host.ip), title_style)),
         Line::from(""),
-        Line::from(Span::styled(sec_sel, dimmed_style)),
-        Line::from(vec![
-            Span::styled("[Space]", hotkey_style),
-            Span::raw(" Toggle host selection (multi-select)"),
-        ]),
-        Line::from(vec![
-            Span::styled("[E]", hotkey_style),
-            Span::raw(format!(" {}", export_dash)),
-        ]),
+        Line::from("Hostname, MAC, open ports and services are cleared here and in the cache."),
         Line::from(""),
-        Line::from(Span::styled(sec_det, dimmed_style)),
         Line::from(vec![
-            Span::styled("[W]", hotkey_style),
-            Span::raw(" Wake-on-LAN  "),
-            Span::styled("[P]", hotkey_style),
-            Span::raw(" Scan ports"),
+            Span::styled("[Y]", hotkey_style),
+            Span::raw(" Forget   "),
+            Span::styled("[N]", hotkey_style),
+            Span::raw(" Keep"),
         ]),
+    ];
+    if host.is_alive {
+        text.push(Line::from(vec![
+            Span::styled("[P]", hotkey_style),
+            Span::raw(" Forget and rescan now"),
+        ]));
+    }
+    draw_confirm(f, app, size, " Forget? ", text);
+}
+
+fn draw_confirm_new_scan(f: &mut Frame, app: &App, size: Rect) {
+    let (title_style, hotkey_style) = if app.compat {
+        (Compat::title(), Compat::hotkey())
+    } else {
+        (Theme::title(), Theme::hotkey())
+    };
+    let dash = if app.compat { "-" } else { "—" };
+
+    let text = vec![
+        Line::from(Span::styled(
+            format!("A paused scan exists {} discard and start new?", dash),
+            title_style,
+        )),
+        Line::from(""),
+        Line::from(format!(
+            "{} of {} addresses scanned, {}.",
+            app.scan_completed,
+            app.scan_total,
+            app::online_text(&app.hosts)
+        )),
+        Line::from(""),
         Line::from(vec![
-            Span::styled("[C]", hotkey_style),
-            Span::raw(" Continuous ping  "),
-            Span::styled("[T]", hotkey_style),
-            Span::raw(" Tracert"),
+            Span::styled("[Enter]", hotkey_style),
+            Span::raw(" Resume it instead"),
         ]),
         Line::from(vec![
-            Span::styled("[A]", hotkey_style),
-            Span::raw(" Save host to file  "),
-            Span::styled("[D]", hotkey_style),
-            Span::raw(" Toggle details pane"),
+            Span::styled("[Y]", hotkey_style),
+            Span::raw(" Discard and start new   "),
+            Span::styled("[N]", hotkey_style),
+            Span::raw(" Keep paused"),
         ]),
+    ];
+    draw_confirm(f, app, size, " New scan? ", text);
+}
+
+fn draw_confirm_large_scan(f: &mut Frame, app: &App, size: Rect) {
+    let (title_style, hotkey_style) = if app.compat {
+        (Compat::title(), Compat::hotkey())
+    } else {
+        (Theme::title(), Theme::hotkey())
+    };
+    let addresses = app.range_size.unwrap_or_default();
+    let ping = &app.config.ping;
+
+    let text = vec![
+        Line::from(Span::styled(
+            format!("Scan {} addresses in {}?", addresses, app.range_input),
+            title_style,
+        )),
+        Line::from(""),
+        Line::from(format!(
+            "With a {} ms timeout, {} at a time, that takes up to {}.",
+            ping.timeout.as_millis(),
+            ping.concurrent_limit,
+            app::estimate_text(app.scan_estimate(addresses))
+        )),
         Line::from(""),
         Line::from(vec![
-            Span::styled("[Q] or [Ctrl+C]", hotkey_style),
-            Span::raw(" Quit"),
+            Span::styled("[Y]", hotkey_style),
+            Span::raw(" Scan   "),
+            Span::styled("[N]", hotkey_style),
+            Span::raw(" Back to the range"),
         ]),
-        Line::from(""),
-        Line::from(Span::styled("Press any key to close", dimmed_style)),
     ];
+    draw_confirm(f, app, size, " Large scan ", text);
+}
+
+/// A confirmation prompt centered over the screen, sized to fit `text`
+fn draw_confirm(f: &mut Frame, app: &App, size: Rect, title: &str, text: Vec<Line>) {
+    let (title_style, default_style, border_style) = if app.compat {
+        (Compat::title(), Compat::default(), Compat::warning())
+    } else {
+        (Theme::title(), Theme::default(), Theme::warning())
+    };
+
+    let width = 52.min(size.width);
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let rows: usize = text.iter().map(|line| line.width().div_ceil(inner_width).max(1)).sum();
+    let height = (rows as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, area);
 
     let mut block = Block::default()
         .borders(Borders::ALL)
         .border_style(border_style)
-        .title(" Help ")
+        .title(title)
         .title_style(title_style);
     if app.compat {
         block = block.border_set(Compat::BORDERS);
     }
 
-    let help = Paragraph::new(help_text)
+    let prompt = Paragraph::new(text)
         .block(block)
         .style(default_style)
-        .wrap(Wrap { trim: false });
-
-    f.render_widget(help, area);
+        .wrap(Wrap { trim: true });
+    f.render_widget(prompt, area);
 }
 
 fn draw_export_overlay(f: &mut Frame, app: &App, size: Rect) {
-    let area = centered_rect(42, 28, size);
+    let area = centered_rect(42, 42, size);
 
     f.render_widget(Clear, area);
 
@@ -766,18 +2252,29 @@ fn draw_export_overlay(f: &mut Frame, app: &App, size: Rect) {
         (Theme::title(), Theme::hotkey(), Theme::dimmed(), Theme::default(), Theme::border_focused())
     };
 
-    let scope = if app.selected_hosts.is_empty() {
-        format!("All {} hosts", app.hosts.len())
-    } else {
-        format!("{} selected host(s)", app.selected_hosts.len())
-    };
+    let checkbox = |on: bool| if on { "[x]" } else { "[ ]" };
 
     let text = vec![
         Line::from(Span::styled("Export Results", title_style)),
         Line::from(""),
         Line::from(vec![
             Span::styled("Scope: ", dimmed_style),
-            Span::styled(scope, default_style),
+            Span::styled(app.export_scope(), default_style),
+        ]),
+        Line::from(vec![
+            Span::styled("[F]", hotkey_style),
+            Span::raw(format!(" {} Respect current filter", checkbox(app.export_respect_filter))),
+        ]),
+        Line::from(vec![
+            Span::styled("[O]", hotkey_style),
+            Span::raw(format!(" {} Include offline hosts", checkbox(app.config.export_offline))),
+        ]),
+        Line::from(vec![
+            Span::styled("[P]", hotkey_style),
+            Span::raw(format!(
+                " {} Only hosts with open ports",
+                checkbox(app.config.export_open_ports_only)
+            )),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -788,6 +2285,10 @@ fn draw_export_overlay(f: &mut Frame, app: &App, size: Rect) {
             Span::styled("[J]", hotkey_style),
             Span::raw(" Export as JSON"),
         ]),
+        Line::from(vec![
+            Span::styled("[T]", hotkey_style),
+            Span::raw(" Save table view as text"),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("[Esc]", hotkey_style),
@@ -811,8 +2312,59 @@ fn draw_export_overlay(f: &mut Frame, app: &App, size: Rect) {
     f.render_widget(export, area);
 }
 
+/// Stand-in for the whole UI while the terminal is below the minimum size;
+/// the next frame after a resize draws the UI again
+fn draw_too_small(f: &mut Frame, app: &App, size: Rect) {
+    let (default_style, dimmed_style) = if app.compat {
+        (Compat::default(), Compat::dimmed())
+    } else {
+        (Theme::default(), Theme::dimmed())
+    };
+    let (dash, times) = if app.compat { ("-", "x") } else { ("\u{2014}", "\u{d7}") };
+    let need = format!(
+        "Terminal too small {} need at least {}{}{}",
+        dash,
+        ui::layout::MIN_WIDTH,
+        times,
+        ui::layout::MIN_HEIGHT
+    );
+    let now = format!("now {}{}{}", size.width, times, size.height);
+    // Rows the message wraps to, to center it vertically
+    let width = size.width.max(1) as usize;
+    let rows = need.width().div_ceil(width) + now.width().div_ceil(width);
+    let top = size.height.saturating_sub(rows as u16) / 2;
+    let area = Rect {
+        y: size.y + top,
+        height: size.height - top,
+        ..size
+    };
+    let text = vec![
+        Line::from(Span::styled(need, default_style)),
+        Line::from(Span::styled(now, dimmed_style)),
+    ];
+    f.render_widget(
+        Paragraph::new(text)
+            .alignment(ratatui::layout::Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
+/// Ping / tracert overlay area: 72×80% of the screen, the full screen on
+/// small terminals
+fn output_area(size: Rect) -> Rect {
+    let percent_x = if size.width < 60 { 100 } else { 72 };
+    let percent_y = if size.height < 20 { 100 } else { 80 };
+    centered_rect(percent_x, percent_y, size)
+}
+
+/// Output lines visible in the overlay: inside the border, above the hint bar
+fn output_page(size: Rect) -> usize {
+    output_area(size).height.saturating_sub(3) as usize
+}
+
 fn draw_output_overlay(f: &mut Frame, app: &App, size: Rect) {
-    let area = centered_rect(72, 80, size);
+    let area = output_area(size);
     f.render_widget(Clear, area);
 
     let (border_style, title_style, content_style, dimmed_style) = if app.compat {
@@ -833,11 +2385,11 @@ fn draw_output_overlay(f: &mut Frame, app: &App, size: Rect) {
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    if inner.height < 2 {
+    if inner.is_empty() {
         return;
     }
 
-    // Reserve last line for hint bar
+    // Reserve last line for hint bar; it stays even when no output fits
     let content_height = (inner.height as usize).saturating_sub(1);
     let max_scroll = app.overlay_lines.len().saturating_sub(content_height);
     let scroll = app.overlay_scroll.min(max_scroll);
@@ -866,103 +2418,98 @@ fn draw_output_overlay(f: &mut Frame, app: &App, size: Rect) {
     let content = Paragraph::new(content_lines).style(content_style);
     f.render_widget(content, content_area);
 
-    let scroll_hint = if app.compat {
-        "[Esc/Q] Stop   [^/v/j/k] Scroll   [Home/End] Top/Bottom"
+    // A finished task has nothing left to stop
+    let close = if app.overlay_cancel_tx.is_some() {
+        "[X] Stop   [Esc/Q] Stop & close"
     } else {
-        "[Esc/Q] Stop   [↑↓/j/k] Scroll   [Home/End] Top/Bottom"
+        "[Esc/Q] Close"
     };
-    let hint = Paragraph::new(Line::from(Span::styled(scroll_hint, dimmed_style)));
+    let scroll = if app.compat { "[^/v/j/k] Scroll" } else { "[↑↓/j/k] Scroll" };
+    let hint_text = format!("{}   {}   [Home/End] Top/Bottom", close, scroll);
+    let hint = Paragraph::new(Line::from(Span::styled(hint_text, dimmed_style)));
     f.render_widget(hint, hint_area);
 }
 
+/// Ctrl popup label for `s`. Enter's labels spell out the configured
+/// `--enter-action`, since that is what a press will actually do.
+fn popup_label(s: &Shortcut, context: Context, app: &App) -> Cow<'static, str> {
+    let host_context = matches!(
+        context,
+        Context::HostsTable | Context::DetailsPane | Context::DetailsPopup
+    );
+    if s.key != "[Enter]" || !host_context {
+        return Cow::Borrowed(s.label);
+    }
+    let opens_details = app.show_details || app.compact_layout;
+    match (context, app.config.enter_action.label()) {
+        (Context::HostsTable, Some(action)) if opens_details => {
+            Cow::Owned(format!("Details, then {}", action))
+        }
+        (Context::HostsTable, None) if opens_details => Cow::Borrowed(s.label),
+        (_, Some(action)) => Cow::Owned(format!("Run {}", action)),
+        (_, None) => Cow::Borrowed("Nothing"),
+    }
+}
+
 fn draw_keybindings_popup(f: &mut Frame, app: &App, size: Rect) {
-    // Build context-sensitive rows of (key, description) pairs
-    type Row = Vec<(&'static str, &'static str)>;
-    let (context, rows): (&str, Vec<Row>) = match app.input_mode {
-        InputMode::EditingRange => (
-            "Editing Range",
-            vec![vec![
-                ("[Enter]", "Apply"),
-                ("[Esc]", "Cancel"),
-                ("[←/→]", "Move cursor"),
-                ("[Tab]", "Edit ports"),
-            ]],
-        ),
-        InputMode::EditingPorts => (
-            "Editing Ports",
-            vec![vec![
-                ("[Enter]", "Apply"),
-                ("[Esc]", "Cancel"),
-                ("[←/→]", "Move cursor"),
-            ]],
-        ),
-        InputMode::OutputOverlay => (
-            "Output View",
-            vec![vec![("[Esc]", "Close"), ("[↑/↓]", "Scroll")]],
-        ),
+    let context = match app.input_mode {
+        InputMode::EditingRange => Context::EditingRange,
+        InputMode::EditingPorts => Context::EditingPorts,
+        InputMode::OutputOverlay => Context::OutputOverlay,
         InputMode::Normal => match app.focus {
-            Focus::RangeInput => (
-                "Range / Scan",
-                vec![vec![
-                    ("[S]", "Scan"),
-                    ("[R]", "Edit range"),
-                    ("[P]", "Edit ports"),
-                    ("[F]", "Filter"),
-                    ("[Tab]", "Next pane"),
-                    ("[Q]", "Quit"),
-                ]],
-            ),
-            Focus::HostsTable => (
-                "Hosts Table",
-                vec![
-                    vec![
-                        ("[↑/↓][j/k]", "Navigate"),
-                        ("[PgUp/PgDn]", "Jump 10"),
-                        ("[Home/End]", "First/last"),
-                        ("[Enter]", "Details"),
-                        ("[Space]", "Select"),
-                    ],
-                    vec![
-                        ("[S]", "Scan"),
-                        ("[F]", "Filter"),
-                        ("[E]", "Export"),
-                        ("[D]", "Details pane"),
-                        ("[Tab]", "Next pane"),
-                        ("[Q]", "Quit"),
-                    ],
-                ],
-            ),
-            Focus::DetailsPane => (
-                "Host Details",
-                vec![
-                    vec![
-                        ("[W]", "Wake-on-LAN"),
-                        ("[P]", "Scan ports"),
-                        ("[C]", "Ping"),
-                        ("[T]", "Tracert"),
-                        ("[A]", "Save"),
-                    ],
-                    vec![("[Tab]", "Next pane"), ("[Q]", "Quit")],
-                ],
-            ),
+            Focus::RangeInput => Context::RangeInput,
+            Focus::HostsTable => Context::HostsTable,
+            Focus::DetailsPane if app.details_popup_open() => Context::DetailsPopup,
+            Focus::DetailsPane => Context::DetailsPane,
         },
         // Help/Exporting overlays are already keyboard-driven; no extra popup needed
         _ => return,
     };
 
-    // Build ratatui text lines: one header + one per row
-    let mut text_lines = vec![Line::from(Span::styled(context, Theme::title()))];
-    for row in &rows {
-        let mut spans: Vec<Span> = Vec::new();
-        for (i, (key, desc)) in row.iter().enumerate() {
-            if i > 0 {
-                spans.push(Span::raw("   "));
-            }
-            spans.push(Span::styled(*key, Theme::hotkey()));
-            spans.push(Span::styled(format!(" {}", desc), Theme::hotkey_desc()));
+    // Pack (key, label) pairs into as many lines as the terminal width needs
+    let (title_style, key_style, desc_style, border_style, default_style) = if app.compat {
+        (
+            Compat::title(),
+            Compat::hotkey(),
+            Compat::dimmed(),
+            Compat::border_focused(),
+            Compat::default(),
+        )
+    } else {
+        (
+            Theme::title(),
+            Theme::hotkey(),
+            Theme::hotkey_desc(),
+            Theme::border_focused(),
+            Theme::default(),
+        )
+    };
+    let max_width = size.width.saturating_sub(2) as usize;
+    let mut text_lines = vec![Line::from(Span::styled(context.title(), title_style))];
+    let mut spans: Vec<Span> = Vec::new();
+    let mut line_width = 0;
+    for s in shortcuts::for_context(context) {
+        let key = s.key(app.compat);
+        let label = popup_label(s, context, app);
+        let entry_width = key.chars().count() + 1 + label.chars().count();
+        if !spans.is_empty() && line_width + 3 + entry_width > max_width {
+            text_lines.push(Line::from(std::mem::take(&mut spans)));
+            line_width = 0;
+        }
+        if !spans.is_empty() {
+            spans.push(Span::raw("   "));
+            line_width += 3;
         }
+        spans.push(Span::styled(key, key_style));
+        spans.push(Span::styled(format!(" {}", label), desc_style));
+        line_width += entry_width;
+    }
+    if !spans.is_empty() {
         text_lines.push(Line::from(spans));
     }
+    if context == Context::HostsTable {
+        text_lines.push(Line::from(Symbol::legend_spans(app.compat)));
+    }
 
     // Height: top border + context label + one line per row + bottom border
     let popup_height = (text_lines.len() as u16) + 2;
@@ -974,40 +2521,60 @@ fn draw_keybindings_popup(f: &mut Frame, app: &App, size: Rect) {
     };
 
     f.render_widget(Clear, popup_area);
-    let popup = Paragraph::new(text_lines)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Theme::border_focused())
-                .title(" Shortcuts ")
-                .title_style(Theme::title()),
-        )
-        .style(Theme::default());
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(" Shortcuts ")
+        .title_style(title_style);
+    if app.compat {
+        block = block.border_set(Compat::BORDERS);
+    }
+    let popup = Paragraph::new(text_lines).block(block).style(default_style);
     f.render_widget(popup, popup_area);
 }
 
-fn draw_message(f: &mut Frame, app: &App, size: Rect, message: &str) {
-    let area = centered_rect(50, 10, size);
+/// Toast in the bottom-right corner, just above the status bar
+fn draw_notification(f: &mut Frame, app: &App, size: Rect, note: &Notification) {
+    let accent = match (note.severity, app.compat) {
+        (Severity::Error, false) => Theme::error(),
+        (Severity::Error, true) => Compat::error(),
+        (Severity::Success, false) => Theme::success(),
+        (Severity::Success, true) => Compat::status_online(),
+        (Severity::Info, false) => Theme::border_focused(),
+        (Severity::Info, true) => Compat::border_focused(),
+    };
+    let default_style = if app.compat { Compat::default() } else { Theme::default() };
+
+    let max_width = (size.width * 6 / 10).max(24).min(size.width);
+    let width = (note.text.chars().count() as u16 + 4).clamp(24.min(max_width), max_width);
+    let inner_width = width.saturating_sub(4).max(1) as usize;
+    let text_lines = note.text.chars().count().div_ceil(inner_width).max(1) as u16;
+    let height = (text_lines + 2).min(size.height.saturating_sub(1));
+    let area = Rect {
+        x: size.width.saturating_sub(width + 1),
+        y: size.height.saturating_sub(height + 1),
+        width,
+        height,
+    };
 
     f.render_widget(Clear, area);
 
-    let (border_style, title_style, default_style) = if app.compat {
-        (Compat::border_focused(), Compat::title(), Compat::default())
-    } else {
-        (Theme::border_focused(), Theme::title(), Theme::default())
-    };
-
+    let mut title = format!(" {} ", note.severity.label());
+    let pending = app.notifications.pending();
+    if pending > 0 {
+        title.push_str(&format!("(+{}) ", pending));
+    }
     let mut block = Block::default()
         .borders(Borders::ALL)
-        .border_style(border_style)
-        .title(" Message ")
-        .title_style(title_style);
+        .border_style(accent)
+        .title(title)
+        .title_style(accent);
     if app.compat {
         block = block.border_set(Compat::BORDERS);
     }
 
-    let msg = Paragraph::new(message)
-        .block(block)
+    let msg = Paragraph::new(note.text.as_str())
+        .block(block.padding(ratatui::widgets::Padding::horizontal(1)))
         .style(default_style)
         .wrap(Wrap { trim: true });
 
@@ -1019,8 +2586,13 @@ fn handle_mouse_event(
     app: &mut App,
     area: ratatui::layout::Rect,
     table_offset: usize,
-) {
-    use input::InputMode;
+) -> Result<Option<AppCommand>> {
+    use input::{Action, InputMode};
+
+    // Nothing to click while only the too-small message is drawn
+    if !AppLayout::fits(area) {
+        return Ok(None);
+    }
 
     // In overlay mode only allow scrolling
     if app.input_mode == InputMode::OutputOverlay {
@@ -1029,54 +2601,160 @@ fn handle_mouse_event(
                 app.overlay_scroll = app.overlay_scroll.saturating_sub(1);
             }
             MouseEventKind::ScrollDown => {
-                // clamped to max_scroll during render
-                app.overlay_scroll = app.overlay_scroll.saturating_add(1);
+                app.overlay_scroll = (app.overlay_scroll + 1).min(app.overlay_max_scroll());
             }
             _ => {}
         }
-        return;
+        return Ok(None);
     }
 
-    // Only handle mouse in Normal mode (help/export overlays are keyboard-driven)
-    if app.input_mode != InputMode::Normal {
-        return;
+    if matches!(
+        app.input_mode,
+        InputMode::EventLog | InputMode::Conflicts | InputMode::Summary | InputMode::WakeReport
+    ) {
+        let action = match mouse.kind {
+            MouseEventKind::ScrollUp => Action::NavigateUp,
+            MouseEventKind::ScrollDown => Action::NavigateDown,
+            _ => return Ok(None),
+        };
+        return app.handle_action(action);
+    }
+
+    if app.input_mode == InputMode::ContextMenu {
+        let menu = context_menu_area(app.menu_anchor, area);
+        let action = match mouse.kind {
+            MouseEventKind::ScrollUp => Action::NavigateUp,
+            MouseEventKind::ScrollDown => Action::NavigateDown,
+            MouseEventKind::Down(MouseButton::Left) if mouse_in(menu, mouse.column, mouse.row) => {
+                // Items sit inside the border, one per row
+                let item = mouse.row.saturating_sub(menu.y + 1) as usize;
+                if mouse.row == menu.y || item >= app::MenuItem::ALL.len() {
+                    return Ok(None);
+                }
+                app.menu_index = item;
+                Action::Select
+            }
+            // Any other click dismisses the menu
+            MouseEventKind::Down(_) => Action::Cancel,
+            _ => return Ok(None),
+        };
+        return app.handle_action(action);
+    }
+
+    if app.input_mode == InputMode::Help {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => app.help_scroll = app.help_scroll.saturating_sub(1),
+            MouseEventKind::ScrollDown => {
+                app.help_scroll = (app.help_scroll + 1).min(app.help_max_scroll);
+            }
+            _ => {}
+        }
+        return Ok(None);
     }
 
-    let layout = AppLayout::new(area);
+    let layout = app_layout(app, area);
     let col = mouse.column;
     let row = mouse.row;
 
-    match mouse.kind {
-        MouseEventKind::ScrollUp => {
-            // Scroll anywhere in the table or details area navigates the host list
-            if mouse_in(layout.hosts_table, col, row)
-                || layout.details_pane.is_some_and(|d| mouse_in(d, col, row))
-            {
-                app.focus = Focus::HostsTable;
-                app.select_previous();
+    // While editing the range, a click inside it moves the cursor
+    if app.input_mode == InputMode::EditingRange {
+        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+            if mouse_in(layout.range_box, col, row) {
+                return app.edit_range_at(col.saturating_sub(layout.range_box.x + 1) as usize);
             }
         }
-        MouseEventKind::ScrollDown => {
-            if mouse_in(layout.hosts_table, col, row)
-                || layout.details_pane.is_some_and(|d| mouse_in(d, col, row))
-            {
+        return Ok(None);
+    }
+
+    // Only handle mouse in Normal mode (the export overlay is keyboard-driven)
+    if app.input_mode != InputMode::Normal {
+        return Ok(None);
+    }
+
+    // Compact details popup is modal: a click outside it closes it
+    if app.details_popup_open() {
+        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+            if !layout.details_popup.is_some_and(|p| mouse_in(p, col, row)) {
                 app.focus = Focus::HostsTable;
-                app.select_next();
             }
         }
+        return Ok(None);
+    }
+
+    // A click on the banner dismisses it
+    if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+        if layout.banner.is_some_and(|b| mouse_in(b, col, row)) {
+            app.banner = None;
+            return Ok(None);
+        }
+    }
+
+    // Table row under the pointer, if any
+    let data_row = ScanTable::row_at(
+        layout.hosts_table,
+        table_offset,
+        app.filtered_hosts.len(),
+        col,
+        row,
+    );
+
+    match mouse.kind {
+        // The wheel over the table scrolls the view; the selection stays put
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+            if mouse_in(layout.hosts_table, col, row) =>
+        {
+            let rows = match mouse.kind {
+                MouseEventKind::ScrollUp => -WHEEL_ROWS,
+                _ => WHEEL_ROWS,
+            };
+            let visible = ScanTable::visible_rows(layout.hosts_table);
+            app.scroll_table(table_offset, rows, visible);
+        }
+        // Over the details pane it steps through hosts
+        MouseEventKind::ScrollUp if layout.details_pane.is_some_and(|d| mouse_in(d, col, row)) => {
+            app.focus = Focus::HostsTable;
+            app.select_previous();
+        }
+        MouseEventKind::ScrollDown if layout.details_pane.is_some_and(|d| mouse_in(d, col, row)) => {
+            app.focus = Focus::HostsTable;
+            app.select_next();
+        }
         MouseEventKind::Down(MouseButton::Left) => {
-            if mouse_in(layout.header, col, row) {
+            if mouse_in(help_hint_area(layout.status_bar, app), col, row) {
+                return app.handle_action(Action::Help);
+            } else if let Some(ip) = mouse_in(layout.status_bar, col, row)
+                .then(|| ticker_entry_at(layout.status_bar, app, col))
+                .flatten()
+            {
+                app.select_ip(ip);
+            } else if mouse_in(layout.range_box, col, row) {
+                // First click focuses the range, a second one edits at the click
+                if app.focus == Focus::RangeInput {
+                    // Text starts inside the block border
+                    return app.edit_range_at(col.saturating_sub(layout.range_box.x + 1) as usize);
+                }
                 app.focus = Focus::RangeInput;
+            } else if mouse_in(layout.status_box, col, row) {
+                app.status_swapped = !app.status_swapped;
             } else if mouse_in(layout.hosts_table, col, row) {
                 app.focus = Focus::HostsTable;
-                // border (1 row) + header row (1 row) = data starts at y+2
-                let top = layout.hosts_table.y + 2;
-                let bottom = layout.hosts_table.y + layout.hosts_table.height - 1;
-                if row >= top && row < bottom {
-                    let abs_row = (row - top) as usize + table_offset;
-                    if abs_row < app.filtered_hosts.len() {
-                        app.table_state.select(Some(abs_row));
+                if let Some(abs_row) = data_row {
+                    app.table_state.select(Some(abs_row));
+                    let now = Instant::now();
+                    let double = app.last_row_click.is_some_and(|(at, r)| {
+                        r == abs_row && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+                    });
+                    if double {
+                        app.last_row_click = None;
+                        // Same as Enter: focus the details pane or open the popup
+                        return app.handle_action(Action::Select);
                     }
+                    app.last_row_click = Some((now, abs_row));
+                    // Dragging from here selects the rows crossed
+                    app.drag = Some(app::DragSelect {
+                        last_row: abs_row,
+                        ..Default::default()
+                    });
                 }
             } else if let Some(details_area) = layout.details_pane {
                 if mouse_in(details_area, col, row) {
@@ -1084,14 +2762,107 @@ fn handle_mouse_event(
                 }
             }
         }
+        MouseEventKind::Drag(MouseButton::Left) => {
+            if let Some(abs_row) = data_row {
+                app.drag_to(abs_row);
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => app.drag = None,
+        MouseEventKind::Down(MouseButton::Right) => {
+            if let Some(abs_row) = data_row {
+                app.focus = Focus::HostsTable;
+                app.table_state.select(Some(abs_row));
+                app.open_context_menu(col, row);
+            }
+        }
         _ => {}
     }
+    Ok(None)
+}
+
+/// Rows the hosts table scrolls per mouse wheel notch
+const WHEEL_ROWS: isize = 3;
+
+/// Two left clicks on the same row within this interval count as a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Host context menu, opened just below-right of the click and kept on screen
+fn context_menu_area(anchor: (u16, u16), size: Rect) -> Rect {
+    let width = 18.min(size.width);
+    let height = (app::MenuItem::ALL.len() as u16 + 2).min(size.height);
+    let (col, row) = anchor;
+    Rect {
+        x: col.saturating_add(1).min(size.width - width),
+        y: row.saturating_add(1).min(size.height - height),
+        width,
+        height,
+    }
 }
 
 fn mouse_in(rect: ratatui::layout::Rect, col: u16, row: u16) -> bool {
     col >= rect.x && col < rect.x + rect.width && row >= rect.y && row < rect.y + rect.height
 }
 
+/// Terminal modes switched on at startup; dropping the guard switches them off
+struct TerminalGuard {
+    mouse_capture: bool,
+    bracketed_paste: bool,
+    keyboard_enhanced: bool,
+    focus_change: bool,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal(
+            self.mouse_capture,
+            self.bracketed_paste,
+            self.keyboard_enhanced,
+            self.focus_change,
+        );
+    }
+}
+
+/// Leave raw mode, the alternate screen, mouse capture, bracketed paste, focus
+/// reporting and keyboard enhancement. Runs at most once (guard drop or panic
+/// hook, whichever is first) and ignores errors: there is nothing useful to do
+/// with them here.
+fn restore_terminal(
+    mouse_capture: bool,
+    bracketed_paste: bool,
+    keyboard_enhanced: bool,
+    focus_change: bool,
+) {
+    static RESTORED: AtomicBool = AtomicBool::new(false);
+    if RESTORED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut stdout = io::stdout();
+    if keyboard_enhanced {
+        let _ = execute!(stdout, PopKeyboardEnhancementFlags);
+    }
+    let _ = disable_raw_mode();
+    if mouse_capture {
+        let _ = execute!(stdout, DisableMouseCapture);
+    }
+    if bracketed_paste {
+        let _ = execute!(stdout, DisableBracketedPaste);
+    }
+    if focus_change {
+        let _ = execute!(stdout, DisableFocusChange);
+    }
+    let _ = execute!(stdout, LeaveAlternateScreen, crossterm::cursor::Show);
+}
+
+/// Run `restore` before the current panic hook, so the panic message is
+/// printed to a usable shell instead of the alternate screen
+fn install_panic_hook(restore: impl Fn() + Send + Sync + 'static) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        previous(info);
+    }));
+}
+
 /// On Windows, crossterm's EnableMouseCapture sends the ANSI ?1000h escape to
 /// stdout, but the ReadConsoleInputW path (which crossterm uses to read events)
 /// only delivers MOUSE_EVENT_RECORD structs when ENABLE_MOUSE_INPUT is set on
@@ -1142,18 +2913,1061 @@ fn is_left_ctrl_held() -> bool {
     false
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
-
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(popup_layout[1])[1]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::style::Color;
+
+    use crate::app::HostInfo;
+    use crate::names::Names;
+    use crate::scanner::{HostStatus, MacInfo, PingMethod, Probe};
+
+    fn sample_host(last_octet: u8, is_alive: bool) -> HostInfo {
+        HostInfo {
+            rtt: is_alive.then(|| Duration::from_millis(3)),
+            hostname: is_alive.then(|| format!("host-{}.lan", last_octet)),
+            mac: Some(MacInfo {
+                address: "AA:BB:CC:DD:EE:FF".to_string(),
+                vendor: Some("Vendor".to_string()),
+            }),
+            open_ports: if is_alive { vec![22, 80] } else { Vec::new() },
+            ports_scanned: is_alive,
+            ..HostInfo::pinged(Ipv4Addr::new(192, 168, 1, last_octet), is_alive)
+        }
+    }
+
+    fn sample_app() -> App {
+        let mut app = App::new(Config::default());
+        app.adapters_loading = false;
+        app.hosts = vec![sample_host(1, true), sample_host(2, false), sample_host(3, true)];
+        app.update_filtered_hosts();
+        app.table_state.select(Some(0));
+        app.selected_hosts.insert(Ipv4Addr::new(192, 168, 1, 3));
+        app
+    }
+
+    fn render(app: &App, width: u16, height: u16) -> ratatui::buffer::Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("test terminal");
+        let mut offset = 0;
+        terminal
+            .draw(|f| draw_ui(f, app, &mut offset))
+            .expect("draw frame");
+        terminal.backend().buffer().clone()
+    }
+
+    #[test]
+    fn monochrome_frame_contains_no_colors() {
+        ui::theme::set_monochrome(true);
+        for compat in [false, true] {
+            let mut app = sample_app();
+            app.compat = compat;
+            for mode in [InputMode::Normal, InputMode::Help, InputMode::Exporting] {
+                app.input_mode = mode;
+                let buffer = render(&app, 120, 40);
+                for cell in buffer.content() {
+                    assert_eq!(cell.fg, Color::Reset, "fg color leaked at {:?}", cell);
+                    assert_eq!(cell.bg, Color::Reset, "bg color leaked at {:?}", cell);
+                }
+            }
+        }
+        ui::theme::set_monochrome(false);
+    }
+
+    #[test]
+    fn colored_frame_uses_theme_colors() {
+        ui::theme::set_monochrome(false);
+        let buffer = render(&sample_app(), 120, 40);
+        assert!(buffer.content().iter().any(|c| c.bg == Theme::BG));
+    }
+
+    fn buffer_text(buffer: &ratatui::buffer::Buffer) -> String {
+        buffer.content().iter().map(|c| c.symbol()).collect()
+    }
+
+    #[test]
+    fn help_overlay_scrolls_on_short_terminal() {
+        let mut app = sample_app();
+        app.input_mode = InputMode::Help;
+        let size = Rect::new(0, 0, 80, 20);
+        assert!(help_max_scroll(size, false) > 0);
+
+        let top = buffer_text(&render(&app, 80, 20));
+        assert!(top.contains("Keyboard Shortcuts"));
+        assert!(top.contains(" 1-18 of "));
+        assert!(!top.contains("Quit (also Ctrl+C)"));
+
+        app.help_scroll = help_max_scroll(size, false);
+        let bottom = buffer_text(&render(&app, 80, 20));
+        assert!(bottom.contains("Quit (also Ctrl+C)"));
+        assert!(!bottom.contains("Keyboard Shortcuts"));
+    }
+
+    #[test]
+    fn notification_renders_as_corner_toast() {
+        ui::theme::set_monochrome(false);
+        let mut app = sample_app();
+        app.notify(Severity::Error, "Export failed");
+        app.notify(Severity::Info, "queued");
+        let buffer = render(&app, 120, 40);
+
+        let text = buffer_text(&buffer);
+        assert!(text.contains("Export failed"));
+        assert!(text.contains(" Error (+1) "));
+        assert!(!text.contains("queued"));
+
+        // Drawn in the lower-right quadrant, with the error color on its border
+        let pos = buffer
+            .content()
+            .iter()
+            .position(|c| c.symbol() == "E" && c.fg == Theme::ERROR)
+            .expect("error-colored title");
+        let (x, y) = buffer.pos_of(pos);
+        assert!(x >= 60 && y >= 30, "toast at ({x}, {y})");
+    }
+
+    #[test]
+    fn connectivity_indicator_sits_on_the_status_box() {
+        let mut app = sample_app();
+        app.connectivity = connectivity::Connectivity {
+            gateway_ip: Some(Ipv4Addr::new(192, 168, 1, 1)),
+            gateway: Link::Up(Duration::from_millis(2)),
+            internet: Link::Down,
+        };
+        let buffer = render(&app, 120, 40);
+        let top: String = (0..120).map(|x| buffer[(x, 0)].symbol()).collect();
+        assert!(top.contains("gw 2ms"), "{}", top);
+        assert!(top.contains("net down"), "{}", top);
+
+        app.config.connectivity_check = false;
+        let top: String = {
+            let buffer = render(&app, 120, 40);
+            (0..120).map(|x| buffer[(x, 0)].symbol()).collect()
+        };
+        assert!(!top.contains("gw "), "{}", top);
+    }
+
+    #[test]
+    fn safe_mode_badge_shows_in_the_header() {
+        let mut app = sample_app();
+        let top = |app: &App| -> String {
+            let buffer = render(app, 120, 40);
+            (0..120).map(|x| buffer[(x, 0)].symbol()).collect()
+        };
+        assert!(!top(&app).contains("SAFE MODE"));
+        app.config.enter_safe_mode(vec![443]);
+        assert!(top(&app).contains(" SAFE MODE "), "{}", top(&app));
+    }
+
+    #[test]
+    fn event_log_overlay_lists_newest_first_and_filters() {
+        let mut app = sample_app();
+        app.log(Severity::Info, "Scan started: 192.168.1.0/24");
+        app.notify(Severity::Error, "WOL error: unreachable");
+        app.log(Severity::Success, "Scan complete");
+        app.input_mode = InputMode::EventLog;
+
+        let text = buffer_text(&render(&app, 120, 40));
+        let complete = text.find("Scan complete").expect("newest entry");
+        let started = text.find("Scan started").expect("oldest entry");
+        assert!(complete < started);
+
+        app.event_log_filter = Some(Severity::Error);
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("WOL error: unreachable"));
+        assert!(!text.contains("Scan started"));
+    }
+
+    #[test]
+    fn range_flag_accepts_adapter_keywords() {
+        use crate::scanner::adapters::AdapterType;
+        assert_eq!(RangeSpec::parse("auto"), RangeSpec::Auto);
+        assert_eq!(RangeSpec::parse("Ethernet"), RangeSpec::Adapter(AdapterType::Ethernet));
+        assert_eq!(RangeSpec::parse("wi-fi"), RangeSpec::Adapter(AdapterType::Wifi));
+        assert_eq!(
+            RangeSpec::parse("10.0.0.1-50; 10.0.1.0/24"),
+            RangeSpec::Literal("10.0.0.1-50; 10.0.1.0/24".to_string())
+        );
+    }
+
+    #[test]
+    fn notify_flag_defaults_to_bell_when_bare() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).expect("valid args").notify;
+        assert_eq!(parse(&["ipscannr"]), AlertMode::Off);
+        assert_eq!(parse(&["ipscannr", "--notify"]), AlertMode::Bell);
+        assert_eq!(parse(&["ipscannr", "--notify", "osc9"]), AlertMode::Osc9);
+    }
+
+    #[test]
+    fn vendor_subcommand_takes_a_mac() {
+        let cli = Cli::try_parse_from(["ipscannr", "vendor", "f027.2d0a.bb01"]).expect("valid args");
+        assert!(matches!(cli.command, Some(Command::Vendor { mac }) if mac == "f027.2d0a.bb01"));
+        assert!(Cli::try_parse_from(["ipscannr", "vendor"]).is_err());
+        assert!(print_vendor("not-a-mac").is_err());
+    }
+
+    #[test]
+    fn safe_ports_must_parse_and_icmp_only_is_spelled_out() {
+        let ports = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).map_err(|e| e.kind())?;
+            Ok::<_, clap::error::ErrorKind>(safe_mode_ports(&cli, &settings::Settings::default()).unwrap())
+        };
+        assert_eq!(ports(&["ipscannr"]), Ok(None));
+        assert_eq!(ports(&["ipscannr", "--safe-mode"]), Ok(Some(vec![443])));
+        assert_eq!(ports(&["ipscannr", "--safe-ports", "8443, 443,8000-8002"]), Ok(Some(vec![443, 8000, 8001, 8002, 8443])));
+        assert_eq!(ports(&["ipscannr", "--safe-ports", "none"]), Ok(Some(Vec::new())));
+        for typo in ["", "433;8443", "https", "443,", "0", "9000-8000", "70000"] {
+            assert!(ports(&["ipscannr", "--safe-ports", typo]).is_err(), "{:?}", typo);
+        }
+
+        // The settings file's list: empty only with icmp_only
+        let cli = Cli::try_parse_from(["ipscannr"]).unwrap();
+        let mut settings = settings::Settings {
+            safe_mode: Some(settings::SafeMode { allowed_ports: Vec::new(), ..Default::default() }),
+            ..Default::default()
+        };
+        assert!(safe_mode_ports(&cli, &settings).unwrap_err().contains("allowed_ports is empty"));
+        settings.safe_mode.as_mut().unwrap().icmp_only = true;
+        assert_eq!(safe_mode_ports(&cli, &settings), Ok(Some(Vec::new())));
+        settings.safe_mode.as_mut().unwrap().enabled = false;
+        assert_eq!(safe_mode_ports(&cli, &settings), Ok(None));
+    }
+
+    #[test]
+    fn confirm_quit_prompt_offers_partial_save() {
+        let mut app = sample_app();
+        app.scan_state = app::ScanState::Scanning;
+        app.input_mode = InputMode::ConfirmQuit;
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("Scan in progress"));
+        assert!(text.contains("Save 3 partial results and quit"));
+    }
+
+    #[test]
+    fn confirm_forget_prompt_names_the_host() {
+        let mut app = sample_app();
+        app.input_mode = InputMode::ConfirmForget;
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("Forget cached details of 192.168.1.1?"), "{}", text);
+        assert!(text.contains("Forget and rescan now"));
+    }
+
+    #[test]
+    fn range_title_shows_the_adapter_gateway_check() {
+        let mut app = sample_app();
+        app.adapters = vec![crate::scanner::AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: crate::scanner::adapters::AdapterType::Ethernet,
+            ip: Ipv4Addr::new(192, 168, 1, 10),
+            prefix_length: 24,
+            subnet: "192.168.1.0/24".to_string(),
+        }];
+        app.adapter_index = Some(0);
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("Range [Ethernet]"), "{}", text);
+
+        app.gateway_checks.insert(
+            Ipv4Addr::new(192, 168, 1, 10),
+            connectivity::GatewayCheck::Checked(Ipv4Addr::new(192, 168, 1, 1), Link::Up(Duration::from_millis(2))),
+        );
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("Range [Ethernet, gw 192.168.1.1 2ms]"), "{}", text);
+    }
+
+    #[test]
+    fn confirm_new_scan_prompt_offers_resume() {
+        let mut app = sample_app();
+        app.scan_state = app::ScanState::Paused;
+        app.scan_completed = 3;
+        app.scan_total = 254;
+        app.input_mode = InputMode::ConfirmNewScan;
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("A paused scan exists — discard and start new?"), "{}", text);
+        assert!(text.contains("3 of 254 addresses scanned, 2 online."), "{}", text);
+        assert!(text.contains("[Enter] Resume it instead"), "{}", text);
+    }
+
+    fn click(kind: MouseEventKind, column: u16, row: u16) -> crossterm::event::MouseEvent {
+        crossterm::event::MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        }
+    }
+
+    /// Screen position of data row `index` in the hosts table
+    fn table_row(app: &App, size: Rect, index: u16) -> (u16, u16) {
+        let layout = app_layout(app, size);
+        (layout.hosts_table.x + 4, layout.hosts_table.y + 2 + index)
+    }
+
+    #[test]
+    fn double_click_opens_details() {
+        let mut app = sample_app();
+        let size = Rect::new(0, 0, 120, 40);
+        let (col, row) = table_row(&app, size, 1);
+        let left = MouseEventKind::Down(MouseButton::Left);
+
+        handle_mouse_event(click(left, col, row), &mut app, size, 0).unwrap();
+        assert_eq!(app.table_state.selected(), Some(1));
+        assert_eq!(app.focus, Focus::HostsTable);
+
+        handle_mouse_event(click(left, col, row), &mut app, size, 0).unwrap();
+        assert_eq!(app.focus, Focus::DetailsPane);
+    }
+
+    #[test]
+    fn copy_command_chooser_shows_numbered_commands() {
+        let mut app = sample_app();
+        app.handle_action(input::Action::CopyCommand).unwrap();
+        assert_eq!(app.input_mode, InputMode::CopyCommand);
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("Copy command: 192.168.1.1"));
+        assert!(text.contains("ssh admin@192.168.1.1"));
+        assert!(text.contains("[1-9]"));
+    }
+
+    #[test]
+    fn right_click_menu_runs_clicked_item() {
+        let mut app = sample_app();
+        let size = Rect::new(0, 0, 120, 40);
+        let (col, row) = table_row(&app, size, 2);
+
+        let right = MouseEventKind::Down(MouseButton::Right);
+        handle_mouse_event(click(right, col, row), &mut app, size, 0).unwrap();
+        assert_eq!(app.input_mode, InputMode::ContextMenu);
+        assert_eq!(app.table_state.selected(), Some(2));
+        assert!(buffer_text(&render(&app, 120, 40)).contains("Wake-on-LAN"));
+
+        // Third item is "Port scan"
+        let menu = context_menu_area(app.menu_anchor, size);
+        let left = MouseEventKind::Down(MouseButton::Left);
+        let command =
+            handle_mouse_event(click(left, menu.x + 2, menu.y + 3), &mut app, size, 0).unwrap();
+        assert!(matches!(command, Some(AppCommand::ScanPortsForSelected)));
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn click_outside_menu_closes_it() {
+        let mut app = sample_app();
+        let size = Rect::new(0, 0, 120, 40);
+        app.open_context_menu(110, 38);
+        let menu = context_menu_area(app.menu_anchor, size);
+        assert!(menu.x + menu.width <= size.width && menu.y + menu.height <= size.height);
+
+        let left = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse_event(click(left, 0, 0), &mut app, size, 0).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn clicking_help_hint_opens_help() {
+        for compat in [false, true] {
+            let mut app = sample_app();
+            app.compat = compat;
+            let size = Rect::new(0, 0, 120, 40);
+            let layout = AppLayout::new(size, 55, false);
+            let hint = help_hint_area(layout.status_bar, &app);
+            let rendered = buffer_text(&render(&app, 120, 40));
+            let line: String = rendered.chars().skip(39 * 120).collect();
+            assert_eq!(&line[hint.x as usize..][..6], "? Help");
+
+            let left = MouseEventKind::Down(MouseButton::Left);
+            handle_mouse_event(click(left, hint.x + 1, hint.y), &mut app, size, 0).unwrap();
+            assert_eq!(app.input_mode, InputMode::Help);
+        }
+    }
+
+    #[test]
+    fn change_ticker_replaces_the_hint_and_selects_on_click() {
+        let mut app = sample_app();
+        let now = cache::now_secs();
+        app.recent_changes = [(3, true, now - 120), (2, false, now - 300)]
+            .into_iter()
+            .map(|(last, up, at)| app::StateChange {
+                ip: Ipv4Addr::new(192, 168, 1, last),
+                up,
+                at,
+            })
+            .collect();
+        let size = Rect::new(0, 0, 120, 40);
+        let rendered = buffer_text(&render(&app, 120, 40));
+        let line: String = rendered.chars().skip(39 * 120).collect();
+        assert!(line.starts_with("1.3 ↑ 2m ago · 1.2 ↓ 5m ago  |  ? Help"), "{}", line);
+
+        let layout = AppLayout::new(size, 55, false);
+        let left = MouseEventKind::Down(MouseButton::Left);
+        let second = "1.3 ↑ 2m ago · ".chars().count() as u16;
+        handle_mouse_event(click(left, second + 1, layout.status_bar.y), &mut app, size, 0).unwrap();
+        assert_eq!(app.selected_host().map(|h| h.ip), Some(Ipv4Addr::new(192, 168, 1, 2)));
+        // The help hint at the end still opens help
+        let hint = help_hint_area(layout.status_bar, &app);
+        assert_eq!(&line.chars().skip(hint.x as usize).take(6).collect::<String>(), "? Help");
+    }
+
+    #[test]
+    fn icmp_banner_sits_above_the_table_until_dismissed() {
+        let mut app = sample_app();
+        app.icmp_unavailable(&scanner::IcmpUnavailable {
+            reason: "Operation not permitted (os error 1)".to_string(),
+            remedy: Some("run: sudo ipscannr".to_string()),
+        });
+        assert_eq!(app.event_log.count(Some(Severity::Error)), 1);
+        let rendered = buffer_text(&render(&app, 120, 40));
+        let line: String = rendered.chars().skip(3 * 120).take(120).collect();
+        assert!(
+            line.starts_with("⚠ ICMP needs privileges, using TCP probes only: run: sudo ipscannr  (Esc dismisses)"),
+            "{}",
+            line
+        );
+
+        let size = Rect::new(0, 0, 120, 40);
+        let left = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse_event(click(left, 10, 3), &mut app, size, 0).unwrap();
+        assert_eq!(app.banner, None);
+        assert_eq!(app_layout(&app, size).hosts_table, AppLayout::new(size, 55, false).hosts_table);
+
+        // Esc on the table dismisses it too; Esc elsewhere only returns to the table
+        app.banner = Some("ICMP unavailable".to_string());
+        app.focus = Focus::DetailsPane;
+        app.handle_action(input::Action::Cancel).unwrap();
+        assert!(app.banner.is_some());
+        app.handle_action(input::Action::Cancel).unwrap();
+        assert_eq!(app.banner, None);
+    }
+
+    #[test]
+    fn second_click_on_range_edits_at_column() {
+        let mut app = sample_app();
+        app.range_input = "10.0.0.0/24".to_string();
+        app.focus = Focus::HostsTable;
+        let size = Rect::new(0, 0, 120, 40);
+        let range = app_layout(&app, size).range_box;
+        let left = MouseEventKind::Down(MouseButton::Left);
+        // Fourth character: the second '.'
+        let at = click(left, range.x + 1 + 4, range.y + 1);
+
+        handle_mouse_event(at, &mut app, size, 0).unwrap();
+        assert_eq!(app.focus, Focus::RangeInput);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        handle_mouse_event(at, &mut app, size, 0).unwrap();
+        assert_eq!(app.input_mode, InputMode::EditingRange);
+        assert_eq!(app.range_cursor, 4);
+
+        // Past the end of the text: cursor goes to the end
+        handle_mouse_event(click(left, range.x + 40, range.y + 1), &mut app, size, 0).unwrap();
+        assert_eq!(app.range_cursor, app.range_input.len());
+    }
+
+    #[test]
+    fn clicking_status_box_swaps_progress_and_summary() {
+        let mut app = sample_app();
+        app.scan_state = app::ScanState::Completed;
+        let size = Rect::new(0, 0, 120, 40);
+        let status = app_layout(&app, size).status_box;
+        assert!(buffer_text(&render(&app, 120, 40)).contains("3 hosts (2 online)"));
+
+        let left = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse_event(click(left, status.x + 2, status.y + 1), &mut app, size, 0).unwrap();
+        assert!(app.status_swapped);
+        assert!(!buffer_text(&render(&app, 120, 40)).contains("3 hosts (2 online)"));
+    }
+
+    #[test]
+    fn scan_start_error_is_shown_on_range_box() {
+        let mut app = sample_app();
+        app.input_error = Some("Invalid IP address: 10.0.0.300".to_string());
+        let buffer = render(&app, 120, 40);
+        assert!(buffer_text(&buffer).contains("Invalid IP address: 10.0.0.300"));
+        let range = app_layout(&app, Rect::new(0, 0, 120, 40)).range_box;
+        assert_eq!(buffer[(range.x, range.y)].style().fg, Theme::error().fg);
+    }
+
+    #[test]
+    fn conflicts_are_marked_and_listed() {
+        // Every sample host shares one MAC
+        let mut app = sample_app();
+        app.conflicts = conflict::detect(&app.hosts, &[]);
+        app.conflicted_ips = app.hosts.iter().map(|h| h.ip).collect();
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("● ⚠"));
+        assert!(text.contains("Same MAC as 192.168.1.2, 192.168.1.3"));
+
+        app.input_mode = InputMode::Conflicts;
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("MAC AA:BB:CC:DD:EE:FF answers on 3 addresses"));
+        assert!(text.contains("+ 192.168.1.3"));
+    }
+
+    #[test]
+    fn details_show_discovery_probe() {
+        let mut app = sample_app();
+        app.show_details = true;
+        app.hosts[0].status = HostStatus::OnlineNoIcmp;
+        app.hosts[0].probe = Some(Probe {
+            port: Some(445),
+            attempt: 2,
+            suspect: false,
+        });
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Discovered: TCP:445 (ICMP blocked), attempt 2"), "{}", text);
+        assert!(!text.contains("Suspect:"));
+
+        app.hosts[0].probe = Some(Probe {
+            port: Some(445),
+            attempt: 1,
+            suspect: true,
+        });
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Suspect:  possible RST proxy"), "{}", text);
+
+        app.hosts[0].status = HostStatus::Online;
+        app.hosts[0].probe = Some(Probe {
+            port: None,
+            attempt: 1,
+            suspect: false,
+        });
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Discovered: ICMP echo"));
+        assert!(!text.contains("attempt"));
+    }
+
+    #[test]
+    fn details_name_the_port_set_and_its_age() {
+        let mut app = sample_app();
+        app.show_details = true;
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Open Ports:"), "{}", text);
+
+        app.hosts[0].ports_scanned_at = Some(cache::now_secs() - 2 * 3600);
+        app.hosts[0].ports_spec = Some("common".to_string());
+        app.hosts[0].open_ports.clear();
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Ports (common, 2h ago):"), "{}", text);
+        assert!(text.contains("No open ports found"), "{}", text);
+    }
+
+    #[test]
+    fn details_list_every_name_by_source() {
+        let mut app = sample_app();
+        app.show_details = true;
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Hostname: host-1.lan"), "{}", text);
+
+        app.hosts[0].names = Names {
+            mdns: Some("Living-Room-TV.local".to_string()),
+            netbios: Some("LGTV".to_string()),
+            ptr: Some("dhcp-42.lan".to_string()),
+            ..Names::default()
+        };
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("mDNS:     Living-Room-TV.local"), "{}", text);
+        assert!(text.contains("NetBIOS:  LGTV"), "{}", text);
+        assert!(text.contains("PTR:      dhcp-42.lan"), "{}", text);
+        assert!(!text.contains("Hostname:"), "{}", text);
+    }
+
+    #[test]
+    fn summary_overlay_shows_breakdown() {
+        let mut app = sample_app();
+        app.handle_action(input::Action::ShowSummary).unwrap();
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("Scan Summary"));
+        assert!(text.contains("Online per /24"));
+        assert!(text.contains("192.168.1.0/24"));
+    }
+
+    #[test]
+    fn output_hint_only_offers_stop_while_running() {
+        let mut app = sample_app();
+        app.overlay_title = "Continuous Ping".to_string();
+        app.input_mode = InputMode::OutputOverlay;
+        let (tx, _rx) = mpsc::channel(1);
+        app.overlay_cancel_tx = Some(tx);
+        assert!(buffer_text(&render(&app, 120, 40)).contains("[X] Stop   [Esc/Q] Stop & close"));
+
+        app.overlay_cancel_tx = None;
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("[Esc/Q] Close"));
+        assert!(!text.contains("[X] Stop"));
+    }
+
+    #[test]
+    fn paused_scan_shows_frozen_progress_and_resume_hint() {
+        for (compat, pause) in [(false, "⏸ Paused at 43%"), (true, "|| Paused at 43%")] {
+            let mut app = sample_app();
+            app.compat = compat;
+            app.scan_state = app::ScanState::Paused;
+            app.scan_total = 100;
+            app.scan_completed = 43;
+            let text = buffer_text(&render(&app, 120, 40));
+            assert!(text.contains(pause), "compat={compat}");
+            assert!(text.contains("[Space] resume"));
+
+            // The box grows to fit the label and a usable bar
+            let status = app_layout(&app, Rect::new(0, 0, 120, 40)).status_box;
+            assert!(status.width > 35);
+        }
+    }
+
+    #[test]
+    fn wheel_scrolls_table_without_moving_selection() {
+        let mut app = sample_app();
+        app.hosts = (1..=100).map(|i| sample_host(i, true)).collect();
+        app.update_filtered_hosts();
+        app.focus = Focus::HostsTable;
+        let size = Rect::new(0, 0, 120, 40);
+        let (col, row) = table_row(&app, size, 0);
+        let visible = ScanTable::visible_rows(AppLayout::new(size, 55, false).hosts_table);
+
+        let (up, down) = (MouseEventKind::ScrollUp, MouseEventKind::ScrollDown);
+        handle_mouse_event(click(down, col, row), &mut app, size, 0).unwrap();
+        handle_mouse_event(click(down, col, row), &mut app, size, 3).unwrap();
+        assert_eq!(app.table_scroll, Some(6));
+        assert_eq!(app.table_state.selected(), Some(0));
+        let text = buffer_text(&render(&app, 120, 40));
+        let first_row: String = text.chars().skip(row as usize * 120).take(60).collect();
+        assert!(first_row.contains("192.168.1.7 "), "{}", first_row);
+
+        // Clamped at both ends, never wrapping
+        handle_mouse_event(click(up, col, row), &mut app, size, 1).unwrap();
+        assert_eq!(app.table_scroll, Some(0));
+        handle_mouse_event(click(down, col, row), &mut app, size, 99).unwrap();
+        assert_eq!(app.table_scroll, Some(100 - visible));
+
+        // Keyboard navigation brings the selection back into view
+        app.handle_action(input::Action::NavigateDown).unwrap();
+        assert_eq!(app.table_scroll, None);
+        assert_eq!(app.table_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn drag_selects_crossed_rows_once() {
+        let mut app = sample_app();
+        app.hosts = (1..=10).map(|i| sample_host(i, true)).collect();
+        app.update_filtered_hosts();
+        app.selected_hosts.clear();
+        let size = Rect::new(0, 0, 120, 40);
+        let (col, top) = table_row(&app, size, 1);
+        let drag = MouseEventKind::Drag(MouseButton::Left);
+
+        let down = MouseEventKind::Down(MouseButton::Left);
+        let up = MouseEventKind::Up(MouseButton::Left);
+        handle_mouse_event(click(down, col, top), &mut app, size, 0).unwrap();
+        assert!(app.selected_hosts.is_empty());
+        // Fast drag from row 1 straight to row 4, then back over row 2
+        handle_mouse_event(click(drag, col, top + 3), &mut app, size, 0).unwrap();
+        handle_mouse_event(click(drag, col, top + 1), &mut app, size, 0).unwrap();
+        let selected: Vec<u8> = (1..=10)
+            .filter(|&i| app.selected_hosts.contains(&Ipv4Addr::new(192, 168, 1, i)))
+            .collect();
+        assert_eq!(selected, vec![2, 3, 4, 5]);
+        assert!(buffer_text(&render(&app, 120, 40)).contains("+4]"));
+
+        handle_mouse_event(click(up, col, top + 1), &mut app, size, 0).unwrap();
+        assert!(app.drag.is_none());
+        assert_eq!(app.selected_hosts.len(), 4);
+    }
+
+    /// Test backend that counts flushed frames
+    struct CountingBackend {
+        inner: TestBackend,
+        frames: usize,
+    }
+
+    impl ratatui::backend::Backend for CountingBackend {
+        fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+        where
+            I: Iterator<Item = (u16, u16, &'a ratatui::buffer::Cell)>,
+        {
+            self.inner.draw(content)
+        }
+        fn hide_cursor(&mut self) -> io::Result<()> {
+            self.inner.hide_cursor()
+        }
+        fn show_cursor(&mut self) -> io::Result<()> {
+            self.inner.show_cursor()
+        }
+        fn get_cursor_position(&mut self) -> io::Result<ratatui::layout::Position> {
+            self.inner.get_cursor_position()
+        }
+        fn set_cursor_position<P: Into<ratatui::layout::Position>>(
+            &mut self,
+            position: P,
+        ) -> io::Result<()> {
+            self.inner.set_cursor_position(position)
+        }
+        fn clear(&mut self) -> io::Result<()> {
+            self.inner.clear()
+        }
+        fn size(&self) -> io::Result<ratatui::layout::Size> {
+            self.inner.size()
+        }
+        fn window_size(&mut self) -> io::Result<ratatui::backend::WindowSize> {
+            self.inner.window_size()
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.frames += 1;
+            self.inner.flush()
+        }
+    }
+
+    #[test]
+    fn idle_ticks_do_not_redraw() {
+        let mut app = sample_app();
+        let backend = CountingBackend {
+            inner: TestBackend::new(120, 40),
+            frames: 0,
+        };
+        let mut terminal = Terminal::new(backend).expect("test terminal");
+        let (mut area, mut offset) = (Rect::default(), 0);
+        let mut tick = |app: &mut App, terminal: &mut Terminal<CountingBackend>| {
+            app.tick_animation();
+            render_if_dirty(terminal, app, &mut area, &mut offset).unwrap();
+        };
+
+        for _ in 0..20 {
+            tick(&mut app, &mut terminal);
+        }
+        assert!(!app.is_animating());
+        assert_eq!(terminal.backend().frames, 1, "only the initial frame");
+
+        // Input marks the frame dirty once
+        app.handle_action(input::Action::NavigateDown).unwrap();
+        app.dirty = true;
+        tick(&mut app, &mut terminal);
+        tick(&mut app, &mut terminal);
+        assert_eq!(terminal.backend().frames, 2);
+
+        // A running scan animates every tick
+        app.scan_state = app::ScanState::Scanning;
+        for _ in 0..5 {
+            tick(&mut app, &mut terminal);
+        }
+        assert_eq!(terminal.backend().frames, 7);
+    }
+
+    #[tokio::test]
+    async fn instant_scan_replies_are_handled_in_batches() {
+        const HOSTS: u32 = 10_000;
+        let mut app = sample_app();
+        app.hosts.clear();
+        app.update_filtered_hosts();
+        app.scan_state = app::ScanState::Scanning;
+        app.scan_total = HOSTS as usize;
+
+        // Probes that resolve instantly: the producer is never the bottleneck
+        let (tx, mut rx) = mpsc::channel(app::SCAN_EVENT_CAPACITY);
+        tokio::spawn(async move {
+            for i in 0..HOSTS {
+                let mut host = sample_host(1, i % 2 == 0);
+                host.ip = Ipv4Addr::from(0x0A00_0000 + i);
+                tx.send(ScanEvent::HostDiscovered(Box::new(host))).await.unwrap();
+            }
+            // Dropping the sender ends the scan (ScanComplete would write the cache)
+        });
+
+        let start = std::time::Instant::now();
+        let mut frames = 0;
+        while let Some(first) = rx.recv().await {
+            let open = app.handle_scan_events(first, &mut rx);
+            // One redraw per batch, as in the main loop
+            render(&app, 120, 40);
+            frames += 1;
+            if !open {
+                break;
+            }
+        }
+        assert_eq!(app.hosts.len(), HOSTS as usize);
+        assert!(frames < 100, "{} redraws for {} hosts", frames, HOSTS);
+        assert!(start.elapsed() < Duration::from_secs(3), "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn selecting_a_whole_slash_16_stays_fast() {
+        let mut app = sample_app();
+        app.hosts = (0..65_536u32)
+            .map(|i| {
+                let mut host = sample_host(1, i % 3 == 0);
+                host.ip = Ipv4Addr::from(0x0A00_0000 + i);
+                host
+            })
+            .collect();
+        app.update_filtered_hosts();
+        app.selected_hosts = app.hosts.iter().map(|h| h.ip).collect();
+
+        let start = std::time::Instant::now();
+        for _ in 0..5 {
+            render(&app, 120, 40);
+        }
+        // Opening the dialog prunes the selection against the results
+        app.handle_action(input::Action::Export).unwrap();
+        assert_eq!(app.input_mode, InputMode::Exporting);
+        for _ in 0..5 {
+            render(&app, 120, 40);
+        }
+        assert_eq!(app.export_scope(), "65536 selected host(s)");
+        let text = buffer_text(&render(&app, 120, 40));
+        assert!(text.contains("65536 selected host(s)"), "{}", text);
+        assert!(start.elapsed() < Duration::from_secs(3), "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn every_overlay_renders_at_pathological_sizes() {
+        let modes = [
+            InputMode::Normal,
+            InputMode::EditingRange,
+            InputMode::EditingPorts,
+            InputMode::Help,
+            InputMode::Exporting,
+            InputMode::OutputOverlay,
+            InputMode::EventLog,
+            InputMode::Conflicts,
+            InputMode::Summary,
+            InputMode::WakeReport,
+            InputMode::ConfirmQuit,
+            InputMode::ConfirmForget,
+            InputMode::ConfirmNewScan,
+            InputMode::ConfirmLargeScan,
+            InputMode::ContextMenu,
+            InputMode::ColumnPicker,
+            InputMode::Settings,
+        ];
+        for (width, height) in [(20u16, 8u16), (8, 3), (1, 1), (200, 5), (30, 100), (60, 15), (61, 100)] {
+            for mode in modes {
+                let mut app = sample_app();
+                app.input_mode = mode;
+                app.show_keybindings = true;
+                app.notify(Severity::Info, "Copied 192.168.1.1");
+                app.overlay_title = "Continuous Ping".to_string();
+                for i in 0..50 {
+                    app.push_overlay_line(format!("reply {}", i));
+                }
+                app.open_context_menu(width.saturating_sub(1), height.saturating_sub(1));
+                app.input_mode = mode;
+                render(&app, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn settings_overlay_lists_tunables_and_edits_in_place() {
+        let mut app = sample_app();
+        app.handle_action(input::Action::OpenSettings).unwrap();
+        let text = buffer_text(&render(&app, 100, 40));
+        assert!(text.contains("Ping timeout        300 ms"));
+        assert!(text.contains("Resolve hostnames   on"));
+
+        app.handle_action(input::Action::Character('5')).unwrap();
+        app.handle_action(input::Action::Select).unwrap();
+        let text = buffer_text(&render(&app, 100, 40));
+        assert!(text.contains("Ping timeout must be 50-10000 ms"));
+        for (width, height) in [(20, 8), (44, 19)] {
+            render(&app, width, height);
+        }
+    }
+
+    #[test]
+    fn details_mark_ports_opened_and_closed_since_the_last_scan() {
+        let mut app = sample_app();
+        app.show_details = true;
+        app.hosts[0].ports_scanned = true;
+        app.hosts[0].open_ports = vec![22, 8080];
+        app.hosts[0].previous_ports = Some(vec![22, 23]);
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("+  8080 "), "{}", text);
+        assert!(text.contains("−    23 telnet"), "{}", text);
+        assert!(text.contains("     22 ssh"), "{}", text);
+
+        app.compat = true;
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("-    23 telnet"), "{}", text);
+    }
+
+    #[test]
+    fn details_say_why_an_offline_host_did_not_answer() {
+        use crate::scanner::ProbeError;
+
+        let mut app = sample_app();
+        app.show_details = true;
+        app.table_state.select(Some(1));
+        app.hosts[1].probe_error = Some(ProbeError::Timeout);
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Status:   Offline "), "{}", text);
+        assert!(!text.contains("timeout"), "{}", text);
+
+        app.hosts[1].probe_error = Some(ProbeError::NetworkUnreachable);
+        let text = buffer_text(&render(&app, 140, 40));
+        assert!(text.contains("Offline — network unreachable"), "{}", text);
+    }
+
+    #[test]
+    fn small_terminal_asks_for_more_room() {
+        let mut app = sample_app();
+        for (width, height) in [(30, 8), (59, 14), (59, 40), (200, 14)] {
+            let text = buffer_text(&render(&app, width, height));
+            assert!(text.contains("too small"), "{}x{}: {}", width, height, text);
+            assert!(text.contains("60×15"), "{}x{}: {}", width, height, text);
+            assert!(!text.contains("192.168.1.1"), "{}x{}: {}", width, height, text);
+        }
+        let text = buffer_text(&render(&app, 59, 14));
+        assert!(text.contains("Terminal too small — need at least 60×15"), "{}", text);
+        assert!(text.contains("now 59×14"), "{}", text);
+
+        app.compat = true;
+        let text = buffer_text(&render(&app, 59, 14));
+        assert!(text.contains("Terminal too small - need at least 60x15"), "{}", text);
+
+        // Resized back: the UI returns
+        let text = buffer_text(&render(&app, 60, 15));
+        assert!(!text.contains("too small"), "{}", text);
+        assert!(text.contains("192.168.1.1"), "{}", text);
+    }
+
+    #[test]
+    fn status_bar_offers_f1_without_ctrl_detection() {
+        let mut app = sample_app();
+        app.selected_hosts.clear();
+        let text = buffer_text(&render(&app, 160, 30));
+        assert!(text.contains("F1 shortcuts  |  ? Help"), "{}", text);
+        assert!(!text.contains(" Shortcuts "));
+
+        app.handle_action(input::Action::ToggleKeybindings).unwrap();
+        let text = buffer_text(&render(&app, 160, 30));
+        assert!(text.contains(" Shortcuts "), "{}", text);
+
+        app.keybindings_pinned = None;
+        app.ctrl_detected = true;
+        let text = buffer_text(&render(&app, 160, 30));
+        assert!(text.contains("^ Ctrl  shortcuts  |  ? Help"), "{}", text);
+    }
+
+    #[test]
+    fn ctrl_popup_describes_enter_action() {
+        let mut app = sample_app();
+        app.focus = Focus::HostsTable;
+        app.show_details = true;
+        app.show_keybindings = true;
+        let text = buffer_text(&render(&app, 160, 30));
+        assert!(text.contains("[Enter] Details, then port scan or ping"), "{}", text);
+
+        app.config.enter_action = HostAction::Tracert;
+        app.focus = Focus::DetailsPane;
+        let text = buffer_text(&render(&app, 160, 30));
+        assert!(text.contains("[Enter] Run tracert"), "{}", text);
+    }
+
+    #[test]
+    fn overlay_scroll_is_clamped_on_resize() {
+        let mut app = sample_app();
+        app.input_mode = InputMode::OutputOverlay;
+        for i in 0..50 {
+            app.push_overlay_line(format!("reply {}", i));
+        }
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).expect("test terminal");
+        let (mut area, mut offset) = (Rect::default(), 0);
+        render_if_dirty(&mut terminal, &mut app, &mut area, &mut offset).unwrap();
+        let max = app.overlay_max_scroll();
+        assert!(max > 0 && max < 49);
+        assert_eq!(app.overlay_scroll, max);
+
+        // Scrolling down past the end doesn't build up a hidden offset
+        for _ in 0..10 {
+            app.handle_action(input::Action::NavigateDown).unwrap();
+        }
+        assert_eq!(app.overlay_scroll, max);
+        app.handle_action(input::Action::NavigateUp).unwrap();
+        assert_eq!(app.overlay_scroll, max - 1);
+
+        // A taller terminal shows more lines: the offset is pulled back
+        terminal.backend_mut().resize(120, 60);
+        app.dirty = true;
+        render_if_dirty(&mut terminal, &mut app, &mut area, &mut offset).unwrap();
+        assert_eq!(area.height, 60);
+        assert!(app.overlay_max_scroll() < max - 1);
+        assert_eq!(app.overlay_scroll, app.overlay_max_scroll());
+        assert!(buffer_text(terminal.backend().buffer()).contains("[Home/End] Top/Bottom"));
+    }
+
+    #[test]
+    fn click_after_filter_shrinks_list_selects_a_real_row() {
+        let mut app = sample_app();
+        app.hosts = (1..=100).map(|i| sample_host(i, i % 10 == 0)).collect();
+        app.update_filtered_hosts();
+        app.table_scroll = Some(80);
+        let size = Rect::new(0, 0, 120, 40);
+        let (col, row) = table_row(&app, size, 5);
+
+        // The frame on screen was drawn at offset 80; the filter leaves 10 hosts
+        app.handle_action(input::Action::ToggleFilter).unwrap();
+        assert_eq!(app.table_scroll, None);
+        let left = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse_event(click(left, col, row), &mut app, size, 80).unwrap();
+        assert!(app.table_state.selected().is_some_and(|s| s < app.filtered_hosts.len()));
+    }
+
+    #[test]
+    fn panic_hook_restores_before_reporting() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::Arc;
+
+        let restored = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&restored);
+        install_panic_hook(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let result = std::panic::catch_unwind(|| panic!("deliberate test panic"));
+        // Put the default hook back so later panics report normally
+        let _ = std::panic::take_hook();
+
+        assert!(result.is_err());
+        assert!(restored.load(Ordering::SeqCst) >= 1);
+    }
+
+    #[test]
+    fn continuous_ping_shows_the_payload_size() {
+        let mut app = sample_app();
+        app.config.ping.payload_size = 1472;
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let _rx = start_continuous_ping(Ipv4Addr::new(192, 168, 1, 5), &mut app);
+        assert_eq!(app.overlay_title, "Continuous Ping — 192.168.1.5 (1472 bytes)");
+        cancel_existing_overlay_task(&mut app);
+
+        let mut result = PingResult {
+            ip: Ipv4Addr::new(192, 168, 1, 5),
+            is_alive: true,
+            rtt: Some(Duration::from_millis(4)),
+            method: PingMethod::Icmp,
+            status: HostStatus::Online,
+            probe: None,
+            error: None,
+        };
+        assert_eq!(
+            ping_reply_line(3, &result, 1472),
+            "[3] Reply from 192.168.1.5: bytes=1472 time=4ms"
+        );
+        result.method = PingMethod::Tcp;
+        assert_eq!(ping_reply_line(3, &result, 1472), "[3] Reply from 192.168.1.5: time=4ms (TCP)");
+        result.is_alive = false;
+        assert_eq!(ping_reply_line(4, &result, 1472), "[4] Request timed out for 192.168.1.5");
+    }
+
+    #[test]
+    fn range_box_counts_addresses_and_large_scans_ask() {
+        let mut app = sample_app();
+        app.range_input = "10.0.0.0/14".to_string();
+        app.range_size = Some(262_144);
+        let text = buffer_text(&render(&app, 120, 30));
+        assert!(text.contains("262144 addresses"), "{}", text);
+
+        app.input_mode = InputMode::ConfirmLargeScan;
+        let text = buffer_text(&render(&app, 120, 30));
+        assert!(text.contains("Scan 262144 addresses in 10.0.0.0/14?"), "{}", text);
+        assert!(text.contains("about 13 min"), "{}", text);
+    }
 }
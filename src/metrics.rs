@@ -0,0 +1,137 @@
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use crate::app::HostInfo;
+use crate::export::rtt_ms;
+
+/// `hosts` in the Prometheus text exposition format: online and total
+/// counts, and an up gauge and RTT per host labelled with its IP and
+/// hostname, by ascending IP so the output is stable between sweeps
+pub fn render(hosts: &[HostInfo]) -> String {
+    let mut sorted: Vec<&HostInfo> = hosts.iter().collect();
+    sorted.sort_by_key(|h| h.ip);
+    let online = hosts.iter().filter(|h| h.is_alive).count();
+
+    let mut out = String::new();
+    heading(&mut out, "ipscannr_hosts_online", "Hosts that answered the last scan");
+    let _ = writeln!(out, "ipscannr_hosts_online {}", online);
+    heading(&mut out, "ipscannr_hosts_total", "Hosts in the scan results");
+    let _ = writeln!(out, "ipscannr_hosts_total {}", hosts.len());
+
+    heading(&mut out, "ipscannr_host_up", "1 if the host answered the last scan, 0 if not");
+    for host in &sorted {
+        let _ = writeln!(out, "ipscannr_host_up{} {}", labels(host), u8::from(host.is_alive));
+    }
+    heading(
+        &mut out,
+        "ipscannr_host_rtt_ms",
+        "Round-trip time of the probe that answered, in milliseconds",
+    );
+    for host in sorted.iter().filter(|h| h.is_alive) {
+        if let Some(rtt) = host.rtt {
+            let _ = writeln!(out, "ipscannr_host_rtt_ms{} {}", labels(host), rtt_ms(rtt));
+        }
+    }
+    out
+}
+
+fn heading(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+}
+
+fn labels(host: &HostInfo) -> String {
+    format!(
+        "{{ip=\"{}\",hostname=\"{}\"}}",
+        host.ip,
+        escape(host.hostname.as_deref().unwrap_or(""))
+    )
+}
+
+/// A label value with backslashes, double quotes and line feeds escaped
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Replace `path` with `text` through a temporary file renamed over it, so
+/// the node exporter's textfile collector never reads half a file
+pub fn write_textfile(path: &Path, text: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, text)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn host(last_octet: u8, rtt_us: Option<u64>, hostname: Option<&str>) -> HostInfo {
+        HostInfo {
+            rtt: rtt_us.map(Duration::from_micros),
+            hostname: hostname.map(str::to_string),
+            ..HostInfo::pinged(Ipv4Addr::new(192, 168, 1, last_octet), rtt_us.is_some())
+        }
+    }
+
+    #[test]
+    fn exposition_snapshot() {
+        let hosts = [
+            host(20, None, Some("printer.lan")),
+            host(5, Some(412), Some("nas.lan")),
+            host(1, Some(23_000), None),
+        ];
+        assert_eq!(
+            render(&hosts),
+            r#"# HELP ipscannr_hosts_online Hosts that answered the last scan
+# TYPE ipscannr_hosts_online gauge
+ipscannr_hosts_online 2
+# HELP ipscannr_hosts_total Hosts in the scan results
+# TYPE ipscannr_hosts_total gauge
+ipscannr_hosts_total 3
+# HELP ipscannr_host_up 1 if the host answered the last scan, 0 if not
+# TYPE ipscannr_host_up gauge
+ipscannr_host_up{ip="192.168.1.1",hostname=""} 1
+ipscannr_host_up{ip="192.168.1.5",hostname="nas.lan"} 1
+ipscannr_host_up{ip="192.168.1.20",hostname="printer.lan"} 0
+# HELP ipscannr_host_rtt_ms Round-trip time of the probe that answered, in milliseconds
+# TYPE ipscannr_host_rtt_ms gauge
+ipscannr_host_rtt_ms{ip="192.168.1.1",hostname=""} 23
+ipscannr_host_rtt_ms{ip="192.168.1.5",hostname="nas.lan"} 0.412
+"#
+        );
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape(r#"Bob's "lab" \ box"#), r#"Bob's \"lab\" \\ box"#);
+        assert_eq!(escape("two\nlines"), r"two\nlines");
+        let text = render(&[host(7, Some(1_000), Some("a\"b"))]);
+        assert!(text.contains(r#"ipscannr_host_up{ip="192.168.1.7",hostname="a\"b"} 1"#), "{}", text);
+        assert_eq!(render(&[]).lines().filter(|l| !l.starts_with('#')).count(), 2);
+    }
+
+    #[test]
+    fn textfile_is_replaced_whole() {
+        let path = std::env::temp_dir().join("ipscannr_metrics_test.prom");
+        write_textfile(&path, "old\n").unwrap();
+        write_textfile(&path, "ipscannr_hosts_total 3\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ipscannr_hosts_total 3\n");
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        assert!(!Path::new(&tmp).exists());
+        let _ = std::fs::remove_file(&path);
+    }
+}
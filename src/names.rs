@@ -0,0 +1,147 @@
+/// Where a host name came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NameSource {
+    /// Set by hand for the address
+    Alias,
+    /// Advertised over mDNS / SSDP (instance, host or friendly name)
+    Mdns,
+    /// NetBIOS computer name
+    Netbios,
+    /// Reverse DNS (PTR record)
+    Ptr,
+}
+
+impl NameSource {
+    /// Which name the table shows when a host has several (`--name-order`)
+    pub const DEFAULT_ORDER: [NameSource; 4] = [
+        NameSource::Alias,
+        NameSource::Mdns,
+        NameSource::Netbios,
+        NameSource::Ptr,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NameSource::Alias => "Alias",
+            NameSource::Mdns => "mDNS",
+            NameSource::Netbios => "NetBIOS",
+            NameSource::Ptr => "PTR",
+        }
+    }
+}
+
+/// A host's names, one per source. A host can legitimately have several
+/// (PTR "dhcp-42.lan", mDNS "Living-Room-TV.local", NetBIOS "LGTV"); every
+/// merge goes through here so they can't overwrite each other.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Names {
+    pub alias: Option<String>,
+    pub mdns: Option<String>,
+    pub netbios: Option<String>,
+    pub ptr: Option<String>,
+}
+
+impl Names {
+    pub fn get(&self, source: NameSource) -> Option<&str> {
+        match source {
+            NameSource::Alias => self.alias.as_deref(),
+            NameSource::Mdns => self.mdns.as_deref(),
+            NameSource::Netbios => self.netbios.as_deref(),
+            NameSource::Ptr => self.ptr.as_deref(),
+        }
+    }
+
+    fn slot(&mut self, source: NameSource) -> &mut Option<String> {
+        match source {
+            NameSource::Alias => &mut self.alias,
+            NameSource::Mdns => &mut self.mdns,
+            NameSource::Netbios => &mut self.netbios,
+            NameSource::Ptr => &mut self.ptr,
+        }
+    }
+
+    /// Record a fresh answer from `source`, replacing its previous name
+    pub fn set(&mut self, source: NameSource, name: &str) {
+        *self.slot(source) = Some(name.to_string());
+    }
+
+    /// Record `name` only if `source` has none yet: the first name a device
+    /// advertises wins over later ones
+    pub fn set_if_missing(&mut self, source: NameSource, name: &str) {
+        self.slot(source).get_or_insert_with(|| name.to_string());
+    }
+
+    /// Keep `earlier`'s names for the sources this has none from, e.g. when
+    /// a rescan finds the host again before its lookups finish
+    pub fn fill_from(&mut self, earlier: Names) {
+        for source in NameSource::DEFAULT_ORDER {
+            if self.get(source).is_none() {
+                *self.slot(source) = earlier.get(source).map(str::to_string);
+            }
+        }
+    }
+
+    /// The name to show: the first source in `order` that has one
+    pub fn display(&self, order: &[NameSource]) -> Option<&str> {
+        order.iter().find_map(|&source| self.get(source))
+    }
+
+    /// `(source, name)` for every known name, in `order`, then any sources
+    /// `order` leaves out
+    pub fn labeled(&self, order: &[NameSource]) -> Vec<(NameSource, &str)> {
+        let rest = NameSource::DEFAULT_ORDER.into_iter().filter(|s| !order.contains(s));
+        order
+            .iter()
+            .copied()
+            .chain(rest)
+            .filter_map(|source| Some((source, self.get(source)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Names {
+        Names {
+            alias: None,
+            mdns: Some("Living-Room-TV.local".to_string()),
+            netbios: Some("LGTV".to_string()),
+            ptr: Some("dhcp-42.lan".to_string()),
+        }
+    }
+
+    #[test]
+    fn display_follows_the_order() {
+        let names = names();
+        assert_eq!(names.display(&NameSource::DEFAULT_ORDER), Some("Living-Room-TV.local"));
+        assert_eq!(names.display(&[NameSource::Ptr, NameSource::Mdns]), Some("dhcp-42.lan"));
+        assert_eq!(names.display(&[NameSource::Alias]), None);
+        assert_eq!(Names::default().display(&NameSource::DEFAULT_ORDER), None);
+
+        // Sources left out of the order are still listed, last
+        let labeled: Vec<NameSource> = names.labeled(&[NameSource::Ptr]).iter().map(|l| l.0).collect();
+        assert_eq!(labeled, [NameSource::Ptr, NameSource::Mdns, NameSource::Netbios]);
+    }
+
+    #[test]
+    fn merges_keep_every_source_apart() {
+        let mut names = names();
+        names.set(NameSource::Ptr, "tv.lan");
+        names.set_if_missing(NameSource::Mdns, "Other-TV.local");
+        names.set_if_missing(NameSource::Alias, "Lounge TV");
+        assert_eq!(names.ptr.as_deref(), Some("tv.lan"));
+        assert_eq!(names.mdns.as_deref(), Some("Living-Room-TV.local"));
+        assert_eq!(names.alias.as_deref(), Some("Lounge TV"));
+
+        let mut fresh = Names {
+            ptr: Some("dhcp-43.lan".to_string()),
+            ..Names::default()
+        };
+        fresh.fill_from(names);
+        assert_eq!(fresh.ptr.as_deref(), Some("dhcp-43.lan"));
+        assert_eq!(fresh.netbios.as_deref(), Some("LGTV"));
+        assert_eq!(fresh.alias.as_deref(), Some("Lounge TV"));
+    }
+}
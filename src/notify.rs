@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How a notification is styled and how long it stays on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Success,
+    Error,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "Info",
+            Severity::Success => "Done",
+            Severity::Error => "Error",
+        }
+    }
+
+    /// Display time; errors linger a little longer so they can be read
+    pub fn duration(self) -> Duration {
+        match self {
+            Severity::Info | Severity::Success => Duration::from_secs(3),
+            Severity::Error => Duration::from_secs(6),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub severity: Severity,
+    pub text: String,
+    /// Set when the notification reaches the front of the queue
+    shown_at: Option<Instant>,
+}
+
+/// FIFO of toast messages. Only the front one is displayed; its timer starts
+/// on the first tick after it becomes visible.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    queue: VecDeque<Notification>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        self.queue.push_back(Notification {
+            severity,
+            text: text.into(),
+            shown_at: None,
+        });
+    }
+
+    /// Notification currently on screen
+    pub fn current(&self) -> Option<&Notification> {
+        self.queue.front()
+    }
+
+    /// Number of messages waiting behind the current one
+    pub fn pending(&self) -> usize {
+        self.queue.len().saturating_sub(1)
+    }
+
+    /// Start the front notification's timer and drop it once expired
+    pub fn tick(&mut self, now: Instant) {
+        let Some(front) = self.queue.front_mut() else {
+            return;
+        };
+        match front.shown_at {
+            None => front.shown_at = Some(now),
+            Some(shown) if now.duration_since(shown) >= front.severity.duration() => {
+                self.queue.pop_front();
+                if let Some(next) = self.queue.front_mut() {
+                    next.shown_at = Some(now);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_expire_in_order() {
+        let start = Instant::now();
+        let mut n = Notifications::default();
+        n.push(Severity::Success, "first");
+        n.push(Severity::Info, "second");
+        assert_eq!(n.pending(), 1);
+
+        n.tick(start);
+        n.tick(start + Duration::from_secs(2));
+        assert_eq!(n.current().unwrap().text, "first");
+
+        n.tick(start + Duration::from_secs(3));
+        assert_eq!(n.current().unwrap().text, "second");
+        n.tick(start + Duration::from_secs(5));
+        assert_eq!(n.current().unwrap().text, "second");
+        n.tick(start + Duration::from_secs(6));
+        assert!(n.current().is_none());
+    }
+
+    #[test]
+    fn errors_persist_longer() {
+        let start = Instant::now();
+        let mut n = Notifications::default();
+        n.push(Severity::Error, "boom");
+        n.tick(start);
+        n.tick(start + Severity::Success.duration());
+        assert_eq!(n.current().unwrap().severity, Severity::Error);
+        n.tick(start + Severity::Error.duration());
+        assert!(n.current().is_none());
+    }
+
+    #[test]
+    fn timer_starts_when_shown() {
+        let start = Instant::now();
+        let mut n = Notifications::default();
+        n.push(Severity::Info, "late");
+        // Queued long before the first tick: still gets its full display time
+        n.tick(start + Duration::from_secs(60));
+        assert!(n.current().is_some());
+    }
+}
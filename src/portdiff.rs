@@ -0,0 +1,71 @@
+use std::net::Ipv4Addr;
+
+use crate::conflict::DiffKind;
+use crate::scanner::get_service_name;
+
+/// Ports a port scan found open that the one before didn't, and the other
+/// way round
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PortDiff {
+    /// Newly open, ascending
+    pub added: Vec<u16>,
+    /// No longer open, ascending
+    pub removed: Vec<u16>,
+}
+
+impl PortDiff {
+    pub fn between(previous: &[u16], current: &[u16]) -> Self {
+        let only_in = |ports: &[u16], other: &[u16]| {
+            let mut only: Vec<u16> = ports.iter().copied().filter(|p| !other.contains(p)).collect();
+            only.sort_unstable();
+            only.dedup();
+            only
+        };
+        Self {
+            added: only_in(current, previous),
+            removed: only_in(previous, current),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// "+8080 -23" for the event log
+    pub fn summary(&self) -> String {
+        let added = self.added.iter().map(|p| format!("+{}", p));
+        let removed = self.removed.iter().map(|p| format!("-{}", p));
+        added.chain(removed).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Diff-style lines for the conflicts overlay
+    pub fn diff_lines(&self, ip: Ipv4Addr) -> Vec<(DiffKind, String)> {
+        let line = |sign: char, port: u16| format!("{} {:5} {}", sign, port, get_service_name(port));
+        let mut lines = vec![(DiffKind::Heading, format!("{} ports changed", ip))];
+        lines.extend(self.added.iter().map(|&p| (DiffKind::Added, line('+', p))));
+        lines.extend(self.removed.iter().map(|&p| (DiffKind::Removed, line('-', p))));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lists_opened_and_closed_ports() {
+        let diff = PortDiff::between(&[443, 22, 23], &[22, 8080, 443, 80]);
+        assert_eq!(diff.added, [80, 8080]);
+        assert_eq!(diff.removed, [23]);
+        assert_eq!(diff.summary(), "+80 +8080 -23");
+
+        let lines = diff.diff_lines(Ipv4Addr::new(192, 168, 1, 5));
+        assert_eq!(lines[0], (DiffKind::Heading, "192.168.1.5 ports changed".to_string()));
+        assert_eq!(lines[1], (DiffKind::Added, "+    80 http".to_string()));
+        assert_eq!(lines[3], (DiffKind::Removed, "-    23 telnet".to_string()));
+
+        assert!(PortDiff::between(&[22, 80], &[80, 22]).is_empty());
+        assert!(PortDiff::between(&[], &[]).is_empty());
+        assert_eq!(PortDiff::between(&[], &[22]).added, [22]);
+    }
+}
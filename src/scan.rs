@@ -0,0 +1,150 @@
+use std::net::Ipv4Addr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::scanner::{
+    get_active_adapters, is_local, resolve_mac, scan_hosts, DnsResolver, HostStatus, IpRange,
+    MacInfo, PingMethod, PingerConfig, PortScanner, PortScannerConfig, Probe,
+};
+
+/// Reverse lookups in flight at once
+const DNS_CONCURRENCY: usize = 50;
+
+/// What [`scan`] does besides pinging
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub ping: PingerConfig,
+    /// Look up each live host's PTR record
+    pub resolve_hostnames: bool,
+    /// Look up each live host's MAC in the ARP table; only hosts on a local
+    /// adapter's subnet have one there
+    pub detect_mac: bool,
+    /// Ports to scan on each live host; `None` skips the port scan
+    pub ports: Option<Vec<u16>>,
+    pub port_scan: PortScannerConfig,
+    /// Report hosts that didn't answer too
+    pub include_offline: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            ping: PingerConfig::default(),
+            resolve_hostnames: true,
+            detect_mac: true,
+            ports: None,
+            port_scan: PortScannerConfig::default(),
+            include_offline: false,
+        }
+    }
+}
+
+/// One host, once its lookups and port scan have finished
+#[derive(Debug, Clone)]
+pub struct HostReport {
+    pub ip: Ipv4Addr,
+    pub is_alive: bool,
+    pub status: HostStatus,
+    pub method: PingMethod,
+    pub rtt: Option<Duration>,
+    /// The probe that answered; `None` for offline hosts
+    pub probe: Option<Probe>,
+    /// PTR name; `None` without one or with `resolve_hostnames` off
+    pub hostname: Option<String>,
+    pub mac: Option<MacInfo>,
+    /// Open ports, ascending; `None` when no port scan ran
+    pub open_ports: Option<Vec<u16>>,
+}
+
+/// Reports from a running [`scan`], in the order hosts finish. Dropping it
+/// stops the scan.
+pub struct HostReports {
+    rx: mpsc::Receiver<HostReport>,
+}
+
+impl HostReports {
+    /// The next report; `None` once every address is done
+    pub async fn next(&mut self) -> Option<HostReport> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for HostReports {
+    type Item = HostReport;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<HostReport>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Ping every address in `range` and report each host with the lookups and
+/// port scan `options` ask for. Must be called inside a Tokio runtime.
+pub fn scan(range: &IpRange, options: ScanOptions) -> HostReports {
+    let (report_tx, report_rx) = mpsc::channel(options.ping.concurrent_limit.max(1));
+    let (ping_tx, mut ping_rx) = mpsc::channel(options.ping.concurrent_limit.max(1));
+    let addresses = range.addresses().to_vec();
+    tokio::spawn(scan_hosts(addresses, options.ping.clone(), ping_tx));
+
+    tokio::spawn(async move {
+        let adapters = if options.detect_mac {
+            tokio::task::spawn_blocking(get_active_adapters).await.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let adapters = Arc::new(adapters);
+        let dns = Arc::new(DnsResolver::new(DNS_CONCURRENCY));
+        let ports = Arc::new(PortScanner::new(options.port_scan.clone()));
+        let options = Arc::new(options);
+
+        while let Some(result) = ping_rx.recv().await {
+            if report_tx.is_closed() {
+                break;
+            }
+            if !result.is_alive && !options.include_offline {
+                continue;
+            }
+            let report_tx = report_tx.clone();
+            let (adapters, dns, ports, options) = (
+                Arc::clone(&adapters),
+                Arc::clone(&dns),
+                Arc::clone(&ports),
+                Arc::clone(&options),
+            );
+            tokio::spawn(async move {
+                let ip = result.ip;
+                let mut report = HostReport {
+                    ip,
+                    is_alive: result.is_alive,
+                    status: result.status,
+                    method: result.method,
+                    rtt: result.rtt,
+                    probe: result.probe,
+                    hostname: None,
+                    mac: None,
+                    open_ports: None,
+                };
+                if result.is_alive {
+                    if options.resolve_hostnames {
+                        report.hostname = dns.resolve(ip).await.name().map(str::to_string);
+                    }
+                    if options.detect_mac && is_local(&adapters, ip) {
+                        report.mac = resolve_mac(ip, false).await;
+                    }
+                    if let Some(list) = &options.ports {
+                        let results = ports.scan_ports(ip, list).await;
+                        report.open_ports =
+                            Some(results.iter().filter(|p| p.is_open()).map(|p| p.port).collect());
+                    }
+                }
+                let _ = report_tx.send(report).await;
+            });
+        }
+    });
+
+    HostReports { rx: report_rx }
+}
@@ -79,35 +79,61 @@ pub struct AdapterInfo {
 impl AdapterInfo {
     /// Calculate subnet in CIDR notation from IP and prefix length
     fn calculate_subnet(ip: Ipv4Addr, prefix_len: u8) -> String {
-        let ip_u32 = u32::from(ip);
-        let mask = if prefix_len == 0 {
-            0
-        } else {
-            !0u32 << (32 - prefix_len)
-        };
-        let network = Ipv4Addr::from(ip_u32 & mask);
+        let network = Ipv4Addr::from(u32::from(ip) & prefix_mask(prefix_len));
         format!("{}/{}", network, prefix_len)
     }
+
+    /// Whether `ip` is on this adapter's subnet
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        let mask = prefix_mask(self.prefix_length);
+        u32::from(ip) & mask == u32::from(self.ip) & mask
+    }
+
+    /// The first of `gateways` on this adapter's subnet: its route out
+    pub fn gateway(&self, gateways: &[Ipv4Addr]) -> Option<Ipv4Addr> {
+        gateways.iter().copied().find(|&gw| self.contains(gw))
+    }
+}
+
+fn prefix_mask(prefix_len: u8) -> u32 {
+    match prefix_len {
+        0 => 0,
+        len => !0u32 << (32 - u32::from(len.min(32))),
+    }
 }
 
+/// Whether `ip` is reachable without a router, so ARP can see its MAC.
+/// With no adapters known (still loading, or not listable on this platform)
+/// every address counts as local rather than guessing.
+pub fn is_local(adapters: &[AdapterInfo], ip: Ipv4Addr) -> bool {
+    adapters.is_empty() || adapters.iter().any(|a| a.contains(ip))
+}
+
+/// Index of the adapter whose subnet is exactly the CIDR `range`. Host bits
+/// are ignored, so `192.168.1.57/24` matches an adapter on `192.168.1.0/24`.
+pub fn adapter_for_range(adapters: &[AdapterInfo], range: &str) -> Option<usize> {
+    let (ip, prefix) = range.trim().split_once('/')?;
+    let ip: Ipv4Addr = ip.trim().parse().ok()?;
+    let prefix: u8 = prefix.trim().parse().ok().filter(|&p| p <= 32)?;
+    let subnet = AdapterInfo::calculate_subnet(ip, prefix);
+    adapters.iter().position(|a| a.subnet == subnet)
+}
+
+/// Lists the default routes' interface indexes, best metric first, as
+/// `route|<index>` lines, then every IPv4 address on an adapter that is up as
+/// `<name>|<address>|<prefix length>|<index>`
+const ADAPTER_SCRIPT: &str = r#"Get-NetRoute -DestinationPrefix 0.0.0.0/0 -ErrorAction SilentlyContinue | Sort-Object RouteMetric | ForEach-Object { "route|$($_.InterfaceIndex)" }; Get-NetIPAddress -AddressFamily IPv4 | Where-Object { $_.IPAddress -ne '127.0.0.1' -and $_.PrefixOrigin -ne 'WellKnown' } | ForEach-Object { $adapter = Get-NetAdapter -InterfaceIndex $_.InterfaceIndex -ErrorAction SilentlyContinue; if ($adapter -and $adapter.Status -eq 'Up') { "$($adapter.Name)|$($_.IPAddress)|$($_.PrefixLength)|$($_.InterfaceIndex)" } }"#;
+
 /// Get all active network adapters with IPv4 addresses using PowerShell
 pub fn get_active_adapters() -> Vec<AdapterInfo> {
     // Try pwsh first, fall back to powershell
     let output = Command::new("pwsh")
-        .args([
-            "-NoProfile",
-            "-Command",
-            r#"Get-NetIPAddress -AddressFamily IPv4 | Where-Object { $_.IPAddress -ne '127.0.0.1' -and $_.PrefixOrigin -ne 'WellKnown' } | ForEach-Object { $adapter = Get-NetAdapter -InterfaceIndex $_.InterfaceIndex -ErrorAction SilentlyContinue; if ($adapter -and $adapter.Status -eq 'Up') { "$($adapter.Name)|$($_.IPAddress)|$($_.PrefixLength)" } }"#,
-        ])
+        .args(["-NoProfile", "-Command", ADAPTER_SCRIPT])
         .output()
         .or_else(|_| {
             // Fall back to Windows PowerShell
             Command::new("powershell")
-                .args([
-                    "-NoProfile",
-                    "-Command",
-                    r#"Get-NetIPAddress -AddressFamily IPv4 | Where-Object { $_.IPAddress -ne '127.0.0.1' -and $_.PrefixOrigin -ne 'WellKnown' } | ForEach-Object { $adapter = Get-NetAdapter -InterfaceIndex $_.InterfaceIndex -ErrorAction SilentlyContinue; if ($adapter -and $adapter.Status -eq 'Up') { "$($adapter.Name)|$($_.IPAddress)|$($_.PrefixLength)" } }"#,
-                ])
+                .args(["-NoProfile", "-Command", ADAPTER_SCRIPT])
                 .output()
         });
 
@@ -116,42 +142,113 @@ pub fn get_active_adapters() -> Vec<AdapterInfo> {
         Err(_) => return Vec::new(),
     };
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    let mut adapters: Vec<AdapterInfo> = stdout
-        .lines()
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split('|').collect();
-            if parts.len() >= 3 {
-                let name = parts[0].trim().to_string();
-                let ip = Ipv4Addr::from_str(parts[1].trim()).ok()?;
-                let prefix_len: u8 = parts[2].trim().parse().ok()?;
+    parse_adapters(&String::from_utf8_lossy(&output.stdout))
+}
 
+/// Adapters in [`ADAPTER_SCRIPT`] output, best first: those carrying a
+/// default route (lowest metric first), then Ethernet, WiFi, VPN and the
+/// rest. A configured but unrouted Ethernet dongle so loses to the WiFi that
+/// carries the traffic.
+fn parse_adapters(output: &str) -> Vec<AdapterInfo> {
+    let mut routes: Vec<u32> = Vec::new();
+    let mut adapters: Vec<(Option<u32>, AdapterInfo)> = Vec::new();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split('|').map(str::trim).collect();
+        match parts.as_slice() {
+            ["route", index] => routes.extend(index.parse::<u32>().ok()),
+            [name, ip, prefix_len, rest @ ..] => {
+                let (Ok(ip), Ok(prefix_len)) = (Ipv4Addr::from_str(ip), prefix_len.parse::<u8>())
+                else {
+                    continue;
+                };
                 // Skip link-local addresses (169.254.x.x)
                 if ip.octets()[0] == 169 && ip.octets()[1] == 254 {
-                    return None;
+                    continue;
                 }
-
-                Some(AdapterInfo {
-                    adapter_type: AdapterType::from_name(&name),
-                    name,
-                    ip,
-                    prefix_length: prefix_len,
-                    subnet: AdapterInfo::calculate_subnet(ip, prefix_len),
-                })
-            } else {
-                None
+                let index = rest.first().and_then(|index| index.parse().ok());
+                adapters.push((
+                    index,
+                    AdapterInfo {
+                        adapter_type: AdapterType::from_name(name),
+                        name: name.to_string(),
+                        ip,
+                        prefix_length: prefix_len,
+                        subnet: AdapterInfo::calculate_subnet(ip, prefix_len),
+                    },
+                ));
             }
-        })
-        .collect();
+            _ => {}
+        }
+    }
+
+    // Stable, so the listing order still breaks ties
+    let route_rank = |index: Option<u32>| {
+        index
+            .and_then(|index| routes.iter().position(|&route| route == index))
+            .unwrap_or(usize::MAX)
+    };
+    adapters.sort_by_key(|(index, adapter)| (route_rank(*index), adapter.adapter_type));
+    adapters.into_iter().map(|(_, adapter)| adapter).collect()
+}
 
-    // Sort by adapter type (Ethernet first, then WiFi, then VPN, then Other)
-    adapters.sort_by(|a, b| a.adapter_type.cmp(&b.adapter_type));
+/// Next hop of the default route, if there is one
+pub fn get_default_gateway() -> Option<Ipv4Addr> {
+    get_default_gateways().into_iter().next()
+}
 
-    adapters
+/// Next hops of every default route (one per adapter that has one), the
+/// preferred route first
+pub fn get_default_gateways() -> Vec<Ipv4Addr> {
+    #[cfg(target_os = "windows")]
+    let output = {
+        let script = "(Get-NetRoute -DestinationPrefix 0.0.0.0/0 -ErrorAction SilentlyContinue | Sort-Object RouteMetric).NextHop";
+        Command::new("pwsh")
+            .args(["-NoProfile", "-Command", script])
+            .output()
+            .or_else(|_| {
+                Command::new("powershell")
+                    .args(["-NoProfile", "-Command", script])
+                    .output()
+            })
+    };
+    // Linux has `ip`; macOS and the BSDs answer `route get`
+    #[cfg(not(target_os = "windows"))]
+    let output = Command::new("ip")
+        .args(["-4", "route", "show", "default"])
+        .output()
+        .or_else(|_| Command::new("route").args(["-n", "get", "default"]).output());
+
+    match output {
+        Ok(output) => parse_gateways(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => Vec::new(),
+    }
 }
 
-/// Get the default adapter (prefer Ethernet over WiFi)
+/// Gateway addresses in `ip route` ("default via 192.168.1.1 dev eth0"),
+/// `route get` ("gateway: 192.168.1.1") or bare PowerShell output, one per
+/// line
+fn parse_gateways(output: &str) -> Vec<Ipv4Addr> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let candidate = match parts.as_slice() {
+                [only] => Some(*only),
+                _ => parts
+                    .windows(2)
+                    .find(|w| w[0] == "via" || w[0] == "gateway:")
+                    .map(|w| w[1]),
+            };
+            candidate?
+                .parse()
+                .ok()
+                .filter(|ip: &Ipv4Addr| !ip.is_unspecified())
+        })
+        .collect()
+}
+
+/// Get the default adapter: the one carrying the default route, else the
+/// first by type (Ethernet over WiFi)
 #[allow(dead_code)]
 pub fn get_default_adapter() -> Option<AdapterInfo> {
     get_active_adapters().into_iter().next()
@@ -170,6 +267,105 @@ mod tests {
         assert_eq!(AdapterInfo::calculate_subnet(ip, 16), "10.0.0.0/16");
     }
 
+    #[test]
+    fn test_local_subnet_overlap() {
+        let adapter = |ip: [u8; 4], prefix_length: u8| AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: AdapterType::Ethernet,
+            ip: Ipv4Addr::from(ip),
+            prefix_length,
+            subnet: AdapterInfo::calculate_subnet(Ipv4Addr::from(ip), prefix_length),
+        };
+        let adapters = [adapter([192, 168, 1, 100], 24), adapter([10, 8, 0, 2], 16)];
+
+        assert!(is_local(&adapters, Ipv4Addr::new(192, 168, 1, 7)));
+        assert!(is_local(&adapters, Ipv4Addr::new(10, 8, 255, 1)));
+        assert!(!is_local(&adapters, Ipv4Addr::new(192, 168, 2, 7)));
+        assert!(!is_local(&adapters, Ipv4Addr::new(172, 16, 0, 1)));
+
+        // Nothing known: assume local
+        assert!(is_local(&[], Ipv4Addr::new(172, 16, 0, 1)));
+        // A /0 route covers everything; a /32 only itself
+        assert!(adapter([1, 2, 3, 4], 0).contains(Ipv4Addr::new(8, 8, 8, 8)));
+        assert!(!adapter([1, 2, 3, 4], 32).contains(Ipv4Addr::new(1, 2, 3, 5)));
+    }
+
+    #[test]
+    fn test_adapter_for_range() {
+        let adapter = |ip: [u8; 4], prefix_length: u8| AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: AdapterType::Ethernet,
+            ip: Ipv4Addr::from(ip),
+            prefix_length,
+            subnet: AdapterInfo::calculate_subnet(Ipv4Addr::from(ip), prefix_length),
+        };
+        let adapters = [adapter([192, 168, 1, 100], 24), adapter([10, 8, 0, 2], 16)];
+
+        assert_eq!(adapter_for_range(&adapters, "192.168.1.0/24"), Some(0));
+        assert_eq!(adapter_for_range(&adapters, " 10.8.77.1/16 "), Some(1));
+        // Same network, different size
+        assert_eq!(adapter_for_range(&adapters, "192.168.1.0/25"), None);
+        assert_eq!(adapter_for_range(&adapters, "10.8.0.0/24"), None);
+        // Not a CIDR
+        assert_eq!(adapter_for_range(&adapters, "192.168.1.1-254"), None);
+        assert_eq!(adapter_for_range(&adapters, "192.168.1.0/33"), None);
+        assert_eq!(adapter_for_range(&[], "192.168.1.0/24"), None);
+    }
+
+    #[test]
+    fn test_parse_gateway() {
+        let gateway = vec![Ipv4Addr::new(192, 168, 1, 1)];
+        assert_eq!(parse_gateways("192.168.1.1\r\n"), gateway);
+        assert_eq!(
+            parse_gateways("default via 192.168.1.1 dev eth0 proto dhcp metric 100\n"),
+            gateway
+        );
+        let route_get = "   route to: default\ndestination: default\n    gateway: 192.168.1.1\n  interface: en0\n";
+        assert_eq!(parse_gateways(route_get), gateway);
+        // On-link default route and no route at all
+        assert!(parse_gateways("0.0.0.0\n").is_empty());
+        assert!(parse_gateways("default dev wg0 scope link\n").is_empty());
+        assert!(parse_gateways("").is_empty());
+    }
+
+    #[test]
+    fn each_adapter_finds_the_gateway_on_its_subnet() {
+        let gateways = parse_gateways(
+            "default via 192.168.1.1 dev eth0 metric 100\ndefault via 10.8.0.1 dev wlan0 metric 600\n",
+        );
+        assert_eq!(gateways, [Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(10, 8, 0, 1)]);
+
+        let adapter = |ip: [u8; 4], prefix_length: u8| AdapterInfo {
+            name: "Ethernet".to_string(),
+            adapter_type: AdapterType::Ethernet,
+            ip: Ipv4Addr::from(ip),
+            prefix_length,
+            subnet: AdapterInfo::calculate_subnet(Ipv4Addr::from(ip), prefix_length),
+        };
+        assert_eq!(adapter([10, 8, 3, 4], 16).gateway(&gateways), Some(Ipv4Addr::new(10, 8, 0, 1)));
+        assert_eq!(adapter([192, 168, 1, 20], 24).gateway(&gateways), Some(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(adapter([172, 16, 0, 2], 24).gateway(&gateways), None);
+    }
+
+    #[test]
+    fn default_route_adapter_ranks_first() {
+        let output = "route|12\r\nroute|7\r\n\
+            USB Ethernet|10.50.0.5|24|19\r\n\
+            Ethernet|169.254.10.2|16|4\r\n\
+            Wi-Fi|192.168.1.23|24|12\r\n\
+            Ethernet 2|172.16.4.9|24|7\r\n\
+            OpenVPN TAP|10.8.0.2|24|30\r\n";
+        let names: Vec<String> = parse_adapters(output).into_iter().map(|a| a.name).collect();
+        assert_eq!(names, ["Wi-Fi", "Ethernet 2", "USB Ethernet", "OpenVPN TAP"]);
+
+        // No routes (or an older listing without indexes): by type alone
+        let output = "Wi-Fi|192.168.1.23|24\nUSB Ethernet|10.50.0.5|24\nbad|line\n";
+        let adapters = parse_adapters(output);
+        assert_eq!(adapters[0].name, "USB Ethernet");
+        assert_eq!(adapters[1].subnet, "192.168.1.0/24");
+        assert_eq!(adapters.len(), 2);
+    }
+
     #[test]
     fn test_adapter_type_detection() {
         assert_eq!(AdapterType::from_name("Ethernet"), AdapterType::Ethernet);
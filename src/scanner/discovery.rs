@@ -0,0 +1,523 @@
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+const SSDP_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+
+/// mDNS packets go out with TTL 255 (RFC 6762); SSDP's default is 2
+const MDNS_TTL: u32 = 255;
+const SSDP_TTL: u32 = 2;
+
+/// DNS-SD meta-query: every service type on the link
+const SERVICES_QUERY: &str = "_services._dns-sd._udp.local";
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+/// IN class with the unicast-response bit set, so answers come straight back
+/// to the querying socket
+const CLASS_IN_QU: u16 = 0x8001;
+
+/// Time allowed to fetch a device description for its friendly name
+const FETCH_TIMEOUT: Duration = Duration::from_secs(2);
+/// Bytes of a device description read at most
+const FETCH_LIMIT: u64 = 64 * 1024;
+
+/// A device that answered (or announced itself) over SSDP or mDNS
+#[derive(Debug, Clone, PartialEq)]
+pub struct Announcement {
+    pub ip: Ipv4Addr,
+    /// Advertised friendly name (UPnP `friendlyName`, mDNS instance or host name)
+    pub name: Option<String>,
+    /// Short service / device types, e.g. `ipp`, `MediaRenderer`
+    pub services: Vec<String>,
+}
+
+impl Announcement {
+    /// Add what a later answer from the same device says: the first name
+    /// wins, services are collected
+    pub fn merge(&mut self, other: &Announcement) {
+        if self.name.is_none() {
+            self.name = other.name.clone();
+        }
+        for service in &other.services {
+            if !self.services.contains(service) {
+                self.services.push(service.clone());
+            }
+        }
+    }
+}
+
+/// Browse for SSDP and mDNS devices through each of `interfaces` for
+/// `window`, sending what every responder advertises as it arrives. Ends
+/// early when `cancel_rx` fires; every socket is closed on return.
+pub async fn discover(
+    interfaces: Vec<Ipv4Addr>,
+    window: Duration,
+    tx: mpsc::Sender<Announcement>,
+    mut cancel_rx: mpsc::Receiver<()>,
+) {
+    let interfaces = if interfaces.is_empty() {
+        vec![Ipv4Addr::UNSPECIFIED]
+    } else {
+        interfaces
+    };
+
+    // Dropping the set aborts whatever is still listening
+    let mut tasks = JoinSet::new();
+    for &interface in &interfaces {
+        if let Ok(socket) = sender(interface, MDNS_TTL) {
+            tasks.spawn(mdns(socket, true, tx.clone()));
+        }
+        if let Ok(socket) = sender(interface, SSDP_TTL) {
+            tasks.spawn(ssdp(socket, true, tx.clone()));
+        }
+    }
+    // Passive listeners catch unsolicited announcements and answers sent to
+    // the group; either port may already be taken without SO_REUSEADDR peers
+    if let Ok(socket) = listener(MDNS_GROUP, MDNS_PORT, &interfaces) {
+        tasks.spawn(mdns(socket, false, tx.clone()));
+    }
+    if let Ok(socket) = listener(SSDP_GROUP, SSDP_PORT, &interfaces) {
+        tasks.spawn(ssdp(socket, false, tx.clone()));
+    }
+    drop(tx);
+    if tasks.is_empty() {
+        return;
+    }
+
+    tokio::select! {
+        _ = cancel_rx.recv() => {}
+        _ = tokio::time::sleep(window) => {}
+    }
+}
+
+/// Socket for queries sent out of `interface`; replies come back to it
+fn sender(interface: Ipv4Addr, ttl: u32) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_multicast_if_v4(&interface)?;
+    socket.set_multicast_ttl_v4(ttl)?;
+    socket.bind(&SocketAddrV4::new(interface, 0).into())?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+/// Socket on the well-known `port`, sharing it with any local responder,
+/// that has joined `group` on every interface it could
+fn listener(group: Ipv4Addr, port: u16, interfaces: &[Ipv4Addr]) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port).into())?;
+    let joined = interfaces
+        .iter()
+        .filter(|interface| socket.join_multicast_v4(&group, interface).is_ok())
+        .count();
+    if joined == 0 {
+        return Err(io::Error::other("could not join the multicast group"));
+    }
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+async fn recv_v4(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, Ipv4Addr)> {
+    loop {
+        let (len, from) = socket.recv_from(buf).await?;
+        if let IpAddr::V4(ip) = from.ip() {
+            return Ok((len, ip));
+        }
+    }
+}
+
+/// Ask for every service type (when `query`), then for the instances of
+/// each type that turns up, announcing responders until aborted
+async fn mdns(socket: UdpSocket, query: bool, tx: mpsc::Sender<Announcement>) {
+    let group = SocketAddr::from((MDNS_GROUP, MDNS_PORT));
+    if query && socket.send_to(&mdns_query(&[SERVICES_QUERY]), group).await.is_err() {
+        return;
+    }
+    let mut asked = HashSet::new();
+    let mut buf = vec![0u8; 9000];
+    while let Ok((len, source)) = recv_v4(&socket, &mut buf).await {
+        let Some(records) = parse_response(&buf[..len]) else {
+            continue;
+        };
+        let (announcement, service_types) = read_mdns(&records, source);
+        if !announcement.services.is_empty() || announcement.name.is_some() {
+            let _ = tx.send(announcement).await;
+        }
+        let new: Vec<String> = service_types
+            .into_iter()
+            .filter(|t| asked.insert(t.to_ascii_lowercase()))
+            .collect();
+        if query && !new.is_empty() {
+            let names: Vec<&str> = new.iter().map(String::as_str).collect();
+            let _ = socket.send_to(&mdns_query(&names), group).await;
+        }
+    }
+}
+
+/// Send an M-SEARCH (when `query`) and announce every device that answers
+/// or notifies, fetching each one's description once for its friendly name
+async fn ssdp(socket: UdpSocket, query: bool, tx: mpsc::Sender<Announcement>) {
+    if query {
+        let search = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: {}:{}\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: ssdp:all\r\n\r\n",
+            SSDP_GROUP, SSDP_PORT
+        );
+        let group = SocketAddr::from((SSDP_GROUP, SSDP_PORT));
+        if socket.send_to(search.as_bytes(), group).await.is_err() {
+            return;
+        }
+    }
+    let mut fetched = HashSet::new();
+    // Aborted along with this task
+    let mut fetches = JoinSet::new();
+    let mut buf = vec![0u8; 9000];
+    while let Ok((len, ip)) = recv_v4(&socket, &mut buf).await {
+        let Some(reply) = parse_ssdp(&String::from_utf8_lossy(&buf[..len])) else {
+            continue;
+        };
+        if let Some(service) = reply.service {
+            let announcement = Announcement {
+                ip,
+                name: None,
+                services: vec![service],
+            };
+            let _ = tx.send(announcement).await;
+        }
+        if let Some(location) = reply.location.filter(|_| fetched.insert(ip)) {
+            let tx = tx.clone();
+            fetches.spawn(async move {
+                if let Some(name) = friendly_name(&location, ip).await {
+                    let announcement = Announcement {
+                        ip,
+                        name: Some(name),
+                        services: Vec::new(),
+                    };
+                    let _ = tx.send(announcement).await;
+                }
+            });
+        }
+    }
+}
+
+/// DNS query with one PTR question per name, asking for unicast replies
+fn mdns_query(names: &[&str]) -> Vec<u8> {
+    let mut packet = vec![0u8; 12];
+    packet[4..6].copy_from_slice(&(names.len() as u16).to_be_bytes());
+    for name in names {
+        for label in name.split('.').filter(|l| !l.is_empty()) {
+            packet.push(label.len().min(63) as u8);
+            packet.extend_from_slice(&label.as_bytes()[..label.len().min(63)]);
+        }
+        packet.push(0);
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN_QU.to_be_bytes());
+    }
+    packet
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RecordData {
+    Ptr(Vec<String>),
+    A(Ipv4Addr),
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    name: Vec<String>,
+    data: RecordData,
+}
+
+/// Resource records of a DNS response, from all three sections; None for
+/// queries and malformed packets
+fn parse_response(packet: &[u8]) -> Option<Vec<Record>> {
+    let header = packet.get(..12)?;
+    let count = |i: usize| u16::from_be_bytes([header[i], header[i + 1]]) as usize;
+    if header[2] & 0x80 == 0 {
+        return None;
+    }
+    let mut pos = 12;
+    for _ in 0..count(4) {
+        pos = read_name(packet, pos)?.1 + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..count(6) + count(8) + count(10) {
+        let (name, next) = read_name(packet, pos)?;
+        let fixed = packet.get(next..next + 10)?;
+        let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let rdlen = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        let start = next + 10;
+        let rdata = packet.get(start..start + rdlen)?;
+        let data = match rtype {
+            TYPE_PTR => RecordData::Ptr(read_name(packet, start)?.0),
+            TYPE_A if rdlen == 4 => RecordData::A(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3])),
+            _ => RecordData::Other,
+        };
+        records.push(Record { name, data });
+        pos = start + rdlen;
+    }
+    Some(records)
+}
+
+/// Labels of the (possibly compressed) name at `pos`, and the offset just
+/// past it
+fn read_name(packet: &[u8], mut pos: usize) -> Option<(Vec<String>, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bounds the pointer chain, which could otherwise loop
+    for _ in 0..128 {
+        let len = *packet.get(pos)? as usize;
+        match len {
+            0 => return Some((labels, end.unwrap_or(pos + 1))),
+            len if len & 0xC0 == 0xC0 => {
+                let offset = ((len & 0x3F) << 8) | *packet.get(pos + 1)? as usize;
+                end.get_or_insert(pos + 2);
+                pos = offset;
+            }
+            len if len & 0xC0 == 0 => {
+                let label = packet.get(pos + 1..pos + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + len;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn name_is(labels: &[String], name: &str) -> bool {
+    labels.join(".").eq_ignore_ascii_case(name)
+}
+
+/// `_ipp._tcp.local` → `ipp`; None for anything that isn't a service type
+fn service_label(labels: &[String]) -> Option<String> {
+    match labels {
+        [service, proto, ..] if service.starts_with('_') && proto.starts_with('_') && proto != "_sub" => {
+            Some(service.trim_start_matches('_').to_string())
+        }
+        _ => None,
+    }
+}
+
+/// What one mDNS response says about `source`, and the service types it
+/// mentioned that are worth asking about
+fn read_mdns(records: &[Record], source: Ipv4Addr) -> (Announcement, Vec<String>) {
+    let mut instance = None;
+    let mut host = None;
+    let mut services = Vec::new();
+    let mut service_types = Vec::new();
+    for record in records {
+        match &record.data {
+            RecordData::Ptr(target) if name_is(&record.name, SERVICES_QUERY) => {
+                if let Some(service) = service_label(target) {
+                    services.push(service);
+                    service_types.push(target.join("."));
+                }
+            }
+            RecordData::Ptr(target) => {
+                // `<instance>._ipp._tcp.local`, pointed to by `_ipp._tcp.local`
+                if let Some(service) = service_label(&record.name) {
+                    services.push(service);
+                    if target.len() > record.name.len() {
+                        instance.get_or_insert_with(|| target[0].clone());
+                    }
+                }
+            }
+            RecordData::A(ip) if *ip == source && !record.name.is_empty() => {
+                host.get_or_insert_with(|| record.name.join("."));
+            }
+            _ => {}
+        }
+    }
+    services.dedup();
+    let announcement = Announcement {
+        ip: source,
+        name: instance.or(host),
+        services,
+    };
+    (announcement, service_types)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SsdpReply {
+    service: Option<String>,
+    location: Option<String>,
+}
+
+/// Search response or NOTIFY alive message; None for searches and byebyes
+fn parse_ssdp(text: &str) -> Option<SsdpReply> {
+    let mut lines = text.lines();
+    let start = lines.next()?.trim();
+    if !(start.starts_with("HTTP/1.1 200") || start.starts_with("NOTIFY ")) {
+        return None;
+    }
+    let mut reply = SsdpReply {
+        service: None,
+        location: None,
+    };
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_uppercase().as_str() {
+            "ST" | "NT" => reply.service = ssdp_service(value),
+            "LOCATION" => reply.location = Some(value.to_string()),
+            "NTS" if value.eq_ignore_ascii_case("ssdp:byebye") => return None,
+            _ => {}
+        }
+    }
+    Some(reply)
+}
+
+/// `urn:schemas-upnp-org:device:MediaRenderer:1` → `MediaRenderer`
+fn ssdp_service(target: &str) -> Option<String> {
+    match target.split(':').collect::<Vec<_>>()[..] {
+        ["urn", _, "device" | "service", name, ..] if !name.is_empty() => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+/// `friendlyName` from the device description at `location`. Only plain
+/// HTTP on the device's own address is fetched.
+async fn friendly_name(location: &str, ip: Ipv4Addr) -> Option<String> {
+    let rest = location.strip_prefix("http://")?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let (host, port) = match authority.split_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (authority, 80),
+    };
+    if host.parse::<Ipv4Addr>().ok()? != ip {
+        return None;
+    }
+    let path = if path.is_empty() { "/" } else { path };
+    let fetch = async {
+        let mut stream = TcpStream::connect((ip, port)).await.ok()?;
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, authority
+        );
+        stream.write_all(request.as_bytes()).await.ok()?;
+        let mut body = Vec::new();
+        stream.take(FETCH_LIMIT).read_to_end(&mut body).await.ok()?;
+        Some(body)
+    };
+    let body = tokio::time::timeout(FETCH_TIMEOUT, fetch).await.ok()??;
+    xml_text(&String::from_utf8_lossy(&body), "friendlyName")
+}
+
+/// Unescaped text of the first `<tag>` element
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    let text = xml[start..end]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode `name` as uncompressed labels
+    fn name(name: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        for label in name.split('.') {
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+        }
+        out.push(0);
+        out
+    }
+
+    fn record(owner: &[u8], rtype: u16, rdata: &[u8]) -> Vec<u8> {
+        let mut out = owner.to_vec();
+        out.extend_from_slice(&rtype.to_be_bytes());
+        out.extend_from_slice(&[0x80, 0x01, 0, 0, 0x11, 0x94]);
+        out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(rdata);
+        out
+    }
+
+    #[test]
+    fn query_asks_for_unicast_ptr_records() {
+        let packet = mdns_query(&[SERVICES_QUERY]);
+        assert_eq!(&packet[4..6], &[0, 1]);
+        assert_eq!(&packet[12..], [name(SERVICES_QUERY), vec![0, 12, 0x80, 0x01]].concat());
+    }
+
+    #[test]
+    fn mdns_response_yields_instance_name_and_services() {
+        let source = Ipv4Addr::new(192, 168, 1, 20);
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 3, 0, 0, 0, 1];
+        // `_ipp._tcp.local` at offset 12 is reused through a pointer below
+        let service_type = name("_ipp._tcp.local");
+        packet.extend(record(&name(SERVICES_QUERY), TYPE_PTR, &service_type));
+        let type_offset = 12 + name(SERVICES_QUERY).len() + 10;
+        let mut instance = name("Office Printer");
+        instance.pop();
+        instance.extend_from_slice(&[0xC0, type_offset as u8]);
+        packet.extend(record(&[0xC0, type_offset as u8], TYPE_PTR, &instance));
+        packet.extend(record(&name("printer.local"), TYPE_A, &[192, 168, 1, 20]));
+        packet.extend(record(&name("other.local"), TYPE_A, &[192, 168, 1, 99]));
+
+        let records = parse_response(&packet).expect("valid response");
+        assert_eq!(records.len(), 4);
+        let (announcement, types) = read_mdns(&records, source);
+        assert_eq!(announcement.name.as_deref(), Some("Office Printer"));
+        assert_eq!(announcement.services, ["ipp"]);
+        assert_eq!(types, ["_ipp._tcp.local"]);
+
+        // Queries and truncated packets are ignored
+        assert!(parse_response(&mdns_query(&[SERVICES_QUERY])).is_none());
+        assert!(parse_response(&packet[..packet.len() - 3]).is_none());
+    }
+
+    #[test]
+    fn compression_loops_are_rejected() {
+        let packet = [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xC0, 12];
+        assert!(read_name(&packet, 12).is_none());
+    }
+
+    #[test]
+    fn ssdp_replies_give_device_type_and_location() {
+        let reply = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nLocation: http://192.168.1.5:49152/desc.xml\r\nST: urn:schemas-upnp-org:device:MediaRenderer:1\r\n\r\n";
+        assert_eq!(
+            parse_ssdp(reply),
+            Some(SsdpReply {
+                service: Some("MediaRenderer".to_string()),
+                location: Some("http://192.168.1.5:49152/desc.xml".to_string()),
+            })
+        );
+        let notify = "NOTIFY * HTTP/1.1\r\nNT: upnp:rootdevice\r\nNTS: ssdp:alive\r\n\r\n";
+        assert_eq!(parse_ssdp(notify).unwrap().service, None);
+        assert!(parse_ssdp("NOTIFY * HTTP/1.1\r\nNTS: ssdp:byebye\r\n\r\n").is_none());
+        assert!(parse_ssdp("M-SEARCH * HTTP/1.1\r\nST: ssdp:all\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn friendly_name_is_unescaped() {
+        let xml = "<root><device><friendlyName> Living Room &amp; Den </friendlyName></device></root>";
+        assert_eq!(xml_text(xml, "friendlyName").as_deref(), Some("Living Room & Den"));
+        assert_eq!(xml_text(xml, "modelName"), None);
+    }
+}
@@ -1,13 +1,55 @@
 use std::collections::HashMap;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 
-use dns_lookup::lookup_addr;
+use dns_lookup::{getnameinfo, LookupErrorKind};
 use tokio::sync::{Mutex, Semaphore};
 
-/// DNS resolver with caching
+/// Longest wait for one reverse lookup before it counts as timed out
+pub const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of a reverse DNS lookup
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Resolved(String),
+    /// The resolver answered: there is no PTR record
+    NoRecord,
+    /// No answer in time, or a temporary server failure; a retry may help
+    Timeout,
+    /// Hostname resolution is turned off
+    Disabled,
+}
+
+impl Resolution {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Resolution::Resolved(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Whether the answer can be cached: a timeout is worth asking again
+    fn is_final(&self) -> bool {
+        matches!(self, Resolution::Resolved(_) | Resolution::NoRecord)
+    }
+}
+
+/// Sort a `getnameinfo` result into a [`Resolution`]
+fn classify(ip: Ipv4Addr, result: Result<String, LookupErrorKind>) -> Resolution {
+    match result {
+        // Without NI_NAMEREQD a missing PTR record comes back as the address
+        Ok(name) if name.is_empty() || name == ip.to_string() => Resolution::NoRecord,
+        Ok(name) => Resolution::Resolved(name),
+        Err(LookupErrorKind::Again) => Resolution::Timeout,
+        Err(_) => Resolution::NoRecord,
+    }
+}
+
+/// DNS resolver with caching. Only definite answers are cached; timeouts
+/// are looked up again next time.
 pub struct DnsResolver {
-    cache: Arc<Mutex<HashMap<Ipv4Addr, Option<String>>>>,
+    cache: Arc<Mutex<HashMap<Ipv4Addr, Resolution>>>,
     semaphore: Arc<Semaphore>,
 }
 
@@ -19,8 +61,8 @@ impl DnsResolver {
         }
     }
 
-    /// Resolve an IP address to a hostname
-    pub async fn resolve(&self, ip: Ipv4Addr) -> Option<String> {
+    /// Reverse-resolve an IP address
+    pub async fn resolve(&self, ip: Ipv4Addr) -> Resolution {
         // Check cache first
         {
             let cache = self.cache.lock().await;
@@ -30,19 +72,25 @@ impl DnsResolver {
         }
 
         let Ok(_permit) = self.semaphore.acquire().await else {
-            return None;
+            return Resolution::Timeout;
+        };
+
+        // Perform DNS lookup in blocking task; a lookup outliving the timeout
+        // finishes on its own thread and is ignored
+        let lookup = tokio::task::spawn_blocking(move || {
+            getnameinfo(&SocketAddr::from((ip, 0)), 0)
+                .map(|(name, _)| name)
+                .map_err(|e| e.kind())
+        });
+        let result = match tokio::time::timeout(LOOKUP_TIMEOUT, lookup).await {
+            Ok(Ok(result)) => classify(ip, result),
+            _ => Resolution::Timeout,
         };
 
-        // Perform DNS lookup in blocking task
-        let result = tokio::task::spawn_blocking(move || {
-            lookup_addr(&ip.into()).ok()
-        })
-        .await
-        .ok()
-        .flatten();
+        tracing::debug!(%ip, resolution = ?result, "reverse DNS");
 
         // Cache the result
-        {
+        if result.is_final() {
             let mut cache = self.cache.lock().await;
             cache.insert(ip, result.clone());
         }
@@ -50,9 +98,15 @@ impl DnsResolver {
         result
     }
 
+    /// Look `ip` up again, ignoring a cached answer
+    pub async fn refresh(&self, ip: Ipv4Addr) -> Resolution {
+        self.cache.lock().await.remove(&ip);
+        self.resolve(ip).await
+    }
+
     /// Resolve multiple IP addresses concurrently
     #[allow(dead_code)]
-    pub async fn resolve_batch(&self, ips: Vec<Ipv4Addr>) -> HashMap<Ipv4Addr, Option<String>> {
+    pub async fn resolve_batch(&self, ips: Vec<Ipv4Addr>) -> HashMap<Ipv4Addr, Resolution> {
         let mut handles = Vec::new();
 
         for ip in ips {
@@ -92,3 +146,22 @@ impl Default for DnsResolver {
         Self::new(20)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_results_are_classified() {
+        let ip = Ipv4Addr::new(192, 168, 1, 5);
+        assert_eq!(
+            classify(ip, Ok("nas.lan".to_string())),
+            Resolution::Resolved("nas.lan".to_string())
+        );
+        assert_eq!(classify(ip, Ok("192.168.1.5".to_string())), Resolution::NoRecord);
+        assert_eq!(classify(ip, Err(LookupErrorKind::NoName)), Resolution::NoRecord);
+        assert_eq!(classify(ip, Err(LookupErrorKind::Again)), Resolution::Timeout);
+        assert!(Resolution::NoRecord.is_final());
+        assert!(!Resolution::Timeout.is_final());
+    }
+}
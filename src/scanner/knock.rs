@@ -0,0 +1,306 @@
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use super::ping::connect_from;
+use super::{BoxFuture, PortScanner, PortState};
+
+/// How long a TCP knock waits on its connect. The SYN is what the knock
+/// daemon counts, so whatever answer comes back (or doesn't) is ignored.
+const TCP_KNOCK_WAIT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnockProtocol {
+    Tcp,
+    Udp,
+}
+
+/// One knock: a TCP connect attempt or an empty UDP datagram to `port`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Knock {
+    pub port: u16,
+    pub protocol: KnockProtocol,
+}
+
+impl FromStr for Knock {
+    type Err = String;
+
+    /// `7000/tcp`, `8000/udp`, or a bare port for TCP
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (port, protocol) = match text.split_once('/') {
+            Some((port, protocol)) => (port, protocol),
+            None => (text, "tcp"),
+        };
+        let protocol = match protocol.to_ascii_lowercase().as_str() {
+            "tcp" => KnockProtocol::Tcp,
+            "udp" => KnockProtocol::Udp,
+            _ => return Err(format!("knock '{}': protocol must be tcp or udp", text)),
+        };
+        let port = port
+            .parse::<u16>()
+            .ok()
+            .filter(|&p| p > 0)
+            .ok_or_else(|| format!("knock '{}': bad port", text))?;
+        Ok(Self { port, protocol })
+    }
+}
+
+impl fmt::Display for Knock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let protocol = match self.protocol {
+            KnockProtocol::Tcp => "tcp",
+            KnockProtocol::Udp => "udp",
+        };
+        write!(f, "{}/{}", self.port, protocol)
+    }
+}
+
+/// A knock sequence and what to do once it's sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnockPlan {
+    pub knocks: Vec<Knock>,
+    /// Between one knock and the next
+    pub delay: Duration,
+    /// TCP port probed right after the last knock, to tell whether the
+    /// sequence opened it
+    pub check_port: Option<u16>,
+}
+
+/// How a [`knock`] run went, step by step
+#[derive(Debug, Clone, PartialEq)]
+pub enum KnockEvent {
+    Sent(Knock),
+    /// The knock couldn't be sent; the sequence carries on regardless
+    Failed(Knock, String),
+    /// What the check port answered after the last knock
+    Checked(u16, PortState),
+}
+
+/// Sends knocks and probes the port they should open. [`PortScanner`] is
+/// the real one.
+pub trait KnockSender: Send + Sync {
+    fn knock(&self, ip: Ipv4Addr, knock: Knock) -> BoxFuture<'_, io::Result<()>>;
+
+    fn check(&self, ip: Ipv4Addr, port: u16) -> BoxFuture<'_, PortState>;
+}
+
+impl KnockSender for PortScanner {
+    fn knock(&self, ip: Ipv4Addr, knock: Knock) -> BoxFuture<'_, io::Result<()>> {
+        let source = self.source_ip();
+        let addr = SocketAddr::new(IpAddr::V4(ip), knock.port);
+        Box::pin(async move {
+            match knock.protocol {
+                KnockProtocol::Tcp => match timeout(TCP_KNOCK_WAIT, connect_from(source, addr)).await {
+                    // A closed port refusing is the expected answer
+                    Ok(Err(e)) if e.kind() != io::ErrorKind::ConnectionRefused => Err(e),
+                    _ => Ok(()),
+                },
+                KnockProtocol::Udp => {
+                    let socket = UdpSocket::bind((source.unwrap_or(Ipv4Addr::UNSPECIFIED), 0)).await?;
+                    socket.send_to(&[], addr).await.map(|_| ())
+                }
+            }
+        })
+    }
+
+    fn check(&self, ip: Ipv4Addr, port: u16) -> BoxFuture<'_, PortState> {
+        Box::pin(async move { self.scan_port(ip, port).await.state })
+    }
+}
+
+/// Send `plan`'s knocks to `ip` in order, `plan.delay` apart, then probe its
+/// check port. Events arrive on `tx`, which closes when the run ends or a
+/// message on `cancel_rx` stops it.
+pub async fn knock(
+    ip: Ipv4Addr,
+    plan: KnockPlan,
+    sender: &dyn KnockSender,
+    tx: mpsc::Sender<KnockEvent>,
+    mut cancel_rx: mpsc::Receiver<()>,
+) {
+    for (i, step) in plan.knocks.iter().enumerate() {
+        if i > 0 {
+            tokio::select! {
+                _ = cancel_rx.recv() => return,
+                _ = tokio::time::sleep(plan.delay) => {}
+            }
+        }
+        let event = match sender.knock(ip, *step).await {
+            Ok(()) => KnockEvent::Sent(*step),
+            Err(e) => KnockEvent::Failed(*step, e.to_string()),
+        };
+        if tx.send(event).await.is_err() {
+            return;
+        }
+    }
+    if let Some(port) = plan.check_port {
+        let state = tokio::select! {
+            _ = cancel_rx.recv() => return,
+            state = sender.check(ip, port) => state,
+        };
+        let _ = tx.send(KnockEvent::Checked(port, state)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::time::Instant;
+
+    use super::*;
+
+    /// Records when each knock and check happened, and fails knocks to
+    /// `failing`
+    struct Recorder {
+        start: Instant,
+        sent: Mutex<Vec<(String, Duration)>>,
+        failing: Option<u16>,
+        check: PortState,
+    }
+
+    impl Recorder {
+        fn new(check: PortState) -> Self {
+            Self {
+                start: Instant::now(),
+                sent: Mutex::new(Vec::new()),
+                failing: None,
+                check,
+            }
+        }
+
+        fn record(&self, what: String) {
+            self.sent.lock().unwrap().push((what, self.start.elapsed()));
+        }
+    }
+
+    impl KnockSender for Recorder {
+        fn knock(&self, _ip: Ipv4Addr, knock: Knock) -> BoxFuture<'_, io::Result<()>> {
+            self.record(knock.to_string());
+            let failed = self.failing == Some(knock.port);
+            Box::pin(async move {
+                if failed {
+                    return Err(io::Error::other("network unreachable"));
+                }
+                Ok(())
+            })
+        }
+
+        fn check(&self, _ip: Ipv4Addr, port: u16) -> BoxFuture<'_, PortState> {
+            self.record(format!("check {}", port));
+            Box::pin(async move { self.check })
+        }
+    }
+
+    fn plan(knocks: &[&str], delay_ms: u64, check_port: Option<u16>) -> KnockPlan {
+        KnockPlan {
+            knocks: knocks.iter().map(|k| k.parse().unwrap()).collect(),
+            delay: Duration::from_millis(delay_ms),
+            check_port,
+        }
+    }
+
+    async fn run(plan: KnockPlan, sender: &Recorder) -> Vec<KnockEvent> {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (_cancel_tx, cancel_rx) = mpsc::channel(1);
+        knock(Ipv4Addr::new(192, 168, 1, 5), plan, sender, tx, cancel_rx).await;
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn knocks_parse_with_tcp_by_default() {
+        let knock = |text: &str| text.parse::<Knock>();
+        assert_eq!(
+            knock("7000"),
+            Ok(Knock { port: 7000, protocol: KnockProtocol::Tcp })
+        );
+        assert_eq!(
+            knock(" 8000/UDP "),
+            Ok(Knock { port: 8000, protocol: KnockProtocol::Udp })
+        );
+        assert_eq!(knock("9000/tcp").unwrap().to_string(), "9000/tcp");
+        assert!(knock("9000/icmp").is_err());
+        assert!(knock("0/tcp").is_err());
+        assert!(knock("70000").is_err());
+        assert!(knock("").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn knocks_are_spaced_by_the_delay_and_checked_straight_after() {
+        let sender = Recorder::new(PortState::Open);
+        let events = run(plan(&["7000/tcp", "8000/udp", "9000/tcp"], 250, Some(22)), &sender).await;
+
+        let ms = Duration::from_millis;
+        assert_eq!(
+            *sender.sent.lock().unwrap(),
+            vec![
+                ("7000/tcp".to_string(), ms(0)),
+                ("8000/udp".to_string(), ms(250)),
+                ("9000/tcp".to_string(), ms(500)),
+                ("check 22".to_string(), ms(500)),
+            ]
+        );
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[3], KnockEvent::Checked(22, PortState::Open));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_knock_does_not_stop_the_sequence() {
+        let mut sender = Recorder::new(PortState::Closed);
+        sender.failing = Some(8000);
+        let events = run(plan(&["7000", "8000/udp", "9000"], 100, None), &sender).await;
+        let knock = |text: &str| text.parse::<Knock>().unwrap();
+        assert_eq!(
+            events,
+            vec![
+                KnockEvent::Sent(knock("7000")),
+                KnockEvent::Failed(knock("8000/udp"), "network unreachable".to_string()),
+                KnockEvent::Sent(knock("9000")),
+            ]
+        );
+        // No check port: nothing probed
+        assert_eq!(sender.sent.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelling_stops_between_knocks() {
+        let sender = Recorder::new(PortState::Open);
+        let (tx, mut rx) = mpsc::channel(16);
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        cancel_tx.send(()).await.unwrap();
+        knock(Ipv4Addr::LOCALHOST, plan(&["7000", "8000"], 1_000, Some(22)), &sender, tx, cancel_rx).await;
+        assert_eq!(rx.recv().await, Some(KnockEvent::Sent("7000".parse().unwrap())));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn tcp_knocks_ignore_refusals_and_udp_knocks_send() {
+        let scanner = PortScanner::new(Default::default());
+        // Nothing listens on a port freed straight after binding
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let tcp = Knock { port: closed, protocol: KnockProtocol::Tcp };
+        assert!(scanner.knock(Ipv4Addr::LOCALHOST, tcp).await.is_ok());
+        assert_eq!(scanner.check(Ipv4Addr::LOCALHOST, closed).await, PortState::Closed);
+
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let udp = Knock {
+            port: receiver.local_addr().unwrap().port(),
+            protocol: KnockProtocol::Udp,
+        };
+        scanner.knock(Ipv4Addr::LOCALHOST, udp).await.unwrap();
+        let mut buf = [0u8; 16];
+        let (len, _) = receiver.recv_from(&mut buf).await.unwrap();
+        assert_eq!(len, 0);
+    }
+}
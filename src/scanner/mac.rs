@@ -1,6 +1,26 @@
 use std::collections::HashMap;
-use std::net::Ipv4Addr;
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::process::Command;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+
+/// Longest an `arp` lookup may run before it counts as no answer
+pub const MAC_LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// `arp` processes running at once for [`lookup_mac_address`]
+static MAC_LOOKUPS: Semaphore = Semaphore::const_new(8);
+
+/// Pre-warm datagrams in flight at once for [`resolve_mac`]
+static ARP_PREWARMS: Semaphore = Semaphore::const_new(32);
+
+/// Time the OS gets to finish ARP resolution after the pre-warm datagram
+/// before the table is read again
+const ARP_SETTLE: Duration = Duration::from_millis(150);
+
+/// Port the pre-warm datagram goes to: discard, so a listener ignores it
+const PREWARM_PORT: u16 = 9;
 
 /// MAC address information
 #[derive(Debug, Clone)]
@@ -10,84 +30,140 @@ pub struct MacInfo {
 }
 
 /// Get MAC address for an IP on the local network using ARP
+#[allow(dead_code)]
 pub fn get_mac_address(ip: Ipv4Addr) -> Option<MacInfo> {
-    // On Windows, use arp -a command
-    #[cfg(target_os = "windows")]
-    {
-        get_mac_from_arp_windows(ip)
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        get_mac_from_arp_unix(ip)
-    }
+    let output = Command::new("arp").args(arp_args(ip)).output().ok()?;
+    parse_arp_output(&String::from_utf8_lossy(&output.stdout), ip)
 }
 
-#[cfg(target_os = "windows")]
-fn get_mac_from_arp_windows(ip: Ipv4Addr) -> Option<MacInfo> {
-    let output = Command::new("arp")
-        .args(["-a", &ip.to_string()])
-        .output()
-        .ok()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    // Parse ARP output to find MAC address
-    for line in stdout.lines() {
-        if line.contains(&ip.to_string()) {
-            // Windows ARP format: "192.168.1.1    aa-bb-cc-dd-ee-ff   dynamic"
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let mac = parts[1].to_uppercase().replace('-', ":");
-                // Validate MAC format
-                if mac.len() == 17 && mac.chars().filter(|c| *c == ':').count() == 5 {
-                    let vendor = lookup_vendor(&mac);
-                    return Some(MacInfo {
-                        address: mac,
-                        vendor,
-                    });
-                }
-            }
+/// [`get_mac_address`] for async callers: doesn't block an executor thread,
+/// queues behind other lookups, and kills an `arp` that hangs past
+/// [`MAC_LOOKUP_TIMEOUT`]
+pub async fn lookup_mac_address(ip: Ipv4Addr) -> Option<MacInfo> {
+    let _permit = MAC_LOOKUPS.acquire().await.ok()?;
+    let arp = tokio::process::Command::new("arp")
+        .args(arp_args(ip))
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(MAC_LOOKUP_TIMEOUT, arp).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            tracing::debug!(%ip, error = %e, "arp failed to run");
+            return None;
         }
-    }
+        Err(_) => {
+            tracing::debug!(%ip, "arp timed out");
+            return None;
+        }
+    };
+    let mac = parse_arp_output(&String::from_utf8_lossy(&output.stdout), ip);
+    tracing::debug!(%ip, mac = ?mac.as_ref().map(|m| &m.address), "MAC lookup");
+    mac
+}
 
-    None
+/// Look up the MAC of a host already known to be alive. With `prewarm`, a
+/// miss sends the host a one-byte UDP datagram, which makes the OS resolve
+/// its address over ARP, and reads the table again: Windows only lists hosts
+/// it has exchanged traffic with itself.
+pub async fn resolve_mac(ip: Ipv4Addr, prewarm: bool) -> Option<MacInfo> {
+    resolve_mac_with(ip, prewarm, lookup_mac_address, send_prewarm).await
 }
 
-#[cfg(not(target_os = "windows"))]
-fn get_mac_from_arp_unix(ip: Ipv4Addr) -> Option<MacInfo> {
-    let output = Command::new("arp")
-        .args(["-n", &ip.to_string()])
-        .output()
-        .ok()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    for line in stdout.lines() {
-        if line.contains(&ip.to_string()) {
-            // Unix ARP format varies, but MAC is usually in format aa:bb:cc:dd:ee:ff
-            for part in line.split_whitespace() {
-                if part.len() == 17 && part.chars().filter(|c| *c == ':').count() == 5 {
-                    let mac = part.to_uppercase();
-                    let vendor = lookup_vendor(&mac);
-                    return Some(MacInfo {
-                        address: mac,
-                        vendor,
-                    });
-                }
-            }
-        }
+/// [`resolve_mac`] with the ARP table reader and the pre-warm step passed in
+async fn resolve_mac_with<R, RF, W, WF>(
+    ip: Ipv4Addr,
+    prewarm: bool,
+    read: R,
+    warm: W,
+) -> Option<MacInfo>
+where
+    R: Fn(Ipv4Addr) -> RF,
+    RF: Future<Output = Option<MacInfo>>,
+    W: FnOnce(Ipv4Addr) -> WF,
+    WF: Future<Output = ()>,
+{
+    if let Some(mac) = read(ip).await {
+        return Some(mac);
+    }
+    if !prewarm {
+        return None;
     }
+    warm(ip).await;
+    read(ip).await
+}
 
-    None
+/// Send `ip` a one-byte datagram and give ARP [`ARP_SETTLE`] to complete
+async fn send_prewarm(ip: Ipv4Addr) {
+    let Ok(_permit) = ARP_PREWARMS.acquire().await else {
+        return;
+    };
+    let sent = match tokio::net::UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket.send_to(&[0], SocketAddr::from((ip, PREWARM_PORT))).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = sent {
+        tracing::debug!(%ip, error = %e, "ARP pre-warm failed");
+        return;
+    }
+    tokio::time::sleep(ARP_SETTLE).await;
 }
 
-/// Lookup vendor from MAC address OUI (first 3 bytes)
-/// This is a small embedded database of common vendors
-fn lookup_vendor(mac: &str) -> Option<String> {
-    let oui = mac.get(0..8)?.to_uppercase();
+/// `arp` arguments that print the entry for `ip`
+fn arp_args(ip: Ipv4Addr) -> [String; 2] {
+    // Windows: arp -a <ip>; elsewhere -n skips the reverse DNS lookup
+    let flag = if cfg!(target_os = "windows") { "-a" } else { "-n" };
+    [flag.to_string(), ip.to_string()]
+}
 
-    OUI_DATABASE.get(oui.as_str()).map(|s| s.to_string())
+/// Find the MAC on the `arp` output line for `ip`. Windows prints
+/// "192.168.1.1    aa-bb-cc-dd-ee-ff   dynamic"; Unix variants put an
+/// aa:bb:cc:dd:ee:ff somewhere on the line.
+fn parse_arp_output(stdout: &str, ip: Ipv4Addr) -> Option<MacInfo> {
+    let ip = ip.to_string();
+    stdout
+        .lines()
+        .filter(|line| {
+            line.split_whitespace()
+                .any(|part| part.trim_matches(|c| c == '(' || c == ')') == ip)
+        })
+        .flat_map(|line| line.split_whitespace())
+        .find_map(normalize_mac)
+        .map(|mac| MacInfo {
+            vendor: lookup_vendor(&mac),
+            address: mac,
+        })
+}
+
+/// "AA:BB:CC:DD:EE:FF" from a MAC written with colons, dashes, Cisco's dots
+/// (aabb.ccdd.eeff) or no separators, in either case; None for anything else
+pub fn normalize_mac(input: &str) -> Option<String> {
+    let input = input.trim();
+    let dotted = input.contains('.');
+    let groups: Vec<&str> = if dotted {
+        input.split('.').collect()
+    } else {
+        input.split([':', '-']).collect()
+    };
+    let valid = match groups.len() {
+        1 => groups[0].len() == 12,
+        3 => dotted && groups.iter().all(|g| g.len() == 4),
+        6 => groups.iter().all(|g| g.len() == 2),
+        _ => false,
+    };
+    let hex = groups.concat();
+    if !valid || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_ascii_uppercase();
+    let octets: Vec<&str> = (0..12).step_by(2).map(|i| &hex[i..i + 2]).collect();
+    Some(octets.join(":"))
+}
+
+/// Vendor of a MAC in any form [`normalize_mac`] takes, from the OUI (first
+/// 3 bytes) in a small embedded database of common vendors
+pub fn lookup_vendor(mac: &str) -> Option<String> {
+    let mac = normalize_mac(mac)?;
+    OUI_DATABASE.get(&mac[..8]).map(|s| s.to_string())
 }
 
 lazy_static::lazy_static! {
@@ -1117,3 +1193,104 @@ lazy_static::lazy_static! {
         m
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_windows_and_unix_arp_output() {
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let windows = "Interface: 192.168.1.50 --- 0x5\n  Internet Address      Physical Address      Type\n  192.168.1.1           00-50-56-c0-00-08     dynamic\n";
+        let mac = parse_arp_output(windows, ip).unwrap();
+        assert_eq!(mac.address, "00:50:56:C0:00:08");
+        assert_eq!(mac.vendor.as_deref(), Some("VMware"));
+
+        let linux = "Address HWtype HWaddress Flags Mask Iface\n192.168.1.1 ether 08:00:27:aa:bb:cc C eth0\n";
+        assert_eq!(parse_arp_output(linux, ip).unwrap().address, "08:00:27:AA:BB:CC");
+
+        let bsd = "? (192.168.1.1) at 52:54:0:1:2:3 on en0\n";
+        assert!(parse_arp_output(bsd, ip).is_none());
+        let bsd = "? (192.168.1.1) at 52:54:00:01:02:03 on en0 ifscope [ethernet]\n";
+        assert_eq!(parse_arp_output(bsd, ip).unwrap().address, "52:54:00:01:02:03");
+    }
+
+    #[test]
+    fn macs_normalize_from_any_notation() {
+        let expected = Some("F0:27:2D:0A:BB:01".to_string());
+        for input in [
+            "f0:27:2d:0a:bb:01",
+            "F0-27-2D-0A-BB-01",
+            "f027.2d0a.bb01",
+            " F0272D0ABB01 ",
+        ] {
+            assert_eq!(normalize_mac(input), expected, "{}", input);
+        }
+        for input in [
+            "",
+            "f0:27:2d:0a:bb",
+            "f027.2d0a.bb0g",
+            "f02.72d0a.bb01",
+            "192.168.1.1",
+            "f0:27:2d:0a:bb:01:02",
+        ] {
+            assert_eq!(normalize_mac(input), None, "{}", input);
+        }
+        assert_eq!(lookup_vendor("f027.2d0a.bb01").as_deref(), Some("eero"));
+        assert_eq!(lookup_vendor("f0-27-2d-0a-bb-01").as_deref(), Some("eero"));
+        assert_eq!(lookup_vendor("not a mac"), None);
+    }
+
+    #[test]
+    fn ignores_entries_for_other_addresses() {
+        // 192.168.1.1 is a prefix of 192.168.1.10
+        let output = "192.168.1.10 ether 08:00:27:aa:bb:cc C eth0\n";
+        assert!(parse_arp_output(output, Ipv4Addr::new(192, 168, 1, 1)).is_none());
+        let missing = "192.168.1.1 (incomplete) eth0\n";
+        assert!(parse_arp_output(missing, Ipv4Addr::new(192, 168, 1, 1)).is_none());
+    }
+
+    #[tokio::test]
+    async fn prewarm_fills_in_macs_missing_from_the_arp_table() {
+        use std::collections::HashSet;
+        use std::sync::{Arc, Mutex};
+
+        let alive: Vec<Ipv4Addr> = (1..=10).map(|n| Ipv4Addr::new(192, 168, 1, n)).collect();
+        let coverage = |prewarm: bool| {
+            // The table starts out knowing the hosts this machine talked to,
+            // and learns any host a datagram is sent to
+            let table: Arc<Mutex<HashSet<Ipv4Addr>>> =
+                Arc::new(Mutex::new(alive[..3].iter().copied().collect()));
+            let warmed = Arc::new(Mutex::new(Vec::new()));
+            let alive = alive.clone();
+            async move {
+                let mut found = 0;
+                for &ip in &alive {
+                    let read = |ip: Ipv4Addr| {
+                        let known = table.lock().unwrap().contains(&ip);
+                        async move {
+                            known.then(|| MacInfo {
+                                address: format!("AA:BB:CC:DD:EE:{:02X}", ip.octets()[3]),
+                                vendor: None,
+                            })
+                        }
+                    };
+                    let warm = |ip: Ipv4Addr| {
+                        table.lock().unwrap().insert(ip);
+                        warmed.lock().unwrap().push(ip);
+                        async {}
+                    };
+                    if resolve_mac_with(ip, prewarm, read, warm).await.is_some() {
+                        found += 1;
+                    }
+                }
+                let warmed = warmed.lock().unwrap().len();
+                (found, warmed)
+            }
+        };
+
+        assert_eq!(coverage(false).await, (3, 0));
+        // Only the misses are sent a datagram
+        assert_eq!(coverage(true).await, (10, 7));
+    }
+}
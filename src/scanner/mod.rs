@@ -1,13 +1,34 @@
 pub mod adapters;
+pub mod discovery;
 pub mod dns;
+pub mod knock;
 pub mod mac;
+pub mod netbios;
 pub mod ping;
 pub mod port;
+pub mod probes;
 pub mod range;
+pub mod rate;
+pub mod wol;
 
-pub use adapters::{get_active_adapters, AdapterInfo};
-pub use dns::DnsResolver;
-pub use mac::{get_mac_address, MacInfo};
-pub use ping::{scan_hosts, HostStatus, PingMethod, PingResult, PingerConfig};
-pub use port::{get_service_name, PortScanner, PortScannerConfig, COMMON_PORTS};
+pub use adapters::{
+    adapter_for_range, get_active_adapters, get_default_gateway, get_default_gateways, is_local,
+    AdapterInfo,
+};
+pub use discovery::{discover, Announcement};
+pub use dns::{DnsResolver, Resolution};
+pub use knock::{knock, Knock, KnockEvent, KnockPlan, KnockProtocol, KnockSender};
+pub use mac::{lookup_vendor, normalize_mac, resolve_mac, MacInfo};
+pub use netbios::NetbiosResolver;
+pub use ping::{
+    check_source_ip, probe_hosts, probe_icmp, scan_hosts, HostStatus, IcmpUnavailable, PingMethod, PingResult,
+    Pinger, PingerConfig, Probe, ProbeError, DEFAULT_ICMP_PAYLOAD, MAX_ICMP_PAYLOAD, TCP_PROBE_PORTS,
+};
+pub use port::{
+    get_service_name, parse_ports, PortProgress, PortResult, PortScanner, PortScannerConfig,
+    PortState, COMMON_PORTS,
+    COMMON_PORTS_SPEC,
+};
+pub use probes::{ArpTable, BoxFuture, HostProbe, MacLookup, NameResolver, NetbiosLookup};
 pub use range::IpRange;
+pub use rate::RateLimiter;
@@ -0,0 +1,200 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+/// NetBIOS name service port
+pub const NETBIOS_PORT: u16 = 137;
+
+/// Longest wait for a node status answer. Windows and Samba machines answer
+/// within a few milliseconds; anything else says nothing, or refuses.
+pub const NETBIOS_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// NBSTAT, the node status query type
+const NBSTAT: u16 = 0x21;
+
+/// Name suffix of a machine's workstation service, which carries its name
+const WORKSTATION: u8 = 0x00;
+
+/// Asks hosts for their NetBIOS computer name with a node status query to
+/// UDP 137
+#[derive(Debug, Clone, Copy)]
+pub struct NetbiosResolver {
+    pub timeout: Duration,
+}
+
+impl Default for NetbiosResolver {
+    fn default() -> Self {
+        Self { timeout: NETBIOS_TIMEOUT }
+    }
+}
+
+impl NetbiosResolver {
+    /// The name `ip` answers with; None when it doesn't run NetBIOS
+    pub async fn name(&self, ip: Ipv4Addr) -> Option<String> {
+        query_name(SocketAddr::from((ip, NETBIOS_PORT)), self.timeout).await
+    }
+}
+
+/// The computer name `addr` reports in its node status, if it answers in
+/// time
+async fn query_name(addr: SocketAddr, wait: Duration) -> Option<String> {
+    let id = rand::random::<u16>();
+    let exchange = async {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await.ok()?;
+        // Connected, so a closed port's ICMP refusal ends the wait early
+        socket.connect(addr).await.ok()?;
+        socket.send(&node_status_request(id)).await.ok()?;
+        let mut buf = [0u8; 1024];
+        loop {
+            let len = socket.recv(&mut buf).await.ok()?;
+            if let Some(answer) = parse_node_status(&buf[..len], id) {
+                return answer;
+            }
+        }
+    };
+    let name = timeout(wait, exchange).await.ok().flatten();
+    tracing::debug!(%addr, ?name, "NetBIOS node status");
+    name
+}
+
+/// A node status query for the wildcard name `*`
+fn node_status_request(id: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(50);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Flags 0, one question, no other records
+    packet.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+    // First-level encoding: each byte of the 16-byte name as two letters
+    let mut name = [0u8; 16];
+    name[0] = b'*';
+    packet.push(32);
+    for byte in name {
+        packet.push(b'A' + (byte >> 4));
+        packet.push(b'A' + (byte & 0x0f));
+    }
+    packet.push(0);
+    packet.extend_from_slice(&NBSTAT.to_be_bytes());
+    // Class IN
+    packet.extend_from_slice(&1u16.to_be_bytes());
+    packet
+}
+
+/// The workstation name in an answer to query `id`: `None` when the packet
+/// isn't that answer, `Some(None)` when it is but names no workstation
+fn parse_node_status(packet: &[u8], id: u16) -> Option<Option<String>> {
+    let header = packet.get(..12)?;
+    let response = header[2] & 0x80 != 0;
+    let answers = u16::from_be_bytes([header[6], header[7]]);
+    if u16::from_be_bytes([header[0], header[1]]) != id || !response {
+        return None;
+    }
+    if answers == 0 {
+        return Some(None);
+    }
+    // Skip the answer's name: labels up to the root, or a pointer
+    let mut at = 12;
+    loop {
+        let len = *packet.get(at)? as usize;
+        if len == 0 {
+            at += 1;
+            break;
+        }
+        if len & 0xc0 == 0xc0 {
+            at += 2;
+            break;
+        }
+        at += 1 + len;
+    }
+    // Type, class, TTL and data length
+    let fixed = packet.get(at..at + 10)?;
+    if u16::from_be_bytes([fixed[0], fixed[1]]) != NBSTAT {
+        return Some(None);
+    }
+    at += 10;
+    let count = *packet.get(at)? as usize;
+    at += 1;
+    let name = packet
+        .get(at..at + count * 18)?
+        .chunks_exact(18)
+        // 15 name bytes, the suffix, then flags whose top bit marks a group
+        .find(|entry| entry[15] == WORKSTATION && entry[16] & 0x80 == 0)
+        .map(|entry| String::from_utf8_lossy(&entry[..15]).trim_end().to_string())
+        .filter(|name| !name.is_empty());
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A node status answer to `id` listing `names` as (name, suffix, group)
+    fn answer(id: u16, names: &[(&str, u8, bool)]) -> Vec<u8> {
+        let request = node_status_request(id);
+        let mut packet = request[..2].to_vec();
+        // Response, authoritative; no questions, one answer
+        packet.extend_from_slice(&[0x84, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+        packet.extend_from_slice(&request[12..46]);
+        packet.extend_from_slice(&NBSTAT.to_be_bytes());
+        packet.extend_from_slice(&[0, 1, 0, 0, 0, 0]);
+        let length = 1 + names.len() * 18 + 46;
+        packet.extend_from_slice(&(length as u16).to_be_bytes());
+        packet.push(names.len() as u8);
+        for &(name, suffix, group) in names {
+            packet.extend_from_slice(format!("{:<15}", name).as_bytes());
+            packet.push(suffix);
+            packet.extend_from_slice(&[if group { 0x84 } else { 0x04 }, 0]);
+        }
+        // Unit ID (the MAC) and statistics
+        packet.extend_from_slice(&[0; 46]);
+        packet
+    }
+
+    #[test]
+    fn request_asks_for_the_wildcard_name() {
+        let request = node_status_request(0x1234);
+        assert_eq!(request.len(), 50);
+        assert_eq!(&request[..4], &[0x12, 0x34, 0, 0]);
+        assert_eq!(&request[13..17], b"CKAA");
+        assert_eq!(&request[46..], &[0, 0x21, 0, 1]);
+    }
+
+    #[test]
+    fn the_unique_workstation_name_is_picked() {
+        let packet = answer(
+            7,
+            &[
+                ("WORKGROUP", 0x00, true),
+                ("LGTV", 0x20, false),
+                ("LGTV", 0x00, false),
+            ],
+        );
+        assert_eq!(parse_node_status(&packet, 7), Some(Some("LGTV".to_string())));
+        // Another query's answer, or a question, isn't ours
+        assert_eq!(parse_node_status(&packet, 8), None);
+        assert_eq!(parse_node_status(&node_status_request(7), 7), None);
+        assert_eq!(parse_node_status(&answer(7, &[("WORKGROUP", 0x00, true)]), 7), Some(None));
+        // Cut short
+        assert_eq!(parse_node_status(&packet[..60], 7), None);
+    }
+
+    #[tokio::test]
+    async fn query_reads_the_answer_and_gives_up_on_silence() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, from) = server.recv_from(&mut buf).await.unwrap();
+            let id = u16::from_be_bytes([buf[0], buf[1]]);
+            assert_eq!(len, 50);
+            server.send_to(&answer(id, &[("NAS", 0x00, false)]), from).await.unwrap();
+            // Then nothing more
+            let _ = server.recv_from(&mut buf).await;
+        });
+        assert_eq!(query_name(addr, Duration::from_secs(2)).await.as_deref(), Some("NAS"));
+
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let silent_addr = silent.local_addr().unwrap();
+        assert_eq!(query_name(silent_addr, Duration::from_millis(50)).await, None);
+    }
+}
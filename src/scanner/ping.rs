@@ -1,4 +1,5 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -7,6 +8,9 @@ use surge_ping::{Client, Config as PingConfig, PingIdentifier, PingSequence};
 use tokio::sync::{mpsc, Mutex, Semaphore};
 use tokio::time::timeout;
 
+use super::probes::HostProbe;
+use super::rate::RateLimiter;
+
 /// Result of a ping operation
 #[derive(Debug, Clone)]
 pub struct PingResult {
@@ -15,6 +19,66 @@ pub struct PingResult {
     pub rtt: Option<Duration>,
     pub method: PingMethod,
     pub status: HostStatus,
+    /// The probe that answered; `None` for offline hosts
+    pub probe: Option<Probe>,
+    /// Why no probe got an answer; `None` for online hosts
+    pub error: Option<ProbeError>,
+}
+
+/// Why probes to a host went unanswered. Anything but a timeout points at
+/// this end (no route, a local firewall) rather than at the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProbeError {
+    /// Nothing came back in time
+    Timeout,
+    NetworkUnreachable,
+    HostUnreachable,
+    /// Refused by this machine, e.g. a local firewall rule
+    PermissionDenied,
+    /// Any other socket error
+    Other(std::io::ErrorKind),
+}
+
+impl ProbeError {
+    pub fn from_io(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::TimedOut => ProbeError::Timeout,
+            std::io::ErrorKind::NetworkUnreachable => ProbeError::NetworkUnreachable,
+            std::io::ErrorKind::HostUnreachable => ProbeError::HostUnreachable,
+            std::io::ErrorKind::PermissionDenied => ProbeError::PermissionDenied,
+            kind => ProbeError::Other(kind),
+        }
+    }
+
+    /// The socket error behind an ICMP failure, found through its source;
+    /// anything else (surge-ping's own timeout) counts as a timeout
+    fn from_icmp(error: &(dyn std::error::Error + 'static)) -> Self {
+        std::iter::successors(Some(error), |e| e.source())
+            .find_map(|e| e.downcast_ref::<std::io::Error>())
+            .map_or(ProbeError::Timeout, Self::from_io)
+    }
+
+    /// The more telling of two errors for the same host: the first that
+    /// isn't a timeout
+    fn or(self, later: ProbeError) -> ProbeError {
+        if self == ProbeError::Timeout {
+            later
+        } else {
+            self
+        }
+    }
+}
+
+impl std::fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeError::Timeout => write!(f, "timeout"),
+            ProbeError::NetworkUnreachable => write!(f, "network unreachable"),
+            ProbeError::HostUnreachable => write!(f, "host unreachable"),
+            ProbeError::PermissionDenied => write!(f, "permission denied"),
+            ProbeError::Other(kind) => write!(f, "{}", kind),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -32,6 +96,74 @@ impl std::fmt::Display for PingMethod {
     }
 }
 
+/// Which probe got an answer from a host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Probe {
+    /// TCP port that accepted or refused the connection; `None` for an ICMP echo
+    pub port: Option<u16>,
+    /// Attempt that answered, from 1; more than one with retries configured
+    pub attempt: u32,
+    /// Every port tried refused instantly with the same timing: the resets
+    /// may come from a firewall or switch answering for a dead address
+    pub suspect: bool,
+}
+
+impl Probe {
+    /// e.g. "TCP:445 (ICMP blocked), attempt 2" or "ICMP echo"
+    pub fn describe(&self, status: HostStatus) -> String {
+        let mut text = match self.port {
+            Some(port) => format!("TCP:{}", port),
+            None => "ICMP echo".to_string(),
+        };
+        if status == HostStatus::OnlineNoIcmp {
+            text.push_str(" (ICMP blocked)");
+        }
+        if self.attempt > 1 {
+            text.push_str(&format!(", attempt {}", self.attempt));
+        }
+        text
+    }
+}
+
+/// Ports tried, in order, when ICMP gets no answer, unless
+/// [`PingerConfig::tcp_ports`] names others
+pub const TCP_PROBE_PORTS: [u16; 11] = [80, 443, 22, 445, 139, 135, 3389, 21, 23, 25, 53];
+
+/// Further ports tried after a refusal, to tell a host with closed ports
+/// from a device resetting connections on behalf of a dead address
+const RST_CHECK_PORTS: usize = 2;
+
+/// Refusals at least this fast count as instant
+const INSTANT_REFUSAL: Duration = Duration::from_millis(2);
+
+/// Largest spread between refusal times that still counts as identical
+const RST_TIMING_SPREAD: Duration = Duration::from_micros(300);
+
+/// What one TCP connection attempt got back
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TcpOutcome {
+    Open(Duration),
+    Refused(Duration),
+    NoAnswer(ProbeError),
+}
+
+/// Whether probe outcomes look like a reset proxy rather than a host: all
+/// refused, all instantly, and all within the same sliver of time. A real
+/// host's closed ports answer from the kernel too, so this is only a hint.
+fn rst_proxy_suspect(outcomes: &[TcpOutcome]) -> bool {
+    let mut times = Vec::with_capacity(outcomes.len());
+    for outcome in outcomes {
+        match outcome {
+            TcpOutcome::Refused(rtt) if *rtt <= INSTANT_REFUSAL => times.push(*rtt),
+            _ => return false,
+        }
+    }
+    let (Some(min), Some(max)) = (times.iter().min(), times.iter().max()) else {
+        return false;
+    };
+    times.len() > RST_CHECK_PORTS && *max - *min <= RST_TIMING_SPREAD
+}
+
 /// Status of the host detection
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HostStatus {
@@ -53,24 +185,152 @@ impl std::fmt::Display for HostStatus {
     }
 }
 
+/// Every probe outcome up to this many is written to the debug log; after
+/// that only one in [`PROBE_LOG_SAMPLE`], so a /16 doesn't flood it
+const PROBE_LOG_FIRST: usize = 64;
+const PROBE_LOG_SAMPLE: usize = 256;
+
+static PROBES_LOGGED: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether to write this probe outcome to the debug log
+fn sample_probe() -> bool {
+    let n = PROBES_LOGGED.fetch_add(1, Ordering::Relaxed);
+    n < PROBE_LOG_FIRST || n.is_multiple_of(PROBE_LOG_SAMPLE)
+}
+
 /// Pinger configuration
 #[derive(Debug, Clone)]
 pub struct PingerConfig {
     pub timeout: Duration,
     pub retries: u32,
     pub concurrent_limit: usize,
+    /// Count a refused TCP connection (port closed) as a live host. Off for
+    /// networks where a firewall sends resets for dead addresses.
+    pub refused_means_alive: bool,
+    /// Caps ICMP and TCP probes per second; share one with the port scanner
+    /// to cap both together
+    pub rate_limit: Option<Arc<RateLimiter>>,
+    /// Local address to send probes from, for hosts where the OS would pick
+    /// the wrong interface (a VPN); `None` leaves it to the OS
+    pub source_ip: Option<Ipv4Addr>,
+    /// ICMP echo payload in bytes, for finding MTU black holes; larger than
+    /// [`MAX_ICMP_PAYLOAD`] can't be sent
+    pub payload_size: usize,
+    /// TCP ports probed, in order, when ICMP gets no answer; empty leaves
+    /// ICMP as the only probe
+    pub tcp_ports: Vec<u16>,
 }
 
+/// Standard ping payload: 64-byte packets with the ICMP header
+pub const DEFAULT_ICMP_PAYLOAD: usize = 56;
+
+/// Largest echo payload an IPv4 packet can carry (65535 less the IP and
+/// ICMP headers)
+pub const MAX_ICMP_PAYLOAD: usize = 65_507;
+
 impl Default for PingerConfig {
     fn default() -> Self {
         Self {
             timeout: Duration::from_millis(300),
             retries: 0,
             concurrent_limit: 100,
+            refused_means_alive: true,
+            rate_limit: None,
+            source_ip: None,
+            payload_size: DEFAULT_ICMP_PAYLOAD,
+            tcp_ports: TCP_PROBE_PORTS.to_vec(),
+        }
+    }
+}
+
+/// Why this process can't send ICMP echo; scans then rely on TCP probes
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcmpUnavailable {
+    /// The socket error, e.g. "Operation not permitted (os error 1)"
+    pub reason: String,
+    /// What to run on this platform to allow ICMP; None when the error
+    /// isn't about privileges
+    pub remedy: Option<String>,
+}
+
+impl IcmpUnavailable {
+    fn from_error(error: &std::io::Error) -> Self {
+        Self {
+            reason: error.to_string(),
+            remedy: (error.kind() == std::io::ErrorKind::PermissionDenied)
+                .then(privilege_remedy),
+        }
+    }
+}
+
+impl std::fmt::Display for IcmpUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ICMP unavailable ({})", self.reason)?;
+        match &self.remedy {
+            Some(remedy) => write!(f, "; {}", remedy),
+            None => Ok(()),
         }
     }
 }
 
+/// Open an ICMP socket the way a scan does, to learn up front whether ICMP
+/// echo works here and, if not, why
+pub fn probe_icmp() -> std::result::Result<(), IcmpUnavailable> {
+    Client::new(&PingConfig::default())
+        .map(drop)
+        .map_err(|e| IcmpUnavailable::from_error(&e))
+}
+
+/// Check that probes can be sent from `source`, so a scan fails up front
+/// rather than finding every host offline
+pub fn check_source_ip(source: Ipv4Addr) -> Result<()> {
+    std::net::UdpSocket::bind((source, 0))
+        .map(drop)
+        .map_err(|e| anyhow::anyhow!("can't send from {}, not an address of this machine ({})", source, e))
+}
+
+/// TCP connect to `addr`, from `source` when one is set
+pub(super) async fn connect_from(
+    source: Option<Ipv4Addr>,
+    addr: SocketAddr,
+) -> std::io::Result<tokio::net::TcpStream> {
+    let Some(source) = source else {
+        return tokio::net::TcpStream::connect(addr).await;
+    };
+    let socket = tokio::net::TcpSocket::new_v4()?;
+    socket.bind(SocketAddr::new(IpAddr::V4(source), 0))?;
+    socket.connect(addr).await
+}
+
+/// Linux needs CAP_NET_RAW for raw ICMP, or a `ping_group_range` covering
+/// the user for unprivileged (DGRAM) ICMP
+#[cfg(target_os = "linux")]
+fn privilege_remedy() -> String {
+    let exe = std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "$(which ipscannr)".to_string());
+    format!(
+        "run: sudo setcap cap_net_raw+ep {} (or allow unprivileged ping: \
+         sudo sysctl -w net.ipv4.ping_group_range=\"0 2147483647\")",
+        exe
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn privilege_remedy() -> String {
+    "run: sudo ipscannr".to_string()
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn privilege_remedy() -> String {
+    "run ipscannr as root".to_string()
+}
+
+#[cfg(windows)]
+fn privilege_remedy() -> String {
+    "run ipscannr from an Administrator terminal".to_string()
+}
+
 /// Pinger for host discovery
 pub struct Pinger {
     config: PingerConfig,
@@ -83,8 +343,20 @@ impl Pinger {
         let semaphore = Arc::new(Semaphore::new(config.concurrent_limit));
         
         // Try to create ICMP client - may fail without admin privileges
-        let icmp_client = Client::new(&PingConfig::default()).ok().map(Arc::new);
-        
+        let icmp_config = match config.source_ip {
+            Some(source) => PingConfig::builder()
+                .bind(SocketAddr::new(IpAddr::V4(source), 0))
+                .build(),
+            None => PingConfig::default(),
+        };
+        let icmp_client = match Client::new(&icmp_config) {
+            Ok(client) => Some(Arc::new(client)),
+            Err(e) => {
+                tracing::debug!(icmp_unavailable_reason = %e, "using TCP probes only");
+                None
+            }
+        };
+
         Self {
             config,
             semaphore,
@@ -102,46 +374,78 @@ impl Pinger {
                 rtt: None,
                 method: PingMethod::Icmp,
                 status: HostStatus::Offline,
+                probe: None,
+                error: None,
             };
         }
         let _permit = permit.ok();
+        let mut error = ProbeError::Timeout;
 
         // Try ICMP ping first if we have a client
         if let Some(client) = &self.icmp_client {
             for attempt in 0..=self.config.retries {
-                if let Some(rtt) = self.icmp_ping(client, ip, attempt as u16).await {
-                    return PingResult {
-                        ip,
-                        is_alive: true,
-                        rtt: Some(rtt),
-                        method: PingMethod::Icmp,
-                        status: HostStatus::Online,
-                    };
+                match self.icmp_ping(client, ip, attempt as u16).await {
+                    Ok(rtt) => {
+                        return PingResult {
+                            ip,
+                            is_alive: true,
+                            rtt: Some(rtt),
+                            method: PingMethod::Icmp,
+                            status: HostStatus::Online,
+                            probe: Some(Probe {
+                                port: None,
+                                attempt: attempt + 1,
+                                suspect: false,
+                            }),
+                            error: None,
+                        };
+                    }
+                    Err(e) => error = error.or(e),
                 }
             }
         }
 
         // ICMP failed or not available - try TCP probes to common ports
-        let ports = [80, 443, 22, 445, 139, 135, 3389, 21, 23, 25, 53];
-        
-        for _ in 0..=self.config.retries {
-            for &port in &ports {
-                if let Some(rtt) = self.tcp_ping(ip, port).await {
-                    // Host has open port but doesn't respond to ICMP
-                    let status = if self.icmp_client.is_some() {
-                        HostStatus::OnlineNoIcmp
-                    } else {
-                        HostStatus::Online
-                    };
-                    
-                    return PingResult {
-                        ip,
-                        is_alive: true,
-                        rtt: Some(rtt),
-                        method: PingMethod::Tcp,
-                        status,
-                    };
-                }
+        for attempt in 0..=self.config.retries {
+            for (i, &port) in self.config.tcp_ports.iter().enumerate() {
+                let (rtt, suspect) = match self.tcp_ping(ip, port).await {
+                    TcpOutcome::Open(rtt) => (rtt, false),
+                    // Connection refused means host is alive but port closed;
+                    // a few more ports tell it from resets sent on its behalf
+                    TcpOutcome::Refused(rtt) if self.config.refused_means_alive => {
+                        let mut outcomes = vec![TcpOutcome::Refused(rtt)];
+                        let others = self.config.tcp_ports.iter().cycle().skip(i + 1);
+                        for &other in others.take(RST_CHECK_PORTS) {
+                            outcomes.push(self.tcp_ping(ip, other).await);
+                        }
+                        (rtt, rst_proxy_suspect(&outcomes))
+                    }
+                    TcpOutcome::Refused(_) => continue,
+                    TcpOutcome::NoAnswer(e) => {
+                        error = error.or(e);
+                        continue;
+                    }
+                };
+                // Host has open port but doesn't respond to ICMP
+                let status = if self.icmp_client.is_some() {
+                    HostStatus::OnlineNoIcmp
+                } else {
+                    HostStatus::Online
+                };
+                
+                return PingResult {
+                    ip,
+                    is_alive: true,
+                    rtt: Some(rtt),
+                    method: PingMethod::Tcp,
+                    status,
+                    probe: Some(Probe {
+                        port: Some(port),
+                        attempt: attempt + 1,
+                        suspect,
+                    }),
+                    error: None,
+                };
             }
         }
 
@@ -156,12 +460,22 @@ impl Pinger {
                 PingMethod::Tcp
             },
             status: HostStatus::Offline,
+            probe: None,
+            error: Some(error),
         }
     }
 
-    async fn icmp_ping(&self, client: &Client, ip: Ipv4Addr, seq: u16) -> Option<Duration> {
-        let payload = [0; 56]; // Standard ping payload size
-        
+    async fn icmp_ping(
+        &self,
+        client: &Client,
+        ip: Ipv4Addr,
+        seq: u16,
+    ) -> std::result::Result<Duration, ProbeError> {
+        if let Some(limit) = &self.config.rate_limit {
+            limit.acquire().await;
+        }
+        let payload = vec![0; self.config.payload_size.min(MAX_ICMP_PAYLOAD)];
+
         let mut pinger = client.pinger(IpAddr::V4(ip), PingIdentifier(rand::random())).await;
         
         let result = timeout(
@@ -170,34 +484,42 @@ impl Pinger {
         )
         .await;
 
-        match result {
-            Ok(Ok((_packet, duration))) => Some(duration),
-            _ => None,
+        let rtt = match result {
+            Ok(Ok((_packet, duration))) => Ok(duration),
+            Ok(Err(e)) => Err(ProbeError::from_icmp(&e)),
+            Err(_) => Err(ProbeError::Timeout),
+        };
+        if sample_probe() {
+            tracing::debug!(%ip, seq, ?rtt, "icmp probe");
         }
+        rtt
     }
 
-    async fn tcp_ping(&self, ip: Ipv4Addr, port: u16) -> Option<Duration> {
+    async fn tcp_ping(&self, ip: Ipv4Addr, port: u16) -> TcpOutcome {
+        if let Some(limit) = &self.config.rate_limit {
+            limit.acquire().await;
+        }
         let start = Instant::now();
         let addr = SocketAddr::new(IpAddr::V4(ip), port);
 
         let result = timeout(
             self.config.timeout,
-            tokio::net::TcpStream::connect(addr),
+            connect_from(self.config.source_ip, addr),
         )
         .await;
 
-        match result {
-            Ok(Ok(_)) => Some(start.elapsed()),
-            Ok(Err(e)) => {
-                // Connection refused means host is alive but port closed
-                if e.kind() == std::io::ErrorKind::ConnectionRefused {
-                    Some(start.elapsed())
-                } else {
-                    None
-                }
+        let outcome = match result {
+            Ok(Ok(_)) => TcpOutcome::Open(start.elapsed()),
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                TcpOutcome::Refused(start.elapsed())
             }
-            Err(_) => None, // Timeout
+            Ok(Err(e)) => TcpOutcome::NoAnswer(ProbeError::from_io(&e)),
+            Err(_) => TcpOutcome::NoAnswer(ProbeError::Timeout),
+        };
+        if sample_probe() {
+            tracing::debug!(%ip, port, ?outcome, "tcp probe");
         }
+        outcome
     }
 
 }
@@ -208,8 +530,19 @@ pub async fn scan_hosts(
     config: PingerConfig,
     progress_tx: tokio::sync::mpsc::Sender<PingResult>,
 ) -> Result<()> {
-    let pinger = Arc::new(Pinger::new(config));
-    let worker_count = pinger.config.concurrent_limit.max(1);
+    let workers = config.concurrent_limit;
+    probe_hosts(addresses, Arc::new(Pinger::new(config)), workers, progress_tx).await
+}
+
+/// [`scan_hosts`] with any [`HostProbe`], checking up to `worker_count`
+/// hosts at once. Results arrive in the order hosts answer.
+pub async fn probe_hosts(
+    addresses: Vec<Ipv4Addr>,
+    pinger: Arc<dyn HostProbe>,
+    worker_count: usize,
+    progress_tx: tokio::sync::mpsc::Sender<PingResult>,
+) -> Result<()> {
+    let worker_count = worker_count.max(1);
     let (job_tx, job_rx) = mpsc::channel::<Ipv4Addr>(worker_count.saturating_mul(2));
     let shared_rx = Arc::new(Mutex::new(job_rx));
 
@@ -248,3 +581,64 @@ pub async fn scan_hosts(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refused(micros: u64) -> TcpOutcome {
+        TcpOutcome::Refused(Duration::from_micros(micros))
+    }
+
+    #[test]
+    fn permission_errors_come_with_a_remedy() {
+        let denied = IcmpUnavailable::from_error(&std::io::Error::from_raw_os_error(1));
+        let remedy = denied.remedy.as_deref().expect("remedy for EPERM");
+        if cfg!(target_os = "linux") {
+            assert!(remedy.contains("setcap cap_net_raw+ep"), "{}", remedy);
+        }
+        assert!(denied.to_string().starts_with("ICMP unavailable ("), "{}", denied);
+
+        let other = IcmpUnavailable::from_error(&std::io::Error::other("no route"));
+        assert_eq!(other.remedy, None);
+        assert_eq!(other.to_string(), "ICMP unavailable (no route)");
+    }
+
+    #[test]
+    fn probe_errors_keep_the_most_telling_cause() {
+        use std::io::{Error, ErrorKind};
+
+        let unreachable = ProbeError::from_io(&Error::from(ErrorKind::NetworkUnreachable));
+        assert_eq!(unreachable, ProbeError::NetworkUnreachable);
+        assert_eq!(unreachable.to_string(), "network unreachable");
+        assert_eq!(ProbeError::from_io(&Error::from(ErrorKind::TimedOut)), ProbeError::Timeout);
+        let other = ProbeError::from_io(&Error::from(ErrorKind::AddrNotAvailable));
+        assert_eq!(other, ProbeError::Other(ErrorKind::AddrNotAvailable));
+
+        // An ICMP error wrapping a socket error reports that error
+        let wrapped = anyhow::Error::new(Error::from(ErrorKind::HostUnreachable)).context("ping");
+        assert_eq!(ProbeError::from_icmp(wrapped.as_ref()), ProbeError::HostUnreachable);
+        assert_eq!(ProbeError::from_icmp(&std::fmt::Error), ProbeError::Timeout);
+
+        // A specific error wins over timeouts, whichever came first
+        assert_eq!(ProbeError::Timeout.or(unreachable), unreachable);
+        assert_eq!(unreachable.or(ProbeError::Timeout), unreachable);
+        assert_eq!(unreachable.or(ProbeError::PermissionDenied), unreachable);
+    }
+
+    #[test]
+    fn identical_instant_refusals_are_suspect() {
+        assert!(rst_proxy_suspect(&[refused(410), refused(420), refused(400)]));
+        // Spread out like separate services, or too slow to be instant
+        assert!(!rst_proxy_suspect(&[refused(400), refused(900), refused(420)]));
+        assert!(!rst_proxy_suspect(&[refused(4_000), refused(4_050), refused(4_020)]));
+        // Anything but a refusal means a real host
+        let timeout = TcpOutcome::NoAnswer(ProbeError::Timeout);
+        assert!(!rst_proxy_suspect(&[refused(400), timeout, refused(400)]));
+        let open = TcpOutcome::Open(Duration::from_micros(400));
+        assert!(!rst_proxy_suspect(&[refused(400), open, refused(400)]));
+        // Too few ports to judge
+        assert!(!rst_proxy_suspect(&[refused(400), refused(400)]));
+        assert!(!rst_proxy_suspect(&[]));
+    }
+}
@@ -5,6 +5,9 @@ use std::time::Duration;
 use tokio::sync::{mpsc, Mutex, Semaphore};
 use tokio::time::timeout;
 
+use super::ping::connect_from;
+use super::rate::RateLimiter;
+
 /// Common ports to scan by default
 pub const COMMON_PORTS: &[u16] = &[
     21,    // FTP
@@ -34,6 +37,9 @@ pub const COMMON_PORTS: &[u16] = &[
     27017, // MongoDB
 ];
 
+/// How a scan of [`COMMON_PORTS`] is recorded on a host
+pub const COMMON_PORTS_SPEC: &str = "common";
+
 /// Get service name for a port
 pub fn get_service_name(port: u16) -> &'static str {
     lazy_static_services().get(&port).copied().unwrap_or("unknown")
@@ -74,20 +80,54 @@ fn lazy_static_services() -> &'static HashMap<u16, &'static str> {
     })
 }
 
+/// Pause before connecting again after a timed-out attempt
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// What a port's connect attempts came back with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortState {
+    Open,
+    /// Refused or otherwise answered with an error
+    Closed,
+    /// Every attempt timed out
+    Filtered,
+}
+
 /// Result of a port scan
 #[derive(Debug, Clone)]
 pub struct PortResult {
     pub port: u16,
-    pub is_open: bool,
+    pub state: PortState,
     #[allow(dead_code)]
     pub service: &'static str,
 }
 
+impl PortResult {
+    pub fn is_open(&self) -> bool {
+        self.state == PortState::Open
+    }
+}
+
+/// How far a host's port scan has got, reported after each port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortProgress {
+    pub ip: Ipv4Addr,
+    pub scanned: usize,
+    pub total: usize,
+}
+
 /// Port scanner configuration
 #[derive(Debug, Clone)]
 pub struct PortScannerConfig {
     pub timeout: Duration,
     pub concurrent_limit: usize,
+    /// Connect attempts after one times out; a refusal is never retried.
+    /// Wi-Fi devices waking their radio often drop the first SYN.
+    pub retries: u32,
+    /// Caps connect attempts per second, shared with whatever else holds it
+    pub rate_limit: Option<Arc<RateLimiter>>,
+    /// Local address to connect from; `None` leaves it to the OS
+    pub source_ip: Option<Ipv4Addr>,
 }
 
 impl Default for PortScannerConfig {
@@ -95,6 +135,9 @@ impl Default for PortScannerConfig {
         Self {
             timeout: Duration::from_millis(500),
             concurrent_limit: 50,
+            retries: 1,
+            rate_limit: None,
+            source_ip: None,
         }
     }
 }
@@ -111,13 +154,18 @@ impl PortScanner {
         Self { config, semaphore }
     }
 
+    /// Local address connects are made from
+    pub(super) fn source_ip(&self) -> Option<Ipv4Addr> {
+        self.config.source_ip
+    }
+
     /// Scan a single port on a host
     pub async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> PortResult {
         let permit = self.semaphore.acquire().await;
         if permit.is_err() {
             return PortResult {
                 port,
-                is_open: false,
+                state: PortState::Closed,
                 service: get_service_name(port),
             };
         }
@@ -125,23 +173,41 @@ impl PortScanner {
 
         let addr = SocketAddr::new(IpAddr::V4(ip), port);
 
-        let is_open = timeout(
-            self.config.timeout,
-            tokio::net::TcpStream::connect(addr),
-        )
-        .await
-        .map(|r| r.is_ok())
-        .unwrap_or(false);
+        let mut state = PortState::Filtered;
+        for attempt in 0..=self.config.retries {
+            if attempt > 0 {
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            if let Some(limit) = &self.config.rate_limit {
+                limit.acquire().await;
+            }
+            match timeout(self.config.timeout, connect_from(self.config.source_ip, addr)).await {
+                Ok(Ok(_)) => state = PortState::Open,
+                Ok(Err(_)) => state = PortState::Closed,
+                Err(_) => continue,
+            }
+            break;
+        }
 
         PortResult {
             port,
-            is_open,
+            state,
             service: get_service_name(port),
         }
     }
 
     /// Scan multiple ports on a host
     pub async fn scan_ports(&self, ip: Ipv4Addr, ports: &[u16]) -> Vec<PortResult> {
+        self.scan_ports_reporting(ip, ports, |_| {}).await
+    }
+
+    /// [`PortScanner::scan_ports`], calling `progress` as each port finishes
+    pub async fn scan_ports_reporting(
+        &self,
+        ip: Ipv4Addr,
+        ports: &[u16],
+        mut progress: impl FnMut(PortProgress),
+    ) -> Vec<PortResult> {
         let worker_count = self.config.concurrent_limit.max(1);
         let (job_tx, job_rx) = mpsc::channel::<u16>(worker_count.saturating_mul(2));
         let (result_tx, mut result_rx) = mpsc::channel::<PortResult>(ports.len().max(1));
@@ -180,6 +246,11 @@ impl PortScanner {
         let mut results = Vec::new();
         while let Some(result) = result_rx.recv().await {
             results.push(result);
+            progress(PortProgress {
+                ip,
+                scanned: results.len(),
+                total: ports.len(),
+            });
         }
 
         for worker in workers {
@@ -201,7 +272,6 @@ impl PortScanner {
 
 /// Parse port specification string
 /// Formats: "80", "80,443,8080", "1-1024", "80,443,1000-2000"
-#[allow(dead_code)]
 pub fn parse_ports(input: &str) -> Vec<u16> {
     let mut ports = Vec::new();
 
@@ -232,7 +302,101 @@ pub fn parse_ports(input: &str) -> Vec<u16> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_ports;
+    use std::time::Instant;
+
+    use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+    use crate::scanner::check_source_ip;
+
+    use super::*;
+
+    fn scanner(retries: u32) -> PortScanner {
+        PortScanner::new(PortScannerConfig {
+            timeout: Duration::from_millis(200),
+            concurrent_limit: 1,
+            retries,
+            rate_limit: None,
+            source_ip: None,
+        })
+    }
+
+    /// A listener whose accept queue is already full, so the kernel drops
+    /// new SYNs like a sleeping radio would, and the connection filling it
+    async fn saturated_listener() -> (TcpListener, TcpStream) {
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(0).unwrap();
+        let queued = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        (listener, queued)
+    }
+
+    #[tokio::test]
+    async fn timed_out_connect_is_retried_before_calling_the_port_filtered() {
+        let (listener, _queued) = saturated_listener().await;
+        let port = listener.local_addr().unwrap().port();
+        // Make room only once the first attempt is under way
+        let waker = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let accepted = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            drop(accepted);
+        });
+        let result = scanner(1).scan_port(Ipv4Addr::LOCALHOST, port).await;
+        assert_eq!(result.state, PortState::Open);
+        waker.abort();
+    }
+
+    #[tokio::test]
+    async fn port_is_filtered_once_every_attempt_times_out() {
+        let (listener, _queued) = saturated_listener().await;
+        let port = listener.local_addr().unwrap().port();
+        let result = scanner(0).scan_port(Ipv4Addr::LOCALHOST, port).await;
+        assert_eq!(result.state, PortState::Filtered);
+        assert!(!result.is_open());
+    }
+
+    #[tokio::test]
+    async fn refused_connect_is_not_retried() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let started = Instant::now();
+        let result = scanner(3).scan_port(Ipv4Addr::LOCALHOST, port).await;
+        assert_eq!(result.state, PortState::Closed);
+        assert!(started.elapsed() < RETRY_DELAY, "{:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn progress_counts_each_finished_port() {
+        let ports: Vec<u16> = (0..3)
+            .map(|_| std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port())
+            .collect();
+        let mut seen = Vec::new();
+        let results = scanner(0)
+            .scan_ports_reporting(Ipv4Addr::LOCALHOST, &ports, |p| seen.push((p.scanned, p.total)))
+            .await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(seen, [(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn connects_from_the_source_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let scanner = PortScanner::new(PortScannerConfig {
+            source_ip: Some(Ipv4Addr::LOCALHOST),
+            ..PortScannerConfig::default()
+        });
+        assert!(scanner.scan_port(Ipv4Addr::LOCALHOST, port).await.is_open());
+        let (_, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer.ip(), Ipv4Addr::LOCALHOST);
+
+        assert!(check_source_ip(Ipv4Addr::LOCALHOST).is_ok());
+        // TEST-NET-1 is never assigned to a local interface
+        let error = check_source_ip(Ipv4Addr::new(192, 0, 2, 1)).unwrap_err();
+        assert!(error.to_string().contains("not an address of this machine"), "{}", error);
+    }
 
     #[test]
     fn parse_ports_handles_single_and_list_values() {
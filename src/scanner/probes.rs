@@ -0,0 +1,67 @@
+use std::future::Future;
+use std::net::Ipv4Addr;
+use std::pin::Pin;
+
+use super::{resolve_mac, DnsResolver, MacInfo, NetbiosResolver, PingResult, Pinger, Resolution};
+
+/// Future returned by the probe traits; boxed so they can be used as trait
+/// objects and spawned onto the runtime
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Tells whether a host is up. [`Pinger`] is the real one; a scan driven
+/// through [`super::probe_hosts`] works with any implementation.
+pub trait HostProbe: Send + Sync {
+    fn ping(&self, ip: Ipv4Addr) -> BoxFuture<'_, PingResult>;
+}
+
+/// Looks up a host's name. [`DnsResolver`] is the real one.
+pub trait NameResolver: Send + Sync {
+    fn resolve(&self, ip: Ipv4Addr) -> BoxFuture<'_, Resolution>;
+
+    /// Look `ip` up again, ignoring any remembered answer
+    fn refresh(&self, ip: Ipv4Addr) -> BoxFuture<'_, Resolution>;
+}
+
+/// Asks a host for its NetBIOS computer name. [`NetbiosResolver`] is the
+/// real one.
+pub trait NetbiosLookup: Send + Sync {
+    fn name(&self, ip: Ipv4Addr) -> BoxFuture<'_, Option<String>>;
+}
+
+/// Finds the MAC behind a live host. [`ArpTable`] is the real one.
+pub trait MacLookup: Send + Sync {
+    /// `prewarm` as in [`resolve_mac`]
+    fn lookup(&self, ip: Ipv4Addr, prewarm: bool) -> BoxFuture<'_, Option<MacInfo>>;
+}
+
+impl HostProbe for Pinger {
+    fn ping(&self, ip: Ipv4Addr) -> BoxFuture<'_, PingResult> {
+        Box::pin(Pinger::ping(self, ip))
+    }
+}
+
+impl NameResolver for DnsResolver {
+    fn resolve(&self, ip: Ipv4Addr) -> BoxFuture<'_, Resolution> {
+        Box::pin(DnsResolver::resolve(self, ip))
+    }
+
+    fn refresh(&self, ip: Ipv4Addr) -> BoxFuture<'_, Resolution> {
+        Box::pin(DnsResolver::refresh(self, ip))
+    }
+}
+
+impl NetbiosLookup for NetbiosResolver {
+    fn name(&self, ip: Ipv4Addr) -> BoxFuture<'_, Option<String>> {
+        Box::pin(NetbiosResolver::name(self, ip))
+    }
+}
+
+/// The operating system's ARP table, read through [`resolve_mac`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArpTable;
+
+impl MacLookup for ArpTable {
+    fn lookup(&self, ip: Ipv4Addr, prewarm: bool) -> BoxFuture<'_, Option<MacInfo>> {
+        Box::pin(resolve_mac(ip, prewarm))
+    }
+}
@@ -19,6 +19,16 @@ impl IpRange {
     /// - Range with full IPs: 192.168.1.1-192.168.1.254
     /// - Comma separated: 192.168.1.1,192.168.1.2,192.168.1.3
     pub fn parse(input: &str) -> Result<Self> {
+        let addresses = Self::spans(input)?
+            .into_iter()
+            .flat_map(|(start, end)| (start..=end).map(Ipv4Addr::from))
+            .collect();
+        Ok(Self { addresses })
+    }
+
+    /// First and last address (inclusive) of each part of `input`, in the
+    /// formats [`IpRange::parse`] takes, without listing the addresses
+    fn spans(input: &str) -> Result<Vec<(u32, u32)>> {
         let input = input.trim();
 
         if input.is_empty() {
@@ -32,38 +42,32 @@ impl IpRange {
 
         // Check for CIDR notation
         if input.contains('/') {
-            return Self::parse_cidr(input);
+            return Ok(vec![Self::parse_cidr(input)?]);
         }
 
         // Check for range notation
         if input.contains('-') {
-            return Self::parse_range(input);
+            return Ok(vec![Self::parse_range(input)?]);
         }
 
         // Single IP
-        let addr = Ipv4Addr::from_str(input)
-            .map_err(|_| anyhow!("Invalid IP address: {}", input))?;
+        let addr = u32::from(Self::parse_single(input)?);
+        Ok(vec![(addr, addr)])
+    }
 
-        Ok(Self {
-            addresses: vec![addr],
-        })
+    fn parse_single(input: &str) -> Result<Ipv4Addr> {
+        Ipv4Addr::from_str(input).map_err(|_| anyhow!("Invalid IP address: {}", input))
     }
 
-    fn parse_cidr(input: &str) -> Result<Self> {
+    fn parse_cidr(input: &str) -> Result<(u32, u32)> {
         let network: Ipv4Network = input
             .parse()
             .map_err(|_| anyhow!("Invalid CIDR notation: {}", input))?;
 
-        let addresses: Vec<Ipv4Addr> = network.iter().collect();
-
-        if addresses.is_empty() {
-            return Err(anyhow!("CIDR range is empty"));
-        }
-
-        Ok(Self { addresses })
+        Ok((u32::from(network.network()), u32::from(network.broadcast())))
     }
 
-    fn parse_range(input: &str) -> Result<Self> {
+    fn parse_range(input: &str) -> Result<(u32, u32)> {
         let parts: Vec<&str> = input.split('-').collect();
 
         if parts.len() != 2 {
@@ -94,15 +98,11 @@ impl IpRange {
             return Err(anyhow!("Start IP is greater than end IP"));
         }
 
-        let addresses: Vec<Ipv4Addr> = (start_u32..=end_u32)
-            .map(Ipv4Addr::from)
-            .collect();
-
-        Ok(Self { addresses })
+        Ok((start_u32, end_u32))
     }
 
-    fn parse_comma_list(input: &str) -> Result<Self> {
-        let mut addresses = Vec::new();
+    fn parse_comma_list(input: &str) -> Result<Vec<(u32, u32)>> {
+        let mut spans = Vec::new();
 
         for part in input.split(',') {
             let part = part.trim();
@@ -111,24 +111,64 @@ impl IpRange {
             }
 
             // Each part could be a single IP, CIDR, or range
-            let range = if part.contains('/') {
+            let span = if part.contains('/') {
                 Self::parse_cidr(part)?
             } else if part.contains('-') {
                 Self::parse_range(part)?
             } else {
-                let addr = Ipv4Addr::from_str(part)
-                    .map_err(|_| anyhow!("Invalid IP address: {}", part))?;
-                Self { addresses: vec![addr] }
+                let addr = u32::from(Self::parse_single(part)?);
+                (addr, addr)
             };
 
-            addresses.extend(range.addresses);
+            spans.push(span);
         }
 
-        if addresses.is_empty() {
+        if spans.is_empty() {
             return Err(anyhow!("No valid IP addresses found"));
         }
 
-        Ok(Self { addresses })
+        Ok(spans)
+    }
+
+    /// Parse a batch: ranges separated by `;`, each scanned on its own and
+    /// cached under its own text. Unlike `,`, which merges addresses into one
+    /// range. Input without `;` is a batch of one.
+    pub fn parse_batch(input: &str) -> Result<Vec<(String, Self)>> {
+        let ranges = input
+            .split(';')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| Ok((part.to_string(), Self::parse(part)?)))
+            .collect::<Result<Vec<_>>>()?;
+        if ranges.is_empty() {
+            return Err(anyhow!("Empty IP range"));
+        }
+        Ok(ranges)
+    }
+
+    /// How many distinct addresses the batch `input` covers, worked out from
+    /// its spans: cheap even for a /8, or a /1 on the way to typing a /16
+    pub fn count_batch(input: &str) -> Result<usize> {
+        let mut spans = Vec::new();
+        for part in input.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+            spans.extend(Self::spans(part)?);
+        }
+        if spans.is_empty() {
+            return Err(anyhow!("Empty IP range"));
+        }
+        spans.sort_unstable();
+        let mut count = 0u64;
+        let mut covered_to: Option<u32> = None;
+        for (start, end) in spans {
+            let from = match covered_to {
+                Some(last) if last >= end => continue,
+                Some(last) if last >= start => last + 1,
+                _ => start,
+            };
+            count += u64::from(end - from) + 1;
+            covered_to = Some(end);
+        }
+        Ok(usize::try_from(count).unwrap_or(usize::MAX))
     }
 
     pub fn addresses(&self) -> &[Ipv4Addr] {
@@ -174,4 +214,25 @@ mod tests {
         let range = IpRange::parse("192.168.1.1-192.168.1.5").unwrap();
         assert_eq!(range.len(), 5);
     }
+
+    #[test]
+    fn test_batch() {
+        let batch = IpRange::parse_batch(" 10.0.1.0/30; 10.0.2.1-3 ;").unwrap();
+        let texts: Vec<&str> = batch.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(texts, ["10.0.1.0/30", "10.0.2.1-3"]);
+        assert_eq!(batch[1].1.len(), 3);
+        assert_eq!(IpRange::parse_batch("10.0.0.1,10.0.0.2").unwrap().len(), 1);
+        assert!(IpRange::parse_batch("10.0.0.1; nope").is_err());
+        assert!(IpRange::parse_batch(" ; ").is_err());
+    }
+
+    #[test]
+    fn batch_count_merges_overlaps_without_listing() {
+        assert_eq!(IpRange::count_batch("10.0.0.0/24; 10.0.0.128/25").unwrap(), 256);
+        assert_eq!(IpRange::count_batch("10.0.0.1-5, 10.0.0.4-9; 10.0.1.1").unwrap(), 10);
+        assert_eq!(IpRange::count_batch("10.0.0.0/14").unwrap(), 262_144);
+        assert_eq!(IpRange::count_batch("0.0.0.0/0").unwrap(), 1 << 32);
+        assert!(IpRange::count_batch("10.0.0.0/33").is_err());
+        assert!(IpRange::count_batch(" ; ").is_err());
+    }
 }
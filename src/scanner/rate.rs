@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long after a probe last had to wait the limiter still counts as
+/// throttling, so the status bar doesn't flicker between probes
+const THROTTLE_HOLD: Duration = Duration::from_secs(1);
+
+/// Token bucket capping probes per second across every task that shares it.
+/// Concurrency limits alone don't: a hundred probes timing out after a
+/// second still go out in one burst.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_sec: u32,
+    bucket: Mutex<Bucket>,
+}
+
+/// Token bucket kept as the time the next token is due, so the math stays
+/// in whole nanoseconds
+#[derive(Debug)]
+struct Bucket {
+    /// Time between tokens
+    interval: Duration,
+    /// How far ahead of the schedule a probe may go: idle time saves up a
+    /// tenth of a second's worth of tokens
+    burst: Duration,
+    /// When the next token is due; later than now while probes are queued
+    next: Instant,
+    /// When the last probe that had to wait was let through
+    waited_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(per_sec: u32, now: Instant) -> Self {
+        let interval = Duration::from_secs(1) / per_sec;
+        Self {
+            interval,
+            burst: interval * (per_sec.div_ceil(10).max(1) - 1),
+            next: now,
+            waited_until: None,
+        }
+    }
+
+    /// Claim the next token at `now`; returns how long to wait before
+    /// probing. Claims queue up, so waiters go out one interval apart.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let due = self.next.max(now);
+        let wait = due.saturating_duration_since(now + self.burst);
+        self.next = due + self.interval;
+        if !wait.is_zero() {
+            self.waited_until = Some(now + wait);
+        }
+        wait
+    }
+
+    fn is_throttled(&self, now: Instant) -> bool {
+        self.waited_until.is_some_and(|until| now < until + THROTTLE_HOLD)
+    }
+}
+
+impl RateLimiter {
+    /// At most `per_sec` probes a second; 0 counts as 1
+    pub fn new(per_sec: u32) -> Self {
+        let per_sec = per_sec.max(1);
+        Self {
+            per_sec,
+            bucket: Mutex::new(Bucket::new(per_sec, Instant::now())),
+        }
+    }
+
+    pub fn per_sec(&self) -> u32 {
+        self.per_sec
+    }
+
+    /// Wait for a token before sending a probe
+    pub async fn acquire(&self) {
+        let wait = self.bucket().reserve(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Whether probes have been held back in the last second
+    pub fn is_throttled(&self) -> bool {
+        self.bucket().is_throttled(Instant::now())
+    }
+
+    fn bucket(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.bucket.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn bucket_spaces_probes_past_the_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(50, start);
+        // A tenth of a second's worth goes out at once
+        for _ in 0..5 {
+            assert_eq!(bucket.reserve(start), Duration::ZERO);
+        }
+        assert!(!bucket.is_throttled(start));
+        // Then 20ms apart, queued behind each other
+        assert_eq!(bucket.reserve(start), 20 * MS);
+        assert_eq!(bucket.reserve(start), 40 * MS);
+        assert!(bucket.is_throttled(start));
+
+        // 100ms on, the queue has drained and three more are free
+        let later = start + 100 * MS;
+        for _ in 0..3 {
+            assert_eq!(bucket.reserve(later), Duration::ZERO);
+        }
+        assert_eq!(bucket.reserve(later), 20 * MS);
+
+        // Idle time refills only up to the burst, and throttling wears off
+        let idle = later + Duration::from_secs(10);
+        assert!(!bucket.is_throttled(idle));
+        for _ in 0..5 {
+            assert_eq!(bucket.reserve(idle), Duration::ZERO);
+        }
+        assert!(bucket.reserve(idle) > Duration::ZERO);
+    }
+
+    #[test]
+    fn slow_rates_still_allow_one_probe() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(2, start);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), 500 * MS);
+        assert_eq!(RateLimiter::new(0).per_sec(), 1);
+    }
+}
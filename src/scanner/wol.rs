@@ -0,0 +1,150 @@
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use super::{Pinger, PingerConfig};
+
+/// Time between pings of the hosts a [`wake_all`] run is still waiting for
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How one host of a [`wake_all`] run went
+#[derive(Debug, Clone, PartialEq)]
+pub enum WakeEvent {
+    /// Magic packet sent
+    Sent(Ipv4Addr),
+    /// The packet couldn't be sent
+    Failed(Ipv4Addr, String),
+    /// Answered a ping after its packet went out
+    Woke(Ipv4Addr),
+    /// Still silent when the watch ended
+    TimedOut(Ipv4Addr),
+}
+
+/// Parse a MAC written as `XX:XX:XX:XX:XX:XX` or `XX-XX-XX-XX-XX-XX`
+pub fn parse_mac(address: &str) -> Option<[u8; 6]> {
+    let parts: Vec<u8> = address
+        .split([':', '-'])
+        .map(|s| u8::from_str_radix(s, 16).ok())
+        .collect::<Option<_>>()?;
+    parts.try_into().ok()
+}
+
+/// Wake-on-LAN magic packet: 6×0xFF, then the MAC repeated 16 times
+pub fn magic_packet(mac: [u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xFF_u8; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    packet
+}
+
+/// Broadcast a magic packet for `mac` to UDP port 9
+pub fn wake(mac: [u8; 6]) -> std::io::Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&magic_packet(mac), "255.255.255.255:9")?;
+    Ok(())
+}
+
+/// Wake each of `targets` in turn, `interval` apart so a switch's storm
+/// control doesn't drop a burst of broadcasts. With `watch`, then ping the
+/// hosts sent to until each answers or the watch time runs out. Events
+/// arrive on `tx`, which closes when the run ends or a message on
+/// `cancel_rx` stops it.
+pub async fn wake_all(
+    targets: Vec<(Ipv4Addr, [u8; 6])>,
+    interval: Duration,
+    watch: Option<(PingerConfig, Duration)>,
+    tx: mpsc::Sender<WakeEvent>,
+    mut cancel_rx: mpsc::Receiver<()>,
+) {
+    let mut waiting = Vec::new();
+    for (i, (ip, mac)) in targets.into_iter().enumerate() {
+        if i > 0 {
+            tokio::select! {
+                _ = cancel_rx.recv() => return,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+        let event = match wake(mac) {
+            Ok(()) => {
+                waiting.push(ip);
+                WakeEvent::Sent(ip)
+            }
+            Err(e) => WakeEvent::Failed(ip, e.to_string()),
+        };
+        if tx.send(event).await.is_err() {
+            return;
+        }
+    }
+
+    let Some((ping, watch_for)) = watch else {
+        return;
+    };
+    let pinger = Arc::new(Pinger::new(ping));
+    let deadline = Instant::now() + watch_for;
+    while !waiting.is_empty() {
+        let mut round = JoinSet::new();
+        for &ip in &waiting {
+            let pinger = Arc::clone(&pinger);
+            round.spawn(async move { (ip, pinger.ping(ip).await.is_alive) });
+        }
+        while let Some(Ok((ip, alive))) = round.join_next().await {
+            if alive {
+                waiting.retain(|&w| w != ip);
+                if tx.send(WakeEvent::Woke(ip)).await.is_err() {
+                    return;
+                }
+            }
+        }
+        if waiting.is_empty() {
+            break;
+        }
+        if Instant::now() >= deadline {
+            for ip in waiting {
+                let _ = tx.send(WakeEvent::TimedOut(ip)).await;
+            }
+            return;
+        }
+        tokio::select! {
+            _ = cancel_rx.recv() => return,
+            _ = tokio::time::sleep_until(deadline.min(Instant::now() + WATCH_INTERVAL)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_packet_repeats_the_mac() {
+        let mac = parse_mac("aa-BB-cc-01-02-03").unwrap();
+        assert_eq!(mac, [0xAA, 0xBB, 0xCC, 0x01, 0x02, 0x03]);
+        assert_eq!(parse_mac("AA:BB:CC:DD:EE"), None);
+        assert_eq!(parse_mac("AA:BB:CC:DD:EE:GG"), None);
+
+        let packet = magic_packet(mac);
+        assert_eq!(packet.len(), 102);
+        assert_eq!(packet[..6], [0xFF; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == mac));
+    }
+
+    #[tokio::test]
+    async fn stopping_a_run_skips_the_hosts_not_sent_to() {
+        let targets = (1..=3).map(|i| (Ipv4Addr::new(127, 0, 0, i), [0x02, 0, 0, 0, 0, i])).collect();
+        let (tx, mut rx) = mpsc::channel(8);
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        let run = tokio::spawn(wake_all(targets, Duration::from_secs(60), None, tx, cancel_rx));
+
+        let first = rx.recv().await.unwrap();
+        assert!(matches!(first, WakeEvent::Sent(ip) | WakeEvent::Failed(ip, _) if ip == Ipv4Addr::new(127, 0, 0, 1)));
+        cancel_tx.send(()).await.unwrap();
+        assert_eq!(rx.recv().await, None);
+        run.await.unwrap();
+    }
+}
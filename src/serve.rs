@@ -0,0 +1,210 @@
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::{self, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use serde::Serialize;
+use tokio::net::TcpListener;
+
+use crate::export::ExportHost;
+
+/// A client gets this long to send its request head
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `/status`: where the scan on screen stands
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Status {
+    /// "idle", "scanning", "paused" or "completed"
+    pub state: &'static str,
+    pub range: String,
+    pub scanned: usize,
+    pub total: usize,
+    pub hosts: usize,
+    pub online: usize,
+    /// Unix time the current or last scan started; null before the first
+    pub started_at: Option<u64>,
+    /// Unix time the last scan finished, or the newest cached result's when
+    /// none has run yet
+    pub last_scan: Option<u64>,
+}
+
+/// What `--listen` serves, replaced by `App::publish` as the results change
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub status: Status,
+    /// Table order, in the JSON export schema
+    pub hosts: Vec<ExportHost>,
+    /// `/metrics`, in the Prometheus text format (`metrics::render`),
+    /// rendered once per finished scan
+    pub metrics: Arc<str>,
+}
+
+pub type Shared = Arc<RwLock<Snapshot>>;
+
+/// `--listen` value: `addr:port`, or a bare port on localhost
+pub fn parse_listen(text: &str) -> Result<SocketAddr, String> {
+    if let Ok(port) = text.parse::<u16>() {
+        return Ok(SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+    }
+    text.parse()
+        .map_err(|_| format!("expected addr:port or a port, got '{}'", text))
+}
+
+/// Answer GET `/hosts`, `/status` and `/metrics` from `shared` until the
+/// process exits
+pub fn spawn(listener: TcpListener, shared: Shared) {
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                // Out of file descriptors and the like: don't spin on it
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            };
+            let shared = shared.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let response = respond(&request, &shared);
+                    async move { Ok::<_, Infallible>(response) }
+                });
+                let connection = http1::Builder::new()
+                    .timer(TokioTimer::new())
+                    .header_read_timeout(READ_TIMEOUT)
+                    .serve_connection(TokioIo::new(stream), service);
+                if let Err(e) = connection.await {
+                    tracing::debug!(error = %e, "status request failed");
+                }
+            });
+        }
+    });
+}
+
+/// The response to `request`, from the snapshot as it is now
+fn respond<B>(request: &Request<B>, shared: &Shared) -> Response<Full<Bytes>> {
+    if request.method() != Method::GET {
+        return reply(StatusCode::METHOD_NOT_ALLOWED, r#"{"error":"only GET is served"}"#.to_string());
+    }
+    let snapshot = shared.read().unwrap_or_else(|e| e.into_inner());
+    let body = match request.uri().path().trim_end_matches('/') {
+        "/hosts" => serde_json::to_string(&snapshot.hosts),
+        "/status" => serde_json::to_string(&snapshot.status),
+        "/metrics" => return reply_as(StatusCode::OK, PROMETHEUS_TEXT, snapshot.metrics.to_string()),
+        _ => {
+            let body = r#"{"error":"try /hosts, /status or /metrics"}"#.to_string();
+            return reply(StatusCode::NOT_FOUND, body);
+        }
+    };
+    match body {
+        Ok(body) => reply(StatusCode::OK, body),
+        Err(e) => reply(StatusCode::INTERNAL_SERVER_ERROR, format!(r#"{{"error":"{}"}}"#, e)),
+    }
+}
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_TEXT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+fn reply(status: StatusCode, body: String) -> Response<Full<Bytes>> {
+    reply_as(status, "application/json", body)
+}
+
+fn reply_as(status: StatusCode, content_type: &str, body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_str(content_type).expect("ASCII content type"));
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    /// A server on a free localhost port, serving `snapshot`
+    async fn serve(snapshot: Snapshot) -> (SocketAddr, Shared) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shared = Shared::new(RwLock::new(snapshot));
+        spawn(listener, shared.clone());
+        (addr, shared)
+    }
+
+    /// Status line and body of `request` sent to `addr`
+    async fn fetch(addr: SocketAddr, request: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    fn get(path: &str) -> String {
+        format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path)
+    }
+
+    #[test]
+    fn listen_defaults_to_localhost() {
+        assert_eq!(parse_listen("9100"), Ok("127.0.0.1:9100".parse().unwrap()));
+        assert_eq!(parse_listen("0.0.0.0:9100"), Ok("0.0.0.0:9100".parse().unwrap()));
+        assert!(parse_listen("localhost").is_err());
+        assert!(parse_listen("70000").is_err());
+    }
+
+    #[tokio::test]
+    async fn serves_status_and_hosts_as_published() {
+        let status = Status {
+            state: "scanning",
+            range: "192.168.1.0/24".to_string(),
+            scanned: 12,
+            total: 254,
+            ..Status::default()
+        };
+        let snapshot = Snapshot {
+            status,
+            metrics: Arc::from("ipscannr_hosts_total 0\n"),
+            ..Snapshot::default()
+        };
+        let (addr, shared) = serve(snapshot).await;
+
+        let (line, body) = fetch(addr, &get("/status")).await;
+        assert_eq!(line, "HTTP/1.1 200 OK");
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["state"], "scanning");
+        assert_eq!(json["range"], "192.168.1.0/24");
+        assert_eq!(json["scanned"], 12);
+        assert!(json["last_scan"].is_null());
+
+        let (line, body) = fetch(addr, &get("/hosts?fresh=1")).await;
+        assert_eq!(line, "HTTP/1.1 200 OK");
+        assert_eq!(body, "[]");
+
+        let (line, body) = fetch(addr, &get("/metrics")).await;
+        assert_eq!(line, "HTTP/1.1 200 OK");
+        assert_eq!(body, "ipscannr_hosts_total 0\n");
+
+        // A later publish is what the next request sees
+        shared.write().unwrap().status.state = "completed";
+        let (_, body) = fetch(addr, &get("/status/")).await;
+        assert!(body.contains(r#""state":"completed""#), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn rejects_other_paths_and_methods() {
+        let (addr, _) = serve(Snapshot::default()).await;
+        assert_eq!(fetch(addr, &get("/")).await.0, "HTTP/1.1 404 Not Found");
+        assert_eq!(fetch(addr, &get("/hosts/1")).await.0, "HTTP/1.1 404 Not Found");
+        let post = "POST /hosts HTTP/1.1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        assert_eq!(fetch(addr, post).await.0, "HTTP/1.1 405 Method Not Allowed");
+        assert_eq!(fetch(addr, "NOT HTTP\r\n\r\n").await.0, "HTTP/1.1 400 Bad Request");
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{default_templates, CommandTemplate, KnockAction};
+use crate::config::SAFE_MODE_PORTS;
+use crate::highlight::{example_rules, HighlightRule};
+use crate::tuning::Tuning;
+use crate::ui::Column;
+use crate::webhook::WebhookConfig;
+
+const SETTINGS_FILE: &str = "ipscannr_settings.json";
+const SETTINGS_FILE_ENV: &str = "IPSCANNR_SETTINGS_FILE";
+
+/// Width of the hosts table as a percentage of the main area (Full layout)
+pub const DEFAULT_SPLIT_PERCENT: u16 = 55;
+pub const MIN_SPLIT_PERCENT: u16 = 30;
+pub const MAX_SPLIT_PERCENT: u16 = 80;
+pub const SPLIT_STEP: u16 = 5;
+
+/// User preferences persisted between runs.
+///
+/// Every field has a serde default so older or hand-edited files keep loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Hosts table share of the table/details split, in percent
+    pub split_percent: u16,
+    /// Always stack the details pane below the table when there is room
+    pub force_stacked: bool,
+    /// Command lines offered by the copy-command chooser
+    pub command_templates: Vec<CommandTemplate>,
+    /// Port knocking sequences offered by the knock chooser; only ever set
+    /// by editing the file
+    pub knock_actions: Vec<KnockAction>,
+    /// Hosts table columns, in order
+    pub columns: Vec<Column>,
+    /// Scan tunables from the settings overlay; None until changed there,
+    /// so the built-in defaults and flags apply
+    pub tuning: Option<Tuning>,
+    /// Export dialog toggles; None until flipped there, so the config
+    /// defaults apply
+    pub export_offline: Option<bool>,
+    pub export_open_ports_only: Option<bool>,
+    /// Where host changes are POSTed; only ever set by editing the file
+    pub webhook: Option<WebhookConfig>,
+    /// Badges and row colors for hosts by hostname or vendor, in precedence
+    /// order; the examples a new file gets are disabled
+    pub highlight_rules: Vec<HighlightRule>,
+    /// `--safe-mode` for every run; only ever set by editing the file
+    pub safe_mode: Option<SafeMode>,
+    /// Names set by hand, by address: shown as the Alias name, which
+    /// outranks the others unless `--name-order` says otherwise
+    pub aliases: BTreeMap<Ipv4Addr, String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            split_percent: DEFAULT_SPLIT_PERCENT,
+            force_stacked: false,
+            command_templates: default_templates(),
+            knock_actions: Vec::new(),
+            columns: Column::DEFAULT.to_vec(),
+            tuning: None,
+            export_offline: None,
+            export_open_ports_only: None,
+            webhook: None,
+            highlight_rules: example_rules(),
+            safe_mode: None,
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+/// The `safe_mode` section of the settings file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafeMode {
+    pub enabled: bool,
+    /// The only TCP ports probed; `--safe-ports` overrides them
+    pub allowed_ports: Vec<u16>,
+    /// Probe with ICMP echo alone, whatever `allowed_ports` says
+    pub icmp_only: bool,
+}
+
+impl Default for SafeMode {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_ports: SAFE_MODE_PORTS.to_vec(),
+            icmp_only: false,
+        }
+    }
+}
+
+impl SafeMode {
+    /// The allow list, empty for `icmp_only`; an error for an empty or
+    /// invalid one, which would otherwise mean ICMP only by accident
+    pub fn allowed(&self) -> Result<Vec<u16>, String> {
+        if self.icmp_only {
+            return Ok(Vec::new());
+        }
+        if self.allowed_ports.is_empty() {
+            let fix = "list the allowed ports, or set \"icmp_only\": true";
+            return Err(format!("safe_mode.allowed_ports is empty: {}", fix));
+        }
+        if self.allowed_ports.contains(&0) {
+            return Err("safe_mode.allowed_ports: 0 is not a port".to_string());
+        }
+        let mut ports = self.allowed_ports.clone();
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(ports)
+    }
+}
+
+impl Settings {
+    /// Move the table/details divider by `steps` increments (negative = narrower table).
+    /// Returns true when the ratio actually changed.
+    pub fn adjust_split(&mut self, steps: i16) -> bool {
+        let current = self.split_percent.clamp(MIN_SPLIT_PERCENT, MAX_SPLIT_PERCENT) as i16;
+        let next = (current + steps * SPLIT_STEP as i16)
+            .clamp(MIN_SPLIT_PERCENT as i16, MAX_SPLIT_PERCENT as i16) as u16;
+        let changed = next != self.split_percent;
+        self.split_percent = next;
+        changed
+    }
+}
+
+fn settings_file_path() -> std::path::PathBuf {
+    std::env::var_os(SETTINGS_FILE_ENV)
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::path::PathBuf::from(SETTINGS_FILE))
+}
+
+/// Load persisted settings, falling back to defaults when missing or malformed.
+pub fn load_settings() -> Settings {
+    let Ok(content) = std::fs::read_to_string(settings_file_path()) else {
+        return Settings::default();
+    };
+    let mut settings: Settings = serde_json::from_str(&content).unwrap_or_default();
+    settings.split_percent = settings
+        .split_percent
+        .clamp(MIN_SPLIT_PERCENT, MAX_SPLIT_PERCENT);
+    settings.columns = Column::normalize(&settings.columns);
+    settings
+}
+
+/// Persist settings using the same temp-file + replace pattern as the cache.
+pub fn save_settings(settings: &Settings) {
+    let path = settings_file_path();
+    if let Ok(json) = serde_json::to_string_pretty(settings) {
+        let tmp_path = path.with_extension("json.tmp");
+        if std::fs::write(&tmp_path, json).is_ok() {
+            let _ = std::fs::remove_file(&path);
+            if std::fs::rename(&tmp_path, &path).is_err() {
+                let _ = std::fs::copy(&tmp_path, &path);
+                let _ = std::fs::remove_file(&tmp_path);
+            }
+        }
+    }
+}
+
+/// Point the settings file at `path` for a test, holding a lock so tests
+/// that save settings don't write each other's files
+#[cfg(test)]
+pub fn use_settings_file(path: &std::path::Path) -> std::sync::MutexGuard<'static, ()> {
+    use std::sync::{Mutex, OnceLock};
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    let guard = LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    unsafe {
+        std::env::set_var(SETTINGS_FILE_ENV, path);
+    }
+    guard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjust_split_steps_and_clamps() {
+        let mut settings = Settings::default();
+        assert!(settings.adjust_split(1));
+        assert_eq!(settings.split_percent, 60);
+        assert!(settings.adjust_split(-2));
+        assert_eq!(settings.split_percent, 50);
+
+        assert!(settings.adjust_split(100));
+        assert_eq!(settings.split_percent, MAX_SPLIT_PERCENT);
+        assert!(!settings.adjust_split(1));
+
+        settings.adjust_split(-100);
+        assert_eq!(settings.split_percent, MIN_SPLIT_PERCENT);
+    }
+
+    #[test]
+    fn settings_round_trip_and_tolerate_missing_fields() {
+        let temp_path = std::env::temp_dir().join("ipscannr_settings_roundtrip_test.json");
+        let _ = std::fs::remove_file(&temp_path);
+        let _guard = use_settings_file(&temp_path);
+
+        assert_eq!(load_settings(), Settings::default());
+
+        let settings = Settings {
+            split_percent: 70,
+            force_stacked: true,
+            ..Settings::default()
+        };
+        save_settings(&settings);
+        assert_eq!(load_settings(), settings);
+
+        std::fs::write(&temp_path, "{}").expect("write empty settings");
+        assert_eq!(load_settings(), Settings::default());
+
+        std::fs::write(&temp_path, r#"{"split_percent": 5}"#).expect("write out-of-range");
+        assert_eq!(load_settings().split_percent, MIN_SPLIT_PERCENT);
+
+        // Repeats are dropped and the IP column comes back
+        std::fs::write(&temp_path, r#"{"columns": ["hostname", "rtt", "hostname"]}"#)
+            .expect("write columns");
+        assert_eq!(load_settings().columns, [Column::Ip, Column::Hostname, Column::Rtt]);
+
+        std::fs::write(&temp_path, r#"{"aliases": {"192.168.1.5": "Lounge TV"}}"#).expect("write aliases");
+        let aliases = load_settings().aliases;
+        assert_eq!(aliases.get(&Ipv4Addr::new(192, 168, 1, 5)).map(String::as_str), Some("Lounge TV"));
+
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn safe_mode_needs_ports_or_an_explicit_icmp_only() {
+        let safe = |json: &str| serde_json::from_str::<SafeMode>(json).unwrap().allowed();
+        assert_eq!(safe("{}"), Ok(vec![443]));
+        assert_eq!(safe(r#"{"allowed_ports": [8443, 443, 443]}"#), Ok(vec![443, 8443]));
+        assert!(safe(r#"{"allowed_ports": []}"#).unwrap_err().contains("icmp_only"));
+        assert!(safe(r#"{"allowed_ports": [0]}"#).is_err());
+        assert_eq!(safe(r#"{"allowed_ports": [], "icmp_only": true}"#), Ok(Vec::new()));
+    }
+}
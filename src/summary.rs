@@ -0,0 +1,428 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+
+use crate::app::HostInfo;
+use crate::scanner::ProbeError;
+use crate::ui::theme::Compat;
+
+/// Ports counted in the services breakdown, with the name shown for each
+pub const SERVICES: [(&str, u16); 5] = [
+    ("SSH", 22),
+    ("HTTP", 80),
+    ("HTTPS", 443),
+    ("SMB", 445),
+    ("RDP", 3389),
+];
+
+/// Vendors listed by name; the rest are lumped together
+const TOP_VENDORS: usize = 8;
+
+/// Upper bounds (ms) of the RTT buckets; the last bucket is open-ended
+const RTT_BOUNDS_MS: [u128; 4] = [1, 5, 20, 100];
+
+/// Width of the bar drawn after each count
+const BAR_WIDTH: usize = 20;
+
+/// Aggregate view of a scan's results
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub total: usize,
+    pub online: usize,
+    /// Online hosts that only answered TCP probes
+    pub tcp_only: usize,
+    /// Hosts with a finished port scan; service counts only cover these
+    pub ports_scanned: usize,
+    /// `(network, online, listed)` per /24, in address order
+    pub subnets: Vec<(Ipv4Addr, usize, usize)>,
+    /// Online hosts per MAC vendor, most common first
+    pub vendors: Vec<(String, usize)>,
+    /// `(name, port, hosts with it open)` for each of [`SERVICES`]
+    pub services: Vec<(&'static str, u16, usize)>,
+    /// Online hosts per RTT bucket, fastest first
+    pub rtt: Vec<(String, usize)>,
+    /// Hosts online in the cached scan of the range but offline now
+    pub stale: Vec<Ipv4Addr>,
+    /// Offline hosts per reason their probes failed, most common first
+    pub offline_causes: Vec<(ProbeError, usize)>,
+}
+
+/// How a line of the summary overlay is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    Heading,
+    Row,
+}
+
+/// Aggregate `hosts`. `offline_dropped` counts offline hosts that a large
+/// scan didn't keep; they only add to the total.
+pub fn summarize<'a>(
+    hosts: impl IntoIterator<Item = &'a HostInfo>,
+    offline_dropped: usize,
+) -> Summary {
+    let hosts: Vec<&HostInfo> = hosts.into_iter().collect();
+    let online: Vec<&HostInfo> = hosts.iter().copied().filter(|h| h.is_alive).collect();
+
+    let mut subnets: BTreeMap<Ipv4Addr, (usize, usize)> = BTreeMap::new();
+    for host in &hosts {
+        let [a, b, c, _] = host.ip.octets();
+        let entry = subnets.entry(Ipv4Addr::new(a, b, c, 0)).or_default();
+        entry.0 += usize::from(host.is_alive);
+        entry.1 += 1;
+    }
+
+    let mut by_vendor: HashMap<&str, usize> = HashMap::new();
+    for host in &online {
+        let vendor = host
+            .mac
+            .as_ref()
+            .and_then(|m| m.vendor.as_deref())
+            .unwrap_or("Unknown");
+        *by_vendor.entry(vendor).or_default() += 1;
+    }
+    let mut vendors: Vec<(String, usize)> = by_vendor
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count))
+        .collect();
+    vendors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if vendors.len() > TOP_VENDORS {
+        let rest = vendors.split_off(TOP_VENDORS);
+        let count = rest.iter().map(|v| v.1).sum();
+        vendors.push((format!("{} others", rest.len()), count));
+    }
+
+    let services = SERVICES
+        .iter()
+        .map(|&(name, port)| {
+            let count = hosts.iter().filter(|h| h.open_ports.contains(&port)).count();
+            (name, port, count)
+        })
+        .collect();
+
+    let mut rtt = vec![0; RTT_BOUNDS_MS.len() + 1];
+    for rtt_ms in online.iter().filter_map(|h| h.rtt).map(|d| d.as_millis()) {
+        let bucket = RTT_BOUNDS_MS.iter().position(|&bound| rtt_ms < bound);
+        rtt[bucket.unwrap_or(RTT_BOUNDS_MS.len())] += 1;
+    }
+    let rtt = rtt
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (rtt_label(i), count))
+        .collect();
+
+    Summary {
+        total: hosts.len() + offline_dropped,
+        online: online.len(),
+        tcp_only: online.iter().filter(|h| h.tcp_only()).count(),
+        ports_scanned: hosts.iter().filter(|h| h.ports_scanned).count(),
+        subnets: subnets
+            .into_iter()
+            .map(|(net, (online, listed))| (net, online, listed))
+            .collect(),
+        vendors,
+        services,
+        rtt,
+        stale: hosts.iter().filter(|h| h.stale).map(|h| h.ip).collect(),
+        offline_causes: Vec::new(),
+    }
+    .count_offline_causes(&offline_causes(&hosts))
+}
+
+fn offline_causes(hosts: &[&HostInfo]) -> HashMap<ProbeError, usize> {
+    let mut causes = HashMap::new();
+    for error in hosts.iter().filter(|h| !h.is_alive).filter_map(|h| h.probe_error) {
+        *causes.entry(error).or_default() += 1;
+    }
+    causes
+}
+
+/// "240 timeout, 14 network unreachable"
+pub fn causes_text(causes: &[(ProbeError, usize)]) -> String {
+    causes
+        .iter()
+        .map(|(error, count)| format!("{} {}", count, error))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn rtt_label(bucket: usize) -> String {
+    match (bucket.checked_sub(1).map(|i| RTT_BOUNDS_MS[i]), RTT_BOUNDS_MS.get(bucket)) {
+        (None, Some(upper)) => format!("< {} ms", upper),
+        (Some(lower), Some(upper)) => format!("{}-{} ms", lower, upper),
+        (Some(lower), None) => format!("{}+ ms", lower),
+        (None, None) => unreachable!("RTT_BOUNDS_MS is not empty"),
+    }
+}
+
+impl Summary {
+    /// Add `causes` (offline hosts per probe error) to the breakdown, e.g.
+    /// for the offline hosts a large scan counted but didn't keep
+    pub fn count_offline_causes(mut self, causes: &HashMap<ProbeError, usize>) -> Self {
+        for (&error, &count) in causes {
+            match self.offline_causes.iter_mut().find(|c| c.0 == error) {
+                Some(cause) => cause.1 += count,
+                None => self.offline_causes.push((error, count)),
+            }
+        }
+        self.offline_causes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self
+    }
+
+    /// Aligned text with a bar per count, for the overlay and the text export
+    pub fn lines(&self, compat: bool) -> Vec<(LineKind, String)> {
+        let heading = |text: String| (LineKind::Heading, text);
+        let mut lines = vec![heading(format!(
+            "{} hosts scanned, {} online",
+            self.total, self.online
+        ))];
+
+        lines.push((LineKind::Row, String::new()));
+        lines.push(heading("By status".to_string()));
+        let statuses = [
+            ("Online (ICMP)", self.online - self.tcp_only),
+            ("Online (TCP only)", self.tcp_only),
+            ("Offline", self.total - self.online),
+        ];
+        let max = statuses.iter().map(|s| s.1).max().unwrap_or(0);
+        lines.extend(
+            statuses
+                .iter()
+                .map(|(label, count)| row(label, &count.to_string(), *count, max, compat)),
+        );
+        // Offline broken down by cause, under its row
+        lines.extend(self.offline_causes.iter().map(|(error, count)| {
+            row(&format!("  {}", error), &count.to_string(), *count, max, compat)
+        }));
+
+        lines.push((LineKind::Row, String::new()));
+        lines.push(heading("Online per /24".to_string()));
+        let max = self.subnets.iter().map(|s| s.1).max().unwrap_or(0);
+        lines.extend(self.subnets.iter().map(|(net, online, listed)| {
+            let label = format!("{}/24", net);
+            row(&label, &format!("{}/{}", online, listed), *online, max, compat)
+        }));
+
+        lines.push((LineKind::Row, String::new()));
+        lines.push(heading("Top vendors (online hosts)".to_string()));
+        let max = self.vendors.iter().map(|v| v.1).max().unwrap_or(0);
+        lines.extend(
+            self.vendors
+                .iter()
+                .map(|(name, count)| row(name, &count.to_string(), *count, max, compat)),
+        );
+
+        lines.push((LineKind::Row, String::new()));
+        lines.push(heading(format!(
+            "Open services ({} of {} hosts port scanned)",
+            self.ports_scanned, self.online
+        )));
+        let max = self.services.iter().map(|s| s.2).max().unwrap_or(0);
+        lines.extend(self.services.iter().map(|(name, port, count)| {
+            let label = format!("{} ({})", name, port);
+            row(&label, &count.to_string(), *count, max, compat)
+        }));
+
+        lines.push((LineKind::Row, String::new()));
+        lines.push(heading("Response time (online hosts)".to_string()));
+        let max = self.rtt.iter().map(|r| r.1).max().unwrap_or(0);
+        lines.extend(
+            self.rtt
+                .iter()
+                .map(|(label, count)| row(label, &count.to_string(), *count, max, compat)),
+        );
+
+        if !self.stale.is_empty() {
+            lines.push((LineKind::Row, String::new()));
+            lines.push(heading(format!(
+                "Stale: online in the cached scan, not now ({})",
+                self.stale.len()
+            )));
+            lines.extend(
+                self.stale
+                    .iter()
+                    .map(|ip| (LineKind::Row, format!("  {}", ip))),
+            );
+        }
+        lines
+    }
+
+    /// Plain ASCII text for the export
+    pub fn to_text(&self) -> String {
+        let mut text: String = self
+            .lines(true)
+            .into_iter()
+            .map(|(_, line)| line.trim_end().to_string() + "\n")
+            .collect();
+        text.insert_str(0, "ipscannr scan summary\n\n");
+        text
+    }
+}
+
+/// `  label   count  ████░░░░` with the bar scaled to `max`
+fn row(label: &str, count: &str, value: usize, max: usize, compat: bool) -> (LineKind, String) {
+    let label = crate::ui::text::truncate(label, 22, compat);
+    (
+        LineKind::Row,
+        format!("  {:<22} {:>9}  {}", label, count, bar(value, max, compat)),
+    )
+}
+
+/// Text bar in the progress bar's glyphs
+fn bar(value: usize, max: usize, compat: bool) -> String {
+    let filled = if max == 0 {
+        0
+    } else {
+        (value * BAR_WIDTH).div_ceil(max)
+    };
+    let (fill, empty) = if compat {
+        (Compat::SYM_PROGRESS_FILL, Compat::SYM_PROGRESS_EMPTY)
+    } else {
+        ("█", "░")
+    };
+    format!("{}{}", fill.repeat(filled), empty.repeat(BAR_WIDTH - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::scanner::{HostStatus, MacInfo};
+
+    fn host(ip: [u8; 4], is_alive: bool, rtt_ms: u64, vendor: Option<&str>) -> HostInfo {
+        HostInfo {
+            rtt: is_alive.then(|| Duration::from_millis(rtt_ms)),
+            mac: vendor.map(|v| MacInfo {
+                address: "AA:BB:CC:DD:EE:FF".to_string(),
+                vendor: Some(v.to_string()),
+            }),
+            ..HostInfo::pinged(Ipv4Addr::from(ip), is_alive)
+        }
+    }
+
+    #[test]
+    fn aggregates_subnets_vendors_services_and_rtt() {
+        let mut hosts = vec![
+            host([10, 0, 1, 5], true, 0, Some("Apple")),
+            host([10, 0, 0, 1], true, 3, Some("Cisco")),
+            host([10, 0, 0, 2], true, 150, Some("Apple")),
+            host([10, 0, 0, 3], true, 12, None),
+            host([10, 0, 0, 4], false, 0, None),
+        ];
+        hosts[1].open_ports = vec![22, 443];
+        hosts[1].ports_scanned = true;
+        hosts[2].open_ports = vec![3389];
+        hosts[2].ports_scanned = true;
+
+        let summary = summarize(&hosts, 10);
+        assert_eq!(summary.total, 15);
+        assert_eq!(summary.online, 4);
+        assert_eq!(summary.ports_scanned, 2);
+        assert_eq!(
+            summary.subnets,
+            vec![
+                (Ipv4Addr::new(10, 0, 0, 0), 3, 4),
+                (Ipv4Addr::new(10, 0, 1, 0), 1, 1),
+            ]
+        );
+        assert_eq!(
+            summary.vendors,
+            vec![
+                ("Apple".to_string(), 2),
+                ("Cisco".to_string(), 1),
+                ("Unknown".to_string(), 1),
+            ]
+        );
+        let open: Vec<usize> = summary.services.iter().map(|s| s.2).collect();
+        assert_eq!(open, [1, 0, 1, 0, 1]);
+        let rtt: Vec<(&str, usize)> = summary.rtt.iter().map(|(l, c)| (l.as_str(), *c)).collect();
+        assert_eq!(
+            rtt,
+            [("< 1 ms", 1), ("1-5 ms", 1), ("5-20 ms", 1), ("20-100 ms", 0), ("100+ ms", 1)]
+        );
+    }
+
+    #[test]
+    fn counts_are_broken_down_by_status() {
+        let mut hosts = [
+            host([10, 0, 0, 1], true, 2, None),
+            host([10, 0, 0, 2], true, 2, None),
+            host([10, 0, 0, 3], false, 0, None),
+        ];
+        hosts[1].status = HostStatus::OnlineNoIcmp;
+        let summary = summarize(&hosts, 5);
+        assert_eq!((summary.online, summary.tcp_only), (2, 1));
+        let text = summary.to_text();
+        let count = |label: &str| -> String {
+            let line = text.lines().find(|l| l.trim_start().starts_with(label)).unwrap();
+            line[2 + 22..][..10].trim().to_string()
+        };
+        assert_eq!(count("Online (ICMP)"), "1");
+        assert_eq!(count("Online (TCP only)"), "1");
+        assert_eq!(count("Offline"), "6");
+    }
+
+    #[test]
+    fn offline_hosts_are_broken_down_by_cause() {
+        let mut hosts = [
+            host([10, 0, 0, 1], true, 2, None),
+            host([10, 0, 0, 2], false, 0, None),
+            host([10, 0, 0, 3], false, 0, None),
+            host([10, 0, 0, 4], false, 0, None),
+        ];
+        hosts[1].probe_error = Some(ProbeError::Timeout);
+        hosts[2].probe_error = Some(ProbeError::NetworkUnreachable);
+        hosts[3].probe_error = Some(ProbeError::Timeout);
+        let dropped = HashMap::from([(ProbeError::NetworkUnreachable, 12), (ProbeError::PermissionDenied, 1)]);
+        let summary = summarize(&hosts, 13).count_offline_causes(&dropped);
+        assert_eq!(
+            causes_text(&summary.offline_causes),
+            "13 network unreachable, 2 timeout, 1 permission denied"
+        );
+
+        let text = summary.to_text();
+        let lines: Vec<&str> = text.lines().collect();
+        let offline = lines.iter().position(|l| l.starts_with("  Offline")).unwrap();
+        assert!(lines[offline + 1].starts_with("    network unreachable"), "{}", text);
+        assert!(lines[offline + 1].contains(" 13  "), "{}", text);
+        assert!(lines[offline + 3].starts_with("    permission denied"), "{}", text);
+    }
+
+    #[test]
+    fn rare_vendors_are_grouped() {
+        let names = ["A", "B", "C", "D", "E", "F", "G", "H", "I", "J"];
+        let hosts: Vec<HostInfo> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| host([10, 0, 0, i as u8], true, 1, Some(name)))
+            .collect();
+        let summary = summarize(&hosts, 0);
+        assert_eq!(summary.vendors.len(), TOP_VENDORS + 1);
+        assert_eq!(summary.vendors.last().unwrap(), &("2 others".to_string(), 2));
+    }
+
+    #[test]
+    fn text_export_is_ascii_with_scaled_bars() {
+        let hosts = [host([10, 0, 0, 1], true, 2, Some("Apple"))];
+        let text = summarize(&hosts, 0).to_text();
+        assert!(text.is_ascii());
+        assert!(text.contains("1 hosts scanned, 1 online"));
+        let full_bar = "#".repeat(BAR_WIDTH);
+        assert!(text.contains("10.0.0.0/24"));
+        assert!(text.lines().any(|l| l.contains("Apple") && l.ends_with(&full_bar)));
+        assert_eq!(bar(0, 0, true), "-".repeat(BAR_WIDTH));
+        assert_eq!(bar(1, 4, true), format!("{}{}", "#".repeat(5), "-".repeat(15)));
+    }
+
+    #[test]
+    fn stale_hosts_get_their_own_section() {
+        let mut hosts = [host([10, 0, 0, 1], true, 2, None), host([10, 0, 0, 9], false, 0, None)];
+        assert!(!summarize(&hosts, 0).to_text().contains("Stale"));
+
+        hosts[1].stale = true;
+        let summary = summarize(&hosts, 0);
+        assert_eq!(summary.stale, [Ipv4Addr::new(10, 0, 0, 9)]);
+        let text = summary.to_text();
+        assert!(text.contains("Stale: online in the cached scan, not now (1)"));
+        assert!(text.ends_with("  10.0.0.9\n"));
+    }
+}
@@ -0,0 +1,252 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// The scan tunables the settings overlay edits, saved with the other
+/// settings once changed there.
+///
+/// Every field has a serde default so older or hand-edited files keep loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+    pub ping_timeout_ms: u64,
+    pub ping_retries: u32,
+    pub ping_concurrency: usize,
+    pub port_timeout_ms: u64,
+    pub port_retries: u32,
+    pub port_concurrency: usize,
+    pub resolve_hostnames: bool,
+    pub detect_mac: bool,
+    pub refused_means_alive: bool,
+    pub arp_prewarm: bool,
+    pub discovery_window_secs: u64,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::capture(&Config::default())
+    }
+}
+
+impl Tuning {
+    pub fn capture(config: &Config) -> Self {
+        Self {
+            ping_timeout_ms: config.ping.timeout.as_millis() as u64,
+            ping_retries: config.ping.retries,
+            ping_concurrency: config.ping.concurrent_limit,
+            port_timeout_ms: config.port_scan.timeout.as_millis() as u64,
+            port_retries: config.port_scan.retries,
+            port_concurrency: config.port_scan.concurrent_limit,
+            resolve_hostnames: config.resolve_hostnames,
+            detect_mac: config.detect_mac,
+            refused_means_alive: config.ping.refused_means_alive,
+            arp_prewarm: config.arp_prewarm,
+            discovery_window_secs: config.discovery_window.as_secs(),
+        }
+    }
+
+    /// Copy into `config`, pulling numbers a hand-edited file put out of
+    /// range back inside it
+    pub fn apply(&self, config: &mut Config) {
+        let clamp = |field: Field, value: u64| {
+            let (min, max) = field.range();
+            value.clamp(min, max)
+        };
+        config.ping.timeout = Duration::from_millis(clamp(Field::PingTimeout, self.ping_timeout_ms));
+        config.ping.retries = clamp(Field::PingRetries, self.ping_retries.into()) as u32;
+        config.ping.concurrent_limit =
+            clamp(Field::PingConcurrency, self.ping_concurrency as u64) as usize;
+        config.port_scan.timeout =
+            Duration::from_millis(clamp(Field::PortTimeout, self.port_timeout_ms));
+        config.port_scan.retries = clamp(Field::PortRetries, self.port_retries.into()) as u32;
+        config.port_scan.concurrent_limit =
+            clamp(Field::PortConcurrency, self.port_concurrency as u64) as usize;
+        config.resolve_hostnames = self.resolve_hostnames;
+        config.detect_mac = self.detect_mac;
+        config.ping.refused_means_alive = self.refused_means_alive;
+        config.arp_prewarm = self.arp_prewarm;
+        config.discovery_window =
+            Duration::from_secs(clamp(Field::DiscoveryWindow, self.discovery_window_secs));
+    }
+}
+
+/// One row of the settings overlay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    PingTimeout,
+    PingRetries,
+    PingConcurrency,
+    PortTimeout,
+    PortRetries,
+    PortConcurrency,
+    ResolveHostnames,
+    DetectMac,
+    RefusedAlive,
+    ArpPrewarm,
+    DiscoveryWindow,
+}
+
+impl Field {
+    pub const ALL: [Field; 11] = [
+        Field::PingTimeout,
+        Field::PingRetries,
+        Field::PingConcurrency,
+        Field::PortTimeout,
+        Field::PortRetries,
+        Field::PortConcurrency,
+        Field::ResolveHostnames,
+        Field::DetectMac,
+        Field::RefusedAlive,
+        Field::ArpPrewarm,
+        Field::DiscoveryWindow,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::PingTimeout => "Ping timeout",
+            Field::PingRetries => "Ping retries",
+            Field::PingConcurrency => "Ping concurrency",
+            Field::PortTimeout => "Port timeout",
+            Field::PortRetries => "Port retries",
+            Field::PortConcurrency => "Port concurrency",
+            Field::ResolveHostnames => "Resolve hostnames",
+            Field::DetectMac => "Detect MAC",
+            Field::RefusedAlive => "Refused = alive",
+            Field::ArpPrewarm => "ARP prewarm",
+            Field::DiscoveryWindow => "Discovery window",
+        }
+    }
+
+    pub fn is_toggle(self) -> bool {
+        matches!(
+            self,
+            Field::ResolveHostnames | Field::DetectMac | Field::RefusedAlive | Field::ArpPrewarm
+        )
+    }
+
+    /// Accepted values of a number field, inclusive
+    fn range(self) -> (u64, u64) {
+        match self {
+            Field::PingTimeout | Field::PortTimeout => (50, 10_000),
+            Field::PingRetries | Field::PortRetries => (0, 5),
+            Field::PingConcurrency => (1, 1000),
+            Field::PortConcurrency => (1, 500),
+            Field::DiscoveryWindow => (1, 60),
+            _ => (0, 1),
+        }
+    }
+
+    fn unit(self) -> &'static str {
+        match self {
+            Field::PingTimeout | Field::PortTimeout => " ms",
+            Field::DiscoveryWindow => " s",
+            _ => "",
+        }
+    }
+
+    /// A number field's value as typed into its editor; empty for a toggle
+    pub fn raw(self, tuning: &Tuning) -> String {
+        let value = match self {
+            Field::PingTimeout => tuning.ping_timeout_ms,
+            Field::PingRetries => tuning.ping_retries.into(),
+            Field::PingConcurrency => tuning.ping_concurrency as u64,
+            Field::PortTimeout => tuning.port_timeout_ms,
+            Field::PortRetries => tuning.port_retries.into(),
+            Field::PortConcurrency => tuning.port_concurrency as u64,
+            Field::DiscoveryWindow => tuning.discovery_window_secs,
+            _ => return String::new(),
+        };
+        value.to_string()
+    }
+
+    /// "300 ms", "on"
+    pub fn value_text(self, tuning: &Tuning) -> String {
+        let toggle = match self {
+            Field::ResolveHostnames => tuning.resolve_hostnames,
+            Field::DetectMac => tuning.detect_mac,
+            Field::RefusedAlive => tuning.refused_means_alive,
+            Field::ArpPrewarm => tuning.arp_prewarm,
+            _ => return format!("{}{}", self.raw(tuning), self.unit()),
+        };
+        if toggle { "on" } else { "off" }.to_string()
+    }
+
+    /// Flip a toggle; number fields are left alone
+    pub fn toggle(self, tuning: &mut Tuning) {
+        let value = match self {
+            Field::ResolveHostnames => &mut tuning.resolve_hostnames,
+            Field::DetectMac => &mut tuning.detect_mac,
+            Field::RefusedAlive => &mut tuning.refused_means_alive,
+            Field::ArpPrewarm => &mut tuning.arp_prewarm,
+            _ => return,
+        };
+        *value = !*value;
+    }
+
+    /// Set a number field from its editor; the error names the accepted range
+    pub fn set(self, tuning: &mut Tuning, text: &str) -> Result<(), String> {
+        let (min, max) = self.range();
+        let value = text
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|v| (min..=max).contains(v))
+            .ok_or_else(|| format!("{} must be {}-{}{}", self.label(), min, max, self.unit()))?;
+        match self {
+            Field::PingTimeout => tuning.ping_timeout_ms = value,
+            Field::PingRetries => tuning.ping_retries = value as u32,
+            Field::PingConcurrency => tuning.ping_concurrency = value as usize,
+            Field::PortTimeout => tuning.port_timeout_ms = value,
+            Field::PortRetries => tuning.port_retries = value as u32,
+            Field::PortConcurrency => tuning.port_concurrency = value as usize,
+            Field::DiscoveryWindow => tuning.discovery_window_secs = value,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_validate_their_range() {
+        let mut tuning = Tuning::default();
+        assert_eq!(Field::PingTimeout.value_text(&tuning), "300 ms");
+        assert_eq!(Field::ResolveHostnames.value_text(&tuning), "on");
+
+        assert!(Field::PingTimeout.set(&mut tuning, " 1200 ").is_ok());
+        assert_eq!(tuning.ping_timeout_ms, 1200);
+        assert_eq!(
+            Field::PingTimeout.set(&mut tuning, "20"),
+            Err("Ping timeout must be 50-10000 ms".to_string())
+        );
+        assert!(Field::PortRetries.set(&mut tuning, "six").is_err());
+        assert!(Field::PortRetries.set(&mut tuning, "").is_err());
+        assert_eq!(tuning.ping_timeout_ms, 1200, "a rejected value changes nothing");
+
+        Field::DetectMac.toggle(&mut tuning);
+        assert_eq!(Field::DetectMac.value_text(&tuning), "off");
+        Field::PingRetries.toggle(&mut tuning);
+        assert_eq!(tuning.ping_retries, 0);
+    }
+
+    #[test]
+    fn apply_clamps_hand_edited_values() {
+        let tuning = Tuning {
+            ping_concurrency: 0,
+            port_timeout_ms: 99_999,
+            detect_mac: false,
+            ..Tuning::default()
+        };
+        let mut config = Config::default();
+        tuning.apply(&mut config);
+        assert_eq!(config.ping.concurrent_limit, 1);
+        assert_eq!(config.port_scan.timeout, Duration::from_secs(10));
+        assert!(!config.detect_mac);
+        assert_eq!(Tuning::capture(&config).port_timeout_ms, 10_000);
+    }
+}
@@ -5,33 +5,80 @@ use ratatui::layout::{Constraint, Direction, Layout, Rect};
 pub enum LayoutMode {
     Compact,
     Full,
+    /// Details pane below the table, for tall but narrow terminals
+    Stacked,
 }
 
+/// Minimum height for the stacked layout: enough for the table and details
+/// pane to both show a useful number of rows
+const STACKED_MIN_HEIGHT: u16 = 40;
+const STACKED_MIN_WIDTH: u16 = 50;
+/// Below this height a forced stacked layout falls back to Compact
+const STACKED_FORCED_MIN_HEIGHT: u16 = 24;
+/// Header Status box width used until its content is known, and its limits
+const STATUS_BOX_WIDTH: u16 = 35;
+const STATUS_BOX_MIN_WIDTH: u16 = 24;
+const STATUS_BOX_MAX_WIDTH: u16 = 60;
+/// The range box never gets narrower than this to make room for the status
+const RANGE_BOX_MIN_WIDTH: u16 = 30;
+
 impl LayoutMode {
     pub fn from_size(width: u16, height: u16) -> Self {
         if width >= 100 && height >= 30 {
             LayoutMode::Full
+        } else if width >= STACKED_MIN_WIDTH && height >= STACKED_MIN_HEIGHT {
+            LayoutMode::Stacked
         } else {
             LayoutMode::Compact
         }
     }
+
+    /// Like `from_size`, but `force_stacked` prefers the stacked layout whenever
+    /// the terminal has room for it.
+    pub fn choose(width: u16, height: u16, force_stacked: bool) -> Self {
+        if force_stacked && width >= STACKED_MIN_WIDTH && height >= STACKED_FORCED_MIN_HEIGHT {
+            LayoutMode::Stacked
+        } else {
+            Self::from_size(width, height)
+        }
+    }
 }
 
+/// Smallest terminal the UI is drawn in; below it the layout's fixed rows
+/// and boxes overlap, so `draw_ui` only asks for a bigger window
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 15;
+
 /// Layout areas for the application
 #[derive(Debug, Clone)]
 pub struct AppLayout {
     pub mode: LayoutMode,
     pub header: Rect,
+    /// Range input box (left part of the header)
+    pub range_box: Rect,
+    /// Scan status / progress box (right part of the header)
+    pub status_box: Rect,
     #[allow(dead_code)]
     pub main: Rect,
     pub hosts_table: Rect,
     pub details_pane: Option<Rect>,
+    /// Centered overlay used to show host details in Compact mode
+    pub details_popup: Option<Rect>,
+    /// One-line banner across the top of the main area
+    pub banner: Option<Rect>,
     pub status_bar: Rect,
 }
 
 impl AppLayout {
-    pub fn new(area: Rect) -> Self {
-        let mode = LayoutMode::from_size(area.width, area.height);
+    /// Whether `area` is at least [`MIN_WIDTH`] × [`MIN_HEIGHT`]
+    pub fn fits(area: Rect) -> bool {
+        area.width >= MIN_WIDTH && area.height >= MIN_HEIGHT
+    }
+
+    /// `split_percent` is the hosts table's share of the width in the Full layout;
+    /// `force_stacked` selects the stacked layout even on wide terminals.
+    pub fn new(area: Rect, split_percent: u16, force_stacked: bool) -> Self {
+        let mode = LayoutMode::choose(area.width, area.height, force_stacked);
 
         let vertical = Layout::default()
             .direction(Direction::Vertical)
@@ -43,34 +90,72 @@ impl AppLayout {
             .split(area);
 
         let header = vertical[0];
+        let (range_box, status_box) = split_header(header, STATUS_BOX_WIDTH);
         let main = vertical[1];
         let status_bar = vertical[2];
 
-        let (hosts_table, details_pane) = match mode {
-            LayoutMode::Compact => (main, None),
+        let (hosts_table, details_pane, details_popup) = match mode {
+            LayoutMode::Compact => (main, None, Some(centered_rect(80, 80, area))),
             LayoutMode::Full => {
                 let horizontal = Layout::default()
                     .direction(Direction::Horizontal)
                     .constraints([
-                        Constraint::Percentage(55),
-                        Constraint::Percentage(45),
+                        Constraint::Percentage(split_percent),
+                        Constraint::Percentage(100 - split_percent.min(100)),
                     ])
                     .split(main);
 
-                (horizontal[0], Some(horizontal[1]))
+                (horizontal[0], Some(horizontal[1]), None)
+            }
+            LayoutMode::Stacked => {
+                let vertical = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .split(main);
+
+                (vertical[0], Some(vertical[1]), None)
             }
         };
 
         Self {
             mode,
             header,
+            range_box,
+            status_box,
             main,
             hosts_table,
             details_pane,
+            details_popup,
+            banner: None,
             status_bar,
         }
     }
 
+    /// Reserve the top line of the main area for a banner, moving the
+    /// table (and a side pane beside it) down
+    pub fn banner(mut self, show: bool) -> Self {
+        if !show || self.main.height < 2 {
+            return self;
+        }
+        let top = self.main.y;
+        self.banner = Some(Rect { height: 1, ..self.main });
+        for area in std::iter::once(&mut self.hosts_table).chain(self.details_pane.as_mut()) {
+            if area.y == top {
+                area.y += 1;
+                area.height -= 1;
+            }
+        }
+        self
+    }
+
+    /// Size the header Status box for `width` columns of content (borders
+    /// included), within limits and without squeezing the range box
+    pub fn status_width(mut self, width: u16) -> Self {
+        let width = width.clamp(STATUS_BOX_MIN_WIDTH, STATUS_BOX_MAX_WIDTH);
+        (self.range_box, self.status_box) = split_header(self.header, width);
+        self
+    }
+
     pub fn is_compact(&self) -> bool {
         self.mode == LayoutMode::Compact
     }
@@ -80,3 +165,139 @@ impl AppLayout {
         self.mode == LayoutMode::Full
     }
 }
+
+/// Range box and Status box side by side in the header
+fn split_header(header: Rect, status_width: u16) -> (Rect, Rect) {
+    let split = Layout::horizontal([
+        Constraint::Min(RANGE_BOX_MIN_WIDTH),
+        Constraint::Length(status_width),
+    ])
+    .split(header);
+    (split[0], split[1])
+}
+
+/// Rect of `percent_x` × `percent_y` centered inside `r`, for popups and
+/// overlays
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_layout_offers_details_popup_inside_frame() {
+        let area = Rect::new(0, 0, 80, 24);
+        let layout = AppLayout::new(area, 55, false);
+        assert!(layout.is_compact());
+        assert!(layout.details_pane.is_none());
+        let popup = layout.details_popup.expect("popup area in compact mode");
+        assert!(popup.width > 0 && popup.height > 0);
+        assert_eq!(popup.intersection(area), popup);
+    }
+
+    #[test]
+    fn centered_rect_fits_very_large_terminals() {
+        let area = Rect::new(0, 0, 1000, 800);
+        let popup = centered_rect(80, 80, area);
+        assert_eq!((popup.width, popup.height), (800, 640));
+        assert_eq!(popup.intersection(area), popup);
+        assert_eq!(centered_rect(100, 100, area), area);
+    }
+
+    #[test]
+    fn full_layout_uses_side_pane() {
+        let layout = AppLayout::new(Rect::new(0, 0, 120, 40), 55, false);
+        assert!(layout.is_full());
+        assert!(layout.details_pane.is_some());
+        assert!(layout.details_popup.is_none());
+    }
+
+    #[test]
+    fn banner_takes_the_top_line_of_the_main_area() {
+        let area = Rect::new(0, 0, 120, 40);
+        let plain = AppLayout::new(area, 55, false);
+        let layout = AppLayout::new(area, 55, false).banner(true);
+        assert_eq!(layout.banner, Some(Rect::new(0, 3, 120, 1)));
+        assert_eq!(layout.hosts_table.y, plain.hosts_table.y + 1);
+        assert_eq!(layout.hosts_table.height, plain.hosts_table.height - 1);
+        assert_eq!(layout.details_pane.unwrap().y, 4);
+
+        // Stacked: only the table is at the top
+        let stacked = AppLayout::new(area, 55, true);
+        let layout = AppLayout::new(area, 55, true).banner(true);
+        assert_eq!(layout.details_pane, stacked.details_pane);
+        assert_eq!(AppLayout::new(area, 55, false).banner(false).banner, None);
+    }
+
+    #[test]
+    fn full_layout_honours_split_ratio() {
+        let area = Rect::new(0, 0, 200, 40);
+        let narrow = AppLayout::new(area, 30, false);
+        let wide = AppLayout::new(area, 80, false);
+        assert_eq!(narrow.hosts_table.width, 60);
+        assert_eq!(wide.hosts_table.width, 160);
+        let details = wide.details_pane.expect("details pane");
+        assert_eq!(wide.hosts_table.width + details.width, area.width);
+    }
+
+    #[test]
+    fn from_size_thresholds() {
+        assert_eq!(LayoutMode::from_size(100, 30), LayoutMode::Full);
+        assert_eq!(LayoutMode::from_size(60, 50), LayoutMode::Stacked);
+        assert_eq!(LayoutMode::from_size(50, 40), LayoutMode::Stacked);
+        assert_eq!(LayoutMode::from_size(49, 60), LayoutMode::Compact);
+        assert_eq!(LayoutMode::from_size(99, 39), LayoutMode::Compact);
+        assert_eq!(LayoutMode::from_size(80, 24), LayoutMode::Compact);
+    }
+
+    #[test]
+    fn forced_stacked_needs_minimum_room() {
+        assert_eq!(LayoutMode::choose(120, 40, true), LayoutMode::Stacked);
+        assert_eq!(LayoutMode::choose(80, 24, true), LayoutMode::Stacked);
+        assert_eq!(LayoutMode::choose(80, 23, true), LayoutMode::Compact);
+        assert_eq!(LayoutMode::choose(120, 40, false), LayoutMode::Full);
+    }
+
+    #[test]
+    fn status_box_sizes_to_content_within_limits() {
+        let area = Rect::new(0, 0, 120, 40);
+        let layout = AppLayout::new(area, 55, false);
+        assert_eq!(layout.status_box.width, STATUS_BOX_WIDTH);
+
+        let wide = layout.clone().status_width(48);
+        assert_eq!(wide.status_box.width, 48);
+        assert_eq!(wide.range_box.width + wide.status_box.width, area.width);
+        assert_eq!(layout.clone().status_width(500).status_box.width, STATUS_BOX_MAX_WIDTH);
+        assert_eq!(layout.status_width(5).status_box.width, STATUS_BOX_MIN_WIDTH);
+
+        // Narrow terminals keep the range box usable
+        let narrow = AppLayout::new(Rect::new(0, 0, 70, 24), 55, false).status_width(60);
+        assert!(narrow.range_box.width >= RANGE_BOX_MIN_WIDTH);
+    }
+
+    #[test]
+    fn stacked_layout_places_details_below_table() {
+        let layout = AppLayout::new(Rect::new(0, 0, 60, 50), 55, false);
+        assert_eq!(layout.mode, LayoutMode::Stacked);
+        assert!(!layout.is_compact());
+        let details = layout.details_pane.expect("details pane");
+        assert_eq!(details.x, layout.hosts_table.x);
+        assert_eq!(details.width, layout.hosts_table.width);
+        assert_eq!(details.y, layout.hosts_table.y + layout.hosts_table.height);
+        assert!(layout.hosts_table.height > details.height);
+    }
+}
@@ -1,7 +1,9 @@
 pub mod layout;
+pub mod shortcuts;
+pub mod text;
 pub mod theme;
 pub mod widgets;
 
 pub use layout::AppLayout;
-pub use theme::{Compat, Theme};
-pub use widgets::{DetailsPane, InputBar, ProgressBar, ScanTable, StatusBar};
+pub use theme::{Compat, Symbol, Theme};
+pub use widgets::{Column, DetailsPane, InputBar, ProgressBar, ScanTable, StatusBar};
@@ -0,0 +1,515 @@
+use ratatui::text::{Line, Span};
+
+use super::theme::{Compat, Symbol, Theme};
+
+/// Where a shortcut is offered in the Ctrl keybindings popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    RangeInput,
+    HostsTable,
+    DetailsPane,
+    DetailsPopup,
+    EditingRange,
+    EditingPorts,
+    OutputOverlay,
+}
+
+impl Context {
+    pub fn title(self) -> &'static str {
+        match self {
+            Context::RangeInput => "Range / Scan",
+            Context::HostsTable => "Hosts Table",
+            Context::DetailsPane => "Host Details",
+            Context::DetailsPopup => "Host Details (popup)",
+            Context::EditingRange => "Editing Range",
+            Context::EditingPorts => "Editing Ports",
+            Context::OutputOverlay => "Output View",
+        }
+    }
+}
+
+/// Help overlay section a shortcut is listed under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Scanning,
+    Navigation,
+    Selection,
+    Details,
+    Display,
+    Editing,
+    General,
+}
+
+impl Section {
+    pub const ALL: [Section; 7] = [
+        Section::Scanning,
+        Section::Navigation,
+        Section::Selection,
+        Section::Details,
+        Section::Display,
+        Section::Editing,
+        Section::General,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Section::Scanning => "Scanning",
+            Section::Navigation => "Navigation",
+            Section::Selection => "Selection & Export",
+            Section::Details => "Host Details",
+            Section::Display => "Layout & Display",
+            Section::Editing => "Editing & Output",
+            Section::General => "General",
+        }
+    }
+}
+
+/// A key binding as shown to the user
+pub struct Shortcut {
+    pub key: &'static str,
+    /// ASCII spelling of `key` for compat mode
+    pub compat_key: &'static str,
+    /// Short label for the Ctrl popup
+    pub label: &'static str,
+    /// Longer description for the help overlay
+    pub help: &'static str,
+    pub section: Section,
+    pub contexts: &'static [Context],
+}
+
+impl Shortcut {
+    pub fn key(&self, compat: bool) -> &'static str {
+        if compat {
+            self.compat_key
+        } else {
+            self.key
+        }
+    }
+}
+
+const fn shortcut(
+    key: &'static str,
+    label: &'static str,
+    help: &'static str,
+    section: Section,
+    contexts: &'static [Context],
+) -> Shortcut {
+    Shortcut {
+        key,
+        compat_key: key,
+        label,
+        help,
+        section,
+        contexts,
+    }
+}
+
+use Context::*;
+
+/// Every user-facing key binding. The help overlay and the Ctrl popup are both
+/// generated from this list, so new keys only need to be added here.
+pub const SHORTCUTS: &[Shortcut] = &[
+    shortcut("[S]", "Scan", "Start scan", Section::Scanning, &[RangeInput, HostsTable]),
+    shortcut("[X]", "Stop", "Stop / pause scan", Section::Scanning, &[]),
+    shortcut("[Space]", "Resume", "Resume a paused scan", Section::Scanning, &[]),
+    shortcut("[R]", "Edit range", "Edit IP range", Section::Scanning, &[RangeInput]),
+    shortcut("[P]", "Edit ports", "Configure ports", Section::Scanning, &[RangeInput]),
+    shortcut(
+        "[B]",
+        "Browse",
+        "Discover devices over SSDP / mDNS (names, services)",
+        Section::Scanning,
+        &[RangeInput, HostsTable],
+    ),
+    shortcut(
+        "[[/]]",
+        "Batch range",
+        "Batch scan (ranges split by ;): show one range at a time",
+        Section::Scanning,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[F]",
+        "Filter",
+        "Cycle filter (All / Online / Pingable / Stale)",
+        Section::Scanning,
+        &[RangeInput, HostsTable],
+    ),
+    Shortcut {
+        key: "[↑/↓][j/k]",
+        compat_key: "[^/v][j/k]",
+        label: "Navigate",
+        help: "Navigate rows",
+        section: Section::Navigation,
+        contexts: &[HostsTable],
+    },
+    shortcut("[PgUp/PgDn]", "Jump 10", "Jump 10 rows", Section::Navigation, &[HostsTable]),
+    shortcut("[Home/End]", "First/last", "First / last row", Section::Navigation, &[HostsTable]),
+    shortcut(
+        "[gg/G]",
+        "First/last",
+        "First / last row (vim); a count jumps: 25j, 10k, 5G",
+        Section::Navigation,
+        &[],
+    ),
+    shortcut(
+        "[:]",
+        "Go to",
+        "Go to a host by its last octet or address (also #), then Enter",
+        Section::Navigation,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[Enter]",
+        "Details",
+        "Open host details; with details hidden, run the Enter action",
+        Section::Navigation,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[Tab]",
+        "Next pane",
+        "Switch panes (Shift+Tab goes back)",
+        Section::Navigation,
+        &[RangeInput, HostsTable, DetailsPane],
+    ),
+    shortcut(
+        "[Space]",
+        "Select",
+        "Toggle host selection (multi-select)",
+        Section::Selection,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[E]",
+        "Export",
+        "Export all hosts, or the selected subset; T saves the table as text",
+        Section::Selection,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[Enter]",
+        "Default action",
+        "Run the Enter action (default: port scan if unscanned, else ping)",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[W]",
+        "Wake-on-LAN",
+        "Wake-on-LAN: the host, or every selected host in turn ([W] again stops)",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut("[P]", "Scan ports", "Scan ports", Section::Details, &[DetailsPane, DetailsPopup]),
+    shortcut("[C]", "Ping", "Continuous ping", Section::Details, &[DetailsPane, DetailsPopup]),
+    shortcut("[T]", "Tracert", "Tracert", Section::Details, &[DetailsPane, DetailsPopup]),
+    shortcut("[A]", "Save", "Save host to file", Section::Details, &[DetailsPane, DetailsPopup]),
+    shortcut(
+        "[Shift+A]",
+        "Save JSON",
+        "Save host as JSON (same fields as the JSON export)",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[Shift+M]",
+        "Refresh MAC",
+        "Read the host's MAC from the ARP table again, with its vendor",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[Del]",
+        "Forget",
+        "Forget cached hostname, MAC and ports of the host",
+        Section::Details,
+        &[HostsTable, DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[O]",
+        "Last output",
+        "Reopen the last ping / tracert output",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[Y]",
+        "Copy IP",
+        "Copy the host's IP to the clipboard",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[Shift+Y]",
+        "Copy command",
+        "Copy a ping / ssh / rdp command line for the host (templates in settings)",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut(
+        "[Ctrl+K]",
+        "Knock",
+        "Send a port knock sequence to the host (knock_actions in settings)",
+        Section::Details,
+        &[DetailsPane, DetailsPopup],
+    ),
+    shortcut("[Esc]", "Close", "Close the details popup", Section::Details, &[DetailsPopup]),
+    shortcut("[Esc]", "Back", "Back to the hosts table", Section::Details, &[DetailsPane]),
+    shortcut("[D]", "Details pane", "Toggle details pane", Section::Display, &[HostsTable]),
+    shortcut(
+        "[</>]",
+        "Resize",
+        "Narrow / widen the hosts table",
+        Section::Display,
+        &[HostsTable],
+    ),
+    shortcut("[V]", "Stack", "Stack details below the table", Section::Display, &[HostsTable]),
+    shortcut(
+        "[L]",
+        "Legend",
+        "Toggle legend under the hosts table",
+        Section::Display,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[N]",
+        "Last seen",
+        "Toggle the last seen column (saved with the column set)",
+        Section::Display,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[M]",
+        "Uptime",
+        "Toggle the uptime column (time up / down across rescans)",
+        Section::Display,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[Shift+H]",
+        "Changes",
+        "Select the host of the latest up / down change (again for older ones)",
+        Section::Navigation,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[=]",
+        "Same MAC",
+        "Select the next address with the selected host's MAC",
+        Section::Navigation,
+        &[HostsTable, DetailsPane],
+    ),
+    shortcut(
+        "[Shift+P]",
+        "Ports",
+        "Toggle the open ports column (service names)",
+        Section::Display,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[Z]",
+        "Columns",
+        "Pick and order the hosts table columns (saved between runs)",
+        Section::Display,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[Enter]",
+        "Apply",
+        "Apply range / ports",
+        Section::Editing,
+        &[EditingRange, EditingPorts],
+    ),
+    shortcut("[Esc]", "Cancel", "Cancel editing", Section::Editing, &[EditingRange, EditingPorts]),
+    Shortcut {
+        key: "[←/→]",
+        compat_key: "[Left/Right]",
+        label: "Move cursor",
+        help: "Move cursor",
+        section: Section::Editing,
+        contexts: &[EditingRange, EditingPorts],
+    },
+    Shortcut {
+        key: "[Ctrl+←/→]",
+        compat_key: "[Ctrl+Left/Right]",
+        label: "Word",
+        help: "Move by word (octet, prefix, port)",
+        section: Section::Editing,
+        contexts: &[EditingRange, EditingPorts],
+    },
+    shortcut(
+        "[Ctrl+Bksp]",
+        "Del word",
+        "Delete the previous word (also Ctrl+W)",
+        Section::Editing,
+        &[EditingRange, EditingPorts],
+    ),
+    shortcut("[Ctrl+U]", "Clear", "Clear the field", Section::Editing, &[EditingRange, EditingPorts]),
+    shortcut("[Tab]", "Edit ports", "Jump from range to ports", Section::Editing, &[EditingRange]),
+    shortcut(
+        "[X]",
+        "Stop",
+        "Stop ping / tracert, keeping the output",
+        Section::Editing,
+        &[OutputOverlay],
+    ),
+    shortcut(
+        "[Esc]",
+        "Close",
+        "Close the output (stops a running ping / tracert)",
+        Section::Editing,
+        &[OutputOverlay],
+    ),
+    Shortcut {
+        key: "[↑/↓]",
+        compat_key: "[^/v]",
+        label: "Scroll",
+        help: "Scroll output",
+        section: Section::Editing,
+        contexts: &[OutputOverlay],
+    },
+    shortcut(
+        "[H]",
+        "Event log",
+        "Event log (scan lifecycle, errors); [F] filters by severity",
+        Section::General,
+        &[RangeInput],
+    ),
+    shortcut(
+        "[I]",
+        "Conflicts",
+        "IP conflicts (MACs on several IPs or changed) and port changes",
+        Section::General,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[U]",
+        "Summary",
+        "Scan summary: hosts per /24, vendors, services, RTT",
+        Section::General,
+        &[HostsTable],
+    ),
+    shortcut(
+        "[F2]",
+        "Settings",
+        "Timeouts, retries, concurrency and lookups (saved between runs)",
+        Section::General,
+        &[RangeInput, HostsTable],
+    ),
+    shortcut("[?]", "Help", "Show this help", Section::General, &[]),
+    shortcut(
+        "[F1]",
+        "Shortcuts",
+        "Show / hide the shortcuts bar (or hold Left Ctrl)",
+        Section::General,
+        &[],
+    ),
+    shortcut(
+        "[Q]",
+        "Quit",
+        "Quit (also Ctrl+C)",
+        Section::General,
+        &[RangeInput, HostsTable, DetailsPane, DetailsPopup],
+    ),
+];
+
+/// Shortcuts offered in `context`, in list order
+pub fn for_context(context: Context) -> impl Iterator<Item = &'static Shortcut> {
+    SHORTCUTS.iter().filter(move |s| s.contexts.contains(&context))
+}
+
+/// Width of the horizontal rule that section headings are padded to
+const RULE_WIDTH: usize = 38;
+
+fn section_heading(title: &str, compat: bool) -> String {
+    let rule = if compat { '-' } else { '─' };
+    let head = format!("{rule}{rule} {title} ");
+    let pad = RULE_WIDTH.saturating_sub(head.chars().count());
+    format!("{head}{}", rule.to_string().repeat(pad))
+}
+
+/// Full help overlay text: one line per shortcut, grouped by section, followed
+/// by the table symbol legend.
+pub fn help_lines(compat: bool) -> Vec<Line<'static>> {
+    let (title_style, hotkey_style, dimmed_style) = if compat {
+        (Compat::title(), Compat::hotkey(), Compat::dimmed())
+    } else {
+        (Theme::title(), Theme::hotkey(), Theme::dimmed())
+    };
+    let title = if compat {
+        "IPSCANNR - Keyboard Shortcuts"
+    } else {
+        "IPSCANNR — Keyboard Shortcuts"
+    };
+
+    let key_width = SHORTCUTS
+        .iter()
+        .map(|s| s.key(compat).chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = vec![Line::from(Span::styled(title, title_style))];
+    for section in Section::ALL {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            section_heading(section.title(), compat),
+            dimmed_style,
+        )));
+        for s in SHORTCUTS.iter().filter(|s| s.section == section) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<key_width$}", s.key(compat)), hotkey_style),
+                Span::raw(format!(" {}", s.help)),
+            ]));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        section_heading("Symbols", compat),
+        dimmed_style,
+    )));
+    lines.push(Line::from(Symbol::legend_spans(compat)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_popup_context_has_shortcuts() {
+        for context in [
+            RangeInput,
+            HostsTable,
+            DetailsPane,
+            DetailsPopup,
+            EditingRange,
+            EditingPorts,
+            OutputOverlay,
+        ] {
+            assert!(for_context(context).next().is_some(), "{:?} empty", context);
+        }
+    }
+
+    #[test]
+    fn compat_keys_are_ascii() {
+        for s in SHORTCUTS {
+            assert!(s.compat_key.is_ascii(), "{} not ASCII", s.compat_key);
+            assert!(s.help.is_ascii() && s.label.is_ascii());
+        }
+    }
+
+    #[test]
+    fn help_lists_every_shortcut() {
+        for compat in [false, true] {
+            let text: String = help_lines(compat)
+                .iter()
+                .flat_map(|l| l.spans.iter().map(|s| s.content.to_string()))
+                .collect();
+            for s in SHORTCUTS {
+                assert!(text.contains(s.key(compat)), "{} missing", s.key(compat));
+                assert!(text.contains(s.help), "{} missing", s.help);
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use unicode_width::UnicodeWidthStr;
+
+/// Shorten `text` to at most `width` terminal cells, ending in "…" ("~" in
+/// compat mode) when anything was cut. Widths are counted in display cells,
+/// so CJK and emoji (two cells each) can't push later columns out of line.
+pub fn truncate(text: &str, width: usize, compat: bool) -> Cow<'_, str> {
+    if text.width() <= width {
+        return Cow::Borrowed(text);
+    }
+    if width == 0 {
+        return Cow::Borrowed("");
+    }
+    let marker = if compat { '~' } else { '…' };
+    let budget = width - 1;
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        // Measure the whole prefix: a variation selector can widen the
+        // character before it, which per-char widths don't see
+        out.push(c);
+        if out.width() > budget {
+            out.pop();
+            break;
+        }
+    }
+    out.push(marker);
+    Cow::Owned(out)
+}
+
+/// "0.4ms" under 10 ms, where a LAN's round trips live; whole milliseconds above
+pub fn rtt_text(rtt: Duration) -> String {
+    let micros = rtt.as_micros();
+    if micros < 9_950 {
+        format!("{:.1}ms", micros as f64 / 1000.0)
+    } else {
+        format!("{}ms", (micros + 500) / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_untouched() {
+        assert!(matches!(truncate("nas.lan", 7, false), Cow::Borrowed("nas.lan")));
+    }
+
+    #[test]
+    fn cuts_to_display_width_with_marker() {
+        assert_eq!(truncate("printer-upstairs.lan", 10, false), "printer-u…");
+        assert_eq!(truncate("printer-upstairs.lan", 10, true), "printer-u~");
+        // Wide characters count double; one that would straddle the edge is dropped
+        assert_eq!(truncate("東京サーバー.local", 8, false), "東京サ…");
+        assert_eq!(truncate("東京サーバー.local", 8, false).width(), 7);
+        assert_eq!(truncate("🖨️printer", 3, true).width(), 3);
+        assert_eq!(truncate("anything", 0, false), "");
+    }
+
+    #[test]
+    fn rtt_is_fractional_under_ten_ms() {
+        assert_eq!(rtt_text(Duration::from_micros(412)), "0.4ms");
+        assert_eq!(rtt_text(Duration::ZERO), "0.0ms");
+        assert_eq!(rtt_text(Duration::from_micros(9_940)), "9.9ms");
+        // Would round up to "10.0ms"
+        assert_eq!(rtt_text(Duration::from_micros(9_960)), "10ms");
+        assert_eq!(rtt_text(Duration::from_millis(23)), "23ms");
+        assert_eq!(rtt_text(Duration::from_micros(23_600)), "24ms");
+    }
+}
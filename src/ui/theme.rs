@@ -1,5 +1,38 @@
+use std::cell::Cell;
+use std::time::Duration;
+
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::symbols;
+use ratatui::text::Span;
+
+thread_local! {
+    // Rendering happens on a single thread, so a thread-local keeps the style
+    // getters argument-free while letting tests toggle the mode in isolation.
+    static MONOCHROME: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enable or disable monochrome rendering (set from `--no-color` / `NO_COLOR`)
+pub fn set_monochrome(enabled: bool) {
+    MONOCHROME.with(|m| m.set(enabled));
+}
+
+pub fn is_monochrome() -> bool {
+    MONOCHROME.with(|m| m.get())
+}
+
+/// Strip foreground/background colors in monochrome mode, keeping modifiers
+/// (bold, reversed, underline) so emphasis and selection stay visible.
+fn paint(style: Style) -> Style {
+    if is_monochrome() {
+        Style {
+            fg: None,
+            bg: None,
+            ..style
+        }
+    } else {
+        style
+    }
+}
 
 /// Minimal dark color palette
 pub struct Theme;
@@ -10,85 +43,134 @@ impl Theme {
     pub const FG: Color = Color::Rgb(200, 200, 210);
     pub const ACCENT: Color = Color::Rgb(100, 149, 237);
     pub const SUCCESS: Color = Color::Rgb(80, 200, 120);
-    #[allow(dead_code)]
     pub const ERROR: Color = Color::Rgb(220, 80, 80);
     pub const WARNING: Color = Color::Rgb(230, 180, 80);
     pub const DIM: Color = Color::Rgb(90, 90, 100);
     pub const BORDER: Color = Color::Rgb(60, 60, 70);
     pub const HIGHLIGHT_BG: Color = Color::Rgb(40, 40, 55);
+    pub const STRIPE_BG: Color = Color::Rgb(25, 25, 33);
 
     // Common styles
     pub fn default() -> Style {
-        Style::default().fg(Self::FG).bg(Self::BG)
+        paint(Style::default().fg(Self::FG).bg(Self::BG))
     }
 
     pub fn title() -> Style {
-        Style::default()
-            .fg(Self::ACCENT)
-            .add_modifier(Modifier::BOLD)
+        paint(
+            Style::default()
+                .fg(Self::ACCENT)
+                .add_modifier(Modifier::BOLD)
+        )
     }
 
     pub fn border() -> Style {
-        Style::default().fg(Self::BORDER)
+        paint(Style::default().fg(Self::BORDER))
     }
 
     pub fn border_focused() -> Style {
-        Style::default().fg(Self::ACCENT)
+        paint(Style::default().fg(Self::ACCENT))
     }
 
     pub fn status_online() -> Style {
-        Style::default().fg(Self::SUCCESS)
+        paint(Style::default().fg(Self::SUCCESS))
     }
 
     pub fn status_offline() -> Style {
-        Style::default().fg(Self::DIM)
+        paint(Style::default().fg(Self::DIM))
+    }
+
+    pub fn status_stale() -> Style {
+        paint(Style::default().fg(Self::ERROR).add_modifier(Modifier::DIM))
     }
 
     #[allow(dead_code)]
     pub fn status_scanning() -> Style {
-        Style::default()
-            .fg(Self::WARNING)
-            .add_modifier(Modifier::SLOW_BLINK)
+        paint(
+            Style::default()
+                .fg(Self::WARNING)
+                .add_modifier(Modifier::SLOW_BLINK)
+        )
     }
 
     pub fn selected() -> Style {
+        if is_monochrome() {
+            // Without a highlight background the row needs reverse video to stand out
+            return Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED);
+        }
         Style::default()
             .bg(Self::HIGHLIGHT_BG)
             .fg(Self::FG)
             .add_modifier(Modifier::BOLD)
     }
 
+    /// Background of every other table row, so the eye can follow a row
+    /// across columns; monochrome drops it along with the other colors
+    pub fn row_stripe() -> Style {
+        paint(Style::default().bg(Self::STRIPE_BG))
+    }
+
+    /// Laid over a pane while another one has focus
+    pub fn unfocused() -> Style {
+        Style::default().add_modifier(Modifier::DIM)
+    }
+
+    pub fn accent() -> Style {
+        paint(Style::default().fg(Self::ACCENT))
+    }
+
+    pub fn success() -> Style {
+        paint(Style::default().fg(Self::SUCCESS))
+    }
+
+    pub fn warning() -> Style {
+        paint(Style::default().fg(Self::WARNING))
+    }
+
     pub fn dimmed() -> Style {
-        Style::default().fg(Self::DIM)
+        paint(Style::default().fg(Self::DIM))
     }
 
-    #[allow(dead_code)]
     pub fn error() -> Style {
-        Style::default().fg(Self::ERROR)
+        paint(Style::default().fg(Self::ERROR))
+    }
+
+    /// Green under 5 ms, unstyled under 50, warning under 200, error above.
+    /// Patch it over the row style; compat mode leaves RTTs plain.
+    pub fn rtt(rtt: Duration) -> Style {
+        match rtt.as_millis() {
+            0..=4 => Self::success(),
+            5..=49 => Style::default(),
+            50..=199 => Self::warning(),
+            _ => Self::error(),
+        }
     }
 
     pub fn hotkey() -> Style {
-        Style::default()
-            .fg(Self::ACCENT)
-            .add_modifier(Modifier::BOLD)
+        paint(
+            Style::default()
+                .fg(Self::ACCENT)
+                .add_modifier(Modifier::BOLD)
+        )
     }
 
     pub fn hotkey_desc() -> Style {
-        Style::default().fg(Self::DIM)
+        paint(Style::default().fg(Self::DIM))
     }
 
     pub fn header() -> Style {
-        Style::default()
-            .fg(Self::ACCENT)
-            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        paint(
+            Style::default()
+                .fg(Self::ACCENT)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        )
     }
 
     pub fn progress_bar() -> Style {
-        Style::default().fg(Self::ACCENT)
+        paint(Style::default().fg(Self::ACCENT))
     }
 
     pub fn progress_bg() -> Style {
-        Style::default().fg(Self::BORDER)
+        paint(Style::default().fg(Self::BORDER))
     }
 }
 
@@ -100,12 +182,14 @@ pub struct Compat;
 impl Compat {
     pub const SYM_ONLINE: &'static str = "*";
     pub const SYM_OFFLINE: &'static str = ".";
-    #[allow(dead_code)]
     pub const SYM_SELECTED: &'static str = "x";
-    pub const SYM_CURSOR: &'static str = "> ";
+    pub const SYM_CURSOR: &'static str = ">";
     pub const SYM_PROGRESS_FILL: &'static str = "#";
     pub const SYM_PROGRESS_EMPTY: &'static str = "-";
     pub const SYM_CACHED: &'static str = "[c]";
+    pub const SYM_PAUSED: &'static str = "||";
+    pub const SYM_CONFLICT: &'static str = "!";
+    pub const SYM_STALE: &'static str = "~";
 
     /// ASCII border set: `+`, `-`, `|` corners for compat rendering
     pub const BORDERS: symbols::border::Set = symbols::border::Set {
@@ -121,45 +205,198 @@ impl Compat {
 
     // Compat styles use basic 16-color ANSI (no RGB) for maximum compatibility
     pub fn default() -> Style {
-        Style::default()
+        paint(Style::default())
     }
     pub fn title() -> Style {
-        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        paint(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
     }
     pub fn border() -> Style {
-        Style::default()
+        paint(Style::default())
     }
     pub fn border_focused() -> Style {
-        Style::default().fg(Color::Cyan)
+        paint(Style::default().fg(Color::Cyan))
     }
     pub fn status_online() -> Style {
-        Style::default().fg(Color::Green)
+        paint(Style::default().fg(Color::Green))
     }
     pub fn status_offline() -> Style {
-        Style::default().fg(Color::DarkGray)
+        paint(Style::default().fg(Color::DarkGray))
+    }
+    pub fn status_stale() -> Style {
+        paint(Style::default().fg(Color::Red).add_modifier(Modifier::DIM))
     }
     pub fn selected() -> Style {
-        Style::default().add_modifier(Modifier::REVERSED)
+        paint(Style::default().add_modifier(Modifier::REVERSED))
     }
     pub fn dimmed() -> Style {
-        Style::default().fg(Color::DarkGray)
+        paint(Style::default().fg(Color::DarkGray))
+    }
+    /// No striping: RMM consoles often render any background as a solid bar
+    pub fn row_stripe() -> Style {
+        Style::default()
+    }
+    pub fn unfocused() -> Style {
+        Style::default()
     }
     pub fn hotkey() -> Style {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        paint(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
     }
     pub fn accent() -> Style {
-        Style::default().fg(Color::Cyan)
+        paint(Style::default().fg(Color::Cyan))
     }
     pub fn header() -> Style {
-        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        paint(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
     }
     pub fn progress_bar() -> Style {
-        Style::default().fg(Color::Cyan)
+        paint(Style::default().fg(Color::Cyan))
     }
     pub fn progress_bg() -> Style {
-        Style::default().fg(Color::DarkGray)
+        paint(Style::default().fg(Color::DarkGray))
     }
     pub fn warning() -> Style {
-        Style::default().fg(Color::Yellow)
+        paint(Style::default().fg(Color::Yellow))
+    }
+    pub fn error() -> Style {
+        paint(Style::default().fg(Color::Red))
+    }
+}
+
+// ── Table symbols ─────────────────────────────────────────────────────────────
+
+/// Glyphs used by the hosts table and details pane. Rendering code and the
+/// legend both read from here so the explanation can't drift from the display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbol {
+    Online,
+    Offline,
+    Selected,
+    Cursor,
+    Cached,
+    /// Duplicate or changed MAC: a possible IP conflict
+    Conflict,
+    /// Offline now, online in the cached scan
+    Stale,
+}
+
+impl Symbol {
+    /// Symbols in legend order
+    pub const ALL: [Symbol; 7] = [
+        Symbol::Online,
+        Symbol::Offline,
+        Symbol::Stale,
+        Symbol::Selected,
+        Symbol::Cursor,
+        Symbol::Cached,
+        Symbol::Conflict,
+    ];
+
+    pub fn glyph(self, compat: bool) -> &'static str {
+        match (self, compat) {
+            (Symbol::Online, false) => "●",
+            (Symbol::Online, true) => Compat::SYM_ONLINE,
+            (Symbol::Offline, false) => "○",
+            (Symbol::Offline, true) => Compat::SYM_OFFLINE,
+            (Symbol::Selected, false) => "✓",
+            (Symbol::Selected, true) => Compat::SYM_SELECTED,
+            (Symbol::Cursor, false) => "▶",
+            (Symbol::Cursor, true) => Compat::SYM_CURSOR,
+            (Symbol::Cached, false) => "◷",
+            (Symbol::Cached, true) => Compat::SYM_CACHED,
+            (Symbol::Conflict, false) => "⚠",
+            (Symbol::Conflict, true) => Compat::SYM_CONFLICT,
+            (Symbol::Stale, false) => "◌",
+            (Symbol::Stale, true) => Compat::SYM_STALE,
+        }
+    }
+
+    pub fn style(self, compat: bool) -> Style {
+        match (self, compat) {
+            (Symbol::Online, false) => Theme::status_online(),
+            (Symbol::Online, true) => Compat::status_online(),
+            (Symbol::Offline, false) => Theme::status_offline(),
+            (Symbol::Offline, true) => Compat::status_offline(),
+            (Symbol::Selected, false) => Theme::success(),
+            (Symbol::Selected, true) => Compat::accent(),
+            (Symbol::Cursor, false) => Theme::accent(),
+            (Symbol::Cursor, true) => Compat::accent(),
+            (Symbol::Cached, false) => Theme::warning(),
+            (Symbol::Cached, true) => Compat::warning(),
+            (Symbol::Conflict, false) => Theme::error(),
+            (Symbol::Conflict, true) => Compat::error(),
+            (Symbol::Stale, false) => Theme::status_stale(),
+            (Symbol::Stale, true) => Compat::status_stale(),
+        }
+    }
+
+    pub fn meaning(self) -> &'static str {
+        match self {
+            Symbol::Online => "online",
+            Symbol::Offline => "offline",
+            Symbol::Selected => "selected",
+            Symbol::Cursor => "cursor",
+            Symbol::Cached => "cached",
+            Symbol::Conflict => "conflict",
+            Symbol::Stale => "stale",
+        }
+    }
+
+    /// Styled glyph span for rendering
+    pub fn span(self, compat: bool) -> Span<'static> {
+        Span::styled(self.glyph(compat), self.style(compat))
+    }
+
+    /// Compact one-line legend: "● online  ○ offline  ✓ selected ..."
+    pub fn legend_spans(compat: bool) -> Vec<Span<'static>> {
+        let desc_style = if compat { Compat::dimmed() } else { Theme::hotkey_desc() };
+        let mut spans = Vec::new();
+        for (i, sym) in Self::ALL.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(sym.span(compat));
+            spans.push(Span::styled(format!(" {}", sym.meaning()), desc_style));
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legend_lists_every_symbol_once() {
+        for compat in [false, true] {
+            let text: String = Symbol::legend_spans(compat)
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect();
+            for sym in Symbol::ALL {
+                assert!(text.contains(sym.glyph(compat)), "{:?} missing", sym);
+                assert_eq!(text.matches(sym.meaning()).count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn rtt_colors_by_threshold() {
+        let ms = |n: u64| Theme::rtt(Duration::from_micros(n));
+        assert_eq!(ms(400), Theme::success());
+        assert_eq!(ms(4_999), Theme::success());
+        assert_eq!(ms(5_000), Style::default());
+        assert_eq!(ms(120_000), Theme::warning());
+        assert_eq!(ms(200_000), Theme::error());
+
+        set_monochrome(true);
+        assert_eq!(ms(400).fg, None);
+        assert_eq!(ms(900_000).fg, None);
+        set_monochrome(false);
+    }
+
+    #[test]
+    fn compat_glyphs_are_ascii() {
+        for sym in Symbol::ALL {
+            assert!(sym.glyph(true).is_ascii(), "{:?} not ASCII", sym);
+        }
     }
 }
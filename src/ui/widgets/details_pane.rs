@@ -1,20 +1,34 @@
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
 use crate::app::HostInfo;
 use crate::cache::format_cache_age;
-use crate::scanner::get_service_name;
-use crate::ui::theme::{Compat, Theme};
+use crate::conflict::{Conflict, MacGroup};
+use crate::highlight::Highlighter;
+use crate::names::NameSource;
+use crate::portdiff::PortDiff;
+use crate::scanner::{get_service_name, ProbeError};
+use crate::ui::text::{rtt_text, truncate};
+use crate::ui::theme::{Compat, Symbol, Theme};
 
 pub struct DetailsPane<'a> {
     host: Option<&'a HostInfo>,
     focused: bool,
     port_scanning: bool,
+    /// Ports the last scan found opened or closed, marked + / - in the list
+    port_diff: Option<PortDiff>,
+    /// Conflicts in the results; the ones involving this host are explained
+    conflicts: &'a [Conflict],
+    /// Hosts sharing a MAC; the others in this host's group are listed
+    mac_groups: &'a [MacGroup],
+    /// Rules tagging hosts; the host's tags are listed
+    highlighter: Option<&'a Highlighter>,
+    /// Order the host's names are listed in (`--name-order`)
+    name_order: &'a [NameSource],
     compat: bool,
 }
 
@@ -24,6 +38,11 @@ impl<'a> DetailsPane<'a> {
             host,
             focused: false,
             port_scanning: false,
+            port_diff: None,
+            conflicts: &[],
+            mac_groups: &[],
+            highlighter: None,
+            name_order: &NameSource::DEFAULT_ORDER,
             compat: false,
         }
     }
@@ -38,6 +57,31 @@ impl<'a> DetailsPane<'a> {
         self
     }
 
+    pub fn port_diff(mut self, diff: Option<PortDiff>) -> Self {
+        self.port_diff = diff;
+        self
+    }
+
+    pub fn conflicts(mut self, conflicts: &'a [Conflict]) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
+
+    pub fn mac_groups(mut self, groups: &'a [MacGroup]) -> Self {
+        self.mac_groups = groups;
+        self
+    }
+
+    pub fn highlighter(mut self, highlighter: &'a Highlighter) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    pub fn name_order(mut self, order: &'a [NameSource]) -> Self {
+        self.name_order = order;
+        self
+    }
+
     pub fn compat(mut self, compat: bool) -> Self {
         self.compat = compat;
         self
@@ -51,7 +95,7 @@ impl Widget for DetailsPane<'_> {
             (border, Compat::title(), Compat::dimmed(), Compat::default(), Compat::header(), Compat::accent(), Compat::status_online(), Compat::status_offline(), Compat::warning())
         } else {
             let border = if self.focused { Theme::border_focused() } else { Theme::border() };
-            (border, Theme::title(), Theme::dimmed(), Theme::default(), Theme::header(), Style::default().fg(Theme::ACCENT), Theme::status_online(), Theme::status_offline(), Style::default().fg(Theme::WARNING))
+            (border, Theme::title(), Theme::dimmed(), Theme::default(), Theme::header(), Theme::accent(), Theme::status_online(), Theme::status_offline(), Theme::warning())
         };
 
         let mut block = Block::default()
@@ -76,11 +120,13 @@ impl Widget for DetailsPane<'_> {
         };
 
         let mut lines = Vec::new();
+        // Room for a value after the 10-column "Label:    " prefix
+        let value_width = (inner.width as usize).saturating_sub(10);
 
         // Cache indicator — shown when this host's data came from a previous scan
         if let Some(scanned_at) = host.cached_at {
             let age = format_cache_age(scanned_at);
-            let cache_sym = if self.compat { Compat::SYM_CACHED } else { "◷" };
+            let cache_sym = Symbol::Cached.glyph(self.compat);
             lines.push(Line::from(vec![
                 Span::styled(
                     format!("{} Cached · {}", cache_sym, age),
@@ -90,33 +136,140 @@ impl Widget for DetailsPane<'_> {
             lines.push(Line::from(""));
         }
 
+        // Possible IP conflicts involving this host
+        let error_style = if self.compat { Compat::error() } else { Theme::error() };
+        let mut conflicted = false;
+        for conflict in self.conflicts.iter().filter(|c| c.involves(host.ip)) {
+            let text = format!(
+                "{} {}",
+                Symbol::Conflict.glyph(self.compat),
+                conflict.describe_for(host.ip)
+            );
+            lines.push(Line::from(Span::styled(
+                truncate(&text, inner.width as usize, self.compat).into_owned(),
+                error_style,
+            )));
+            conflicted = true;
+        }
+        if conflicted {
+            lines.push(Line::from(""));
+        }
+
         // IP Address
         lines.push(Line::from(vec![
             Span::styled("IP:       ", dimmed_style),
             Span::styled(host.ip.to_string(), default_style),
         ]));
 
-        // Status
-        let status_style = if host.is_alive { status_online_style } else { status_offline_style };
-        let status_text = if host.is_alive { "Online" } else { "Offline" };
+        // Tags from the highlight rules, in the color the row gets
+        let highlight = self.highlighter.map(|h| h.highlight(host)).unwrap_or_default();
+        if !highlight.tags.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled("Tags:     ", dimmed_style),
+                Span::styled(
+                    truncate(&highlight.tags.join(", "), value_width, self.compat).into_owned(),
+                    highlight.badge_style(self.compat),
+                ),
+            ]));
+        }
+
+        // Status, with why the probes failed when it's more than a timeout
+        let dash = if self.compat { "-" } else { "—" };
+        let cause = host
+            .probe_error
+            .filter(|e| !host.is_alive && *e != ProbeError::Timeout)
+            .map(|e| format!(" {} {}", dash, e))
+            .unwrap_or_default();
+        let (status_text, status_style) = match (host.is_alive, host.stale) {
+            (true, _) => ("Online".to_string(), status_online_style),
+            (false, true) => (
+                format!("Offline (stale: online in the cached scan){}", cause),
+                Symbol::Stale.style(self.compat),
+            ),
+            (false, false) => (format!("Offline{}", cause), status_offline_style),
+        };
         lines.push(Line::from(vec![
             Span::styled("Status:   ", dimmed_style),
-            Span::styled(status_text, status_style),
+            Span::styled(
+                truncate(&status_text, value_width, self.compat).into_owned(),
+                status_style,
+            ),
         ]));
 
+        // When an offline host was last seen online, in this or an earlier scan
+        if let Some(at) = host.last_seen.filter(|_| !host.is_alive) {
+            lines.push(Line::from(vec![
+                Span::styled("Last seen: ", dimmed_style),
+                Span::styled(format_cache_age(at), default_style),
+            ]));
+        }
+
         // RTT
         if let Some(rtt) = host.rtt {
             lines.push(Line::from(vec![
                 Span::styled("RTT:      ", dimmed_style),
-                Span::styled(format!("{}ms", rtt.as_millis()), default_style),
+                Span::styled(
+                    rtt_text(rtt),
+                    if self.compat { default_style } else { default_style.patch(Theme::rtt(rtt)) },
+                ),
+            ]));
+        }
+
+        // Which probe answered: tells a firewalled host from one answering ping
+        if let Some(probe) = host.probe.filter(|_| host.is_alive) {
+            let text = probe.describe(host.status);
+            lines.push(Line::from(vec![
+                Span::styled("Discovered: ", dimmed_style),
+                Span::styled(
+                    truncate(&text, value_width.saturating_sub(2), self.compat).into_owned(),
+                    default_style,
+                ),
+            ]));
+            if probe.suspect {
+                lines.push(Line::from(vec![
+                    Span::styled("Suspect:  ", dimmed_style),
+                    Span::styled(
+                        truncate("possible RST proxy", value_width, self.compat).into_owned(),
+                        error_style,
+                    ),
+                ]));
+            }
+        }
+
+        // Every name found, labeled by source; the table shows the first.
+        // A hostname without sources predates them (an older cache).
+        let mut names: Vec<(String, &str)> = host
+            .names
+            .labeled(self.name_order)
+            .into_iter()
+            .map(|(source, name)| (format!("{}:", source.label()), name))
+            .collect();
+        if names.is_empty() {
+            if let Some(hostname) = &host.hostname {
+                names.push(("Hostname:".to_string(), hostname));
+            }
+        }
+        for (label, name) in names {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<10}", label), dimmed_style),
+                Span::styled(truncate(name, value_width, self.compat).into_owned(), default_style),
+            ]));
+        }
+        if host.names.ptr.is_none() && host.dns_timed_out() {
+            lines.push(Line::from(vec![
+                Span::styled("PTR:      ", dimmed_style),
+                Span::styled("(dns timeout; [P] retries)", dimmed_style),
             ]));
         }
 
-        // Hostname
-        if let Some(hostname) = &host.hostname {
+        // Services advertised over SSDP / mDNS
+        if !host.services.is_empty() {
             lines.push(Line::from(vec![
-                Span::styled("Hostname: ", dimmed_style),
-                Span::styled(hostname.clone(), default_style),
+                Span::styled("Services: ", dimmed_style),
+                Span::styled(
+                    truncate(&host.services.join(", "), value_width, self.compat).into_owned(),
+                    default_style,
+                ),
             ]));
         }
 
@@ -129,27 +282,62 @@ impl Widget for DetailsPane<'_> {
             };
             lines.push(Line::from(vec![
                 Span::styled("MAC:      ", dimmed_style),
-                Span::styled(mac_text, default_style),
+                Span::styled(
+                    truncate(&mac_text, value_width, self.compat).into_owned(),
+                    default_style,
+                ),
             ]));
         }
 
+        // Other addresses answering with this MAC; [=] moves between them
+        if let Some(group) = self.mac_groups.iter().find(|g| g.ips.contains(&host.ip)) {
+            let others: Vec<String> = group.others(host.ip).map(|ip| ip.to_string()).collect();
+            let text = format!("Other IPs with this MAC: {} (=)", others.join(", "));
+            lines.push(Line::from(Span::styled(
+                truncate(&text, inner.width as usize, self.compat).into_owned(),
+                if group.same_host { default_style } else { error_style },
+            )));
+        }
+
         // Open Ports
         lines.push(Line::from(""));
         if self.port_scanning {
             lines.push(Line::from(Span::styled("Scanning ports...", dimmed_style)));
-        } else if host.open_ports.is_empty() {
-            if host.ports_scanned && host.is_alive {
-                lines.push(Line::from(Span::styled("No open ports found", dimmed_style)));
+        } else if !host.open_ports.is_empty() || (host.ports_scanned && host.is_alive) {
+            // "Ports (common, 2h ago):", warning-colored when older than the
+            // liveness above
+            let scanned = [
+                host.ports_spec.clone(),
+                host.ports_scanned_at.map(format_cache_age),
+            ];
+            let scanned: Vec<String> = scanned.into_iter().flatten().collect();
+            let heading = if scanned.is_empty() {
+                "Open Ports:".to_string()
+            } else {
+                format!("Ports ({}):", scanned.join(", "))
+            };
+            let heading_style = if host.ports_outdated() { warning_style } else { header_style };
+            lines.push(Line::from(Span::styled(heading, heading_style)));
+            if host.open_ports.is_empty() {
+                lines.push(Line::from(Span::styled("  No open ports found", dimmed_style)));
             }
-        } else {
-            lines.push(Line::from(Span::styled("Open Ports:", header_style)));
+            let diff = self.port_diff.unwrap_or_default();
             for port in &host.open_ports {
                 let service = get_service_name(*port);
+                let marker = if diff.added.contains(port) { "+" } else { " " };
                 lines.push(Line::from(vec![
-                    Span::styled(format!("  {:5} ", port), accent_style),
+                    Span::styled(format!("{} {:5} ", marker, port), accent_style),
                     Span::styled(service, dimmed_style),
                 ]));
             }
+            // Closed since the scan before, after the ports still open
+            for port in &diff.removed {
+                let marker = if self.compat { "-" } else { "\u{2212}" };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{} {:5} ", marker, port), warning_style),
+                    Span::styled(get_service_name(*port), dimmed_style),
+                ]));
+            }
         }
 
         let paragraph = Paragraph::new(lines);
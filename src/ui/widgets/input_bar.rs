@@ -5,6 +5,9 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
+use unicode_width::UnicodeWidthStr;
+
+use crate::ui::text::truncate;
 use crate::ui::theme::{Compat, Theme};
 
 pub struct InputBar<'a> {
@@ -12,6 +15,12 @@ pub struct InputBar<'a> {
     value: &'a str,
     cursor_position: usize,
     focused: bool,
+    /// Shown in a red border below the value
+    error: Option<&'a str>,
+    /// Like `error`, in warning colors; an error takes precedence
+    warning: Option<&'a str>,
+    /// Dimmed, at the right of the top border (the range's address count)
+    hint: Option<&'a str>,
     compat: bool,
 }
 
@@ -22,6 +31,9 @@ impl<'a> InputBar<'a> {
             value,
             cursor_position: value.len(),
             focused: false,
+            error: None,
+            warning: None,
+            hint: None,
             compat: false,
         }
     }
@@ -36,6 +48,21 @@ impl<'a> InputBar<'a> {
         self
     }
 
+    pub fn error(mut self, error: Option<&'a str>) -> Self {
+        self.error = error;
+        self
+    }
+
+    pub fn warning(mut self, warning: Option<&'a str>) -> Self {
+        self.warning = warning;
+        self
+    }
+
+    pub fn hint(mut self, hint: Option<&'a str>) -> Self {
+        self.hint = hint;
+        self
+    }
+
     pub fn compat(mut self, compat: bool) -> Self {
         self.compat = compat;
         self
@@ -44,13 +71,21 @@ impl<'a> InputBar<'a> {
 
 impl Widget for InputBar<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let (border_style, title_style, text_style, cursor_style) = if self.compat {
+        let (mut border_style, title_style, text_style, cursor_style, error_style, warning_style) = if self.compat {
             let border = if self.focused { Compat::border_focused() } else { Compat::border() };
-            (border, Compat::title(), Compat::default(), Compat::selected())
+            (border, Compat::title(), Compat::default(), Compat::selected(), Compat::error(), Compat::warning())
         } else {
             let border = if self.focused { Theme::border_focused() } else { Theme::border() };
-            (border, Theme::title(), Theme::default(), Theme::selected())
+            (border, Theme::title(), Theme::default(), Theme::selected(), Theme::error(), Theme::warning())
+        };
+        let notice = match (self.error, self.warning) {
+            (Some(error), _) => Some((error, error_style)),
+            (None, Some(warning)) => Some((warning, warning_style)),
+            (None, None) => None,
         };
+        if let Some((_, style)) = notice {
+            border_style = style;
+        }
 
         let mut block = Block::default()
             .borders(Borders::ALL)
@@ -60,6 +95,21 @@ impl Widget for InputBar<'_> {
         if self.compat {
             block = block.border_set(Compat::BORDERS);
         }
+        if let Some(hint) = self.hint {
+            // Only where it fits beside the label
+            let room = usize::from(area.width).saturating_sub(self.label.width() + 6);
+            if hint.width() <= room {
+                let style = if self.compat { Compat::dimmed() } else { Theme::dimmed() };
+                let text = Line::from(Span::styled(format!(" {} ", hint), style));
+                block = block.title(text.right_aligned());
+            }
+        }
+        if let Some((message, style)) = notice {
+            // Leave a corner and a space on each side of the message
+            let room = usize::from(area.width.saturating_sub(4));
+            let text = format!(" {} ", truncate(message, room, self.compat));
+            block = block.title_bottom(Line::from(Span::styled(text, style)));
+        }
 
         let inner = block.inner(area);
         block.render(area, buf);
@@ -7,5 +7,5 @@ pub mod status_bar;
 pub use details_pane::DetailsPane;
 pub use input_bar::InputBar;
 pub use progress::ProgressBar;
-pub use scan_table::ScanTable;
+pub use scan_table::{Column, ScanTable};
 pub use status_bar::StatusBar;
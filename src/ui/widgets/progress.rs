@@ -5,6 +5,8 @@ use ratatui::{
     widgets::Widget,
 };
 
+use unicode_width::UnicodeWidthStr;
+
 use crate::ui::theme::{Compat, Theme};
 
 pub struct ProgressBar {
@@ -24,7 +26,6 @@ impl ProgressBar {
         }
     }
 
-    #[allow(dead_code)]
     pub fn label(mut self, label: impl Into<String>) -> Self {
         self.label = Some(label.into());
         self
@@ -55,7 +56,7 @@ impl Widget for ProgressBar {
             String::new()
         };
 
-        let label_width = label_text.len() as u16;
+        let label_width = label_text.width() as u16;
         let percentage_width = percentage_text.len() as u16;
         let bar_width = area
             .width
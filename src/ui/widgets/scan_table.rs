@@ -1,36 +1,208 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::net::Ipv4Addr;
 
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Row, StatefulWidget, Table, TableState},
 };
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
 
 use crate::app::HostInfo;
-use crate::ui::theme::{Compat, Theme};
+use crate::cache::format_cache_age;
+use crate::highlight::{Highlight, Highlighter};
+use crate::scanner::get_service_name;
+use crate::ui::text::{rtt_text, truncate};
+use crate::ui::theme::{Compat, Symbol, Theme};
 
+/// A hosts table column. The columns shown, and their order, are a setting
+/// picked in the column overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Ip,
+    Status,
+    Hostname,
+    Rtt,
+    Mac,
+    Vendor,
+    Ports,
+    Method,
+    LastSeen,
+    Uptime,
+    /// Service / device types the host advertised over SSDP / mDNS
+    DeviceType,
+}
+
+impl Column {
+    /// Every column, in the order the picker lists the hidden ones
+    pub const ALL: [Column; 11] = [
+        Column::Ip,
+        Column::Status,
+        Column::Hostname,
+        Column::Rtt,
+        Column::Mac,
+        Column::Vendor,
+        Column::Ports,
+        Column::Method,
+        Column::LastSeen,
+        Column::Uptime,
+        Column::DeviceType,
+    ];
+
+    pub const DEFAULT: [Column; 4] = [Column::Ip, Column::Status, Column::Hostname, Column::Rtt];
+
+    /// Name in the column picker
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Ip => "IP",
+            Column::Status => "Status",
+            Column::Hostname => "Hostname",
+            Column::Rtt => "RTT",
+            Column::Mac => "MAC",
+            Column::Vendor => "Vendor",
+            Column::Ports => "Ports",
+            Column::Method => "Method",
+            Column::LastSeen => "Last seen",
+            Column::Uptime => "Uptime",
+            Column::DeviceType => "Device type",
+        }
+    }
+
+    /// Heading in the table and the plain-text view
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Ip => "IP",
+            Column::Status => "STATUS",
+            Column::Hostname => "HOSTNAME",
+            Column::Rtt => "RTT",
+            Column::Mac => "MAC",
+            Column::Vendor => "VENDOR",
+            Column::Ports => "PORTS",
+            Column::Method => "METHOD",
+            Column::LastSeen => "SEEN",
+            Column::Uptime => "UPTIME",
+            Column::DeviceType => "TYPE",
+        }
+    }
+
+    fn width(self) -> Constraint {
+        match self {
+            Column::Ip => Constraint::Length(18),
+            Column::Status => Constraint::Length(8),
+            Column::Hostname => Constraint::Min(15),
+            Column::Rtt => Constraint::Length(8),
+            Column::Mac => Constraint::Length(17),
+            Column::Vendor => Constraint::Length(16),
+            Column::Ports => Constraint::Length(18),
+            Column::Method => Constraint::Length(6),
+            Column::LastSeen => Constraint::Length(9),
+            Column::Uptime => Constraint::Length(12),
+            Column::DeviceType => Constraint::Length(14),
+        }
+    }
+
+    /// `host`'s value in this column as plain text, in full: what the
+    /// table shows with symbols spelled out and nothing cut to fit
+    pub fn text(self, host: &HostInfo, now: u64) -> String {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        match self {
+            Column::Ip => host.ip.to_string(),
+            Column::Status if host.is_alive => "online".to_string(),
+            Column::Status if host.stale => "stale".to_string(),
+            Column::Status => "offline".to_string(),
+            Column::Hostname => or_dash(host.hostname.clone().or_else(|| {
+                host.mac
+                    .as_ref()
+                    .and_then(|m| m.vendor.as_ref())
+                    .map(|vendor| format!("[{}]", vendor))
+            })),
+            Column::Rtt => or_dash(host.rtt.map(rtt_text)),
+            Column::Mac => or_dash(host.mac.as_ref().map(|m| m.address.clone())),
+            Column::Vendor => or_dash(host.mac.as_ref().and_then(|m| m.vendor.clone())),
+            Column::Ports if host.ports_scanned && host.open_ports.is_empty() => "none".to_string(),
+            Column::Ports if host.ports_scanned => host
+                .open_ports
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            Column::Ports => "-".to_string(),
+            Column::Method if host.is_alive => host.method.to_string(),
+            Column::Method => "-".to_string(),
+            Column::LastSeen if host.is_alive => "now".to_string(),
+            Column::LastSeen => or_dash(host.last_seen.map(format_cache_age)),
+            Column::Uptime => uptime_text(host, now),
+            Column::DeviceType if host.services.is_empty() => "-".to_string(),
+            Column::DeviceType => host.services.join(","),
+        }
+    }
+
+    /// `columns` without repeats, with the IP column added at the front if
+    /// it is missing: rows can't be told apart without it
+    pub fn normalize(columns: &[Column]) -> Vec<Column> {
+        let mut seen = Vec::new();
+        if !columns.contains(&Column::Ip) {
+            seen.push(Column::Ip);
+        }
+        for &column in columns {
+            if !seen.contains(&column) {
+                seen.push(column);
+            }
+        }
+        seen
+    }
+}
+
+/// Hosts table. Borrows the host list and the filtered index list rather than
+/// a copy, and only builds rows for the visible window, so a /16 renders as
+/// cheaply as a /24.
 pub struct ScanTable<'a> {
     hosts: &'a [HostInfo],
+    /// Indices into `hosts` of the rows to show, in display order
+    rows: &'a [usize],
+    columns: &'a [Column],
     show_rtt: bool,
     focused: bool,
     selected_ips: Option<&'a HashSet<Ipv4Addr>>,
+    conflicted_ips: Option<&'a HashSet<Ipv4Addr>>,
+    port_progress: Option<&'a HashMap<Ipv4Addr, (usize, usize)>>,
+    highlighter: Option<&'a Highlighter>,
+    range_label: Option<String>,
+    filter_label: Option<&'static str>,
     compat: bool,
+    show_legend: bool,
 }
 
 impl<'a> ScanTable<'a> {
-    pub fn new(hosts: &'a [HostInfo]) -> Self {
+    pub fn new(hosts: &'a [HostInfo], rows: &'a [usize]) -> Self {
         Self {
             hosts,
+            rows,
+            columns: &Column::DEFAULT,
             show_rtt: true,
             focused: true,
             selected_ips: None,
+            conflicted_ips: None,
+            port_progress: None,
+            highlighter: None,
+            range_label: None,
+            filter_label: None,
             compat: false,
+            show_legend: false,
         }
     }
 
+    /// Columns to show, in order
+    pub fn columns(mut self, columns: &'a [Column]) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Leave out the RTT column even when it is picked (narrow layouts)
     pub fn show_rtt(mut self, show: bool) -> Self {
         self.show_rtt = show;
         self
@@ -46,131 +218,292 @@ impl<'a> ScanTable<'a> {
         self
     }
 
+    /// Hosts to flag with the conflict marker
+    pub fn conflicted_ips(mut self, ips: &'a HashSet<Ipv4Addr>) -> Self {
+        self.conflicted_ips = Some(ips);
+        self
+    }
+
+    /// (ports scanned, total) of hosts whose port scan is running, shown as
+    /// a gauge in the Ports column
+    pub fn port_progress(mut self, progress: &'a HashMap<Ipv4Addr, (usize, usize)>) -> Self {
+        self.port_progress = Some(progress);
+        self
+    }
+
+    /// Rules giving hosts badges after the hostname and a row color
+    pub fn highlighter(mut self, highlighter: &'a Highlighter) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    /// Batch range the rows are narrowed to, shown in the title
+    pub fn range_label(mut self, label: Option<String>) -> Self {
+        self.range_label = label;
+        self
+    }
+
+    /// Active host filter, shown in the title
+    pub fn filter_label(mut self, label: Option<&'static str>) -> Self {
+        self.filter_label = label;
+        self
+    }
+
     pub fn compat(mut self, compat: bool) -> Self {
         self.compat = compat;
         self
     }
+
+    /// Host rows that fit in `area` (borders and header row excluded)
+    pub fn visible_rows(area: Rect) -> usize {
+        area.height.saturating_sub(3) as usize
+    }
+
+    /// Index into the displayed rows of the host at screen `(column, row)`,
+    /// or None off the data rows. `offset` is the first row the last frame
+    /// showed; it's clamped to `len` the same way the next draw will, so a
+    /// list that shrank since then can't yield an out-of-range index.
+    pub fn row_at(area: Rect, offset: usize, len: usize, column: u16, row: u16) -> Option<usize> {
+        // Top border and header row come before the first host
+        let first = area.y + 2;
+        let inside = column >= area.x && column < area.x + area.width;
+        if !inside || row < first || (row - first) as usize >= Self::visible_rows(area) {
+            return None;
+        }
+        let index = offset.min(len.saturating_sub(1)) + (row - first) as usize;
+        (index < len).then_some(index)
+    }
+
+    /// First row to show: `offset`, moved the least needed to keep the
+    /// selected row on screen (the same rule ratatui's `Table` applies)
+    fn window_start(&self, state: &TableState, visible: usize) -> usize {
+        let last = self.rows.len().saturating_sub(1);
+        let mut start = state.offset().min(last);
+        if let Some(selected) = state.selected().map(|s| s.min(last)) {
+            if selected >= start + visible {
+                start = selected + 1 - visible;
+            }
+            start = start.min(selected);
+        }
+        start
+    }
+
+    /// `host`'s cell in `column`, cut to `width`
+    fn cell(
+        &self,
+        host: &HostInfo,
+        column: Column,
+        width: usize,
+        is_selected: bool,
+        now: u64,
+        dimmed: Style,
+    ) -> Line<'static> {
+        let cut = |text: &str| truncate(text, width, self.compat).into_owned();
+        match column {
+            Column::Ip if is_selected => Line::from(vec![
+                Symbol::Selected.span(self.compat),
+                Span::raw(" "),
+                Span::raw(host.ip.to_string()),
+            ]),
+            Column::Ip => Line::from(host.ip.to_string()),
+            Column::Status => {
+                let mut status = vec![if host.is_alive {
+                    Symbol::Online.span(self.compat)
+                } else if host.stale {
+                    Symbol::Stale.span(self.compat)
+                } else {
+                    Symbol::Offline.span(self.compat)
+                }];
+                if self.conflicted_ips.is_some_and(|c| c.contains(&host.ip)) {
+                    status.push(Span::raw(" "));
+                    status.push(Symbol::Conflict.span(self.compat));
+                }
+                Line::from(status)
+            }
+            // A timed-out lookup is flagged (a rescan retries it); otherwise
+            // fall back to MAC vendor when no hostname is resolved
+            Column::Hostname => {
+                if let Some(name) = host.hostname.as_deref() {
+                    Line::from(cut(name))
+                } else if host.dns_timed_out() {
+                    Line::from(Span::styled(cut("(dns timeout)"), dimmed))
+                } else if let Some(vendor) = host.mac.as_ref().and_then(|m| m.vendor.as_deref()) {
+                    Line::from(Span::styled(cut(&format!("[{}]", vendor)), dimmed))
+                } else {
+                    Line::from("-")
+                }
+            }
+            Column::Rtt => match host.rtt {
+                Some(rtt) if self.compat => Line::from(rtt_text(rtt)),
+                Some(rtt) => Line::from(Span::styled(rtt_text(rtt), Theme::rtt(rtt))),
+                None => Line::from("-"),
+            },
+            Column::Mac => Line::from(host.mac.as_ref().map_or("-".to_string(), |m| m.address.clone())),
+            Column::Vendor => Line::from(
+                host.mac
+                    .as_ref()
+                    .and_then(|m| m.vendor.as_deref())
+                    .map_or("-".to_string(), cut),
+            ),
+            Column::Ports => match self.port_progress.and_then(|p| p.get(&host.ip)) {
+                Some(&(scanned, total)) => Line::from(Span::styled(
+                    port_gauge(scanned, total, width, self.compat),
+                    dimmed,
+                )),
+                None if host.ports_scanned => {
+                    Line::from(ports_text(&host.open_ports, width, self.compat))
+                }
+                None => Line::from("-"),
+            },
+            Column::Method if host.is_alive => Line::from(host.method.to_string()),
+            Column::Method => Line::from("-"),
+            Column::LastSeen => Line::from(match host.last_seen {
+                _ if host.is_alive => "now".to_string(),
+                Some(at) => format_cache_age(at),
+                None => "-".to_string(),
+            }),
+            Column::Uptime => Line::from(uptime_text(host, now)),
+            Column::DeviceType if host.services.is_empty() => Line::from("-"),
+            Column::DeviceType => Line::from(cut(&host.services.join(","))),
+        }
+    }
+
+    /// The hostname cell with `highlight`'s badges after the name, which is
+    /// cut shorter to make room; too narrow a column keeps just the name
+    fn badged_hostname(
+        &self,
+        host: &HostInfo,
+        width: usize,
+        highlight: &Highlight,
+        now: u64,
+        dimmed: Style,
+    ) -> Line<'static> {
+        let badges = highlight.badges();
+        let Some(room) = width.checked_sub(badges.width() + 1).filter(|&room| room >= 4) else {
+            return self.cell(host, Column::Hostname, width, false, now, dimmed);
+        };
+        let mut line = self.cell(host, Column::Hostname, room, false, now, dimmed);
+        line.push_span(Span::raw(" "));
+        line.push_span(Span::styled(badges, highlight.badge_style(self.compat)));
+        line
+    }
+
+    /// Show the symbol legend along the bottom border
+    pub fn show_legend(mut self, show: bool) -> Self {
+        self.show_legend = show;
+        self
+    }
 }
 
 impl<'a> StatefulWidget for ScanTable<'a> {
     type State = TableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let header_cells = if self.show_rtt {
-            vec!["IP", "STATUS", "HOSTNAME", "RTT"]
-        } else {
-            vec!["IP", "STATUS", "HOSTNAME"]
-        };
+        let columns: Vec<Column> = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|&c| self.show_rtt || c != Column::Rtt)
+            .collect();
+        let header_cells: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+        let widths: Vec<Constraint> = columns.iter().map(|c| c.width()).collect();
+        let now = crate::cache::now_secs();
 
         let header_style = if self.compat { Compat::header() } else { Theme::header() };
         let header = Row::new(header_cells)
             .style(header_style)
             .height(1);
 
+        let (border_style, title_style, highlight_style, stripe_style, unfocused_style) = if self.compat {
+            let border = if self.focused { Compat::border_focused() } else { Compat::border() };
+            (border, Compat::title(), Compat::selected(), Compat::row_stripe(), Compat::unfocused())
+        } else {
+            let border = if self.focused { Theme::border_focused() } else { Theme::border() };
+            (border, Theme::title(), Theme::selected(), Theme::row_stripe(), Theme::unfocused())
+        };
+        let cursor_sym = format!("{} ", Symbol::Cursor.glyph(self.compat));
+
+        // Build only the rows on screen and render them with a state relative
+        // to that window; the caller's state keeps absolute positions
+        let visible = Self::visible_rows(area);
+        let start = self.window_start(state, visible);
+        let mut window_state = TableState::default()
+            .with_selected(state.selected().and_then(|s| s.checked_sub(start)));
+        *state.offset_mut() = start;
+
+        // Column widths, laid out exactly as `Table` will, so long names can
+        // be cut with a visible marker instead of silently
+        let selection_width = if window_state.selected().is_some() {
+            cursor_sym.width() as u16
+        } else {
+            0
+        };
+        let columns_area = Rect::new(0, 0, area.width.saturating_sub(2 + selection_width), 1);
+        let column_widths: Vec<usize> = Layout::horizontal(&widths)
+            .spacing(1)
+            .split(columns_area)
+            .iter()
+            .map(|r| r.width as usize)
+            .collect();
+        let dimmed = if self.compat { Compat::dimmed() } else { Theme::dimmed() };
+        let highlighter = self.highlighter.filter(|h| !h.is_empty());
+
         let rows: Vec<Row> = self
-            .hosts
+            .rows
             .iter()
-            .map(|host| {
+            .enumerate()
+            .skip(start)
+            .take(visible)
+            .map(|(row, &index)| {
+                let host = &self.hosts[index];
                 let is_selected = self
                     .selected_ips
                     .is_some_and(|s| s.contains(&host.ip));
 
-                let ip_cell = if is_selected {
-                    let (sel_sym, sel_style) = if self.compat {
-                        ("x ", Compat::accent())
-                    } else {
-                        ("✓ ", Style::default().fg(Theme::SUCCESS))
-                    };
-                    Line::from(vec![
-                        Span::styled(sel_sym, sel_style),
-                        Span::raw(host.ip.to_string()),
-                    ])
-                } else {
-                    Line::from(host.ip.to_string())
-                };
-
-                let status_span = if self.compat {
-                    if host.is_alive {
-                        Span::styled(Compat::SYM_ONLINE, Compat::status_online())
-                    } else {
-                        Span::styled(Compat::SYM_OFFLINE, Compat::status_offline())
-                    }
-                } else if host.is_alive {
-                    Span::styled("●", Theme::status_online())
-                } else {
-                    Span::styled("○", Theme::status_offline())
-                };
-
-                // Fall back to MAC vendor when no hostname is resolved
-                let (hostname_text, hostname_style) = if let Some(name) = host.hostname.as_deref() {
-                    let style = if self.compat { Compat::default() } else { Theme::default() };
-                    (name.to_string(), style)
-                } else if let Some(vendor) = host.mac.as_ref().and_then(|m| m.vendor.as_deref()) {
-                    let style = if self.compat { Compat::dimmed() } else { Theme::dimmed() };
-                    (format!("[{}]", vendor), style)
-                } else {
-                    let style = if self.compat { Compat::default() } else { Theme::default() };
-                    ("-".to_string(), style)
-                };
-
-                let row_style = if self.compat { Compat::default() } else { Theme::default() };
-                let cells: Vec<Line> = if self.show_rtt {
-                    let rtt = host
-                        .rtt
-                        .map(|d| format!("{}ms", d.as_millis()))
-                        .unwrap_or_else(|| "-".to_string());
-
-                    vec![
-                        ip_cell,
-                        Line::from(status_span),
-                        Line::from(Span::styled(hostname_text, hostname_style)),
-                        Line::from(rtt),
-                    ]
-                } else {
-                    vec![
-                        ip_cell,
-                        Line::from(status_span),
-                        Line::from(Span::styled(hostname_text, hostname_style)),
-                    ]
-                };
+                // Stripes follow the row's place in the list, so they don't
+                // swap over as the table scrolls
+                let mut row_style = if self.compat { Compat::default() } else { Theme::default() };
+                if row % 2 == 1 {
+                    row_style = row_style.patch(stripe_style);
+                }
+                let highlight = highlighter.map(|h| h.highlight(host)).unwrap_or_default();
+                if let Some(color) = highlight.color {
+                    row_style = row_style.patch(color.style(self.compat));
+                }
+                let cells: Vec<Line> = columns
+                    .iter()
+                    .zip(&column_widths)
+                    .map(|(&column, &width)| match column {
+                        Column::Hostname if !highlight.tags.is_empty() => {
+                            self.badged_hostname(host, width, &highlight, now, dimmed)
+                        }
+                        _ => self.cell(host, column, width, is_selected, now, dimmed),
+                    })
+                    .collect();
 
                 Row::new(cells).style(row_style)
             })
             .collect();
 
-        let widths = if self.show_rtt {
-            [
-                ratatui::layout::Constraint::Length(18),
-                ratatui::layout::Constraint::Length(8),
-                ratatui::layout::Constraint::Min(15),
-                ratatui::layout::Constraint::Length(8),
-            ]
-            .as_slice()
-        } else {
-            [
-                ratatui::layout::Constraint::Length(18),
-                ratatui::layout::Constraint::Length(8),
-                ratatui::layout::Constraint::Min(15),
-            ]
-            .as_slice()
-        };
-
-        let (border_style, title_style, highlight_style, cursor_sym) = if self.compat {
-            let border = if self.focused { Compat::border_focused() } else { Compat::border() };
-            (border, Compat::title(), Compat::selected(), Compat::SYM_CURSOR)
-        } else {
-            let border = if self.focused { Theme::border_focused() } else { Theme::border() };
-            (border, Theme::title(), Theme::selected(), "▶ ")
-        };
-
         let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(border_style)
-            .title(" Hosts ")
+            .title(match (&self.range_label, self.filter_label) {
+                (Some(range), Some(filter)) => format!(" Hosts: {} [{}] ", range, filter),
+                (Some(range), None) => format!(" Hosts: {} ", range),
+                (None, Some(filter)) => format!(" Hosts [{}] ", filter),
+                (None, None) => " Hosts ".to_string(),
+            })
             .title_style(title_style);
         if self.compat {
             block = block.border_set(Compat::BORDERS);
         }
+        if self.show_legend {
+            let mut legend = vec![Span::raw(" ")];
+            legend.extend(Symbol::legend_spans(self.compat));
+            legend.push(Span::raw(" "));
+            block = block.title_bottom(Line::from(legend));
+        }
 
         let table = Table::new(rows, widths)
             .header(header)
@@ -178,6 +511,406 @@ impl<'a> StatefulWidget for ScanTable<'a> {
             .row_highlight_style(highlight_style)
             .highlight_symbol(cursor_sym);
 
-        StatefulWidget::render(table, area, buf, state);
+        StatefulWidget::render(table, area, buf, &mut window_state);
+        if !self.focused {
+            buf.set_style(area, unfocused_style);
+        }
+    }
+}
+
+/// "up 3h 12m" / "down 8m": how long `host` has been in its current state
+fn uptime_text(host: &HostInfo, now: u64) -> String {
+    let Some(since) = host.state_since else {
+        return "-".to_string();
+    };
+    let state = if host.is_alive { "up" } else { "down" };
+    let minutes = now.saturating_sub(since) / 60;
+    let span = match (minutes / 1440, minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    };
+    format!("{} {}", state, span)
+}
+
+/// "▰▰▰▱▱▱ 9/25" while a host's ports are being scanned: the bar takes
+/// what `width` leaves after the count, and is dropped when too narrow
+fn port_gauge(scanned: usize, total: usize, width: usize, compat: bool) -> String {
+    let count = format!("{}/{}", scanned, total);
+    let bar_width = width.saturating_sub(count.len() + 1).min(10);
+    if bar_width < 3 {
+        return truncate(&count, width, compat).into_owned();
+    }
+    let filled = (bar_width * scanned).checked_div(total).unwrap_or(0).min(bar_width);
+    let (full, empty) = if compat { ('#', '-') } else { ('▰', '▱') };
+    let mut gauge: String = std::iter::repeat_n(full, filled)
+        .chain(std::iter::repeat_n(empty, bar_width - filled))
+        .collect();
+    gauge.push(' ');
+    gauge.push_str(&count);
+    gauge
+}
+
+/// "ssh,http,https +2": as many of `ports` as fit in `width` cells, by
+/// service name where there is one, with a count of the rest
+fn ports_text(ports: &[u16], width: usize, compat: bool) -> String {
+    if ports.is_empty() {
+        return "none".to_string();
+    }
+    let names: Vec<String> = ports
+        .iter()
+        .map(|&port| match get_service_name(port) {
+            "unknown" => port.to_string(),
+            name => name.to_string(),
+        })
+        .collect();
+    for shown in (1..=names.len()).rev() {
+        let mut text = names[..shown].join(",");
+        if shown < names.len() {
+            text.push_str(&format!(" +{}", names.len() - shown));
+        }
+        if text.width() <= width {
+            return text;
+        }
+    }
+    // Not even one name and the count fit: cut the name, and drop the count
+    // too if that would leave nothing but the marker
+    let rest = format!(" +{}", names.len() - 1);
+    let first = truncate(&names[0], width.saturating_sub(rest.width()), compat);
+    if names.len() > 1 && first.width() > 1 {
+        format!("{}{}", first, rest)
+    } else {
+        truncate(&names[0], width, compat).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use crate::scanner::Resolution;
+
+    fn hosts(count: u32) -> Vec<HostInfo> {
+        (0..count)
+            .map(|i| HostInfo {
+                is_alive: i % 3 == 0,
+                rtt: Some(Duration::from_millis(2)),
+                hostname: Some(format!("host-{}", i)),
+                ..HostInfo::pinged(Ipv4Addr::from(0x0A00_0000 + i), true)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn row_at_maps_clicks_to_rows() {
+        let area = Rect::new(0, 3, 60, 10); // 7 data rows, screen rows 5..12
+        assert_eq!(ScanTable::row_at(area, 0, 100, 5, 5), Some(0));
+        assert_eq!(ScanTable::row_at(area, 0, 100, 5, 11), Some(6));
+        assert_eq!(ScanTable::row_at(area, 40, 100, 5, 7), Some(42));
+        // Border, header, bottom border and outside columns
+        for (column, row) in [(5, 3), (5, 4), (5, 12), (60, 5)] {
+            assert_eq!(ScanTable::row_at(area, 0, 100, column, row), None);
+        }
+        // Short list: clicks below the last host select nothing
+        assert_eq!(ScanTable::row_at(area, 0, 3, 5, 7), Some(2));
+        assert_eq!(ScanTable::row_at(area, 0, 3, 5, 8), None);
+        assert_eq!(ScanTable::row_at(area, 0, 0, 5, 5), None);
+        // Filter shrank the list under a large offset
+        assert_eq!(ScanTable::row_at(area, 40, 5, 5, 5), Some(4));
+        assert_eq!(ScanTable::row_at(area, 40, 5, 5, 6), None);
+        // Too short for any data row
+        assert_eq!(ScanTable::row_at(Rect::new(0, 3, 60, 2), 0, 10, 5, 4), None);
+        assert_eq!(ScanTable::row_at(Rect::new(0, 3, 60, 3), 0, 10, 5, 5), None);
+    }
+
+    #[test]
+    fn window_follows_selection_like_ratatui() {
+        let hosts = hosts(100);
+        let rows: Vec<usize> = (0..100).collect();
+        let table = ScanTable::new(&hosts, &rows);
+        let mut state = TableState::default().with_selected(Some(50));
+        assert_eq!(table.window_start(&state, 10), 41);
+        *state.offset_mut() = 45;
+        assert_eq!(table.window_start(&state, 10), 45);
+        *state.offset_mut() = 60;
+        assert_eq!(table.window_start(&state, 10), 50);
+    }
+
+    #[test]
+    fn wide_hostnames_are_cut_and_keep_rtt_aligned() {
+        let mut hosts = hosts(4);
+        hosts[0].hostname = Some("plain.lan".to_string());
+        hosts[1].hostname = Some("東京オフィスのファイルサーバー.local".to_string());
+        hosts[2].hostname = Some("🖨️ printer-on-the-third-floor-by-the-kitchen.lan".to_string());
+        hosts[3].hostname = Some("カメラ📷.lan".to_string());
+        let rows: Vec<usize> = (0..hosts.len()).collect();
+
+        for compat in [false, true] {
+            let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+            let mut state = TableState::default().with_selected(Some(0));
+            terminal
+                .draw(|f| {
+                    let table = ScanTable::new(&hosts, &rows).compat(compat);
+                    f.render_stateful_widget(table, f.area(), &mut state);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+
+            // Column where "2.0ms" starts on each data row
+            let rtt_columns: Vec<u16> = (2..6)
+                .map(|y| {
+                    (0..58)
+                        .find(|&x| {
+                            (0..5).all(|i| buffer[(x + i, y)].symbol() == &"2.0ms"[i as usize..][..1])
+                        })
+                        .expect("RTT on row")
+                })
+                .collect();
+            assert!(rtt_columns.iter().all(|&x| x == rtt_columns[0]), "{:?}", rtt_columns);
+
+            let marker = if compat { "~" } else { "…" };
+            let row: String = (0..60).map(|x| buffer[(x, 3)].symbol()).collect();
+            assert!(row.contains(marker), "{}", row);
+        }
+    }
+
+    #[test]
+    fn highlighted_hosts_get_badges_after_the_hostname() {
+        let mut hosts = hosts(2);
+        hosts[0].hostname = Some("pve-01.lan".to_string());
+        let rule: crate::highlight::HighlightRule =
+            serde_json::from_str(r#"{"pattern": "PVE", "tag": "HYPERVISOR", "color": "accent"}"#).unwrap();
+        let (highlighter, _) = Highlighter::new(&[rule]);
+        let rows: Vec<usize> = (0..hosts.len()).collect();
+
+        let draw = |width: u16| {
+            let mut terminal = Terminal::new(TestBackend::new(width, 5)).unwrap();
+            let mut state = TableState::default();
+            terminal
+                .draw(|f| {
+                    let table = ScanTable::new(&hosts, &rows).highlighter(&highlighter);
+                    f.render_stateful_widget(table, f.area(), &mut state);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            let row = |y: u16| -> String { (0..width).map(|x| buffer[(x, y)].symbol()).collect() };
+            (row(2), row(3))
+        };
+        let (tagged, plain) = draw(70);
+        assert!(tagged.contains("pve-01.lan [HYPERVISOR]"), "{}", tagged);
+        assert!(!plain.contains('['), "{}", plain);
+        // No room beside the name: the badge is dropped, not the name
+        let (narrow, _) = draw(50);
+        assert!(narrow.contains("pve-01") && !narrow.contains("HYPER"), "{}", narrow);
+    }
+
+    #[test]
+    fn dns_timeouts_are_told_apart_from_missing_records() {
+        let mut hosts = hosts(2);
+        for host in &mut hosts {
+            host.hostname = None;
+        }
+        hosts[0].dns = Some(Resolution::Timeout);
+        hosts[1].dns = Some(Resolution::NoRecord);
+        let rows: Vec<usize> = (0..hosts.len()).collect();
+        let mut terminal = Terminal::new(TestBackend::new(60, 6)).unwrap();
+        let mut state = TableState::default();
+        terminal
+            .draw(|f| f.render_stateful_widget(ScanTable::new(&hosts, &rows), f.area(), &mut state))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String { (0..60).map(|x| buffer[(x, y)].symbol()).collect() };
+        assert!(row(2).contains("(dns timeout)"), "{}", row(2));
+        assert!(!row(3).contains("dns"), "{}", row(3));
+    }
+
+    #[test]
+    fn large_table_renders_within_budget() {
+        let hosts = hosts(50_000);
+        let rows: Vec<usize> = (0..hosts.len()).collect();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        let mut state = TableState::default().with_selected(Some(40_000));
+
+        let start = Instant::now();
+        for _ in 0..20 {
+            terminal
+                .draw(|f| {
+                    let table = ScanTable::new(&hosts, &rows);
+                    f.render_stateful_widget(table, f.area(), &mut state);
+                })
+                .unwrap();
+        }
+        // Generous for debug builds; cloning and building every row took seconds
+        assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("10.0.156.64"), "selected host (index 40000) on screen");
+    }
+
+    #[test]
+    fn uptime_reads_as_state_and_duration() {
+        let now = 1_700_000_000;
+        let mut host = hosts(1).remove(0);
+        assert_eq!(uptime_text(&host, now), "-");
+        host.state_since = Some(now - 8 * 60 - 30);
+        assert_eq!(uptime_text(&host, now), "up 8m");
+        host.state_since = Some(now - (3 * 60 + 12) * 60);
+        assert_eq!(uptime_text(&host, now), "up 3h 12m");
+        host.is_alive = false;
+        host.state_since = Some(now - (2 * 1440 + 5 * 60 + 7) * 60);
+        assert_eq!(uptime_text(&host, now), "down 2d 5h");
+        // A clock that went backwards shows zero rather than wrapping
+        host.state_since = Some(now + 60);
+        assert_eq!(uptime_text(&host, now), "down 0m");
+    }
+
+    #[test]
+    fn every_other_row_is_striped_and_an_unfocused_table_dims() {
+        use ratatui::style::Modifier;
+
+        let hosts = hosts(4);
+        let rows = [0, 1, 2, 3];
+        let area = Rect::new(0, 0, 60, 8);
+        let draw = |table: ScanTable| {
+            let mut buf = Buffer::empty(area);
+            table.render(area, &mut buf, &mut TableState::default());
+            buf
+        };
+        // Data rows start under the border and the header
+        let buf = draw(ScanTable::new(&hosts, &rows));
+        let bg = |buf: &Buffer, row: u16| buf[(10, 2 + row)].bg;
+        assert_eq!([0, 1, 2, 3].map(|r| bg(&buf, r)), [Theme::BG, Theme::STRIPE_BG, Theme::BG, Theme::STRIPE_BG]);
+        assert!(!buf[(10, 2)].modifier.contains(Modifier::DIM));
+
+        let buf = draw(ScanTable::new(&hosts, &rows).focused(false));
+        assert!(buf[(10, 2)].modifier.contains(Modifier::DIM));
+        assert!(buf[(0, 0)].modifier.contains(Modifier::DIM));
+
+        // Compat and monochrome draw no stripes
+        let buf = draw(ScanTable::new(&hosts, &rows).compat(true));
+        assert_eq!(bg(&buf, 1), bg(&buf, 0));
+        crate::ui::theme::set_monochrome(true);
+        let buf = draw(ScanTable::new(&hosts, &rows));
+        crate::ui::theme::set_monochrome(false);
+        assert_eq!(bg(&buf, 1), bg(&buf, 0));
+    }
+
+    #[test]
+    fn ports_column_names_what_fits_and_counts_the_rest() {
+        let ports = [22, 80, 443, 445, 12345];
+        assert_eq!(ports_text(&ports, 40, false), "ssh,http,https,smb,12345");
+        assert_eq!(ports_text(&ports, 18, false), "ssh,http,https +2");
+        assert_eq!(ports_text(&ports, 11, false), "ssh,http +3");
+        assert_eq!(ports_text(&ports, 6, false), "ssh +4");
+        assert_eq!(ports_text(&[22], 3, false), "ssh");
+        assert_eq!(ports_text(&[], 18, false), "none");
+        // Too narrow for a whole name: it is cut, with the marker for the mode
+        assert_eq!(ports_text(&[5432, 22], 7, false), "pos… +1");
+        assert_eq!(ports_text(&[5432, 22], 7, true), "pos~ +1");
+        assert_eq!(ports_text(&[5432], 5, true), "post~");
+        assert_eq!(ports_text(&[5432, 22], 4, true), "pos~");
+        for compat in [false, true] {
+            for width in 0..30 {
+                let text = ports_text(&ports, width, compat);
+                assert!(text.width() <= width, "{:?} at {}", text, width);
+            }
+        }
+
+        let mut hosts = hosts(2);
+        hosts[0].open_ports = vec![22, 80, 443, 445, 3389];
+        hosts[0].ports_scanned = true;
+        let area = Rect::new(0, 0, 90, 5);
+        let mut buf = Buffer::empty(area);
+        ScanTable::new(&hosts, &[0, 1])
+            .columns(&[Column::Ip, Column::Status, Column::Hostname, Column::Rtt, Column::Ports])
+            .render(area, &mut buf, &mut TableState::default());
+        let line = |y: u16| -> String {
+            (0..area.width).map(|x| buf[(x, y)].symbol()).collect()
+        };
+        assert!(line(1).contains("PORTS"));
+        assert!(line(2).contains("ssh,http,https +2"), "{}", line(2));
+        assert!(line(3).trim_end_matches(['│', ' ']).ends_with('-'), "{}", line(3));
+
+        // A running scan shows its gauge instead of the last result
+        let progress = HashMap::from([(hosts[0].ip, (10, 25)), (hosts[1].ip, (0, 25))]);
+        let mut buf = Buffer::empty(area);
+        ScanTable::new(&hosts, &[0, 1])
+            .columns(&[Column::Ip, Column::Status, Column::Hostname, Column::Rtt, Column::Ports])
+            .port_progress(&progress)
+            .render(area, &mut buf, &mut TableState::default());
+        let line = |y: u16| -> String {
+            (0..area.width).map(|x| buf[(x, y)].symbol()).collect()
+        };
+        assert!(line(2).contains("▰▰▰▰▱▱▱▱▱▱ 10/25"), "{}", line(2));
+        assert!(line(3).contains("▱▱▱▱▱▱▱▱▱▱ 0/25"), "{}", line(3));
+        assert_eq!(port_gauge(25, 25, 12, true), "###### 25/25");
+        assert_eq!(port_gauge(3, 25, 6, false), "3/25");
+        assert_eq!(port_gauge(3, 0, 4, false), "3/0");
+    }
+
+    #[test]
+    fn picked_columns_render_in_order() {
+        let mut hosts = hosts(2);
+        hosts[0].mac = Some(crate::scanner::MacInfo {
+            address: "AA:BB:CC:DD:EE:01".to_string(),
+            vendor: Some("Synology".to_string()),
+        });
+        hosts[0].services = vec!["MediaRenderer".to_string()];
+        let area = Rect::new(0, 0, 110, 5);
+        let mut buf = Buffer::empty(area);
+        let columns = [
+            Column::Hostname,
+            Column::Ip,
+            Column::Mac,
+            Column::Vendor,
+            Column::Method,
+            Column::DeviceType,
+        ];
+        ScanTable::new(&hosts, &[0, 1])
+            .columns(&columns)
+            .render(area, &mut buf, &mut TableState::default());
+        let line = |y: u16| -> String {
+            (0..area.width).map(|x| buf[(x, y)].symbol()).collect()
+        };
+        let header = line(1);
+        let at = |name: &str| header.find(name).unwrap_or_else(|| panic!("{} in {}", name, header));
+        assert!(at("HOSTNAME") < at("IP") && at("IP") < at("MAC") && at("VENDOR") < at("TYPE"));
+        assert!(!header.contains("STATUS") && !header.contains("RTT"));
+        for text in ["host-0", "AA:BB:CC:DD:EE:01", "Synology", "ICMP", "MediaRenderer"] {
+            assert!(line(2).contains(text), "{} in {}", text, line(2));
+        }
+        // Offline, no MAC, nothing advertised
+        assert!(!line(3).contains("ICMP"), "{}", line(3));
+
+        assert_eq!(
+            Column::normalize(&[Column::Rtt, Column::Rtt, Column::Status]),
+            [Column::Ip, Column::Rtt, Column::Status]
+        );
+    }
+
+    #[test]
+    fn last_seen_column_shows_age_of_offline_hosts() {
+        let mut hosts = hosts(3);
+        hosts[1].is_alive = false;
+        hosts[1].last_seen = Some(crate::cache::now_secs() - 2 * 3600);
+        hosts[2].is_alive = false;
+        let area = Rect::new(0, 0, 80, 8);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default();
+        ScanTable::new(&hosts, &[0, 1, 2])
+            .columns(&[Column::Ip, Column::Status, Column::Hostname, Column::Rtt, Column::LastSeen])
+            .render(area, &mut buf, &mut state);
+
+        let line = |y: u16| -> String {
+            (0..area.width).map(|x| buf[(x, y)].symbol()).collect()
+        };
+        assert!(line(1).contains("SEEN"));
+        assert!(line(2).contains("now"));
+        assert!(line(3).contains("2h ago"));
+        assert!(!line(4).contains("ago") && !line(4).contains("now"));
     }
 }
@@ -1,16 +1,25 @@
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
+    layout::Rect,
     text::{Line, Span},
     widgets::Widget,
 };
 
+use unicode_width::UnicodeWidthStr;
+
 use crate::ui::theme::{Compat, Theme};
 
+/// Columns kept free between the left hint and the right status
+const GAP: u16 = 1;
+
 pub struct StatusBar<'a> {
     hotkeys: Vec<(&'a str, &'a str)>,
     status_left: Option<String>,
+    /// Shorter hint used when `status_left` doesn't fit
+    status_left_short: Option<String>,
     status_right: Option<String>,
+    /// Condensed status used when `status_right` doesn't fit
+    status_right_minimal: Option<String>,
     compat: bool,
 }
 
@@ -27,7 +36,9 @@ impl<'a> StatusBar<'a> {
                 ("Q", "Quit"),
             ],
             status_left: None,
+            status_left_short: None,
             status_right: None,
+            status_right_minimal: None,
             compat: false,
         }
     }
@@ -41,7 +52,9 @@ impl<'a> StatusBar<'a> {
                 ("?", "Help"),
             ],
             status_left: None,
+            status_left_short: None,
             status_right: None,
+            status_right_minimal: None,
             compat: false,
         }
     }
@@ -56,11 +69,49 @@ impl<'a> StatusBar<'a> {
         self
     }
 
+    pub fn status_left_short(mut self, status: impl Into<String>) -> Self {
+        self.status_left_short = Some(status.into());
+        self
+    }
+
     pub fn status_right(mut self, status: impl Into<String>) -> Self {
         self.status_right = Some(status.into());
         self
     }
 
+    pub fn status_right_minimal(mut self, status: impl Into<String>) -> Self {
+        self.status_right_minimal = Some(status.into());
+        self
+    }
+
+    /// Right status drawn in `width` columns: the full text if it fits,
+    /// otherwise the minimal form
+    fn right_text(&self, width: u16) -> Option<&str> {
+        let full = self.status_right.as_deref()?;
+        match self.status_right_minimal.as_deref() {
+            Some(minimal) if full.width() > usize::from(width) => Some(minimal),
+            _ => Some(full),
+        }
+    }
+
+    /// Columns left for the hint once the right status has taken its share
+    fn left_width(&self, width: u16) -> u16 {
+        match self.right_text(width) {
+            Some(right) => width.saturating_sub(right.width() as u16 + GAP),
+            None => width,
+        }
+    }
+
+    /// Left hint drawn in `width` columns. The right status has priority, so
+    /// the hint falls back to its short form and then disappears.
+    pub fn left_text(&self, width: u16) -> Option<&str> {
+        let room = usize::from(self.left_width(width));
+        [self.status_left.as_deref(), self.status_left_short.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|text| text.width() <= room)
+    }
+
     #[allow(dead_code)]
     pub fn hotkeys(mut self, hotkeys: Vec<(&'a str, &'a str)>) -> Self {
         self.hotkeys = hotkeys;
@@ -76,6 +127,10 @@ impl Default for StatusBar<'_> {
 
 impl Widget for StatusBar<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        // Squeezed out entirely on very short terminals
+        if area.is_empty() {
+            return;
+        }
         let (hotkey_style, desc_style, dimmed_style) = if self.compat {
             (Compat::hotkey(), Compat::dimmed(), Compat::dimmed())
         } else {
@@ -96,26 +151,71 @@ impl Widget for StatusBar<'_> {
             hotkey_spans.push(Span::styled(*desc, desc_style));
         }
 
-        let chunks = Layout::horizontal([
-            Constraint::Min(20),
-            Constraint::Length(30),
-        ])
-        .split(area);
+        let left_width = self.left_width(area.width);
 
         // Render status_left (dim hint) if set, otherwise render hotkeys
-        if let Some(left) = self.status_left {
-            let left_line = Line::from(Span::styled(left, dimmed_style));
-            buf.set_line(chunks[0].x, chunks[0].y, &left_line, chunks[0].width);
+        if self.status_left.is_some() {
+            if let Some(left) = self.left_text(area.width) {
+                let left_line = Line::from(Span::styled(left, dimmed_style));
+                buf.set_line(area.x, area.y, &left_line, left_width);
+            }
         } else {
             let hotkey_line = Line::from(hotkey_spans);
-            buf.set_line(chunks[0].x, chunks[0].y, &hotkey_line, chunks[0].width);
+            buf.set_line(area.x, area.y, &hotkey_line, left_width);
         }
 
-        // Render status on the right
-        if let Some(status) = self.status_right {
+        // Right-aligned status; only clipped if even the minimal form is too wide
+        if let Some(status) = self.right_text(area.width) {
             let status_line = Line::from(Span::styled(status, dimmed_style));
-            let x = chunks[1].x + chunks[1].width.saturating_sub(status_line.width() as u16);
-            buf.set_line(x, chunks[1].y, &status_line, chunks[1].width);
+            let x = area.x + area.width.saturating_sub(status_line.width() as u16);
+            buf.set_line(x, area.y, &status_line, area.width);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HINT: &str = "^ Ctrl  shortcuts  |  ? Help";
+
+    fn bar() -> StatusBar<'static> {
+        StatusBar::new()
+            .status_left(HINT)
+            .status_left_short("? Help")
+            .status_right("[120✓ +15] 254 online | ⠙ 187/254")
+            .status_right_minimal("[120✓ +15] 254↑ 187/254")
+    }
+
+    fn rendered(width: u16) -> String {
+        let area = Rect::new(0, 0, width, 1);
+        let mut buf = Buffer::empty(area);
+        bar().render(area, &mut buf);
+        buf.content().iter().map(|c| c.symbol()).collect()
+    }
+
+    #[test]
+    fn wide_terminals_show_everything() {
+        for width in [80, 120] {
+            let line = rendered(width);
+            assert!(line.starts_with(HINT), "{width}: {line}");
+            assert!(line.trim_end().ends_with("[120✓ +15] 254 online | ⠙ 187/254"));
         }
     }
+
+    #[test]
+    fn hint_degrades_before_status() {
+        let line = rendered(60);
+        assert!(line.starts_with("? Help "), "{line}");
+        assert!(!line.contains("Ctrl"));
+        assert!(line.trim_end().ends_with("[120✓ +15] 254 online | ⠙ 187/254"));
+        assert_eq!(bar().left_text(60), Some("? Help"));
+    }
+
+    #[test]
+    fn very_narrow_collapses_to_minimal_status() {
+        let line = rendered(28);
+        assert!(line.trim().starts_with("[120✓ +15] 254↑ 187/254"), "{line}");
+        assert_eq!(bar().left_text(28), None);
+    }
 }
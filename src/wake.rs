@@ -0,0 +1,219 @@
+use std::net::Ipv4Addr;
+
+use tokio::sync::mpsc;
+
+use crate::scanner::wol::WakeEvent;
+use crate::summary::LineKind;
+
+/// Where one host of a wake job stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Waiting for its turn
+    Queued,
+    /// Packet sent; waiting for it to answer when watching
+    Sent,
+    Failed(String),
+    Woke,
+    TimedOut,
+}
+
+/// A Wake-on-LAN run over several hosts, from the first packet to the
+/// summary
+#[derive(Debug)]
+pub struct WakeJob {
+    /// Hosts with a usable MAC, in the order they are woken
+    pub hosts: Vec<(Ipv4Addr, Outcome)>,
+    /// Selected hosts skipped for lack of a MAC
+    pub no_mac: Vec<Ipv4Addr>,
+    /// Whether hosts are pinged after their packet
+    pub watching: bool,
+    /// Stopped before every host was sent to or answered
+    pub stopped: bool,
+    cancel_tx: Option<mpsc::Sender<()>>,
+}
+
+impl WakeJob {
+    pub fn new(
+        targets: &[Ipv4Addr],
+        no_mac: Vec<Ipv4Addr>,
+        watching: bool,
+        cancel_tx: mpsc::Sender<()>,
+    ) -> Self {
+        Self {
+            hosts: targets.iter().map(|&ip| (ip, Outcome::Queued)).collect(),
+            no_mac,
+            watching,
+            stopped: false,
+            cancel_tx: Some(cancel_tx),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.cancel_tx.is_some()
+    }
+
+    /// Stop sending and watching; hosts not reached keep their outcome
+    pub fn stop(&mut self) {
+        if let Some(tx) = self.cancel_tx.take() {
+            let _ = tx.try_send(());
+            self.stopped = true;
+        }
+    }
+
+    /// The run's channel closed
+    pub fn finish(&mut self) {
+        self.cancel_tx = None;
+    }
+
+    pub fn apply(&mut self, event: &WakeEvent) {
+        let (ip, outcome) = match event {
+            WakeEvent::Sent(ip) => (ip, Outcome::Sent),
+            WakeEvent::Failed(ip, error) => (ip, Outcome::Failed(error.clone())),
+            WakeEvent::Woke(ip) => (ip, Outcome::Woke),
+            WakeEvent::TimedOut(ip) => (ip, Outcome::TimedOut),
+        };
+        if let Some(host) = self.hosts.iter_mut().find(|h| h.0 == *ip) {
+            host.1 = outcome;
+        }
+    }
+
+    fn count(&self, matches: impl Fn(&Outcome) -> bool) -> usize {
+        self.hosts.iter().filter(|h| matches(&h.1)).count()
+    }
+
+    /// Status bar progress: "WOL 12/30" while sending, "WOL 18/27 woke"
+    /// while watching
+    pub fn progress_text(&self) -> Option<String> {
+        if !self.is_running() {
+            return None;
+        }
+        let queued = self.count(|o| *o == Outcome::Queued);
+        if queued > 0 || !self.watching {
+            let done = self.hosts.len() - queued;
+            return Some(format!("WOL {}/{}", done, self.hosts.len()));
+        }
+        let sent = self.count(|o| !matches!(o, Outcome::Failed(_)));
+        let woke = self.count(|o| *o == Outcome::Woke);
+        Some(format!("WOL {}/{} woke", woke, sent))
+    }
+
+    /// "24 woke, 3 timed out, 3 no MAC"; counts of zero are left out
+    pub fn headline(&self) -> String {
+        let waiting = if self.watching {
+            "not answering"
+        } else {
+            "sent"
+        };
+        let parts = [
+            (self.count(|o| *o == Outcome::Woke), "woke"),
+            (self.count(|o| *o == Outcome::Sent), waiting),
+            (self.count(|o| *o == Outcome::TimedOut), "timed out"),
+            (self.count(|o| matches!(o, Outcome::Failed(_))), "failed"),
+            (self.count(|o| *o == Outcome::Queued), "not sent"),
+            (self.no_mac.len(), "no MAC"),
+        ];
+        let text: Vec<String> = parts
+            .iter()
+            .filter(|p| p.0 > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect();
+        if text.is_empty() {
+            "nothing to wake".to_string()
+        } else {
+            text.join(", ")
+        }
+    }
+
+    /// Lines of the summary overlay: the headline, then the hosts under
+    /// each outcome
+    pub fn report_lines(&self) -> Vec<(LineKind, String)> {
+        let mut lines = vec![(LineKind::Heading, self.headline())];
+        if self.stopped {
+            lines.push((LineKind::Row, "Stopped before the run finished".to_string()));
+        }
+        let waiting = if self.watching {
+            "Not answering"
+        } else {
+            "Sent"
+        };
+        let groups: [(&str, Vec<String>); 5] = [
+            ("Woke", self.listed(|o| *o == Outcome::Woke)),
+            ("Timed out", self.listed(|o| *o == Outcome::TimedOut)),
+            (waiting, self.listed(|o| *o == Outcome::Sent)),
+            ("Not sent", self.listed(|o| *o == Outcome::Queued)),
+            (
+                "Failed",
+                self.hosts
+                    .iter()
+                    .filter_map(|(ip, o)| match o {
+                        Outcome::Failed(error) => Some(format!("  {}  {}", ip, error)),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+        ];
+        let no_mac = self.no_mac.iter().map(|ip| format!("  {}", ip)).collect();
+        for (title, rows) in groups.into_iter().chain([("No MAC", no_mac)]) {
+            if rows.is_empty() {
+                continue;
+            }
+            lines.push((LineKind::Row, String::new()));
+            lines.push((LineKind::Heading, format!("{} ({})", title, rows.len())));
+            lines.extend(rows.into_iter().map(|row| (LineKind::Row, row)));
+        }
+        lines
+    }
+
+    fn listed(&self, matches: impl Fn(&Outcome) -> bool) -> Vec<String> {
+        self.hosts
+            .iter()
+            .filter(|h| matches(&h.1))
+            .map(|(ip, _)| format!("  {}", ip))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last_octet: u8) -> Ipv4Addr {
+        Ipv4Addr::new(10, 0, 0, last_octet)
+    }
+
+    #[test]
+    fn job_counts_each_outcome() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut job = WakeJob::new(&[ip(1), ip(2), ip(3), ip(4)], vec![ip(9)], true, tx);
+        assert_eq!(job.progress_text().as_deref(), Some("WOL 0/4"));
+
+        job.apply(&WakeEvent::Sent(ip(1)));
+        job.apply(&WakeEvent::Sent(ip(2)));
+        job.apply(&WakeEvent::Failed(ip(3), "network unreachable".to_string()));
+        job.apply(&WakeEvent::Sent(ip(4)));
+        assert_eq!(job.progress_text().as_deref(), Some("WOL 0/3 woke"));
+        job.apply(&WakeEvent::Woke(ip(2)));
+        job.apply(&WakeEvent::Woke(ip(1)));
+        job.apply(&WakeEvent::TimedOut(ip(4)));
+        job.finish();
+        assert_eq!(job.progress_text(), None);
+        assert_eq!(job.headline(), "2 woke, 1 timed out, 1 failed, 1 no MAC");
+
+        let lines: Vec<String> = job.report_lines().into_iter().map(|l| l.1).collect();
+        assert_eq!(lines[2], "Woke (2)");
+        assert!(lines.contains(&"  10.0.0.3  network unreachable".to_string()));
+        assert_eq!(lines.last().map(String::as_str), Some("  10.0.0.9"));
+    }
+
+    #[test]
+    fn stopped_job_reports_hosts_not_reached() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut job = WakeJob::new(&[ip(1), ip(2), ip(3)], Vec::new(), false, tx);
+        job.apply(&WakeEvent::Sent(ip(1)));
+        job.stop();
+        assert!(rx.try_recv().is_ok());
+        assert!(!job.is_running());
+        assert_eq!(job.headline(), "1 sent, 2 not sent");
+        assert_eq!(job.report_lines()[1].1, "Stopped before the run finished");
+    }
+}
@@ -0,0 +1,63 @@
+//! The library facade against listeners on localhost
+
+use std::net::{Ipv4Addr, TcpListener};
+
+use ipscannr::scanner::IpRange;
+use ipscannr::{scan, ScanOptions};
+
+fn localhost_options(ports: Option<Vec<u16>>) -> ScanOptions {
+    ScanOptions {
+        resolve_hostnames: false,
+        detect_mac: false,
+        ports,
+        ..ScanOptions::default()
+    }
+}
+
+/// A port nothing listens on
+fn closed_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+#[tokio::test]
+async fn reports_open_ports_on_localhost() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let open = listener.local_addr().unwrap().port();
+    let closed = closed_port();
+
+    let range = IpRange::parse("127.0.0.1").unwrap();
+    let mut reports = scan(&range, localhost_options(Some(vec![closed, open])));
+    let host = reports.next().await.expect("a report for localhost");
+    assert_eq!(host.ip, Ipv4Addr::LOCALHOST);
+    assert!(host.is_alive);
+    assert_eq!(host.open_ports, Some(vec![open]));
+    assert_eq!(host.hostname, None);
+    assert!(reports.next().await.is_none());
+}
+
+#[tokio::test]
+async fn skips_the_port_scan_unless_asked() {
+    let range = IpRange::parse("127.0.0.1").unwrap();
+    let mut reports = scan(&range, localhost_options(None));
+    let host = reports.next().await.expect("a report for localhost");
+    assert!(host.is_alive);
+    assert_eq!(host.open_ports, None);
+    assert!(reports.next().await.is_none());
+}
+
+#[tokio::test]
+async fn offline_hosts_are_only_reported_on_request() {
+    // TEST-NET-1 (RFC 5737): never routed, so nothing answers
+    let range = IpRange::parse("192.0.2.1").unwrap();
+    let mut options = localhost_options(None);
+    options.ping.timeout = std::time::Duration::from_millis(100);
+    options.ping.refused_means_alive = false;
+    let mut reports = scan(&range, options.clone());
+    assert!(reports.next().await.is_none());
+
+    options.include_offline = true;
+    let mut reports = scan(&range, options);
+    let host = reports.next().await.expect("a report for the offline address");
+    assert!(!host.is_alive);
+    assert_eq!(host.ip, Ipv4Addr::new(192, 0, 2, 1));
+}